@@ -33,6 +33,21 @@ pub trait SpotAgent: Send + Sync {
     fn model_override(&self) -> Option<&str> {
         None
     }
+
+    /// Sampling temperature for this agent, over the model's setting.
+    fn temperature(&self) -> Option<f32> {
+        None
+    }
+
+    /// Top-p for this agent, over the model's setting.
+    fn top_p(&self) -> Option<f32> {
+        None
+    }
+
+    /// Output token limit for this agent, over the model's setting.
+    fn max_tokens(&self) -> Option<u32> {
+        None
+    }
 }
 
 /// Boxed agent for dynamic dispatch.
@@ -26,6 +26,7 @@ impl SpotAgent for PlanningAgent {
         vec![
             "list_files",
             "read_file",
+            "stat_file",
             "grep",
             "share_your_reasoning",
             "invoke_agent",
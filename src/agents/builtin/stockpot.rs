@@ -26,9 +26,13 @@ impl SpotAgent for StockpotAgent {
         vec![
             "list_files",
             "read_file",
+            "stat_file",
             "edit_file",
             "delete_file",
+            "move_file",
+            "make_directory",
             "grep",
+            "replace_in_files",
             "run_shell_command",
             "share_your_reasoning",
             "invoke_agent",
@@ -87,6 +91,10 @@ mod tests {
         assert!(tools.contains(&"read_file"), "Should have read_file");
         assert!(tools.contains(&"edit_file"), "Should have edit_file");
         assert!(tools.contains(&"delete_file"), "Should have delete_file");
+        assert!(
+            tools.contains(&"make_directory"),
+            "Should have make_directory"
+        );
 
         // Must have search
         assert!(tools.contains(&"grep"), "Should have grep");
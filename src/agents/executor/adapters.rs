@@ -14,6 +14,12 @@ use serdes_ai_core::{ModelRequest, ModelResponse, ModelSettings, ToolReturnPart}
 use serdes_ai_models::{Model, ModelError, ModelProfile, ModelRequestParameters, StreamedResponse};
 use serdes_ai_tools::{RunContext, Tool, ToolError, ToolReturn};
 
+use super::approval::{is_read_only, ApprovalPolicy, Decision, ToolCall};
+use super::cache::ToolCache;
+use super::prefetch::{self, ToolPrefetch};
+use super::quotas::ToolQuotas;
+use crate::session::BudgetTracker;
+
 /// Wrapper to make `Arc<dyn Model>` implement `Model`.
 ///
 /// This allows us to use dynamically dispatched models with serdesAI's
@@ -64,14 +70,142 @@ impl Model for ArcModel {
 /// Wrapper that adapts an `Arc<dyn Tool>` to work as a `ToolExecutor<()>`.
 ///
 /// This bridges our Tool implementations (which use `call()`) to
-/// serdesAI's executor interface (which uses `execute()`).
+/// serdesAI's executor interface (which uses `execute()`). When quotas are
+/// attached, calls beyond a tool's per-run limit are refused; with an
+/// approval policy, calls to tools that aren't read-only must be approved;
+/// with a budget, nothing runs once the session is over it. With a cache,
+/// repeated read-only calls are answered from it and other calls invalidate
+/// it. With a prefetch, calls it already started are picked up rather than
+/// run again.
+#[derive(Clone)]
 pub(super) struct ToolExecutorAdapter {
     tool: Arc<dyn Tool + Send + Sync>,
+    name: String,
+    quotas: Option<Arc<ToolQuotas>>,
+    approval: Option<Arc<dyn ApprovalPolicy>>,
+    budget: Option<Arc<BudgetTracker>>,
+    cache: Option<Arc<ToolCache>>,
+    prefetch: Option<Arc<ToolPrefetch>>,
 }
 
 impl ToolExecutorAdapter {
     pub fn new(tool: Arc<dyn Tool + Send + Sync>) -> Self {
-        Self { tool }
+        let name = tool.definition().name.clone();
+        Self {
+            tool,
+            name,
+            quotas: None,
+            approval: None,
+            budget: None,
+            cache: None,
+            prefetch: None,
+        }
+    }
+
+    /// Name of the wrapped tool.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Enforce per-run call quotas shared with other tools in the run.
+    pub fn with_quotas(mut self, quotas: Arc<ToolQuotas>) -> Self {
+        self.quotas = Some(quotas);
+        self
+    }
+
+    /// Ask `policy` before running this tool, unless it is read-only.
+    pub fn with_approval(mut self, policy: Option<Arc<dyn ApprovalPolicy>>) -> Self {
+        self.approval = policy;
+        self
+    }
+
+    /// Refuse to run once the session is over its budget.
+    pub fn with_budget(mut self, tracker: Option<Arc<BudgetTracker>>) -> Self {
+        self.budget = tracker;
+        self
+    }
+
+    /// Answer repeated read-only calls from `cache`, shared with the run's
+    /// other tools.
+    pub fn with_cache(mut self, cache: Arc<ToolCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Pick up calls `prefetch` has already started.
+    pub fn with_prefetch(mut self, prefetch: Option<Arc<ToolPrefetch>>) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Run a call through the budget, approval, cache and quota checks.
+    pub async fn run(&self, args: JsonValue, model_name: &str) -> Result<ToolReturn, ToolError> {
+        if let Some(tracker) = &self.budget {
+            if let Err(e) = tracker.check() {
+                tracing::warn!(tool = %self.name, error = %e, "Tool call over budget");
+                return Ok(ToolReturn::error(format!(
+                    "Tool '{}' was not run: {}. Stop and report progress to the user.",
+                    self.name, e
+                )));
+            }
+        }
+
+        if let Some(policy) = &self.approval {
+            if !is_read_only(&self.name) {
+                // A policy may block, e.g. waiting for an answer on stdin,
+                // so it runs off the async workers
+                let policy = Arc::clone(policy);
+                let name = self.name.clone();
+                let call_args = args.clone();
+                let decision = tokio::task::spawn_blocking(move || {
+                    policy.approve(&ToolCall {
+                        tool_name: &name,
+                        args: &call_args,
+                    })
+                })
+                .await
+                .unwrap_or_else(|e| Decision::Deny(format!("approval failed: {}", e)));
+                if let Decision::Deny(reason) = decision {
+                    tracing::info!(tool = %self.name, %reason, "Tool call denied");
+                    return Ok(ToolReturn::error(format!(
+                        "Tool '{}' was not approved: {}. \
+                         Continue without it or ask the user for permission.",
+                        self.name, reason
+                    )));
+                }
+            }
+        }
+
+        // A cached answer costs nothing, so it doesn't count against the quota
+        if let Some(cached) = self.cache.as_ref().and_then(|c| c.get(&self.name, &args)) {
+            return Ok(cached);
+        }
+
+        if let Some(quotas) = &self.quotas {
+            if let Err(limit) = quotas.try_acquire(&self.name) {
+                tracing::warn!(tool = %self.name, limit, "Tool quota reached");
+                return Ok(ToolReturn::error(format!(
+                    "Tool '{}' has reached its limit of {} calls for this run. \
+                     Continue without it or ask the user to raise the limit.",
+                    self.name, limit
+                )));
+            }
+        }
+
+        let tool_ctx = RunContext::minimal(model_name);
+        let Some(cache) = &self.cache else {
+            return self.tool.call(&tool_ctx, args).await;
+        };
+
+        let result = self.tool.call(&tool_ctx, args.clone()).await;
+        if is_read_only(&self.name) {
+            if let Ok(ret) = &result {
+                cache.insert(&self.name, &args, ret);
+            }
+        } else {
+            cache.invalidate(&self.name, &args);
+        }
+        result
     }
 }
 
@@ -82,9 +216,14 @@ impl serdes_ai_agent::ToolExecutor<()> for ToolExecutorAdapter {
         args: JsonValue,
         ctx: &serdes_ai_agent::RunContext<()>,
     ) -> Result<ToolReturn, ToolError> {
-        // Convert serdes_ai_agent::RunContext to serdes_ai_tools::RunContext
-        let tool_ctx = RunContext::minimal(&ctx.model_name);
-        self.tool.call(&tool_ctx, args).await
+        if let Some(handle) = self
+            .prefetch
+            .as_ref()
+            .and_then(|prefetch| prefetch.take(&self.name, &args))
+        {
+            return prefetch::join(handle).await;
+        }
+        self.run(args, &ctx.model_name).await
     }
 }
 
@@ -323,6 +462,92 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn tool_executor_adapter_refuses_calls_over_quota() {
+        use std::collections::HashMap;
+
+        let quotas = Arc::new(ToolQuotas::new(HashMap::from([(
+            "run_shell_command".to_string(),
+            2,
+        )])));
+        let shell = ToolExecutorAdapter::new(Arc::new(MockTool::new("run_shell_command", "ok")))
+            .with_quotas(Arc::clone(&quotas));
+        let grep = ToolExecutorAdapter::new(Arc::new(MockTool::new("grep", "match")))
+            .with_quotas(Arc::clone(&quotas));
+
+        let ctx = make_test_ctx("test-model", Some("run_shell_command"), None);
+        for _ in 0..2 {
+            let ret = shell.execute(serde_json::json!({}), &ctx).await.unwrap();
+            assert!(!ret.is_error());
+        }
+
+        // The third shell call is refused
+        let ret = shell.execute(serde_json::json!({}), &ctx).await.unwrap();
+        assert!(ret.is_error());
+        assert!(ret.as_text().unwrap().contains("limit of 2 calls"));
+
+        // Uncapped tools sharing the same quotas still run
+        let ctx = make_test_ctx("test-model", Some("grep"), None);
+        let ret = grep.execute(serde_json::json!({}), &ctx).await.unwrap();
+        assert_eq!(ret.as_text(), Some("match"));
+    }
+
+    #[tokio::test]
+    async fn tool_executor_adapter_denied_calls_do_not_run() {
+        use super::super::approval::DenyAll;
+
+        let quotas = Arc::new(ToolQuotas::new(std::collections::HashMap::from([(
+            "delete_file".to_string(),
+            1,
+        )])));
+        let delete = ToolExecutorAdapter::new(Arc::new(MockTool::new("delete_file", "deleted")))
+            .with_quotas(quotas)
+            .with_approval(Some(Arc::new(DenyAll)));
+
+        let ctx = make_test_ctx("test-model", Some("delete_file"), None);
+        for _ in 0..2 {
+            let ret = delete.execute(serde_json::json!({}), &ctx).await.unwrap();
+            assert!(ret.is_error());
+            assert!(ret.as_text().unwrap().contains("was not approved"));
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_executor_adapter_refuses_calls_over_budget() {
+        use crate::session::{SessionBudget, SessionUsage};
+
+        let tracker = Arc::new(BudgetTracker::new(
+            SessionBudget {
+                max_tokens: Some(10),
+                ..Default::default()
+            },
+            SessionUsage::default(),
+        ));
+        let grep = ToolExecutorAdapter::new(Arc::new(MockTool::new("grep", "match")))
+            .with_budget(Some(Arc::clone(&tracker)));
+        let ctx = make_test_ctx("test-model", Some("grep"), None);
+
+        let ret = grep.execute(serde_json::json!({}), &ctx).await.unwrap();
+        assert_eq!(ret.as_text(), Some("match"));
+
+        tracker.record_tokens(11);
+        let ret = grep.execute(serde_json::json!({}), &ctx).await.unwrap();
+        assert!(ret.is_error());
+        assert!(ret.as_text().unwrap().contains("token budget exceeded"));
+    }
+
+    #[tokio::test]
+    async fn tool_executor_adapter_read_only_tools_skip_approval() {
+        use super::super::approval::DenyAll;
+
+        let read = ToolExecutorAdapter::new(Arc::new(MockTool::new("read_file", "contents")))
+            .with_approval(Some(Arc::new(DenyAll)));
+
+        let ctx = make_test_ctx("test-model", Some("read_file"), None);
+        let ret = read.execute(serde_json::json!({}), &ctx).await.unwrap();
+        assert_eq!(ret.as_text(), Some("contents"));
+    }
+
     #[test]
     fn recording_tool_executor_new() {
         let tool: Arc<dyn Tool + Send + Sync> = Arc::new(MockTool::new("test", "result"));
@@ -0,0 +1,213 @@
+//! Approval gate for tools that change things.
+//!
+//! An [`ApprovalPolicy`] attached with
+//! [`AgentExecutor::with_approval_policy`](super::AgentExecutor::with_approval_policy)
+//! is asked before every tool call that isn't read-only: file edits and
+//! deletes, shell commands and MCP tools. A denied call never runs; the
+//! model gets an error return explaining why and can carry on without it.
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::sync::Mutex;
+
+use serde_json::Value as JsonValue;
+
+/// Tools that only read, which never need approval.
+const READ_ONLY_TOOLS: &[&str] = &[
+    "list_files",
+    "read_file",
+    "stat_file",
+    "grep",
+    "share_your_reasoning",
+    "list_agents",
+];
+
+/// Whether `tool_name` can run without asking.
+pub fn is_read_only(tool_name: &str) -> bool {
+    READ_ONLY_TOOLS.contains(&tool_name)
+}
+
+/// A tool call awaiting approval.
+#[derive(Debug, Clone, Copy)]
+pub struct ToolCall<'a> {
+    pub tool_name: &'a str,
+    pub args: &'a JsonValue,
+}
+
+/// What a policy decided about a tool call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    /// Refuse the call; the reason is shown to the model
+    Deny(String),
+}
+
+/// Decides whether a non-read-only tool call may run.
+pub trait ApprovalPolicy: Send + Sync {
+    fn approve(&self, call: &ToolCall<'_>) -> Decision;
+}
+
+/// Approve everything (the same as having no policy).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllowAll;
+
+impl ApprovalPolicy for AllowAll {
+    fn approve(&self, _call: &ToolCall<'_>) -> Decision {
+        Decision::Allow
+    }
+}
+
+/// Refuse every call that isn't read-only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DenyAll;
+
+impl ApprovalPolicy for DenyAll {
+    fn approve(&self, call: &ToolCall<'_>) -> Decision {
+        Decision::Deny(format!("{} is not allowed in this run", call.tool_name))
+    }
+}
+
+/// Sandbox mode: nothing that isn't read-only may run.
+///
+/// Sandboxed executors also drop those tools up front; this catches any
+/// that get registered anyway.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct Sandbox;
+
+impl ApprovalPolicy for Sandbox {
+    fn approve(&self, call: &ToolCall<'_>) -> Decision {
+        Decision::Deny(format!(
+            "sandbox mode is on, so {} (which can modify things) is disabled",
+            call.tool_name
+        ))
+    }
+}
+
+/// Ask on the terminal before each call.
+///
+/// The question goes to stderr and the answer is read from stdin, one
+/// question at a time when calls run in parallel. When stdin isn't a
+/// terminal there is nobody to ask, so the call is denied.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Interactive;
+
+impl ApprovalPolicy for Interactive {
+    fn approve(&self, call: &ToolCall<'_>) -> Decision {
+        static ASKING: Mutex<()> = Mutex::new(());
+
+        let stdin = std::io::stdin();
+        if !stdin.is_terminal() {
+            return Decision::Deny("approval needed but no terminal to ask".to_string());
+        }
+        let _asking = ASKING.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut stderr = std::io::stderr();
+        let _ = write!(
+            stderr,
+            "Allow {} {}? [y/N] ",
+            call.tool_name,
+            summarize_args(call.args)
+        );
+        let _ = stderr.flush();
+
+        let mut answer = String::new();
+        match stdin.lock().read_line(&mut answer) {
+            Ok(_) => parse_answer(&answer),
+            Err(e) => Decision::Deny(format!("could not read approval: {}", e)),
+        }
+    }
+}
+
+/// Anything but an explicit yes is a no.
+fn parse_answer(answer: &str) -> Decision {
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Decision::Allow,
+        _ => Decision::Deny("denied by the user".to_string()),
+    }
+}
+
+/// One-line view of the arguments for the approval question.
+fn summarize_args(args: &JsonValue) -> String {
+    const MAX_CHARS: usize = 200;
+
+    let text = args.to_string();
+    if text.chars().count() <= MAX_CHARS {
+        text
+    } else {
+        let truncated: String = text.chars().take(MAX_CHARS).collect();
+        format!("{}…", truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_read_only_classification() {
+        for tool in [
+            "read_file",
+            "stat_file",
+            "list_files",
+            "grep",
+            "share_your_reasoning",
+        ] {
+            assert!(is_read_only(tool), "{} should be read-only", tool);
+        }
+        for tool in [
+            "edit_file",
+            "delete_file",
+            "make_directory",
+            "run_shell_command",
+            "invoke_agent",
+        ] {
+            assert!(!is_read_only(tool), "{} should need approval", tool);
+        }
+        // Unknown (e.g. MCP) tools are gated
+        assert!(!is_read_only("github_create_issue"));
+    }
+
+    #[test]
+    fn test_builtin_policies() {
+        let args = json!({"command": "rm -rf target"});
+        let call = ToolCall {
+            tool_name: "run_shell_command",
+            args: &args,
+        };
+
+        assert_eq!(AllowAll.approve(&call), Decision::Allow);
+        match DenyAll.approve(&call) {
+            Decision::Deny(reason) => assert!(reason.contains("run_shell_command")),
+            Decision::Allow => panic!("DenyAll allowed a call"),
+        }
+    }
+
+    #[test]
+    fn test_sandbox_policy_denies() {
+        let args = json!({"file_path": "src/main.rs"});
+        let call = ToolCall {
+            tool_name: "edit_file",
+            args: &args,
+        };
+        match Sandbox.approve(&call) {
+            Decision::Deny(reason) => assert!(reason.contains("sandbox mode")),
+            Decision::Allow => panic!("Sandbox allowed a call"),
+        }
+    }
+
+    #[test]
+    fn test_parse_answer() {
+        assert_eq!(parse_answer("y\n"), Decision::Allow);
+        assert_eq!(parse_answer(" YES "), Decision::Allow);
+        assert!(matches!(parse_answer("\n"), Decision::Deny(_)));
+        assert!(matches!(parse_answer("nope"), Decision::Deny(_)));
+    }
+
+    #[test]
+    fn test_summarize_args_truncates() {
+        let args = json!({"content": "x".repeat(1_000)});
+        let summary = summarize_args(&args);
+        assert!(summary.chars().count() <= 201);
+        assert!(summary.ends_with('…'));
+    }
+}
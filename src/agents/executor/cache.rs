@@ -0,0 +1,319 @@
+//! Per-run cache of read-only tool results.
+//!
+//! Models sometimes read the same file or run the same grep twice in one
+//! run. A [`ToolCache`] keeps the results of the read-only file tools, keyed
+//! by tool and arguments, and hands back a copy marked as cached. Writing a
+//! path drops every entry that could have seen it; tools whose effects can't
+//! be pinned to paths, such as shell commands, clear the whole cache, and
+//! so does every MCP tool call.
+//!
+//! Changes made outside the run, e.g. in the user's editor, are caught by
+//! a [`Stamp`] of what was read: an entry whose file or directory changed
+//! since is not used.
+//!
+//! A fresh cache is created for each executor run, like [`ToolQuotas`](super::quotas::ToolQuotas).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use ignore::WalkBuilder;
+use serde_json::Value as JsonValue;
+
+use serdes_ai_tools::ToolReturn;
+
+/// Cached tool results for one agent run.
+#[derive(Debug, Default)]
+pub(super) struct ToolCache {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// File or directory the result was read from.
+    path: PathBuf,
+    /// What `path` looked like when it was read.
+    stamp: Stamp,
+    text: String,
+}
+
+/// Modification time and size of a file, or for a directory the newest
+/// time, total size and number of entries under it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    entries: u64,
+}
+
+impl Stamp {
+    /// Stamp `path` now, or `None` if it can't be read.
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let mut stamp = Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            entries: 1,
+        };
+        if metadata.is_dir() {
+            // The walk matches what grep and list_files look at
+            let walk = WalkBuilder::new(path)
+                .hidden(false)
+                .git_ignore(true)
+                .git_global(false)
+                .git_exclude(false)
+                .build();
+            for metadata in walk.flatten().filter_map(|entry| entry.metadata().ok()) {
+                stamp.modified = stamp.modified.max(metadata.modified().ok());
+                stamp.len += metadata.len();
+                stamp.entries += 1;
+            }
+        }
+        Some(stamp)
+    }
+}
+
+impl ToolCache {
+    /// The result of an identical earlier call, if nothing it read has
+    /// changed since.
+    pub fn get(&self, tool_name: &str, args: &JsonValue) -> Option<ToolReturn> {
+        let key = call_key(tool_name, args);
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(&key)?;
+        if Stamp::of(&entry.path) != Some(entry.stamp) {
+            tracing::debug!(tool = %tool_name, "Cached tool result is stale");
+            entries.remove(&key);
+            return None;
+        }
+        tracing::debug!(tool = %tool_name, "Returning cached tool result");
+        Some(ToolReturn::text(format!(
+            "[Cached: unchanged since an earlier identical call in this run]\n{}",
+            entry.text
+        )))
+    }
+
+    /// Remember a successful result from a cacheable tool.
+    pub fn insert(&self, tool_name: &str, args: &JsonValue, result: &ToolReturn) {
+        if result.is_error() {
+            return;
+        }
+        let (Some(path), Some(text)) = (read_path(tool_name, args), result.as_text()) else {
+            return;
+        };
+        let Some(stamp) = Stamp::of(&path) else {
+            return;
+        };
+        self.entries.lock().unwrap().insert(
+            call_key(tool_name, args),
+            Entry {
+                path,
+                stamp,
+                text: text.to_string(),
+            },
+        );
+    }
+
+    /// Forget results a call to a tool that isn't read-only may have changed.
+    pub fn invalidate(&self, tool_name: &str, args: &JsonValue) {
+        let mut entries = self.entries.lock().unwrap();
+        match written_paths(tool_name, args) {
+            Some(written) => entries.retain(|_, entry| {
+                !written
+                    .iter()
+                    .any(|path| path.starts_with(&entry.path) || entry.path.starts_with(path))
+            }),
+            None => entries.clear(),
+        }
+    }
+
+    /// Forget everything, e.g. after a sub-agent or MCP tool ran.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+/// Identifies a call by tool and arguments, ignoring arguments left null.
+fn call_key(tool_name: &str, args: &JsonValue) -> String {
+    match args {
+        JsonValue::Object(map) => {
+            let set: serde_json::Map<String, JsonValue> = map
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            format!("{}:{}", tool_name, JsonValue::Object(set))
+        }
+        other => format!("{}:{}", tool_name, other),
+    }
+}
+
+/// What a cacheable tool reads, or `None` for tools that aren't cached.
+fn read_path(tool_name: &str, args: &JsonValue) -> Option<PathBuf> {
+    match tool_name {
+        "read_file" | "stat_file" => path_arg(args, "file_path"),
+        "list_files" | "grep" => {
+            Some(path_arg(args, "directory").unwrap_or_else(|| normalize(".")))
+        }
+        _ => None,
+    }
+}
+
+/// Paths a tool may write, or `None` if that can't be told from its
+/// arguments.
+fn written_paths(tool_name: &str, args: &JsonValue) -> Option<Vec<PathBuf>> {
+    match tool_name {
+        "edit_file" | "delete_file" => Some(vec![path_arg(args, "file_path")?]),
+        "move_file" => Some(vec![path_arg(args, "from")?, path_arg(args, "to")?]),
+        "make_directory" => Some(vec![path_arg(args, "path")?]),
+        "replace_in_files" => Some(vec![
+            path_arg(args, "directory").unwrap_or_else(|| normalize("."))
+        ]),
+        _ => None,
+    }
+}
+
+fn path_arg(args: &JsonValue, key: &str) -> Option<PathBuf> {
+    args.get(key).and_then(|v| v.as_str()).map(normalize)
+}
+
+/// Absolute form of `path` with `.` and `..` resolved, without touching the
+/// filesystem (the path may not exist yet).
+fn normalize(path: &str) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| PathBuf::from(path));
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn cache_with(calls: &[(&str, JsonValue)]) -> ToolCache {
+        let cache = ToolCache::default();
+        for (tool_name, args) in calls {
+            cache.insert(
+                tool_name,
+                args,
+                &ToolReturn::text(format!("{} result", tool_name)),
+            );
+        }
+        cache
+    }
+
+    #[test]
+    fn test_hit_is_marked_and_ignores_null_args() {
+        let cache = cache_with(&[("read_file", json!({"file_path": "src/lib.rs"}))]);
+
+        let hit = cache
+            .get(
+                "read_file",
+                &json!({"file_path": "src/lib.rs", "start_line": null}),
+            )
+            .expect("cached");
+        let text = hit.as_text().unwrap();
+        assert!(text.starts_with("[Cached:"));
+        assert!(text.ends_with("read_file result"));
+
+        assert!(cache
+            .get(
+                "read_file",
+                &json!({"file_path": "src/lib.rs", "start_line": 5})
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_only_read_tools_and_successes_are_cached() {
+        let cache = cache_with(&[
+            ("run_shell_command", json!({"command": "ls"})),
+            ("share_your_reasoning", json!({"reasoning": "hmm"})),
+        ]);
+        cache.insert(
+            "read_file",
+            &json!({"file_path": "missing.rs"}),
+            &ToolReturn::error("File not found: missing.rs"),
+        );
+
+        assert!(cache
+            .get("run_shell_command", &json!({"command": "ls"}))
+            .is_none());
+        assert!(cache
+            .get("share_your_reasoning", &json!({"reasoning": "hmm"}))
+            .is_none());
+        assert!(cache
+            .get("read_file", &json!({"file_path": "missing.rs"}))
+            .is_none());
+    }
+
+    #[test]
+    fn test_write_invalidates_related_paths() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().display().to_string();
+        fs::create_dir_all(temp.path().join("src")).unwrap();
+        fs::create_dir_all(temp.path().join("docs")).unwrap();
+        fs::write(temp.path().join("src/lib.rs"), "fn lib() {}").unwrap();
+        fs::write(temp.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(temp.path().join("docs/guide.md"), "fn in docs").unwrap();
+
+        let read = json!({"file_path": format!("{}/src/lib.rs", root)});
+        let other = json!({"file_path": format!("{}/src/main.rs", root)});
+        let grep_src = json!({"pattern": "fn", "directory": format!("{}/src", root)});
+        let grep_docs = json!({"pattern": "fn", "directory": format!("{}/docs", root)});
+        let cache = cache_with(&[
+            ("read_file", read.clone()),
+            ("read_file", other.clone()),
+            ("grep", grep_src.clone()),
+            ("grep", grep_docs.clone()),
+        ]);
+
+        cache.invalidate(
+            "edit_file",
+            &json!({"file_path": format!("{}/src/../src/lib.rs", root)}),
+        );
+
+        assert!(cache.get("read_file", &read).is_none());
+        assert!(cache.get("grep", &grep_src).is_none());
+        assert!(cache.get("read_file", &other).is_some());
+        assert!(cache.get("grep", &grep_docs).is_some());
+    }
+
+    #[test]
+    fn test_changes_outside_the_run_are_not_cached() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("notes.txt");
+        fs::write(&file, "first").unwrap();
+
+        let read = json!({"file_path": file.display().to_string()});
+        let list = json!({"directory": temp.path().display().to_string()});
+        let cache = cache_with(&[("read_file", read.clone()), ("list_files", list.clone())]);
+        assert!(cache.get("read_file", &read).is_some());
+
+        // Edited in another program: the size changes even within one
+        // mtime tick
+        fs::write(&file, "second draft").unwrap();
+        assert!(cache.get("read_file", &read).is_none());
+        assert!(cache.get("list_files", &list).is_none());
+    }
+
+    #[test]
+    fn test_untracked_writes_clear_everything() {
+        let read = json!({"file_path": "src/lib.rs"});
+        let cache = cache_with(&[("read_file", read.clone())]);
+
+        cache.invalidate("run_shell_command", &json!({"command": "cargo fmt"}));
+        assert!(cache.get("read_file", &read).is_none());
+    }
+}
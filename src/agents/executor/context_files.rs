@@ -0,0 +1,221 @@
+//! Per-agent context files.
+//!
+//! Paths from the `agent_context.<agent>` setting are read fresh before
+//! every run and prepended to the prompt, so edits on disk are picked up
+//! without restarting. Directories are walked (respecting `.gitignore`,
+//! skipping hidden files) and everything is bounded by a token budget:
+//! the file that crosses the budget is truncated and later ones are only
+//! listed by name.
+
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use serdes_ai_core::messages::{UserContent, UserContentPart};
+
+use crate::config::Settings;
+use crate::db::Database;
+
+/// Token budget for an agent's context files.
+pub(super) const CONTEXT_TOKEN_BUDGET: usize = 16_000;
+
+/// Matches the ~4 chars per token estimate in `crate::tokens`.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// How deep to walk context directories.
+const MAX_DEPTH: usize = 8;
+
+/// Load the configured context for an agent, if any.
+pub(super) fn load_agent_context(db: &Database, agent_name: &str) -> Option<String> {
+    let paths = Settings::new(db).get_agent_context_paths(agent_name);
+    build_context(&paths, CONTEXT_TOKEN_BUDGET)
+}
+
+/// Expand paths to a sorted list of files.
+fn collect_files(paths: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for raw in paths {
+        let expanded = shellexpand::tilde(raw).to_string();
+        let path = Path::new(&expanded);
+        if path.is_file() {
+            files.push(path.to_path_buf());
+        } else if path.is_dir() {
+            let walker = WalkBuilder::new(path)
+                .hidden(true)
+                .git_ignore(true)
+                .max_depth(Some(MAX_DEPTH))
+                .sort_by_file_path(|a, b| a.cmp(b))
+                .build();
+            files.extend(
+                walker
+                    .flatten()
+                    .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+                    .map(|e| e.into_path()),
+            );
+        } else {
+            tracing::warn!(path = %raw, "Agent context path not found");
+        }
+    }
+    files
+}
+
+/// Render the given paths as a context block within `token_budget`.
+///
+/// Returns `None` when there is nothing to include.
+pub(super) fn build_context(paths: &[String], token_budget: usize) -> Option<String> {
+    let files = collect_files(paths);
+    if files.is_empty() {
+        return None;
+    }
+
+    let mut remaining = token_budget * CHARS_PER_TOKEN;
+    let mut body = String::new();
+    let mut omitted = Vec::new();
+
+    for path in files {
+        // Binary and non-UTF-8 files aren't useful as context
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let header = format!("--- {} ---\n", path.display());
+        let header_len = header.chars().count();
+
+        if header_len >= remaining {
+            remaining = 0;
+            omitted.push(path.display().to_string());
+            continue;
+        }
+        remaining -= header_len;
+        body.push_str(&header);
+
+        let len = content.chars().count();
+        if len <= remaining {
+            body.push_str(&content);
+            remaining -= len;
+        } else {
+            body.extend(content.chars().take(remaining));
+            body.push_str("\n… [truncated]");
+            remaining = 0;
+        }
+        if !body.ends_with('\n') {
+            body.push('\n');
+        }
+    }
+
+    if body.is_empty() && omitted.is_empty() {
+        return None;
+    }
+    if !omitted.is_empty() {
+        body.push_str(&format!(
+            "(omitted, over the context budget: {})\n",
+            omitted.join(", ")
+        ));
+    }
+
+    Some(format!("<context_files>\n{}</context_files>", body))
+}
+
+/// Put the context block ahead of the user's prompt.
+pub(super) fn prepend_context(prompt: UserContent, context: &str) -> UserContent {
+    match prompt {
+        UserContent::Text(text) => UserContent::text(&format!("{}\n\n{}", context, text)),
+        UserContent::Parts(mut parts) => {
+            parts.insert(0, UserContentPart::text(context));
+            UserContent::parts(parts)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &str) -> String {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(&path, content).unwrap();
+        path.display().to_string()
+    }
+
+    #[test]
+    fn test_build_context_includes_files_and_directories() {
+        let temp = TempDir::new().unwrap();
+        let notes = write(temp.path(), "NOTES.md", "remember the milk");
+        write(temp.path(), "src/a.rs", "fn a() {}");
+        write(temp.path(), "src/b.rs", "fn b() {}");
+        let src = temp.path().join("src").display().to_string();
+
+        let context = build_context(&[notes, src], 1_000).unwrap();
+        assert!(context.starts_with("<context_files>"));
+        assert!(context.contains("remember the milk"));
+        let a = context.find("fn a() {}").unwrap();
+        let b = context.find("fn b() {}").unwrap();
+        assert!(a < b, "directory entries should be sorted");
+    }
+
+    #[test]
+    fn test_build_context_respects_budget() {
+        let temp = TempDir::new().unwrap();
+        let big = write(temp.path(), "big.txt", &"x".repeat(10_000));
+        let later = write(temp.path(), "later.txt", "never shown");
+
+        let budget = 100;
+        let context = build_context(&[big, later.clone()], budget).unwrap();
+
+        let x_count = context.chars().filter(|c| *c == 'x').count();
+        assert!(x_count < budget * CHARS_PER_TOKEN);
+        assert!(context.contains("[truncated]"));
+        assert!(!context.contains("never shown"));
+        assert!(context.contains(&later), "omitted files are listed by name");
+    }
+
+    #[test]
+    fn test_build_context_picks_up_changes() {
+        let temp = TempDir::new().unwrap();
+        let path = write(temp.path(), "plan.md", "step one");
+        let paths = vec![path.clone()];
+        assert!(build_context(&paths, 1_000).unwrap().contains("step one"));
+
+        std::fs::write(&path, "step two").unwrap();
+        let context = build_context(&paths, 1_000).unwrap();
+        assert!(context.contains("step two"));
+        assert!(!context.contains("step one"));
+    }
+
+    #[test]
+    fn test_build_context_nothing_to_include() {
+        assert!(build_context(&[], 1_000).is_none());
+        assert!(build_context(&["/definitely/not/here".to_string()], 1_000).is_none());
+    }
+
+    #[test]
+    fn test_load_agent_context_uses_settings() {
+        let temp = TempDir::new().unwrap();
+        let db = Database::open_at(temp.path().join("test.db")).unwrap();
+        db.migrate().unwrap();
+        let path = write(temp.path(), "ctx.md", "agent specific context");
+
+        assert!(load_agent_context(&db, "stockpot").is_none());
+        Settings::new(&db)
+            .set_agent_context_paths("stockpot", &[path])
+            .unwrap();
+        assert!(load_agent_context(&db, "stockpot")
+            .unwrap()
+            .contains("agent specific context"));
+        assert!(load_agent_context(&db, "explore").is_none());
+    }
+
+    #[test]
+    fn test_prepend_context_to_text_prompt() {
+        let prompt = prepend_context(UserContent::text("fix the bug"), "<context_files/>");
+        match prompt {
+            UserContent::Text(text) => {
+                assert!(text.starts_with("<context_files/>"));
+                assert!(text.ends_with("fix the bug"));
+            }
+            _ => panic!("Expected text content"),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Workspace instructions.
+//!
+//! A `.stockpot/instructions.md` in the working directory or any directory
+//! above it holds project conventions ("run cargo test before finishing").
+//! The nearest one is appended to every agent's system prompt, so they
+//! apply without editing agent definitions.
+
+use std::path::{Path, PathBuf};
+
+/// Where instructions live, relative to a workspace directory.
+pub(super) const INSTRUCTIONS_FILE: &str = ".stockpot/instructions.md";
+
+/// The nearest instructions file at or above `start`.
+pub(super) fn find_instructions(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(INSTRUCTIONS_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Read the instructions that apply in the current directory, if any.
+pub(super) fn load_workspace_instructions() -> Option<String> {
+    let path = find_instructions(&std::env::current_dir().ok()?)?;
+    match std::fs::read_to_string(&path) {
+        Ok(content) if !content.trim().is_empty() => Some(content.trim().to_string()),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "Failed to read workspace instructions");
+            None
+        }
+    }
+}
+
+/// Append workspace instructions to an agent's system prompt.
+pub(super) fn append_instructions(system_prompt: String, instructions: Option<&str>) -> String {
+    match instructions {
+        Some(instructions) => format!(
+            "{}\n\n<workspace_instructions>\n{}\n</workspace_instructions>",
+            system_prompt, instructions
+        ),
+        None => system_prompt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_instructions_walks_up_to_nearest() {
+        let temp = TempDir::new().unwrap();
+        let nested = temp.path().join("crates/core/src");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_instructions(&nested), None);
+
+        let root = temp.path().join(INSTRUCTIONS_FILE);
+        std::fs::create_dir_all(root.parent().unwrap()).unwrap();
+        std::fs::write(&root, "root rules").unwrap();
+        assert_eq!(find_instructions(&nested), Some(root));
+
+        let closer = temp.path().join("crates/core").join(INSTRUCTIONS_FILE);
+        std::fs::create_dir_all(closer.parent().unwrap()).unwrap();
+        std::fs::write(&closer, "core rules").unwrap();
+        assert_eq!(find_instructions(&nested), Some(closer));
+    }
+
+    #[test]
+    fn test_append_instructions() {
+        assert_eq!(
+            append_instructions("Be helpful.".into(), None),
+            "Be helpful."
+        );
+        assert_eq!(
+            append_instructions("Be helpful.".into(), Some("Run tests.")),
+            "Be helpful.\n\n<workspace_instructions>\nRun tests.\n</workspace_instructions>"
+        );
+    }
+}
@@ -1,30 +1,175 @@
 //! MCP (Model Context Protocol) tool executor.
 //!
 //! Provides `McpToolExecutor` which wraps MCP tools to work with
-//! serdesAI's tool execution interface.
+//! serdesAI's tool execution interface. Arguments are checked against the
+//! tool's input schema before dispatch, so the model gets a precise error
+//! to correct instead of an opaque failure from the server.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use serde_json::Value as JsonValue;
 
 use serdes_ai_tools::{RunContext, Tool, ToolDefinition, ToolError, ToolReturn};
 
+use super::cache::ToolCache;
+use super::CancelToken;
 use crate::mcp::McpManager;
+use crate::messaging::{Message, MessageSender, ToolContentStore, ToolResultContent};
 
 /// Tool executor that calls MCP server tools.
 ///
-/// Note: We use a raw pointer to McpManager because we can't easily
-/// share Arc across async boundaries here. The pointer is valid for
-/// the duration of the executor run.
+/// Holds its own clone of the manager (cheap, `Arc`-backed), so it stays
+/// valid even if the executor outlives the run that created it.
 pub(super) struct McpToolExecutor {
     pub server_name: String,
     pub tool_name: String,
-    pub mcp_manager_ptr: *const McpManager,
+    pub mcp_manager: McpManager,
+    /// Maximum time to wait for a single call before giving up.
+    pub timeout: Duration,
+    /// Run cancellation signal; aborts the in-flight call when fired.
+    pub cancel: Option<CancelToken>,
+    /// Bus for the cancellation note, if the run publishes events.
+    pub bus: Option<MessageSender>,
+    /// Where image and JSON results are published for rich rendering.
+    pub contents: Option<ToolContentStore>,
+    /// The tool's compiled input schema; arguments are sent unchecked
+    /// without one.
+    pub validator: Option<Arc<jsonschema::Validator>>,
+    /// The run's tool cache, cleared after each call: the server may have
+    /// written files the cache read.
+    pub cache: Option<Arc<ToolCache>>,
+}
+
+/// Compile an MCP tool's input schema for argument validation.
+///
+/// A schema that doesn't compile is logged and skipped rather than making
+/// the tool unusable; the server still checks its own arguments.
+pub(super) fn compile_schema(
+    tool_name: &str,
+    schema: &JsonValue,
+) -> Option<Arc<jsonschema::Validator>> {
+    match jsonschema::validator_for(schema) {
+        Ok(validator) => Some(Arc::new(validator)),
+        Err(e) => {
+            tracing::warn!(tool = %tool_name, error = %e, "Invalid MCP input schema, not validating arguments");
+            None
+        }
+    }
+}
+
+/// Every way `args` breaks the schema, one per line, or `None` if it's valid.
+fn validation_errors(validator: &jsonschema::Validator, args: &JsonValue) -> Option<String> {
+    let errors: Vec<String> = validator
+        .iter_errors(args)
+        .map(|e| {
+            let path = e.instance_path.to_string();
+            if path.is_empty() {
+                format!("- {}", e)
+            } else {
+                format!("- {}: {}", path, e)
+            }
+        })
+        .collect();
+    (!errors.is_empty()).then(|| errors.join("\n"))
+}
+
+/// Extract `(media_type, base64 data)` from a serialized MCP image item.
+fn image_item(item: &JsonValue) -> Option<(String, String)> {
+    if item.get("type")?.as_str()? != "image" {
+        return None;
+    }
+    let data = item.get("data")?.as_str()?.to_string();
+    let media_type = item
+        .get("mimeType")
+        .or_else(|| item.get("mime_type"))
+        .and_then(|m| m.as_str())
+        .unwrap_or("image/png")
+        .to_string();
+    Some((media_type, data))
 }
 
-// Safety: The pointer is only used during a single executor run
-// where the McpManager is guaranteed to outlive the tool executor.
-unsafe impl Send for McpToolExecutor {}
-unsafe impl Sync for McpToolExecutor {}
+/// Pick the structured form of an MCP result, if it has one.
+///
+/// Images win (the first one is shown); otherwise text that parses as a JSON
+/// object or array is kept as JSON. Plain text needs no special rendering.
+fn structured_content(items: &[JsonValue], text: &str) -> Option<ToolResultContent> {
+    if let Some((media_type, data)) = items.iter().find_map(image_item) {
+        return Some(ToolResultContent::Image { media_type, data });
+    }
+    match serde_json::from_str::<JsonValue>(text.trim()) {
+        Ok(value) if value.is_object() || value.is_array() => Some(ToolResultContent::Json(value)),
+        _ => None,
+    }
+}
+
+/// How a guarded MCP call finished.
+enum CallOutcome<T> {
+    Completed(T),
+    TimedOut,
+    Cancelled,
+}
+
+/// Drive `call` until it completes, times out, or the run is cancelled.
+///
+/// Cancellation is checked first so an already-cancelled run never starts
+/// waiting on the server.
+async fn guarded_call<F: Future>(
+    call: F,
+    timeout: Duration,
+    cancel: Option<&CancelToken>,
+) -> CallOutcome<F::Output> {
+    let timed = tokio::time::timeout(timeout, call);
+    let result = match cancel {
+        Some(cancel) => tokio::select! {
+            biased;
+            _ = cancel.cancelled() => return CallOutcome::Cancelled,
+            result = timed => result,
+        },
+        None => timed.await,
+    };
+    match result {
+        Ok(output) => CallOutcome::Completed(output),
+        Err(_) => CallOutcome::TimedOut,
+    }
+}
+
+impl McpToolExecutor {
+    /// Clean up after a cancelled call and build the result for the model.
+    ///
+    /// The dropped request may still be in progress on the server side, so the
+    /// connection is restarted in the background to avoid a stray response
+    /// being matched to the next call.
+    fn handle_cancelled(&self) -> ToolReturn {
+        tracing::info!(
+            server = %self.server_name,
+            tool = %self.tool_name,
+            "MCP tool call cancelled"
+        );
+
+        if let Some(bus) = &self.bus {
+            let _ = bus.send(Message::tool_failed(&self.tool_name, "Cancelled"));
+        }
+
+        let manager = self.mcp_manager.clone();
+        let server = self.server_name.clone();
+        tokio::spawn(async move {
+            if !manager.is_running(&server).await {
+                return;
+            }
+            if let Err(e) = manager.restart_server(&server).await {
+                tracing::warn!(server = %server, error = %e, "Failed to reset MCP server after cancellation");
+            }
+        });
+
+        ToolReturn::error(format!(
+            "MCP tool '{}' on server '{}' was cancelled",
+            self.tool_name, self.server_name
+        ))
+    }
+}
 
 #[async_trait]
 impl Tool for McpToolExecutor {
@@ -36,13 +181,50 @@ impl Tool for McpToolExecutor {
     }
 
     async fn call(&self, _ctx: &RunContext<()>, args: JsonValue) -> Result<ToolReturn, ToolError> {
-        // Safety: The McpManager outlives this executor
-        let manager = unsafe { &*self.mcp_manager_ptr };
-
-        match manager
-            .call_tool(&self.server_name, &self.tool_name, args)
-            .await
+        if let Some(errors) = self
+            .validator
+            .as_deref()
+            .and_then(|validator| validation_errors(validator, &args))
         {
+            tracing::debug!(
+                server = %self.server_name,
+                tool = %self.tool_name,
+                "MCP tool arguments failed schema validation"
+            );
+            return Ok(ToolReturn::error(format!(
+                "Invalid arguments for MCP tool '{}':\n{}\nFix the arguments and call it again.",
+                self.tool_name, errors
+            )));
+        }
+
+        // Dropping the call future on timeout or cancel aborts the pending request
+        let call = self
+            .mcp_manager
+            .call_tool(&self.server_name, &self.tool_name, args);
+        let outcome = guarded_call(call, self.timeout, self.cancel.as_ref()).await;
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        let outcome = match outcome {
+            CallOutcome::Completed(outcome) => outcome,
+            CallOutcome::Cancelled => return Ok(self.handle_cancelled()),
+            CallOutcome::TimedOut => {
+                tracing::warn!(
+                    server = %self.server_name,
+                    tool = %self.tool_name,
+                    timeout_secs = self.timeout.as_secs(),
+                    "MCP tool call timed out"
+                );
+                return Ok(ToolReturn::error(format!(
+                    "MCP tool '{}' on server '{}' timed out after {}s",
+                    self.tool_name,
+                    self.server_name,
+                    self.timeout.as_secs()
+                )));
+            }
+        };
+
+        match outcome {
             Ok(result) => {
                 // Convert MCP result to ToolReturn
                 if result.is_error {
@@ -56,15 +238,29 @@ impl Tool for McpToolExecutor {
                         .unwrap_or_else(|| "Unknown error".to_string());
                     Ok(ToolReturn::error(error_msg))
                 } else {
+                    let items: Vec<JsonValue> = result
+                        .content
+                        .iter()
+                        .map(|c| serde_json::to_value(c).unwrap_or_default())
+                        .collect();
+                    // The model gets text; images are noted so it knows they were shown
                     let text = result
                         .content
                         .into_iter()
-                        .filter_map(|c| match c {
+                        .zip(items.iter())
+                        .filter_map(|(c, item)| match c {
                             serdes_ai_mcp::ToolResultContent::Text { text } => Some(text),
-                            _ => None,
+                            _ => image_item(item)
+                                .map(|(media_type, _)| format!("[image: {}]", media_type)),
                         })
                         .collect::<Vec<_>>()
                         .join("\n");
+
+                    if let Some(contents) = &self.contents {
+                        if let Some(content) = structured_content(&items, &text) {
+                            contents.push(&self.tool_name, content);
+                        }
+                    }
                     Ok(ToolReturn::text(text))
                 }
             }
@@ -79,15 +275,25 @@ impl Tool for McpToolExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::ptr;
+    use crate::mcp::McpConfig;
+    use crate::messaging::MessageBus;
+
+    fn empty_manager() -> McpManager {
+        McpManager::with_config(McpConfig::new())
+    }
 
     #[test]
     fn mcp_tool_executor_definition_name() {
-        // Use null pointer - safe as long as we don't call()
         let executor = McpToolExecutor {
             server_name: "filesystem".to_string(),
             tool_name: "read_file".to_string(),
-            mcp_manager_ptr: ptr::null(),
+            mcp_manager: empty_manager(),
+            timeout: Duration::from_secs(30),
+            cancel: None,
+            bus: None,
+            contents: None,
+            validator: None,
+            cache: None,
         };
 
         let def = executor.definition();
@@ -99,7 +305,13 @@ mod tests {
         let executor = McpToolExecutor {
             server_name: "github-mcp".to_string(),
             tool_name: "list_issues".to_string(),
-            mcp_manager_ptr: ptr::null(),
+            mcp_manager: empty_manager(),
+            timeout: Duration::from_secs(30),
+            cancel: None,
+            bus: None,
+            contents: None,
+            validator: None,
+            cache: None,
         };
 
         let def = executor.definition();
@@ -111,13 +323,248 @@ mod tests {
         let executor = McpToolExecutor {
             server_name: "my-server".to_string(),
             tool_name: "my-tool".to_string(),
-            mcp_manager_ptr: ptr::null(),
+            mcp_manager: empty_manager(),
+            timeout: Duration::from_secs(30),
+            cancel: None,
+            bus: None,
+            contents: None,
+            validator: None,
+            cache: None,
         };
 
         assert_eq!(executor.server_name, "my-server");
         assert_eq!(executor.tool_name, "my-tool");
     }
 
-    // Note: call() tests require a running MCP server, skipped for unit tests.
+    #[tokio::test]
+    async fn mcp_tool_executor_server_not_running_returns_error() {
+        let executor = McpToolExecutor {
+            server_name: "offline".to_string(),
+            tool_name: "fetch".to_string(),
+            mcp_manager: empty_manager(),
+            timeout: Duration::from_secs(30),
+            cancel: None,
+            bus: None,
+            contents: None,
+            validator: None,
+            cache: None,
+        };
+
+        let ctx = RunContext::minimal("test");
+        let result = executor.call(&ctx, serde_json::json!({})).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn mcp_tool_executor_call_clears_cache() {
+        let cache = Arc::new(ToolCache::default());
+        let read = serde_json::json!({"file_path": "src/lib.rs"});
+        cache.insert("read_file", &read, &ToolReturn::text("contents"));
+        assert!(cache.get("read_file", &read).is_some());
+
+        let executor = McpToolExecutor {
+            server_name: "offline".to_string(),
+            tool_name: "write_file".to_string(),
+            mcp_manager: empty_manager(),
+            timeout: Duration::from_secs(30),
+            cancel: None,
+            bus: None,
+            contents: None,
+            validator: None,
+            cache: Some(Arc::clone(&cache)),
+        };
+
+        let ctx = RunContext::minimal("test");
+        let _ = executor.call(&ctx, serde_json::json!({})).await;
+        assert!(cache.get("read_file", &read).is_none());
+    }
+
+    #[tokio::test]
+    async fn mcp_tool_executor_outlives_original_manager() {
+        let manager = empty_manager();
+        let executor = McpToolExecutor {
+            server_name: "offline".to_string(),
+            tool_name: "fetch".to_string(),
+            mcp_manager: manager.clone(),
+            timeout: Duration::from_secs(30),
+            cancel: None,
+            bus: None,
+            contents: None,
+            validator: None,
+            cache: None,
+        };
+        drop(manager);
+
+        // Still safe to call after the original handle is gone
+        let ctx = RunContext::minimal("test");
+        let handle = tokio::spawn(async move { executor.call(&ctx, serde_json::json!({})).await });
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn guarded_call_cancelled_during_slow_call() {
+        let token = CancelToken::new();
+        let slow = tokio::time::sleep(Duration::from_secs(60));
+
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let outcome = guarded_call(slow, Duration::from_secs(30), Some(&token)).await;
+        assert!(matches!(outcome, CallOutcome::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn guarded_call_times_out_and_completes() {
+        let slow = tokio::time::sleep(Duration::from_secs(60));
+        let outcome = guarded_call(slow, Duration::from_millis(10), None).await;
+        assert!(matches!(outcome, CallOutcome::TimedOut));
+
+        let token = CancelToken::new();
+        let outcome = guarded_call(async { 42 }, Duration::from_secs(1), Some(&token)).await;
+        assert!(matches!(outcome, CallOutcome::Completed(42)));
+    }
+
+    #[tokio::test]
+    async fn mcp_tool_executor_cancelled_call_keeps_manager_usable() {
+        let manager = empty_manager();
+        let bus = MessageBus::new();
+        let mut rx = bus.subscribe();
+        let token = CancelToken::new();
+        let executor = McpToolExecutor {
+            server_name: "slow".to_string(),
+            tool_name: "crunch".to_string(),
+            mcp_manager: manager.clone(),
+            timeout: Duration::from_secs(30),
+            cancel: Some(token.clone()),
+            bus: Some(bus.sender()),
+            contents: None,
+            validator: None,
+            cache: None,
+        };
+
+        token.cancel();
+        let ctx = RunContext::minimal("test");
+        let result = executor.call(&ctx, serde_json::json!({})).await.unwrap();
+        assert!(result.is_error());
+
+        // A cancellation note is published for renderers
+        match rx.try_recv() {
+            Ok(Some(Message::Tool(tool))) => {
+                assert_eq!(tool.tool_name, "crunch");
+                assert_eq!(tool.error.as_deref(), Some("Cancelled"));
+            }
+            other => panic!("expected tool cancellation note, got {:?}", other),
+        }
+
+        // The shared manager still answers promptly afterwards
+        let follow_up = tokio::time::timeout(
+            Duration::from_secs(1),
+            manager.call_tool("slow", "crunch", serde_json::json!({})),
+        )
+        .await
+        .expect("manager should not be left hanging");
+        assert!(follow_up.is_err());
+        assert!(manager.running_servers().await.is_empty());
+    }
+
+    #[test]
+    fn validation_errors_lists_each_problem() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "repo": {"type": "string"},
+                "limit": {"type": "integer"}
+            },
+            "required": ["repo"]
+        });
+        let validator = compile_schema("list_issues", &schema).unwrap();
+
+        assert_eq!(
+            validation_errors(&validator, &serde_json::json!({"repo": "a/b", "limit": 5})),
+            None
+        );
+
+        let errors = validation_errors(&validator, &serde_json::json!({"limit": "five"})).unwrap();
+        assert_eq!(errors.lines().count(), 2);
+        assert!(errors.contains("\"repo\" is a required property"));
+        assert!(errors.contains("/limit: "));
+    }
+
+    #[test]
+    fn compile_schema_skips_invalid_schema() {
+        assert!(compile_schema("broken", &serde_json::json!({"type": 12})).is_none());
+    }
+
+    #[tokio::test]
+    async fn mcp_tool_executor_rejects_invalid_args_before_dispatch() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {"url": {"type": "string"}},
+            "required": ["url"]
+        });
+        let executor = McpToolExecutor {
+            server_name: "offline".to_string(),
+            tool_name: "fetch".to_string(),
+            mcp_manager: empty_manager(),
+            timeout: Duration::from_secs(30),
+            cancel: None,
+            bus: None,
+            contents: None,
+            validator: compile_schema("fetch", &schema),
+            cache: None,
+        };
+
+        // An error return the model can act on, not the server's failure
+        let ctx = RunContext::minimal("test");
+        let result = executor.call(&ctx, serde_json::json!({})).await.unwrap();
+        assert!(result.is_error());
+        assert!(result
+            .as_text()
+            .unwrap()
+            .contains("\"url\" is a required property"));
+
+        let result = executor
+            .call(&ctx, serde_json::json!({"url": "https://example.com"}))
+            .await;
+        assert!(
+            result.is_err(),
+            "valid arguments reach the (offline) server"
+        );
+    }
+
+    #[test]
+    fn structured_content_prefers_images() {
+        let items = vec![
+            serde_json::json!({"type": "text", "text": "captured"}),
+            serde_json::json!({"type": "image", "data": "aGVsbG8=", "mimeType": "image/jpeg"}),
+        ];
+        assert_eq!(
+            structured_content(&items, "captured\n[image: image/jpeg]"),
+            Some(ToolResultContent::Image {
+                media_type: "image/jpeg".to_string(),
+                data: "aGVsbG8=".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn structured_content_json_and_plain_text() {
+        let json = structured_content(&[], r#"{"issues": [1, 2]}"#);
+        assert_eq!(
+            json,
+            Some(ToolResultContent::Json(
+                serde_json::json!({"issues": [1, 2]})
+            ))
+        );
+
+        assert_eq!(structured_content(&[], "just words"), None);
+        // Bare JSON scalars aren't worth a structured view
+        assert_eq!(structured_content(&[], "42"), None);
+    }
+
+    // Note: call() tests against a live (or hung) MCP server are skipped for unit tests.
     // Integration tests should cover MCP tool execution.
 }
@@ -5,46 +5,116 @@
 //!
 //! ## Submodules
 //! - `adapters`: Model and tool adapters for serdesAI integration
+//! - `approval`: Approval policies for tools that change things
+//! - `context_files`: Per-agent context files prepended to prompts
+//! - `instructions`: Workspace instructions appended to system prompts
+//! - `retry`: Bounded retries for failed model requests
 //! - `sub_agents`: Executors for invoke_agent and list_agents tools
+//! - `timeouts`: Time limits on streamed model responses
+//! - `titles`: Background titles for new sub-agent sessions
 //! - `mcp`: MCP tool executor
+//! - `network`: Proxy and CA bundle settings for HTTP requests
 //! - `types`: Result types and errors
 //! - `model_factory`: Model resolution and creation
 
 mod adapters;
+mod approval;
+mod cache;
+mod context_files;
+mod instructions;
 mod mcp;
 mod model_factory;
+mod network;
+mod prefetch;
+mod quotas;
+mod retry;
 mod sub_agents;
+mod timeouts;
+mod titles;
 mod types;
 
 // Re-export public API
+pub use approval::{
+    is_read_only, AllowAll, ApprovalPolicy, Decision, DenyAll, Interactive, ToolCall,
+};
+pub(crate) use model_factory::endpoint_api_key;
 pub use model_factory::get_model;
-pub use types::{ExecuteContext, ExecutorError, ExecutorResult, ExecutorStreamReceiver};
+pub use network::{export_network_env, http_client, restore_child_env};
+pub use retry::{ErrorClass, GaveUp, RetryPolicy};
+pub use types::{
+    CancelToken, ExecuteContext, ExecutorError, ExecutorResult, ExecutorStreamReceiver,
+    OutputFilter,
+};
 
 use crate::agents::SpotAgent;
 use crate::config::Settings;
 use crate::db::Database;
 use crate::mcp::McpManager;
-use crate::messaging::{EventBridge, MessageSender};
-use crate::models::settings::ModelSettings as SpotModelSettings;
+use crate::messaging::{EventBridge, MessageSender, ToolContentStore};
+use crate::models::settings::{ModelSettings as SpotModelSettings, SamplingOverride};
 use crate::models::ModelRegistry;
-use crate::tools::SpotToolRegistry;
+use crate::session::BudgetTracker;
+use crate::tokens::{
+    estimate_content_tokens, estimate_text_tokens, estimate_tokens, MIN_COMPLETION_TOKENS,
+};
+use crate::tools::registry::ArcTool;
+use crate::tools::{CommandRules, SpotToolRegistry, UndoRun};
 
 use adapters::{ArcModel, ToolExecutorAdapter};
-use mcp::McpToolExecutor;
-use sub_agents::{InvokeAgentExecutor, ListAgentsExecutor};
+use approval::Sandbox;
+use cache::ToolCache;
+use mcp::{compile_schema, McpToolExecutor};
+use quotas::ToolQuotas;
+use sub_agents::{parent_history, InvokeAgentExecutor, ListAgentsExecutor};
+use timeouts::timed_out;
 
 use serdes_ai_agent::{agent, RunOptions};
 use serdes_ai_core::messages::{ImageMediaType, UserContent, UserContentPart};
 use serdes_ai_core::{ModelRequest, ToolReturnPart};
 use serdes_ai_tools::{Tool, ToolDefinition};
 
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tokio::sync::Mutex;
 use tracing::{debug, info};
 
 // Re-export stream event
 pub use serdes_ai_agent::AgentStreamEvent as StreamEvent;
 
+/// Output token limit when neither the agent nor the model sets one.
+const DEFAULT_MAX_TOKENS: u64 = 30000;
+
+/// Rough size of one tool definition in a request.
+const TOOL_DEFINITION_TOKENS: usize = 150;
+
+/// Generation parameters for one run, see [`AgentExecutor::sampling_params`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sampling {
+    temperature: f64,
+    top_p: f64,
+    max_tokens: u64,
+}
+
+/// Estimated prompt size for a run: system prompt, history, the new
+/// prompt and the tool definitions sent with them.
+fn estimate_prompt_tokens(
+    system_prompt: &str,
+    history: Option<&[ModelRequest]>,
+    prompt_tokens: usize,
+    tool_count: usize,
+) -> usize {
+    estimate_text_tokens(system_prompt)
+        + history.map(estimate_tokens).unwrap_or(0)
+        + prompt_tokens
+        + tool_count * TOOL_DEFINITION_TOKENS
+}
+
+/// Cap `requested` output tokens to the room left in a context window,
+/// never going below [`MIN_COMPLETION_TOKENS`] (or `requested`, if smaller).
+fn fit_completion(requested: u64, context_length: usize, prompt_tokens: usize) -> u64 {
+    let available = context_length.saturating_sub(prompt_tokens) as u64;
+    requested.min(available.max(MIN_COMPLETION_TOKENS))
+}
+
 /// Agent executor that bridges SpotAgents with serdesAI.
 ///
 /// This replaces raw model calls with proper agent execution including:
@@ -57,6 +127,30 @@ pub struct AgentExecutor<'a> {
     registry: &'a ModelRegistry,
     /// Optional message bus for event publishing.
     bus: Option<MessageSender>,
+    /// Optional cancellation signal for in-flight tool calls.
+    cancel: Option<CancelToken>,
+    /// One-off temperature/top_p override for this executor's runs.
+    sampling: SamplingOverride,
+    /// Applied in order to the final output; history keeps the raw text.
+    output_filters: Vec<OutputFilter>,
+    /// Consulted before tools that aren't read-only; `None` allows all.
+    approval: Option<Arc<dyn ApprovalPolicy>>,
+    /// Session budget checked before each turn and tool call.
+    budget: Option<Arc<BudgetTracker>>,
+    /// Read-only tools only, in addition to the `sandbox_mode` setting.
+    sandbox: bool,
+    /// No tools at all, for plain chat.
+    no_tools: bool,
+    /// Tools turned off by name for this executor's runs.
+    disabled_tools: Vec<String>,
+    /// Agents whose invoke_agent calls led to this run, outermost first.
+    callers: Vec<String>,
+    /// Attempt and time limits for retrying a failed model request.
+    retry: RetryPolicy,
+    /// Snapshot of files changed by the run, for undo.
+    undo: Option<UndoRun>,
+    /// Workspace instructions, looked up once per executor.
+    instructions: OnceLock<Option<String>>,
 }
 
 impl<'a> AgentExecutor<'a> {
@@ -66,6 +160,18 @@ impl<'a> AgentExecutor<'a> {
             db,
             registry,
             bus: None,
+            cancel: None,
+            sampling: SamplingOverride::default(),
+            output_filters: Vec::new(),
+            approval: None,
+            budget: None,
+            sandbox: false,
+            no_tools: false,
+            disabled_tools: Vec::new(),
+            callers: Vec::new(),
+            retry: RetryPolicy::default(),
+            undo: None,
+            instructions: OnceLock::new(),
         }
     }
 
@@ -78,17 +184,248 @@ impl<'a> AgentExecutor<'a> {
         self
     }
 
+    /// Attach a cancellation token for the run.
+    ///
+    /// Cancelling the token ends a streaming run at once, returning the
+    /// history it got through: calls that returned, with their results, and
+    /// the text streamed so far. It also aborts any in-flight MCP tool call
+    /// and resets that server's connection so the next run starts clean.
+    pub fn with_cancellation(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Record the chain of agents that invoked this run's agent, so its own
+    /// invoke_agent calls can be checked for depth and cycles.
+    fn with_callers(mut self, callers: Vec<String>) -> Self {
+        self.callers = callers;
+        self
+    }
+
+    /// The invocation chain for sub-agents that `agent_name` invokes.
+    fn chain_through(&self, agent_name: &str) -> Vec<String> {
+        let mut chain = self.callers.clone();
+        chain.push(agent_name.to_string());
+        chain
+    }
+
+    /// Override temperature/top_p for this executor's runs without
+    /// touching the persisted per-model settings.
+    pub fn with_sampling_override(mut self, sampling: SamplingOverride) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Require approval for tool calls that aren't read-only.
+    ///
+    /// Applies to this executor's own tools, MCP tools and the tools of
+    /// sub-agents started with `invoke_agent`.
+    pub fn with_approval_policy(mut self, policy: Arc<dyn ApprovalPolicy>) -> Self {
+        self.approval = Some(policy);
+        self
+    }
+
+    /// Restrict runs to read-only tools.
+    ///
+    /// Sandbox mode is also on whenever the `sandbox_mode` setting is set.
+    /// Tools that can modify anything (edits, deletes, shell commands, MCP
+    /// tools and sub-agents) are left out, and any that still get called
+    /// are refused.
+    pub fn with_sandbox(mut self, sandbox: bool) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Run without any tools, so the model can only reply with text.
+    ///
+    /// Overrides the agent's tool list: no registry, MCP or sub-agent
+    /// tools are registered.
+    pub fn with_no_tools(mut self, no_tools: bool) -> Self {
+        self.no_tools = no_tools;
+        self
+    }
+
+    /// Leave these tools out, whether the agent lists them or they come
+    /// from an MCP server.
+    pub fn with_disabled_tools(mut self, tools: Vec<String>) -> Self {
+        self.disabled_tools = tools;
+        self
+    }
+
+    /// Whether `name` was turned off with [`with_disabled_tools`](Self::with_disabled_tools).
+    fn tool_disabled(&self, name: &str) -> bool {
+        self.disabled_tools.iter().any(|tool| tool == name)
+    }
+
+    /// Whether this executor runs sandboxed.
+    pub fn sandbox_active(&self) -> bool {
+        self.sandbox || sandbox_mode_enabled(self.db)
+    }
+
+    /// The policy tool adapters consult before running.
+    fn approval_policy(&self) -> Option<Arc<dyn ApprovalPolicy>> {
+        if self.sandbox_active() {
+            Some(Arc::new(Sandbox))
+        } else {
+            self.approval.clone()
+        }
+    }
+
+    /// Limit how long a failed model request is retried.
+    ///
+    /// Rate limits and transient errors are retried with backoff until the
+    /// policy's attempts or deadline run out, whichever comes first; the
+    /// run then fails with the last error. Applies to streaming runs, where
+    /// a request that never started can be sent again safely.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Halt runs once the session goes over its budget.
+    ///
+    /// The tracker is checked before the run, before each model turn and
+    /// before each tool call, and counts the tokens the run consumes; read
+    /// [`BudgetTracker::run_usage`] afterwards to add them to the session.
+    pub fn with_budget(mut self, tracker: Arc<BudgetTracker>) -> Self {
+        self.budget = Some(tracker);
+        self
+    }
+
+    /// Fail if the session is already over budget.
+    fn check_budget(&self) -> Result<(), ExecutorError> {
+        match &self.budget {
+            Some(tracker) => Ok(tracker.check()?),
+            None => Ok(()),
+        }
+    }
+
+    /// Save files to `run` before `edit_file`, `delete_file`, `move_file`
+    /// or `replace_in_files` change them.
+    ///
+    /// Once the run completes, the snapshot is keyed by its run ID and can
+    /// be restored with [`UndoJournal::undo_last`](crate::tools::UndoJournal::undo_last).
+    /// Sub-agents started with `invoke_agent` record into the same run.
+    pub fn with_undo(mut self, run: UndoRun) -> Self {
+        self.undo = Some(run);
+        self
+    }
+
+    /// Key the undo snapshot by the run ID and apply output filters.
+    fn finish_run(&self, result: ExecutorResult) -> ExecutorResult {
+        if let Some(undo) = &self.undo {
+            if let Err(e) = undo.finish(&result.run_id) {
+                tracing::warn!(run_id = %result.run_id, error = %e, "Failed to save undo snapshot");
+            }
+        }
+        self.filter_output(result)
+    }
+
+    /// Post-process the final output before it is returned.
+    ///
+    /// Filters run in registration order on [`ExecutorResult::output`] only;
+    /// streamed deltas and the message history are left untouched, so the
+    /// model still sees exactly what it said on the next turn.
+    pub fn with_output_filter(mut self, filter: OutputFilter) -> Self {
+        self.output_filters.push(filter);
+        self
+    }
+
+    /// Run the registered output filters over a finished result.
+    fn filter_output(&self, mut result: ExecutorResult) -> ExecutorResult {
+        for filter in &self.output_filters {
+            result.output = filter(&result.output);
+        }
+        result
+    }
+
+    /// Generation parameters to run an agent on a model with.
+    ///
+    /// Each value comes from, in order of precedence: the run's override
+    /// (`--temperature`/`--top-p`), the agent's definition, the model's
+    /// settings, then the defaults. When thinking is enabled, temperature
+    /// MUST be 1.0 per Claude API requirements, so any other is ignored.
+    fn sampling_params(&self, spot_agent: &dyn SpotAgent, model_name: &str) -> Sampling {
+        let mut spot_settings = SpotModelSettings::load(self.db, model_name).unwrap_or_default();
+        let agent_sampling = SamplingOverride {
+            temperature: spot_agent.temperature(),
+            top_p: spot_agent.top_p(),
+        };
+        agent_sampling.apply(&mut spot_settings);
+        self.sampling.apply(&mut spot_settings);
+
+        // Check if this model has thinking enabled (supports it and not explicitly disabled)
+        let model_supports_thinking = self
+            .registry
+            .get(model_name)
+            .map(|c| c.supports_thinking)
+            .unwrap_or(false);
+        let temperature = if spot_settings.thinking_enabled(model_supports_thinking) {
+            if self.sampling.temperature.is_some() || agent_sampling.temperature.is_some() {
+                tracing::warn!(
+                    model = %model_name,
+                    agent = %spot_agent.name(),
+                    "Ignoring temperature override: thinking models require 1.0"
+                );
+            }
+            1.0
+        } else {
+            spot_settings.effective_temperature() as f64
+        };
+
+        let max_tokens = spot_agent
+            .max_tokens()
+            .map(u64::from)
+            .or(spot_settings.max_tokens.and_then(|t| u64::try_from(t).ok()))
+            .unwrap_or(DEFAULT_MAX_TOKENS);
+
+        Sampling {
+            temperature,
+            top_p: spot_settings.effective_top_p() as f64,
+            max_tokens,
+        }
+    }
+
+    /// Output tokens to ask for: `requested`, cut down to what's left of the
+    /// model's context window after the estimated prompt.
+    ///
+    /// Models missing from the registry have no known window and get
+    /// `requested` as is.
+    fn completion_budget(&self, model_name: &str, requested: u64, prompt_tokens: usize) -> u64 {
+        let Some(context_length) = self.registry.get(model_name).map(|c| c.context_length) else {
+            return requested;
+        };
+        let max_tokens = fit_completion(requested, context_length, prompt_tokens);
+        debug!(
+            model = %model_name,
+            context_length,
+            prompt_tokens,
+            requested,
+            max_tokens,
+            "Computed completion budget"
+        );
+        max_tokens
+    }
+
     /// Filter tool names based on settings.
     ///
     /// Filters out:
     /// - `share_your_reasoning` unless `show_reasoning` is enabled
     /// - `invoke_agent` and `list_agents` (these use custom executors)
+    /// - anything that isn't read-only, in sandbox mode
+    /// - tools turned off by name, and everything when tools are turned off
     fn filter_tools<'b>(&self, tool_names: Vec<&'b str>) -> Vec<&'b str> {
+        if self.no_tools {
+            return Vec::new();
+        }
         let settings = Settings::new(self.db);
-        let show_reasoning = settings.get_bool("show_reasoning").unwrap_or(false);
+        let show_reasoning = settings.show_reasoning();
+        let sandbox = self.sandbox_active();
 
         tool_names
             .into_iter()
+            .filter(|name| !sandbox || is_read_only(name))
+            .filter(|name| !self.tool_disabled(name))
             .filter(|name| {
                 match *name {
                     "share_your_reasoning" => show_reasoning,
@@ -100,14 +437,81 @@ impl<'a> AgentExecutor<'a> {
             .collect()
     }
 
+    /// Registry tools by name, with the agent's shell command rules applied
+    /// to `run_shell_command`, the `max_read_file_bytes` setting to
+    /// `read_file` and file changes recorded for undo.
+    fn registry_tools(
+        &self,
+        registry: &SpotToolRegistry,
+        tool_names: &[&str],
+        agent_name: &str,
+    ) -> Vec<ArcTool> {
+        let mut tools = registry.tools_by_name(tool_names);
+        let settings = Settings::new(self.db);
+        if tool_names.contains(&"read_file") {
+            let max_bytes = settings.max_read_file_bytes();
+            let read_file: ArcTool =
+                Arc::new(registry.read_file.clone().with_max_size(max_bytes.into()));
+            for tool in tools.iter_mut() {
+                if tool.definition().name() == "read_file" {
+                    *tool = Arc::clone(&read_file);
+                }
+            }
+        }
+        if tool_names.contains(&"run_shell_command") {
+            let rules = CommandRules {
+                allow: settings.get_shell_allowlist(agent_name),
+                deny: settings.get_shell_denylist(agent_name),
+            };
+            if !rules.is_empty() {
+                let shell: ArcTool =
+                    Arc::new(registry.run_shell_command.clone().with_command_rules(rules));
+                for tool in tools.iter_mut() {
+                    if tool.definition().name() == "run_shell_command" {
+                        *tool = Arc::clone(&shell);
+                    }
+                }
+            }
+        }
+        if let Some(undo) = &self.undo {
+            let edit: ArcTool = Arc::new(registry.edit_file.clone().with_undo(undo.clone()));
+            let delete: ArcTool = Arc::new(registry.delete_file.clone().with_undo(undo.clone()));
+            let move_file: ArcTool = Arc::new(registry.move_file.clone().with_undo(undo.clone()));
+            let replace: ArcTool =
+                Arc::new(registry.replace_in_files.clone().with_undo(undo.clone()));
+            for tool in tools.iter_mut() {
+                match tool.definition().name() {
+                    "edit_file" => *tool = Arc::clone(&edit),
+                    "delete_file" => *tool = Arc::clone(&delete),
+                    "move_file" => *tool = Arc::clone(&move_file),
+                    "replace_in_files" => *tool = Arc::clone(&replace),
+                    _ => {}
+                }
+            }
+        }
+        tools
+    }
+
+    /// The agent's system prompt with the workspace's instructions appended.
+    fn system_prompt(&self, spot_agent: &dyn SpotAgent) -> String {
+        let workspace = self
+            .instructions
+            .get_or_init(instructions::load_workspace_instructions);
+        instructions::append_instructions(spot_agent.system_prompt(), workspace.as_deref())
+    }
+
     /// Check if agent wants invoke_agent tool.
     fn wants_invoke_agent(&self, tool_names: &[&str]) -> bool {
+        // Sub-agents could modify things, so the sandbox has none
         tool_names.contains(&"invoke_agent")
+            && !self.sandbox_active()
+            && !self.no_tools
+            && !self.tool_disabled("invoke_agent")
     }
 
     /// Check if agent wants list_agents tool.
     fn wants_list_agents(&self, tool_names: &[&str]) -> bool {
-        tool_names.contains(&"list_agents")
+        tool_names.contains(&"list_agents") && !self.no_tools && !self.tool_disabled("list_agents")
     }
 
     /// Execute an agent with a prompt (blocking mode).
@@ -123,6 +527,31 @@ impl<'a> AgentExecutor<'a> {
         tool_registry: &SpotToolRegistry,
         mcp_manager: &McpManager,
     ) -> Result<ExecutorResult, ExecutorError> {
+        let context = ExecuteContext {
+            tool_registry,
+            mcp_manager,
+        };
+        self.execute_content(
+            spot_agent,
+            model_name,
+            UserContent::text(prompt),
+            message_history,
+            &context,
+        )
+        .await
+    }
+
+    /// Blocking execution with full control over user content.
+    async fn execute_content(
+        &self,
+        spot_agent: &dyn SpotAgent,
+        model_name: &str,
+        prompt: UserContent,
+        message_history: Option<Vec<ModelRequest>>,
+        context: &ExecuteContext<'_>,
+    ) -> Result<ExecutorResult, ExecutorError> {
+        self.check_budget()?;
+
         // Load model settings for thinking configuration
         let spot_settings = SpotModelSettings::load(self.db, model_name).ok();
 
@@ -137,18 +566,63 @@ impl<'a> AgentExecutor<'a> {
 
         // Get the tools this agent should have access to (filtered by settings)
         let tool_names = self.filter_tools(original_tools);
-        let tools = tool_registry.tools_by_name(&tool_names);
+        let tools = self.registry_tools(context.tool_registry, &tool_names, spot_agent.name());
+
+        // Per-run call limits and cached reads shared by every tool in this run
+        let quotas = Arc::new(ToolQuotas::load(self.db));
+        let cache = Arc::new(ToolCache::default());
+
+        // Collect MCP tools (filtered by agent attachments)
+        let mcp_tools = self
+            .collect_mcp_tools(
+                context.mcp_manager,
+                Some(spot_agent.name()),
+                None,
+                Some(&cache),
+            )
+            .await;
+
+        // What invoke_agent can share with a sub-agent, before context files
+        let parent = wants_invoke
+            .then(|| parent_history(message_history.as_deref(), &prompt))
+            .unwrap_or_default();
+
+        // Prepend the agent's context files, read fresh for this run
+        let prompt = match context_files::load_agent_context(self.db, spot_agent.name()) {
+            Some(context) => context_files::prepend_context(prompt, &context),
+            None => prompt,
+        };
+
+        // Size the completion to what the model's context window has left
+        let system_prompt = self.system_prompt(spot_agent);
+        let sampling = self.sampling_params(spot_agent, model_name);
+        let tool_count =
+            tools.len() + usize::from(wants_invoke) + usize::from(wants_list) + mcp_tools.len();
+        let prompt_tokens = estimate_prompt_tokens(
+            &system_prompt,
+            message_history.as_deref(),
+            estimate_content_tokens(&prompt),
+            tool_count,
+        );
+        let max_tokens = self.completion_budget(model_name, sampling.max_tokens, prompt_tokens);
 
         // Build the serdesAI agent
         let mut builder = agent(wrapped_model)
-            .system_prompt(spot_agent.system_prompt())
+            .system_prompt(system_prompt)
             .temperature(1.0)
-            .max_tokens(30000);
+            .max_tokens(max_tokens);
 
         // Register built-in tools with real executors
         for tool in tools {
             let def = tool.definition();
-            builder = builder.tool_with_executor(def, ToolExecutorAdapter::new(Arc::clone(&tool)));
+            builder = builder.tool_with_executor(
+                def,
+                ToolExecutorAdapter::new(Arc::clone(&tool))
+                    .with_quotas(Arc::clone(&quotas))
+                    .with_approval(self.approval_policy())
+                    .with_budget(self.budget.clone())
+                    .with_cache(Arc::clone(&cache)),
+            );
         }
 
         // Add invoke_agent with custom executor (has database access)
@@ -157,7 +631,12 @@ impl<'a> AgentExecutor<'a> {
                 InvokeAgentExecutor::new(self.db, model_name, bus.clone())
             } else {
                 InvokeAgentExecutor::new_legacy(self.db, model_name)
-            };
+            }
+            .with_cache(Arc::clone(&cache))
+            .with_parent_history(parent)
+            .with_chain(self.chain_through(spot_agent.name()))
+            .with_approval(self.approval.clone())
+            .with_undo(self.undo.clone());
             builder =
                 builder.tool_with_executor(InvokeAgentExecutor::definition(), invoke_executor);
         }
@@ -170,40 +649,25 @@ impl<'a> AgentExecutor<'a> {
             );
         }
 
-        // Add MCP tools (filtered by agent attachments)
-        let mcp_tools = self
-            .collect_mcp_tools(mcp_manager, Some(spot_agent.name()))
-            .await;
+        // Add MCP tools
         for (def, tool) in mcp_tools {
-            builder = builder.tool_with_executor(def, ToolExecutorAdapter::new(tool));
+            builder = builder.tool_with_executor(
+                def,
+                ToolExecutorAdapter::new(tool)
+                    .with_quotas(Arc::clone(&quotas))
+                    .with_approval(self.approval_policy())
+                    .with_budget(self.budget.clone())
+                    .with_cache(Arc::clone(&cache)),
+            );
         }
 
         let serdes_agent = builder.build();
 
-        // Load per-model settings from database
-        let spot_settings = SpotModelSettings::load(self.db, model_name).unwrap_or_default();
-
-        // Check if this model has thinking enabled (supports it and not explicitly disabled)
-        let model_supports_thinking = self
-            .registry
-            .get(model_name)
-            .map(|c| c.supports_thinking)
-            .unwrap_or(false);
-        let thinking_explicitly_disabled = spot_settings.extended_thinking == Some(false);
-        let thinking_enabled = model_supports_thinking && !thinking_explicitly_disabled;
-
         // Convert to serdes_ai_core::ModelSettings
-        // When thinking is enabled, temperature MUST be 1.0 per Claude API requirements
-        let effective_temp = if thinking_enabled {
-            1.0
-        } else {
-            spot_settings.effective_temperature() as f64
-        };
-
         let core_settings = serdes_ai_core::ModelSettings::new()
-            .temperature(effective_temp)
-            .top_p(spot_settings.effective_top_p() as f64)
-            .max_tokens(30000);
+            .temperature(sampling.temperature)
+            .top_p(sampling.top_p)
+            .max_tokens(max_tokens);
 
         // Set up run options with message history if provided
         let options = match message_history {
@@ -213,17 +677,27 @@ impl<'a> AgentExecutor<'a> {
             None => RunOptions::new().model_settings(core_settings),
         };
 
-        // Run the agent
-        let result = serdes_agent
-            .run_with_options(prompt, (), options)
-            .await
-            .map_err(|e| ExecutorError::Execution(e.to_string()))?;
+        // Run the agent. Its requests can't be timed one by one here, so
+        // the limit bounds the whole run
+        let run = serdes_agent.run_with_options(prompt, (), options);
+        let result = match spot_settings.as_ref().and_then(|s| s.request_timeout()) {
+            Some(limit) => tokio::time::timeout(limit, run)
+                .await
+                .map_err(|_| ExecutorError::Model(timed_out(limit)))?,
+            None => run.await,
+        }
+        .map_err(|e| ExecutorError::Execution(e.to_string()))?;
+
+        // Blocking runs don't expose turns, so count the final history once
+        if let Some(tracker) = &self.budget {
+            tracker.record_tokens(estimate_tokens(&result.messages));
+        }
 
-        Ok(ExecutorResult {
+        Ok(self.finish_run(ExecutorResult {
             output: result.output.clone(),
             messages: result.messages,
             run_id: result.run_id,
-        })
+        }))
     }
 
     /// Execute agent with events published to message bus.
@@ -250,10 +724,29 @@ impl<'a> AgentExecutor<'a> {
 
         // Create event bridge for this agent
         let mut bridge =
-            EventBridge::new(bus.clone(), spot_agent.name(), spot_agent.display_name());
+            EventBridge::new(bus.clone(), spot_agent.name(), spot_agent.display_name())
+                .with_reasoning(Settings::new(self.db).show_reasoning())
+                .with_depth(self.callers.len());
 
         bridge.agent_started();
 
+        let exec_context = ExecuteContext {
+            tool_registry,
+            mcp_manager,
+        };
+        if !self.supports_streaming(model_name) {
+            return self
+                .execute_unstreamed(
+                    &mut bridge,
+                    spot_agent,
+                    model_name,
+                    UserContent::text(prompt),
+                    message_history,
+                    &exec_context,
+                )
+                .await;
+        }
+
         // Track tool returns during streaming so we can reconstruct message history.
         let tool_return_recorder: Arc<Mutex<Vec<ToolReturnPart>>> =
             Arc::new(Mutex::new(Vec::new()));
@@ -265,10 +758,6 @@ impl<'a> AgentExecutor<'a> {
         messages.push(user_req);
 
         // Use internal streaming execution
-        let exec_context = ExecuteContext {
-            tool_registry,
-            mcp_manager,
-        };
         let mut stream = self
             .execute_stream_internal(
                 spot_agent,
@@ -277,6 +766,7 @@ impl<'a> AgentExecutor<'a> {
                 message_history,
                 &exec_context,
                 Some(Arc::clone(&tool_return_recorder)),
+                Some(bridge.content_store()),
             )
             .await?;
 
@@ -291,18 +781,8 @@ impl<'a> AgentExecutor<'a> {
             )
             .await?;
 
-        // Get the run_id (from RunComplete event)
-        let run_id = final_run_id.ok_or_else(|| {
-            ExecutorError::Execution("Stream ended without RunComplete event".into())
-        })?;
-
-        bridge.agent_completed(&run_id);
-
-        Ok(ExecutorResult {
-            output: accumulated_text,
-            messages,
-            run_id,
-        })
+        streaming::complete_run(&bridge, accumulated_text, final_run_id, messages)
+            .map(|result| self.finish_run(result))
     }
 
     /// Execute agent with images (multimodal content).
@@ -324,7 +804,9 @@ impl<'a> AgentExecutor<'a> {
 
         // Create event bridge for this agent
         let mut bridge =
-            EventBridge::new(bus.clone(), spot_agent.name(), spot_agent.display_name());
+            EventBridge::new(bus.clone(), spot_agent.name(), spot_agent.display_name())
+                .with_reasoning(Settings::new(self.db).show_reasoning())
+                .with_depth(self.callers.len());
 
         bridge.agent_started();
 
@@ -372,6 +854,19 @@ impl<'a> AgentExecutor<'a> {
             }
         }
 
+        if !self.supports_streaming(model_name) {
+            return self
+                .execute_unstreamed(
+                    &mut bridge,
+                    spot_agent,
+                    model_name,
+                    user_content,
+                    message_history,
+                    context,
+                )
+                .await;
+        }
+
         // Start with any provided history, then add the current user prompt.
         let mut messages = message_history.clone().unwrap_or_default();
         let mut user_req = ModelRequest::new();
@@ -387,6 +882,7 @@ impl<'a> AgentExecutor<'a> {
                 message_history,
                 context,
                 Some(Arc::clone(&tool_return_recorder)),
+                Some(bridge.content_store()),
             )
             .await?;
 
@@ -401,17 +897,8 @@ impl<'a> AgentExecutor<'a> {
             )
             .await?;
 
-        let run_id = final_run_id.ok_or_else(|| {
-            ExecutorError::Execution("Stream ended without RunComplete event".into())
-        })?;
-
-        bridge.agent_completed(&run_id);
-
-        Ok(ExecutorResult {
-            output: accumulated_text,
-            messages,
-            run_id,
-        })
+        streaming::complete_run(&bridge, accumulated_text, final_run_id, messages)
+            .map(|result| self.finish_run(result))
     }
 
     /// Execute an agent with streaming output.
@@ -441,6 +928,7 @@ impl<'a> AgentExecutor<'a> {
             message_history,
             &context,
             None,
+            None,
         )
         .await
     }
@@ -450,13 +938,28 @@ impl<'a> AgentExecutor<'a> {
     /// Only returns tools from MCP servers that are attached to the given agent.
     /// If no agent_name is provided or the agent has no attachments, returns tools
     /// from ALL running servers (for backwards compatibility).
+    ///
+    /// When `contents` is given, image and JSON results are also pushed there
+    /// so the event bridge can publish them in structured form. `cache` is
+    /// cleared after every call.
     async fn collect_mcp_tools(
         &self,
         mcp_manager: &McpManager,
         agent_name: Option<&str>,
+        contents: Option<&ToolContentStore>,
+        cache: Option<&Arc<ToolCache>>,
     ) -> Vec<(ToolDefinition, Arc<dyn Tool + Send + Sync>)> {
         let mut tools = Vec::new();
 
+        // MCP tools can do anything, so the sandbox has none
+        if self.sandbox_active() {
+            debug!("Sandbox mode: skipping MCP tools");
+            return tools;
+        }
+        if self.no_tools {
+            return tools;
+        }
+
         // Get agent's MCP attachments from settings
         let attached_mcps: Option<Vec<String>> = agent_name.and_then(|name| {
             let settings = Settings::new(self.db);
@@ -491,7 +994,17 @@ impl<'a> AgentExecutor<'a> {
                 "Including MCP tools from server"
             );
 
+            let timeout = mcp_manager
+                .config()
+                .get_server(&server_name)
+                .map(|entry| entry.tool_timeout())
+                .unwrap_or(crate::mcp::DEFAULT_TOOL_TIMEOUT);
+
             for mcp_tool in server_tools {
+                if self.tool_disabled(&mcp_tool.name) {
+                    continue;
+                }
+
                 // Create a tool definition from MCP tool
                 let def = ToolDefinition::new(
                     mcp_tool.name.clone(),
@@ -503,7 +1016,13 @@ impl<'a> AgentExecutor<'a> {
                 let executor = McpToolExecutor {
                     server_name: server_name.clone(),
                     tool_name: mcp_tool.name.clone(),
-                    mcp_manager_ptr: mcp_manager as *const McpManager,
+                    mcp_manager: mcp_manager.clone(),
+                    timeout,
+                    cancel: self.cancel.clone(),
+                    bus: self.bus.clone(),
+                    contents: contents.cloned(),
+                    validator: compile_schema(&mcp_tool.name, &mcp_tool.input_schema),
+                    cache: cache.cloned(),
                 };
 
                 tools.push((def, Arc::new(executor) as Arc<dyn Tool + Send + Sync>));
@@ -521,12 +1040,19 @@ impl<'a> AgentExecutor<'a> {
     }
 }
 
+/// Whether the `sandbox_mode` setting is on.
+pub fn sandbox_mode_enabled(db: &Database) -> bool {
+    Settings::new(db).sandbox_mode()
+}
+
 // Private implementation details in a separate impl block
 mod streaming;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agents::builtin::StockpotAgent;
+    use crate::agents::json_agent::{JsonAgent, JsonAgentDef};
     use crate::messaging::MessageBus;
     use tempfile::TempDir;
 
@@ -544,6 +1070,119 @@ mod tests {
         let registry = ModelRegistry::new();
         let executor = AgentExecutor::new(&db, &registry);
         assert!(executor.bus.is_none());
+        assert!(executor.cancel.is_none());
+    }
+
+    /// An agent with its own generation parameters.
+    fn tuned_agent(temperature: Option<f32>, max_tokens: Option<u32>) -> JsonAgent {
+        JsonAgent::new(JsonAgentDef {
+            name: "tuned".to_string(),
+            display_name: None,
+            description: None,
+            system_prompt: "You plan.".to_string(),
+            tools: Vec::new(),
+            model: None,
+            capabilities: None,
+            visibility: None,
+            temperature,
+            top_p: None,
+            max_tokens,
+        })
+    }
+
+    #[test]
+    fn test_sampling_override_reaches_core_settings_without_persisting() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        SpotModelSettings::save_setting(&db, "gpt-4o", "temperature", "0.3").unwrap();
+        let agent = StockpotAgent;
+
+        let executor = AgentExecutor::new(&db, &registry);
+        let sampling = executor.sampling_params(&agent, "gpt-4o");
+        assert_eq!((sampling.temperature, sampling.top_p), (0.3f32 as f64, 1.0));
+
+        let sampling = SamplingOverride::new(Some(1.5), Some(0.5)).unwrap();
+        let executor = AgentExecutor::new(&db, &registry).with_sampling_override(sampling);
+        let sampling = executor.sampling_params(&agent, "gpt-4o");
+        assert_eq!((sampling.temperature, sampling.top_p), (1.5, 0.5));
+
+        let stored = SpotModelSettings::load(&db, "gpt-4o").unwrap();
+        assert_eq!(stored.temperature, Some(0.3));
+        assert_eq!(stored.top_p, None);
+    }
+
+    #[test]
+    fn test_sampling_override_keeps_thinking_temperature() {
+        let (_temp, db) = setup_test_db();
+        let mut registry = ModelRegistry::new();
+        registry.add(crate::models::ModelConfig {
+            name: "thinker".to_string(),
+            supports_thinking: true,
+            ..Default::default()
+        });
+
+        let sampling = SamplingOverride::new(Some(0.2), Some(0.8)).unwrap();
+        let executor = AgentExecutor::new(&db, &registry).with_sampling_override(sampling);
+        let sampling = executor.sampling_params(&tuned_agent(Some(0.4), None), "thinker");
+        assert_eq!((sampling.temperature, sampling.top_p), (1.0, 0.8f32 as f64));
+    }
+
+    #[test]
+    fn test_agent_sampling_precedence() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        SpotModelSettings::save_setting(&db, "gpt-4o", "temperature", "0.3").unwrap();
+        SpotModelSettings::save_setting(&db, "gpt-4o", "max_tokens", "4096").unwrap();
+
+        // Defaults when nothing is set
+        let executor = AgentExecutor::new(&db, &registry);
+        let sampling = executor.sampling_params(&StockpotAgent, "gpt-4o-mini");
+        assert_eq!(sampling.max_tokens, DEFAULT_MAX_TOKENS);
+
+        // Model settings over the defaults
+        let sampling = executor.sampling_params(&StockpotAgent, "gpt-4o");
+        assert_eq!(sampling.max_tokens, 4096);
+
+        // Agent over the model
+        let agent = tuned_agent(Some(0.9), Some(2000));
+        let sampling = executor.sampling_params(&agent, "gpt-4o");
+        assert_eq!(sampling.temperature, 0.9f32 as f64);
+        assert_eq!(sampling.max_tokens, 2000);
+
+        // The run's override over the agent
+        let run = SamplingOverride::new(Some(0.1), None).unwrap();
+        let executor = AgentExecutor::new(&db, &registry).with_sampling_override(run);
+        let sampling = executor.sampling_params(&agent, "gpt-4o");
+        assert_eq!(sampling.temperature, 0.1f32 as f64);
+        assert_eq!(sampling.max_tokens, 2000);
+    }
+
+    #[test]
+    fn test_completion_budget_fits_context_window() {
+        let (_temp, db) = setup_test_db();
+        let mut registry = ModelRegistry::new();
+        registry.add(crate::models::ModelConfig {
+            name: "local-8k".to_string(),
+            context_length: 8192,
+            ..Default::default()
+        });
+        let executor = AgentExecutor::new(&db, &registry);
+
+        assert_eq!(
+            executor.completion_budget("local-8k", DEFAULT_MAX_TOKENS, 2000),
+            6192
+        );
+        // A prompt that fills the window still leaves room to answer
+        assert_eq!(
+            executor.completion_budget("local-8k", DEFAULT_MAX_TOKENS, 8000),
+            MIN_COMPLETION_TOKENS
+        );
+        // Smaller limits are kept, and unknown models aren't capped
+        assert_eq!(executor.completion_budget("local-8k", 500, 8000), 500);
+        assert_eq!(
+            executor.completion_budget("unlisted", DEFAULT_MAX_TOKENS, 2000),
+            DEFAULT_MAX_TOKENS
+        );
     }
 
     #[test]
@@ -881,4 +1520,134 @@ mod tests {
         assert!(registry.is_empty());
         assert!(executor.bus.is_none());
     }
+
+    #[test]
+    fn test_sandbox_filters_mutating_tools() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry).with_sandbox(true);
+        let tools = vec![
+            "read_file",
+            "edit_file",
+            "delete_file",
+            "run_shell_command",
+            "grep",
+            "list_files",
+        ];
+        assert_eq!(
+            executor.filter_tools(tools),
+            vec!["read_file", "grep", "list_files"]
+        );
+        assert!(!executor.wants_invoke_agent(&["invoke_agent"]));
+        assert!(executor.wants_list_agents(&["list_agents"]));
+        assert!(executor.approval_policy().is_some());
+    }
+
+    #[test]
+    fn test_sandbox_mode_setting() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry);
+        assert!(!executor.sandbox_active());
+        assert!(executor.approval_policy().is_none());
+
+        Settings::new(&db).set("sandbox_mode", "true").unwrap();
+        assert!(executor.sandbox_active());
+        assert_eq!(
+            executor.filter_tools(vec!["edit_file", "read_file"]),
+            vec!["read_file"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_tools_registers_nothing() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry).with_no_tools(true);
+        assert!(executor
+            .filter_tools(vec!["read_file", "edit_file", "share_your_reasoning"])
+            .is_empty());
+        assert!(!executor.wants_invoke_agent(&["invoke_agent"]));
+        assert!(!executor.wants_list_agents(&["list_agents"]));
+        let tools = executor
+            .collect_mcp_tools(&McpManager::new(), Some("stockpot"), None, None)
+            .await;
+        assert!(tools.is_empty());
+    }
+
+    #[test]
+    fn test_disabled_tools_are_filtered() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry)
+            .with_disabled_tools(vec!["run_shell_command".into(), "invoke_agent".into()]);
+        assert_eq!(
+            executor.filter_tools(vec!["read_file", "run_shell_command", "grep"]),
+            vec!["read_file", "grep"]
+        );
+        assert!(!executor.wants_invoke_agent(&["invoke_agent"]));
+        assert!(executor.wants_list_agents(&["list_agents"]));
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_has_no_mcp_tools() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry).with_sandbox(true);
+        let tools = executor
+            .collect_mcp_tools(&McpManager::new(), Some("stockpot"), None, None)
+            .await;
+        assert!(tools.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_registry_tools_apply_agent_shell_denylist() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry);
+        let tool_registry = SpotToolRegistry::new();
+        Settings::new(&db)
+            .set_shell_denylist(Some("reviewer"), &["rm".to_string()])
+            .unwrap();
+
+        let ctx = serdes_ai_tools::RunContext::minimal("test");
+        let args = serde_json::json!({ "command": "rm -rf scratch" });
+
+        let tools = executor.registry_tools(
+            &tool_registry,
+            &["read_file", "run_shell_command"],
+            "reviewer",
+        );
+        assert_eq!(tools.len(), 2);
+        let result = tools[1].call(&ctx, args.clone()).await.unwrap();
+        assert!(result.is_error());
+        assert!(result.as_text().unwrap().contains("`rm -rf scratch`"));
+
+        // Other agents keep the unrestricted tool
+        let tools = executor.registry_tools(&tool_registry, &["run_shell_command"], "stockpot");
+        let harmless = serde_json::json!({ "command": "echo rm" });
+        assert!(!tools[0].call(&ctx, harmless).await.unwrap().is_error());
+    }
+
+    #[tokio::test]
+    async fn test_registry_tools_record_file_changes_for_undo() {
+        let (temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let journal = crate::tools::UndoJournal::with_dir(temp.path().join("undo"));
+        let run = journal.begin();
+        let executor = AgentExecutor::new(&db, &registry).with_undo(run.clone());
+        let tool_registry = SpotToolRegistry::new();
+
+        let file = temp.path().join("notes.txt");
+        std::fs::write(&file, "draft").unwrap();
+        let ctx = serdes_ai_tools::RunContext::minimal("test");
+        let tools = executor.registry_tools(&tool_registry, &["delete_file"], "stockpot");
+        let args = serde_json::json!({ "file_path": file.to_str().unwrap() });
+        assert!(!tools[0].call(&ctx, args).await.unwrap().is_error());
+        assert!(!file.exists());
+
+        run.finish("run-1").unwrap();
+        journal.undo_last().unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "draft");
+    }
 }
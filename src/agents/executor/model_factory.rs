@@ -13,8 +13,16 @@ use crate::db::Database;
 use crate::models::settings::ModelSettings as SpotModelSettings;
 use crate::models::{resolve_api_key, ModelRegistry, ModelType};
 
+use super::network::check_network_settings;
 use super::ExecutorError;
 
+/// Key sent to custom endpoints configured without one.
+///
+/// The OpenAI client always sends `Authorization: Bearer <key>`, and an
+/// empty key makes that header malformed, which some servers and proxies
+/// reject. Servers that don't check keys ignore a well-formed one.
+const KEYLESS_API_KEY: &str = "no-key";
+
 /// Get a model by name, handling custom endpoints, OAuth models, and standard models.
 ///
 /// This function checks the model registry first for custom configurations,
@@ -22,10 +30,15 @@ use super::ExecutorError;
 /// API key-based models.
 ///
 /// # Model Resolution Order
-/// 1. Custom endpoint models (from `/add-model`)
-/// 2. OAuth models by config type (ClaudeCode, ChatgptOauth)
-/// 3. OAuth models by prefix (legacy: `chatgpt-*`, `claude-code-*`)
-/// 4. Standard models via `infer_model()` (uses environment API keys)
+/// 1. Azure OpenAI models, by deployment name on their resource endpoint
+/// 2. Custom endpoint models (from `/add-model`)
+/// 3. OAuth models by config type (ClaudeCode, ChatgptOauth)
+/// 4. OAuth models by prefix (legacy: `chatgpt-*`, `claude-code-*`)
+/// 5. Standard models via `infer_model()` (uses environment API keys)
+///
+/// The `proxy_url` and `ca_bundle_path` settings are checked first; they
+/// reach the model's HTTP client through the environment exported at
+/// startup.
 pub async fn get_model(
     db: &Database,
     model_name: &str,
@@ -34,6 +47,8 @@ pub async fn get_model(
 ) -> Result<Arc<dyn Model>, ExecutorError> {
     debug!(model_name = %model_name, ?model_settings, "get_model called");
 
+    check_network_settings(db)?;
+
     // Calculate thinking budget - default to enabled for models that support it
     let thinking_budget = if registry
         .get(model_name)
@@ -71,6 +86,41 @@ pub async fn get_model(
             "Found model in registry"
         );
 
+        // Azure serves deployments, not model IDs, from a per-resource endpoint
+        if matches!(config.model_type, ModelType::AzureOpenai) {
+            let resource_endpoint = config
+                .custom_endpoint
+                .as_ref()
+                .map(|e| e.url.clone())
+                .or_else(|| resolve_api_key(db, "AZURE_OPENAI_ENDPOINT"));
+            let (base_url, deployment) = config
+                .azure_target(resource_endpoint.as_deref())
+                .map_err(|e| ExecutorError::Config(e.to_string()))?;
+            let api_key = match config
+                .custom_endpoint
+                .as_ref()
+                .and_then(|e| e.api_key.as_deref())
+            {
+                Some(key_template) => endpoint_api_key(db, key_template)?,
+                None => resolve_api_key(db, "AZURE_OPENAI_API_KEY").ok_or_else(|| {
+                    ExecutorError::Config(
+                        "API key AZURE_OPENAI_API_KEY not found. Set the environment variable or save the key."
+                            .to_string(),
+                    )
+                })?,
+            };
+
+            let model = OpenAIChatModel::new(&deployment, api_key).with_base_url(&base_url);
+
+            info!(
+                model_name = %model_name,
+                deployment = %deployment,
+                endpoint = %base_url,
+                "Azure OpenAI model ready"
+            );
+            return Ok(Arc::new(model));
+        }
+
         // Handle custom endpoint models (e.g., from /add-model)
         if let Some(endpoint) = &config.custom_endpoint {
             debug!(
@@ -80,29 +130,14 @@ pub async fn get_model(
             );
             debug!("Using custom endpoint for model: {}", model_name);
 
-            // Resolve the API key from database or environment
-            let api_key = if let Some(ref key_template) = endpoint.api_key {
-                if key_template.starts_with('$') {
-                    // It's an env var reference like $API_KEY or ${API_KEY}
-                    let var_name = key_template
-                        .trim_start_matches('$')
-                        .trim_matches(|c| c == '{' || c == '}');
-                    // Check database first, then environment
-                    resolve_api_key(db, var_name).ok_or_else(|| {
-                        ExecutorError::Config(format!(
-                            "API key {} not found. Run /add_model to configure it, or set the environment variable.",
-                            var_name
-                        ))
-                    })?
-                } else {
-                    // It's a literal key
-                    key_template.clone()
+            // Resolve the API key from database or environment. Endpoints
+            // without one (local servers like Ollama) get a placeholder.
+            let api_key = match endpoint.api_key {
+                Some(ref key_template) => endpoint_api_key(db, key_template)?,
+                None => {
+                    debug!("No API key configured, sending a placeholder");
+                    KEYLESS_API_KEY.to_string()
                 }
-            } else {
-                return Err(ExecutorError::Config(format!(
-                    "Model {} has custom endpoint but no API key configured",
-                    model_name
-                )));
             };
 
             // Get the actual model ID to send to the API
@@ -188,6 +223,23 @@ pub async fn get_model(
     Ok(model)
 }
 
+/// Resolve an endpoint's API key: a literal key, or a `$VAR`/`${VAR}`
+/// reference looked up in the database, then the environment.
+pub(crate) fn endpoint_api_key(db: &Database, key_template: &str) -> Result<String, ExecutorError> {
+    if !key_template.starts_with('$') {
+        return Ok(key_template.to_string());
+    }
+    let var_name = key_template
+        .trim_start_matches('$')
+        .trim_matches(|c| c == '{' || c == '}');
+    resolve_api_key(db, var_name).ok_or_else(|| {
+        ExecutorError::Config(format!(
+            "API key {} not found. Run /add_model to configure it, or set the environment variable.",
+            var_name
+        ))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,6 +264,7 @@ mod tests {
             supports_thinking: false,
             supports_vision: false,
             supports_tools: true,
+            supports_streaming: true,
             description: None,
             custom_endpoint: Some(CustomEndpoint {
                 url: url.to_string(),
@@ -300,15 +353,10 @@ mod tests {
     async fn test_custom_endpoint_no_api_key_configured() {
         let (_temp, db) = setup_test_db();
         let mut registry = ModelRegistry::new();
-        let model = create_custom_model("custom-missing", "https://api.example.com/v1", None);
+        let model = create_custom_model("custom-local", "http://localhost:11434/v1", None);
         registry.add(model);
-        let result = get_model(&db, "custom-missing", &registry, None).await;
-        assert!(result.is_err());
-        if let Err(ExecutorError::Config(msg)) = result {
-            assert!(msg.contains("no API key configured"));
-        } else {
-            panic!("Expected ExecutorError::Config for missing API key");
-        }
+        let result = get_model(&db, "custom-local", &registry, None).await;
+        assert!(result.is_ok(), "Keyless endpoints should not need a key");
     }
 
     #[tokio::test]
@@ -496,4 +544,38 @@ mod tests {
         let model = result.unwrap();
         assert!(model.identifier().contains("fallback-name"));
     }
+
+    #[tokio::test]
+    async fn test_azure_model_uses_deployment() {
+        let (_temp, db) = setup_test_db();
+        let mut registry = ModelRegistry::new();
+        let endpoint = CustomEndpoint {
+            url: "https://my-resource.openai.azure.com".to_string(),
+            api_key: Some("azure-key".to_string()),
+            headers: HashMap::new(),
+            ca_certs_path: None,
+        };
+        registry.add(ModelConfig {
+            name: "azure-gpt-4o".to_string(),
+            model_type: ModelType::AzureOpenai,
+            model_id: Some("gpt-4o".to_string()),
+            azure_deployment: Some("prod-gpt4o".to_string()),
+            custom_endpoint: Some(endpoint.clone()),
+            ..Default::default()
+        });
+        registry.add(ModelConfig {
+            name: "azure-undeployed".to_string(),
+            model_type: ModelType::AzureOpenai,
+            custom_endpoint: Some(endpoint),
+            ..Default::default()
+        });
+
+        let model = get_model(&db, "azure-gpt-4o", &registry, None)
+            .await
+            .unwrap();
+        assert!(model.identifier().contains("prod-gpt4o"));
+
+        let result = get_model(&db, "azure-undeployed", &registry, None).await;
+        assert!(matches!(result, Err(ExecutorError::Config(_))));
+    }
 }
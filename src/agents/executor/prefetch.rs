@@ -0,0 +1,277 @@
+//! Concurrent execution of read-only tool calls.
+//!
+//! serdesAI runs the tool calls in a response one after another. When a
+//! response asks for several read-only calls (reads, greps, listings),
+//! [`ToolPrefetch`] starts them all as soon as the response is complete, at
+//! most `limit` at a time, and each tool's executor then picks up the result
+//! instead of running the call itself. The agent still awaits executors in
+//! call order, so results reach the model in the order it asked for them.
+//!
+//! Only the read-only calls before the first other call in a response are
+//! started early; anything after a mutating call waits for it as before.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value as JsonValue;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+use serdes_ai_tools::{ToolError, ToolReturn};
+
+use super::adapters::ToolExecutorAdapter;
+use super::approval::is_read_only;
+use super::StreamEvent;
+
+type CallResult = Result<ToolReturn, ToolError>;
+
+/// Starts a response's read-only tool calls together; see the module docs.
+pub(super) struct ToolPrefetch {
+    model_name: String,
+    permits: Arc<Semaphore>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Read-only tools that may be started early, by name.
+    executors: HashMap<String, Arc<ToolExecutorAdapter>>,
+    /// Calls in the response being streamed: tool name and arguments so far.
+    calls: Vec<(String, String)>,
+    /// Calls started early that no executor has picked up yet.
+    started: HashMap<String, JoinHandle<CallResult>>,
+    /// Calls an executor got to first and is running itself.
+    claimed: HashSet<String>,
+}
+
+impl ToolPrefetch {
+    /// Prefetch for a run on `model_name`, running up to `limit` calls at once.
+    pub fn new(model_name: &str, limit: usize) -> Self {
+        Self {
+            model_name: model_name.to_string(),
+            permits: Arc::new(Semaphore::new(limit.max(1))),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Allow calls to `adapter`'s tool to be started early, if it's read-only.
+    pub fn register(&self, adapter: &ToolExecutorAdapter) {
+        if is_read_only(adapter.name()) {
+            self.state.lock().unwrap().executors.insert(
+                adapter.name().to_string(),
+                // Without a prefetch of its own, or the two would keep each other alive
+                Arc::new(adapter.clone().with_prefetch(None)),
+            );
+        }
+    }
+
+    /// Follow the run's stream, starting calls once their response is complete.
+    pub fn observe(&self, event: &StreamEvent) {
+        let mut state = self.state.lock().unwrap();
+        match event {
+            StreamEvent::RequestStart { .. } => {
+                // The previous response's calls have all been picked up by now
+                state.calls.clear();
+                state.claimed.clear();
+                for (_, handle) in state.started.drain() {
+                    handle.abort();
+                }
+            }
+            StreamEvent::ToolCallStart { tool_name, .. } => {
+                state.calls.push((tool_name.clone(), String::new()));
+            }
+            StreamEvent::ToolCallDelta { delta, .. } => {
+                if let Some((_, args)) = state.calls.last_mut() {
+                    args.push_str(delta);
+                }
+            }
+            StreamEvent::ResponseComplete { .. } => {
+                let calls = std::mem::take(&mut state.calls);
+                self.start(&mut state, calls);
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&self, state: &mut State, calls: Vec<(String, String)>) {
+        for (tool_name, args) in calls {
+            let Some(adapter) = state.executors.get(&tool_name).cloned() else {
+                // Anything after a call that may change things must see its effects
+                break;
+            };
+            let args: JsonValue = if args.trim().is_empty() {
+                JsonValue::Object(Default::default())
+            } else {
+                match serde_json::from_str(&args) {
+                    Ok(args) => args,
+                    Err(_) => break,
+                }
+            };
+
+            let key = call_key(&tool_name, &args);
+            if state.claimed.contains(&key) || state.started.contains_key(&key) {
+                continue;
+            }
+
+            let permits = Arc::clone(&self.permits);
+            let model_name = self.model_name.clone();
+            let handle = tokio::spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                adapter.run(args, &model_name).await
+            });
+            state.started.insert(key, handle);
+        }
+    }
+
+    /// The early-started call matching this one, if there is one. Otherwise
+    /// the call is marked as taken so it isn't started as well.
+    pub fn take(&self, tool_name: &str, args: &JsonValue) -> Option<JoinHandle<CallResult>> {
+        let mut state = self.state.lock().unwrap();
+        if !state.executors.contains_key(tool_name) {
+            return None;
+        }
+
+        let key = call_key(tool_name, args);
+        let handle = state.started.remove(&key);
+        if handle.is_none() {
+            state.claimed.insert(key);
+        }
+        handle
+    }
+}
+
+impl Drop for ToolPrefetch {
+    fn drop(&mut self) {
+        if let Ok(state) = self.state.get_mut() {
+            for handle in state.started.values() {
+                handle.abort();
+            }
+        }
+    }
+}
+
+/// Wait for a call started by [`ToolPrefetch`].
+pub(super) async fn join(handle: JoinHandle<CallResult>) -> CallResult {
+    handle.await.unwrap_or_else(|e| {
+        Err(ToolError::execution_failed(format!(
+            "Tool task failed: {}",
+            e
+        )))
+    })
+}
+
+/// Identifies a call by tool and arguments; the ids serdesAI assigns aren't
+/// always available on both sides.
+fn call_key(tool_name: &str, args: &JsonValue) -> String {
+    format!("{}:{}", tool_name, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serdes_ai_tools::{RunContext, Tool, ToolDefinition, ToolResult};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Read-only stand-in that counts calls and how many overlap.
+    struct SlowRead {
+        calls: Arc<AtomicUsize>,
+        running: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Tool for SlowRead {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition::new("read_file", "test")
+        }
+
+        async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(ToolReturn::text(args["file_path"].as_str().unwrap_or("?")))
+        }
+    }
+
+    fn response(calls: &[(&str, &str)]) -> Vec<StreamEvent> {
+        let mut events = vec![StreamEvent::RequestStart { step: 1 }];
+        for (tool_name, args) in calls {
+            events.push(StreamEvent::ToolCallStart {
+                tool_name: tool_name.to_string(),
+                tool_call_id: None,
+            });
+            events.push(StreamEvent::ToolCallDelta {
+                tool_call_id: None,
+                delta: args.to_string(),
+            });
+        }
+        events.push(StreamEvent::ResponseComplete { step: 1 });
+        events
+    }
+
+    fn setup(limit: usize) -> (ToolPrefetch, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let tool = SlowRead {
+            calls: Arc::clone(&calls),
+            running: Arc::new(AtomicUsize::new(0)),
+            peak: Arc::clone(&peak),
+        };
+        let prefetch = ToolPrefetch::new("test-model", limit);
+        prefetch.register(&ToolExecutorAdapter::new(Arc::new(tool)));
+        (prefetch, calls, peak)
+    }
+
+    #[tokio::test]
+    async fn test_reads_run_together_up_to_limit() {
+        let (prefetch, calls, peak) = setup(2);
+        let files = ["a.rs", "b.rs", "c.rs"];
+        let args: Vec<String> = files
+            .iter()
+            .map(|f| format!(r#"{{"file_path":"{}"}}"#, f))
+            .collect();
+        let batch: Vec<(&str, &str)> = args.iter().map(|a| ("read_file", a.as_str())).collect();
+        for event in response(&batch) {
+            prefetch.observe(&event);
+        }
+
+        // Picked up in call order, each with its own result
+        for file in files {
+            let handle = prefetch
+                .take("read_file", &serde_json::json!({ "file_path": file }))
+                .expect("call was started early");
+            let result = join(handle).await.unwrap();
+            assert_eq!(result.as_text(), Some(file));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stops_at_first_mutating_call() {
+        let (prefetch, calls, _) = setup(4);
+        for event in response(&[
+            ("read_file", r#"{"file_path":"a.rs"}"#),
+            ("edit_file", r#"{"file_path":"a.rs","content":""}"#),
+            ("read_file", r#"{"file_path":"b.rs"}"#),
+        ]) {
+            prefetch.observe(&event);
+        }
+
+        assert!(prefetch
+            .take("read_file", &serde_json::json!({"file_path": "a.rs"}))
+            .is_some());
+        assert!(prefetch
+            .take("read_file", &serde_json::json!({"file_path": "b.rs"}))
+            .is_none());
+        assert!(prefetch
+            .take("edit_file", &serde_json::json!({"file_path": "a.rs"}))
+            .is_none());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,92 @@
+//! Per-run tool call quotas.
+//!
+//! Limits come from `tool_quota.<tool>` settings. A fresh [`ToolQuotas`] is
+//! created for each executor run, so counts reset between runs.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::config::Settings;
+use crate::db::Database;
+
+/// Call limits and usage counts for one agent run.
+#[derive(Debug, Default)]
+pub(super) struct ToolQuotas {
+    limits: HashMap<String, u32>,
+    counts: Mutex<HashMap<String, u32>>,
+}
+
+impl ToolQuotas {
+    /// Create quotas from explicit limits.
+    pub fn new(limits: HashMap<String, u32>) -> Self {
+        Self {
+            limits,
+            counts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load limits configured in settings.
+    pub fn load(db: &Database) -> Self {
+        Self::new(Settings::new(db).get_all_tool_quotas().unwrap_or_default())
+    }
+
+    /// Record a call to `tool_name`.
+    ///
+    /// Returns `Err(limit)` without counting the call if the tool has
+    /// already been called `limit` times this run.
+    pub fn try_acquire(&self, tool_name: &str) -> Result<(), u32> {
+        let Some(&limit) = self.limits.get(tool_name) else {
+            return Ok(());
+        };
+
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts.entry(tool_name.to_string()).or_insert(0);
+        if *count >= limit {
+            return Err(limit);
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_uncapped_tool_always_allowed() {
+        let quotas = ToolQuotas::default();
+        for _ in 0..100 {
+            assert!(quotas.try_acquire("read_file").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_capped_tool_refused_after_limit() {
+        let quotas = ToolQuotas::new(HashMap::from([("run_shell_command".to_string(), 2)]));
+
+        assert!(quotas.try_acquire("run_shell_command").is_ok());
+        assert!(quotas.try_acquire("run_shell_command").is_ok());
+        assert_eq!(quotas.try_acquire("run_shell_command"), Err(2));
+        assert!(quotas.try_acquire("grep").is_ok());
+    }
+
+    #[test]
+    fn test_zero_limit_blocks_tool() {
+        let quotas = ToolQuotas::new(HashMap::from([("delete_file".to_string(), 0)]));
+        assert_eq!(quotas.try_acquire("delete_file"), Err(0));
+    }
+
+    #[test]
+    fn test_load_from_settings() {
+        let temp = TempDir::new().unwrap();
+        let db = Database::open_at(temp.path().join("test.db")).unwrap();
+        db.migrate().unwrap();
+        Settings::new(&db).set_tool_quota("grep", 1).unwrap();
+
+        let quotas = ToolQuotas::load(&db);
+        assert!(quotas.try_acquire("grep").is_ok());
+        assert_eq!(quotas.try_acquire("grep"), Err(1));
+    }
+}
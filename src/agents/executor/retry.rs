@@ -0,0 +1,329 @@
+//! Bounded retries for model requests.
+//!
+//! A failed request is classified from its error text. Rate limits and
+//! transient failures are retried with exponential backoff; everything
+//! else fails straight away. One [`RetryPolicy`] caps the whole request:
+//! the number of attempts and the total time spent, waits included. Once
+//! either runs out, the last error is returned as [`GaveUp`].
+
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use super::types::ExecutorError;
+
+/// What kind of failure a model request hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// HTTP 429 or a provider rate-limit message
+    RateLimited,
+    /// 5xx responses, timeouts and dropped connections
+    Transient,
+    /// Bad or expired credentials; retrying won't help
+    Auth,
+    /// Anything else, e.g. a malformed request
+    Fatal,
+}
+
+impl ErrorClass {
+    /// Classify an error from its message.
+    pub fn classify(error: &str) -> Self {
+        let error = error.to_lowercase();
+        if error.contains("status: 429") || error.contains("rate limit") {
+            Self::RateLimited
+        } else if error.contains("status: 401") || error.contains("status: 403") {
+            Self::Auth
+        } else if error.contains("status: 5")
+            || error.contains("overloaded")
+            || error.contains("timed out")
+            || error.contains("timeout")
+            || error.contains("connection")
+        {
+            Self::Transient
+        } else {
+            Self::Fatal
+        }
+    }
+
+    /// Whether another attempt could succeed.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::RateLimited | Self::Transient)
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::RateLimited => "rate limited",
+            Self::Transient => "transient",
+            Self::Auth => "authentication",
+            Self::Fatal => "fatal",
+        })
+    }
+}
+
+/// Limits on retrying a single model request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first
+    pub max_attempts: u32,
+    /// Time allowed for all attempts and the waits between them
+    pub deadline: Duration,
+    /// Wait before the first retry; doubles after each one
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            deadline: Duration::from_secs(120),
+            initial_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Wait before retry `retry` (0-based) after a failure of `class`.
+    ///
+    /// Rate limits back off twice as long as other failures.
+    pub fn backoff(&self, retry: u32, class: ErrorClass) -> Duration {
+        let delay = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry));
+        match class {
+            ErrorClass::RateLimited => delay.saturating_mul(2),
+            _ => delay,
+        }
+    }
+}
+
+/// A request that failed for good.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GaveUp {
+    /// Attempts made, including the first
+    pub attempts: u32,
+    /// Classification of the last error
+    pub class: ErrorClass,
+    pub last_error: String,
+}
+
+impl fmt::Display for GaveUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.attempts > 1 {
+            write!(
+                f,
+                "Gave up after {} attempts ({}): {}",
+                self.attempts, self.class, self.last_error
+            )
+        } else {
+            f.write_str(&self.last_error)
+        }
+    }
+}
+
+impl std::error::Error for GaveUp {}
+
+impl From<GaveUp> for ExecutorError {
+    fn from(e: GaveUp) -> Self {
+        match e.class {
+            ErrorClass::Auth => Self::Auth(e.to_string()),
+            _ => Self::Model(e.to_string()),
+        }
+    }
+}
+
+/// Run `attempt` until it succeeds or `policy` runs out.
+///
+/// `attempt` gets the 0-based attempt number. A failure that isn't worth
+/// retrying, or whose backoff would overrun the deadline, ends the loop
+/// early.
+pub(super) async fn with_retries<T, F, Fut>(
+    policy: &RetryPolicy,
+    mut attempt: F,
+) -> Result<T, GaveUp>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let started = Instant::now();
+    let mut attempts = 0;
+
+    loop {
+        let error = match attempt(attempts).await {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        attempts += 1;
+
+        let class = ErrorClass::classify(&error);
+        let gave_up = GaveUp {
+            attempts,
+            class,
+            last_error: error,
+        };
+        if !class.is_retryable() || attempts >= policy.max_attempts {
+            return Err(gave_up);
+        }
+
+        let delay = policy.backoff(attempts - 1, class);
+        if started.elapsed() + delay > policy.deadline {
+            return Err(gave_up);
+        }
+
+        warn!(
+            attempt = attempts,
+            class = %class,
+            delay_ms = delay.as_millis() as u64,
+            error = %gave_up.last_error,
+            "Model request failed, retrying"
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            deadline: Duration::from_secs(10),
+            initial_backoff: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            ErrorClass::classify("HTTP error status: 429, body: slow down"),
+            ErrorClass::RateLimited
+        );
+        assert_eq!(
+            ErrorClass::classify("Rate limit reached for requests"),
+            ErrorClass::RateLimited
+        );
+        assert_eq!(
+            ErrorClass::classify("status: 503 Service Unavailable"),
+            ErrorClass::Transient
+        );
+        assert_eq!(
+            ErrorClass::classify("request timed out"),
+            ErrorClass::Transient
+        );
+        assert_eq!(ErrorClass::classify("status: 401"), ErrorClass::Auth);
+        assert_eq!(
+            ErrorClass::classify("status: 400, invalid model"),
+            ErrorClass::Fatal
+        );
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_rate_limits_wait_longer() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.backoff(0, ErrorClass::Transient),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            policy.backoff(2, ErrorClass::Transient),
+            Duration::from_millis(400)
+        );
+        assert_eq!(
+            policy.backoff(2, ErrorClass::RateLimited),
+            Duration::from_millis(800)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_budget_caps_attempts_across_mixed_failures() {
+        let calls = AtomicU32::new(0);
+        let errors = [
+            "status: 429",
+            "status: 503",
+            "connection reset",
+            "status: 502",
+        ];
+
+        let result: Result<(), GaveUp> = with_retries(&fast_policy(3), |n| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            let error = errors[n as usize].to_string();
+            async move { Err(error) }
+        })
+        .await;
+
+        let gave_up = result.unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(gave_up.attempts, 3);
+        assert_eq!(gave_up.class, ErrorClass::Transient);
+        assert_eq!(gave_up.last_error, "connection reset");
+        assert!(gave_up
+            .to_string()
+            .starts_with("Gave up after 3 attempts (transient)"));
+    }
+
+    #[tokio::test]
+    async fn test_recovers_within_budget() {
+        let result = with_retries(&fast_policy(3), |n| async move {
+            match n {
+                0 => Err("status: 429".to_string()),
+                1 => Err("status: 500".to_string()),
+                _ => Ok(n),
+            }
+        })
+        .await;
+        assert_eq!(result, Ok(2));
+    }
+
+    #[tokio::test]
+    async fn test_fatal_errors_are_not_retried() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), GaveUp> = with_retries(&fast_policy(5), |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err("status: 401 Unauthorized".to_string()) }
+        })
+        .await;
+
+        let gave_up = result.unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(gave_up.class, ErrorClass::Auth);
+        assert!(matches!(
+            ExecutorError::from(gave_up),
+            ExecutorError::Auth(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_stops_retries_early() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            deadline: Duration::from_millis(50),
+            initial_backoff: Duration::from_millis(40),
+        };
+        let calls = AtomicU32::new(0);
+        let result: Result<(), GaveUp> = with_retries(&policy, |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err("status: 503".to_string()) }
+        })
+        .await;
+
+        // 40ms wait fits, the next 80ms one would overrun the deadline
+        assert_eq!(result.unwrap_err().attempts, 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
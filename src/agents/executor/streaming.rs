@@ -5,9 +5,11 @@
 
 use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
 
 use futures::StreamExt;
 use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
 use tracing::{debug, error, info, warn};
 
 use serdes_ai_agent::{agent, RunOptions};
@@ -19,14 +21,24 @@ use serdes_ai_core::{
 };
 use serdes_ai_tools::{Tool, ToolDefinition};
 
-use crate::messaging::EventBridge;
+use crate::config::Settings;
+use crate::messaging::{EventBridge, ToolContentStore};
 use crate::models::settings::ModelSettings as SpotModelSettings;
+use crate::tokens::{estimate_content_tokens, estimate_message_tokens, estimate_tokens};
 
 use super::adapters::{ArcModel, RecordingToolExecutor, ToolExecutorAdapter};
+use super::cache::ToolCache;
+use super::context_files::{load_agent_context, prepend_context};
 use super::model_factory::get_model;
-use super::sub_agents::{InvokeAgentExecutor, ListAgentsExecutor};
-use super::types::{ExecuteContext, ExecutorError, ExecutorStreamReceiver};
-use super::{AgentExecutor, SpotAgent, StreamEvent};
+use super::prefetch::ToolPrefetch;
+use super::quotas::ToolQuotas;
+use super::retry::with_retries;
+use super::sub_agents::{parent_history, InvokeAgentExecutor, ListAgentsExecutor};
+use super::timeouts::{timed_out, ResponseTimer};
+use super::types::{
+    CancelToken, ExecuteContext, ExecutorError, ExecutorResult, ExecutorStreamReceiver,
+};
+use super::{estimate_prompt_tokens, AgentExecutor, SpotAgent, StreamEvent};
 
 /// Helper struct to track in-progress tool calls during streaming.
 struct RawToolCall {
@@ -35,9 +47,314 @@ struct RawToolCall {
     args_buffer: String,
 }
 
+/// Assembles the model response for the current request from stream events.
+///
+/// A response may carry text, tool calls, or both; a tool-call-only turn
+/// simply produces no `TextPart`.
+#[derive(Default)]
+struct ResponseAssembly {
+    text: String,
+    completed_tool_calls: Vec<RawToolCall>,
+    in_progress_tool_call: Option<RawToolCall>,
+}
+
+impl ResponseAssembly {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn push_text(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    fn start_tool_call(&mut self, tool_name: &str, tool_call_id: Option<String>) {
+        if let Some(tc) = self.in_progress_tool_call.take() {
+            self.completed_tool_calls.push(tc);
+        }
+        self.in_progress_tool_call = Some(RawToolCall {
+            tool_name: tool_name.to_string(),
+            tool_call_id,
+            args_buffer: String::new(),
+        });
+    }
+
+    fn push_tool_args(&mut self, delta: &str) {
+        if let Some(tc) = self.in_progress_tool_call.as_mut() {
+            tc.args_buffer.push_str(delta);
+        }
+    }
+
+    /// Whether anything has been assembled since the last `finish`.
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+            && self.completed_tool_calls.is_empty()
+            && self.in_progress_tool_call.is_none()
+    }
+
+    /// Finish the response, returning it as a history entry (if non-empty)
+    /// along with the tool calls awaiting returns, in call order.
+    fn finish(
+        &mut self,
+        model_name: &str,
+    ) -> (Option<ModelRequest>, VecDeque<(String, Option<String>)>) {
+        if let Some(tc) = self.in_progress_tool_call.take() {
+            self.completed_tool_calls.push(tc);
+        }
+
+        let pending_tool_calls = self
+            .completed_tool_calls
+            .iter()
+            .map(|tc| (tc.tool_name.clone(), tc.tool_call_id.clone()))
+            .collect();
+
+        let mut response_parts: Vec<ModelResponsePart> = Vec::new();
+
+        if !self.text.is_empty() {
+            response_parts.push(ModelResponsePart::Text(TextPart::new(std::mem::take(
+                &mut self.text,
+            ))));
+        }
+
+        for tc in self.completed_tool_calls.drain(..) {
+            let mut part = ToolCallPart::new(tc.tool_name, ToolCallArgs::from(tc.args_buffer));
+            if let Some(id) = tc.tool_call_id {
+                part = part.with_tool_call_id(id);
+            }
+            response_parts.push(ModelResponsePart::ToolCall(part));
+        }
+
+        let request = (!response_parts.is_empty()).then(|| {
+            let response =
+                ModelResponse::with_parts(response_parts).with_model_name(model_name.to_string());
+            let mut response_req = ModelRequest::new();
+            response_req
+                .parts
+                .push(ModelRequestPart::ModelResponse(Box::new(response)));
+            response_req
+        });
+
+        (request, pending_tool_calls)
+    }
+
+    /// Finish a response cut off before `ResponseComplete`, keeping only its
+    /// text: its tool calls may have partial args and never ran.
+    fn finish_text(&mut self, model_name: &str) -> Option<ModelRequest> {
+        self.completed_tool_calls.clear();
+        self.in_progress_tool_call = None;
+        self.finish(model_name).0
+    }
+}
+
+/// Drop the last `count` tool calls of the last response in `messages`,
+/// calls that a stopped run never got returns for. Returns are matched to
+/// calls in order, so the unanswered ones are at the end.
+fn drop_unreturned_calls(messages: &mut Vec<ModelRequest>, count: usize) {
+    let last_response = messages.iter().rposition(|request| {
+        request
+            .parts
+            .iter()
+            .any(|part| matches!(part, ModelRequestPart::ModelResponse(_)))
+    });
+    let Some(at) = last_response else {
+        return;
+    };
+
+    for part in &mut messages[at].parts {
+        if let ModelRequestPart::ModelResponse(response) = part {
+            let calls = response
+                .parts
+                .iter()
+                .filter(|part| matches!(part, ModelResponsePart::ToolCall(_)))
+                .count();
+            let mut keep = calls.saturating_sub(count);
+            response.parts.retain(|part| match part {
+                ModelResponsePart::ToolCall(_) if keep == 0 => false,
+                ModelResponsePart::ToolCall(_) => {
+                    keep -= 1;
+                    true
+                }
+                _ => true,
+            });
+        }
+    }
+    messages[at].parts.retain(|part| match part {
+        ModelRequestPart::ModelResponse(response) => !response.parts.is_empty(),
+        _ => true,
+    });
+    if messages[at].parts.is_empty() {
+        messages.remove(at);
+    }
+}
+
+/// Finish a bus-driven run: require a run id and announce completion.
+///
+/// The output may be empty when the model's last turn only called tools.
+pub(super) fn complete_run(
+    bridge: &EventBridge,
+    output: String,
+    run_id: Option<String>,
+    messages: Vec<ModelRequest>,
+) -> Result<ExecutorResult, ExecutorError> {
+    let run_id = run_id
+        .ok_or_else(|| ExecutorError::Execution("Stream ended without RunComplete event".into()))?;
+
+    if output.is_empty() {
+        debug!(run_id = %run_id, "Run completed without text output");
+    }
+
+    bridge.agent_completed(&run_id);
+
+    Ok(ExecutorResult {
+        output,
+        messages,
+        run_id,
+    })
+}
+
+/// Stream events matching the responses and tool returns in `messages`, so
+/// a run that wasn't streamed can be published as if it had been.
+///
+/// Tool returns don't say whether the call failed, so every replayed call
+/// is reported as successful.
+fn replay_events(messages: &[ModelRequest]) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    let mut step = 0;
+
+    for request in messages {
+        for part in &request.parts {
+            match part {
+                ModelRequestPart::ModelResponse(response) => {
+                    step += 1;
+                    events.push(StreamEvent::RequestStart { step });
+                    for part in &response.parts {
+                        let value = serde_json::to_value(part).unwrap_or_default();
+                        match part {
+                            ModelResponsePart::Text(_) => {
+                                let text = value
+                                    .get("content")
+                                    .or_else(|| value.get("text"))
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or_default();
+                                if !text.is_empty() {
+                                    events.push(StreamEvent::TextDelta {
+                                        text: text.to_string(),
+                                    });
+                                }
+                            }
+                            ModelResponsePart::ToolCall(_) => {
+                                let tool_name = value
+                                    .get("tool_name")
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or_default()
+                                    .to_string();
+                                let tool_call_id = value
+                                    .get("tool_call_id")
+                                    .and_then(|v| v.as_str())
+                                    .map(str::to_string);
+                                let args = match value.get("args") {
+                                    Some(serde_json::Value::String(args)) => args.clone(),
+                                    Some(args) => args.to_string(),
+                                    None => String::new(),
+                                };
+                                events.push(StreamEvent::ToolCallStart {
+                                    tool_name: tool_name.clone(),
+                                    tool_call_id: tool_call_id.clone(),
+                                });
+                                events.push(StreamEvent::ToolCallDelta {
+                                    delta: args,
+                                    tool_call_id: tool_call_id.clone(),
+                                });
+                                events.push(StreamEvent::ToolCallComplete {
+                                    tool_name,
+                                    tool_call_id,
+                                });
+                            }
+                            _ => {}
+                        }
+                    }
+                    events.push(StreamEvent::ResponseComplete { step });
+                }
+                ModelRequestPart::ToolReturn(tool_return) => {
+                    events.push(StreamEvent::ToolExecuted {
+                        tool_name: tool_return.tool_name.clone(),
+                        tool_call_id: tool_return.tool_call_id.clone(),
+                        success: true,
+                        error: None,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    events
+}
+
 impl<'a> AgentExecutor<'a> {
+    /// Whether runs on `model_name` can stream. Models not in the registry
+    /// are assumed to.
+    pub(super) fn supports_streaming(&self, model_name: &str) -> bool {
+        self.registry
+            .get(model_name)
+            .is_none_or(|config| config.supports_streaming)
+    }
+
+    /// Run a bus-driven execution without streaming, for models whose
+    /// endpoint can't stream, then publish the finished run's responses and
+    /// tool calls through `bridge`.
+    pub(super) async fn execute_unstreamed(
+        &self,
+        bridge: &mut EventBridge,
+        spot_agent: &dyn SpotAgent,
+        model_name: &str,
+        prompt: UserContent,
+        message_history: Option<Vec<ModelRequest>>,
+        context: &ExecuteContext<'_>,
+    ) -> Result<ExecutorResult, ExecutorError> {
+        info!(model = %model_name, "Model doesn't stream, running without streaming");
+        let history_len = message_history.as_ref().map_or(0, Vec::len);
+
+        let result = match self
+            .execute_content(spot_agent, model_name, prompt, message_history, context)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                bridge.agent_error(&e.to_string());
+                return Err(e);
+            }
+        };
+
+        let new_messages = result.messages.get(history_len..).unwrap_or_default();
+        for event in replay_events(new_messages) {
+            bridge.process(event);
+        }
+        bridge.agent_completed(&result.run_id);
+
+        Ok(result)
+    }
+
+    /// The next event of `stream`, or `None` once it ends or the run is
+    /// stopped with its cancellation token.
+    async fn next_event(
+        &self,
+        stream: &mut ExecutorStreamReceiver,
+    ) -> Option<Result<StreamEvent, ExecutorError>> {
+        match &self.cancel {
+            Some(cancel) => tokio::select! {
+                biased;
+                _ = cancel.cancelled() => None,
+                event = stream.recv() => event,
+            },
+            None => stream.recv().await,
+        }
+    }
+
     /// Process a stream of events and accumulate results.
     ///
+    /// A run stopped with its cancellation token ends here with what it got
+    /// through, as if the stream had completed.
+    ///
     /// Returns (accumulated_text, final_run_id, messages).
     pub(super) async fn process_stream(
         &self,
@@ -52,9 +369,7 @@ impl<'a> AgentExecutor<'a> {
         let mut final_run_id: Option<String> = None;
 
         // Track per-response state so we can rebuild `ModelResponse` parts.
-        let mut current_response_text = String::new();
-        let mut completed_tool_calls: Vec<RawToolCall> = Vec::new();
-        let mut in_progress_tool_call: Option<RawToolCall> = None;
+        let mut response = ResponseAssembly::default();
 
         // Track tool return parts emitted by tool executors.
         let mut expected_tool_returns: usize = 0;
@@ -62,8 +377,10 @@ impl<'a> AgentExecutor<'a> {
         let mut pending_tool_returns: Vec<ToolReturnPart> = Vec::new();
         let mut pending_tool_calls: VecDeque<(String, Option<String>)> = VecDeque::new();
 
-        // Process all events through the bridge
-        while let Some(event_result) = stream.recv().await {
+        // Process all events through the bridge, until the stream ends or the
+        // run is stopped
+        let mut started_run_id: Option<String> = None;
+        while let Some(event_result) = self.next_event(stream).await {
             match event_result {
                 Ok(event) => {
                     let tool_executed_info = match &event {
@@ -78,75 +395,45 @@ impl<'a> AgentExecutor<'a> {
 
                     match &event {
                         StreamEvent::RequestStart { .. } => {
-                            current_response_text.clear();
-                            completed_tool_calls.clear();
-                            in_progress_tool_call = None;
+                            response.reset();
+
+                            // Each turn resends the history, so charge it before going on
+                            if let Some(tracker) = &self.budget {
+                                tracker.record_tokens(estimate_tokens(&messages));
+                                if let Err(e) = tracker.check() {
+                                    bridge.agent_error(&e.to_string());
+                                    return Err(e.into());
+                                }
+                            }
                         }
                         StreamEvent::TextDelta { text } => {
                             accumulated_text.push_str(text);
-                            current_response_text.push_str(text);
+                            response.push_text(text);
                         }
                         StreamEvent::ToolCallStart {
                             tool_name,
                             tool_call_id,
                         } => {
-                            if let Some(tc) = in_progress_tool_call.take() {
-                                completed_tool_calls.push(tc);
-                            }
-                            in_progress_tool_call = Some(RawToolCall {
-                                tool_name: tool_name.clone(),
-                                tool_call_id: tool_call_id.clone(),
-                                args_buffer: String::new(),
-                            });
+                            response.start_tool_call(tool_name, tool_call_id.clone());
                         }
                         StreamEvent::ToolCallDelta { delta, .. } => {
-                            if let Some(tc) = in_progress_tool_call.as_mut() {
-                                tc.args_buffer.push_str(delta);
-                            }
+                            response.push_tool_args(delta);
                         }
                         StreamEvent::ResponseComplete { .. } => {
-                            if let Some(tc) = in_progress_tool_call.take() {
-                                completed_tool_calls.push(tc);
-                            }
-
-                            pending_tool_calls = completed_tool_calls
-                                .iter()
-                                .map(|tc| (tc.tool_name.clone(), tc.tool_call_id.clone()))
-                                .collect();
-
-                            let mut response_parts: Vec<ModelResponsePart> = Vec::new();
-
-                            if !current_response_text.is_empty() {
-                                response_parts.push(ModelResponsePart::Text(TextPart::new(
-                                    current_response_text.clone(),
-                                )));
-                            }
-
-                            for tc in completed_tool_calls.drain(..) {
-                                let mut part = ToolCallPart::new(
-                                    tc.tool_name,
-                                    ToolCallArgs::from(tc.args_buffer),
-                                );
-                                if let Some(id) = tc.tool_call_id {
-                                    part = part.with_tool_call_id(id);
+                            let (response_req, calls) = response.finish(model_name);
+                            if let Some(response_req) = response_req {
+                                if let Some(tracker) = &self.budget {
+                                    tracker.record_tokens(estimate_message_tokens(&response_req));
                                 }
-                                response_parts.push(ModelResponsePart::ToolCall(part));
-                            }
-
-                            if !response_parts.is_empty() {
-                                let response = ModelResponse::with_parts(response_parts)
-                                    .with_model_name(model_name.to_string());
-
-                                let mut response_req = ModelRequest::new();
-                                response_req
-                                    .parts
-                                    .push(ModelRequestPart::ModelResponse(Box::new(response)));
                                 messages.push(response_req);
                             }
 
+                            pending_tool_calls = calls;
                             expected_tool_returns = pending_tool_calls.len();
                             pending_tool_returns.clear();
-                            current_response_text.clear();
+                        }
+                        StreamEvent::RunStart { run_id } => {
+                            started_run_id = Some(run_id.clone());
                         }
                         StreamEvent::RunComplete { run_id } => {
                             final_run_id = Some(run_id.clone());
@@ -211,6 +498,26 @@ impl<'a> AgentExecutor<'a> {
             }
         }
 
+        // Final throughput reading; the bridge only reports periodically while streaming
+        bridge.report_metrics();
+
+        // A stopped run completes with what it got through
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            final_run_id = final_run_id.or(started_run_id);
+        }
+
+        // A stopped run leaves calls without returns, which the next request
+        // can't carry
+        if !pending_tool_calls.is_empty() {
+            drop_unreturned_calls(&mut messages, pending_tool_calls.len());
+        }
+
+        // The text of a response cut off before ResponseComplete still
+        // belongs in history
+        if !response.is_empty() {
+            messages.extend(response.finish_text(model_name));
+        }
+
         // Flush any tool returns we managed to capture
         if !pending_tool_returns.is_empty() {
             let mut tool_req = ModelRequest::new();
@@ -232,7 +539,10 @@ impl<'a> AgentExecutor<'a> {
         message_history: Option<Vec<ModelRequest>>,
         context: &ExecuteContext<'_>,
         tool_return_recorder: Option<Arc<Mutex<Vec<ToolReturnPart>>>>,
+        tool_contents: Option<ToolContentStore>,
     ) -> Result<ExecutorStreamReceiver, ExecutorError> {
+        self.check_budget()?;
+
         // Load model settings for thinking configuration
         let spot_settings = SpotModelSettings::load(self.db, model_name).ok();
 
@@ -244,50 +554,65 @@ impl<'a> AgentExecutor<'a> {
         let wants_invoke = self.wants_invoke_agent(&original_tools);
         let wants_list = self.wants_list_agents(&original_tools);
 
+        // What invoke_agent can share with a sub-agent, before context files
+        let parent = wants_invoke
+            .then(|| parent_history(message_history.as_deref(), &prompt))
+            .unwrap_or_default();
+
+        // Prepend the agent's context files, read fresh for this run
+        let prompt = match load_agent_context(self.db, spot_agent.name()) {
+            Some(context) => prepend_context(prompt, &context),
+            None => prompt,
+        };
+
         // Get the tools this agent should have access to (filtered by settings)
         let tool_names = self.filter_tools(original_tools);
-        let tools = context.tool_registry.tools_by_name(&tool_names);
+        let tools = self.registry_tools(context.tool_registry, &tool_names, spot_agent.name());
 
         // Collect tool definitions and Arc references
         let mut tool_data: Vec<(ToolDefinition, Arc<dyn Tool + Send + Sync>)> =
             tools.into_iter().map(|t| (t.definition(), t)).collect();
 
         // Collect MCP tools from running servers (filtered by agent attachments)
+        let cache = Arc::new(ToolCache::default());
         let mcp_tool_calls = self
-            .collect_mcp_tools(context.mcp_manager, Some(spot_agent.name()))
+            .collect_mcp_tools(
+                context.mcp_manager,
+                Some(spot_agent.name()),
+                tool_contents.as_ref(),
+                Some(&cache),
+            )
             .await;
         tool_data.extend(mcp_tool_calls);
 
-        // Load per-model settings from database
-        let spot_settings = SpotModelSettings::load(self.db, model_name).unwrap_or_default();
-
-        // Check if this model has thinking enabled (supports it and not explicitly disabled)
-        let model_supports_thinking = self
-            .registry
-            .get(model_name)
-            .map(|c| c.supports_thinking)
-            .unwrap_or(false);
-        let thinking_explicitly_disabled = spot_settings.extended_thinking == Some(false);
-        let thinking_enabled = model_supports_thinking && !thinking_explicitly_disabled;
-
-        // Convert to serdes_ai_core::ModelSettings
-        // When thinking is enabled, temperature MUST be 1.0 per Claude API requirements
-        let effective_temp = if thinking_enabled {
-            1.0
-        } else {
-            spot_settings.effective_temperature() as f64
-        };
-
-        let core_settings = serdes_ai_core::ModelSettings::new()
-            .temperature(effective_temp)
-            .top_p(spot_settings.effective_top_p() as f64)
-            .max_tokens(30000);
+        // Size the completion to what the model's context window has left
+        let system_prompt = self.system_prompt(spot_agent);
+        let sampling = self.sampling_params(spot_agent, model_name);
+        let prompt_tokens = estimate_prompt_tokens(
+            &system_prompt,
+            message_history.as_deref(),
+            estimate_content_tokens(&prompt),
+            tool_data.len(),
+        );
+        let max_tokens = self.completion_budget(model_name, sampling.max_tokens, prompt_tokens);
 
         // Prepare data for the spawned task
-        let system_prompt = spot_agent.system_prompt();
         let model_name_owned = model_name.to_string();
         let db_path = self.db.path().to_path_buf();
         let bus = self.bus.clone();
+        let quotas = Arc::new(ToolQuotas::load(self.db));
+        let approval = self.approval_policy();
+        let undo = self.undo.clone();
+        let budget = self.budget.clone();
+        let retry = self.retry;
+        let request_timeout = spot_settings.as_ref().and_then(|s| s.request_timeout());
+        let settings = Settings::new(self.db);
+        let idle_timeout = match settings.stream_idle_timeout_secs() {
+            0 => None,
+            secs => Some(Duration::from_secs(secs.into())),
+        };
+        let parallel_tools = settings.parallel_tools() as usize;
+        let chain = self.chain_through(spot_agent.name());
         let tool_return_recorder = tool_return_recorder.clone();
         let (tx, rx) = mpsc::channel(32);
 
@@ -321,7 +646,22 @@ impl<'a> AgentExecutor<'a> {
             let mut builder = agent(wrapped_model)
                 .system_prompt(system_prompt)
                 .temperature(1.0)
-                .max_tokens(30000);
+                .max_tokens(max_tokens);
+
+            // Read-only calls in the same response run together when allowed
+            let prefetch = (parallel_tools > 1)
+                .then(|| Arc::new(ToolPrefetch::new(&model_name_owned, parallel_tools)));
+            let adapter = |tool: Arc<dyn Tool + Send + Sync>| {
+                let adapter = ToolExecutorAdapter::new(tool)
+                    .with_quotas(quotas.clone())
+                    .with_approval(approval.clone())
+                    .with_budget(budget.clone())
+                    .with_cache(cache.clone());
+                if let Some(prefetch) = &prefetch {
+                    prefetch.register(&adapter);
+                }
+                adapter.with_prefetch(prefetch.clone())
+            };
 
             match tool_return_recorder {
                 Some(recorder) => {
@@ -330,10 +670,7 @@ impl<'a> AgentExecutor<'a> {
                         debug!(tool_name = %def.name, "Registering tool");
                         builder = builder.tool_with_executor(
                             def,
-                            RecordingToolExecutor::new(
-                                ToolExecutorAdapter::new(tool),
-                                recorder.clone(),
-                            ),
+                            RecordingToolExecutor::new(adapter(tool), recorder.clone()),
                         );
                     }
 
@@ -343,7 +680,12 @@ impl<'a> AgentExecutor<'a> {
                             db_path.clone(),
                             &model_name_owned,
                             bus.clone(),
-                        );
+                        )
+                        .with_cache(cache.clone())
+                        .with_parent_history(parent.clone())
+                        .with_chain(chain.clone())
+                        .with_approval(approval.clone())
+                        .with_undo(undo.clone());
                         builder = builder.tool_with_executor(
                             InvokeAgentExecutor::definition(),
                             RecordingToolExecutor::new(invoke_executor, recorder.clone()),
@@ -355,7 +697,8 @@ impl<'a> AgentExecutor<'a> {
                         builder = builder.tool_with_executor(
                             ListAgentsExecutor::definition(),
                             RecordingToolExecutor::new(
-                                ListAgentsExecutor::new_with_path(db_path.clone()),
+                                ListAgentsExecutor::new_with_path(db_path.clone())
+                                    .with_contents(tool_contents.clone()),
                                 recorder.clone(),
                             ),
                         );
@@ -365,7 +708,7 @@ impl<'a> AgentExecutor<'a> {
                     // Register tools with real executors
                     for (def, tool) in tool_data {
                         debug!(tool_name = %def.name, "Registering tool");
-                        builder = builder.tool_with_executor(def, ToolExecutorAdapter::new(tool));
+                        builder = builder.tool_with_executor(def, adapter(tool));
                     }
 
                     // Add invoke_agent with custom executor (has database access)
@@ -374,7 +717,11 @@ impl<'a> AgentExecutor<'a> {
                             db_path.clone(),
                             &model_name_owned,
                             bus.clone(),
-                        );
+                        )
+                        .with_cache(cache.clone())
+                        .with_parent_history(parent.clone())
+                        .with_chain(chain.clone())
+                        .with_approval(approval.clone());
                         builder = builder
                             .tool_with_executor(InvokeAgentExecutor::definition(), invoke_executor);
                     }
@@ -383,7 +730,8 @@ impl<'a> AgentExecutor<'a> {
                     if wants_list {
                         builder = builder.tool_with_executor(
                             ListAgentsExecutor::definition(),
-                            ListAgentsExecutor::new_with_path(db_path.clone()),
+                            ListAgentsExecutor::new_with_path(db_path.clone())
+                                .with_contents(tool_contents.clone()),
                         );
                     }
                 }
@@ -396,30 +744,78 @@ impl<'a> AgentExecutor<'a> {
             let history_len = message_history.as_ref().map(|h| h.len()).unwrap_or(0);
             debug!(history_messages = history_len, "Setting up run options");
 
-            let options = match message_history {
-                Some(history) => RunOptions::new()
-                    .model_settings(core_settings)
-                    .message_history(history),
-                None => RunOptions::new().model_settings(core_settings),
+            // Nothing has been streamed before the stream starts, so a failed
+            // start can be retried within the request's retry budget
+            let agent_ref = &serdes_agent;
+            let start_stream = |attempt: u32| {
+                let core_settings = serdes_ai_core::ModelSettings::new()
+                    .temperature(sampling.temperature)
+                    .top_p(sampling.top_p)
+                    .max_tokens(max_tokens);
+                let options = match message_history.clone() {
+                    Some(history) => RunOptions::new()
+                        .model_settings(core_settings)
+                        .message_history(history),
+                    None => RunOptions::new().model_settings(core_settings),
+                };
+                let prompt = prompt.clone();
+
+                async move {
+                    // Use real streaming from serdesAI
+                    debug!(attempt, "Calling run_stream_with_options");
+                    let start = agent_ref.run_stream_with_options(prompt, (), options);
+                    let started = match request_timeout {
+                        Some(limit) => tokio::time::timeout(limit, start)
+                            .await
+                            .map_err(|_| timed_out(limit))?,
+                        None => start.await,
+                    };
+                    started.map_err(|e| {
+                        let error_str = e.to_string();
+                        log_http_error(&error_str);
+                        error_str
+                    })
+                }
             };
 
-            // Use real streaming from serdesAI
-            debug!("Calling run_stream_with_options");
-
-            match serdes_agent
-                .run_stream_with_options(prompt, (), options)
-                .await
-            {
+            match with_retries(&retry, start_stream).await {
                 Ok(mut stream) => {
                     debug!("Stream started, forwarding events");
                     let mut event_count = 0u32;
+                    let mut timer = ResponseTimer::new(request_timeout, idle_timeout);
 
                     // Forward all events from the stream
-                    while let Some(event_result) = stream.next().await {
+                    loop {
+                        let next = match timer.deadline(Instant::now()) {
+                            Some((deadline, timeout_error)) => {
+                                match tokio::time::timeout_at(deadline, stream.next()).await {
+                                    Ok(next) => next,
+                                    Err(_) => {
+                                        error!(error = %timeout_error, "Stream error");
+                                        let _ = tx
+                                            .send(Ok(StreamEvent::Error {
+                                                message: timeout_error.clone(),
+                                            }))
+                                            .await;
+                                        let _ =
+                                            tx.send(Err(ExecutorError::Model(timeout_error))).await;
+                                        break;
+                                    }
+                                }
+                            }
+                            None => stream.next().await,
+                        };
+                        let Some(event_result) = next else {
+                            break;
+                        };
                         event_count += 1;
                         match event_result {
                             Ok(event) => {
                                 debug!(event_num = event_count, "Received stream event");
+                                timer.observe(&event, Instant::now());
+                                if let Some(prefetch) = &prefetch {
+                                    prefetch.observe(&event);
+                                }
                                 if tx.send(Ok(event)).await.is_err() {
                                     warn!("Receiver dropped, stopping stream");
                                     break;
@@ -442,18 +838,21 @@ impl<'a> AgentExecutor<'a> {
                     }
                     debug!(total_events = event_count, "Stream completed");
                 }
-                Err(e) => {
-                    let error_str = e.to_string();
-                    error!(error = %error_str, "Failed to start stream");
-                    log_http_error(&error_str);
-
-                    // Send error event
+                Err(gave_up) => {
+                    error!(
+                        attempts = gave_up.attempts,
+                        class = %gave_up.class,
+                        error = %gave_up.last_error,
+                        "Failed to start stream"
+                    );
+
+                    // Send error event, saying how many attempts were made
                     let _ = tx
                         .send(Ok(StreamEvent::Error {
-                            message: error_str.clone(),
+                            message: gave_up.to_string(),
                         }))
                         .await;
-                    let _ = tx.send(Err(ExecutorError::Execution(error_str))).await;
+                    let _ = tx.send(Err(gave_up.into())).await;
                 }
             }
             debug!("Streaming task exiting");
@@ -487,6 +886,612 @@ fn log_http_error(error_str: &str) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::Database;
+    use crate::messaging::{AgentEvent, Message, MessageBus};
+    use crate::models::ModelRegistry;
+    use crate::session::BudgetTracker;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    async fn mock_stream(events: Vec<StreamEvent>) -> ExecutorStreamReceiver {
+        let (tx, rx) = mpsc::channel(events.len().max(1));
+        for event in events {
+            tx.send(Ok(event)).await.unwrap();
+        }
+        ExecutorStreamReceiver::new(rx)
+    }
+
+    fn user_prompt(text: &str) -> Vec<ModelRequest> {
+        let mut req = ModelRequest::new();
+        req.add_user_prompt(text.to_string());
+        vec![req]
+    }
+
+    /// A turn that calls `list_files` and then ends with an empty response.
+    fn tool_call_only_events() -> Vec<StreamEvent> {
+        vec![
+            StreamEvent::RunStart {
+                run_id: "run-1".to_string(),
+            },
+            StreamEvent::RequestStart { step: 1 },
+            StreamEvent::ToolCallStart {
+                tool_name: "list_files".to_string(),
+                tool_call_id: Some("call_1".to_string()),
+            },
+            StreamEvent::ToolCallDelta {
+                delta: "{\"path\": \".\"}".to_string(),
+                tool_call_id: Some("call_1".to_string()),
+            },
+            StreamEvent::ToolCallComplete {
+                tool_name: "list_files".to_string(),
+                tool_call_id: Some("call_1".to_string()),
+            },
+            StreamEvent::ResponseComplete { step: 1 },
+            StreamEvent::ToolExecuted {
+                tool_name: "list_files".to_string(),
+                tool_call_id: Some("call_1".to_string()),
+                success: true,
+                error: None,
+            },
+            StreamEvent::RequestStart { step: 2 },
+            StreamEvent::ResponseComplete { step: 2 },
+            StreamEvent::RunComplete {
+                run_id: "run-1".to_string(),
+            },
+        ]
+    }
+
+    // =========================================================================
+    // Tool-Call-Only Turn Tests
+    // =========================================================================
+
+    #[tokio::test]
+    async fn test_process_stream_tool_call_only_turn_completes_cleanly() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry);
+        let bus = MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let recorder = Arc::new(Mutex::new(Vec::new()));
+
+        let mut stream = mock_stream(tool_call_only_events()).await;
+        let (output, run_id, messages) = executor
+            .process_stream(
+                &mut stream,
+                &mut bridge,
+                user_prompt("list the files"),
+                "gpt-4o",
+                &recorder,
+            )
+            .await
+            .unwrap();
+
+        assert!(output.is_empty());
+        // user prompt, tool call response, tool return; the empty final response adds nothing
+        assert_eq!(messages.len(), 3);
+        match &messages[1].parts[0] {
+            ModelRequestPart::ModelResponse(response) => {
+                assert_eq!(response.parts.len(), 1);
+                assert!(matches!(response.parts[0], ModelResponsePart::ToolCall(_)));
+            }
+            _ => panic!("Expected the tool call response"),
+        }
+        match &messages[2].parts[0] {
+            ModelRequestPart::ToolReturn(part) => {
+                assert_eq!(part.tool_call_id.as_deref(), Some("call_1"));
+            }
+            _ => panic!("Expected the tool return"),
+        }
+
+        let result = complete_run(&bridge, output, run_id, messages).unwrap();
+        assert_eq!(result.run_id, "run-1");
+        assert!(result.output.is_empty());
+
+        let mut completed = false;
+        while let Ok(Some(msg)) = receiver.try_recv() {
+            if let Message::Agent(agent) = msg {
+                completed |= matches!(agent.event, AgentEvent::Completed { .. });
+            }
+        }
+        assert!(completed, "agent_completed should fire without text output");
+    }
+
+    fn tool_call_start(name: &str, id: &str) -> StreamEvent {
+        StreamEvent::ToolCallStart {
+            tool_name: name.to_string(),
+            tool_call_id: Some(id.to_string()),
+        }
+    }
+
+    /// Tool calls in `messages`, by id, and the ids of their returns.
+    fn calls_and_returns(messages: &[ModelRequest]) -> (Vec<String>, Vec<String>) {
+        let mut calls = Vec::new();
+        let mut returns = Vec::new();
+        for event in replay_events(messages) {
+            match event {
+                StreamEvent::ToolCallStart { tool_call_id, .. } => calls.extend(tool_call_id),
+                StreamEvent::ToolExecuted { tool_call_id, .. } => returns.extend(tool_call_id),
+                _ => {}
+            }
+        }
+        (calls, returns)
+    }
+
+    #[tokio::test]
+    async fn test_process_stream_cut_off_response_keeps_only_text() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry);
+        let bus = MessageBus::new();
+        let mut bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let recorder = Arc::new(Mutex::new(Vec::new()));
+
+        // The stream closes halfway through the call's args
+        let mut stream = mock_stream(vec![
+            StreamEvent::RequestStart { step: 1 },
+            StreamEvent::TextDelta {
+                text: "Let me look".to_string(),
+            },
+            tool_call_start("read_file", "call_1"),
+            StreamEvent::ToolCallDelta {
+                delta: "{\"path\": \"src/ma".to_string(),
+                tool_call_id: Some("call_1".to_string()),
+            },
+        ])
+        .await;
+        let (output, _, messages) = executor
+            .process_stream(
+                &mut stream,
+                &mut bridge,
+                user_prompt("read main"),
+                "gpt-4o",
+                &recorder,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output, "Let me look");
+        assert_eq!(messages.len(), 2);
+        match &messages[1].parts[0] {
+            ModelRequestPart::ModelResponse(response) => {
+                assert_eq!(response.parts.len(), 1);
+                assert!(matches!(response.parts[0], ModelResponsePart::Text(_)));
+            }
+            _ => panic!("Expected the partial response"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_stream_stopped_run_drops_unreturned_calls() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry);
+        let bus = MessageBus::new();
+        let mut bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let recorder = Arc::new(Mutex::new(Vec::new()));
+
+        // Stopped after the first of two calls returned
+        let mut stream = mock_stream(vec![
+            StreamEvent::RequestStart { step: 1 },
+            tool_call_start("read_file", "call_1"),
+            tool_call_start("edit_file", "call_2"),
+            StreamEvent::ResponseComplete { step: 1 },
+            StreamEvent::ToolExecuted {
+                tool_name: "read_file".to_string(),
+                tool_call_id: Some("call_1".to_string()),
+                success: true,
+                error: None,
+            },
+        ])
+        .await;
+        let (_, _, messages) = executor
+            .process_stream(
+                &mut stream,
+                &mut bridge,
+                user_prompt("fix main"),
+                "gpt-4o",
+                &recorder,
+            )
+            .await
+            .unwrap();
+
+        let (calls, returns) = calls_and_returns(&messages);
+        assert_eq!(calls, vec!["call_1".to_string()]);
+        assert_eq!(returns, calls);
+    }
+
+    #[tokio::test]
+    async fn test_process_stream_stop_keeps_returned_calls() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let cancel = CancelToken::new();
+        let executor = AgentExecutor::new(&db, &registry).with_cancellation(cancel.clone());
+        let bus = MessageBus::new();
+        let mut bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let recorder = Arc::new(Mutex::new(Vec::new()));
+
+        // The stream stays open, as a model still working would leave it
+        let (tx, rx) = mpsc::channel(8);
+        let mut stream = ExecutorStreamReceiver::new(rx);
+        for event in [
+            StreamEvent::RunStart {
+                run_id: "run-1".to_string(),
+            },
+            StreamEvent::RequestStart { step: 1 },
+            tool_call_start("edit_file", "call_1"),
+            StreamEvent::ResponseComplete { step: 1 },
+            StreamEvent::ToolExecuted {
+                tool_name: "edit_file".to_string(),
+                tool_call_id: Some("call_1".to_string()),
+                success: true,
+                error: None,
+            },
+            StreamEvent::RequestStart { step: 2 },
+            tool_call_start("run_shell_command", "call_2"),
+            StreamEvent::ResponseComplete { step: 2 },
+        ] {
+            tx.send(Ok(event)).await.unwrap();
+        }
+        tokio::spawn(async move { cancel.cancel() });
+
+        let (_, run_id, messages) = executor
+            .process_stream(
+                &mut stream,
+                &mut bridge,
+                user_prompt("fix main"),
+                "gpt-4o",
+                &recorder,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(run_id.as_deref(), Some("run-1"));
+        let (calls, returns) = calls_and_returns(&messages);
+        assert_eq!(calls, vec!["call_1".to_string()]);
+        assert_eq!(returns, calls);
+        drop(tx);
+    }
+
+    #[tokio::test]
+    async fn test_cancelling_mid_stream_returns_the_partial_run() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let cancel = CancelToken::new();
+        let executor = AgentExecutor::new(&db, &registry).with_cancellation(cancel.clone());
+        let bus = MessageBus::new();
+        let mut bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let recorder = Arc::new(Mutex::new(Vec::new()));
+
+        // Cancelled while the model is still writing a tool call
+        let (tx, rx) = mpsc::channel(8);
+        let mut stream = ExecutorStreamReceiver::new(rx);
+        for event in [
+            StreamEvent::RunStart {
+                run_id: "run-1".to_string(),
+            },
+            StreamEvent::RequestStart { step: 1 },
+            StreamEvent::TextDelta {
+                text: "I'll update the config".to_string(),
+            },
+            tool_call_start("edit_file", "call_1"),
+            StreamEvent::ToolCallDelta {
+                delta: "{\"path\": \"conf".to_string(),
+                tool_call_id: Some("call_1".to_string()),
+            },
+        ] {
+            tx.send(Ok(event)).await.unwrap();
+        }
+        tokio::spawn(async move { cancel.cancel() });
+
+        let run = executor.process_stream(
+            &mut stream,
+            &mut bridge,
+            user_prompt("bump the version"),
+            "gpt-4o",
+            &recorder,
+        );
+        let (output, run_id, messages) = tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .expect("a cancelled run should stop while the stream is still open")
+            .unwrap();
+
+        assert_eq!(output, "I'll update the config");
+        let (calls, _) = calls_and_returns(&messages);
+        assert!(calls.is_empty(), "no tool call should be left dangling");
+        let result = complete_run(&bridge, output, run_id, messages).unwrap();
+        assert_eq!(result.run_id, "run-1");
+        assert_eq!(result.messages.len(), 2);
+        drop(tx);
+    }
+
+    #[tokio::test]
+    async fn test_replay_events_match_rebuilt_history() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry);
+        let bus = MessageBus::new();
+        let mut bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let recorder = Arc::new(Mutex::new(Vec::new()));
+
+        let mut stream = mock_stream(tool_call_only_events()).await;
+        let (_, _, messages) = executor
+            .process_stream(
+                &mut stream,
+                &mut bridge,
+                user_prompt("list the files"),
+                "gpt-4o",
+                &recorder,
+            )
+            .await
+            .unwrap();
+
+        let events = replay_events(&messages);
+        assert!(matches!(
+                events.as_slice(),
+                [
+                    StreamEvent::RequestStart { step: 1 },
+                    StreamEvent::ToolCallStart { tool_name, .. },
+                    StreamEvent::ToolCallDelta { .. },
+                    StreamEvent::ToolCallComplete { .. },
+                    StreamEvent::ResponseComplete { step: 1 },
+                    StreamEvent::ToolExecuted {
+                        tool_call_id: Some(id),
+                        success: true,
+                        ..
+                    },
+                ] if tool_name == "list_files" && id == "call_1"
+        ));
+    }
+
+    #[test]
+    fn test_supports_streaming_follows_model_config() {
+        let (_temp, db) = setup_test_db();
+        let mut registry = ModelRegistry::new();
+        registry.add(crate::models::ModelConfig {
+            name: "self-hosted".to_string(),
+            supports_streaming: false,
+            ..Default::default()
+        });
+        let executor = AgentExecutor::new(&db, &registry);
+
+        assert!(!executor.supports_streaming("self-hosted"));
+        assert!(executor.supports_streaming("unknown-model"));
+    }
+
+    #[tokio::test]
+    async fn test_process_stream_keeps_tool_calls_cut_off_before_response_complete() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry);
+        let bus = MessageBus::new();
+        let mut bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let recorder = Arc::new(Mutex::new(Vec::new()));
+
+        let mut stream = mock_stream(vec![
+            StreamEvent::RequestStart { step: 1 },
+            StreamEvent::ToolCallStart {
+                tool_name: "grep".to_string(),
+                tool_call_id: Some("call_9".to_string()),
+            },
+        ])
+        .await;
+        let (output, run_id, messages) = executor
+            .process_stream(&mut stream, &mut bridge, Vec::new(), "gpt-4o", &recorder)
+            .await
+            .unwrap();
+
+        assert!(output.is_empty());
+        assert!(run_id.is_none());
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            messages[0].parts[0],
+            ModelRequestPart::ModelResponse(_)
+        ));
+    }
+
+    #[test]
+    fn test_complete_run_requires_run_id() {
+        let bus = MessageBus::new();
+        let bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let result = complete_run(&bridge, String::new(), None, Vec::new());
+        assert!(matches!(result, Err(ExecutorError::Execution(_))));
+    }
+
+    // =========================================================================
+    // Budget Tests
+    // =========================================================================
+
+    #[tokio::test]
+    async fn test_process_stream_halts_when_session_exceeds_token_budget() {
+        use crate::session::{SessionBudget, SessionUsage};
+
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let tracker = Arc::new(BudgetTracker::new(
+            SessionBudget {
+                max_tokens: Some(50),
+                ..Default::default()
+            },
+            SessionUsage::default(),
+        ));
+        let executor = AgentExecutor::new(&db, &registry).with_budget(Arc::clone(&tracker));
+        let bus = MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let recorder = Arc::new(Mutex::new(Vec::new()));
+
+        // The first turn fits; its long answer pushes the second turn over
+        let mut stream = mock_stream(vec![
+            StreamEvent::RunStart {
+                run_id: "run-1".to_string(),
+            },
+            StreamEvent::RequestStart { step: 1 },
+            StreamEvent::TextDelta {
+                text: "word ".repeat(200),
+            },
+            StreamEvent::ResponseComplete { step: 1 },
+            StreamEvent::RequestStart { step: 2 },
+            StreamEvent::TextDelta {
+                text: "never reached".to_string(),
+            },
+            StreamEvent::ResponseComplete { step: 2 },
+            StreamEvent::RunComplete {
+                run_id: "run-1".to_string(),
+            },
+        ])
+        .await;
+        let result = executor
+            .process_stream(
+                &mut stream,
+                &mut bridge,
+                user_prompt("hi"),
+                "gpt-4o",
+                &recorder,
+            )
+            .await;
+
+        match result {
+            Err(ExecutorError::BudgetExceeded(msg)) => {
+                assert!(msg.contains("token budget exceeded"), "{}", msg)
+            }
+            Err(e) => panic!("Expected BudgetExceeded, got {}", e),
+            Ok(_) => panic!("Expected the run to halt"),
+        }
+        assert!(tracker.run_usage().tokens > 50);
+
+        let mut errored = false;
+        while let Ok(Some(msg)) = receiver.try_recv() {
+            if let Message::Agent(agent) = msg {
+                errored |= matches!(agent.event, AgentEvent::Error { .. });
+            }
+        }
+        assert!(errored, "the halt should be reported on the bus");
+    }
+
+    #[test]
+    fn test_execute_refuses_to_start_over_budget() {
+        use crate::session::{SessionBudget, SessionUsage};
+
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let tracker = BudgetTracker::new(
+            SessionBudget {
+                max_tokens: Some(100),
+                ..Default::default()
+            },
+            SessionUsage {
+                tokens: 101,
+                elapsed_ms: 0,
+            },
+        );
+        let executor = AgentExecutor::new(&db, &registry).with_budget(Arc::new(tracker));
+
+        assert!(matches!(
+            executor.check_budget(),
+            Err(ExecutorError::BudgetExceeded(_))
+        ));
+    }
+
+    // =========================================================================
+    // Output Filter Tests
+    // =========================================================================
+
+    #[tokio::test]
+    async fn test_output_filter_applies_to_output_not_history() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry)
+            .with_output_filter(Box::new(|text: &str| text.to_uppercase()));
+        let bus = MessageBus::new();
+        let mut bridge = EventBridge::new(bus.sender(), "stockpot", "Stockpot");
+        let recorder = Arc::new(Mutex::new(Vec::new()));
+
+        let mut stream = mock_stream(vec![
+            StreamEvent::RunStart {
+                run_id: "run-1".to_string(),
+            },
+            StreamEvent::RequestStart { step: 1 },
+            StreamEvent::TextDelta {
+                text: "hello there".to_string(),
+            },
+            StreamEvent::ResponseComplete { step: 1 },
+            StreamEvent::RunComplete {
+                run_id: "run-1".to_string(),
+            },
+        ])
+        .await;
+        let (output, run_id, messages) = executor
+            .process_stream(
+                &mut stream,
+                &mut bridge,
+                user_prompt("greet me"),
+                "gpt-4o",
+                &recorder,
+            )
+            .await
+            .unwrap();
+
+        let result = complete_run(&bridge, output, run_id, messages)
+            .map(|result| executor.filter_output(result))
+            .unwrap();
+        assert_eq!(result.output, "HELLO THERE");
+
+        let history = serde_json::to_string(&result.messages).unwrap();
+        assert!(history.contains("hello there"));
+        assert!(!history.contains("HELLO THERE"));
+    }
+
+    #[test]
+    fn test_output_filters_run_in_order() {
+        let (_temp, db) = setup_test_db();
+        let registry = ModelRegistry::new();
+        let executor = AgentExecutor::new(&db, &registry)
+            .with_output_filter(Box::new(|text: &str| text.trim().to_string()))
+            .with_output_filter(Box::new(|text: &str| format!("[{}]", text)));
+
+        let result = executor.filter_output(ExecutorResult {
+            output: "  done \n".to_string(),
+            messages: Vec::new(),
+            run_id: "run-1".to_string(),
+        });
+        assert_eq!(result.output, "[done]");
+    }
+
+    #[test]
+    fn test_response_assembly_tool_call_only() {
+        let mut assembly = ResponseAssembly::default();
+        assembly.start_tool_call("read_file", Some("call_a".to_string()));
+        assembly.push_tool_args("{}");
+        assembly.start_tool_call("grep", None);
+        assert!(!assembly.is_empty());
+
+        let (request, pending) = assembly.finish("gpt-4o");
+        assert!(request.is_some());
+        assert_eq!(
+            pending,
+            VecDeque::from(vec![
+                ("read_file".to_string(), Some("call_a".to_string())),
+                ("grep".to_string(), None),
+            ])
+        );
+        assert!(assembly.is_empty());
+    }
+
+    #[test]
+    fn test_response_assembly_empty_response() {
+        let mut assembly = ResponseAssembly::default();
+        let (request, pending) = assembly.finish("gpt-4o");
+        assert!(request.is_none());
+        assert!(pending.is_empty());
+    }
 
     // =========================================================================
     // log_http_error Tests
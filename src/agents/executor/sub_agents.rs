@@ -3,32 +3,67 @@
 //! These executors handle the special agent management tools:
 //! - `InvokeAgentExecutor`: Invokes sub-agents with proper session management
 //! - `ListAgentsExecutor`: Returns available agents to the caller
+//!
+//! A sub-agent normally sees only the prompt it's given. With `context_turns`
+//! set, `invoke_agent` also hands it the caller's last few turns as a plain
+//! dialogue, up to the prompt that led to the call, so a reviewer can see
+//! why the code looks the way it does. Every shared turn is sent as input
+//! tokens on each of the sub-agent's requests, so at most
+//! [`MAX_CONTEXT_TURNS`] are shared, and only those are kept for it.
+//!
+//! Each run knows the chain of agents that invoked it. `invoke_agent` fails
+//! with a tool error rather than start an agent already in the chain (a
+//! cycle, like A → B → A) or one deeper than the `max_agent_depth` setting.
 
 use async_trait::async_trait;
 use serde_json::Value as JsonValue;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tracing::{debug, warn};
 
+use serdes_ai_core::messages::UserContent;
+use serdes_ai_core::ModelRequest;
 use serdes_ai_tools::{Tool, ToolDefinition, ToolError, ToolReturn};
 
 use crate::agents::AgentManager;
 use crate::config::Settings;
 use crate::db::Database;
 use crate::mcp::McpManager;
-use crate::messaging::MessageSender;
+use crate::messaging::{MessageSender, ToolContentStore, ToolResultContent};
 use crate::models::ModelRegistry;
-use crate::session::SessionManager;
-use crate::tools::agent_tools::InvokeAgentTool;
-use crate::tools::SpotToolRegistry;
-
+use crate::session::{
+    compact_turns, oversized_history_warning, recent_dialogue, recent_turns, CompactCommand,
+    SessionManager,
+};
+use crate::tokens::estimate_tokens;
+use crate::tools::agent_tools::{InvokeAgentTool, ListAgentsFilter, ListAgentsTool};
+use crate::tools::{SpotToolRegistry, UndoRun};
+
+use super::approval::ApprovalPolicy;
+use super::cache::ToolCache;
+use super::titles::spawn_session_title;
 use super::AgentExecutor;
 
+/// Most turns `context_turns` shares with a sub-agent.
+pub(super) const MAX_CONTEXT_TURNS: usize = 10;
+
 /// Executor for invoke_agent that has access to all required dependencies.
 pub(super) struct InvokeAgentExecutor {
     db_path: PathBuf,
     current_model: String,
     /// Optional message bus for sub-agent event publishing.
     bus: Option<MessageSender>,
+    /// The calling run's tool cache, cleared once the sub-agent is done.
+    cache: Option<Arc<ToolCache>>,
+    /// The calling run's history and prompt, for `context_turns`.
+    parent_history: Arc<Vec<ModelRequest>>,
+    /// The calling agent and the agents that invoked it, outermost first.
+    chain: Vec<String>,
+    /// The calling run's approval policy, which sub-agents run under too.
+    approval: Option<Arc<dyn ApprovalPolicy>>,
+    /// The calling run's undo snapshot, which records the sub-agent's file
+    /// changes too.
+    undo: Option<UndoRun>,
 }
 
 impl InvokeAgentExecutor {
@@ -38,6 +73,11 @@ impl InvokeAgentExecutor {
             db_path: db.path().to_path_buf(),
             current_model: current_model.to_string(),
             bus: Some(bus),
+            cache: None,
+            parent_history: Arc::default(),
+            chain: Vec::new(),
+            approval: None,
+            undo: None,
         }
     }
 
@@ -47,6 +87,11 @@ impl InvokeAgentExecutor {
             db_path: db.path().to_path_buf(),
             current_model: current_model.to_string(),
             bus: None,
+            cache: None,
+            parent_history: Arc::default(),
+            chain: Vec::new(),
+            approval: None,
+            undo: None,
         }
     }
 
@@ -60,12 +105,106 @@ impl InvokeAgentExecutor {
             db_path,
             current_model: current_model.to_string(),
             bus,
+            cache: None,
+            parent_history: Arc::default(),
+            chain: Vec::new(),
+            approval: None,
+            undo: None,
         }
     }
 
+    /// Clear `cache` after each sub-agent run, since the sub-agent may have
+    /// written files the caller has read.
+    pub fn with_cache(mut self, cache: Arc<ToolCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Let the sub-agent see the caller's recent turns when asked to with
+    /// `context_turns`.
+    pub fn with_parent_history(mut self, history: Arc<Vec<ModelRequest>>) -> Self {
+        self.parent_history = history;
+        self
+    }
+
+    /// The calling agent and the agents above it, outermost first.
+    pub fn with_chain(mut self, chain: Vec<String>) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Ask `policy` before the sub-agent's tool calls, as for the caller's.
+    pub fn with_approval(mut self, policy: Option<Arc<dyn ApprovalPolicy>>) -> Self {
+        self.approval = policy;
+        self
+    }
+
+    /// Record the sub-agent's file changes in `run`, so undoing the
+    /// caller's run undoes them too.
+    pub fn with_undo(mut self, run: Option<UndoRun>) -> Self {
+        self.undo = run;
+        self
+    }
+
     pub fn definition() -> ToolDefinition {
         InvokeAgentTool.definition()
     }
+
+    /// The prompt for the sub-agent, led by the caller's last `turns` turns
+    /// (at most [`MAX_CONTEXT_TURNS`]).
+    fn prompt_with_context(&self, prompt: &str, turns: usize) -> String {
+        let dialogue = recent_dialogue(&self.parent_history, turns.min(MAX_CONTEXT_TURNS));
+        if dialogue.is_empty() {
+            return prompt.to_string();
+        }
+        format!(
+            "The conversation that led to this request, for context:\n\n{}\n\n---\n\n{}",
+            dialogue, prompt
+        )
+    }
+}
+
+/// Check that `chain` may invoke `agent_name`: not an agent already in it,
+/// and no deeper than `max_depth` levels below the first.
+fn check_invocation(chain: &[String], agent_name: &str, max_depth: usize) -> Result<(), String> {
+    let path = || {
+        let mut path: Vec<&str> = chain.iter().map(String::as_str).collect();
+        path.push(agent_name);
+        path.join(" → ")
+    };
+    if chain.iter().any(|caller| caller == agent_name) {
+        return Err(format!(
+            "Not invoking {}: it would call itself ({}). Finish the task here instead.",
+            agent_name,
+            path()
+        ));
+    }
+    if chain.len() > max_depth {
+        return Err(format!(
+            "Not invoking {}: agents may only nest {} deep ({}). Finish the task here instead.",
+            agent_name,
+            max_depth,
+            path()
+        ));
+    }
+    Ok(())
+}
+
+/// The turns of a run `context_turns` can share, its prompt being the
+/// newest, for [`InvokeAgentExecutor::with_parent_history`].
+///
+/// Only the last [`MAX_CONTEXT_TURNS`] turns are copied.
+pub(super) fn parent_history(
+    history: Option<&[ModelRequest]>,
+    prompt: &UserContent,
+) -> Arc<Vec<ModelRequest>> {
+    let mut messages = history
+        .map(|history| recent_turns(history, MAX_CONTEXT_TURNS - 1).to_vec())
+        .unwrap_or_default();
+    let mut request = ModelRequest::new();
+    request.add_user_prompt(prompt.clone());
+    messages.push(request);
+    Arc::new(messages)
 }
 
 #[async_trait]
@@ -81,6 +220,8 @@ impl serdes_ai_agent::ToolExecutor<()> for InvokeAgentExecutor {
             prompt: String,
             #[serde(default)]
             session_id: Option<String>,
+            #[serde(default)]
+            context_turns: usize,
         }
 
         let args: Args = serde_json::from_value(args.clone())
@@ -92,9 +233,13 @@ impl serdes_ai_agent::ToolExecutor<()> for InvokeAgentExecutor {
         let db_path = self.db_path.clone();
         let current_model = self.current_model.clone();
         let agent_name = args.agent_name.clone();
-        let prompt = args.prompt.clone();
+        let prompt = self.prompt_with_context(&args.prompt, args.context_turns);
         let session_id = args.session_id.clone();
         let bus = self.bus.clone();
+        let chain = self.chain.clone();
+        let approval = self.approval.clone();
+        let undo = self.undo.clone();
+        let runtime = tokio::runtime::Handle::current();
 
         // Run the agent in a blocking context to handle the non-Send Database
         let result = tokio::task::spawn_blocking(move || {
@@ -112,8 +257,15 @@ impl serdes_ai_agent::ToolExecutor<()> for InvokeAgentExecutor {
                 // Load fresh registries
                 let model_registry = ModelRegistry::load_from_db(&db).unwrap_or_default();
                 let agent_manager = AgentManager::new();
-                let tool_registry = SpotToolRegistry::new();
-                let mcp_manager = McpManager::new();
+                let tool_registry = match bus {
+                    Some(ref bus) => SpotToolRegistry::new().with_bus(bus.clone()),
+                    None => SpotToolRegistry::new(),
+                }
+                .with_shell_confinement(Settings::new(&db).confine_shell());
+                let mcp_manager = McpManager::new().with_api_keys_from_db(&db);
+
+                let max_depth = Settings::new(&db).max_agent_depth() as usize;
+                check_invocation(&chain, &agent_name, max_depth)?;
 
                 // Find the agent
                 let agent = agent_manager
@@ -129,12 +281,12 @@ impl serdes_ai_agent::ToolExecutor<()> for InvokeAgentExecutor {
                 };
 
                 // Load session history if session_id provided
-                let session_manager = SessionManager::new();
-                let message_history = session_id.as_ref().and_then(|sid| {
+                let session_manager = SessionManager::from_settings(&Settings::new(&db));
+                let session = session_id.as_ref().and_then(|sid| {
                     match session_manager.load(sid) {
                         Ok(data) => {
                             debug!(session_id = %sid, messages = data.messages.len(), "Loaded session history");
-                            Some(data.messages)
+                            Some(data)
                         }
                         Err(e) => {
                             debug!(session_id = %sid, error = %e, "No existing session, starting fresh");
@@ -142,9 +294,51 @@ impl serdes_ai_agent::ToolExecutor<()> for InvokeAgentExecutor {
                         }
                     }
                 });
+                let budget = session
+                    .as_ref()
+                    .map(|data| Arc::new(data.meta.budget_tracker()));
+                let is_new_session = session.is_none();
+
+                // A history too big for the model's window is compacted
+                // first, since a run with it would fail straight away
+                let context_length = model_registry
+                    .get(&effective_model)
+                    .map(|config| config.context_length);
+                let mut compacted_pins = None;
+                let message_history = session.map(|data| {
+                    let mut messages = data.messages;
+                    let Some(context_length) = context_length else {
+                        return messages;
+                    };
+                    if let Some(warning) =
+                        oversized_history_warning(estimate_tokens(&messages), context_length)
+                    {
+                        let mut pinned = data.pinned;
+                        let report = compact_turns(
+                            &mut messages,
+                            &mut pinned,
+                            CompactCommand::Fit,
+                            context_length,
+                        );
+                        warn!(session_id = ?session_id, %warning, %report, "Compacted the session history");
+                        compacted_pins = Some(pinned);
+                    }
+                    messages
+                });
 
                 // Create executor - with bus if available for visible sub-agent output
-                let executor = AgentExecutor::new(&db, &model_registry);
+                let mut callers = chain;
+                callers.push(agent_name.clone());
+                let mut executor = AgentExecutor::new(&db, &model_registry).with_callers(callers);
+                if let Some(budget) = &budget {
+                    executor = executor.with_budget(Arc::clone(budget));
+                }
+                if let Some(policy) = approval {
+                    executor = executor.with_approval_policy(policy);
+                }
+                if let Some(run) = undo {
+                    executor = executor.with_undo(run);
+                }
 
                 let result = if let Some(bus) = bus {
                     // Use execute_with_bus - events flow to the same bus!
@@ -159,7 +353,6 @@ impl serdes_ai_agent::ToolExecutor<()> for InvokeAgentExecutor {
                             &mcp_manager,
                         )
                         .await
-                        .map_err(|e| format!("Agent execution failed: {}", e))?
                 } else {
                     // Legacy: no bus, sub-agent output only in response
                     executor
@@ -172,9 +365,16 @@ impl serdes_ai_agent::ToolExecutor<()> for InvokeAgentExecutor {
                             &mcp_manager,
                         )
                         .await
-                        .map_err(|e| format!("Agent execution failed: {}", e))?
                 };
 
+                // Charge the session for this run, even one that failed or was halted
+                if let (Some(sid), Some(budget)) = (&session_id, &budget) {
+                    if let Err(e) = session_manager.record_usage(sid, budget.run_usage()) {
+                        warn!(session_id = %sid, error = %e, "Failed to record session usage");
+                    }
+                }
+                let result = result.map_err(|e| format!("Agent execution failed: {}", e))?;
+
                 // Save session for future continuation
                 let final_session_id = session_id.clone().unwrap_or_else(|| {
                     session_manager.generate_name(&agent_name)
@@ -191,15 +391,35 @@ impl serdes_ai_agent::ToolExecutor<()> for InvokeAgentExecutor {
                         warn!(error = %e, "Failed to save session");
                     } else {
                         debug!(session_id = %final_session_id, messages = result.messages.len(), "Saved session");
+                        if let Some(pinned) = &compacted_pins {
+                            if let Err(e) = session_manager.record_pins(&final_session_id, pinned) {
+                                warn!(error = %e, "Failed to save the session pins");
+                            }
+                        }
+                        if is_new_session && Settings::new(&db).title_sessions() {
+                            spawn_session_title(
+                                &runtime,
+                                db.path().to_path_buf(),
+                                final_session_id.clone(),
+                                effective_model.clone(),
+                                prompt.clone(),
+                                result.output.clone(),
+                            );
+                        }
                     }
                 }
 
                 Ok::<_, String>((result.output, final_session_id))
             })
         })
-        .await
-        .map_err(|e| ToolError::execution_failed(format!("Task join error: {}", e)))?
-        .map_err(ToolError::execution_failed)?;
+        .await;
+
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        let result = result
+            .map_err(|e| ToolError::execution_failed(format!("Task join error: {}", e)))?
+            .map_err(ToolError::execution_failed)?;
 
         Ok(ToolReturn::json(serde_json::json!({
             "agent": args.agent_name,
@@ -211,23 +431,29 @@ impl serdes_ai_agent::ToolExecutor<()> for InvokeAgentExecutor {
 }
 
 /// Executor for list_agents that returns available agents.
-pub(super) struct ListAgentsExecutor;
+pub(super) struct ListAgentsExecutor {
+    /// Where to publish the agent list as a table, if the run renders one.
+    contents: Option<ToolContentStore>,
+}
 
 impl ListAgentsExecutor {
     pub fn new(_db: &Database) -> Self {
-        Self
+        Self { contents: None }
     }
 
     /// Create executor from a path (used in spawned tasks where Database isn't Send).
     pub fn new_with_path(_db_path: PathBuf) -> Self {
-        Self
+        Self { contents: None }
+    }
+
+    /// Publish results as a structured table to the given store.
+    pub fn with_contents(mut self, contents: Option<ToolContentStore>) -> Self {
+        self.contents = contents;
+        self
     }
 
     pub fn definition() -> ToolDefinition {
-        ToolDefinition::new(
-            "list_agents",
-            "List all available agents that can be invoked.",
-        )
+        ListAgentsTool.definition()
     }
 }
 
@@ -235,13 +461,15 @@ impl ListAgentsExecutor {
 impl serdes_ai_agent::ToolExecutor<()> for ListAgentsExecutor {
     async fn execute(
         &self,
-        _args: JsonValue,
+        args: JsonValue,
         _ctx: &serdes_ai_agent::RunContext<()>,
     ) -> Result<ToolReturn, ToolError> {
+        let filter = ListAgentsFilter::from_args(&args)?;
         let agent_manager = AgentManager::new();
         let agents: Vec<_> = agent_manager
             .list()
             .iter()
+            .filter(|info| filter.matches(info))
             .map(|info| {
                 serde_json::json!({
                     "name": info.name,
@@ -251,6 +479,16 @@ impl serdes_ai_agent::ToolExecutor<()> for ListAgentsExecutor {
             })
             .collect();
 
+        if let Some(contents) = &self.contents {
+            contents.push(
+                "list_agents",
+                ToolResultContent::table_from_objects(
+                    &["name", "display_name", "description"],
+                    &agents,
+                ),
+            );
+        }
+
         Ok(ToolReturn::json(serde_json::json!({
             "agents": agents,
             "count": agents.len()
@@ -292,6 +530,64 @@ mod tests {
         assert!(executor.bus.is_some());
     }
 
+    #[test]
+    fn test_prompt_with_context_shares_recent_turns() {
+        let mut history = Vec::new();
+        for text in ["first question", "the latest question"] {
+            let mut request = ModelRequest::new();
+            request.add_user_prompt(text.to_string());
+            history.push(request);
+        }
+        let parent = parent_history(Some(&history), &UserContent::text("review this"));
+        let executor =
+            InvokeAgentExecutor::new_with_path(PathBuf::from("/tmp/test.db"), "gpt-4", None)
+                .with_parent_history(parent);
+
+        assert_eq!(executor.prompt_with_context("check it", 0), "check it");
+        let prompt = executor.prompt_with_context("check it", 2);
+        assert!(!prompt.contains("first question"));
+        assert!(prompt.contains("**user**: the latest question"));
+        assert!(prompt.contains("**user**: review this"));
+        assert!(prompt.ends_with("---\n\ncheck it"));
+    }
+
+    #[test]
+    fn test_parent_history_keeps_only_shareable_turns() {
+        let history: Vec<ModelRequest> = (0..30)
+            .map(|i| {
+                let mut request = ModelRequest::new();
+                request.add_user_prompt(format!("question {}", i));
+                request
+            })
+            .collect();
+        let parent = parent_history(Some(&history), &UserContent::text("review this"));
+        assert_eq!(parent.len(), MAX_CONTEXT_TURNS);
+
+        let executor =
+            InvokeAgentExecutor::new_with_path(PathBuf::from("/tmp/test.db"), "gpt-4", None)
+                .with_parent_history(parent);
+        let prompt = executor.prompt_with_context("check it", 1000);
+        assert!(!prompt.contains("question 20"));
+        assert!(prompt.contains("**user**: question 21"));
+        assert!(prompt.contains("**user**: review this"));
+    }
+
+    #[test]
+    fn test_check_invocation_depth_and_cycles() {
+        let chain = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        assert!(check_invocation(&chain(&["stockpot"]), "reviewer", 3).is_ok());
+        assert!(check_invocation(&chain(&["stockpot", "planner", "coder"]), "reviewer", 3).is_ok());
+
+        let err = check_invocation(&chain(&["stockpot", "reviewer"]), "stockpot", 3).unwrap_err();
+        assert!(err.contains("stockpot → reviewer → stockpot"));
+        assert!(check_invocation(&chain(&["stockpot"]), "stockpot", 3).is_err());
+
+        let err = check_invocation(&chain(&["a", "b", "c", "d"]), "e", 3).unwrap_err();
+        assert!(err.contains("only nest 3 deep"));
+        assert!(check_invocation(&chain(&["a", "b"]), "c", 1).is_err());
+    }
+
     #[test]
     fn test_invoke_agent_executor_definition_valid() {
         let def = InvokeAgentExecutor::definition();
@@ -324,8 +620,28 @@ mod tests {
     #[test]
     fn test_list_agents_executor_new_with_path() {
         let db_path = PathBuf::from("/tmp/test.db");
-        let _executor = ListAgentsExecutor::new_with_path(db_path);
-        // ListAgentsExecutor is a unit struct - creation is the test
+        let executor = ListAgentsExecutor::new_with_path(db_path);
+        assert!(executor.contents.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_agents_executor_publishes_table() {
+        use serdes_ai_agent::ToolExecutor;
+
+        let store = ToolContentStore::new();
+        let executor = ListAgentsExecutor::new_with_path(PathBuf::from("/tmp/test.db"))
+            .with_contents(Some(store.clone()));
+        let ctx = serdes_ai_agent::RunContext::new((), "test-model");
+
+        executor.execute(serde_json::json!({}), &ctx).await.unwrap();
+
+        match store.take("list_agents") {
+            Some(ToolResultContent::Table { headers, rows }) => {
+                assert_eq!(headers, vec!["name", "display_name", "description"]);
+                assert!(!rows.is_empty());
+            }
+            other => panic!("expected table, got {:?}", other),
+        }
     }
 
     #[test]
@@ -0,0 +1,123 @@
+//! Time limits on model responses.
+//!
+//! Two limits apply while a response is streaming, from its `RequestStart`
+//! to its `ResponseComplete`: the model's `request_timeout_secs` bounds the
+//! whole response, and the `stream_idle_timeout_secs` setting bounds the gap
+//! between events, so a stream that goes silent without closing is caught
+//! while a long but active one keeps going. Neither runs while tools execute
+//! between responses.
+//!
+//! Both errors say "timed out", which the retry policy treats as transient,
+//! but only the start of a stream is retried: a request that times out
+//! before streaming is sent again, one that times out while streaming ends
+//! the run.
+//!
+//! A run that doesn't stream, such as a batch prompt or a model that can't
+//! stream, exposes no requests to time, so `request_timeout_secs` bounds
+//! the whole run instead.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use super::StreamEvent;
+
+/// The error for a model request that ran past its `request_timeout_secs`.
+pub(super) fn timed_out(limit: Duration) -> String {
+    format!("Model request timed out after {}s", limit.as_secs())
+}
+
+/// The error for a stream that sent nothing for the idle timeout.
+fn went_idle(limit: Duration) -> String {
+    format!(
+        "Model stream timed out after {}s without sending anything",
+        limit.as_secs()
+    )
+}
+
+/// Tracks the response being streamed against both limits.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ResponseTimer {
+    request_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    /// When the response being streamed started, if one is
+    started: Option<Instant>,
+}
+
+impl ResponseTimer {
+    pub fn new(request_timeout: Option<Duration>, idle_timeout: Option<Duration>) -> Self {
+        Self {
+            request_timeout,
+            idle_timeout,
+            started: None,
+        }
+    }
+
+    /// Follow the stream, starting the clock at each request to the model.
+    pub fn observe(&mut self, event: &StreamEvent, now: Instant) {
+        match event {
+            StreamEvent::RequestStart { .. } => self.started = Some(now),
+            StreamEvent::ResponseComplete { .. } => self.started = None,
+            _ => {}
+        }
+    }
+
+    /// When to stop waiting for the next event, and the error to give then.
+    /// A limit too far off to represent doesn't apply.
+    pub fn deadline(&self, now: Instant) -> Option<(Instant, String)> {
+        let started = self.started?;
+        let whole = self
+            .request_timeout
+            .and_then(|limit| Some((started.checked_add(limit)?, timed_out(limit))));
+        let idle = self
+            .idle_timeout
+            .and_then(|limit| Some((now.checked_add(limit)?, went_idle(limit))));
+        match (whole, idle) {
+            (Some(whole), Some(idle)) => Some(if whole.0 <= idle.0 { whole } else { idle }),
+            (whole, idle) => whole.or(idle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::executor::ErrorClass;
+
+    #[test]
+    fn test_limits_only_apply_while_a_response_streams() {
+        let mut timer =
+            ResponseTimer::new(Some(Duration::from_secs(60)), Some(Duration::from_secs(10)));
+        let start = Instant::now();
+        assert!(timer.deadline(start).is_none());
+
+        timer.observe(&StreamEvent::RequestStart { step: 1 }, start);
+        let (at, error) = timer.deadline(start).unwrap();
+        assert_eq!(at, start + Duration::from_secs(10));
+        assert!(error.contains("without sending anything"));
+
+        // Still active near the end: the whole-response limit comes first
+        let late = start + Duration::from_secs(55);
+        let (at, error) = timer.deadline(late).unwrap();
+        assert_eq!(at, start + Duration::from_secs(60));
+        assert_eq!(error, "Model request timed out after 60s");
+
+        timer.observe(&StreamEvent::ResponseComplete { step: 1 }, late);
+        assert!(timer.deadline(late).is_none());
+    }
+
+    #[test]
+    fn test_unrepresentable_limit_does_not_panic() {
+        let mut timer = ResponseTimer::new(Some(Duration::MAX), None);
+        let start = Instant::now();
+        timer.observe(&StreamEvent::RequestStart { step: 1 }, start);
+        assert!(timer.deadline(start).is_none());
+    }
+
+    #[test]
+    fn test_timeout_errors_are_retryable() {
+        let limit = Duration::from_secs(45);
+        assert!(ErrorClass::classify(&timed_out(limit)).is_retryable());
+        assert!(ErrorClass::classify(&went_idle(limit)).is_retryable());
+    }
+}
@@ -0,0 +1,145 @@
+//! Short descriptive titles for saved sessions.
+//!
+//! Sub-agent sessions are saved under generated names like
+//! `code-reviewer-20240101-120000`. With `title_sessions` on, a new
+//! session's first exchange is sent to the model in the background to get
+//! a title for [`SessionMeta::description`](crate::session::SessionMeta).
+
+use std::path::PathBuf;
+
+use serdes_ai_agent::{agent, RunOptions};
+use tracing::{debug, warn};
+
+use crate::config::Settings;
+use crate::db::Database;
+use crate::models::ModelRegistry;
+use crate::session::SessionManager;
+
+use super::adapters::ArcModel;
+use super::model_factory::get_model;
+use super::types::ExecutorError;
+
+/// Longest title kept, in characters.
+const MAX_TITLE_CHARS: usize = 60;
+
+/// How much of the prompt and reply the model sees.
+const EXCERPT_CHARS: usize = 2000;
+
+const TITLE_INSTRUCTIONS: &str = "You name conversations. Reply with a title of at most \
+eight words describing what the conversation is about: no quotes, no trailing period, \
+nothing else.";
+
+/// Title the session `name` from its first exchange, without waiting.
+///
+/// Runs on `runtime` off the caller's task; failures are only logged.
+pub(super) fn spawn_session_title(
+    runtime: &tokio::runtime::Handle,
+    db_path: PathBuf,
+    name: String,
+    model_name: String,
+    prompt: String,
+    reply: String,
+) {
+    runtime.spawn_blocking(move || {
+        let rt = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(rt) => rt,
+            Err(e) => {
+                warn!(error = %e, "Failed to create runtime for session title");
+                return;
+            }
+        };
+        rt.block_on(async {
+            let result = async {
+                let db = Database::open_at(db_path).map_err(|e| {
+                    ExecutorError::Config(format!("Failed to open database: {}", e))
+                })?;
+                let registry = ModelRegistry::load_from_db(&db).unwrap_or_default();
+                let title = generate_title(&db, &registry, &model_name, &prompt, &reply).await?;
+                Ok::<_, ExecutorError>((SessionManager::from_settings(&Settings::new(&db)), title))
+            }
+            .await;
+            match result {
+                Ok((manager, Some(title))) => match manager.set_description(&name, &title) {
+                    Ok(()) => debug!(session = %name, %title, "Titled session"),
+                    Err(e) => warn!(session = %name, error = %e, "Failed to save session title"),
+                },
+                Ok((_, None)) => debug!(session = %name, "Model gave no usable session title"),
+                Err(e) => warn!(session = %name, error = %e, "Failed to generate session title"),
+            }
+        });
+    });
+}
+
+/// Ask `model_name` for a title for an exchange.
+async fn generate_title(
+    db: &Database,
+    registry: &ModelRegistry,
+    model_name: &str,
+    prompt: &str,
+    reply: &str,
+) -> Result<Option<String>, ExecutorError> {
+    let model = get_model(db, model_name, registry, None).await?;
+    let titler = agent(ArcModel(model))
+        .system_prompt(TITLE_INSTRUCTIONS.to_string())
+        .max_tokens(64)
+        .build();
+    let request = format!(
+        "User:\n{}\n\nAssistant:\n{}",
+        excerpt(prompt),
+        excerpt(reply)
+    );
+    let result = titler
+        .run_with_options(request.as_str(), (), RunOptions::new())
+        .await
+        .map_err(|e| ExecutorError::Execution(e.to_string()))?;
+    Ok(clean_title(&result.output))
+}
+
+fn excerpt(text: &str) -> String {
+    text.chars().take(EXCERPT_CHARS).collect()
+}
+
+/// First line of a model's answer, unquoted and cut to [`MAX_TITLE_CHARS`].
+fn clean_title(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line
+        .trim_start_matches(|c: char| c == '#' || c.is_whitespace())
+        .trim_start_matches("Title:")
+        .trim()
+        .trim_matches(|c| matches!(c, '"' | '\'' | '*' | '`'))
+        .trim_end_matches('.')
+        .trim();
+    if line.is_empty() {
+        return None;
+    }
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        return Some(line.to_string());
+    }
+    let cut: String = line.chars().take(MAX_TITLE_CHARS - 1).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_title() {
+        assert_eq!(
+            clean_title("\n\"Fixing the tokenizer panic.\"\n").as_deref(),
+            Some("Fixing the tokenizer panic")
+        );
+        assert_eq!(
+            clean_title("Title: Parser rewrite").as_deref(),
+            Some("Parser rewrite")
+        );
+        assert_eq!(clean_title("  \n "), None);
+
+        let long = clean_title(&"word ".repeat(30)).unwrap();
+        assert_eq!(long.chars().count(), MAX_TITLE_CHARS);
+        assert!(long.ends_with('…'));
+    }
+}
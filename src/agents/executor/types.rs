@@ -4,12 +4,17 @@
 //! - `ExecutorResult`: The result of agent execution
 //! - `ExecutorStreamReceiver`: Wrapper for receiving stream events
 //! - `ExecutorError`: Error types for executor operations
+//! - `CancelToken`: Cooperative cancellation signal for a run
+//! - `OutputFilter`: Post-processing hook for the final output
+
+use std::sync::Arc;
 
 use crate::mcp::McpManager;
+use crate::session::BudgetExceeded;
 use crate::tools::SpotToolRegistry;
 use serdes_ai_core::ModelRequest;
 use thiserror::Error;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
 use super::StreamEvent;
 
@@ -34,6 +39,11 @@ pub struct ExecutorResult {
     pub run_id: String,
 }
 
+/// Transforms an agent's final text output before it is returned.
+///
+/// Registered with [`AgentExecutor::with_output_filter`](super::AgentExecutor::with_output_filter).
+pub type OutputFilter = Box<dyn Fn(&str) -> String + Send + Sync>;
+
 /// Receiver for streaming events from agent execution.
 ///
 /// This wraps an mpsc receiver and provides a convenient interface
@@ -56,6 +66,47 @@ impl ExecutorStreamReceiver {
     }
 }
 
+/// Cooperative cancellation signal for an agent run.
+///
+/// Clones share the same signal, so cancelling any clone cancels them all.
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl CancelToken {
+    /// Create a token that has not been cancelled.
+    pub fn new() -> Self {
+        let (tx, _rx) = watch::channel(false);
+        Self { tx: Arc::new(tx) }
+    }
+
+    /// Signal cancellation to every holder of this token.
+    pub fn cancel(&self) {
+        self.tx.send_replace(true);
+    }
+
+    /// Whether cancellation has been signalled.
+    pub fn is_cancelled(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Wait until cancellation is signalled.
+    ///
+    /// Resolves immediately if the token is already cancelled.
+    pub async fn cancelled(&self) {
+        let mut rx = self.tx.subscribe();
+        // The sender lives in `self`, so this can't fail while we wait
+        let _ = rx.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Errors that can occur during agent execution.
 #[derive(Debug, Error)]
 pub enum ExecutorError {
@@ -69,6 +120,14 @@ pub enum ExecutorError {
     Execution(String),
     #[error("Configuration error: {0}")]
     Config(String),
+    #[error("{0}")]
+    BudgetExceeded(String),
+}
+
+impl From<BudgetExceeded> for ExecutorError {
+    fn from(e: BudgetExceeded) -> Self {
+        Self::BudgetExceeded(e.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -105,6 +164,19 @@ mod tests {
         assert_eq!(err.to_string(), "Configuration error: missing model");
     }
 
+    #[test]
+    fn executor_error_from_budget_exceeded() {
+        let err = ExecutorError::from(BudgetExceeded::Time {
+            elapsed: 90,
+            limit: 60,
+        });
+        assert!(matches!(err, ExecutorError::BudgetExceeded(_)));
+        assert_eq!(
+            err.to_string(),
+            "Session time budget exceeded: ran 90s of 60s"
+        );
+    }
+
     #[test]
     fn executor_result_fields() {
         let result = ExecutorResult {
@@ -161,4 +233,20 @@ mod tests {
         // Should return None
         assert!(receiver.recv().await.is_none());
     }
+
+    #[tokio::test]
+    async fn cancel_token_shared_between_clones() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        let waiter = tokio::spawn(async move { clone.cancelled().await });
+        token.cancel();
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+            .await
+            .expect("cancelled() should resolve after cancel()")
+            .unwrap();
+        assert!(token.is_cancelled());
+    }
 }
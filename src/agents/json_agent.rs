@@ -12,14 +12,22 @@
 //!   "system_prompt": "You are...",
 //!   "tools": ["read_file", "edit_file", "grep"],
 //!   "model": "gpt-4o",
+//!   "temperature": 0.2,
+//!   "top_p": 0.9,
+//!   "max_tokens": 8000,
 //!   "visibility": "main"
 //! }
 //! ```
 //!
 //! // visibility: "main" | "sub" | "hidden" (default: "main")
+//!
+//! `temperature`, `top_p` and `max_tokens` are optional and win over the
+//! model's settings; a one-off `--temperature`/`--top-p` still wins over
+//! them.
 
 use super::base::SpotAgent;
 use super::{AgentCapabilities, AgentVisibility};
+use crate::models::settings::SamplingOverride;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -63,6 +71,15 @@ pub struct JsonAgentDef {
     /// Visibility level for UI filtering (main, sub, hidden).
     #[serde(default)]
     pub visibility: Option<AgentVisibility>,
+    /// Sampling temperature (0.0 - 2.0), over the model's setting.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Top-p (0.0 - 1.0), over the model's setting.
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Output token limit, over the model's setting.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
 }
 
 /// Capabilities defined in JSON.
@@ -119,6 +136,13 @@ impl JsonAgent {
                 "system_prompt is required".to_string(),
             ));
         }
+        SamplingOverride::new(def.temperature, def.top_p)
+            .map_err(|e| JsonAgentError::Invalid(e.to_string()))?;
+        if def.max_tokens == Some(0) {
+            return Err(JsonAgentError::Invalid(
+                "max_tokens must be at least 1".to_string(),
+            ));
+        }
 
         Ok(Self::new(def))
     }
@@ -159,6 +183,18 @@ impl SpotAgent for JsonAgent {
     fn model_override(&self) -> Option<&str> {
         self.def.model.as_deref()
     }
+
+    fn temperature(&self) -> Option<f32> {
+        self.def.temperature
+    }
+
+    fn top_p(&self) -> Option<f32> {
+        self.def.top_p
+    }
+
+    fn max_tokens(&self) -> Option<u32> {
+        self.def.max_tokens
+    }
 }
 
 /// Get the agents directory path.
@@ -253,6 +289,9 @@ mod tests {
             model: Some("gpt-4o".to_string()),
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -326,6 +365,9 @@ mod tests {
                 mcp: Some(false),
             }),
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -369,6 +411,41 @@ mod tests {
         assert!(err.to_string().contains("system_prompt is required"));
     }
 
+    #[test]
+    fn test_from_file_sampling_fields() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("planner.json");
+
+        fs::write(
+            &path,
+            r#"{
+            "name": "planner",
+            "system_prompt": "You plan.",
+            "temperature": 0.9,
+            "max_tokens": 2000
+        }"#,
+        )
+        .unwrap();
+        let agent = JsonAgent::from_file(&path).unwrap();
+        assert_eq!(agent.temperature(), Some(0.9));
+        assert_eq!(agent.top_p(), None);
+        assert_eq!(agent.max_tokens(), Some(2000));
+
+        fs::write(
+            &path,
+            r#"{
+            "name": "planner",
+            "system_prompt": "You plan.",
+            "top_p": 10
+        }"#,
+        )
+        .unwrap();
+        let err = JsonAgent::from_file(&path).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("top_p must be between 0.0 and 1.0"));
+    }
+
     #[test]
     fn test_from_file_io_error() {
         let result = JsonAgent::from_file("/nonexistent/path/agent.json");
@@ -397,6 +474,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -414,6 +494,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -432,6 +515,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
         let agent_default = JsonAgent::new(def_default);
         assert_eq!(agent_default.visibility(), AgentVisibility::default());
@@ -446,6 +532,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: Some(AgentVisibility::Hidden),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
         let agent_hidden = JsonAgent::new(def_hidden);
         assert_eq!(agent_hidden.visibility(), AgentVisibility::Hidden);
@@ -460,6 +549,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: Some(AgentVisibility::Sub),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
         let agent_sub = JsonAgent::new(def_sub);
         assert_eq!(agent_sub.visibility(), AgentVisibility::Sub);
@@ -582,6 +674,9 @@ mod tests {
                 mcp: None,               // defaults to true
             }),
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -604,6 +699,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -628,6 +726,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -645,6 +746,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -663,6 +767,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -711,6 +818,9 @@ mod tests {
                 mcp: Some(true),
             }),
             visibility: Some(AgentVisibility::Sub),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let json = serde_json::to_string(&def).unwrap();
@@ -962,6 +1072,9 @@ mod tests {
                 mcp: None,
             }),
             visibility: Some(AgentVisibility::Sub),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -987,6 +1100,9 @@ mod tests {
             model: Some("model".to_string()),
             capabilities: Some(JsonCapabilities::default()),
             visibility: Some(AgentVisibility::Main),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let cloned = def.clone();
@@ -1026,6 +1142,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -1046,6 +1165,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let debug_str = format!("{:?}", def);
@@ -1090,6 +1212,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -1148,6 +1273,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: Some(AgentVisibility::Hidden),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let json = serde_json::to_string(&def).unwrap();
@@ -1214,6 +1342,9 @@ mod tests {
                 mcp: Some(false),
             }),
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -1243,6 +1374,9 @@ mod tests {
                 mcp: Some(true),
             }),
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -1268,6 +1402,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -1291,6 +1428,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -1310,6 +1450,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
@@ -1395,6 +1538,9 @@ mod tests {
             model: None,
             capabilities: None,
             visibility: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
         };
 
         let agent = JsonAgent::new(def);
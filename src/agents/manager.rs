@@ -3,7 +3,7 @@
 use super::base::{BoxedAgent, SpotAgent};
 use super::builtin;
 use super::json_agent::load_json_agents;
-use super::{AgentVisibility, UserMode};
+use super::{AgentCapabilities, AgentVisibility, UserMode};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
@@ -96,6 +96,7 @@ impl AgentManager {
                 display_name: a.display_name().to_string(),
                 description: a.description().to_string(),
                 visibility: a.visibility(),
+                capabilities: a.capabilities(),
             })
             .collect();
         agents.sort_by(|a, b| a.name.cmp(&b.name));
@@ -134,6 +135,7 @@ pub struct AgentInfo {
     pub display_name: String,
     pub description: String,
     pub visibility: AgentVisibility,
+    pub capabilities: AgentCapabilities,
 }
 
 /// Agent-related errors.
@@ -621,6 +623,7 @@ mod tests {
             display_name: "Test".to_string(),
             description: "Desc".to_string(),
             visibility: AgentVisibility::Main,
+            capabilities: AgentCapabilities::default(),
         };
 
         let cloned = info.clone();
@@ -637,6 +640,7 @@ mod tests {
             display_name: "Test".to_string(),
             description: "Desc".to_string(),
             visibility: AgentVisibility::Sub,
+            capabilities: AgentCapabilities::default(),
         };
 
         let debug = format!("{:?}", info);
@@ -1095,6 +1099,7 @@ mod tests {
             display_name: "".to_string(),
             description: "".to_string(),
             visibility: AgentVisibility::Main,
+            capabilities: AgentCapabilities::default(),
         };
 
         // Should not panic
@@ -1110,6 +1115,7 @@ mod tests {
             display_name: "Test".to_string(),
             description: long_desc.clone(),
             visibility: AgentVisibility::Main,
+            capabilities: AgentCapabilities::default(),
         };
 
         assert_eq!(info.description.len(), 10000);
@@ -18,7 +18,13 @@ pub mod json_agent;
 mod manager;
 
 pub use base::SpotAgent;
-pub use executor::{AgentExecutor, ExecuteContext};
+pub(crate) use executor::endpoint_api_key;
+pub use executor::sandbox_mode_enabled;
+pub use executor::{export_network_env, http_client, restore_child_env};
+pub use executor::{
+    AgentExecutor, AllowAll, ApprovalPolicy, CancelToken, Decision, DenyAll, ExecuteContext,
+    ExecutorError, ExecutorResult, Interactive, OutputFilter, RetryPolicy, ToolCall,
+};
 pub use manager::{AgentInfo, AgentManager};
 
 /// Agent capability flags.
@@ -0,0 +1,190 @@
+//! Crash-safe file replacement.
+//!
+//! `fs::write` truncates the target before writing, so a process killed
+//! partway through leaves a cut-off file behind. [`write_atomic`] writes a
+//! sibling temp file, syncs it and renames it over the target instead:
+//! readers see either the old contents or the new, never a mix.
+//!
+//! For the user's own files, [`replace_atomic`] also keeps what a rename
+//! would lose: the file's mode, a symlink and hard links.
+
+use std::fs::{self, File, OpenOptions, Permissions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Numbers the temp files of one process, so concurrent writes to the
+/// same target don't share one.
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// Replace the contents of `path` with `contents` atomically.
+///
+/// The temp file lives in the same directory, since a rename is only
+/// atomic within one filesystem. It's removed if anything fails.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_with(path, contents.as_ref(), None)
+}
+
+/// Replace the contents of an existing file atomically, keeping its
+/// permissions. A symlink is followed and its target replaced. A file with
+/// other hard links is written in place instead, as a rename would split
+/// it off from them.
+pub fn replace_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let target = fs::canonicalize(path)?;
+    let metadata = fs::metadata(&target)?;
+    if hard_linked(&metadata) {
+        return fs::write(&target, contents);
+    }
+    write_with(&target, contents.as_ref(), Some(metadata.permissions()))
+}
+
+/// Write a temp file with `permissions`, or the default mode, and rename
+/// it over `path`.
+fn write_with(path: &Path, contents: &[u8], permissions: Option<Permissions>) -> io::Result<()> {
+    let temp = temp_path(path)?;
+    let result = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+        return result;
+    }
+    sync_parent(path);
+    Ok(())
+}
+
+/// Whether other names share the file's contents.
+fn hard_linked(metadata: &fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.nlink() > 1
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// `.<name>.tmp-<pid>-<n>` next to `path`, unique within this process.
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a file path", path.display()),
+        )
+    })?;
+    Ok(path.with_file_name(format!(
+        ".{}.tmp-{}-{}",
+        name.to_string_lossy(),
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+    )))
+}
+
+/// Persist the rename itself. Best effort: not every platform can open a
+/// directory to sync it.
+fn sync_parent(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_atomic_replaces_and_cleans_up() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("session.json");
+
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+
+        let entries: Vec<_> = fs::read_dir(temp.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1, "no temp file left behind");
+    }
+
+    #[test]
+    fn test_concurrent_writes_to_one_file() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("session.json");
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        write_atomic(path, format!("writer {}", i)).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert!(fs::read_to_string(&path).unwrap().starts_with("writer "));
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_failed_write_keeps_old_contents() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("session.json");
+        write_atomic(&path, "intact").unwrap();
+
+        // Renaming a file over a directory fails after the temp file is written
+        let dir_target = temp.path().join("dir");
+        fs::create_dir(&dir_target).unwrap();
+        fs::write(dir_target.join("child"), "").unwrap();
+        assert!(write_atomic(&dir_target, "new").is_err());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "intact");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replace_atomic_keeps_mode_and_links() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let temp = TempDir::new().unwrap();
+        let script = temp.path().join("build.sh");
+        fs::write(&script, "echo old").unwrap();
+        fs::set_permissions(&script, Permissions::from_mode(0o755)).unwrap();
+
+        // Through a symlink: the target changes and the link stays a link
+        let link = temp.path().join("link.sh");
+        symlink(&script, &link).unwrap();
+        replace_atomic(&link, "echo new").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&script).unwrap(), "echo new");
+        let mode = fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        // Both names of a hard-linked file see the change
+        let other = temp.path().join("other.sh");
+        fs::hard_link(&script, &other).unwrap();
+        replace_atomic(&script, "echo linked").unwrap();
+        assert_eq!(fs::read_to_string(&other).unwrap(), "echo linked");
+    }
+}
@@ -0,0 +1,35 @@
+//! The local callback server of the OAuth login flow.
+//!
+//! The browser redirects back to a localhost port once sign-in is done.
+//! That port is fixed per provider (it's registered with the provider
+//! along with the client ID), so it can't be moved when something else,
+//! often a second login, already holds it. The flow's bind error is
+//! recognized from its message and turned into one that says so.
+
+/// Whether a login flow failed because its callback port was taken.
+pub(super) fn is_port_in_use(error: &str) -> bool {
+    let error = error.to_lowercase();
+    error.contains("address already in use")
+        || error.contains("addrinuse")
+        || error.contains("os error 48")
+        || error.contains("os error 98")
+        || error.contains("os error 10048")
+        || error.contains("only one usage of each socket address")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_port_in_use() {
+        assert!(is_port_in_use(
+            "Server error: Address already in use (os error 98)"
+        ));
+        assert!(is_port_in_use("bind failed: os error 48"));
+        assert!(is_port_in_use(
+            "Only one usage of each socket address is normally permitted. (os error 10048)"
+        ));
+        assert!(!is_port_in_use("Token exchange failed: invalid_grant"));
+    }
+}
@@ -1,6 +1,9 @@
 //! ChatGPT OAuth authentication.
 
+use super::callback::is_port_in_use;
+use super::refresh::{fresh_access_token, RefreshError};
 use super::storage::{StoredTokens, TokenStorage, TokenStorageError};
+use super::OAuthProvider;
 use crate::db::Database;
 use crate::models::{ModelConfig, ModelType};
 use base64::Engine;
@@ -92,10 +95,14 @@ pub enum ChatGptAuthError {
     OAuth(#[from] OAuthError),
     #[error("Storage error: {0}")]
     Storage(#[from] TokenStorageError),
+    #[error(transparent)]
+    Refresh(#[from] RefreshError),
     #[error("Not authenticated")]
     NotAuthenticated,
     #[error("Browser error: {0}")]
     Browser(String),
+    #[error("Couldn't start the OAuth callback server: its port is already in use ({0}). Finish or cancel the other login (another stockpot, or a CLI that signs in to the same provider) and try again.")]
+    CallbackPortInUse(String),
 }
 
 /// ChatGPT authentication manager.
@@ -118,50 +125,48 @@ impl<'a> ChatGptAuth<'a> {
 
     /// Save tokens from OAuth response.
     pub fn save_tokens(&self, tokens: &TokenResponse) -> Result<(), ChatGptAuthError> {
-        // Try to extract account_id from id_token if available
-        let account_id = tokens
-            .id_token
-            .as_ref()
-            .and_then(|id_token| extract_account_id_from_token(id_token));
-
-        if account_id.is_some() {
-            info!("Extracted account_id from OAuth token");
-        }
-
-        self.storage.save(
-            PROVIDER,
-            &tokens.access_token,
-            tokens.refresh_token.as_deref(),
-            tokens.expires_in,
-            account_id.as_deref(),
-            None,
-        )?;
-        Ok(())
+        Ok(store_tokens(&self.storage, tokens)?)
     }
 
-    /// Refresh tokens if needed.
+    /// The access token, refreshed first if it's about to expire.
     pub async fn refresh_if_needed(&self) -> Result<String, ChatGptAuthError> {
-        let tokens = self
-            .storage
-            .load(PROVIDER)?
-            .ok_or(ChatGptAuthError::NotAuthenticated)?;
-
-        // Refresh if expired or expiring within 5 minutes
-        if tokens.expires_within(300) {
-            if let Some(refresh_token) = &tokens.refresh_token {
+        let access_token = fresh_access_token(
+            &self.storage,
+            OAuthProvider::ChatGpt,
+            |refresh_token| async move {
                 let config = chatgpt_oauth_config();
-                let new_tokens = oauth_refresh_token(&config, refresh_token).await?;
-                self.save_tokens(&new_tokens)?;
-                return Ok(new_tokens.access_token);
-            }
-            // No refresh token and expired
-            if tokens.is_expired() {
-                return Err(ChatGptAuthError::NotAuthenticated);
-            }
-        }
+                oauth_refresh_token(&config, &refresh_token).await
+            },
+            |tokens| store_tokens(&self.storage, tokens),
+        )
+        .await?;
+        Ok(access_token)
+    }
+}
 
-        Ok(tokens.access_token)
+/// Save tokens from an OAuth response, with the account ID from the ID token.
+fn store_tokens(
+    storage: &TokenStorage<'_>,
+    tokens: &TokenResponse,
+) -> Result<(), TokenStorageError> {
+    // Try to extract account_id from id_token if available
+    let account_id = tokens
+        .id_token
+        .as_ref()
+        .and_then(|id_token| extract_account_id_from_token(id_token));
+
+    if account_id.is_some() {
+        info!("Extracted account_id from OAuth token");
     }
+
+    storage.save(
+        PROVIDER,
+        &tokens.access_token,
+        tokens.refresh_token.as_deref(),
+        tokens.expires_in,
+        account_id.as_deref(),
+        None,
+    )
 }
 
 // ============================================================================
@@ -230,6 +235,7 @@ fn save_chatgpt_models_to_db(db: &Database, models: &[String]) -> Result<(), std
             supports_thinking,
             supports_vision,
             supports_tools: true,
+            supports_streaming: true,
             description: Some(format!("ChatGPT OAuth: {}", model_name)),
             custom_endpoint: None,
             azure_deployment: None,
@@ -271,13 +277,24 @@ pub async fn run_chatgpt_auth(db: &Database) -> Result<(), ChatGptAuthError> {
     println!("🔐 Starting ChatGPT OAuth authentication...");
 
     let config = chatgpt_oauth_config();
-    let (auth_url, handle) = run_pkce_flow(&config).await?;
+    let (auth_url, handle) = run_pkce_flow(&config).await.map_err(|e| {
+        let message = e.to_string();
+        if is_port_in_use(&message) {
+            ChatGptAuthError::CallbackPortInUse(message)
+        } else {
+            e.into()
+        }
+    })?;
 
     println!("📋 Open this URL in your browser:");
     println!("   {}", auth_url);
     println!();
     println!(
-        "⏳ Waiting for authentication callback on port {}...",
+        "⏳ Waiting for authentication callback on http://localhost:{}...",
+        handle.port()
+    );
+    println!(
+        "   (on a remote machine, forward it first: ssh -L {0}:localhost:{0} <host>)",
         handle.port()
     );
 
@@ -1,6 +1,9 @@
 //! Claude Code OAuth authentication.
 
+use super::callback::is_port_in_use;
+use super::refresh::{fresh_access_token, RefreshError};
 use super::storage::{TokenStorage, TokenStorageError};
+use super::OAuthProvider;
 use crate::db::Database;
 use crate::models::{ModelConfig, ModelType};
 use serde::Deserialize;
@@ -21,10 +24,14 @@ pub enum ClaudeCodeAuthError {
     OAuth(#[from] OAuthError),
     #[error("Storage error: {0}")]
     Storage(#[from] TokenStorageError),
+    #[error(transparent)]
+    Refresh(#[from] RefreshError),
     #[error("Not authenticated")]
     NotAuthenticated,
     #[error("Browser error: {0}")]
     Browser(String),
+    #[error("Couldn't start the OAuth callback server: its port is already in use ({0}). Finish or cancel the other login (another stockpot, or a CLI that signs in to the same provider) and try again.")]
+    CallbackPortInUse(String),
 }
 
 /// Claude Code authentication manager.
@@ -53,48 +60,28 @@ impl<'a> ClaudeCodeAuth<'a> {
         Ok(())
     }
 
-    /// Refresh tokens if needed.
+    /// The access token, refreshed first if it's about to expire.
     pub async fn refresh_if_needed(&self) -> Result<String, ClaudeCodeAuthError> {
-        debug!("Checking Claude Code token status");
-
-        let tokens = self.storage.load(PROVIDER)?.ok_or_else(|| {
-            warn!("No Claude Code tokens found in storage");
-            ClaudeCodeAuthError::NotAuthenticated
-        })?;
-
-        debug!(
-            has_refresh_token = tokens.refresh_token.is_some(),
-            is_expired = tokens.is_expired(),
-            expires_within_5min = tokens.expires_within(300),
-            "Token status"
-        );
-
-        // Refresh if expired or expiring within 5 minutes
-        if tokens.expires_within(300) {
-            if let Some(refresh_token) = &tokens.refresh_token {
-                info!("Token expiring soon, refreshing...");
+        let access_token = fresh_access_token(
+            &self.storage,
+            OAuthProvider::ClaudeCode,
+            |refresh_token| async move {
                 let config = claude_code_oauth_config();
-                match oauth_refresh_token(&config, refresh_token).await {
-                    Ok(new_tokens) => {
-                        info!("Token refreshed successfully");
-                        self.save_tokens(&new_tokens)?;
-                        return Ok(new_tokens.access_token);
-                    }
-                    Err(e) => {
-                        error!(error = %e, "Failed to refresh token");
-                        return Err(e.into());
-                    }
-                }
-            }
-            // No refresh token and expired
-            if tokens.is_expired() {
-                warn!("Token expired and no refresh token available");
-                return Err(ClaudeCodeAuthError::NotAuthenticated);
-            }
-        }
-
-        debug!("Using existing valid token");
-        Ok(tokens.access_token)
+                oauth_refresh_token(&config, &refresh_token).await
+            },
+            |tokens| {
+                self.storage.save(
+                    PROVIDER,
+                    &tokens.access_token,
+                    tokens.refresh_token.as_deref(),
+                    tokens.expires_in,
+                    None,
+                    None,
+                )
+            },
+        )
+        .await?;
+        Ok(access_token)
     }
 }
 
@@ -114,8 +101,12 @@ struct ModelsResponse {
 }
 
 /// Fetch available models from Claude API
-async fn fetch_claude_models(access_token: &str) -> Result<Vec<String>, ClaudeCodeAuthError> {
-    let client = reqwest::Client::new();
+async fn fetch_claude_models(
+    db: &Database,
+    access_token: &str,
+) -> Result<Vec<String>, ClaudeCodeAuthError> {
+    let client = crate::agents::http_client(db)
+        .map_err(|e| ClaudeCodeAuthError::OAuth(OAuthError::TokenExchange(e.to_string())))?;
     let response = client
         .get("https://api.anthropic.com/v1/models")
         .header("Authorization", format!("Bearer {}", access_token))
@@ -249,6 +240,7 @@ fn save_claude_models_to_db(db: &Database, models: &[String]) -> Result<(), std:
             supports_thinking,
             supports_vision: true,
             supports_tools: true,
+            supports_streaming: true,
             description: Some(format!("Claude Code OAuth: {}", model_name)),
             custom_endpoint: None,
             azure_deployment: None,
@@ -269,13 +261,24 @@ pub async fn run_claude_code_auth(db: &Database) -> Result<(), ClaudeCodeAuthErr
     println!("🔐 Starting Claude Code OAuth authentication...");
 
     let config = claude_code_oauth_config();
-    let (auth_url, handle) = run_pkce_flow(&config).await?;
+    let (auth_url, handle) = run_pkce_flow(&config).await.map_err(|e| {
+        let message = e.to_string();
+        if is_port_in_use(&message) {
+            ClaudeCodeAuthError::CallbackPortInUse(message)
+        } else {
+            e.into()
+        }
+    })?;
 
     println!("📋 Open this URL in your browser:");
     println!("   {}", auth_url);
     println!();
     println!(
-        "⏳ Waiting for authentication callback on port {}...",
+        "⏳ Waiting for authentication callback on http://localhost:{}...",
+        handle.port()
+    );
+    println!(
+        "   (on a remote machine, forward it first: ssh -L {0}:localhost:{0} <host>)",
         handle.port()
     );
 
@@ -294,7 +297,7 @@ pub async fn run_claude_code_auth(db: &Database) -> Result<(), ClaudeCodeAuthErr
 
     // Fetch and save available models
     println!("📥 Fetching available Claude models...");
-    match fetch_claude_models(&tokens.access_token).await {
+    match fetch_claude_models(db, &tokens.access_token).await {
         Ok(models) => {
             let filtered = filter_latest_models(models);
             if filtered.is_empty() {
@@ -2,16 +2,21 @@
 //!
 //! This module handles:
 //! - Token storage in SQLite
-//! - Token refresh when expired
+//! - Token refresh shortly before expiry
 //! - Model factory functions that load tokens from storage
 
+mod callback;
 mod chatgpt;
 mod claude_code;
+mod refresh;
+mod status;
 mod storage;
 
 pub use chatgpt::{get_chatgpt_model, run_chatgpt_auth};
 pub use claude_code::{get_claude_code_model, run_claude_code_auth};
-pub use storage::TokenStorage;
+pub use refresh::{RefreshError, REFRESH_MARGIN_SECS};
+pub use status::{auth_status, ProviderStatus};
+pub use storage::{StoredTokens, TokenStorage};
 
 /// Supported OAuth providers.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -0,0 +1,187 @@
+//! Refreshing OAuth tokens before they expire.
+//!
+//! Models get their access token when they're created, so a token that
+//! expires during a long run fails the requests after it. Tokens are
+//! renewed when they're within [`REFRESH_MARGIN_SECS`] of expiry instead.
+//!
+//! Refresh tokens can be single use, so refreshes for one provider are
+//! serialized: a sub-agent that starts while its parent is refreshing
+//! waits, then picks up the token that was just saved.
+
+use std::future::Future;
+
+use serdes_ai_providers::oauth::{OAuthError, TokenResponse};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+use super::storage::{TokenStorage, TokenStorageError};
+use super::OAuthProvider;
+
+/// Refresh tokens that expire within this many seconds.
+pub const REFRESH_MARGIN_SECS: i64 = 300;
+
+static CHATGPT_REFRESH: Mutex<()> = Mutex::const_new(());
+static CLAUDE_CODE_REFRESH: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Error)]
+pub enum RefreshError {
+    #[error("Not connected to {0}. Connect it in Settings to use its models.")]
+    NotAuthenticated(&'static str),
+    #[error("{provider} session expired and couldn't be renewed ({reason}). Reconnect it in Settings to sign in again.")]
+    Reauthenticate {
+        provider: &'static str,
+        reason: String,
+    },
+    #[error("Storage error: {0}")]
+    Storage(#[from] TokenStorageError),
+}
+
+impl OAuthProvider {
+    /// Name to show the user.
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::ChatGpt => "ChatGPT",
+            Self::ClaudeCode => "Claude Code",
+        }
+    }
+
+    fn refresh_lock(&self) -> &'static Mutex<()> {
+        match self {
+            Self::ChatGpt => &CHATGPT_REFRESH,
+            Self::ClaudeCode => &CLAUDE_CODE_REFRESH,
+        }
+    }
+}
+
+/// An access token for `provider` that's good for at least
+/// [`REFRESH_MARGIN_SECS`], refreshing it first if needed.
+///
+/// `refresh` exchanges a refresh token for new tokens and `save` persists
+/// them; both run while the provider's refresh lock is held.
+pub(super) async fn fresh_access_token<R, Fut>(
+    storage: &TokenStorage<'_>,
+    provider: OAuthProvider,
+    refresh: R,
+    save: impl FnOnce(&TokenResponse) -> Result<(), TokenStorageError>,
+) -> Result<String, RefreshError>
+where
+    R: FnOnce(String) -> Fut,
+    Fut: Future<Output = Result<TokenResponse, OAuthError>>,
+{
+    let name = provider.display_name();
+    let _guard = provider.refresh_lock().lock().await;
+
+    // Loaded under the lock, so a refresh that just finished is seen
+    let tokens = storage.load(provider.as_str())?.ok_or_else(|| {
+        warn!(provider = %name, "No OAuth tokens found in storage");
+        RefreshError::NotAuthenticated(name)
+    })?;
+
+    debug!(
+        provider = %name,
+        has_refresh_token = tokens.refresh_token.is_some(),
+        is_expired = tokens.is_expired(),
+        needs_refresh = tokens.needs_refresh(),
+        "Token status"
+    );
+
+    if !tokens.needs_refresh() {
+        return Ok(tokens.access_token);
+    }
+
+    let Some(refresh_token) = tokens.refresh_token else {
+        if tokens.is_expired() {
+            warn!(provider = %name, "Token expired and no refresh token available");
+            return Err(RefreshError::Reauthenticate {
+                provider: name,
+                reason: "no refresh token".to_string(),
+            });
+        }
+        // Still usable for a little while; nothing to refresh with
+        return Ok(tokens.access_token);
+    };
+
+    info!(provider = %name, "Token expiring soon, refreshing...");
+    let new_tokens = refresh(refresh_token).await.map_err(|e| {
+        error!(provider = %name, error = %e, "Failed to refresh token");
+        RefreshError::Reauthenticate {
+            provider: name,
+            reason: e.to_string(),
+        }
+    })?;
+    save(&new_tokens)?;
+    info!(provider = %name, "Token refreshed successfully");
+    Ok(new_tokens.access_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_at(temp_dir.path().join("test.db")).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    /// Fetch a token with a refresh that must not happen.
+    async fn access_token(storage: &TokenStorage<'_>) -> Result<String, RefreshError> {
+        fresh_access_token(
+            storage,
+            OAuthProvider::ClaudeCode,
+            |_| std::future::pending(),
+            |_| unreachable!("nothing to save"),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_fresh_token_used_as_is() {
+        let (_temp, db) = setup_test_db();
+        let storage = TokenStorage::new(&db);
+        storage
+            .save(
+                "claude-code",
+                "access",
+                Some("refresh"),
+                Some(3600),
+                None,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(access_token(&storage).await.unwrap(), "access");
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_unrenewable_token_asks_to_reconnect() {
+        let (_temp, db) = setup_test_db();
+        let storage = TokenStorage::new(&db);
+        assert!(matches!(
+            access_token(&storage).await,
+            Err(RefreshError::NotAuthenticated("Claude Code"))
+        ));
+
+        storage
+            .save("claude-code", "access", None, Some(0), None, None)
+            .unwrap();
+        let err = access_token(&storage).await.unwrap_err();
+        assert!(matches!(err, RefreshError::Reauthenticate { .. }));
+        assert!(err.to_string().contains("Reconnect it in Settings"));
+    }
+
+    #[tokio::test]
+    async fn test_expiring_token_without_refresh_token_still_used() {
+        let (_temp, db) = setup_test_db();
+        let storage = TokenStorage::new(&db);
+        storage
+            .save("claude-code", "access", None, Some(60), None, None)
+            .unwrap();
+
+        assert_eq!(access_token(&storage).await.unwrap(), "access");
+    }
+}
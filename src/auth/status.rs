@@ -0,0 +1,130 @@
+//! Connection state of each OAuth provider, for `spot auth status`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::Utc;
+
+use super::storage::{StoredTokens, TokenStorage, TokenStorageError};
+use super::OAuthProvider;
+use crate::db::Database;
+
+impl OAuthProvider {
+    /// Every provider, in display order.
+    pub const ALL: [OAuthProvider; 2] = [Self::ChatGpt, Self::ClaudeCode];
+}
+
+impl FromStr for OAuthProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "chatgpt" => Ok(Self::ChatGpt),
+            "claude-code" => Ok(Self::ClaudeCode),
+            _ => Err(format!(
+                "unknown provider '{}' (expected chatgpt or claude-code)",
+                s
+            )),
+        }
+    }
+}
+
+/// One provider's stored tokens, if it's connected.
+#[derive(Debug, Clone)]
+pub struct ProviderStatus {
+    pub provider: OAuthProvider,
+    pub tokens: Option<StoredTokens>,
+}
+
+/// The status of every provider.
+pub fn auth_status(db: &Database) -> Result<Vec<ProviderStatus>, TokenStorageError> {
+    let storage = TokenStorage::new(db);
+    OAuthProvider::ALL
+        .into_iter()
+        .map(|provider| {
+            Ok(ProviderStatus {
+                provider,
+                tokens: storage.load(provider.as_str())?,
+            })
+        })
+        .collect()
+}
+
+impl fmt::Display for ProviderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<12} ", self.provider.display_name())?;
+        let Some(tokens) = &self.tokens else {
+            return f.write_str("not connected");
+        };
+        f.write_str("connected")?;
+        let Some(expires_at) = tokens.expires_at else {
+            return Ok(());
+        };
+
+        let remaining = expires_at - Utc::now().timestamp();
+        if remaining > 0 {
+            write!(f, ", token expires in {}", format_span(remaining))?;
+        } else {
+            write!(f, ", token expired {} ago", format_span(-remaining))?;
+        }
+        if tokens.needs_refresh() {
+            if tokens.refresh_token.is_some() {
+                f.write_str(" (renewed on next use)")?;
+            } else if tokens.is_expired() {
+                write!(
+                    f,
+                    " (run `spot auth login {}` to reconnect)",
+                    self.provider.as_str()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A rough length of time, like "3 hours".
+fn format_span(secs: i64) -> String {
+    let (count, unit) = match secs {
+        s if s < 60 => (s, "second"),
+        s if s < 3600 => (s / 60, "min"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s => (s / 86400, "day"),
+    };
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_provider_from_str() {
+        assert_eq!("chatgpt".parse(), Ok(OAuthProvider::ChatGpt));
+        assert_eq!("Claude_Code".parse(), Ok(OAuthProvider::ClaudeCode));
+        assert!("gemini".parse::<OAuthProvider>().is_err());
+    }
+
+    #[test]
+    fn test_status_lines() {
+        let temp = TempDir::new().unwrap();
+        let db = Database::open_at(temp.path().join("test.db")).unwrap();
+        db.migrate().unwrap();
+        TokenStorage::new(&db)
+            .save("chatgpt", "access", Some("refresh"), Some(5400), None, None)
+            .unwrap();
+
+        let status = auth_status(&db).unwrap();
+        assert!(status[0]
+            .to_string()
+            .starts_with("ChatGPT      connected, token expires in 1 hour"));
+        assert_eq!(status[1].to_string(), "Claude Code  not connected");
+
+        TokenStorage::new(&db)
+            .save("claude-code", "access", None, Some(0), None, None)
+            .unwrap();
+        let line = auth_status(&db).unwrap()[1].to_string();
+        assert!(line.contains("expired"));
+        assert!(line.ends_with("(run `spot auth login claude-code` to reconnect)"));
+    }
+}
@@ -44,6 +44,11 @@ impl StoredTokens {
             false
         }
     }
+
+    /// Check if the token should be refreshed before it's used.
+    pub fn needs_refresh(&self) -> bool {
+        self.expires_within(super::refresh::REFRESH_MARGIN_SECS)
+    }
 }
 
 /// Token storage operations.
@@ -57,7 +62,7 @@ impl<'a> TokenStorage<'a> {
         Self { db }
     }
 
-    /// Save tokens for a provider.
+    /// Save tokens for a provider, sealed when encryption is on.
     pub fn save(
         &self,
         provider: &str,
@@ -68,6 +73,8 @@ impl<'a> TokenStorage<'a> {
         extra_data: Option<&str>,
     ) -> Result<(), TokenStorageError> {
         let expires_at = expires_in.map(|secs| Utc::now().timestamp() + secs as i64);
+        let access_token = self.db.seal(access_token)?;
+        let refresh_token = refresh_token.map(|token| self.db.seal(token)).transpose()?;
 
         self.db.conn().execute(
             "INSERT INTO oauth_tokens (provider, access_token, refresh_token, expires_at, account_id, extra_data, updated_at)
@@ -112,7 +119,14 @@ impl<'a> TokenStorage<'a> {
         );
 
         match result {
-            Ok(tokens) => Ok(Some(tokens)),
+            Ok(mut tokens) => {
+                tokens.access_token = self.db.unseal(tokens.access_token)?;
+                tokens.refresh_token = tokens
+                    .refresh_token
+                    .map(|token| self.db.unseal(token))
+                    .transpose()?;
+                Ok(Some(tokens))
+            }
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(TokenStorageError::Database(e)),
         }
@@ -0,0 +1,225 @@
+//! Batch mode for `spot --batch <file>`.
+//!
+//! Runs the current agent over a list of prompts, one after another. Each
+//! prompt starts a fresh session unless `--batch-shared` is given, in which
+//! case the history carries over. A failed prompt is recorded and the batch
+//! moves on, so one bad run never costs the rest.
+
+use std::path::Path;
+use std::time::Instant;
+
+use serde::Serialize;
+use serdes_ai_core::ModelRequest;
+
+use crate::headless::{Headless, HeadlessOptions};
+use crate::messaging::MessageBus;
+use crate::tokens::estimate_tokens;
+
+/// How a batch is run.
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    /// Carry the conversation from one prompt into the next
+    pub shared_session: bool,
+    /// Sampling override and sandbox, applied to every prompt
+    pub run: HeadlessOptions,
+}
+
+/// The outcome of one prompt in a batch.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchEntry {
+    /// Position in the input, starting at 0
+    pub index: usize,
+    pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub duration_ms: u64,
+    /// Estimated tokens in the message history after the run
+    pub token_estimate: usize,
+}
+
+/// Everything a batch produced, written as JSON.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchReport {
+    pub agent: String,
+    pub model: String,
+    pub shared_session: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BatchEntry>,
+}
+
+impl BatchReport {
+    fn push(&mut self, entry: BatchEntry) {
+        if entry.error.is_some() {
+            self.failed += 1;
+        } else {
+            self.succeeded += 1;
+        }
+        self.results.push(entry);
+    }
+}
+
+/// Parse a batch file: a JSON array of strings, or one prompt per line.
+///
+/// In the line form, blank lines are skipped and a line holding a JSON
+/// string is decoded, so prompts can contain escaped newlines.
+pub fn parse_prompts(content: &str) -> anyhow::Result<Vec<String>> {
+    let trimmed = content.trim();
+    let prompts: Vec<String> = if trimmed.starts_with('[') {
+        serde_json::from_str(trimmed)
+            .map_err(|e| anyhow::anyhow!("Invalid JSON prompt list: {}", e))?
+    } else {
+        trimmed
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                if line.starts_with('"') {
+                    serde_json::from_str(line).unwrap_or_else(|_| line.to_string())
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect()
+    };
+
+    let prompts: Vec<String> = prompts
+        .into_iter()
+        .filter(|prompt| !prompt.trim().is_empty())
+        .collect();
+    if prompts.is_empty() {
+        anyhow::bail!("No prompts found in batch file");
+    }
+    Ok(prompts)
+}
+
+/// Run every prompt in `path` with the current agent.
+///
+/// Progress goes to stderr; the report is returned for the caller to write.
+pub async fn run_batch(path: &Path, options: BatchOptions) -> anyhow::Result<BatchReport> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let prompts = parse_prompts(&content)?;
+
+    let bus = MessageBus::new();
+    let env = Headless::start(bus.sender(), options.run).await?;
+
+    let agent_name = env.agents.current_name();
+    let agent = env
+        .agents
+        .get(&agent_name)
+        .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_name))?;
+    let model = env.model_for(&agent_name);
+
+    let mut report = BatchReport {
+        agent: agent_name.clone(),
+        model: model.clone(),
+        shared_session: options.shared_session,
+        ..Default::default()
+    };
+    let mut history: Vec<ModelRequest> = Vec::new();
+    let total = prompts.len();
+
+    for (index, prompt) in prompts.into_iter().enumerate() {
+        let executor = env.executor().with_bus(bus.sender());
+        let message_history =
+            (options.shared_session && !history.is_empty()).then(|| history.clone());
+
+        let started = Instant::now();
+        let result = executor
+            .execute(
+                agent,
+                &model,
+                &prompt,
+                message_history,
+                &env.tool_registry,
+                &env.mcp_manager,
+            )
+            .await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let mut entry = BatchEntry {
+            index,
+            prompt,
+            duration_ms,
+            ..Default::default()
+        };
+        match result {
+            Ok(result) => {
+                entry.token_estimate = estimate_tokens(&result.messages);
+                entry.output = Some(result.output);
+                entry.run_id = Some(result.run_id);
+                if options.shared_session {
+                    history = result.messages;
+                }
+                eprintln!("[{}/{}] ok ({} ms)", index + 1, total, duration_ms);
+            }
+            Err(e) => {
+                eprintln!("[{}/{}] failed: {}", index + 1, total, e);
+                entry.error = Some(e.to_string());
+            }
+        }
+        report.push(entry);
+    }
+
+    env.shutdown().await;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prompts_json_array() {
+        let prompts = parse_prompts(r#"["first", "second\nline", "  "]"#).unwrap();
+        assert_eq!(prompts, vec!["first", "second\nline"]);
+    }
+
+    #[test]
+    fn test_parse_prompts_lines() {
+        let content = "explain main.rs\n\n  list the tests  \n\"multi\\nline\"\n";
+        let prompts = parse_prompts(content).unwrap();
+        assert_eq!(
+            prompts,
+            vec!["explain main.rs", "list the tests", "multi\nline"]
+        );
+    }
+
+    #[test]
+    fn test_parse_prompts_rejects_empty_and_bad_json() {
+        assert!(parse_prompts("\n  \n").is_err());
+        assert!(parse_prompts("[\"unterminated").is_err());
+        assert!(parse_prompts("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_report_counts_failures_without_stopping() {
+        let mut report = BatchReport::default();
+        report.push(BatchEntry {
+            index: 0,
+            prompt: "a".to_string(),
+            output: Some("done".to_string()),
+            ..Default::default()
+        });
+        report.push(BatchEntry {
+            index: 1,
+            prompt: "b".to_string(),
+            error: Some("model unavailable".to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(report.succeeded, 1);
+        assert_eq!(report.failed, 1);
+
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["results"][0]["output"], "done");
+        assert!(value["results"][0].get("error").is_none());
+        assert_eq!(value["results"][1]["error"], "model unavailable");
+        assert!(value["results"][1].get("output").is_none());
+    }
+}
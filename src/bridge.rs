@@ -0,0 +1,180 @@
+//! Headless bridge mode for `spot --bridge`.
+//!
+//! Serves one interactive session over stdio for editor integrations:
+//! [`BridgeCommand`]s are read from stdin and every bus message is written
+//! to stdout as a [`BridgeEvent`](crate::messaging::BridgeEvent) line, after
+//! the handshake. Logs go to stderr so stdout stays pure NDJSON.
+//! `--temperature`/`--top-p`, `--sandbox` and `--no-tools` apply to every run
+//! in the session.
+
+use futures::future::{FutureExt, LocalBoxFuture};
+use serdes_ai_core::ModelRequest;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::agents::{CancelToken, ExecuteContext, ExecutorError, ExecutorResult};
+use crate::headless::{Headless, HeadlessOptions};
+use crate::messaging::{
+    BridgeCommand, BridgeRenderer, Message, MessageBus, MessageReceiver, MessageRenderer,
+};
+use crate::tools::{expand_file_references, ExpandedPrompt};
+
+/// An agent run in progress, with the token that cancels it.
+struct ActiveRun<'a> {
+    cancel: CancelToken,
+    run: LocalBoxFuture<'a, Result<ExecutorResult, ExecutorError>>,
+}
+
+/// Run bridge mode until stdin closes, applying `options` to every run.
+pub async fn run_bridge_mode(options: HeadlessOptions) -> anyhow::Result<()> {
+    let bus = MessageBus::new();
+    let mut receiver = bus.subscribe();
+    let sender = bus.sender();
+    let mut renderer = BridgeRenderer::new();
+    renderer.start()?;
+
+    let env = Headless::start(sender.clone(), options).await?;
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut history: Vec<ModelRequest> = Vec::new();
+    let mut active: Option<ActiveRun<'_>> = None;
+
+    loop {
+        tokio::select! {
+            result = async { (&mut active.as_mut().expect("guarded by is_some").run).await },
+                if active.is_some() =>
+            {
+                active = None;
+                match result {
+                    Ok(result) => {
+                        if !result.messages.is_empty() {
+                            history = result.messages;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = sender.send(Message::error(e.to_string()));
+                    }
+                }
+            }
+            msg = receiver.recv() => {
+                if let Ok(msg) = msg {
+                    renderer.render(&msg)?;
+                }
+            }
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let command = match BridgeCommand::decode(&line) {
+                    Ok(command) => command,
+                    Err(e) => {
+                        let _ = sender.send(Message::error(e.to_string()));
+                        continue;
+                    }
+                };
+
+                match command {
+                    BridgeCommand::Prompt { .. } if active.is_some() => {
+                        let _ = sender.send(Message::error(
+                            "A run is already in progress; cancel it first",
+                        ));
+                    }
+                    BridgeCommand::Prompt { text } => {
+                        let agent_name = env.agents.current_name();
+                        let Some(agent) = env.agents.get(&agent_name) else {
+                            let _ = sender.send(Message::error(format!(
+                                "Agent not found: {}",
+                                agent_name
+                            )));
+                            continue;
+                        };
+
+                        let model = env.model_for(&agent_name);
+                        let expanded = match std::env::current_dir() {
+                            Ok(cwd) => expand_file_references(&text, &cwd),
+                            Err(_) => ExpandedPrompt {
+                                prompt: text,
+                                ..Default::default()
+                            },
+                        };
+                        for (reference, reason) in &expanded.failed {
+                            sender.warning(format!("Couldn't read @{}: {}", reference, reason));
+                        }
+                        if !expanded.images.is_empty() && !env.supports_vision(&model) {
+                            sender.error(format!(
+                                "Model '{}' doesn't support images; pick a vision model to use @image: references",
+                                model
+                            ));
+                            continue;
+                        }
+
+                        let cancel = CancelToken::new();
+                        let executor = env
+                            .executor()
+                            .with_bus(sender.clone())
+                            .with_cancellation(cancel.clone());
+                        let message_history = (!history.is_empty()).then(|| history.clone());
+                        let context = ExecuteContext {
+                            tool_registry: &env.tool_registry,
+                            mcp_manager: &env.mcp_manager,
+                        };
+
+                        let run = async move {
+                            executor
+                                .execute_with_images(
+                                    agent,
+                                    &model,
+                                    &expanded.prompt,
+                                    &expanded.images,
+                                    message_history,
+                                    &context,
+                                )
+                                .await
+                        }
+                        .boxed_local();
+                        active = Some(ActiveRun { cancel, run });
+                    }
+                    BridgeCommand::Cancel => match active.take() {
+                        // Dropping the run stops streaming; the token stops in-flight MCP calls
+                        Some(run) => {
+                            run.cancel.cancel();
+                            let _ = sender.send(Message::warning("Run cancelled"));
+                        }
+                        None => {
+                            let _ = sender.send(Message::warning("No run in progress"));
+                        }
+                    },
+                    BridgeCommand::SwitchAgent { name } => match env.agents.switch(&name) {
+                        Ok(()) => {
+                            let _ = sender.send(Message::success(format!(
+                                "Switched to agent {}",
+                                name
+                            )));
+                        }
+                        Err(e) => {
+                            let _ = sender.send(Message::error(e.to_string()));
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    if let Some(run) = active.take() {
+        run.cancel.cancel();
+    }
+    drain(&mut receiver, &mut renderer)?;
+    env.shutdown().await;
+    Ok(())
+}
+
+/// Write any messages still queued on the bus.
+fn drain(receiver: &mut MessageReceiver, renderer: &mut BridgeRenderer) -> std::io::Result<()> {
+    while let Ok(Some(msg)) = receiver.try_recv() {
+        renderer.render(&msg)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Images from the system clipboard, for `spot --paste-image`.
+//!
+//! The clipboard hands back raw RGBA pixels; they're scaled down like GUI
+//! attachments and encoded as PNG, ready for `execute_with_images`.
+
+use std::io::Cursor;
+
+use image::{imageops::FilterType, DynamicImage, ImageFormat, RgbaImage};
+use thiserror::Error;
+
+/// Longest side of a pasted image, matching GUI attachments.
+const MAX_DIMENSION: u32 = 1000;
+
+#[derive(Debug, Error)]
+pub enum ClipboardError {
+    #[error("Clipboard is not available: {0}")]
+    Unavailable(String),
+    #[error("No image on the clipboard")]
+    NoImage,
+    #[error("Failed to convert the clipboard image: {0}")]
+    Convert(String),
+}
+
+/// The image on the clipboard, as PNG bytes.
+pub fn paste_image() -> Result<Vec<u8>, ClipboardError> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| ClipboardError::Unavailable(e.to_string()))?;
+    let image = clipboard.get_image().map_err(|e| match e {
+        arboard::Error::ContentNotAvailable | arboard::Error::ConversionFailure => {
+            ClipboardError::NoImage
+        }
+        other => ClipboardError::Unavailable(other.to_string()),
+    })?;
+    rgba_to_png(
+        image.width as u32,
+        image.height as u32,
+        image.bytes.into_owned(),
+    )
+}
+
+/// Encode RGBA pixels as PNG, scaled to fit [`MAX_DIMENSION`].
+fn rgba_to_png(width: u32, height: u32, pixels: Vec<u8>) -> Result<Vec<u8>, ClipboardError> {
+    let image = RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| ClipboardError::Convert("pixel data doesn't match its size".into()))?;
+    let mut image = DynamicImage::ImageRgba8(image);
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        image = image.resize(MAX_DIMENSION, MAX_DIMENSION, FilterType::Lanczos3);
+    }
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| ClipboardError::Convert(e.to_string()))?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgba_to_png_scales_large_images() {
+        let png = rgba_to_png(2000, 500, vec![255; 2000 * 500 * 4]).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (1000, 250));
+
+        let png = rgba_to_png(2, 2, vec![0; 16]).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn test_rgba_to_png_rejects_short_pixel_data() {
+        assert!(matches!(
+            rgba_to_png(4, 4, vec![0; 8]),
+            Err(ClipboardError::Convert(_))
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! One-time migration of legacy file-based config into the database.
+//!
+//! Older versions stored settings as flat JSON objects under `~/.stockpot/`
+//! (`config.json` or `settings.json`). Settings now live in SQLite, so on
+//! startup (or via `spot config migrate`) we import any such files through
+//! [`Settings`] and archive the originals with a `.migrated` suffix.
+//!
+//! Nested objects are flattened into dotted keys, so a legacy
+//! `{"agent_pin": {"stockpot": "gpt-4o"}}` becomes `agent_pin.stockpot`.
+//! Keys that already exist in the database are left untouched, which makes
+//! the migration safe to re-run.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+use super::settings::{Settings, SettingsError};
+use crate::db::Database;
+
+/// Legacy config file names, in import order.
+const LEGACY_CONFIG_FILES: &[&str] = &["config.json", "settings.json"];
+
+/// Suffix appended to legacy files once imported.
+const ARCHIVE_SUFFIX: &str = "migrated";
+
+#[derive(Debug, Error)]
+pub enum MigrateError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("{0} does not contain a JSON object")]
+    NotAnObject(PathBuf),
+    #[error(transparent)]
+    Settings(#[from] SettingsError),
+}
+
+/// Summary of what a migration run did.
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Legacy files that were imported and archived.
+    pub files: Vec<PathBuf>,
+    /// Setting keys written to the database.
+    pub imported: Vec<String>,
+    /// Setting keys skipped because the database already had a value.
+    pub skipped: Vec<String>,
+}
+
+impl MigrationReport {
+    /// Whether nothing was found to migrate.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// Default directory holding legacy config files (`~/.stockpot`).
+pub fn legacy_config_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".stockpot")
+}
+
+/// Import legacy config files from `dir` into the database and archive them.
+pub fn migrate_legacy_config(db: &Database, dir: &Path) -> Result<MigrationReport, MigrateError> {
+    let settings = Settings::new(db);
+    let mut report = MigrationReport::default();
+
+    for file_name in LEGACY_CONFIG_FILES {
+        let path = dir.join(file_name);
+        if !path.is_file() {
+            continue;
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let value: JsonValue =
+            serde_json::from_str(&content).map_err(|source| MigrateError::Parse {
+                path: path.clone(),
+                source,
+            })?;
+        if !value.is_object() {
+            return Err(MigrateError::NotAnObject(path));
+        }
+
+        let mut pairs = Vec::new();
+        flatten_json("", &value, &mut pairs);
+
+        for (key, value) in pairs {
+            if settings.get(&key)?.is_some() {
+                report.skipped.push(key);
+                continue;
+            }
+            settings.set(&key, &value)?;
+            report.imported.push(key);
+        }
+
+        let mut archived = path.clone().into_os_string();
+        archived.push(".");
+        archived.push(ARCHIVE_SUFFIX);
+        fs::rename(&path, &archived)?;
+
+        tracing::info!(file = %path.display(), "Migrated legacy config file");
+        report.files.push(path);
+    }
+
+    Ok(report)
+}
+
+/// Flatten a JSON value into `(dotted.key, value)` setting pairs.
+fn flatten_json(prefix: &str, value: &JsonValue, out: &mut Vec<(String, String)>) {
+    match value {
+        JsonValue::Object(map) => {
+            for (key, child) in map {
+                let full_key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_json(&full_key, child, out);
+            }
+        }
+        JsonValue::Null => {}
+        JsonValue::String(s) => out.push((prefix.to_string(), s.clone())),
+        // Lists are stored comma-separated, matching `agent_mcp.*` settings
+        JsonValue::Array(items) => {
+            let joined = items
+                .iter()
+                .map(|item| match item {
+                    JsonValue::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            out.push((prefix.to_string(), joined));
+        }
+        other => out.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    #[test]
+    fn test_migrate_imports_legacy_files_into_db() {
+        let (_temp, db) = setup_test_db();
+        let legacy = TempDir::new().unwrap();
+        fs::write(
+            legacy.path().join("config.json"),
+            r#"{
+                "model": "claude-sonnet-4",
+                "yolo_mode": true,
+                "agent_pin": { "planning": "gpt-4.1" },
+                "agent_mcp": { "stockpot": ["filesystem", "github"] }
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            legacy.path().join("settings.json"),
+            r#"{ "owner_name": "Tony" }"#,
+        )
+        .unwrap();
+
+        let report = migrate_legacy_config(&db, legacy.path()).unwrap();
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.imported.len(), 5);
+
+        let settings = Settings::new(&db);
+        assert_eq!(settings.model(), "claude-sonnet-4");
+        assert!(settings.yolo_mode());
+        assert_eq!(settings.owner_name(), "Tony");
+        assert_eq!(
+            settings.get_agent_pinned_model("planning"),
+            Some("gpt-4.1".to_string())
+        );
+        assert_eq!(
+            settings.get_agent_mcps("stockpot"),
+            vec!["filesystem".to_string(), "github".to_string()]
+        );
+
+        // Originals are archived
+        assert!(!legacy.path().join("config.json").exists());
+        assert!(legacy.path().join("config.json.migrated").exists());
+        assert!(legacy.path().join("settings.json.migrated").exists());
+    }
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let (_temp, db) = setup_test_db();
+        let legacy = TempDir::new().unwrap();
+        fs::write(
+            legacy.path().join("config.json"),
+            r#"{ "model": "gpt-4.1" }"#,
+        )
+        .unwrap();
+
+        let first = migrate_legacy_config(&db, legacy.path()).unwrap();
+        assert!(!first.is_empty());
+
+        let second = migrate_legacy_config(&db, legacy.path()).unwrap();
+        assert!(second.is_empty());
+        assert_eq!(Settings::new(&db).model(), "gpt-4.1");
+    }
+
+    #[test]
+    fn test_migrate_keeps_existing_db_values() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+        settings.set("model", "already-set").unwrap();
+
+        let legacy = TempDir::new().unwrap();
+        fs::write(
+            legacy.path().join("config.json"),
+            r#"{ "model": "legacy" }"#,
+        )
+        .unwrap();
+
+        let report = migrate_legacy_config(&db, legacy.path()).unwrap();
+        assert_eq!(report.skipped, vec!["model".to_string()]);
+        assert_eq!(settings.model(), "already-set");
+    }
+
+    #[test]
+    fn test_migrate_empty_dir_is_noop() {
+        let (_temp, db) = setup_test_db();
+        let legacy = TempDir::new().unwrap();
+
+        let report = migrate_legacy_config(&db, legacy.path()).unwrap();
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_invalid_json_leaves_file_in_place() {
+        let (_temp, db) = setup_test_db();
+        let legacy = TempDir::new().unwrap();
+        fs::write(legacy.path().join("config.json"), "not json").unwrap();
+
+        let result = migrate_legacy_config(&db, legacy.path());
+        assert!(matches!(result, Err(MigrateError::Parse { .. })));
+        assert!(legacy.path().join("config.json").exists());
+    }
+
+    #[test]
+    fn test_flatten_json_nested_and_scalars() {
+        let value = serde_json::json!({
+            "a": { "b": { "c": 1 } },
+            "flag": false,
+            "skip": null
+        });
+        let mut out = Vec::new();
+        flatten_json("", &value, &mut out);
+        out.sort();
+        assert_eq!(
+            out,
+            vec![
+                ("a.b.c".to_string(), "1".to_string()),
+                ("flag".to_string(), "false".to_string()),
+            ]
+        );
+    }
+}
@@ -1,5 +1,14 @@
 //! Configuration management.
 
+mod migrate;
+mod profiles;
+mod schema;
 mod settings;
 
-pub use settings::{PdfMode, Settings};
+pub use migrate::{legacy_config_dir, migrate_legacy_config, MigrateError, MigrationReport};
+pub use profiles::DEFAULT_PROFILE;
+pub use schema::{
+    default_value, mask_url_credentials, SettingDef, SettingEntry, SettingKind, MODEL_SETTINGS,
+    SETTINGS, SETTING_FAMILIES,
+};
+pub use settings::{PdfMode, Settings, SettingsError};
@@ -0,0 +1,244 @@
+//! Settings profiles.
+//!
+//! A profile is a named copy of the whole settings table: default model,
+//! agent pins, per-model settings, sandbox and shell rules. Activating one
+//! saves the current settings back into the profile that was active (or
+//! `default`, the first time) and swaps in the new one's, in a single
+//! transaction, so settings are never left half switched.
+//!
+//! Settings that describe the database and the machine rather than a way
+//! of working stay put: whether credentials are encrypted has to match how
+//! they're stored, and sessions stay where they were saved.
+
+use rusqlite::OptionalExtension;
+
+use super::settings::{Settings, SettingsError};
+use crate::db::ENCRYPT_SECRETS_KEY;
+
+/// Profile that holds the settings from before any profile was activated.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Settings profiles neither save nor replace.
+const UNPROFILED_KEYS: [&str; 2] = [ENCRYPT_SECRETS_KEY, "sessions_dir"];
+
+impl Settings<'_> {
+    /// The active profile, if one has been activated.
+    pub fn active_profile(&self) -> Result<Option<String>, SettingsError> {
+        Ok(self
+            .db()
+            .conn()
+            .query_row("SELECT name FROM profiles WHERE is_active = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    /// Names of all saved profiles, sorted.
+    pub fn list_profiles(&self) -> Result<Vec<String>, SettingsError> {
+        let mut stmt = self
+            .db()
+            .conn()
+            .prepare("SELECT name FROM profiles ORDER BY name")?;
+        let names = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(names)
+    }
+
+    /// Save the current settings as profile `name`, replacing what it held.
+    pub fn save_profile(&self, name: &str) -> Result<(), SettingsError> {
+        validate_profile_name(name)?;
+        let tx = self.db().conn().unchecked_transaction()?;
+        snapshot(&tx, name)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Switch to profile `name`.
+    ///
+    /// The current settings are saved into the active profile first, so
+    /// changes made since it was activated are kept with it.
+    pub fn activate_profile(&self, name: &str) -> Result<(), SettingsError> {
+        let current = self.active_profile()?;
+        if current.as_deref() == Some(name) {
+            return Ok(());
+        }
+        if !self.list_profiles()?.iter().any(|profile| profile == name) {
+            return Err(SettingsError::ProfileNotFound(name.to_string()));
+        }
+
+        let tx = self.db().conn().unchecked_transaction()?;
+        snapshot(&tx, current.as_deref().unwrap_or(DEFAULT_PROFILE))?;
+        tx.execute(
+            &format!(
+                "DELETE FROM settings WHERE key NOT IN {}",
+                unprofiled_keys()
+            ),
+            [],
+        )?;
+        tx.execute(
+            &format!(
+                "INSERT INTO settings (key, value, updated_at)
+                 SELECT key, value, unixepoch() FROM profile_settings
+                 WHERE profile = ? AND key NOT IN {}",
+                unprofiled_keys()
+            ),
+            [name],
+        )?;
+        tx.execute("UPDATE profiles SET is_active = (name = ?)", [name])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Delete a saved profile. The active profile can't be deleted.
+    pub fn delete_profile(&self, name: &str) -> Result<(), SettingsError> {
+        if self.active_profile()?.as_deref() == Some(name) {
+            return Err(SettingsError::ProfileActive(name.to_string()));
+        }
+        let deleted = self
+            .db()
+            .conn()
+            .execute("DELETE FROM profiles WHERE name = ?", [name])?;
+        if deleted == 0 {
+            return Err(SettingsError::ProfileNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Copy the settings table into profile `name`, creating it if needed.
+fn snapshot(conn: &rusqlite::Connection, name: &str) -> Result<(), SettingsError> {
+    conn.execute(
+        "INSERT INTO profiles (name) VALUES (?) ON CONFLICT(name) DO NOTHING",
+        [name],
+    )?;
+    conn.execute("DELETE FROM profile_settings WHERE profile = ?", [name])?;
+    conn.execute(
+        &format!(
+            "INSERT INTO profile_settings (profile, key, value)
+             SELECT ?, key, value FROM settings WHERE key NOT IN {}",
+            unprofiled_keys()
+        ),
+        [name],
+    )?;
+    Ok(())
+}
+
+/// [`UNPROFILED_KEYS`] as an SQL list.
+fn unprofiled_keys() -> String {
+    let quoted: Vec<String> = UNPROFILED_KEYS
+        .iter()
+        .map(|key| format!("'{}'", key))
+        .collect();
+    format!("({})", quoted.join(", "))
+}
+
+fn validate_profile_name(name: &str) -> Result<(), SettingsError> {
+    if name.is_empty() || name.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(SettingsError::InvalidProfileName(name.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_at(temp_dir.path().join("test.db")).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    #[test]
+    fn test_activate_profile_swaps_settings() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        settings.set("model", "local-llama").unwrap();
+        settings.set("agent_pin.planning", "local-llama").unwrap();
+        settings.save_profile("personal").unwrap();
+
+        settings.delete("agent_pin.planning").unwrap();
+        settings.set("model", "azure-gpt-4o").unwrap();
+        settings.set("sandbox_mode", "true").unwrap();
+        settings.save_profile("work").unwrap();
+        assert_eq!(settings.active_profile().unwrap(), None);
+
+        settings.activate_profile("personal").unwrap();
+        assert_eq!(
+            settings.active_profile().unwrap().as_deref(),
+            Some("personal")
+        );
+        assert_eq!(settings.model(), "local-llama");
+        assert!(!settings.sandbox_mode());
+        assert_eq!(
+            settings.get_agent_pinned_model("planning").as_deref(),
+            Some("local-llama")
+        );
+
+        // Changes stay with the profile they were made in
+        settings.set("show_reasoning", "true").unwrap();
+        settings.activate_profile("work").unwrap();
+        assert_eq!(settings.model(), "azure-gpt-4o");
+        assert!(settings.sandbox_mode());
+        assert!(!settings.show_reasoning());
+        assert_eq!(settings.get_agent_pinned_model("planning"), None);
+
+        settings.activate_profile("personal").unwrap();
+        assert!(settings.show_reasoning());
+
+        // The settings from before the first switch were kept as `default`
+        assert_eq!(
+            settings.list_profiles().unwrap(),
+            vec!["default", "personal", "work"]
+        );
+    }
+
+    #[test]
+    fn test_profiles_leave_storage_and_security_settings() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        settings.save_profile("work").unwrap();
+        settings.set(ENCRYPT_SECRETS_KEY, "true").unwrap();
+        settings.set("sessions_dir", "~/notes/sessions").unwrap();
+        settings.set("model", "local-llama").unwrap();
+
+        settings.activate_profile("work").unwrap();
+        assert!(settings.encrypt_secrets());
+        assert_eq!(settings.sessions_dir(), "~/notes/sessions");
+        assert_ne!(settings.model(), "local-llama");
+
+        settings.activate_profile(DEFAULT_PROFILE).unwrap();
+        assert_eq!(settings.model(), "local-llama");
+        assert!(settings.encrypt_secrets());
+    }
+
+    #[test]
+    fn test_profile_errors() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        assert!(matches!(
+            settings.activate_profile("missing"),
+            Err(SettingsError::ProfileNotFound(_))
+        ));
+        assert!(matches!(
+            settings.save_profile("has space"),
+            Err(SettingsError::InvalidProfileName(_))
+        ));
+
+        settings.save_profile("work").unwrap();
+        settings.activate_profile("work").unwrap();
+        assert!(matches!(
+            settings.delete_profile("work"),
+            Err(SettingsError::ProfileActive(_))
+        ));
+        settings.delete_profile("default").unwrap();
+        assert_eq!(settings.list_profiles().unwrap(), vec!["work"]);
+    }
+}
@@ -2,8 +2,9 @@
 
 use std::collections::HashMap;
 
+use super::schema;
 use crate::agents::UserMode;
-use crate::db::Database;
+use crate::db::{Database, ENCRYPT_SECRETS_KEY};
 use thiserror::Error;
 
 /// PDF processing mode for attachments
@@ -40,6 +41,27 @@ pub enum SettingsError {
     Database(#[from] rusqlite::Error),
     #[error("Setting not found: {0}")]
     NotFound(String),
+    #[error("Unknown setting: {key}{}", did_you_mean(.suggestion))]
+    UnknownKey {
+        key: String,
+        suggestion: Option<&'static str>,
+    },
+    #[error("Invalid value for {key}: {reason}")]
+    InvalidValue { key: String, reason: String },
+    #[error("Profile not found: {0}")]
+    ProfileNotFound(String),
+    #[error("Profile {0} is active; switch to another profile first")]
+    ProfileActive(String),
+    #[error("Invalid profile name: '{0}'")]
+    InvalidProfileName(String),
+    #[error("Failed to convert stored credentials: {0}")]
+    Secrets(String),
+}
+
+fn did_you_mean(suggestion: &Option<&str>) -> String {
+    suggestion
+        .map(|key| format!(" (did you mean {}?)", key))
+        .unwrap_or_default()
 }
 
 /// Settings manager backed by SQLite.
@@ -53,6 +75,11 @@ impl<'a> Settings<'a> {
         Self { db }
     }
 
+    /// The database the settings live in.
+    pub(super) fn db(&self) -> &'a Database {
+        self.db
+    }
+
     /// Get a setting value.
     pub fn get(&self, key: &str) -> Result<Option<String>, SettingsError> {
         let result: Result<String, _> =
@@ -123,45 +150,43 @@ impl<'a> Settings<'a> {
         Ok(settings)
     }
 
-    // Convenience accessors for common settings
-
-    /// Get the current model name.
-    pub fn model(&self) -> String {
-        self.get_or("model", "gpt-4o")
-    }
-
-    /// Get YOLO mode status.
-    pub fn yolo_mode(&self) -> bool {
-        self.get_bool("yolo_mode").unwrap_or(false)
-    }
-
-    /// Get the assistant name.
-    pub fn assistant_name(&self) -> String {
-        self.get_or("assistant_name", "Stockpot")
-    }
-
-    /// Get the owner name.
-    pub fn owner_name(&self) -> String {
-        self.get_or("owner_name", "Master")
+    /// Set a known setting, checking the key and value against the schema.
+    ///
+    /// Unknown keys and values the setting can't take are refused; the value
+    /// is stored normalized (`Yes` as `true`). Changing `encrypt_secrets`
+    /// also seals or opens the stored credentials, so they match it.
+    pub fn set_validated(&self, key: &str, value: &str) -> Result<(), SettingsError> {
+        let def = schema::lookup(key).ok_or_else(|| SettingsError::UnknownKey {
+            key: key.to_string(),
+            suggestion: schema::suggest(key),
+        })?;
+        let value = def
+            .kind
+            .validate(value)
+            .map_err(|reason| SettingsError::InvalidValue {
+                key: key.to_string(),
+                reason,
+            })?;
+        if key == ENCRYPT_SECRETS_KEY {
+            let converted = if value == "true" {
+                self.db.encrypt_secrets()
+            } else {
+                self.db.decrypt_secrets()
+            };
+            return converted
+                .map(|_| ())
+                .map_err(|e| SettingsError::Secrets(e.to_string()));
+        }
+        self.set(key, &value)
     }
 
-    /// Get the current user mode.
-    pub fn user_mode(&self) -> UserMode {
-        self.get_or("user_mode", "normal")
-            .parse()
-            .unwrap_or_default()
-    }
+    // Convenience accessors for common settings are generated in `schema`
 
     /// Set the user mode.
     pub fn set_user_mode(&self, mode: UserMode) -> Result<(), SettingsError> {
         self.set("user_mode", &mode.to_string())
     }
 
-    /// Get PDF processing mode (default: Image)
-    pub fn pdf_mode(&self) -> PdfMode {
-        self.get_or("pdf_mode", "image").parse().unwrap_or_default()
-    }
-
     /// Set PDF processing mode
     pub fn set_pdf_mode(&self, mode: PdfMode) -> Result<(), SettingsError> {
         self.set("pdf_mode", &mode.to_string())
@@ -300,6 +325,164 @@ impl<'a> Settings<'a> {
         }
         Ok(attachments)
     }
+
+    // Agent context file management
+
+    /// Build the settings key for an agent's context paths.
+    fn agent_context_key(agent_name: &str) -> String {
+        format!("agent_context.{}", agent_name)
+    }
+
+    /// Get the files/directories always given to an agent as context.
+    ///
+    /// Stored one path per line, since paths may contain commas.
+    pub fn get_agent_context_paths(&self, agent_name: &str) -> Vec<String> {
+        self.get(&Self::agent_context_key(agent_name))
+            .ok()
+            .flatten()
+            .map(|s| {
+                s.lines()
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Set the context paths for an agent.
+    pub fn set_agent_context_paths(
+        &self,
+        agent_name: &str,
+        paths: &[String],
+    ) -> Result<(), SettingsError> {
+        self.set(&Self::agent_context_key(agent_name), &paths.join("\n"))
+    }
+
+    /// Clear all context paths from an agent.
+    pub fn clear_agent_context_paths(&self, agent_name: &str) -> Result<(), SettingsError> {
+        self.delete(&Self::agent_context_key(agent_name))
+    }
+
+    // Tool quota management
+
+    /// Build the settings key for a tool's per-run call limit.
+    fn tool_quota_key(tool_name: &str) -> String {
+        format!("tool_quota.{}", tool_name)
+    }
+
+    /// Get the maximum calls per run allowed for a tool.
+    pub fn get_tool_quota(&self, tool_name: &str) -> Option<u32> {
+        self.get(&Self::tool_quota_key(tool_name))
+            .ok()
+            .flatten()
+            .and_then(|v| v.trim().parse().ok())
+    }
+
+    /// Set the maximum calls per run allowed for a tool.
+    pub fn set_tool_quota(&self, tool_name: &str, max_calls: u32) -> Result<(), SettingsError> {
+        self.set(&Self::tool_quota_key(tool_name), &max_calls.to_string())
+    }
+
+    /// Remove the call limit for a tool.
+    pub fn clear_tool_quota(&self, tool_name: &str) -> Result<(), SettingsError> {
+        self.delete(&Self::tool_quota_key(tool_name))
+    }
+
+    /// Get all tool->limit mappings. Unparseable values are skipped.
+    pub fn get_all_tool_quotas(&self) -> Result<HashMap<String, u32>, SettingsError> {
+        let prefix = "tool_quota.";
+        let mut stmt = self
+            .db
+            .conn()
+            .prepare("SELECT key, value FROM settings WHERE key LIKE ? ORDER BY key")?;
+        let pattern = format!("{}%", prefix);
+        let rows = stmt.query_map([pattern], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut quotas = HashMap::new();
+        for row in rows {
+            let (key, value) = row?;
+            if let Some(tool_name) = key.strip_prefix(prefix) {
+                if let Ok(limit) = value.trim().parse() {
+                    quotas.insert(tool_name.to_string(), limit);
+                }
+            }
+        }
+        Ok(quotas)
+    }
+
+    // Shell command rules
+
+    /// Build the settings key for a shell command list: `shell_denylist`
+    /// or `shell_allowlist`, optionally for one agent.
+    fn shell_list_key(list: &str, agent_name: Option<&str>) -> String {
+        match agent_name {
+            Some(agent_name) => format!("{}.{}", list, agent_name),
+            None => list.to_string(),
+        }
+    }
+
+    /// Read a comma-separated list of command patterns.
+    fn get_command_patterns(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .ok()
+            .flatten()
+            .map(|s| {
+                s.split(',')
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The global and per-agent patterns of a shell command list.
+    fn get_shell_list(&self, list: &str, agent_name: &str) -> Vec<String> {
+        let mut patterns = self.get_command_patterns(&Self::shell_list_key(list, None));
+        for pattern in self.get_command_patterns(&Self::shell_list_key(list, Some(agent_name))) {
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        }
+        patterns
+    }
+
+    /// Get the commands an agent may not run: `shell_denylist` plus
+    /// `shell_denylist.<agent>`.
+    pub fn get_shell_denylist(&self, agent_name: &str) -> Vec<String> {
+        self.get_shell_list("shell_denylist", agent_name)
+    }
+
+    /// Set denied commands globally (`None`) or for one agent.
+    pub fn set_shell_denylist(
+        &self,
+        agent_name: Option<&str>,
+        patterns: &[String],
+    ) -> Result<(), SettingsError> {
+        self.set(
+            &Self::shell_list_key("shell_denylist", agent_name),
+            &patterns.join(","),
+        )
+    }
+
+    /// Get the only commands an agent may run: `shell_allowlist` plus
+    /// `shell_allowlist.<agent>`. Empty means no restriction.
+    pub fn get_shell_allowlist(&self, agent_name: &str) -> Vec<String> {
+        self.get_shell_list("shell_allowlist", agent_name)
+    }
+
+    /// Set allowed commands globally (`None`) or for one agent.
+    pub fn set_shell_allowlist(
+        &self,
+        agent_name: Option<&str>,
+        patterns: &[String],
+    ) -> Result<(), SettingsError> {
+        self.set(
+            &Self::shell_list_key("shell_allowlist", agent_name),
+            &patterns.join(","),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -312,8 +495,10 @@ mod tests {
     //! - Boolean setting parsing
     //! - Default value fallbacks
     //! - Convenience accessors (model, yolo_mode, etc.)
+    //! - Schema-validated writes
     //! - Agent model pin management
     //! - Agent MCP attachment management
+    //! - Tool quota management
 
     use super::*;
     use tempfile::TempDir;
@@ -547,6 +732,46 @@ mod tests {
         assert!(!settings.get_bool("nonexistent").unwrap());
     }
 
+    // =========================================================================
+    // Validated Set Tests
+    // =========================================================================
+
+    #[test]
+    fn test_set_validated_normalizes_known_keys() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        settings.set_validated("show_reasoning", "Yes").unwrap();
+        assert_eq!(
+            settings.get("show_reasoning").unwrap().as_deref(),
+            Some("true")
+        );
+        assert!(settings.show_reasoning());
+
+        settings.set_validated("tool_quota.grep", "5").unwrap();
+        assert_eq!(settings.get_tool_quota("grep"), Some(5));
+    }
+
+    #[test]
+    fn test_set_validated_rejects_typos_and_bad_values() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        let err = settings
+            .set_validated("show_reasonign", "true")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown setting: show_reasonign (did you mean show_reasoning?)"
+        );
+
+        let err = settings
+            .set_validated("model_settings.gpt-4o.temperature", "warm")
+            .unwrap_err();
+        assert!(matches!(err, SettingsError::InvalidValue { .. }));
+        assert!(settings.list().unwrap().is_empty());
+    }
+
     // =========================================================================
     // Convenience Accessor Tests
     // =========================================================================
@@ -1294,4 +1519,109 @@ mod tests {
         // Should be sorted alphabetically
         assert_eq!(keys, vec!["alpha", "beta", "zebra"]);
     }
+
+    // =========================================================================
+    // Agent Context Path Tests
+    // =========================================================================
+
+    #[test]
+    fn test_agent_context_paths_roundtrip() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        assert!(settings.get_agent_context_paths("stockpot").is_empty());
+
+        let paths = vec!["src/lib.rs".to_string(), "docs/a,b.md".to_string()];
+        settings
+            .set_agent_context_paths("stockpot", &paths)
+            .unwrap();
+        assert_eq!(settings.get_agent_context_paths("stockpot"), paths);
+        assert!(settings.get_agent_context_paths("explore").is_empty());
+
+        settings.clear_agent_context_paths("stockpot").unwrap();
+        assert!(settings.get_agent_context_paths("stockpot").is_empty());
+    }
+
+    // =========================================================================
+    // Tool Quota Tests
+    // =========================================================================
+
+    #[test]
+    fn test_tool_quota_roundtrip() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        assert_eq!(settings.get_tool_quota("run_shell_command"), None);
+        settings.set_tool_quota("run_shell_command", 5).unwrap();
+        assert_eq!(settings.get_tool_quota("run_shell_command"), Some(5));
+
+        settings.clear_tool_quota("run_shell_command").unwrap();
+        assert_eq!(settings.get_tool_quota("run_shell_command"), None);
+    }
+
+    #[test]
+    fn test_get_all_tool_quotas_skips_invalid() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        settings.set_tool_quota("grep", 10).unwrap();
+        settings.set_tool_quota("edit_file", 3).unwrap();
+        settings.set("tool_quota.broken", "lots").unwrap();
+
+        let quotas = settings.get_all_tool_quotas().unwrap();
+        assert_eq!(quotas.len(), 2);
+        assert_eq!(quotas.get("grep"), Some(&10));
+        assert_eq!(quotas.get("edit_file"), Some(&3));
+    }
+
+    // =========================================================================
+    // Shell Command List Tests
+    // =========================================================================
+
+    #[test]
+    fn test_shell_denylist_merges_global_and_agent() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        assert!(settings.get_shell_denylist("reviewer").is_empty());
+
+        settings
+            .set_shell_denylist(None, &["curl".to_string()])
+            .unwrap();
+        settings
+            .set_shell_denylist(
+                Some("reviewer"),
+                &["rm".to_string(), "git push".to_string(), "curl".to_string()],
+            )
+            .unwrap();
+
+        assert_eq!(
+            settings.get_shell_denylist("reviewer"),
+            vec!["curl", "rm", "git push"]
+        );
+        assert_eq!(settings.get_shell_denylist("stockpot"), vec!["curl"]);
+        assert!(settings.get_shell_allowlist("reviewer").is_empty());
+    }
+
+    #[test]
+    fn test_shell_allowlist_roundtrip() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+
+        settings
+            .set("shell_allowlist", " cargo , ,git status")
+            .unwrap();
+        assert_eq!(
+            settings.get_shell_allowlist("stockpot"),
+            vec!["cargo", "git status"]
+        );
+
+        settings
+            .set_shell_allowlist(Some("stockpot"), &["ls".to_string()])
+            .unwrap();
+        assert_eq!(
+            settings.get_shell_allowlist("stockpot"),
+            vec!["cargo", "git status", "ls"]
+        );
+    }
 }
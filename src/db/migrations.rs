@@ -25,6 +25,11 @@ pub fn run_migrations(conn: &Connection) -> anyhow::Result<()> {
             "005_model_sources",
             include_str!("sql/005_model_sources.sql"),
         ),
+        ("006_profiles", include_str!("sql/006_profiles.sql")),
+        (
+            "007_model_streaming",
+            include_str!("sql/007_model_streaming.sql"),
+        ),
     ];
 
     for (name, sql) in migrations {
@@ -2,16 +2,24 @@
 
 mod migrations;
 mod schema;
+mod secrets;
 
 use rusqlite::Connection;
+use std::cell::RefCell;
 use std::path::PathBuf;
 
 pub use schema::*;
+pub use secrets::{is_sealed, SecretCipher, SecretError, SEALED_PREFIX};
+
+/// Setting that turns on encryption of stored credentials.
+pub const ENCRYPT_SECRETS_KEY: &str = "encrypt_secrets";
 
 /// Database connection wrapper.
 pub struct Database {
     conn: Connection,
     path: PathBuf,
+    /// Master key cipher, loaded from the keychain on first use.
+    cipher: RefCell<Option<SecretCipher>>,
 }
 
 impl Database {
@@ -44,7 +52,11 @@ impl Database {
         // Enable foreign keys
         conn.execute_batch("PRAGMA foreign_keys = ON;")?;
 
-        Ok(Self { conn, path })
+        Ok(Self {
+            conn,
+            path,
+            cipher: RefCell::new(None),
+        })
     }
 
     /// Get the default database path.
@@ -76,16 +88,154 @@ impl Database {
         &self.path
     }
 
+    // =========================================================================
+    // Secret Encryption
+    // =========================================================================
+
+    /// Whether credentials are encrypted before they're stored.
+    pub fn encrypts_secrets(&self) -> bool {
+        self.conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?",
+                [ENCRYPT_SECRETS_KEY],
+                |row| row.get::<_, String>(0),
+            )
+            .is_ok_and(|value| matches!(value.to_lowercase().as_str(), "true" | "1" | "yes" | "on"))
+    }
+
+    /// The master key cipher, from the keychain the first time.
+    fn cipher(&self) -> Result<SecretCipher, SecretError> {
+        if let Some(cipher) = self.cipher.borrow().as_ref() {
+            return Ok(cipher.clone());
+        }
+        let cipher = SecretCipher::from_keychain()?;
+        *self.cipher.borrow_mut() = Some(cipher.clone());
+        Ok(cipher)
+    }
+
+    /// A credential as it should be stored: sealed when encryption is on.
+    ///
+    /// Fails without a usable keychain rather than store the value in plain
+    /// text while `encrypt_secrets` says it's encrypted.
+    pub fn seal(&self, value: &str) -> Result<String, rusqlite::Error> {
+        if !self.encrypts_secrets() {
+            return Ok(value.to_string());
+        }
+        self.cipher()
+            .and_then(|cipher| cipher.encrypt(value))
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+
+    /// A stored credential in plain text. Values stored before encryption
+    /// was turned on are returned unchanged.
+    pub fn unseal(&self, stored: String) -> Result<String, rusqlite::Error> {
+        if !is_sealed(&stored) {
+            return Ok(stored);
+        }
+        self.cipher()
+            .and_then(|cipher| cipher.decrypt(&stored))
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })
+    }
+
+    /// Turn encryption on and seal every stored credential.
+    ///
+    /// Fails, changing nothing, when the OS keychain isn't available.
+    /// Returns how many values were sealed.
+    pub fn encrypt_secrets(&self) -> anyhow::Result<usize> {
+        let cipher = self.cipher()?;
+        self.rewrite_secrets(ENCRYPT_SECRETS_KEY, "true", |value| {
+            if is_sealed(value) {
+                Ok(None)
+            } else {
+                cipher.encrypt(value).map(Some)
+            }
+        })
+    }
+
+    /// Turn encryption off and store every credential in plain text again.
+    /// Returns how many values were opened.
+    pub fn decrypt_secrets(&self) -> anyhow::Result<usize> {
+        self.rewrite_secrets(ENCRYPT_SECRETS_KEY, "false", |value| {
+            if is_sealed(value) {
+                self.cipher()?.decrypt(value).map(Some)
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    /// Rewrite each stored credential with `convert` (`None` leaves it) and
+    /// set `key` to `value`, all in one transaction.
+    fn rewrite_secrets(
+        &self,
+        key: &str,
+        value: &str,
+        convert: impl Fn(&str) -> Result<Option<String>, SecretError>,
+    ) -> anyhow::Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut changed = 0;
+
+        let rows: Vec<(String, String)> = tx
+            .prepare("SELECT name, api_key FROM api_keys")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        for (name, api_key) in rows {
+            if let Some(new) = convert(&api_key)? {
+                tx.execute(
+                    "UPDATE api_keys SET api_key = ? WHERE name = ?",
+                    [&new, &name],
+                )?;
+                changed += 1;
+            }
+        }
+
+        let rows: Vec<(String, String, Option<String>)> = tx
+            .prepare("SELECT provider, access_token, refresh_token FROM oauth_tokens")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_, _>>()?;
+        for (provider, access, refresh) in rows {
+            if let Some(new) = convert(&access)? {
+                tx.execute(
+                    "UPDATE oauth_tokens SET access_token = ? WHERE provider = ?",
+                    [&new, &provider],
+                )?;
+                changed += 1;
+            }
+            if let Some(new) = refresh.as_deref().map(&convert).transpose()?.flatten() {
+                tx.execute(
+                    "UPDATE oauth_tokens SET refresh_token = ? WHERE provider = ?",
+                    [&new, &provider],
+                )?;
+                changed += 1;
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO settings (key, value, updated_at) VALUES (?, ?, unixepoch())
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            [key, value],
+        )?;
+        tx.commit()?;
+        Ok(changed)
+    }
+
     // =========================================================================
     // API Key Storage
     // =========================================================================
 
-    /// Save an API key to the database.
+    /// Save an API key to the database, sealed when encryption is on.
     pub fn save_api_key(&self, name: &str, api_key: &str) -> Result<(), rusqlite::Error> {
+        let api_key = self.seal(api_key)?;
         self.conn.execute(
             "INSERT INTO api_keys (name, api_key, updated_at) VALUES (?, ?, unixepoch())
              ON CONFLICT(name) DO UPDATE SET api_key = excluded.api_key, updated_at = excluded.updated_at",
-            [name, api_key],
+            [name, &api_key],
         )?;
         Ok(())
     }
@@ -97,7 +247,7 @@ impl Database {
             .prepare("SELECT api_key FROM api_keys WHERE name = ?")?;
         let result = stmt.query_row([name], |row| row.get(0));
         match result {
-            Ok(key) => Ok(Some(key)),
+            Ok(key) => Ok(Some(self.unseal(key)?)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e),
         }
@@ -134,6 +284,7 @@ mod tests {
     //! - Migration logic (including idempotency)
     //! - API key storage/retrieval/deletion
     //! - Helper methods (conn, path, default_path)
+    //! - Secret encryption (seal/unseal, encrypt/decrypt all)
 
     use super::*;
     use tempfile::TempDir;
@@ -551,4 +702,99 @@ mod tests {
             .unwrap();
         assert_eq!(fk_status, 1, "Foreign keys should be enabled");
     }
+
+    // =========================================================================
+    // Secret Encryption Tests
+    // =========================================================================
+
+    /// A test database with a fixed master key instead of the keychain.
+    fn setup_sealing_db() -> (TempDir, Database) {
+        let (temp, db) = setup_test_db();
+        *db.cipher.borrow_mut() = Some(SecretCipher::new(&[7; 32]));
+        (temp, db)
+    }
+
+    fn stored_api_key(db: &Database, name: &str) -> String {
+        db.conn()
+            .query_row(
+                "SELECT api_key FROM api_keys WHERE name = ?",
+                [name],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_api_key_sealed_when_encryption_on() {
+        let (_temp, db) = setup_sealing_db();
+        db.save_api_key("OLD_KEY", "sk-old").unwrap();
+        assert!(!db.encrypts_secrets());
+        assert_eq!(stored_api_key(&db, "OLD_KEY"), "sk-old");
+
+        // Existing plaintext values are converted
+        assert_eq!(db.encrypt_secrets().unwrap(), 1);
+        assert!(db.encrypts_secrets());
+        assert!(is_sealed(&stored_api_key(&db, "OLD_KEY")));
+        assert_eq!(
+            db.get_api_key("OLD_KEY").unwrap().as_deref(),
+            Some("sk-old")
+        );
+
+        db.save_api_key("NEW_KEY", "sk-new").unwrap();
+        assert!(is_sealed(&stored_api_key(&db, "NEW_KEY")));
+        assert_eq!(
+            db.get_api_key("NEW_KEY").unwrap().as_deref(),
+            Some("sk-new")
+        );
+
+        // Running it again leaves sealed values alone
+        assert_eq!(db.encrypt_secrets().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_decrypt_secrets_restores_plaintext() {
+        let (_temp, db) = setup_sealing_db();
+        db.encrypt_secrets().unwrap();
+        db.save_api_key("KEY", "sk-123").unwrap();
+        crate::auth::TokenStorage::new(&db)
+            .save("openai", "access", Some("refresh"), None, None, None)
+            .unwrap();
+
+        assert_eq!(db.decrypt_secrets().unwrap(), 3);
+        assert!(!db.encrypts_secrets());
+        assert_eq!(stored_api_key(&db, "KEY"), "sk-123");
+        let tokens = crate::auth::TokenStorage::new(&db)
+            .load("openai")
+            .unwrap()
+            .unwrap();
+        assert_eq!(tokens.access_token, "access");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh"));
+    }
+
+    #[test]
+    fn test_setting_encrypt_secrets_converts_stored_values() {
+        let (_temp, db) = setup_sealing_db();
+        db.save_api_key("KEY", "sk-123").unwrap();
+        let settings = crate::config::Settings::new(&db);
+
+        settings.set_validated(ENCRYPT_SECRETS_KEY, "yes").unwrap();
+        assert!(db.encrypts_secrets());
+        assert!(is_sealed(&stored_api_key(&db, "KEY")));
+
+        settings
+            .set_validated(ENCRYPT_SECRETS_KEY, "false")
+            .unwrap();
+        assert!(!db.encrypts_secrets());
+        assert_eq!(stored_api_key(&db, "KEY"), "sk-123");
+    }
+
+    #[test]
+    fn test_sealed_value_unreadable_with_wrong_key() {
+        let (_temp, db) = setup_sealing_db();
+        db.encrypt_secrets().unwrap();
+        db.save_api_key("KEY", "sk-123").unwrap();
+
+        *db.cipher.borrow_mut() = Some(SecretCipher::new(&[8; 32]));
+        assert!(db.get_api_key("KEY").is_err());
+    }
 }
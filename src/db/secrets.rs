@@ -0,0 +1,149 @@
+//! Encryption at rest for API keys and OAuth tokens.
+//!
+//! With `encrypt_secrets` on, credentials are sealed with ChaCha20-Poly1305
+//! before they're written. The 256-bit master key is created on first use
+//! and kept in the OS keychain, never in the database, so a copied or
+//! backed-up `spot.db` doesn't give the credentials away.
+//!
+//! Sealed values carry a prefix, and plaintext values from before
+//! encryption was turned on are still read as they are. Opening a sealed
+//! value only needs the keychain, whatever the setting says.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use thiserror::Error;
+
+/// Marks a stored value as sealed, and with which scheme.
+pub const SEALED_PREFIX: &str = "enc:v1:";
+
+const KEYCHAIN_SERVICE: &str = "stockpot";
+const KEYCHAIN_USER: &str = "database-master-key";
+
+/// Nonce length for ChaCha20-Poly1305.
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("OS keychain unavailable: {0}")]
+    Keychain(String),
+    #[error("Master key in the keychain is malformed")]
+    BadKey,
+    #[error("Failed to decrypt a stored secret (wrong or missing master key?)")]
+    Decrypt,
+    #[error("Failed to encrypt a secret")]
+    Encrypt,
+}
+
+/// Seals and opens stored credentials with the master key.
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl std::fmt::Debug for SecretCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretCipher(..)")
+    }
+}
+
+impl SecretCipher {
+    /// A cipher using `key` directly.
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+        }
+    }
+
+    /// A cipher using the master key from the OS keychain, creating and
+    /// storing one if there isn't one yet.
+    pub fn from_keychain() -> Result<Self, SecretError> {
+        let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER)
+            .map_err(|e| SecretError::Keychain(e.to_string()))?;
+        match entry.get_password() {
+            Ok(encoded) => {
+                let bytes = BASE64.decode(encoded).map_err(|_| SecretError::BadKey)?;
+                let key: [u8; 32] = bytes.try_into().map_err(|_| SecretError::BadKey)?;
+                Ok(Self::new(&key))
+            }
+            Err(keyring::Error::NoEntry) => {
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                entry
+                    .set_password(&BASE64.encode(key))
+                    .map_err(|e| SecretError::Keychain(e.to_string()))?;
+                Ok(Self {
+                    cipher: ChaCha20Poly1305::new(&key),
+                })
+            }
+            Err(e) => Err(SecretError::Keychain(e.to_string())),
+        }
+    }
+
+    /// Seal `plaintext` for storage.
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, SecretError> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| SecretError::Encrypt)?;
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+        Ok(format!("{}{}", SEALED_PREFIX, BASE64.encode(payload)))
+    }
+
+    /// Open a value sealed by [`encrypt`](Self::encrypt).
+    pub fn decrypt(&self, stored: &str) -> Result<String, SecretError> {
+        let encoded = stored
+            .strip_prefix(SEALED_PREFIX)
+            .ok_or(SecretError::Decrypt)?;
+        let payload = BASE64.decode(encoded).map_err(|_| SecretError::Decrypt)?;
+        if payload.len() < NONCE_LEN {
+            return Err(SecretError::Decrypt);
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| SecretError::Decrypt)?;
+        String::from_utf8(plaintext).map_err(|_| SecretError::Decrypt)
+    }
+}
+
+/// Whether a stored value is sealed.
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let cipher = SecretCipher::new(&[7; 32]);
+        let sealed = cipher.encrypt("sk-secret").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("sk-secret"));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), "sk-secret");
+
+        // A fresh nonce each time
+        assert_ne!(cipher.encrypt("sk-secret").unwrap(), sealed);
+    }
+
+    #[test]
+    fn test_wrong_key_or_tampering_fails() {
+        let sealed = SecretCipher::new(&[7; 32]).encrypt("sk-secret").unwrap();
+        assert!(matches!(
+            SecretCipher::new(&[8; 32]).decrypt(&sealed),
+            Err(SecretError::Decrypt)
+        ));
+
+        let cipher = SecretCipher::new(&[7; 32]);
+        let mut payload = BASE64.decode(&sealed[SEALED_PREFIX.len()..]).unwrap();
+        *payload.last_mut().unwrap() ^= 1;
+        let tampered = format!("{}{}", SEALED_PREFIX, BASE64.encode(payload));
+        assert!(cipher.decrypt(&tampered).is_err());
+        assert!(cipher.decrypt("plaintext").is_err());
+    }
+}
@@ -0,0 +1,14 @@
+-- Named bundles of settings that can be swapped in as a whole
+CREATE TABLE IF NOT EXISTS profiles (
+    name TEXT PRIMARY KEY,
+    is_active INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER DEFAULT (unixepoch())
+);
+
+-- The settings saved in each profile
+CREATE TABLE IF NOT EXISTS profile_settings (
+    profile TEXT NOT NULL REFERENCES profiles(name) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (profile, key)
+);
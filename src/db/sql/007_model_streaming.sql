@@ -0,0 +1,2 @@
+-- Track models whose endpoints can't stream responses
+ALTER TABLE models ADD COLUMN supports_streaming INTEGER DEFAULT 1;
@@ -0,0 +1,400 @@
+//! Environment diagnostics for `spot doctor`.
+//!
+//! Runs a series of independent checks (database, directories, credentials,
+//! OAuth tokens, MCP commands, terminal) and reports each as pass, warning
+//! or failure with a remediation hint.
+
+use std::fmt;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::auth::TokenStorage;
+use crate::db::Database;
+use crate::mcp::McpConfig;
+use crate::models::ModelRegistry;
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    fn symbol(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "✓",
+            CheckStatus::Warn => "!",
+            CheckStatus::Fail => "✗",
+        }
+    }
+}
+
+/// Result of a single diagnostic check.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// How to fix the problem, for warnings and failures.
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// All check results from a doctor run.
+#[derive(Debug, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl DoctorReport {
+    /// Whether any check failed outright.
+    pub fn has_failures(&self) -> bool {
+        self.checks.iter().any(|c| c.status == CheckStatus::Fail)
+    }
+
+    /// Find a check by name.
+    pub fn get(&self, name: &str) -> Option<&CheckResult> {
+        self.checks.iter().find(|c| c.name == name)
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(
+                f,
+                "{} {}: {}",
+                check.status.symbol(),
+                check.name,
+                check.detail
+            )?;
+            if let Some(hint) = &check.hint {
+                writeln!(f, "    → {}", hint)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run every check against the default locations.
+pub fn run_diagnostics() -> DoctorReport {
+    let mut report = DoctorReport::default();
+
+    let db = Database::open().and_then(|db| {
+        db.migrate()?;
+        Ok(db)
+    });
+    report.checks.push(check_database(&db));
+
+    let mut dirs = Vec::new();
+    if let Ok(db_path) = Database::default_path() {
+        if let Some(parent) = db_path.parent() {
+            dirs.push(parent.to_path_buf());
+        }
+    }
+    if let Ok(config_dir) = ModelRegistry::config_dir() {
+        dirs.push(config_dir);
+    }
+    report.checks.push(check_dirs_writable(&dirs));
+
+    if let Ok(db) = &db {
+        let registry = ModelRegistry::load_from_db(db).unwrap_or_default();
+        report.checks.push(check_credentials(db, &registry));
+        report.checks.push(check_oauth_tokens(db));
+    }
+
+    report
+        .checks
+        .push(check_mcp_commands(&McpConfig::load_or_default()));
+    report.checks.push(check_terminal());
+
+    report
+}
+
+/// Check that the database could be opened and migrated.
+pub fn check_database(db: &anyhow::Result<Database>) -> CheckResult {
+    const NAME: &str = "Database";
+    match db {
+        Ok(db) => CheckResult::pass(
+            NAME,
+            format!("{} is open and migrated", db.path().display()),
+        ),
+        Err(e) => CheckResult::fail(
+            NAME,
+            format!("cannot open or migrate: {}", e),
+            "Check permissions on the data directory, or move a corrupt spot.db aside",
+        ),
+    }
+}
+
+/// Check that each directory exists (or can be created) and is writable.
+pub fn check_dirs_writable(dirs: &[PathBuf]) -> CheckResult {
+    const NAME: &str = "Directories";
+    let unwritable: Vec<String> = dirs
+        .iter()
+        .filter(|dir| !is_writable(dir))
+        .map(|dir| dir.display().to_string())
+        .collect();
+
+    if unwritable.is_empty() {
+        CheckResult::pass(NAME, format!("{} directories writable", dirs.len()))
+    } else {
+        CheckResult::fail(
+            NAME,
+            format!("not writable: {}", unwritable.join(", ")),
+            "Fix ownership/permissions (e.g. chown -R $USER) on the listed directories",
+        )
+    }
+}
+
+fn is_writable(dir: &Path) -> bool {
+    if std::fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+    let probe = dir.join(".spot_doctor_probe");
+    let ok = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    ok
+}
+
+/// Check that at least one configured model has credentials.
+pub fn check_credentials(db: &Database, registry: &ModelRegistry) -> CheckResult {
+    const NAME: &str = "Credentials";
+    let available = registry.list_available(db);
+
+    if available.is_empty() {
+        CheckResult::fail(
+            NAME,
+            format!(
+                "none of {} configured models have credentials",
+                registry.len()
+            ),
+            "Set OPENAI_API_KEY or ANTHROPIC_API_KEY, add a key in Settings → Models, \
+             or sign in with an OAuth provider",
+        )
+    } else {
+        CheckResult::pass(
+            NAME,
+            format!("{} of {} models usable", available.len(), registry.len()),
+        )
+    }
+}
+
+/// Check stored OAuth tokens for expiry.
+pub fn check_oauth_tokens(db: &Database) -> CheckResult {
+    const NAME: &str = "OAuth tokens";
+    let storage = TokenStorage::new(db);
+    let providers = match storage.list_providers() {
+        Ok(p) => p,
+        Err(e) => {
+            return CheckResult::warn(
+                NAME,
+                format!("could not read tokens: {}", e),
+                "Re-run `spot` to apply database migrations",
+            )
+        }
+    };
+
+    if providers.is_empty() {
+        return CheckResult::pass(NAME, "no OAuth providers configured");
+    }
+
+    let stale: Vec<String> = providers
+        .iter()
+        .filter(|provider| match storage.load(provider) {
+            Ok(Some(tokens)) => tokens.is_expired() && tokens.refresh_token.is_none(),
+            _ => true,
+        })
+        .cloned()
+        .collect();
+
+    if stale.is_empty() {
+        CheckResult::pass(NAME, format!("valid for {}", providers.join(", ")))
+    } else {
+        CheckResult::warn(
+            NAME,
+            format!("expired without refresh token: {}", stale.join(", ")),
+            "Sign in again to the listed providers from Settings",
+        )
+    }
+}
+
+/// Check that enabled MCP server commands resolve on PATH.
+pub fn check_mcp_commands(config: &McpConfig) -> CheckResult {
+    const NAME: &str = "MCP servers";
+    let mut enabled: Vec<(&String, &crate::mcp::McpServerEntry)> =
+        config.enabled_servers().collect();
+    if enabled.is_empty() {
+        return CheckResult::pass(NAME, "no MCP servers enabled");
+    }
+    enabled.sort_by(|a, b| a.0.cmp(b.0));
+
+    let missing: Vec<String> = enabled
+        .iter()
+        .filter(|(_, entry)| {
+            // Resolve ${VAR} references the same way the manager does at spawn time
+            let command = entry
+                .interpolate(|_| None)
+                .map(|e| e.command)
+                .unwrap_or_else(|_| entry.command.clone());
+            which::which(command).is_err()
+        })
+        .map(|(name, entry)| format!("{} ({})", name, entry.command))
+        .collect();
+
+    if missing.is_empty() {
+        CheckResult::pass(NAME, format!("{} server commands found", enabled.len()))
+    } else {
+        CheckResult::fail(
+            NAME,
+            format!("command not found: {}", missing.join(", ")),
+            "Install the missing commands or fix `command` in ~/.stockpot/mcp_servers.json",
+        )
+    }
+}
+
+/// Check terminal capabilities for CLI output.
+pub fn check_terminal() -> CheckResult {
+    const NAME: &str = "Terminal";
+    let term = std::env::var("TERM").unwrap_or_default();
+
+    if !std::io::stdout().is_terminal() {
+        CheckResult::warn(
+            NAME,
+            "stdout is not a terminal",
+            "Colors and interactive prompts are disabled when output is piped",
+        )
+    } else if term == "dumb" {
+        CheckResult::warn(
+            NAME,
+            "TERM=dumb",
+            "Set TERM to a capable terminal type such as xterm-256color",
+        )
+    } else if std::env::var_os("NO_COLOR").is_some() {
+        CheckResult::pass(NAME, "interactive (NO_COLOR set)")
+    } else {
+        CheckResult::pass(NAME, "interactive")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::McpServerEntry;
+    use crate::models::{ModelConfig, ModelType};
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> (TempDir, Database) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::open_at(db_path).unwrap();
+        db.migrate().unwrap();
+        (temp_dir, db)
+    }
+
+    #[test]
+    fn test_check_credentials_reports_missing() {
+        let (_temp, db) = setup_test_db();
+        let mut registry = ModelRegistry::new();
+        registry.add(ModelConfig {
+            name: "claude-code-sonnet".to_string(),
+            model_type: ModelType::ClaudeCode,
+            ..Default::default()
+        });
+
+        let result = check_credentials(&db, &registry);
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.detail.contains("credentials"));
+        assert!(result.hint.is_some());
+    }
+
+    #[test]
+    fn test_check_credentials_passes_with_db_key() {
+        let (_temp, db) = setup_test_db();
+        db.save_api_key("OPENAI_API_KEY", "sk-test").unwrap();
+        let mut registry = ModelRegistry::new();
+        registry.add(ModelConfig::default());
+
+        let result = check_credentials(&db, &registry);
+        assert_eq!(result.status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_check_database_failure() {
+        let result = check_database(&Err(anyhow::anyhow!("disk full")));
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.detail.contains("disk full"));
+    }
+
+    #[test]
+    fn test_check_dirs_writable() {
+        let temp = TempDir::new().unwrap();
+        let result = check_dirs_writable(&[temp.path().join("nested")]);
+        assert_eq!(result.status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_check_oauth_tokens_none_configured() {
+        let (_temp, db) = setup_test_db();
+        assert_eq!(check_oauth_tokens(&db).status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_check_mcp_commands_missing() {
+        let mut config = McpConfig::new();
+        config.add_server(
+            "ghost",
+            McpServerEntry::new("nonexistent_command_that_does_not_exist_12345"),
+        );
+
+        let result = check_mcp_commands(&config);
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.detail.contains("ghost"));
+    }
+
+    #[test]
+    fn test_report_display_includes_hints() {
+        let report = DoctorReport {
+            checks: vec![
+                CheckResult::pass("Database", "ok"),
+                CheckResult::fail("Credentials", "none", "add a key"),
+            ],
+        };
+        assert!(report.has_failures());
+        let text = report.to_string();
+        assert!(text.contains("✓ Database: ok"));
+        assert!(text.contains("✗ Credentials: none"));
+        assert!(text.contains("→ add a key"));
+        assert!(report.get("Credentials").is_some());
+    }
+}
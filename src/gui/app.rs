@@ -16,10 +16,10 @@ use super::theme::Theme;
 use crate::agents::{AgentManager, UserMode};
 use crate::config::{PdfMode, Settings};
 use crate::db::Database;
-use crate::mcp::McpManager;
+use crate::mcp::{McpManager, RestartPolicy};
 use crate::messaging::MessageBus;
 use crate::models::ModelRegistry;
-use crate::tools::SpotToolRegistry;
+use crate::tools::{FileWatcher, SpotToolRegistry};
 
 actions!(
     stockpot_gui,
@@ -33,6 +33,7 @@ actions!(
         PrevAgent,
         CloseDialog,
         PasteAttachment,
+        CompletePath,
     ]
 );
 
@@ -74,6 +75,26 @@ pub struct ChatApp {
     pdf_mode: PdfMode,
     /// Whether to show agent reasoning in the UI
     show_reasoning: bool,
+    /// Tools turned off by name for each agent (`/tools disable`), or all
+    /// of them for plain chat (`/tools off`)
+    tool_toggles: crate::session::ToolToggles,
+    /// Temperature and top_p for this conversation's runs (`/temp`,
+    /// `/top-p`), never saved to the model's settings
+    sampling: crate::models::settings::SamplingOverride,
+    /// Read-only tools only for this session (`spot --sandbox`)
+    sandbox: bool,
+    /// Whether the `sandbox_mode` setting puts every run in the sandbox
+    sandbox_mode: bool,
+    /// Saving the conversation every few turns (`autosave_every_turns`)
+    autosave: crate::session::Autosave,
+    /// Messages `/compact` must keep (`/pin`), by history index
+    pinned_messages: std::collections::BTreeSet<usize>,
+    /// Limits for the conversation (`/budget`)
+    session_budget: crate::session::SessionBudget,
+    /// What the conversation's runs have used, checked against the budget
+    session_usage: crate::session::SessionUsage,
+    /// Stops the run in progress (Escape)
+    run_cancel: Option<crate::agents::CancelToken>,
     /// Color theme
     theme: Theme,
     /// Whether we're currently generating a response
@@ -88,6 +109,8 @@ pub struct ChatApp {
     model_registry: Arc<ModelRegistry>,
     /// Tool registry
     tool_registry: Arc<SpotToolRegistry>,
+    /// Watcher for files the agent read (`watch_read_files` setting)
+    file_watcher: Option<FileWatcher>,
     /// MCP manager
     mcp_manager: Arc<McpManager>,
     /// Message history for context
@@ -173,6 +196,8 @@ pub struct ChatApp {
     add_model_selected_model: Option<String>,
     /// Text input for API key in add model dialog
     add_model_api_key_input_entity: Option<Entity<InputState>>,
+    /// Add models without an API key (local OpenAI-compatible servers)
+    add_model_no_api_key: bool,
     add_model_loading: bool,
     add_model_error: Option<String>,
 
@@ -220,12 +245,25 @@ impl ChatApp {
         // Run migrations to ensure schema is up to date
         db.migrate().expect("Failed to run database migrations");
 
+        // Import any legacy file-based config (no-op once archived)
+        match crate::config::migrate_legacy_config(&db, &crate::config::legacy_config_dir()) {
+            Ok(report) if !report.is_empty() => tracing::info!(
+                files = report.files.len(),
+                imported = report.imported.len(),
+                skipped = report.skipped.len(),
+                "Migrated legacy config into the database"
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Legacy config migration failed: {}", e),
+        }
+
         // Load settings
         let settings = Settings::new(&db);
         let current_model = settings.model();
         let user_mode = settings.user_mode();
         let pdf_mode = settings.pdf_mode();
-        let show_reasoning = settings.get_bool("show_reasoning").unwrap_or(false);
+        let show_reasoning = settings.show_reasoning();
+        let sandbox_mode = settings.sandbox_mode();
 
         // Initialize model registry
         let model_registry = Arc::new(ModelRegistry::load_from_db(&db).unwrap_or_default());
@@ -242,16 +280,35 @@ impl ChatApp {
             .map(|info| (info.name.clone(), info.display_name.clone()))
             .collect();
 
+        // Initialize file watcher (opt-in)
+        let file_watcher = if settings.watch_read_files() {
+            FileWatcher::new()
+                .map_err(|e| tracing::warn!("Failed to start file watcher: {}", e))
+                .ok()
+        } else {
+            None
+        };
+
         // Initialize tool registry
-        let tool_registry = Arc::new(SpotToolRegistry::new());
+        let mut tool_registry = SpotToolRegistry::new()
+            .with_bus(message_bus.sender())
+            .with_shell_confinement(settings.confine_shell());
+        if let Some(watcher) = &file_watcher {
+            tool_registry = tool_registry.with_file_watcher(watcher.clone());
+        }
+        let tool_registry = Arc::new(tool_registry);
 
         // Initialize MCP manager
-        let mcp_manager = Arc::new(McpManager::new());
+        let mcp_manager = Arc::new(McpManager::new().with_api_keys_from_db(&db));
 
         // Create input state with auto-grow (1-3 lines, then scrollbar)
         let input_state = cx.new(|cx| {
             InputState::new(window, cx)
-                .placeholder("Type a message...")
+                .placeholder(if sandbox_mode {
+                    "Sandbox, read-only tools. Type a message..."
+                } else {
+                    "Type a message..."
+                })
                 .auto_grow(1, 3)
         });
 
@@ -274,6 +331,15 @@ impl ChatApp {
             user_mode,
             pdf_mode,
             show_reasoning,
+            tool_toggles: Default::default(),
+            sampling: Default::default(),
+            sandbox: false,
+            sandbox_mode,
+            autosave: Default::default(),
+            pinned_messages: Default::default(),
+            session_budget: Default::default(),
+            session_usage: Default::default(),
+            run_cancel: None,
             theme,
             is_generating: false,
             message_bus,
@@ -281,6 +347,7 @@ impl ChatApp {
             agents,
             model_registry,
             tool_registry,
+            file_watcher,
             mcp_manager,
             message_history: Vec::new(),
             context_tokens_used: 0,
@@ -330,6 +397,7 @@ impl ChatApp {
             add_model_models: Vec::new(),
             add_model_selected_model: None,
             add_model_api_key_input_entity: None,
+            add_model_no_api_key: false,
             add_model_loading: false,
             add_model_error: None,
 
@@ -419,6 +487,7 @@ impl ChatApp {
     /// Start MCP servers
     fn start_mcp_servers(&self, cx: &mut Context<Self>) {
         let mcp = self.mcp_manager.clone();
+        let bus = self.message_bus.sender();
         cx.spawn(
             async move |_this: WeakEntity<ChatApp>, _cx: &mut AsyncApp| {
                 let enabled_count = mcp.config().enabled_servers().count();
@@ -433,6 +502,9 @@ impl ChatApp {
                     let running = mcp.running_servers().await;
                     tracing::info!(servers = ?running, "MCP servers started successfully");
                 }
+
+                // Watch for crashed servers for the rest of the session
+                mcp.spawn_supervisor(RestartPolicy::default(), Some(bus));
             },
         )
         .detach();
@@ -787,6 +859,7 @@ impl Render for ChatApp {
             .on_action(cx.listener(Self::prev_agent))
             .on_action(cx.listener(Self::close_dialog))
             .on_action(cx.listener(Self::on_paste_attachment))
+            .on_action(cx.listener(Self::complete_path))
             // File drag and drop support
             .on_drop(cx.listener(|this, paths: &ExternalPaths, _window, cx| {
                 this.handle_file_drop(paths, cx);
@@ -826,6 +899,8 @@ pub fn register_keybindings(cx: &mut App) {
         // PasteAttachment checks for image in clipboard; propagates to Input if text
         KeyBinding::new("cmd-v", PasteAttachment, None),
         KeyBinding::new("ctrl-v", PasteAttachment, None),
+        // CompletePath completes a file path in the message input, else propagates
+        KeyBinding::new("tab", CompletePath, None),
         // Note: Enter key for Send is handled via InputEvent::PressEnter subscription
         // Note: Text input keybindings (copy, cut, paste, etc.) are handled by gpui-component Input internally
     ]);
@@ -3,14 +3,43 @@
 //! This module contains keyboard action handlers and conversation management:
 //! - `new_conversation()` - Start a fresh conversation
 //! - `quit()` - Handle quit action
-//! - `close_dialog()` - Close active dialogs
+//! - `close_dialog()` - Close active dialogs, else stop the run in progress
 //! - `on_send()` - Handle send action
+//! - `complete_path()` - Tab-complete a file path in the input
+//! - `edit_last_prompt()` - Take back the last prompt for revision (`/edit`)
+//! - `undo_last_run()` - Restore files changed by the last run (`/undo`)
+//! - `show_run_diff()` - Show what recent runs changed (`/diff [path]`)
+//! - `run_config_command()` - List, show or change settings (`/config`)
+//! - `compact_context()` - Drop older turns from the context (`/compact`)
+//! - `run_history_command()` - List, show or truncate the context (`/history`)
+//! - `run_pin_command()` - Keep a message through compaction (`/pin`, `/unpin`)
+//! - `run_budget_command()` - Show or set the conversation's budget (`/budget`)
+//! - `fork_session()` - Copy the conversation to a new session and continue there (`/fork`)
+//! - `resume_last_session()` - Continue the most recent session (`/resume`)
+//! - `search_sessions()` - Find saved sessions by content (`/search`)
+//! - `list_sessions()` - List saved sessions, optionally by tag (`/sessions`)
+//! - `tag_session()` - Tag or untag a saved session (`/tag`, `/untag`)
+//! - `pin_session()` - Keep a saved session from cleanup (`/pin-session`)
+//! - `run_tools_command()` - List or turn off the agent's tools (`/tools`)
+//! - `run_sampling_command()` - Override temperature or top_p (`/temp`, `/top-p`)
+//! - `enable_sandbox()` - Keep runs to read-only tools (`spot --sandbox`)
 //! - `next_agent()` / `prev_agent()` - Agent navigation
 //! - `set_current_agent()` - Set the active agent
 
-use gpui::{Context, Window};
+use gpui::{AsyncApp, Context, Focusable, WeakEntity, Window};
 
-use super::{ChatApp, CloseDialog, NewConversation, NextAgent, PrevAgent, Quit, Send};
+use crate::config::Settings;
+use crate::models::settings::SamplingOverride;
+use crate::session::{
+    compact_turns, describe_budget, list_history, oversized_history_warning, rewind_last_prompt,
+    show_message, transcript, truncate_history, BudgetCommand, CompactCommand, HistoryCommand,
+    PinCommand, SessionManager, ToolsCommand,
+};
+use crate::tools::{complete_input, UndoJournal};
+
+use super::{
+    ChatApp, CloseDialog, CompletePath, NewConversation, NextAgent, PrevAgent, Quit, Send,
+};
 
 impl ChatApp {
     /// Handle new conversation
@@ -22,6 +51,13 @@ impl ChatApp {
     ) {
         self.conversation.clear();
         self.message_history.clear();
+        self.autosave = Default::default();
+        self.pinned_messages.clear();
+        self.tool_toggles = Default::default();
+        self.show_tools_placeholder(window, cx);
+        self.sampling = Default::default();
+        self.session_budget = Default::default();
+        self.session_usage = Default::default();
         self.update_context_usage();
         self.active_agent_stack.clear();
         self.active_section_ids.clear();
@@ -65,6 +101,9 @@ impl ChatApp {
             self.show_settings = false;
             self.show_default_model_dropdown = false;
             self.default_model_dropdown_bounds = None;
+        } else if let Some(cancel) = &self.run_cancel {
+            // With no dialog open, Escape stops the run in progress
+            cancel.cancel();
         }
         cx.notify();
     }
@@ -74,6 +113,693 @@ impl ChatApp {
         self.send_message(window, cx);
     }
 
+    /// Complete the file path being typed in the message input.
+    ///
+    /// Anything else (another input focused, no path to complete) goes on
+    /// to the focused element.
+    pub(super) fn complete_path(
+        &mut self,
+        _: &CompletePath,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.input_state.focus_handle(cx).is_focused(window) {
+            cx.propagate();
+            return;
+        }
+
+        let line = self.input_state.read(cx).value().to_string();
+        let sessions = SessionManager::from_settings(&Settings::new(&self.db));
+        let completed = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| complete_input(&line, &cwd, &sessions));
+        match completed {
+            Some(completed) => {
+                self.input_state.update(cx, |state, cx| {
+                    state.set_value(completed, window, cx);
+                });
+                cx.notify();
+            }
+            None => cx.propagate(),
+        }
+    }
+
+    /// Load the last prompt back into the input and drop its reply.
+    ///
+    /// Sending the revised text then continues from the history before it.
+    pub(super) fn edit_last_prompt(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        // What was typed, not the prompt with @file contents expanded
+        let input = self.conversation.last_input().map(str::to_string);
+        let Some(prompt) = rewind_last_prompt(&mut self.message_history) else {
+            self.error_message = Some("Nothing to edit: no previous message".to_string());
+            cx.notify();
+            return;
+        };
+
+        let len = self.message_history.len();
+        self.pinned_messages.retain(|&i| i < len);
+        self.conversation.remove_last_exchange();
+        self.sync_messages_list_state();
+        self.update_context_usage();
+        self.error_message = None;
+        self.input_state.update(cx, |state, cx| {
+            state.set_value(input.unwrap_or(prompt), window, cx);
+        });
+        cx.notify();
+    }
+
+    /// Restore the files changed by the most recent run.
+    ///
+    /// The report is shown in the conversation but not sent to the model.
+    pub(super) fn undo_last_run(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        match UndoJournal::new().undo_last() {
+            Ok(report) => self.show_note(&report.to_string()),
+            Err(e) => self.error_message = Some(format!("Undo failed: {}", e)),
+        }
+        self.clear_input(window, cx);
+    }
+
+    /// Show a diff of the files recent runs changed, optionally just one.
+    pub(super) fn show_run_diff(
+        &mut self,
+        path: Option<String>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match UndoJournal::new().diff(path.as_deref().map(std::path::Path::new)) {
+            Ok(diff) if diff.is_empty() => self.show_note("No changes"),
+            Ok(diff) => self.show_note(&format!("```diff\n{}```", diff)),
+            Err(e) => self.error_message = Some(format!("Diff failed: {}", e)),
+        }
+        self.clear_input(window, cx);
+    }
+
+    /// `/config`, `/config get <key>` and `/config set <key> <value>`.
+    pub(super) fn run_config_command(
+        &mut self,
+        args: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let args = args.trim();
+        let (command, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        let rest = rest.trim();
+
+        let settings = Settings::new(&self.db);
+        let result = match (command, rest.split_once(char::is_whitespace)) {
+            ("", _) => settings.entries().map(|entries| {
+                let lines: Vec<String> = entries.iter().map(|e| e.to_string()).collect();
+                format!("```\n{}\n```", lines.join("\n"))
+            }),
+            ("get", None) if !rest.is_empty() => settings.entry(rest).map(|e| e.to_string()),
+            ("set", Some((key, value))) => settings
+                .set_validated(key, value.trim())
+                .map(|()| format!("Set {}", key)),
+            _ => {
+                self.error_message =
+                    Some("Usage: /config, /config get <key>, /config set <key> <value>".into());
+                return;
+            }
+        };
+        match result {
+            Ok(text) => {
+                self.reload_cached_settings();
+                self.show_tools_placeholder(window, cx);
+                self.show_note(&text);
+            }
+            Err(e) => self.error_message = Some(e.to_string()),
+        }
+        self.clear_input(window, cx);
+    }
+
+    /// `/compact` and `/compact keep <n>`: drop older turns from the history
+    /// sent to the model. The conversation on screen is left as is.
+    pub(super) fn compact_context(
+        &mut self,
+        args: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let command = match CompactCommand::parse(args) {
+            Ok(command) => command,
+            Err(usage) => {
+                self.error_message = Some(usage);
+                cx.notify();
+                return;
+            }
+        };
+
+        self.update_context_usage();
+        let report = compact_turns(
+            &mut self.message_history,
+            &mut self.pinned_messages,
+            command,
+            self.context_window_size,
+        );
+        self.update_context_usage();
+        self.show_note(&report.to_string());
+        self.clear_input(window, cx);
+    }
+
+    /// `/history`, `/history show <n>` and `/history truncate <n>`. Like
+    /// `/compact`, truncating changes what the model sees next, not the
+    /// conversation on screen.
+    pub(super) fn run_history_command(
+        &mut self,
+        args: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let result = HistoryCommand::parse(args).and_then(|command| match command {
+            HistoryCommand::List => Ok(list_history(&self.message_history)),
+            HistoryCommand::Show(n) => show_message(&self.message_history, n),
+            HistoryCommand::Truncate(n) => {
+                let dropped = truncate_history(&mut self.message_history, n)?;
+                self.pinned_messages.retain(|&i| i < n);
+                self.update_context_usage();
+                Ok(match dropped {
+                    0 => format!("Nothing after message {} to drop", n),
+                    1 => format!("Dropped 1 message after message {}", n),
+                    _ => format!("Dropped {} messages after message {}", dropped, n),
+                })
+            }
+        });
+        match result {
+            Ok(text) => {
+                self.show_note(&text);
+                self.clear_input(window, cx);
+            }
+            Err(e) => {
+                self.error_message = Some(e);
+                cx.notify();
+            }
+        }
+    }
+
+    /// `/pin <n>` and `/unpin <n>`: keep message `n` of the history,
+    /// counting from 0, when compacting.
+    pub(super) fn run_pin_command(
+        &mut self,
+        input: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let result = PinCommand::parse(input)
+            .ok_or_else(|| "Usage: /pin <n>, /unpin <n>".to_string())
+            .and_then(|command| {
+                command
+                    .apply(&mut self.pinned_messages, self.message_history.len())
+                    .map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(text) => {
+                self.save_session_pins();
+                self.show_note(&text);
+                self.clear_input(window, cx);
+            }
+            Err(e) => {
+                self.error_message = Some(e);
+                cx.notify();
+            }
+        }
+    }
+
+    /// `/budget`: show or change the token and time limits of the
+    /// conversation. A run stops once the conversation goes over them.
+    pub(super) fn run_budget_command(
+        &mut self,
+        args: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match BudgetCommand::parse(args) {
+            Ok(command) => {
+                command.apply(&mut self.session_budget);
+                if command != BudgetCommand::Show {
+                    self.save_session_budget();
+                }
+                self.show_note(&describe_budget(&self.session_budget, &self.session_usage));
+                self.clear_input(window, cx);
+            }
+            Err(e) => {
+                self.error_message = Some(e);
+                cx.notify();
+            }
+        }
+    }
+
+    /// `/temp` and `/top-p`: show or override temperature or top_p for
+    /// this conversation's runs, without touching the model's settings.
+    pub(super) fn run_sampling_command(
+        &mut self,
+        top_p: bool,
+        args: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let (name, usage) = if top_p {
+            ("top_p", "Usage: /top-p, /top-p <0.0-1.0>, /top-p off")
+        } else {
+            ("temperature", "Usage: /temp, /temp <0.0-2.0>, /temp off")
+        };
+        let current = if top_p {
+            self.sampling.top_p
+        } else {
+            self.sampling.temperature
+        };
+        let value = match args.trim() {
+            "" => {
+                match current {
+                    Some(value) => {
+                        self.show_note(&format!("{} is {} for this conversation", name, value))
+                    }
+                    None => {
+                        self.show_note(&format!("{} comes from the agent and model settings", name))
+                    }
+                }
+                return self.clear_input(window, cx);
+            }
+            "off" => None,
+            value => match value.parse::<f32>() {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    self.error_message = Some(usage.to_string());
+                    cx.notify();
+                    return;
+                }
+            },
+        };
+
+        let mut sampling = self.sampling;
+        if top_p {
+            sampling.top_p = value;
+        } else {
+            sampling.temperature = value;
+        }
+        match SamplingOverride::new(sampling.temperature, sampling.top_p) {
+            Ok(sampling) => {
+                self.sampling = sampling;
+                match value {
+                    Some(value) => self.show_note(&format!(
+                        "{} is {} for this conversation (not saved)",
+                        name, value
+                    )),
+                    None => self.show_note(&format!(
+                        "{} comes from the agent and model settings again",
+                        name
+                    )),
+                }
+                self.clear_input(window, cx);
+            }
+            Err(e) => {
+                self.error_message = Some(e.to_string());
+                cx.notify();
+            }
+        }
+    }
+
+    /// `/resume` and `spot --resume`: load the most recently updated
+    /// session with its agent and model, and keep autosaving to it.
+    pub fn resume_last_session(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.clear_input(window, cx);
+        let manager = SessionManager::from_settings(&Settings::new(&self.db));
+        let session = match manager.latest() {
+            Ok(Some(session)) => session,
+            Ok(None) => return self.show_note("No saved sessions to resume"),
+            Err(e) => {
+                self.error_message = Some(format!("Failed to load the last session: {}", e));
+                return;
+            }
+        };
+        let meta = session.meta;
+
+        let mut notes = Vec::new();
+        if self.agents.get(&meta.agent).is_some() {
+            self.current_agent = meta.agent.clone();
+            let _ = self.agents.switch(&meta.agent);
+        } else {
+            notes.push(format!(
+                "Agent {} isn't available; staying with {}",
+                meta.agent, self.current_agent
+            ));
+        }
+        if self.available_models.contains(&meta.model) {
+            self.current_model = meta.model.clone();
+            if let Err(e) = Settings::new(&self.db).set("model", &meta.model) {
+                tracing::warn!("Failed to save model: {}", e);
+            }
+        } else {
+            notes.push(format!(
+                "Model {} isn't available; staying with {}",
+                meta.model, self.current_model
+            ));
+        }
+
+        self.conversation.clear();
+        for (role, text) in transcript(&session.messages) {
+            if role == "user" {
+                self.conversation.add_user_message(text);
+            } else {
+                self.conversation.start_assistant_message();
+                self.conversation.append_to_current(&text);
+                self.conversation.finish_current_message();
+            }
+        }
+        self.message_history = session.messages;
+        self.autosave.continue_in(&meta.name);
+        self.pinned_messages = session.pinned;
+        self.tool_toggles = session.tools;
+        self.show_tools_placeholder(window, cx);
+        self.session_budget = meta.budget;
+        self.session_usage = meta.usage;
+        self.update_context_usage();
+        notes.extend(oversized_history_warning(
+            self.context_tokens_used,
+            self.context_window_size,
+        ));
+
+        notes.insert(
+            0,
+            format!(
+                "Resumed **{}** ({} messages, {} on {})",
+                meta.name, meta.message_count, self.current_agent, self.current_model
+            ),
+        );
+        self.show_note(&notes.join("\n\n"));
+    }
+
+    /// `/search <query>`: list saved sessions that mention the query.
+    pub(super) fn search_sessions(
+        &mut self,
+        query: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let hits = SessionManager::from_settings(&Settings::new(&self.db)).search(query);
+        if hits.is_empty() {
+            self.show_note(&format!("No saved sessions mention \"{}\"", query.trim()));
+        } else {
+            let lines: Vec<String> = hits.iter().map(|hit| format!("- {}", hit)).collect();
+            self.show_note(&lines.join("\n"));
+        }
+        self.clear_input(window, cx);
+    }
+
+    /// `/sessions` and `/sessions --tag <tag>`.
+    pub(super) fn list_sessions(
+        &mut self,
+        args: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let manager = SessionManager::from_settings(&Settings::new(&self.db));
+        let result = match args.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [] => manager.list(),
+            ["--tag", tag] => manager.list_by_tag(tag),
+            _ => {
+                self.error_message = Some("Usage: /sessions, /sessions --tag <tag>".into());
+                cx.notify();
+                return;
+            }
+        };
+        match result {
+            Ok(sessions) if sessions.is_empty() => self.show_note("No saved sessions"),
+            Ok(sessions) => {
+                let lines: Vec<String> =
+                    sessions.iter().map(|meta| format!("- {}", meta)).collect();
+                self.show_note(&lines.join("\n"));
+            }
+            Err(e) => self.error_message = Some(e.to_string()),
+        }
+        self.clear_input(window, cx);
+    }
+
+    /// `/tag <session> <tag>` and `/untag <session> <tag>`.
+    pub(super) fn tag_session(
+        &mut self,
+        args: &str,
+        remove: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let [session, tag] = args.split_whitespace().collect::<Vec<_>>()[..] else {
+            let command = if remove { "/untag" } else { "/tag" };
+            self.error_message = Some(format!("Usage: {} <session> <tag>", command));
+            cx.notify();
+            return;
+        };
+        let manager = SessionManager::from_settings(&Settings::new(&self.db));
+        let result = if remove {
+            manager.remove_tag(session, tag).map(|removed| {
+                if removed {
+                    format!("Removed tag {} from {}", tag, session)
+                } else {
+                    format!("{} wasn't tagged {}", session, tag)
+                }
+            })
+        } else {
+            manager.add_tag(session, tag).map(|added| {
+                if added {
+                    format!("Tagged {} with {}", session, tag)
+                } else {
+                    format!("{} is already tagged {}", session, tag)
+                }
+            })
+        };
+        match result {
+            Ok(text) => self.show_note(&text),
+            Err(e) => self.error_message = Some(e.to_string()),
+        }
+        self.clear_input(window, cx);
+    }
+
+    /// `/fork <name>`: save the conversation, copy its session to `name`
+    /// and keep going in the copy, leaving the original as it was.
+    pub(super) fn fork_session(&mut self, name: &str, window: &mut Window, cx: &mut Context<Self>) {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            self.error_message = Some("Usage: /fork <name>".to_string());
+            cx.notify();
+            return;
+        }
+        if self.message_history.is_empty() {
+            self.error_message = Some("Nothing to fork yet".to_string());
+            cx.notify();
+            return;
+        }
+
+        let settings = Settings::new(&self.db);
+        let manager = SessionManager::from_settings(&settings);
+        let model = settings
+            .get_agent_pinned_model(&self.current_agent)
+            .unwrap_or_else(|| self.current_model.clone());
+        let source =
+            match self
+                .autosave
+                .save(&manager, &self.message_history, &self.current_agent, &model)
+            {
+                Ok(meta) => meta.name,
+                Err(e) => {
+                    self.error_message = Some(format!("Failed to save the conversation: {}", e));
+                    cx.notify();
+                    return;
+                }
+            };
+        self.save_session_pins();
+        self.save_session_tools();
+        self.save_session_budget();
+
+        match manager.fork(&source, name) {
+            Ok(meta) => {
+                self.autosave.continue_in(&meta.name);
+                self.show_note(&format!(
+                    "Forked {} as {}; this conversation now saves to {}",
+                    source, meta.name, meta.name
+                ));
+                self.clear_input(window, cx);
+            }
+            Err(e) => {
+                self.error_message = Some(e.to_string());
+                cx.notify();
+            }
+        }
+    }
+
+    /// `/pin-session <name>` and `/unpin-session <name>`.
+    pub(super) fn pin_session(
+        &mut self,
+        name: &str,
+        pinned: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let state = if pinned { "pinned" } else { "unpinned" };
+        match SessionManager::from_settings(&Settings::new(&self.db)).set_pinned(name, pinned) {
+            Ok(true) => self.show_note(&format!("{} is now {}", name, state)),
+            Ok(false) => self.show_note(&format!("{} was already {}", name, state)),
+            Err(e) => self.error_message = Some(e.to_string()),
+        }
+        self.clear_input(window, cx);
+    }
+
+    /// `/tools`: list the current agent's tools, turn one off or on by name
+    /// (`/tools disable <tool>`, `/tools enable <tool>`), or turn them all off
+    /// for plain chat and back on (`/tools off`, `/tools on`).
+    pub(super) fn run_tools_command(
+        &mut self,
+        args: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let command = match ToolsCommand::parse(args) {
+            Ok(command) => command,
+            Err(usage) => {
+                self.error_message = Some(usage);
+                cx.notify();
+                return;
+            }
+        };
+        if let ToolsCommand::AllOff | ToolsCommand::AllOn = command {
+            self.tool_toggles
+                .set_all_off(command == ToolsCommand::AllOff);
+            self.show_tools_placeholder(window, cx);
+            self.save_session_tools();
+            self.show_note(if self.tool_toggles.all_off() {
+                "Tools are off: the model can only reply with text"
+            } else {
+                "Tools are on"
+            });
+            self.clear_input(window, cx);
+            return;
+        }
+
+        let agent = self.current_agent.clone();
+        let mut tools: Vec<String> = self
+            .agents
+            .get(&agent)
+            .map(|a| a.available_tools().into_iter().map(String::from).collect())
+            .unwrap_or_default();
+        // No attachments means the agent gets every server's tools
+        let attached = Settings::new(&self.db).get_agent_mcps(&agent);
+        let mcp = self.mcp_manager.clone();
+        self.clear_input(window, cx);
+
+        // MCP tools are only known to their servers, so ask them first
+        cx.spawn(async move |this: WeakEntity<ChatApp>, cx: &mut AsyncApp| {
+            for (server, server_tools) in mcp.list_all_tools().await {
+                if attached.is_empty() || attached.contains(&server) {
+                    tools.extend(server_tools.into_iter().map(|tool| tool.name));
+                }
+            }
+            this.update(cx, |app, cx| {
+                app.apply_tools_command(command, &agent, &tools);
+                cx.notify();
+            })
+            .map_err(|e| tracing::error!("this.update() failed: {:?}", e))
+            .ok();
+        })
+        .detach();
+    }
+
+    /// List, disable or enable one of `tools`, the agent's built-in and
+    /// MCP tools.
+    fn apply_tools_command(&mut self, command: ToolsCommand, agent: &str, tools: &[String]) {
+        let (tool, enable) = match command {
+            ToolsCommand::List => {
+                let tools: Vec<&str> = tools.iter().map(String::as_str).collect();
+                let list = self.tool_toggles.list(agent, &tools);
+                if self.tool_toggles.all_off() {
+                    self.show_note(&format!(
+                        "{}\n\nAll tools are off (`/tools on` to turn them back on)",
+                        list
+                    ));
+                } else {
+                    self.show_note(&list);
+                }
+                return;
+            }
+            // Handled in `run_tools_command` without asking the servers
+            ToolsCommand::AllOff | ToolsCommand::AllOn => return,
+            ToolsCommand::Disable(tool) => (tool, false),
+            ToolsCommand::Enable(tool) => (tool, true),
+        };
+        if !tools.contains(&tool) {
+            self.error_message = Some(format!("{} isn't one of {}'s tools", tool, agent));
+            return;
+        }
+
+        let state = if enable { "enabled" } else { "disabled" };
+        if self.tool_toggles.set_enabled(agent, &tool, enable) {
+            self.save_session_tools();
+            let verb = if enable { "Enabled" } else { "Disabled" };
+            self.show_note(&format!("{} {} for {}", verb, tool, agent));
+        } else {
+            self.show_note(&format!("{} is already {} for {}", tool, state, agent));
+        }
+    }
+
+    /// `spot --sandbox`: keep every run in this window to read-only tools.
+    pub fn enable_sandbox(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.sandbox = true;
+        self.show_tools_placeholder(window, cx);
+        cx.notify();
+    }
+
+    /// Whether runs only get read-only tools, from `--sandbox` or the
+    /// `sandbox_mode` setting.
+    pub(super) fn sandboxed(&self) -> bool {
+        self.sandbox || self.sandbox_mode
+    }
+
+    /// Say in the input placeholder when all tools are off or the
+    /// session is sandboxed.
+    pub(super) fn show_tools_placeholder(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let placeholder = if self.tool_toggles.all_off() {
+            "Tools off, chat only. Type a message..."
+        } else if self.sandboxed() {
+            "Sandbox, read-only tools. Type a message..."
+        } else {
+            "Type a message..."
+        };
+        self.input_state.update(cx, |state, cx| {
+            state.set_placeholder(placeholder, window, cx);
+        });
+    }
+
+    /// Pick up settings the app keeps a copy of after one is changed.
+    fn reload_cached_settings(&mut self) {
+        let settings = Settings::new(&self.db);
+        self.show_reasoning = settings.show_reasoning();
+        self.pdf_mode = settings.pdf_mode();
+        self.sandbox_mode = settings.sandbox_mode();
+        let user_mode = settings.user_mode();
+        if user_mode != self.user_mode {
+            self.user_mode = user_mode;
+            self.available_agents = self
+                .agents
+                .list_filtered(user_mode)
+                .into_iter()
+                .map(|info| (info.name.clone(), info.display_name.clone()))
+                .collect();
+        }
+    }
+
+    /// Add a local note to the conversation; it isn't part of the history
+    /// sent to the model.
+    pub(super) fn show_note(&mut self, text: &str) {
+        self.error_message = None;
+        self.conversation.start_assistant_message();
+        self.conversation.append_to_current(text);
+        self.conversation.finish_current_message();
+        self.sync_messages_list_state();
+    }
+
+    pub(super) fn clear_input(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.input_state.update(cx, |state, cx| {
+            state.set_value("", window, cx);
+        });
+        cx.notify();
+    }
+
     /// Switch to next agent
     pub(super) fn next_agent(
         &mut self,
@@ -3,22 +3,33 @@
 //! This module handles sending messages and executing agents:
 //! - `send_message()` - Prepare and send a user message
 //! - `execute_agent()` - Run an agent with the current context
+//! - `keep_stopped_run()` - Keep what a stopped run got through in the history
+//! - `autosave_turn()` - Save the conversation when an autosave is due
+//! - `save_session_budget()` - Save the conversation's budget and usage
 
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
+use futures::future::{self, Either};
 use gpui::{AsyncApp, Context, WeakEntity, Window};
 
-use crate::agents::{AgentExecutor, AgentManager, ExecuteContext};
+use crate::agents::{AgentExecutor, AgentManager, CancelToken, ExecuteContext};
 use crate::config::{PdfMode, Settings};
 use crate::db::Database;
 use crate::mcp::McpManager;
+use crate::models::settings::SamplingOverride;
 use crate::models::ModelRegistry;
-use crate::tools::SpotToolRegistry;
+use crate::session::{keep_interrupted_turn, BudgetTracker, SessionManager};
+use crate::tools::{changed_files_note, expand_file_references, SpotToolRegistry, UndoJournal};
 use serdes_ai_core::messages::ImageMediaType;
+use serdes_ai_core::ModelRequest;
 
 use super::{ChatApp, PendingAttachment, MAX_IMAGE_DIMENSION};
 
+/// How long a stopped run has to return the history it got through.
+const STOP_GRACE: Duration = Duration::from_secs(2);
+
 impl ChatApp {
     /// Handle sending a message with real agent execution
     pub(super) fn send_message(&mut self, window: &mut Window, cx: &mut Context<Self>) {
@@ -35,8 +46,112 @@ impl ChatApp {
             return;
         }
 
-        // Build the message including attachments
-        let mut full_message = text.clone();
+        if !has_attachments {
+            match text.as_str() {
+                "/edit" => return self.edit_last_prompt(window, cx),
+                "/undo" => return self.undo_last_run(window, cx),
+                "/diff" => return self.show_run_diff(None, window, cx),
+                "/config" => return self.run_config_command("", window, cx),
+                "/model-info" => return self.show_model_info("", window, cx),
+                "/model" => return self.pick_model("", window, cx),
+                "/compact" => return self.compact_context("", window, cx),
+                "/history" => return self.run_history_command("", window, cx),
+                "/budget" => return self.run_budget_command("", window, cx),
+                "/resume" => return self.resume_last_session(window, cx),
+                "/sessions" => return self.list_sessions("", window, cx),
+                "/tools" => return self.run_tools_command("", window, cx),
+                "/fork" => return self.fork_session("", window, cx),
+                "/temp" => return self.run_sampling_command(false, "", window, cx),
+                "/top-p" => return self.run_sampling_command(true, "", window, cx),
+                command => {
+                    if let Some(args) = command.strip_prefix("/config ") {
+                        let args = args.to_string();
+                        return self.run_config_command(&args, window, cx);
+                    }
+                    if let Some(args) = command.strip_prefix("/tools ") {
+                        let args = args.to_string();
+                        return self.run_tools_command(&args, window, cx);
+                    }
+                    for (prefix, top_p) in [("/temp ", false), ("/top-p ", true)] {
+                        if let Some(args) = command.strip_prefix(prefix) {
+                            let args = args.to_string();
+                            return self.run_sampling_command(top_p, &args, window, cx);
+                        }
+                    }
+                    if let Some(args) = command.strip_prefix("/sessions ") {
+                        let args = args.to_string();
+                        return self.list_sessions(&args, window, cx);
+                    }
+                    for (prefix, remove) in [("/tag ", false), ("/untag ", true)] {
+                        if let Some(args) = command.strip_prefix(prefix) {
+                            let args = args.to_string();
+                            return self.tag_session(&args, remove, window, cx);
+                        }
+                    }
+                    if command.starts_with("/pin ") || command.starts_with("/unpin ") {
+                        let command = command.to_string();
+                        return self.run_pin_command(&command, window, cx);
+                    }
+                    for (prefix, pinned) in [("/pin-session ", true), ("/unpin-session ", false)] {
+                        if let Some(name) = command.strip_prefix(prefix) {
+                            let name = name.trim().to_string();
+                            return self.pin_session(&name, pinned, window, cx);
+                        }
+                    }
+                    if let Some(name) = command.strip_prefix("/fork ") {
+                        let name = name.trim().to_string();
+                        return self.fork_session(&name, window, cx);
+                    }
+                    if let Some(query) = command.strip_prefix("/search ") {
+                        let query = query.to_string();
+                        return self.search_sessions(&query, window, cx);
+                    }
+                    if let Some(args) = command.strip_prefix("/compact ") {
+                        let args = args.to_string();
+                        return self.compact_context(&args, window, cx);
+                    }
+                    if let Some(args) = command.strip_prefix("/history ") {
+                        let args = args.to_string();
+                        return self.run_history_command(&args, window, cx);
+                    }
+                    if let Some(args) = command.strip_prefix("/budget ") {
+                        let args = args.to_string();
+                        return self.run_budget_command(&args, window, cx);
+                    }
+                    if let Some(args) = command.strip_prefix("/model-info ") {
+                        let args = args.to_string();
+                        return self.show_model_info(&args, window, cx);
+                    }
+                    if let Some(name) = command.strip_prefix("/model ") {
+                        let name = name.to_string();
+                        return self.pick_model(&name, window, cx);
+                    }
+                    if let Some(path) = command.strip_prefix("/diff ") {
+                        let path = path.trim().to_string();
+                        return self.show_run_diff(Some(path), window, cx);
+                    }
+                }
+            }
+        }
+
+        // Build the message including @path references and attachments
+        let mut referenced_images = Vec::new();
+        let mut full_message = match std::env::current_dir() {
+            Ok(cwd) => {
+                let expanded = expand_file_references(&text, &cwd);
+                if !expanded.failed.is_empty() {
+                    let failed: Vec<String> = expanded
+                        .failed
+                        .iter()
+                        .map(|(reference, reason)| format!("@{} ({})", reference, reason))
+                        .collect();
+                    self.error_message = Some(format!("Couldn't read {}", failed.join(", ")));
+                }
+                referenced_images = expanded.images;
+                expanded.prompt
+            }
+            Err(_) => text.clone(),
+        };
 
         // Add file references for non-image attachments
         let file_refs: Vec<String> = self
@@ -67,6 +182,7 @@ impl ChatApp {
                 _ => None,
             })
             .collect();
+        images.extend(referenced_images);
 
         // Log collected images
         tracing::info!(
@@ -203,6 +319,15 @@ impl ChatApp {
             }
         }
 
+        // Tell the agent which files it read have changed since
+        if let Some(note) = self
+            .file_watcher
+            .as_ref()
+            .and_then(|watcher| changed_files_note(&watcher.take_changes()))
+        {
+            full_message = format!("{}\n{}", note, full_message);
+        }
+
         // Add user message to conversation
         if has_attachments {
             let attachment_note = format!(
@@ -214,9 +339,9 @@ impl ChatApp {
                 },
                 self.pending_attachments.len()
             );
-            self.conversation.add_user_message(&attachment_note);
+            self.conversation.add_user_input(&attachment_note, &text);
         } else {
-            self.conversation.add_user_message(&text);
+            self.conversation.add_user_input(&text, &text);
         }
         self.sync_messages_list_state();
 
@@ -250,9 +375,15 @@ impl ChatApp {
             tool_registry: Arc<SpotToolRegistry>,
             mcp_manager: Arc<McpManager>,
             message_bus_sender: crate::messaging::MessageSender,
+            no_tools: bool,
+            sandbox: bool,
+            sampling: SamplingOverride,
+            disabled_tools: Vec<String>,
             prompt: String,
             images: Vec<(Vec<u8>, ImageMediaType)>,
             history: Option<Vec<serdes_ai_core::ModelRequest>>,
+            budget: Arc<BudgetTracker>,
+            cancel: CancelToken,
         }
 
         let data = ExecuteData {
@@ -264,6 +395,10 @@ impl ChatApp {
             tool_registry: self.tool_registry.clone(),
             mcp_manager: self.mcp_manager.clone(),
             message_bus_sender: self.message_bus.sender(),
+            no_tools: self.tool_toggles.all_off(),
+            sandbox: self.sandbox,
+            sampling: self.sampling,
+            disabled_tools: self.tool_toggles.disabled(&self.current_agent),
             prompt,
             images,
             history: if self.message_history.is_empty() {
@@ -271,7 +406,11 @@ impl ChatApp {
             } else {
                 Some(self.message_history.clone())
             },
+            budget: Arc::new(BudgetTracker::new(self.session_budget, self.session_usage)),
+            cancel: CancelToken::new(),
         };
+        let sent_prompt = data.prompt.clone();
+        self.run_cancel = Some(data.cancel.clone());
 
         // Log BEFORE the spawn to verify data is correct in struct
         tracing::info!(
@@ -297,9 +436,15 @@ impl ChatApp {
                 tool_registry,
                 mcp_manager,
                 message_bus_sender,
+                no_tools,
+                sandbox,
+                sampling,
+                disabled_tools,
                 prompt,
                 images,
                 history,
+                budget,
+                cancel,
             } = data;
 
             // Log images inside async block to verify they survived the move
@@ -313,6 +458,7 @@ impl ChatApp {
             let Some(agent) = agents.get(&agent_name) else {
                 this.update(cx, |app, cx| {
                     app.is_generating = false;
+                    app.run_cancel = None;
                     app.error_message = Some("No agent selected".to_string());
                     cx.notify();
                 })
@@ -321,7 +467,15 @@ impl ChatApp {
             };
 
             // Create executor with message bus
-            let executor = AgentExecutor::new(&db, &model_registry).with_bus(message_bus_sender);
+            let executor = AgentExecutor::new(&db, &model_registry)
+                .with_bus(message_bus_sender)
+                .with_no_tools(no_tools)
+                .with_sandbox(sandbox)
+                .with_sampling_override(sampling)
+                .with_disabled_tools(disabled_tools)
+                .with_undo(UndoJournal::new().begin())
+                .with_budget(Arc::clone(&budget))
+                .with_cancellation(cancel.clone());
 
             // Get the effective model for this agent (pinned or default)
             let effective_model = {
@@ -336,41 +490,63 @@ impl ChatApp {
                 images_empty = images.is_empty(),
                 "execute_agent: about to choose execution path"
             );
-            let result = if images.is_empty() {
-                executor
-                    .execute_with_bus(
-                        agent,
-                        &effective_model,
-                        &prompt,
-                        history,
-                        &tool_registry,
-                        &mcp_manager,
-                    )
-                    .await
-            } else {
-                let context = ExecuteContext {
-                    tool_registry: &tool_registry,
-                    mcp_manager: &mcp_manager,
-                };
-                executor
-                    .execute_with_images(
-                        agent,
-                        &effective_model,
-                        &prompt,
-                        &images,
-                        history,
-                        &context,
-                    )
-                    .await
+            let run = async {
+                if images.is_empty() {
+                    executor
+                        .execute_with_bus(
+                            agent,
+                            &effective_model,
+                            &prompt,
+                            history,
+                            &tool_registry,
+                            &mcp_manager,
+                        )
+                        .await
+                } else {
+                    let context = ExecuteContext {
+                        tool_registry: &tool_registry,
+                        mcp_manager: &mcp_manager,
+                    };
+                    executor
+                        .execute_with_images(
+                            agent,
+                            &effective_model,
+                            &prompt,
+                            &images,
+                            history,
+                            &context,
+                        )
+                        .await
+                }
             };
+            // Stopping ends a streaming run with the history it got through;
+            // one that doesn't return within STOP_GRACE is dropped
+            let result = match future::select(Box::pin(run), Box::pin(cancel.cancelled())).await {
+                Either::Left((result, _)) => Some(result),
+                Either::Right((_, run)) => {
+                    let grace = cx.background_executor().timer(STOP_GRACE);
+                    match future::select(run, grace).await {
+                        Either::Left((result, _)) => Some(result),
+                        Either::Right(_) => None,
+                    }
+                }
+            };
+            let stopped = cancel.is_cancelled();
 
             // Update state based on result
             tracing::info!("Execution async task finished, calling this.update()");
             this.update(cx, |app, cx| {
                 tracing::info!("Inside this.update() callback");
                 app.is_generating = false;
+                app.run_cancel = None;
+                // Charge the conversation for the run, even one that failed or was halted
+                app.session_usage.add(budget.run_usage());
+                // Changes made during the run are the agent's own edits
+                if let Some(watcher) = &app.file_watcher {
+                    watcher.clear_changes();
+                }
                 match result {
-                    Ok(exec_result) => {
+                    Some(Ok(exec_result)) if !stopped => {
                         tracing::info!(
                             messages_count = exec_result.messages.len(),
                             "Execution completed, updating message history"
@@ -378,15 +554,21 @@ impl ChatApp {
                         if !exec_result.messages.is_empty() {
                             app.message_history = exec_result.messages;
                             app.update_context_usage();
+                            app.autosave_turn();
                         }
                     }
-                    Err(e) => {
+                    Some(Err(e)) if !stopped => {
                         app.error_message = Some(e.to_string());
                         app.conversation
                             .append_to_current(&format!("\n\n❌ Error: {}", e));
                         app.conversation.finish_current_message();
                     }
+                    result => app.keep_stopped_run(
+                        &sent_prompt,
+                        result.and_then(Result::ok).map(|r| r.messages),
+                    ),
                 }
+                app.save_session_budget();
                 cx.notify();
             })
             .map_err(|e| tracing::error!("this.update() failed: {:?}", e))
@@ -394,4 +576,79 @@ impl ChatApp {
         })
         .detach();
     }
+
+    /// Keep a run stopped with Escape in the history: the `messages` it
+    /// returned, with the tool calls it finished and their results, or when
+    /// it couldn't return them, its prompt and the reply as far as it got.
+    fn keep_stopped_run(&mut self, prompt: &str, messages: Option<Vec<ModelRequest>>) {
+        match messages {
+            Some(messages) if !messages.is_empty() => self.message_history = messages,
+            _ => {
+                let partial = self.conversation.current_reply_text();
+                keep_interrupted_turn(&mut self.message_history, prompt, &partial);
+            }
+        }
+        self.conversation.finish_current_message();
+        self.show_note("_Stopped_");
+        self.update_context_usage();
+        self.autosave_turn();
+    }
+
+    /// Save the history to the conversation's session if a save is due
+    /// under `autosave_every_turns`.
+    fn autosave_turn(&mut self) {
+        let settings = Settings::new(&self.db);
+        if !self.autosave.turn_finished(settings.autosave_every_turns()) {
+            return;
+        }
+        let manager = SessionManager::from_settings(&settings);
+        let model = settings
+            .get_agent_pinned_model(&self.current_agent)
+            .unwrap_or_else(|| self.current_model.clone());
+        if let Err(e) =
+            self.autosave
+                .save(&manager, &self.message_history, &self.current_agent, &model)
+        {
+            tracing::warn!(error = %e, "Autosave failed");
+        } else {
+            self.save_session_pins();
+            self.save_session_tools();
+        }
+    }
+
+    /// Keep the pins of the conversation's session, once it has one, up
+    /// to date.
+    pub(super) fn save_session_pins(&self) {
+        let Some(name) = self.autosave.name() else {
+            return;
+        };
+        let manager = SessionManager::from_settings(&Settings::new(&self.db));
+        if let Err(e) = manager.record_pins(name, &self.pinned_messages) {
+            tracing::warn!(session = %name, error = %e, "Failed to save the session pins");
+        }
+    }
+
+    /// Keep the tool toggles of the conversation's session, once it has
+    /// one, up to date.
+    pub(super) fn save_session_tools(&self) {
+        let Some(name) = self.autosave.name() else {
+            return;
+        };
+        let manager = SessionManager::from_settings(&Settings::new(&self.db));
+        if let Err(e) = manager.record_tools(name, &self.tool_toggles) {
+            tracing::warn!(session = %name, error = %e, "Failed to save the session tools");
+        }
+    }
+
+    /// Keep the budget and usage of the conversation's session, once it
+    /// has one, up to date.
+    pub(super) fn save_session_budget(&self) {
+        let Some(name) = self.autosave.name() else {
+            return;
+        };
+        let manager = SessionManager::from_settings(&Settings::new(&self.db));
+        if let Err(e) = manager.record_budget(name, self.session_budget, self.session_usage) {
+            tracing::warn!(session = %name, error = %e, "Failed to save the session budget");
+        }
+    }
 }
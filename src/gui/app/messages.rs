@@ -5,7 +5,9 @@ use gpui::{
 use gpui_component::text::markdown;
 
 use super::ChatApp;
-use crate::gui::components::{collapsible_display, list_scrollbar, CollapsibleProps};
+use crate::gui::components::{
+    collapsible_display, list_scrollbar, render_tool_result, CollapsibleProps,
+};
 use crate::gui::state::{
     AgentContentItem, MessageRole, MessageSection, ThinkingSection, ToolCallSection,
 };
@@ -314,13 +316,11 @@ impl ChatApp {
             Some(("✗", theme.error))
         };
 
-        div()
-            .id(element_id)
+        let line = div()
             .flex()
             .items_center()
             .gap(px(6.))
             .py(px(3.))
-            .my(px(2.))
             .text_size(px(13.))
             // Muted bullet
             .child(div().text_color(theme.tool_bullet).child("•"))
@@ -339,9 +339,33 @@ impl ChatApp {
                         .child(tool_section.info.subject.clone()),
                 )
             })
+            // Live progress while running (e.g. file counts from list_files/grep)
+            .when_some(
+                tool_section
+                    .progress
+                    .clone()
+                    .filter(|_| tool_section.is_running),
+                |el, progress| el.child(div().text_color(theme.text_muted).child(progress)),
+            )
             // Status indicator at end
             .when_some(status, |el, (icon, color)| {
                 el.child(div().text_color(color).child(icon))
+            });
+
+        div()
+            .id(element_id)
+            .flex()
+            .flex_col()
+            .gap(px(4.))
+            .my(px(2.))
+            .child(line)
+            // Structured result (tables, images, JSON) below the tool line
+            .when_some(tool_section.result.as_ref(), |el, content| {
+                el.child(div().pl(px(14.)).child(render_tool_result(
+                    content,
+                    &tool_section.id,
+                    theme,
+                )))
             })
             .into_any_element()
     }
@@ -5,15 +5,19 @@
 //! - `fetch_providers()` - Fetch available providers for add-model dialog
 //! - `add_single_model()` - Add a new model configuration
 //! - `delete_model()` - Remove a model configuration
+//! - `set_model_streaming()` - Turn streaming off for endpoints that can't stream
 //! - `start_oauth_flow()` - Initiate OAuth for a provider
+//! - `show_model_info()` - Show what a model supports (`/model-info`)
+//! - `pick_model()` - List models with their details, or pin one to the agent (`/model`)
 //! - `refresh_api_keys_list()` - Refresh stored API keys
 
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use gpui::{AsyncApp, Context, WeakEntity};
+use gpui::{AsyncApp, Context, WeakEntity, Window};
 
-use crate::models::ModelRegistry;
+use crate::config::Settings;
+use crate::models::{probe_tool_support, ModelConfig, ModelRegistry};
 
 use super::ChatApp;
 
@@ -87,7 +91,7 @@ impl ChatApp {
             .map(|e| e.read(cx).value().to_string())
             .unwrap_or_default();
 
-        if !api_key_value.is_empty() {
+        if !self.add_model_no_api_key && !api_key_value.is_empty() {
             if let Err(e) = self.db.save_api_key(env_var, &api_key_value) {
                 self.add_model_error = Some(format!("Failed to save API key: {}", e));
                 cx.notify();
@@ -129,10 +133,11 @@ impl ChatApp {
             supports_thinking: false,
             supports_vision: false,
             supports_tools: true,
+            supports_streaming: true,
             description: Some(description),
             custom_endpoint: Some(CustomEndpoint {
                 url: api_url,
-                api_key: Some(format!("${}", env_var)),
+                api_key: (!self.add_model_no_api_key).then(|| format!("${}", env_var)),
                 headers: HashMap::new(),
                 ca_certs_path: None,
             }),
@@ -154,6 +159,23 @@ impl ChatApp {
         cx.notify();
     }
 
+    /// Turn streaming on or off for a model; runs on models without it
+    /// use blocking requests.
+    pub(super) fn set_model_streaming(
+        &mut self,
+        model_name: &str,
+        enabled: bool,
+        cx: &mut Context<Self>,
+    ) {
+        match ModelRegistry::set_supports_streaming(&self.db, model_name, enabled) {
+            Ok(()) => self.refresh_models(),
+            Err(e) => {
+                self.error_message = Some(format!("Failed to save streaming setting: {}", e));
+            }
+        }
+        cx.notify();
+    }
+
     /// Delete a model from the registry
     pub(super) fn delete_model(&mut self, model_name: &str, cx: &mut Context<Self>) {
         if self.current_model == model_name {
@@ -168,12 +190,21 @@ impl ChatApp {
             }
         }
 
-        if let Err(e) = ModelRegistry::remove_model_from_db(&self.db, model_name) {
-            tracing::warn!("Failed to delete model {}: {}", model_name, e);
-            return;
+        let mut registry = ModelRegistry::load_from_db(&self.db).unwrap_or_default();
+        match registry.remove(model_name, &self.db) {
+            Ok(removal) if !removal.is_empty() => tracing::info!(
+                "Deleted model {}; unpinned agents: {:?}; removed from round-robin: {:?}",
+                model_name,
+                removal.unpinned_agents,
+                removal.round_robin_models
+            ),
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("Failed to delete model {}: {}", model_name, e);
+                return;
+            }
         }
 
-        let registry = ModelRegistry::load_from_db(&self.db).unwrap_or_default();
         self.available_models = registry.list_available(&self.db);
         self.model_registry = Arc::new(registry);
 
@@ -213,8 +244,136 @@ impl ChatApp {
         .detach();
     }
 
+    /// `/model-info [name] [--probe]`: show a model's capabilities, the
+    /// current model's by default. `--probe` checks tool calling on a
+    /// custom endpoint with a test request.
+    pub(super) fn show_model_info(
+        &mut self,
+        args: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mut probe = false;
+        let mut name = None;
+        for arg in args.split_whitespace() {
+            match arg {
+                "--probe" => probe = true,
+                other => name = Some(other.to_string()),
+            }
+        }
+        let name = name.unwrap_or_else(|| self.current_model.clone());
+        self.clear_input(window, cx);
+
+        let Some(config) = self.model_registry.get(&name).cloned() else {
+            self.error_message = Some(format!("Unknown model: {}", name));
+            return;
+        };
+        let mut note = format!(
+            "**{}** ({})\n```\n{}\n```",
+            name,
+            config.model_type,
+            config.capabilities()
+        );
+        match (config.custom_endpoint.is_some(), probe) {
+            (true, false) => note.push_str(&format!(
+                "\nRun `/model-info {} --probe` to test tool calling on its endpoint.",
+                name
+            )),
+            (false, true) => note.push_str("\n`--probe` only applies to custom endpoint models."),
+            _ => {}
+        }
+        self.show_note(&note);
+        if config.custom_endpoint.is_none() || !probe {
+            return;
+        }
+
+        let db = self.db.clone();
+        cx.spawn(async move |this: WeakEntity<ChatApp>, cx: &mut AsyncApp| {
+            let result = probe_tool_support(&db, &config).await;
+            this.update(cx, |app, cx| {
+                match result {
+                    Ok(probe) => app.show_note(&format!("Probe of {}: {}", name, probe)),
+                    Err(e) => app.error_message = Some(e.to_string()),
+                }
+                cx.notify();
+            })
+            .map_err(|e| tracing::error!("this.update() failed: {:?}", e))
+            .ok();
+        })
+        .detach();
+    }
+
+    /// `/model [name]`: list the available models by provider, with their
+    /// context length, capabilities and price where known, or pin `name`
+    /// to the current agent the way the toolbar's model dropdown does.
+    pub(super) fn pick_model(&mut self, args: &str, window: &mut Window, cx: &mut Context<Self>) {
+        self.clear_input(window, cx);
+        let name = args.trim();
+
+        if name.is_empty() {
+            if self.available_models.is_empty() {
+                self.show_note("No models available; add one in Settings → Models");
+                return;
+            }
+            let (effective_model, _) = self.current_effective_model();
+            let mut lines = Vec::new();
+            for (provider, models) in self.group_models_by_type(&self.available_models) {
+                lines.push(format!("**{}**", provider));
+                for (model, _) in models {
+                    let details = self
+                        .model_registry
+                        .get(&model)
+                        .map(model_details)
+                        .unwrap_or_default();
+                    if model == effective_model {
+                        lines.push(format!("- **`{}`** (current) {}", model, details));
+                    } else {
+                        lines.push(format!("- `{}` {}", model, details));
+                    }
+                }
+            }
+            lines.push("\nSwitch this agent with `/model <name>`.".to_string());
+            self.show_note(&lines.join("\n"));
+            return;
+        }
+
+        let Some(config) = self
+            .available_models
+            .iter()
+            .any(|m| m == name)
+            .then(|| self.model_registry.get(name))
+            .flatten()
+        else {
+            self.error_message = Some(format!("Unknown or unavailable model: {}", name));
+            return;
+        };
+        let details = model_details(config);
+
+        if let Err(e) = Settings::new(&self.db).set_agent_pinned_model(&self.current_agent, name) {
+            self.error_message = Some(format!(
+                "Failed to pin model for {}: {}",
+                self.current_agent, e
+            ));
+            return;
+        }
+        self.update_context_usage();
+        self.show_note(&format!(
+            "Switched {} to **{}** {}",
+            self.current_agent, name, details
+        ));
+    }
+
     /// Refresh the API keys list from database
     pub(super) fn refresh_api_keys_list(&mut self) {
         self.api_keys_list = self.db.list_api_keys().unwrap_or_default();
     }
 }
+
+/// Context, capabilities and price of a model, for `/model` listings.
+fn model_details(config: &ModelConfig) -> String {
+    let mut details = config.capabilities().summary();
+    if let Some(price) = config.catalog_info().and_then(|info| info.price_label()) {
+        details.push_str(&format!(" · {}", price));
+    }
+    format!("— {}", details)
+}
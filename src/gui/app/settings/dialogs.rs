@@ -423,11 +423,17 @@ impl ChatApp {
                                                                 let value = this
                                                                     .api_key_new_value
                                                                     .clone();
-                                                                let _ = this
-                                                                    .db
-                                                                    .save_api_key(&name, &value);
-                                                                this.api_key_new_name.clear();
-                                                                this.api_key_new_value.clear();
+                                                                if let Err(e) =
+                                                                    this.db.save_api_key(&name, &value)
+                                                                {
+                                                                    this.error_message = Some(format!(
+                                                                        "Failed to save API key: {}",
+                                                                        e
+                                                                    ));
+                                                                } else {
+                                                                    this.api_key_new_name.clear();
+                                                                    this.api_key_new_value.clear();
+                                                                }
                                                                 this.refresh_api_keys_list();
                                                                 cx.notify();
                                                             }),
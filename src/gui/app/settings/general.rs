@@ -274,7 +274,7 @@ impl ChatApp {
                                     this.show_reasoning = !this.show_reasoning;
                                     let settings = Settings::new(&this.db);
                                     let value = if this.show_reasoning { "true" } else { "false" };
-                                    if let Err(e) = settings.set("show_reasoning", value) {
+                                    if let Err(e) = settings.set_validated("show_reasoning", value) {
                                         tracing::warn!("Failed to save show_reasoning: {}", e);
                                     }
                                     cx.notify();
@@ -30,7 +30,7 @@ impl ChatApp {
             .as_ref()
             .map(|e| !e.read(cx).value().is_empty())
             .unwrap_or(false);
-        let can_add_models = has_existing_key || has_key_input;
+        let can_add_models = has_existing_key || has_key_input || self.add_model_no_api_key;
 
         let provider_id = provider_id.clone();
         let env_var = env_var.to_string();
@@ -125,6 +125,36 @@ impl ChatApp {
                     }),
             )
             .child(self.render_paste_button(cx))
+            .child(self.render_no_key_toggle(cx))
+    }
+
+    /// Render the toggle for adding models that don't need an API key.
+    fn render_no_key_toggle(&self, cx: &Context<Self>) -> impl IntoElement {
+        let theme = self.theme.clone();
+        let no_key = self.add_model_no_api_key;
+
+        div()
+            .id("no-api-key")
+            .px(px(12.))
+            .py(px(8.))
+            .rounded(px(6.))
+            .bg(if no_key {
+                theme.accent
+            } else {
+                theme.tool_card
+            })
+            .text_color(if no_key { rgb(0xffffff) } else { theme.text })
+            .text_size(px(12.))
+            .cursor_pointer()
+            .hover(|s| s.opacity(0.8))
+            .on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|this, _, _, cx| {
+                    this.add_model_no_api_key = !this.add_model_no_api_key;
+                    cx.notify();
+                }),
+            )
+            .child("No key needed")
     }
 
     /// Render the paste button for API key.
@@ -38,7 +38,7 @@ impl ChatApp {
     }
 
     /// Group available models by their provider type.
-    fn group_models_by_type(
+    pub(crate) fn group_models_by_type(
         &self,
         available_models: &[String],
     ) -> BTreeMap<String, Vec<(String, Option<String>)>> {
@@ -116,6 +116,7 @@ impl ChatApp {
                         this.add_model_selected_provider = None;
                         this.add_model_selected_model = None;
                         this.add_model_models.clear();
+                        this.add_model_no_api_key = false;
                         this.add_model_error = None;
 
                         if this.add_model_api_key_input_entity.is_none() {
@@ -483,6 +484,8 @@ impl ChatApp {
             .child(self.render_temp_input(cx))
             // Top P
             .child(self.render_top_p_input(cx))
+            // Streaming
+            .child(self.render_streaming_toggle(&model_name_for_save, cx))
             // Save button
             .child(self.render_save_settings_button(&model_name_for_save, api_key_env_for_save, cx))
     }
@@ -533,6 +536,54 @@ impl ChatApp {
             )
     }
 
+    /// Render the streaming on/off row.
+    fn render_streaming_toggle(&self, model_name: &str, cx: &Context<Self>) -> impl IntoElement {
+        let theme = self.theme.clone();
+        let model_name = model_name.to_string();
+        let streaming = self
+            .model_registry
+            .get(&model_name)
+            .is_none_or(|config| config.supports_streaming);
+
+        div()
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(
+                div()
+                    .text_size(px(12.))
+                    .text_color(theme.text_muted)
+                    .child("Stream responses"),
+            )
+            .child(
+                div()
+                    .id("model-streaming-toggle")
+                    .px(px(10.))
+                    .py(px(4.))
+                    .rounded(px(6.))
+                    .bg(if streaming {
+                        theme.accent
+                    } else {
+                        theme.tool_card
+                    })
+                    .text_color(if streaming { rgb(0xffffff) } else { theme.text })
+                    .text_size(px(12.))
+                    .cursor_pointer()
+                    .hover(|s| s.opacity(0.8))
+                    .on_mouse_down(MouseButton::Left, |_, _, cx| {
+                        cx.stop_propagation();
+                    })
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(move |this, _, _, cx| {
+                            cx.stop_propagation();
+                            this.set_model_streaming(&model_name, !streaming, cx);
+                        }),
+                    )
+                    .child(if streaming { "On" } else { "Off" }),
+            )
+    }
+
     /// Render the API key setting row.
     fn render_api_key_setting_row(&self, env_var: &str, _cx: &Context<Self>) -> impl IntoElement {
         let theme = self.theme.clone();
@@ -723,6 +774,23 @@ impl ChatApp {
             }
         }
 
+        // Thinking models run at 1.0 whatever is saved
+        if !had_error {
+            let supports_thinking = self
+                .model_registry
+                .get(model_name)
+                .is_some_and(|config| config.supports_thinking);
+            let ignored = SpotModelSettings::load(&self.db, model_name)
+                .ok()
+                .and_then(|settings| settings.ignored_temperature(supports_thinking));
+            if let Some(temperature) = ignored {
+                self.error_message = Some(format!(
+                    "Saved, but temperature {} won't be used: {} has thinking on, which always runs at 1.0",
+                    temperature, model_name
+                ));
+            }
+        }
+
         // Save API key if provided
         if let Some(env_var) = api_key_env {
             if let Some(input) = &self.model_api_key_input_entity {
@@ -175,6 +175,7 @@ impl ChatApp {
                     if let Some(input) = &this.add_model_api_key_input_entity {
                         input.update(cx, |state, cx| state.set_value("", window, cx));
                     }
+                    this.add_model_no_api_key = false;
                     this.add_model_error = None;
                     cx.notify();
                 }),
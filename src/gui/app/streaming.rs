@@ -40,7 +40,7 @@ use std::time::Duration;
 use gpui::{AsyncApp, Context, WeakEntity};
 use tokio::time::timeout;
 
-use crate::messaging::{AgentEvent, Message, ToolStatus};
+use crate::messaging::{AgentEvent, McpServerEvent, Message, ToolStatus};
 
 use super::ChatApp;
 
@@ -174,6 +174,25 @@ impl ChatApp {
                     self.conversation.append_thinking(&thinking.text);
                 }
             }
+            Message::Reasoning(reasoning) => {
+                // Shared reasoning goes in a thinking section, apart from the answer
+                let mut text = reasoning.reasoning.clone();
+                if let Some(next_steps) = &reasoning.next_steps {
+                    text.push_str(&format!("\n\nNext steps: {}", next_steps));
+                }
+                text.push_str("\n\n");
+
+                let section_id = reasoning
+                    .agent_name
+                    .as_ref()
+                    .and_then(|agent_name| self.active_section_ids.get(agent_name));
+                if let Some(section_id) = section_id {
+                    self.conversation
+                        .append_thinking_in_section(section_id, &text);
+                } else {
+                    self.conversation.append_thinking(&text);
+                }
+            }
             Message::Tool(tool) => {
                 match tool.status {
                     ToolStatus::Executing => {
@@ -205,10 +224,16 @@ impl ChatApp {
                                     true,
                                 );
                             } else {
-                                self.conversation.complete_tool_call(&tool.tool_name, true);
+                                self.conversation.complete_tool_call_with_result(
+                                    &tool.tool_name,
+                                    tool.content.clone(),
+                                );
                             }
                         } else {
-                            self.conversation.complete_tool_call(&tool.tool_name, true);
+                            self.conversation.complete_tool_call_with_result(
+                                &tool.tool_name,
+                                tool.content.clone(),
+                            );
                         }
                         // Update context usage after tool completes
                         self.update_context_usage();
@@ -233,6 +258,29 @@ impl ChatApp {
                     _ => {}
                 }
             }
+            Message::ToolProgress(progress) => {
+                let text = match progress.matches {
+                    Some(matches) => format!("{} files, {} matches", progress.processed, matches),
+                    None => format!("{} entries", progress.processed),
+                };
+                self.conversation.update_tool_progress(&text);
+            }
+            Message::McpServer(server) => match server.event {
+                McpServerEvent::Crashed => {
+                    self.error_message =
+                        Some(format!("MCP server '{}' stopped responding", server.server));
+                }
+                McpServerEvent::Restarted => {
+                    tracing::info!(server = %server.server, attempt = server.attempt, "MCP server restarted");
+                    self.error_message = None;
+                }
+                McpServerEvent::GaveUp => {
+                    self.error_message = Some(format!(
+                        "MCP server '{}' crashed and could not be restarted after {} attempts",
+                        server.server, server.attempt
+                    ));
+                }
+            },
             Message::Agent(agent) => match &agent.event {
                 AgentEvent::Started => {
                     if self.active_agent_stack.is_empty() {
@@ -318,6 +366,7 @@ impl ChatApp {
         // This prevents double-rendering and reduces GPU pressure.
         let should_notify = match &msg {
             Message::TextDelta(_) => false, // Animation timer handles render
+            Message::Metrics(_) => false,   // Nothing displays it
             _ => true,                      // Other message types still notify immediately
         };
 
@@ -3,7 +3,7 @@ use rfd::FileDialog;
 
 use super::super::components::{throughput_chart, ThroughputChartProps};
 use super::{ChatApp, NewConversation};
-use crate::tokens::format_tokens_with_separator;
+use crate::tokens::{format_tokens_with_separator, ContextUsage, UsageLevel};
 
 impl ChatApp {
     pub(super) fn render_toolbar(&self, cx: &Context<Self>) -> impl IntoElement {
@@ -21,19 +21,17 @@ impl ChatApp {
         };
 
         // Context usage calculation
-        let usage_percent = if self.context_window_size > 0 {
-            ((self.context_tokens_used as f64 / self.context_window_size as f64) * 100.0).min(100.0)
-        } else {
-            0.0
+        let usage = ContextUsage {
+            used: self.context_tokens_used,
+            window: self.context_window_size,
         };
+        let usage_percent = usage.percent();
 
         // Color based on usage threshold
-        let (progress_color, label_color) = if usage_percent > 80.0 {
-            (self.theme.error, self.theme.error)
-        } else if usage_percent > 60.0 {
-            (self.theme.warning, self.theme.warning)
-        } else {
-            (self.theme.accent, self.theme.text_muted)
+        let (progress_color, label_color) = match usage.level() {
+            UsageLevel::Critical => (self.theme.error, self.theme.error),
+            UsageLevel::Warning => (self.theme.warning, self.theme.warning),
+            UsageLevel::Normal => (self.theme.accent, self.theme.text_muted),
         };
 
         // Capture for tooltip closure
@@ -107,6 +105,20 @@ impl ChatApp {
                                             .child("Stockpot"),
                                     ),
                             )
+                            // Sandbox badge
+                            .when(self.sandboxed(), |el| {
+                                el.child(
+                                    div()
+                                        .px(px(8.))
+                                        .py(px(2.))
+                                        .rounded(px(6.))
+                                        .border_1()
+                                        .border_color(self.theme.warning)
+                                        .text_color(self.theme.warning)
+                                        .text_size(px(12.))
+                                        .child("🔒 Sandbox"),
+                                )
+                            })
                             // Agent selector
                             .child(
                                 div()
@@ -12,6 +12,7 @@ mod spinner;
 // Note: text_input.rs is no longer used - now using gpui_component::input::Input
 // mod text_input;
 mod throughput_chart;
+mod tool_result;
 mod toolbar;
 mod tooltip;
 
@@ -36,4 +37,5 @@ pub use spinner::{current_spinner_frame, spinner, Spinner};
 
 pub use collapsible::{collapsible, collapsible_display, CollapsibleProps};
 pub use throughput_chart::{throughput_chart, ThroughputChartProps};
+pub use tool_result::render_tool_result;
 pub use tooltip::{MarkdownTooltip, SimpleTooltip};
@@ -0,0 +1,144 @@
+//! Structured tool result rendering
+//!
+//! Renders the `ToolResultContent` attached to a completed tool call:
+//! text and JSON as code blocks, tables as a grid, images inline.
+
+use std::sync::Arc;
+
+use base64::Engine;
+use gpui::{div, img, prelude::*, px, AnyElement, ImageSource, RenderImage, SharedString, Styled};
+use gpui_component::text::markdown;
+use image::Frame;
+
+use crate::gui::theme::Theme;
+use crate::messaging::ToolResultContent;
+
+/// Longest text/JSON result shown before truncating
+const MAX_PREVIEW_LINES: usize = 40;
+/// Most table rows shown before truncating
+const MAX_TABLE_ROWS: usize = 50;
+/// Maximum rendered width of an inline image
+const MAX_IMAGE_WIDTH: f32 = 480.0;
+
+/// Decode base64 image data (any format the `image` crate reads) for gpui
+fn decode_image(data: &str) -> Option<ImageSource> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .ok()?;
+    let mut data = image::load_from_memory(&bytes).ok()?.into_rgba8();
+
+    // Convert from RGBA to BGRA (gpui uses BGRA internally)
+    for pixel in data.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+
+    let render_image = RenderImage::new(vec![Frame::new(data)]);
+    Some(ImageSource::Render(Arc::new(render_image)))
+}
+
+/// Keep the first `MAX_PREVIEW_LINES` lines, noting how many were dropped
+fn truncate_lines(text: &str) -> String {
+    let total = text.lines().count();
+    if total <= MAX_PREVIEW_LINES {
+        return text.to_string();
+    }
+    let kept: Vec<&str> = text.lines().take(MAX_PREVIEW_LINES).collect();
+    format!(
+        "{}\n… {} more lines",
+        kept.join("\n"),
+        total - MAX_PREVIEW_LINES
+    )
+}
+
+fn render_code_block(id: &str, language: &str, text: &str) -> AnyElement {
+    let source = format!("```{}\n{}\n```", language, truncate_lines(text));
+    div()
+        .id(SharedString::from(format!("tool-result-{}", id)))
+        .w_full()
+        .overflow_x_hidden()
+        .child(markdown(&source).selectable(true))
+        .into_any_element()
+}
+
+fn render_table(id: &str, headers: &[String], rows: &[Vec<String>], theme: &Theme) -> AnyElement {
+    let cell = |text: String| {
+        div()
+            .flex_1()
+            .min_w(px(0.))
+            .px(px(8.))
+            .py(px(4.))
+            .overflow_hidden()
+            .text_ellipsis()
+            .child(text)
+    };
+
+    let header_row = div()
+        .flex()
+        .bg(theme.tool_card)
+        .font_weight(gpui::FontWeight::SEMIBOLD)
+        .text_color(theme.text)
+        .children(headers.iter().cloned().map(cell));
+
+    let body_rows = rows.iter().take(MAX_TABLE_ROWS).map(|row| {
+        div()
+            .flex()
+            .border_t_1()
+            .border_color(theme.border)
+            .text_color(theme.text)
+            .children(row.iter().cloned().map(cell))
+    });
+
+    div()
+        .id(SharedString::from(format!("tool-result-{}", id)))
+        .w_full()
+        .flex()
+        .flex_col()
+        .rounded(px(6.))
+        .border_1()
+        .border_color(theme.border)
+        .overflow_hidden()
+        .text_size(px(12.))
+        .child(header_row)
+        .children(body_rows)
+        .when(rows.len() > MAX_TABLE_ROWS, |el| {
+            el.child(
+                div()
+                    .px(px(8.))
+                    .py(px(4.))
+                    .text_color(theme.text_muted)
+                    .child(format!("… {} more rows", rows.len() - MAX_TABLE_ROWS)),
+            )
+        })
+        .into_any_element()
+}
+
+/// Render a structured tool result below its tool call line
+pub fn render_tool_result(content: &ToolResultContent, id: &str, theme: &Theme) -> AnyElement {
+    match content {
+        ToolResultContent::Text(text) => render_code_block(id, "", text),
+        ToolResultContent::Json(value) => {
+            let pretty = serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+            render_code_block(id, "json", &pretty)
+        }
+        ToolResultContent::Table { headers, rows } => render_table(id, headers, rows, theme),
+        ToolResultContent::Image { media_type, data } => {
+            let element_id = SharedString::from(format!("tool-result-{}", id));
+            match decode_image(data) {
+                Some(src) => div()
+                    .id(element_id)
+                    .rounded(px(6.))
+                    .border_1()
+                    .border_color(theme.border)
+                    .overflow_hidden()
+                    .child(img(src).max_w(px(MAX_IMAGE_WIDTH)))
+                    .into_any_element(),
+                None => div()
+                    .id(element_id)
+                    .text_size(px(12.))
+                    .text_color(theme.text_muted)
+                    .child(format!("🖼️ {} (could not be decoded)", media_type))
+                    .into_any_element(),
+            }
+        }
+    }
+}
@@ -2,9 +2,10 @@
 //!
 //! Manages chat messages and tool calls for the GUI.
 
-use super::message::{ChatMessage, ToolCall, ToolCallState};
+use super::message::{ChatMessage, MessageRole, ToolCall, ToolCallState};
 use super::sections::{MessageSection, ToolCallSection};
 use super::tool_display::get_tool_display_info;
+use crate::messaging::ToolResultContent;
 
 /// A conversation (list of messages)
 #[derive(Debug, Clone, Default)]
@@ -22,6 +23,22 @@ impl Conversation {
         self.messages.push(ChatMessage::user(content));
     }
 
+    /// Add a user message showing `content` that was typed as `input`.
+    pub fn add_user_input(&mut self, content: impl Into<String>, input: &str) {
+        let mut message = ChatMessage::user(content);
+        message.input = Some(input.to_string());
+        self.messages.push(message);
+    }
+
+    /// What the user typed for the last user message, if it was typed in
+    /// this window.
+    pub fn last_input(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rfind(|msg| msg.role == MessageRole::User)
+            .and_then(|msg| msg.input.as_deref())
+    }
+
     pub fn start_assistant_message(&mut self) {
         self.messages.push(ChatMessage::assistant());
         self.is_generating = true;
@@ -48,6 +65,22 @@ impl Conversation {
         }
     }
 
+    /// The text of the assistant message being written, without what
+    /// nested agents added to it.
+    pub fn current_reply_text(&self) -> String {
+        match self.messages.last() {
+            Some(msg) if msg.role == MessageRole::Assistant => msg
+                .sections
+                .iter()
+                .filter_map(|section| match section {
+                    MessageSection::Text(text) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect(),
+            _ => String::new(),
+        }
+    }
+
     pub fn finish_current_message(&mut self) {
         if let Some(msg) = self.messages.last_mut() {
             msg.finish_streaming();
@@ -199,6 +232,22 @@ impl Conversation {
         self.is_generating = false;
     }
 
+    /// Remove the last user message and everything after it.
+    /// Returns `false` if there is no user message.
+    pub fn remove_last_exchange(&mut self) -> bool {
+        match self
+            .messages
+            .iter()
+            .rposition(|msg| msg.role == MessageRole::User)
+        {
+            Some(index) => {
+                self.messages.truncate(index);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Append a tool call section to the current message
     /// Returns the section ID for later completion tracking
     pub fn append_tool_call(
@@ -210,7 +259,7 @@ impl Conversation {
         let info = get_tool_display_info(name, &args);
 
         if let Some(msg) = self.messages.last_mut() {
-            let section = ToolCallSection::new(info);
+            let section = ToolCallSection::new(name, info);
             let id = section.id.clone();
             msg.sections.push(MessageSection::ToolCall(section));
             Some(id)
@@ -237,14 +286,48 @@ impl Conversation {
         None
     }
 
-    /// Mark the most recent tool call as completed
-    pub fn complete_tool_call(&mut self, _name: &str, success: bool) {
+    /// The most recent running call of the tool `name`
+    fn running_tool_call_mut(&mut self, name: &str) -> Option<&mut ToolCallSection> {
+        let msg = self.messages.last_mut()?;
+        msg.sections
+            .iter_mut()
+            .rev()
+            .find_map(|section| match section {
+                MessageSection::ToolCall(tool) if tool.is_running && tool.name == name => {
+                    Some(tool)
+                }
+                _ => None,
+            })
+    }
+
+    /// Mark the most recent running call of the tool `name` as completed
+    pub fn complete_tool_call(&mut self, name: &str, success: bool) {
+        if let Some(tool) = self.running_tool_call_mut(name) {
+            tool.complete(success);
+        }
+    }
+
+    /// Complete the most recent running call of the tool `name`
+    /// successfully, keeping its structured result (if the tool provided
+    /// one) for rich rendering
+    pub fn complete_tool_call_with_result(
+        &mut self,
+        name: &str,
+        result: Option<ToolResultContent>,
+    ) {
+        if let Some(tool) = self.running_tool_call_mut(name) {
+            tool.complete(true);
+            tool.result = result;
+        }
+    }
+
+    /// Update the live progress text of the most recent running tool call
+    pub fn update_tool_progress(&mut self, progress: &str) {
         if let Some(msg) = self.messages.last_mut() {
-            // Find the last ToolCall section that's still running
             for section in msg.sections.iter_mut().rev() {
                 if let MessageSection::ToolCall(ref mut tool) = section {
                     if tool.is_running {
-                        tool.complete(success);
+                        tool.progress = Some(progress.to_string());
                         return;
                     }
                 }
@@ -345,6 +428,35 @@ mod tests {
         assert!(section.is_complete);
     }
 
+    #[test]
+    fn test_conversation_remove_last_exchange() {
+        let mut conv = Conversation::new();
+        assert!(!conv.remove_last_exchange());
+
+        conv.add_user_message("first");
+        conv.start_assistant_message();
+        conv.finish_current_message();
+        conv.add_user_message("second");
+        conv.start_assistant_message();
+        conv.finish_current_message();
+
+        assert!(conv.remove_last_exchange());
+        assert_eq!(conv.messages.len(), 2);
+        assert_eq!(conv.messages[0].content, "first");
+    }
+
+    #[test]
+    fn test_conversation_last_input() {
+        let mut conv = Conversation::new();
+        conv.add_user_message("restored from a session");
+        assert_eq!(conv.last_input(), None);
+
+        conv.add_user_input("see @notes.md\n\n📎 1 attachment(s)", "see @notes.md");
+        conv.start_assistant_message();
+        conv.finish_current_message();
+        assert_eq!(conv.last_input(), Some("see @notes.md"));
+    }
+
     #[test]
     fn test_conversation_start_nested_agent_no_message() {
         let mut conv = Conversation::new();
@@ -484,6 +596,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_complete_tool_call_with_result() {
+        let mut conv = Conversation::new();
+        conv.start_assistant_message();
+
+        conv.append_tool_call("list_agents", None);
+        let table = ToolResultContent::Table {
+            headers: vec!["name".to_string()],
+            rows: vec![vec!["stockpot".to_string()]],
+        };
+        conv.complete_tool_call_with_result("list_agents", Some(table.clone()));
+
+        let msg = conv.messages.last().unwrap();
+        if let Some(MessageSection::ToolCall(tool)) = msg.sections.iter().find(|s| s.is_tool_call())
+        {
+            assert!(!tool.is_running);
+            assert_eq!(tool.succeeded, Some(true));
+            assert_eq!(tool.result, Some(table));
+        } else {
+            panic!("Expected ToolCall section");
+        }
+    }
+
+    #[test]
+    fn test_complete_tool_call_with_result_finds_the_tool_by_name() {
+        let mut conv = Conversation::new();
+        conv.start_assistant_message();
+
+        // Parallel calls, finishing in a different order than they started
+        conv.append_tool_call("list_agents", None);
+        conv.append_tool_call("read_file", None);
+        let table = ToolResultContent::Table {
+            headers: vec!["name".to_string()],
+            rows: vec![vec!["stockpot".to_string()]],
+        };
+        conv.complete_tool_call_with_result("list_agents", Some(table.clone()));
+
+        let msg = conv.messages.last().unwrap();
+        let tools: Vec<_> = msg
+            .sections
+            .iter()
+            .filter_map(|s| match s {
+                MessageSection::ToolCall(tool) => Some(tool),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tools[0].name, "list_agents");
+        assert!(!tools[0].is_running);
+        assert_eq!(tools[0].result, Some(table));
+        assert!(tools[1].is_running);
+        assert!(tools[1].result.is_none());
+    }
+
     #[test]
     fn test_complete_tool_call_failure() {
         let mut conv = Conversation::new();
@@ -607,6 +772,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_update_tool_progress_targets_running_tool() {
+        let mut conv = Conversation::new();
+        conv.start_assistant_message();
+        conv.append_tool_call("grep", None);
+
+        conv.update_tool_progress("500 files, 3 matches");
+
+        let msg = conv.messages.last().unwrap();
+        let Some(MessageSection::ToolCall(tool)) = msg.sections.last() else {
+            panic!("Expected ToolCall section");
+        };
+        assert_eq!(tool.progress.as_deref(), Some("500 files, 3 matches"));
+
+        conv.complete_tool_call("grep", true);
+        let msg = conv.messages.last().unwrap();
+        let Some(MessageSection::ToolCall(tool)) = msg.sections.last() else {
+            panic!("Expected ToolCall section");
+        };
+        assert!(tool.progress.is_none());
+    }
+
     // ==========================================================================
     // Thinking section tests
     // ==========================================================================
@@ -41,6 +41,9 @@ pub struct ChatMessage {
     pub sections: Vec<MessageSection>,
     pub tool_calls: Vec<ToolCall>,
     pub is_streaming: bool,
+    /// What the user typed, before `@file` references were expanded
+    /// (None for messages rebuilt from a saved session)
+    pub input: Option<String>,
 }
 
 impl ChatMessage {
@@ -53,6 +56,7 @@ impl ChatMessage {
             sections: vec![MessageSection::Text(content_str)],
             tool_calls: vec![],
             is_streaming: false,
+            input: None,
         }
     }
 
@@ -64,6 +68,7 @@ impl ChatMessage {
             sections: vec![],
             tool_calls: vec![],
             is_streaming: true,
+            input: None,
         }
     }
 
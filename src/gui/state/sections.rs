@@ -3,6 +3,7 @@
 //! Provides section abstractions for collapsible nested agent output.
 
 use super::tool_display::ToolDisplayInfo;
+use crate::messaging::ToolResultContent;
 
 /// Content item within a nested agent section
 #[derive(Debug, Clone)]
@@ -290,27 +291,37 @@ impl Default for ThinkingSection {
 pub struct ToolCallSection {
     /// Unique ID for this section
     pub id: String,
+    /// Name of the tool that was called
+    pub name: String,
     /// The tool display info (verb + subject)
     pub info: ToolDisplayInfo,
     /// Whether the tool call is still running
     pub is_running: bool,
     /// Whether the tool call succeeded (None if still running)
     pub succeeded: Option<bool>,
+    /// Live progress text while running (e.g. "1500 files, 12 matches")
+    pub progress: Option<String>,
+    /// Structured result, for tools that provide one
+    pub result: Option<ToolResultContent>,
 }
 
 impl ToolCallSection {
-    pub fn new(info: ToolDisplayInfo) -> Self {
+    pub fn new(name: &str, info: ToolDisplayInfo) -> Self {
         Self {
             id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
             info,
             is_running: true,
             succeeded: None,
+            progress: None,
+            result: None,
         }
     }
 
     pub fn complete(&mut self, success: bool) {
         self.is_running = false;
         self.succeeded = Some(success);
+        self.progress = None;
     }
 }
 
@@ -618,8 +629,9 @@ mod tests {
     #[test]
     fn test_tool_call_section_new() {
         let info = ToolDisplayInfo::new("Edited", "src/main.rs");
-        let section = ToolCallSection::new(info);
+        let section = ToolCallSection::new("edit_file", info);
         assert!(!section.id.is_empty());
+        assert_eq!(section.name, "edit_file");
         assert!(section.is_running);
         assert!(section.succeeded.is_none());
         assert_eq!(section.info.verb, "Edited");
@@ -629,14 +641,14 @@ mod tests {
     #[test]
     fn test_tool_call_section_complete() {
         let info = ToolDisplayInfo::new("Read", "file.rs");
-        let mut section = ToolCallSection::new(info);
+        let mut section = ToolCallSection::new("read_file", info);
 
         section.complete(true);
         assert!(!section.is_running);
         assert_eq!(section.succeeded, Some(true));
 
         let info2 = ToolDisplayInfo::new("Deleted", "old.rs");
-        let mut section2 = ToolCallSection::new(info2);
+        let mut section2 = ToolCallSection::new("delete_file", info2);
         section2.complete(false);
         assert_eq!(section2.succeeded, Some(false));
     }
@@ -47,7 +47,12 @@ pub fn get_tool_display_info(name: &str, args: &serde_json::Value) -> ToolDispla
                 .get("file_path")
                 .and_then(|v| v.as_str())
                 .unwrap_or("?");
-            ToolDisplayInfo::new("Edited", path)
+            let dry_run = args
+                .get("dry_run")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let verb = if dry_run { "Previewed" } else { "Edited" };
+            ToolDisplayInfo::new(verb, path)
         }
         "delete_file" => {
             let path = args
@@ -56,6 +61,35 @@ pub fn get_tool_display_info(name: &str, args: &serde_json::Value) -> ToolDispla
                 .unwrap_or("?");
             ToolDisplayInfo::new("Deleted", path)
         }
+        "move_file" => {
+            let from = args.get("from").and_then(|v| v.as_str()).unwrap_or("?");
+            let to = args.get("to").and_then(|v| v.as_str()).unwrap_or("?");
+            ToolDisplayInfo::new("Moved", format!("{} → {}", from, to))
+        }
+        "stat_file" => {
+            let path = args
+                .get("file_path")
+                .and_then(|v| v.as_str())
+                .unwrap_or("?");
+            ToolDisplayInfo::new("Checked", path)
+        }
+        "make_directory" => {
+            let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("?");
+            ToolDisplayInfo::new("Created directory", path)
+        }
+        "replace_in_files" => {
+            let pattern = args.get("pattern").and_then(|v| v.as_str()).unwrap_or("?");
+            let dir = args
+                .get("directory")
+                .and_then(|v| v.as_str())
+                .unwrap_or(".");
+            let dry_run = args
+                .get("dry_run")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+            let verb = if dry_run { "Previewed" } else { "Replaced" };
+            ToolDisplayInfo::new(verb, format!("'{}' in {}", pattern, dir))
+        }
         "grep" => {
             let pattern = args
                 .get("pattern")
@@ -131,6 +165,13 @@ mod tests {
         assert_eq!(info, ToolDisplayInfo::new("Edited", "test.py"));
     }
 
+    #[test]
+    fn test_get_tool_display_info_edit_file_dry_run() {
+        let args = serde_json::json!({"file_path": "test.py", "dry_run": true});
+        let info = get_tool_display_info("edit_file", &args);
+        assert_eq!(info, ToolDisplayInfo::new("Previewed", "test.py"));
+    }
+
     #[test]
     fn test_get_tool_display_info_delete_file() {
         let args = serde_json::json!({"file_path": "old.txt"});
@@ -0,0 +1,603 @@
+//! Non-GUI entry points: shared setup and single-prompt mode (`spot -p`,
+//! `spot -p -`, `spot --compose` or `spot -p < prompt.txt`).
+//!
+//! Ctrl+C while the agent works stops the run and keeps what it had written
+//! so far; a second Ctrl+C during cleanup quits at once.
+//!
+//! [`Headless`] wires up the database, registries and MCP servers the same
+//! way the GUI does, for `spot -p` and `spot --bridge`.
+
+use std::io::{self, IsTerminal, Read};
+use std::process::Command;
+use std::sync::Arc;
+
+use nu_ansi_term::Color;
+use serde::Serialize;
+use serdes_ai_core::messages::ImageMediaType;
+
+use crate::agents::{
+    sandbox_mode_enabled, AgentExecutor, AgentManager, ApprovalPolicy, CancelToken, DenyAll,
+    ExecuteContext, Interactive,
+};
+use crate::config::Settings;
+use crate::db::Database;
+use crate::mcp::{McpManager, RestartPolicy};
+use crate::messaging::{
+    BridgeRenderer, Message, MessageBus, MessageRenderer, MessageSender, RenderStyle,
+    SpinnerConfig, TerminalRenderer, ToolStatus,
+};
+use crate::models::settings::SamplingOverride;
+use crate::models::ModelRegistry;
+use crate::tokens::{estimate_tokens, ContextUsage, UsageLevel};
+use crate::tools::{expand_file_references, SpotToolRegistry, UndoJournal};
+
+/// Per-invocation options shared by the headless modes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeadlessOptions {
+    /// One-off temperature/top_p override
+    pub sampling: SamplingOverride,
+    /// Read-only tools only (`--sandbox`)
+    pub sandbox: bool,
+    /// No tools at all, for plain chat (`--no-tools`)
+    pub no_tools: bool,
+    /// Who approves tool calls that change things (`--approve`)
+    pub approval: ApprovalMode,
+    /// No spinner in text output (`--no-spinner`)
+    pub no_spinner: bool,
+    /// Text output without markdown styling or colors (`--plain`)
+    pub plain: bool,
+    /// Show sub-agent output nested under the caller (`--expand-agents`)
+    pub expand_agents: bool,
+}
+
+/// Who approves tool calls that aren't read-only (`--approve`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ApprovalMode {
+    /// Run them without asking
+    #[default]
+    Auto,
+    /// Ask on the terminal before each one
+    Ask,
+    /// Refuse them all
+    Deny,
+}
+
+impl ApprovalMode {
+    /// The policy runs are given, `None` to allow everything.
+    pub fn policy(self) -> Option<Arc<dyn ApprovalPolicy>> {
+        match self {
+            ApprovalMode::Auto => None,
+            ApprovalMode::Ask => Some(Arc::new(Interactive)),
+            ApprovalMode::Deny => Some(Arc::new(DenyAll)),
+        }
+    }
+}
+
+/// Everything an agent run needs outside the GUI.
+pub struct Headless {
+    pub db: Database,
+    pub registry: ModelRegistry,
+    pub agents: AgentManager,
+    pub tool_registry: SpotToolRegistry,
+    pub mcp_manager: Arc<McpManager>,
+    pub options: HeadlessOptions,
+}
+
+impl Headless {
+    /// Open the database and start enabled MCP servers, publishing to `bus`.
+    ///
+    /// Announces sandbox mode on stderr when it's on, from `--sandbox` or
+    /// the `sandbox_mode` setting, and `--no-tools`.
+    pub async fn start(bus: MessageSender, options: HeadlessOptions) -> anyhow::Result<Self> {
+        let db = Database::open()?;
+        db.migrate()?;
+
+        if options.sandbox || sandbox_mode_enabled(&db) {
+            eprintln!("🔒 Sandbox mode: read-only tools only, nothing will be modified");
+        }
+        if options.no_tools {
+            eprintln!("💬 Tools disabled: the model can only reply with text");
+        }
+
+        let registry = ModelRegistry::load_from_db(&db).unwrap_or_default();
+        let agents = AgentManager::new();
+        let tool_registry = SpotToolRegistry::new()
+            .with_bus(bus.clone())
+            .with_shell_confinement(Settings::new(&db).confine_shell());
+        let mcp_manager = Arc::new(McpManager::new().with_api_keys_from_db(&db));
+
+        if mcp_manager.config().enabled_servers().next().is_some() {
+            if let Err(e) = mcp_manager.start_all().await {
+                tracing::error!(error = %e, "Failed to start MCP servers");
+            }
+            Arc::clone(&mcp_manager).spawn_supervisor(RestartPolicy::default(), Some(bus));
+        }
+
+        Ok(Self {
+            db,
+            registry,
+            agents,
+            tool_registry,
+            mcp_manager,
+            options,
+        })
+    }
+
+    /// An executor with this invocation's options applied, recording its
+    /// file changes for `spot undo`.
+    pub fn executor(&self) -> AgentExecutor<'_> {
+        let executor = AgentExecutor::new(&self.db, &self.registry)
+            .with_sampling_override(self.options.sampling)
+            .with_sandbox(self.options.sandbox)
+            .with_no_tools(self.options.no_tools)
+            .with_undo(UndoJournal::new().begin());
+        match self.options.approval.policy() {
+            Some(policy) => executor.with_approval_policy(policy),
+            None => executor,
+        }
+    }
+
+    /// The model an agent runs with: its pin, else the default model.
+    pub fn model_for(&self, agent_name: &str) -> String {
+        let settings = Settings::new(&self.db);
+        settings
+            .get_agent_pinned_model(agent_name)
+            .unwrap_or_else(|| settings.model())
+    }
+
+    /// Context window of `model`, assuming 128k for models missing from
+    /// the registry.
+    pub fn context_length(&self, model: &str) -> usize {
+        self.registry
+            .get(model)
+            .map(|config| config.context_length)
+            .unwrap_or(128_000)
+    }
+
+    /// Whether `model` accepts images. Models missing from the registry are
+    /// assumed to, as most current ones do.
+    pub fn supports_vision(&self, model: &str) -> bool {
+        self.registry
+            .get(model)
+            .map(|config| config.supports_vision)
+            .unwrap_or(true)
+    }
+
+    /// Stop MCP servers.
+    pub async fn shutdown(&self) {
+        if let Err(e) = self.mcp_manager.stop_all().await {
+            tracing::warn!(error = %e, "Failed to stop MCP servers");
+        }
+    }
+}
+
+/// How `spot -p` reports the run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text, streamed as it arrives
+    #[default]
+    Text,
+    /// One JSON object once the run finishes
+    Json,
+    /// Bridge protocol events, one per line
+    Ndjson,
+}
+
+/// Returned by [`run_single_prompt`] when Ctrl+C stopped the run.
+#[derive(Debug, thiserror::Error)]
+#[error("Run cancelled")]
+pub struct Cancelled;
+
+/// A tool call made during the run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolCallSummary {
+    pub name: String,
+    pub status: ToolStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The `--output json` result of a single prompt.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PromptSummary {
+    pub output: String,
+    pub tool_calls: Vec<ToolCallSummary>,
+    pub run_id: String,
+    /// Estimated tokens in the final message history
+    pub token_estimate: usize,
+    /// Stopped with Ctrl+C; `output` is the text streamed until then
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+}
+
+impl PromptSummary {
+    /// Record finished tool calls from a bus message.
+    pub fn record(&mut self, msg: &Message) {
+        if let Message::Tool(tool) = msg {
+            if matches!(tool.status, ToolStatus::Completed | ToolStatus::Failed) {
+                self.tool_calls.push(ToolCallSummary {
+                    name: tool.tool_name.clone(),
+                    status: tool.status.clone(),
+                    error: tool.error.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Work out the single-prompt text, if this invocation is one.
+///
+/// `-p TEXT` is used as-is and `-p -` (or a bare `-p`) reads the prompt
+/// from piped stdin. Without `-p` stdin is never touched, so a pipe that
+/// stays open (an IDE or process manager launching the GUI) can't block
+/// startup.
+pub fn resolve_prompt(
+    arg: Option<&str>,
+    stdin_is_terminal: bool,
+    mut stdin: impl Read,
+) -> io::Result<Option<String>> {
+    let mut read_stdin = || -> io::Result<String> {
+        let mut text = String::new();
+        stdin.read_to_string(&mut text)?;
+        Ok(text.trim().to_string())
+    };
+
+    match arg {
+        Some("-") if stdin_is_terminal => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "-p - reads the prompt from a pipe or file, but stdin is a terminal",
+        )),
+        Some("-") => {
+            let text = read_stdin()?;
+            if text.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no prompt given on stdin",
+                ));
+            }
+            Ok(Some(text))
+        }
+        Some(text) => Ok(Some(text.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// The editor to compose prompts in: `$VISUAL`, else `$EDITOR`, else `vi`.
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Write a prompt in `editor`, starting from `initial`.
+///
+/// The editor runs on a temp file; whatever it saves is the prompt.
+/// `editor` may carry arguments (e.g. `code --wait`). Quitting without
+/// saving, an editor error exit or an empty file cancel with `Ok(None)`.
+pub fn compose_in_editor(editor: &str, initial: &str) -> io::Result<Option<String>> {
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no editor configured"))?;
+
+    let path = std::env::temp_dir().join(format!("spot-prompt-{}.md", uuid::Uuid::new_v4()));
+    std::fs::write(&path, initial)?;
+    let written = std::fs::metadata(&path)?.modified()?;
+
+    let result = Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .and_then(|status| {
+            let saved = std::fs::metadata(&path)?.modified()? != written;
+            let text = std::fs::read_to_string(&path)?;
+            let changed = saved || text != initial;
+            let text = text.trim().to_string();
+            Ok((status.success() && changed && !text.is_empty()).then_some(text))
+        });
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Run the current agent once on `prompt` and report it in `format`.
+///
+/// `images` are sent along with any the prompt attaches with `@image:`.
+pub async fn run_single_prompt(
+    prompt: &str,
+    images: Vec<(Vec<u8>, ImageMediaType)>,
+    format: OutputFormat,
+    options: HeadlessOptions,
+) -> anyhow::Result<()> {
+    let bus = MessageBus::new();
+    let mut receiver = bus.subscribe();
+    let env = Headless::start(bus.sender(), options).await?;
+
+    let mut renderer: Option<Box<dyn MessageRenderer>> = match format {
+        OutputFormat::Text => {
+            let mut terminal = TerminalRenderer::new();
+            if options.no_spinner {
+                terminal = terminal.with_spinner(SpinnerConfig::disabled());
+            }
+            let mut style = if options.plain {
+                RenderStyle::plain()
+            } else {
+                terminal.style()
+            };
+            style.expand_agents = options.expand_agents;
+            Some(Box::new(terminal.with_style(style)))
+        }
+        OutputFormat::Ndjson => Some(Box::new(BridgeRenderer::new())),
+        OutputFormat::Json => None,
+    };
+    if let Some(renderer) = renderer.as_mut() {
+        renderer.start()?;
+    }
+
+    let agent_name = env.agents.current_name();
+    let agent = env
+        .agents
+        .get(&agent_name)
+        .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_name))?;
+    let model = env.model_for(&agent_name);
+
+    let mut expanded = expand_file_references(prompt, &std::env::current_dir()?);
+    expanded.images.splice(0..0, images);
+    for (reference, reason) in &expanded.failed {
+        bus.sender()
+            .warning(format!("Couldn't read @{}: {}", reference, reason));
+    }
+
+    if !expanded.images.is_empty() && !env.supports_vision(&model) {
+        env.shutdown().await;
+        anyhow::bail!(
+            "Model '{}' doesn't support images; pick a vision model to use @image: references",
+            model
+        );
+    }
+
+    let cancel = CancelToken::new();
+    let executor = env
+        .executor()
+        .with_bus(bus.sender())
+        .with_cancellation(cancel.clone());
+    let context = ExecuteContext {
+        tool_registry: &env.tool_registry,
+        mcp_manager: &env.mcp_manager,
+    };
+    let run = executor.execute_with_images(
+        agent,
+        &model,
+        &expanded.prompt,
+        &expanded.images,
+        None,
+        &context,
+    );
+    tokio::pin!(run);
+
+    let mut summary = PromptSummary::default();
+    let mut partial = String::new();
+    let mut handle = |msg: Message, summary: &mut PromptSummary| -> std::io::Result<()> {
+        summary.record(&msg);
+        if let Message::TextDelta(delta) = &msg {
+            if delta.agent_name.as_deref() == Some(agent_name.as_str()) {
+                partial.push_str(&delta.text);
+            }
+        }
+        match renderer.as_mut() {
+            Some(renderer) => renderer.render(&msg),
+            None => Ok(()),
+        }
+    };
+
+    let interrupt = tokio::signal::ctrl_c();
+    tokio::pin!(interrupt);
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break Some(result),
+            Ok(msg) = receiver.recv() => handle(msg, &mut summary)?,
+            _ = &mut interrupt => {
+                // The run is no longer polled; the token stops in-flight MCP calls
+                cancel.cancel();
+                bus.sender().warning("Run cancelled");
+                break None;
+            }
+        }
+    };
+    while let Ok(Some(msg)) = receiver.try_recv() {
+        handle(msg, &mut summary)?;
+    }
+    let context_window = Settings::new(&env.db)
+        .show_context_usage()
+        .then(|| env.context_length(&model));
+    tokio::select! {
+        _ = env.shutdown() => {}
+        _ = tokio::signal::ctrl_c() => return Err(Cancelled.into()),
+    }
+
+    let Some(result) = result else {
+        if format == OutputFormat::Json {
+            summary.output = partial;
+            summary.cancelled = true;
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        }
+        return Err(Cancelled.into());
+    };
+    let result = result?;
+    if let (OutputFormat::Text, Some(window)) = (format, context_window) {
+        let color =
+            !options.plain && std::env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal();
+        eprintln!(
+            "{}",
+            usage_line(ContextUsage::of(&result.messages, window), color)
+        );
+    }
+    if format == OutputFormat::Json {
+        summary.output = result.output;
+        summary.run_id = result.run_id;
+        summary.token_estimate = estimate_tokens(&result.messages);
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    }
+    Ok(())
+}
+
+/// Context usage, colored as it nears the limit when `color` is set.
+fn usage_line(usage: ContextUsage, color: bool) -> String {
+    let line = format!("context {}", usage);
+    if !color {
+        return line;
+    }
+    let color = match usage.level() {
+        UsageLevel::Normal => Color::DarkGray,
+        UsageLevel::Warning => Color::Yellow,
+        UsageLevel::Critical => Color::Red,
+    };
+    color.paint(line).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_summary_records_finished_tool_calls() {
+        let mut summary = PromptSummary::default();
+        summary.record(&Message::tool_executing("read_file", None));
+        summary.record(&Message::tool_completed("read_file"));
+        summary.record(&Message::tool_failed("grep", "bad regex"));
+        summary.record(&Message::info("not a tool"));
+
+        assert_eq!(
+            summary.tool_calls,
+            vec![
+                ToolCallSummary {
+                    name: "read_file".to_string(),
+                    status: ToolStatus::Completed,
+                    error: None,
+                },
+                ToolCallSummary {
+                    name: "grep".to_string(),
+                    status: ToolStatus::Failed,
+                    error: Some("bad regex".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_prompt_inline() {
+        let prompt = resolve_prompt(Some("explain"), true, io::empty()).unwrap();
+        assert_eq!(prompt.as_deref(), Some("explain"));
+    }
+
+    #[test]
+    fn test_resolve_prompt_dash_reads_stdin() {
+        let stdin = io::Cursor::new("summarize spec.md\n");
+        let prompt = resolve_prompt(Some("-"), false, stdin).unwrap();
+        assert_eq!(prompt.as_deref(), Some("summarize spec.md"));
+
+        let err = resolve_prompt(Some("-"), false, io::Cursor::new("  \n")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_resolve_prompt_without_flag_does_not_read() {
+        struct Unreadable;
+        impl Read for Unreadable {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                panic!("stdin must not be read without -p");
+            }
+        }
+        // A terminal, or a pipe that may never close (launched from an IDE)
+        assert!(resolve_prompt(None, true, Unreadable).unwrap().is_none());
+        assert!(resolve_prompt(None, false, Unreadable).unwrap().is_none());
+
+        let err = resolve_prompt(Some("-"), true, Unreadable).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(unix)]
+    fn fake_editor(dir: &std::path::Path, script: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("editor.sh");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.display().to_string()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compose_in_editor_returns_saved_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let editor = fake_editor(dir.path(), "printf '  line one\\nline two\\n' > \"$1\"");
+        let prompt = compose_in_editor(&editor, "").unwrap();
+        assert_eq!(prompt.as_deref(), Some("line one\nline two"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compose_in_editor_cancels() {
+        let dir = tempfile::tempdir().unwrap();
+        // Quit without saving
+        assert!(compose_in_editor("true", "draft").unwrap().is_none());
+        // Editor error exit, like vim's :cq
+        let editor = fake_editor(dir.path(), "echo changed > \"$1\"; exit 1");
+        assert!(compose_in_editor(&editor, "").unwrap().is_none());
+        // Saved empty
+        let editor = fake_editor(dir.path(), ": > \"$1\"");
+        assert!(compose_in_editor(&editor, "draft").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_usage_line_color_is_optional() {
+        let usage = ContextUsage {
+            used: 950,
+            window: 1000,
+        };
+        let plain = usage_line(usage, false);
+        assert!(plain.starts_with("context "));
+        assert!(!plain.contains('\x1b'));
+        assert!(usage_line(usage, true).contains('\x1b'));
+    }
+
+    #[test]
+    fn test_approval_mode_policy() {
+        use crate::agents::{Decision, ToolCall};
+
+        assert!(ApprovalMode::Auto.policy().is_none());
+        let args = serde_json::json!({"path": "notes.txt"});
+        let call = ToolCall {
+            tool_name: "delete_file",
+            args: &args,
+        };
+        let policy = ApprovalMode::Deny.policy().unwrap();
+        assert!(matches!(policy.approve(&call), Decision::Deny(_)));
+        assert!(ApprovalMode::Ask.policy().is_some());
+    }
+
+    #[test]
+    fn test_prompt_summary_json_shape() {
+        let mut summary = PromptSummary {
+            output: "done".to_string(),
+            run_id: "run-1".to_string(),
+            token_estimate: 42,
+            ..Default::default()
+        };
+        summary.record(&Message::tool_completed("list_files"));
+
+        let value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(value["output"], "done");
+        assert_eq!(value["run_id"], "run-1");
+        assert_eq!(value["token_estimate"], 42);
+        assert_eq!(value["tool_calls"][0]["name"], "list_files");
+        assert_eq!(value["tool_calls"][0]["status"], "completed");
+        assert!(value["tool_calls"][0].get("error").is_none());
+        assert!(value.get("cancelled").is_none());
+
+        summary.cancelled = true;
+        let value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(value["cancelled"], true);
+    }
+}
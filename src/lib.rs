@@ -3,9 +3,15 @@
 //! This crate provides the core functionality for the Stockpot GUI application.
 
 pub mod agents;
+pub mod atomic_file;
 pub mod auth;
+pub mod batch;
+pub mod bridge;
+pub mod clipboard;
 pub mod config;
 pub mod db;
+pub mod doctor;
+pub mod headless;
 pub mod mcp;
 pub mod messaging;
 pub mod models;
@@ -2,7 +2,16 @@
 //!
 //! A GUI application for AI-assisted coding.
 
-use clap::Parser;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use stockpot::auth::OAuthProvider;
+use stockpot::headless::{
+    compose_in_editor, editor_command, resolve_prompt, ApprovalMode, Cancelled, HeadlessOptions,
+    OutputFormat,
+};
+use stockpot::models::settings::SamplingOverride;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 /// Stockpot - Your AI coding companion 🍲
@@ -23,9 +32,261 @@ pub struct Args {
     #[arg(short = 'v', long)]
     pub verbose: bool,
 
+    /// Switch to a saved settings profile before starting (stays active)
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
     /// Skip checking for new versions
     #[arg(long)]
     pub skip_update_check: bool,
+
+    /// Serve a headless NDJSON session over stdin/stdout (for editor integrations)
+    #[arg(long)]
+    pub bridge: bool,
+
+    /// Open the GUI on the most recently updated session
+    #[arg(long, conflicts_with_all = ["bridge", "prompt", "compose", "batch"])]
+    pub resume: bool,
+
+    /// Run a single prompt with the current agent and exit (bare -p or "-" reads piped stdin)
+    #[arg(
+        short = 'p',
+        long,
+        conflicts_with = "bridge",
+        num_args = 0..=1,
+        default_missing_value = "-"
+    )]
+    pub prompt: Option<String>,
+
+    /// Write the prompt in $VISUAL/$EDITOR, then run it like -p
+    #[arg(long, conflicts_with_all = ["bridge", "prompt", "batch"])]
+    pub compose: bool,
+
+    /// Attach the image on the clipboard to the prompt (-p, --compose or piped stdin)
+    #[arg(long, conflicts_with_all = ["bridge", "batch"])]
+    pub paste_image: bool,
+
+    /// Output format for single-prompt mode
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// Don't animate a spinner while the agent works (text output)
+    #[arg(long)]
+    pub no_spinner: bool,
+
+    /// Print text output as plain text, without markdown styling or colors
+    #[arg(long)]
+    pub plain: bool,
+
+    /// Show what sub-agents write, indented under the caller, not just a summary (text output)
+    #[arg(long)]
+    pub expand_agents: bool,
+
+    /// Run every prompt in a file (JSON array or one per line) and report the results
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["bridge", "prompt"])]
+    pub batch: Option<PathBuf>,
+
+    /// Carry one session across all batch prompts instead of starting fresh each time
+    #[arg(long, requires = "batch")]
+    pub batch_shared: bool,
+
+    /// Write the batch report to a file instead of stdout
+    #[arg(long, value_name = "FILE", requires = "batch")]
+    pub batch_output: Option<PathBuf>,
+
+    /// Read-only tools only: nothing can be edited, deleted or run
+    #[arg(long)]
+    pub sandbox: bool,
+
+    /// Plain chat: the agent gets no tools at all (-p, --batch, --bridge)
+    #[arg(long)]
+    pub no_tools: bool,
+
+    /// Tool calls that change things: run them, ask first, or refuse (-p, --batch, --bridge)
+    #[arg(long, value_enum, default_value_t = ApprovalMode::Auto)]
+    pub approve: ApprovalMode,
+
+    /// Sampling temperature for this run only, 0.0-2.0 (not saved)
+    #[arg(long)]
+    pub temperature: Option<f32>,
+
+    /// Top-p for this run only, 0.0-1.0 (not saved)
+    #[arg(long)]
+    pub top_p: Option<f32>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Diagnose common environment and configuration problems
+    Doctor,
+    /// Manage configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Connect, check and disconnect OAuth providers (ChatGPT, Claude Code)
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommand,
+    },
+    /// Export a saved session as a shareable transcript
+    Export {
+        /// Session name
+        session: String,
+
+        /// Transcript format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Html)]
+        format: ExportFormat,
+
+        /// Write to a file instead of stdout
+        #[arg(short = 'o', long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// List saved sessions, most recent first
+    Sessions {
+        /// Only sessions with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Tag a saved session (or untag it with --remove)
+    Tag {
+        /// Session name
+        session: String,
+
+        /// Tag, like frontend or infra
+        tag: String,
+
+        /// Remove the tag instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Pin a saved session so cleanup never deletes it (or unpin it with --remove)
+    Pin {
+        /// Session name
+        session: String,
+
+        /// Unpin instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Salvage the readable parts of a damaged session into a new one
+    Recover {
+        /// Session that no longer loads
+        session: String,
+
+        /// Name for the recovered copy (default: <session>-recovered)
+        #[arg(long, value_name = "NAME")]
+        save_as: Option<String>,
+    },
+    /// Find saved sessions whose messages mention a word or phrase
+    Search {
+        /// Text to look for, ignoring case
+        query: String,
+    },
+    /// Copy a saved session under a new name, to try another direction from it
+    Fork {
+        /// Session to copy
+        source: String,
+
+        /// Name for the copy
+        name: String,
+    },
+    /// Show a model's capabilities (thinking, vision, tools, context length)
+    ModelInfo {
+        /// Model name, as listed in the model picker
+        name: String,
+
+        /// Test tool calling on a custom endpoint with a small request
+        #[arg(long)]
+        probe: bool,
+    },
+    /// Restore the files changed by the most recent agent run
+    Undo,
+    /// Show what recent agent runs changed, as a unified diff
+    Diff {
+        /// Only show changes to this file
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Standalone styled HTML page
+    Html,
+    /// Raw session data
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Import legacy file-based config into the database
+    Migrate,
+    /// Show every setting with its current value and default
+    List,
+    /// Show one setting
+    Get {
+        /// Setting key
+        key: String,
+    },
+    /// Change a setting, checking the key and value
+    Set {
+        /// Setting key, e.g. show_reasoning or model_settings.gpt-4o.temperature
+        key: String,
+        value: String,
+    },
+    /// List the settings that can be changed
+    Keys,
+    /// Save, switch and remove named settings profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommand,
+    },
+    /// Encrypt or decrypt stored API keys and OAuth tokens
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthCommand {
+    /// Show which providers are connected and when their tokens expire
+    Status,
+    /// Sign in to a provider in the browser and register its models
+    Login {
+        /// chatgpt or claude-code
+        provider: OAuthProvider,
+    },
+    /// Remove a provider's stored tokens
+    Logout {
+        /// chatgpt or claude-code
+        provider: OAuthProvider,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommand {
+    /// List saved profiles, marking the active one
+    List,
+    /// Save the current settings as a profile
+    Save { name: String },
+    /// Switch to a saved profile
+    Use { name: String },
+    /// Delete a saved profile
+    Delete { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SecretsCommand {
+    /// Show whether stored credentials are encrypted
+    Status,
+    /// Encrypt stored credentials with a key kept in the OS keychain
+    Encrypt,
+    /// Store credentials in plain text again
+    Decrypt,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -36,7 +297,574 @@ fn main() -> anyhow::Result<()> {
         std::env::set_current_dir(cwd)?;
     }
 
-    run_gui(args)
+    if let Some(profile) = &args.profile {
+        run_profile(&ProfileCommand::Use {
+            name: profile.clone(),
+        })?;
+    }
+
+    // Model clients read the proxy settings from the environment; set it
+    // while this is still the only thread
+    export_network_settings();
+
+    match &args.command {
+        Some(Command::Config {
+            action: ConfigCommand::Migrate,
+        }) => run_config_migrate(),
+        Some(Command::Config {
+            action: ConfigCommand::List,
+        }) => run_config_list(None),
+        Some(Command::Config {
+            action: ConfigCommand::Get { key },
+        }) => run_config_list(Some(key)),
+        Some(Command::Config {
+            action: ConfigCommand::Set { key, value },
+        }) => run_config_set(key, value),
+        Some(Command::Config {
+            action: ConfigCommand::Keys,
+        }) => run_config_keys(),
+        Some(Command::Config {
+            action: ConfigCommand::Profile { action },
+        }) => run_profile(action),
+        Some(Command::Config {
+            action: ConfigCommand::Secrets { action },
+        }) => run_secrets(action),
+        Some(Command::Auth { action }) => run_auth(action),
+        Some(Command::Doctor) => run_doctor(),
+        Some(Command::Export {
+            session,
+            format,
+            output,
+        }) => run_export(session, *format, output.as_deref()),
+        Some(Command::Sessions { tag }) => run_sessions(tag.as_deref()),
+        Some(Command::Tag {
+            session,
+            tag,
+            remove,
+        }) => run_tag(session, tag, *remove),
+        Some(Command::Pin { session, remove }) => run_pin(session, *remove),
+        Some(Command::Recover { session, save_as }) => run_recover(session, save_as.as_deref()),
+        Some(Command::Search { query }) => run_search(query),
+        Some(Command::Fork { source, name }) => run_fork(source, name),
+        Some(Command::ModelInfo { name, probe }) => run_model_info(name, *probe),
+        Some(Command::Undo) => run_undo(),
+        Some(Command::Diff { path }) => run_diff(path.as_deref()),
+        None if args.bridge => run_bridge(&args),
+        None if args.compose => match compose_in_editor(&editor_command(), "")? {
+            Some(prompt) => run_prompt(&args, &prompt),
+            None => {
+                eprintln!("No prompt saved, nothing sent");
+                Ok(())
+            }
+        },
+        None if args.batch.is_some() => run_batch(&args),
+        None => {
+            // Only -p reads stdin; without it this is the GUI
+            let stdin = std::io::stdin();
+            let is_terminal = stdin.is_terminal();
+            match resolve_prompt(args.prompt.as_deref(), is_terminal, stdin.lock())? {
+                Some(prompt) => run_prompt(&args, &prompt),
+                None => run_gui(args),
+            }
+        }
+    }
+}
+
+/// Export the proxy and CA bundle settings for model requests. A bad
+/// value only warns, so `spot config set` can still fix it.
+fn export_network_settings() {
+    let Ok(db) = stockpot::db::Database::open() else {
+        return;
+    };
+    if let Err(e) = stockpot::agents::export_network_env(&db) {
+        eprintln!("Warning: {}", e);
+    }
+}
+
+/// Run environment diagnostics and exit non-zero on failure
+fn run_doctor() -> anyhow::Result<()> {
+    let report = stockpot::doctor::run_diagnostics();
+    print!("{}", report);
+
+    if report.has_failures() {
+        std::process::exit(1);
+    }
+    println!("\nAll checks passed.");
+    Ok(())
+}
+
+/// Run the legacy config migration and print what was imported
+fn run_config_migrate() -> anyhow::Result<()> {
+    use stockpot::config::{legacy_config_dir, migrate_legacy_config};
+    use stockpot::db::Database;
+
+    let db = Database::open()?;
+    db.migrate()?;
+
+    let dir = legacy_config_dir();
+    let report = migrate_legacy_config(&db, &dir)?;
+
+    if report.is_empty() {
+        println!("No legacy config files found in {}", dir.display());
+        return Ok(());
+    }
+
+    for file in &report.files {
+        println!("Migrated {} (archived as .migrated)", file.display());
+    }
+    for key in &report.imported {
+        println!("  imported {}", key);
+    }
+    for key in &report.skipped {
+        println!("  skipped {} (already set)", key);
+    }
+    println!(
+        "{} setting(s) imported, {} skipped",
+        report.imported.len(),
+        report.skipped.len()
+    );
+
+    Ok(())
+}
+
+/// Print all settings, or just `key`, with secrets masked
+fn run_config_list(key: Option<&str>) -> anyhow::Result<()> {
+    use stockpot::config::Settings;
+    use stockpot::db::Database;
+
+    let db = Database::open()?;
+    db.migrate()?;
+    let settings = Settings::new(&db);
+    match key {
+        Some(key) => println!("{}", settings.entry(key)?),
+        None => {
+            for entry in settings.entries()? {
+                println!("{}", entry);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Set one setting after validating it against the schema
+fn run_config_set(key: &str, value: &str) -> anyhow::Result<()> {
+    use stockpot::config::Settings;
+    use stockpot::db::Database;
+    use stockpot::models::settings::ModelSettings;
+    use stockpot::models::ModelRegistry;
+
+    let db = Database::open()?;
+    db.migrate()?;
+    Settings::new(&db).set_validated(key, value)?;
+    println!("Set {}", key);
+
+    // Thinking models run at 1.0 whatever is saved
+    if let Some(model) = key
+        .strip_prefix("model_settings.")
+        .and_then(|rest| rest.rsplit_once('.'))
+        .map(|(model, _)| model)
+    {
+        let supports_thinking = ModelRegistry::load_from_db(&db)?
+            .get(model)
+            .is_some_and(|config| config.supports_thinking);
+        let ignored = ModelSettings::load(&db, model)?.ignored_temperature(supports_thinking);
+        if let Some(temperature) = ignored {
+            eprintln!(
+                "Warning: temperature {} won't be used; {} has thinking on, which always runs at 1.0",
+                temperature, model
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print every known setting with the values it takes
+fn run_config_keys() -> anyhow::Result<()> {
+    use stockpot::config::{MODEL_SETTINGS, SETTINGS, SETTING_FAMILIES};
+
+    for def in SETTINGS {
+        println!("{:<40} {} ({})", def.key, def.description, def.kind);
+    }
+    for def in SETTING_FAMILIES {
+        let key = format!("{}.<name>", def.key);
+        println!("{:<40} {} ({})", key, def.description, def.kind);
+    }
+    for def in MODEL_SETTINGS {
+        let key = format!("model_settings.<model>.{}", def.key);
+        println!("{:<40} {} ({})", key, def.description, def.kind);
+    }
+    Ok(())
+}
+
+/// Manage settings profiles
+fn run_profile(action: &ProfileCommand) -> anyhow::Result<()> {
+    use stockpot::config::Settings;
+    use stockpot::db::Database;
+
+    let db = Database::open()?;
+    db.migrate()?;
+    let settings = Settings::new(&db);
+    match action {
+        ProfileCommand::List => {
+            let active = settings.active_profile()?;
+            let profiles = settings.list_profiles()?;
+            if profiles.is_empty() {
+                println!("No profiles saved");
+            }
+            for name in profiles {
+                let marker = if active.as_ref() == Some(&name) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{} {}", marker, name);
+            }
+        }
+        ProfileCommand::Save { name } => {
+            settings.save_profile(name)?;
+            println!("Saved current settings as profile {}", name);
+        }
+        ProfileCommand::Use { name } => {
+            settings.activate_profile(name)?;
+            eprintln!("Using profile {}", name);
+        }
+        ProfileCommand::Delete { name } => {
+            settings.delete_profile(name)?;
+            println!("Deleted profile {}", name);
+        }
+    }
+    Ok(())
+}
+
+/// Turn encryption of stored credentials on or off
+fn run_secrets(action: &SecretsCommand) -> anyhow::Result<()> {
+    use stockpot::db::Database;
+
+    let db = Database::open()?;
+    db.migrate()?;
+    match action {
+        SecretsCommand::Status => {
+            if db.encrypts_secrets() {
+                println!("Credentials are encrypted with a key in the OS keychain");
+            } else {
+                println!("Credentials are stored in plain text");
+            }
+        }
+        SecretsCommand::Encrypt => {
+            let count = db.encrypt_secrets()?;
+            println!("Encrypted {} stored credential(s)", count);
+        }
+        SecretsCommand::Decrypt => {
+            let count = db.decrypt_secrets()?;
+            println!("Decrypted {} stored credential(s)", count);
+        }
+    }
+    Ok(())
+}
+
+/// Manage OAuth providers without the GUI
+fn run_auth(action: &AuthCommand) -> anyhow::Result<()> {
+    use stockpot::auth::{self, TokenStorage};
+    use stockpot::db::Database;
+
+    let db = Database::open()?;
+    db.migrate()?;
+    match action {
+        AuthCommand::Status => {
+            for status in auth::auth_status(&db)? {
+                println!("{}", status);
+            }
+        }
+        AuthCommand::Login { provider } => {
+            let runtime = tokio::runtime::Runtime::new()?;
+            match provider {
+                OAuthProvider::ChatGpt => runtime.block_on(auth::run_chatgpt_auth(&db))?,
+                OAuthProvider::ClaudeCode => runtime.block_on(auth::run_claude_code_auth(&db))?,
+            }
+        }
+        AuthCommand::Logout { provider } => {
+            let storage = TokenStorage::new(&db);
+            if !storage.is_authenticated(provider.as_str())? {
+                println!("{} is not connected", provider.display_name());
+                return Ok(());
+            }
+            storage.delete(provider.as_str())?;
+            println!("Disconnected {}", provider.display_name());
+        }
+    }
+    Ok(())
+}
+
+/// Print what a model supports, optionally probing its endpoint
+fn run_model_info(name: &str, probe: bool) -> anyhow::Result<()> {
+    use stockpot::db::Database;
+    use stockpot::models::{probe_tool_support, ModelRegistry};
+
+    let db = Database::open()?;
+    db.migrate()?;
+    let registry = ModelRegistry::load_from_db(&db)?;
+    let config = registry
+        .get(name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown model: {}", name))?;
+
+    println!("{} ({})", name, config.model_type);
+    println!("{}", config.capabilities());
+    if config.custom_endpoint.is_none() {
+        if probe {
+            eprintln!("--probe only applies to custom endpoint models");
+        }
+        return Ok(());
+    }
+    if probe {
+        let runtime = tokio::runtime::Runtime::new()?;
+        let result = runtime.block_on(probe_tool_support(&db, config))?;
+        println!("Probe: {}", result);
+    }
+    Ok(())
+}
+
+/// Sessions in the configured directory
+fn session_manager() -> anyhow::Result<stockpot::session::SessionManager> {
+    use stockpot::config::Settings;
+    use stockpot::db::Database;
+    use stockpot::session::SessionManager;
+
+    let db = Database::open()?;
+    db.migrate()?;
+    Ok(SessionManager::from_settings(&Settings::new(&db)))
+}
+
+/// Export a saved session to stdout or a file
+fn run_export(
+    name: &str,
+    format: ExportFormat,
+    output: Option<&std::path::Path>,
+) -> anyhow::Result<()> {
+    let manager = session_manager()?;
+    let content = match format {
+        ExportFormat::Html => manager.export_html(name)?,
+        ExportFormat::Json => serde_json::to_string_pretty(&manager.load(name)?)?,
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, content)?;
+            println!("Exported {} to {}", name, path.display());
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+/// List saved sessions, optionally only those with `tag`
+fn run_sessions(tag: Option<&str>) -> anyhow::Result<()> {
+    let manager = session_manager()?;
+    let sessions = match tag {
+        Some(tag) => manager.list_by_tag(tag)?,
+        None => manager.list()?,
+    };
+    if sessions.is_empty() {
+        println!("No saved sessions");
+    }
+    for meta in &sessions {
+        println!("{}", meta);
+    }
+    Ok(())
+}
+
+/// Add or remove a session tag
+fn run_tag(session: &str, tag: &str, remove: bool) -> anyhow::Result<()> {
+    let manager = session_manager()?;
+    let message = if remove {
+        if manager.remove_tag(session, tag)? {
+            format!("Removed tag {} from {}", tag, session)
+        } else {
+            format!("{} wasn't tagged {}", session, tag)
+        }
+    } else if manager.add_tag(session, tag)? {
+        format!("Tagged {} with {}", session, tag)
+    } else {
+        format!("{} is already tagged {}", session, tag)
+    };
+    println!("{}", message);
+    Ok(())
+}
+
+/// Pin or unpin a saved session
+fn run_pin(session: &str, remove: bool) -> anyhow::Result<()> {
+    let changed = session_manager()?.set_pinned(session, !remove)?;
+    let state = if remove { "unpinned" } else { "pinned" };
+    if changed {
+        println!("{} is now {}", session, state);
+    } else {
+        println!("{} was already {}", session, state);
+    }
+    Ok(())
+}
+
+/// Copy what can be read from a damaged session into a new session
+fn run_recover(session: &str, save_as: Option<&str>) -> anyhow::Result<()> {
+    let manager = session_manager()?;
+    let recovered = manager.load_lenient(session)?;
+    if recovered.dropped.is_empty() {
+        println!("{} loads fine; nothing to recover", session);
+        return Ok(());
+    }
+
+    println!("Couldn't read:");
+    for entry in &recovered.dropped {
+        println!("  {}", entry);
+    }
+    let name = save_as
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}-recovered", session));
+    let meta = manager.save_recovered(&name, &recovered)?;
+    println!("Saved {} messages as {}", meta.message_count, meta.name);
+    Ok(())
+}
+
+/// List saved sessions that mention `query`
+fn run_search(query: &str) -> anyhow::Result<()> {
+    use stockpot::session::MAX_SEARCH_RESULTS;
+
+    let hits = session_manager()?.search(query);
+    if hits.is_empty() {
+        println!("No saved sessions mention \"{}\"", query);
+    }
+    for hit in &hits {
+        println!("{}", hit);
+    }
+    if hits.len() == MAX_SEARCH_RESULTS {
+        println!("(showing the {} most recent)", MAX_SEARCH_RESULTS);
+    }
+    Ok(())
+}
+
+/// Fork a saved session
+fn run_fork(source: &str, name: &str) -> anyhow::Result<()> {
+    let meta = session_manager()?.fork(source, name)?;
+    println!(
+        "Forked {} into {} ({} messages)",
+        source, meta.name, meta.message_count
+    );
+    Ok(())
+}
+
+/// Undo the most recent run's file changes
+fn run_undo() -> anyhow::Result<()> {
+    let report = stockpot::tools::UndoJournal::new().undo_last()?;
+    println!("{}", report);
+    Ok(())
+}
+
+/// Print the changes recorded for undo
+fn run_diff(path: Option<&std::path::Path>) -> anyhow::Result<()> {
+    let diff = stockpot::tools::UndoJournal::new().diff(path)?;
+    if diff.is_empty() {
+        println!("No changes");
+    } else {
+        print!("{}", diff);
+    }
+    Ok(())
+}
+
+/// Log to stderr for the headless modes, whose stdout is the output
+fn init_headless_tracing(args: &Args) {
+    let default_filter = if args.verbose {
+        "trace"
+    } else if args.debug {
+        "debug"
+    } else {
+        "warn"
+    };
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+}
+
+/// Validate the one-off --temperature/--top-p overrides and collect
+/// --sandbox, --no-tools, --approve, --no-spinner, --plain and --expand-agents
+fn headless_options(args: &Args) -> anyhow::Result<HeadlessOptions> {
+    Ok(HeadlessOptions {
+        sampling: SamplingOverride::new(args.temperature, args.top_p)?,
+        sandbox: args.sandbox,
+        no_tools: args.no_tools,
+        approval: args.approve,
+        no_spinner: args.no_spinner,
+        plain: args.plain,
+        expand_agents: args.expand_agents,
+    })
+}
+
+/// Run headless bridge mode until stdin closes
+fn run_bridge(args: &Args) -> anyhow::Result<()> {
+    if args.approve == ApprovalMode::Ask {
+        anyhow::bail!(
+            "--approve ask needs the terminal, but --bridge reads its requests from stdin"
+        );
+    }
+    let options = headless_options(args)?;
+    init_headless_tracing(args);
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(stockpot::bridge::run_bridge_mode(options))
+}
+
+/// Run a single prompt and print the result in the requested format.
+/// Exits with 130, as for SIGINT, when Ctrl+C stops the run.
+fn run_prompt(args: &Args, prompt: &str) -> anyhow::Result<()> {
+    use serdes_ai_core::messages::ImageMediaType;
+
+    let options = headless_options(args)?;
+    let images = if args.paste_image {
+        vec![(stockpot::clipboard::paste_image()?, ImageMediaType::Png)]
+    } else {
+        Vec::new()
+    };
+    init_headless_tracing(args);
+    let runtime = tokio::runtime::Runtime::new()?;
+    match runtime.block_on(stockpot::headless::run_single_prompt(
+        prompt,
+        images,
+        args.output,
+        options,
+    )) {
+        Err(e) if e.is::<Cancelled>() => std::process::exit(130),
+        result => result,
+    }
+}
+
+/// Run a batch of prompts and write the JSON report
+fn run_batch(args: &Args) -> anyhow::Result<()> {
+    use stockpot::batch::BatchOptions;
+
+    let run = headless_options(args)?;
+    init_headless_tracing(args);
+    let path = args.batch.clone().unwrap_or_default();
+    let options = BatchOptions {
+        shared_session: args.batch_shared,
+        run,
+    };
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let report = runtime.block_on(stockpot::batch::run_batch(&path, options))?;
+    let json = serde_json::to_string_pretty(&report)?;
+
+    match &args.batch_output {
+        Some(output) => {
+            std::fs::write(output, json)?;
+            eprintln!(
+                "{} succeeded, {} failed; report written to {}",
+                report.succeeded,
+                report.failed,
+                output.display()
+            );
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
 }
 
 /// Run the GUI application
@@ -49,6 +877,13 @@ fn run_gui(args: Args) -> anyhow::Result<()> {
     use gpui_component::{Root, Theme, ThemeMode};
     use stockpot::gui;
 
+    if args.no_tools {
+        anyhow::bail!("--no-tools applies to -p, --batch and --bridge; use /tools off in the GUI");
+    }
+    if args.approve != ApprovalMode::Auto {
+        anyhow::bail!("--approve applies to -p, --batch and --bridge");
+    }
+
     // Initialize tracing for GUI mode
     let default_filter = if args.verbose {
         "trace"
@@ -98,12 +933,15 @@ fn run_gui(args: Args) -> anyhow::Result<()> {
         });
     }
 
+    let resume = args.resume;
+    let sandbox = args.sandbox;
+
     // Create GPUI application with gpui-component assets
     // Use LastWindowClosed quit mode so closing the window terminates the app on macOS
     Application::new()
         .with_assets(gpui_component_assets::Assets)
         .with_quit_mode(QuitMode::LastWindowClosed)
-        .run(|cx: &mut App| {
+        .run(move |cx: &mut App| {
             // Initialize gpui-component (REQUIRED - sets up themes, icons, etc.)
             gpui_component::init(cx);
 
@@ -127,9 +965,15 @@ fn run_gui(args: Args) -> anyhow::Result<()> {
                     }),
                     ..Default::default()
                 },
-                |window, cx| {
+                move |window, cx| {
                     // Create the main app view
                     let app_view = cx.new(|cx| gui::ChatApp::new(window, cx));
+                    if sandbox {
+                        app_view.update(cx, |app, cx| app.enable_sandbox(window, cx));
+                    }
+                    if resume {
+                        app_view.update(cx, |app, cx| app.resume_last_session(window, cx));
+                    }
                     // Wrap in Root (required by gpui-component)
                     cx.new(|cx| Root::new(app_view, window, cx))
                 },
@@ -6,8 +6,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::atomic_file::write_atomic;
+
 /// Error type for MCP configuration operations.
 #[derive(Debug, Error)]
 pub enum McpConfigError {
@@ -19,6 +22,9 @@ pub enum McpConfigError {
 
     #[error("Config file not found: {0}")]
     NotFound(PathBuf),
+
+    #[error("Environment variable not set: {0}")]
+    UnsetVariable(String),
 }
 
 /// MCP server entry in the configuration file.
@@ -42,8 +48,15 @@ pub struct McpServerEntry {
     /// Optional description of the server.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// Per-call tool timeout in seconds (defaults to 30).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
 }
 
+/// Default timeout for a single MCP tool call.
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+
 fn default_enabled() -> bool {
     true
 }
@@ -57,6 +70,7 @@ impl McpServerEntry {
             env: HashMap::new(),
             enabled: true,
             description: None,
+            timeout_secs: None,
         }
     }
 
@@ -78,6 +92,19 @@ impl McpServerEntry {
         self
     }
 
+    /// Set the per-call tool timeout in seconds.
+    pub fn with_timeout_secs(mut self, secs: u64) -> Self {
+        self.timeout_secs = Some(secs);
+        self
+    }
+
+    /// Timeout applied to each tool call on this server.
+    pub fn tool_timeout(&self) -> Duration {
+        self.timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TOOL_TIMEOUT)
+    }
+
     /// Expand environment variables in the config.
     ///
     /// Replaces `${VAR_NAME}` patterns with actual environment values.
@@ -95,6 +122,49 @@ impl McpServerEntry {
             .collect();
         self.env = expanded;
     }
+
+    /// Resolve `${VAR}` and `$VAR` references in the command, args and env.
+    ///
+    /// Variables are looked up in the process environment first, then via
+    /// `fallback` (e.g. stored API keys). Unlike [`Self::expand_env_vars`],
+    /// an unresolved variable is an error rather than an empty string.
+    pub fn interpolate<F>(&self, fallback: F) -> Result<Self, McpConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let resolve = |s: &str| interpolate_vars(s, &fallback);
+
+        let mut resolved = self.clone();
+        resolved.command = resolve(&self.command)?;
+        resolved.args = self
+            .args
+            .iter()
+            .map(|arg| resolve(arg))
+            .collect::<Result<_, _>>()?;
+        resolved.env = self
+            .env
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), resolve(v)?)))
+            .collect::<Result<_, McpConfigError>>()?;
+
+        Ok(resolved)
+    }
+}
+
+/// Strictly interpolate `${VAR}` and `$VAR` references in a string.
+fn interpolate_vars<F>(s: &str, fallback: &F) -> Result<String, McpConfigError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    shellexpand::env_with_context(s, |var: &str| {
+        std::env::var(var)
+            .ok()
+            .or_else(|| fallback(var))
+            .map(Some)
+            .ok_or(())
+    })
+    .map(|s| s.into_owned())
+    .map_err(|e| McpConfigError::UnsetVariable(e.var_name))
 }
 
 /// Expand environment variables in a string.
@@ -150,13 +220,10 @@ impl McpConfig {
             return Err(McpConfigError::NotFound(path.to_path_buf()));
         }
 
+        // Variable references are kept verbatim here and resolved by
+        // `McpManager` when the server is spawned.
         let content = fs::read_to_string(path)?;
-        let mut config: McpConfig = serde_json::from_str(&content)?;
-
-        // Expand environment variables in all entries
-        for entry in config.servers.values_mut() {
-            entry.expand_env_vars();
-        }
+        let config: McpConfig = serde_json::from_str(&content)?;
 
         Ok(config)
     }
@@ -180,7 +247,7 @@ impl McpConfig {
         }
 
         let content = serde_json::to_string_pretty(self)?;
-        fs::write(path, content)?;
+        write_atomic(path, content)?;
         Ok(())
     }
 
@@ -927,6 +994,99 @@ mod tests {
         assert_eq!(entry.env.get("KEY3"), Some(&"expanded_suffix".to_string()));
     }
 
+    // =========================================================================
+    // Timeout Tests
+    // =========================================================================
+
+    #[test]
+    fn test_tool_timeout_defaults_to_30s() {
+        let entry = McpServerEntry::new("cmd");
+        assert_eq!(entry.timeout_secs, None);
+        assert_eq!(entry.tool_timeout(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_tool_timeout_per_server() {
+        let entry = McpServerEntry::new("cmd").with_timeout_secs(120);
+        assert_eq!(entry.tool_timeout(), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_timeout_secs_serde() {
+        let entry: McpServerEntry =
+            serde_json::from_str(r#"{"command": "cmd", "timeout_secs": 5}"#).unwrap();
+        assert_eq!(entry.timeout_secs, Some(5));
+
+        // Omitted when unset so existing configs round-trip unchanged
+        let json = serde_json::to_string(&McpServerEntry::new("cmd")).unwrap();
+        assert!(!json.contains("timeout_secs"));
+    }
+
+    // =========================================================================
+    // interpolate Tests
+    // =========================================================================
+
+    #[test]
+    fn test_interpolate_both_syntaxes() {
+        std::env::set_var("INTERP_HOME", "/home/tester");
+        std::env::set_var("INTERP_TOKEN", "tok123");
+
+        let entry = McpServerEntry::new("${INTERP_HOME}/bin/server")
+            .with_args(vec![
+                "--root=${INTERP_HOME}".to_string(),
+                "$INTERP_TOKEN".to_string(),
+            ])
+            .with_env("TOKEN", "Bearer $INTERP_TOKEN");
+
+        let resolved = entry.interpolate(|_| None).unwrap();
+        assert_eq!(resolved.command, "/home/tester/bin/server");
+        assert_eq!(resolved.args, vec!["--root=/home/tester", "tok123"]);
+        assert_eq!(
+            resolved.env.get("TOKEN"),
+            Some(&"Bearer tok123".to_string())
+        );
+    }
+
+    #[test]
+    fn test_interpolate_uses_fallback() {
+        let entry = McpServerEntry::new("cmd").with_env("KEY", "${INTERP_STORED_KEY_12345}");
+
+        let resolved = entry
+            .interpolate(|var| (var == "INTERP_STORED_KEY_12345").then(|| "stored".to_string()))
+            .unwrap();
+        assert_eq!(resolved.env.get("KEY"), Some(&"stored".to_string()));
+    }
+
+    #[test]
+    fn test_interpolate_env_wins_over_fallback() {
+        std::env::set_var("INTERP_PRIORITY", "from-env");
+        let entry = McpServerEntry::new("cmd").with_args(vec!["$INTERP_PRIORITY".to_string()]);
+
+        let resolved = entry.interpolate(|_| Some("from-db".to_string())).unwrap();
+        assert_eq!(resolved.args[0], "from-env");
+    }
+
+    #[test]
+    fn test_interpolate_unset_variable_errors() {
+        let entry =
+            McpServerEntry::new("cmd").with_args(vec!["${INTERP_MISSING_VAR_12345}".to_string()]);
+
+        let err = entry.interpolate(|_| None).unwrap_err();
+        assert!(
+            matches!(&err, McpConfigError::UnsetVariable(v) if v == "INTERP_MISSING_VAR_12345")
+        );
+        assert!(err.to_string().contains("INTERP_MISSING_VAR_12345"));
+    }
+
+    #[test]
+    fn test_interpolate_leaves_plain_values() {
+        let entry = McpServerEntry::new("npx").with_args(vec!["-y".to_string(), "pkg".to_string()]);
+
+        let resolved = entry.interpolate(|_| None).unwrap();
+        assert_eq!(resolved.command, "npx");
+        assert_eq!(resolved.args, entry.args);
+    }
+
     // =========================================================================
     // Server Configuration Edge Cases
     // =========================================================================
@@ -999,7 +1159,7 @@ mod tests {
     // =========================================================================
 
     #[test]
-    fn test_load_expands_env_vars() {
+    fn test_load_keeps_env_var_references() {
         std::env::set_var("LOAD_TEST_VAR", "loaded_value");
 
         let temp = TempDir::new().unwrap();
@@ -1019,8 +1179,11 @@ mod tests {
         let config = McpConfig::load_from_path(&path).unwrap();
         let server = config.get_server("test").unwrap();
 
-        assert_eq!(server.args[0], "loaded_value");
-        assert_eq!(server.env.get("KEY"), Some(&"loaded_value".to_string()));
+        // References survive loading and resolve at spawn time
+        assert_eq!(server.args[0], "${LOAD_TEST_VAR}");
+        let resolved = server.interpolate(|_| None).unwrap();
+        assert_eq!(resolved.args[0], "loaded_value");
+        assert_eq!(resolved.env.get("KEY"), Some(&"loaded_value".to_string()));
     }
 
     #[test]
@@ -1,15 +1,25 @@
 //! MCP server lifecycle management.
 //!
 //! Handles starting, stopping, and managing MCP server connections.
+//!
+//! A background supervisor (see [`McpManager::spawn_supervisor`]) pings
+//! running servers, marks unresponsive ones as crashed and restarts them
+//! with exponential backoff.
 
 use super::config::{McpConfig, McpServerEntry};
+use crate::db::Database;
+use crate::messaging::{McpServerEvent, Message, MessageSender};
 use serdes_ai_mcp::{McpClient, McpError};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
+/// How long a health-check ping may take before the server counts as dead.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Error type for MCP manager operations.
 #[derive(Debug, Error)]
 pub enum McpManagerError {
@@ -35,35 +45,98 @@ pub struct McpServerHandle {
     pub client: Arc<McpClient>,
 }
 
+/// Health of a managed MCP server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerStatus {
+    /// Connected and responding to health checks.
+    Running,
+    /// Stopped responding or exited; not currently connected.
+    Crashed,
+    /// A restart is in progress.
+    Restarting,
+}
+
+/// Restart behaviour for the MCP server supervisor.
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// Maximum restarts per server before giving up (0 disables restarts).
+    pub max_restarts: u32,
+    /// Delay before the first restart; doubled on each further attempt.
+    pub initial_backoff: Duration,
+    /// Time between health checks.
+    pub check_interval: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 3,
+            initial_backoff: Duration::from_secs(1),
+            check_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Backoff delay before restart attempt `attempt` (0-based).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
 /// Manager for MCP server connections.
 ///
 /// Handles loading configuration, starting/stopping servers,
 /// and providing toolsets for agent integration.
+///
+/// State is held behind `Arc`s, so cloning is cheap and every clone shares
+/// the same running servers. Tool executors keep their own clone rather than
+/// borrowing the manager.
+#[derive(Clone)]
 pub struct McpManager {
-    config: McpConfig,
-    servers: RwLock<HashMap<String, McpServerHandle>>,
+    config: Arc<McpConfig>,
+    servers: Arc<RwLock<HashMap<String, McpServerHandle>>>,
+    /// Health of each server that has been started.
+    status: Arc<RwLock<HashMap<String, ServerStatus>>>,
+    /// Stored API keys used to resolve variables missing from the environment.
+    api_keys: Arc<HashMap<String, String>>,
 }
 
 impl McpManager {
     /// Create a new MCP manager with default configuration.
     pub fn new() -> Self {
-        Self {
-            config: McpConfig::load_or_default(),
-            servers: RwLock::new(HashMap::new()),
-        }
+        Self::with_config(McpConfig::load_or_default())
     }
 
     /// Create a manager with a specific configuration.
     pub fn with_config(config: McpConfig) -> Self {
         Self {
-            config,
-            servers: RwLock::new(HashMap::new()),
+            config: Arc::new(config),
+            servers: Arc::new(RwLock::new(HashMap::new())),
+            status: Arc::new(RwLock::new(HashMap::new())),
+            api_keys: Arc::new(HashMap::new()),
         }
     }
 
+    /// Resolve config variables against the API keys stored in the database.
+    pub fn with_api_keys_from_db(mut self, db: &Database) -> Self {
+        let names = db.list_api_keys().unwrap_or_default();
+        self.api_keys = Arc::new(
+            names
+                .into_iter()
+                .filter_map(|name| match db.get_api_key(&name) {
+                    Ok(Some(key)) => Some((name, key)),
+                    _ => None,
+                })
+                .collect(),
+        );
+        self
+    }
+
     /// Load configuration from the default path.
     pub fn load_config(&mut self) -> Result<(), McpManagerError> {
-        self.config = McpConfig::load_default()?;
+        self.config = Arc::new(McpConfig::load_default()?);
         Ok(())
     }
 
@@ -95,6 +168,7 @@ impl McpManager {
         // Store the handle
         let mut servers = self.servers.write().await;
         servers.insert(name.to_string(), handle);
+        self.set_status(name, ServerStatus::Running).await;
 
         info!(server = %name, "MCP server started");
         Ok(())
@@ -105,6 +179,7 @@ impl McpManager {
         let mut servers = self.servers.write().await;
 
         if let Some(handle) = servers.remove(name) {
+            self.status.write().await.remove(name);
             info!("Stopping MCP server: {}", name);
             if let Err(e) = handle.client.close().await {
                 warn!("Error closing MCP server {}: {}", name, e);
@@ -161,6 +236,132 @@ impl McpManager {
         servers.contains_key(name)
     }
 
+    /// Get the health of every server that has been started.
+    ///
+    /// Servers that were stopped explicitly are not included.
+    pub async fn server_status(&self) -> HashMap<String, ServerStatus> {
+        self.status.read().await.clone()
+    }
+
+    async fn set_status(&self, name: &str, status: ServerStatus) {
+        self.status.write().await.insert(name.to_string(), status);
+    }
+
+    /// Ping every running server and mark unresponsive ones as crashed.
+    ///
+    /// Crashed servers are disconnected so their tools are no longer offered.
+    /// Returns the names of servers that were found dead by this check.
+    pub async fn check_health(&self) -> Vec<String> {
+        let clients: Vec<(String, Arc<McpClient>)> = {
+            let servers = self.servers.read().await;
+            servers
+                .iter()
+                .map(|(name, h)| (name.clone(), Arc::clone(&h.client)))
+                .collect()
+        };
+
+        let mut crashed = Vec::new();
+        for (name, client) in clients {
+            // A dead process or broken pipe surfaces as an error; a hung one as a timeout
+            match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, client.list_tools()).await {
+                Ok(Ok(_)) => continue,
+                Ok(Err(e)) => warn!(server = %name, error = %e, "MCP server health check failed"),
+                Err(_) => warn!(server = %name, "MCP server health check timed out"),
+            }
+
+            let handle = self.servers.write().await.remove(&name);
+            if let Some(handle) = handle {
+                let _ = handle.client.close().await;
+            }
+            self.set_status(&name, ServerStatus::Crashed).await;
+            crashed.push(name);
+        }
+
+        crashed
+    }
+
+    /// Reconnect a server, replacing any existing connection.
+    pub async fn restart_server(&self, name: &str) -> Result<(), McpManagerError> {
+        if !self.config.has_server(name) {
+            return Err(McpManagerError::ServerNotFound(name.to_string()));
+        }
+
+        self.set_status(name, ServerStatus::Restarting).await;
+
+        let handle = self.servers.write().await.remove(name);
+        if let Some(handle) = handle {
+            let _ = handle.client.close().await;
+        }
+
+        match self.start_server(name).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                self.set_status(name, ServerStatus::Crashed).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Spawn a background task that health-checks servers and restarts
+    /// crashed ones according to `policy`.
+    ///
+    /// Each restart outcome is published on `bus` as a
+    /// [`Message::McpServer`]. The task runs until the returned handle is
+    /// aborted.
+    pub fn spawn_supervisor(
+        self: Arc<Self>,
+        policy: RestartPolicy,
+        bus: Option<MessageSender>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let emit = |server: &str, event: McpServerEvent, attempt: u32| {
+                if let Some(bus) = &bus {
+                    let _ = bus.send(Message::mcp_server(server, event, attempt));
+                }
+            };
+            let mut attempts: HashMap<String, u32> = HashMap::new();
+
+            loop {
+                tokio::time::sleep(policy.check_interval).await;
+
+                for name in self.check_health().await {
+                    let attempt = attempts.get(&name).copied().unwrap_or(0);
+                    emit(&name, McpServerEvent::Crashed, attempt);
+                }
+
+                let crashed: Vec<String> = self
+                    .server_status()
+                    .await
+                    .into_iter()
+                    .filter(|(_, status)| *status == ServerStatus::Crashed)
+                    .map(|(name, _)| name)
+                    .collect();
+
+                for name in crashed {
+                    let attempt = attempts.entry(name.clone()).or_insert(0);
+                    if *attempt >= policy.max_restarts {
+                        continue;
+                    }
+
+                    tokio::time::sleep(policy.backoff(*attempt)).await;
+                    *attempt += 1;
+                    info!(server = %name, attempt = *attempt, "Restarting crashed MCP server");
+
+                    match self.restart_server(&name).await {
+                        Ok(()) => emit(&name, McpServerEvent::Restarted, *attempt),
+                        Err(e) => {
+                            warn!(server = %name, error = %e, "MCP server restart failed");
+                            if *attempt >= policy.max_restarts {
+                                error!(server = %name, "Giving up on crashed MCP server");
+                                emit(&name, McpServerEvent::GaveUp, *attempt);
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
     /// Get a server handle by name.
     pub async fn get_handle(&self, name: &str) -> Option<Arc<McpClient>> {
         let servers = self.servers.read().await;
@@ -231,7 +432,15 @@ impl McpManager {
         name: &str,
         entry: &McpServerEntry,
     ) -> Result<McpServerHandle, McpManagerError> {
-        // Build args with env vars
+        // Resolve ${VAR} / $VAR references before spawning
+        let entry = match entry.interpolate(|var| self.api_keys.get(var).cloned()) {
+            Ok(e) => e,
+            Err(e) => {
+                error!(server = %name, error = %e, "Failed to resolve MCP server config");
+                return Err(e.into());
+            }
+        };
+
         let args: Vec<&str> = entry.args.iter().map(|s| s.as_str()).collect();
 
         // Create the client
@@ -1124,6 +1333,145 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_start_server_unset_variable_errors() {
+        let mut config = McpConfig::new();
+        config.add_server(
+            "needs-token",
+            McpServerEntry::new("cmd").with_env("TOKEN", "${MGR_MISSING_TOKEN_12345}"),
+        );
+        let manager = McpManager::with_config(config);
+
+        let err = manager.start_server("needs-token").await.unwrap_err();
+        assert!(err.to_string().contains("MGR_MISSING_TOKEN_12345"));
+        assert!(!manager.is_running("needs-token").await);
+    }
+
+    #[test]
+    fn test_with_api_keys_from_db() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let db = Database::open_at(temp.path().join("test.db")).unwrap();
+        db.migrate().unwrap();
+        db.save_api_key("MGR_STORED_KEY", "secret").unwrap();
+
+        let manager = McpManager::with_config(McpConfig::new()).with_api_keys_from_db(&db);
+        assert_eq!(
+            manager.api_keys.get("MGR_STORED_KEY"),
+            Some(&"secret".to_string())
+        );
+    }
+
+    // =========================================================================
+    // Health and Restart Tests
+    // =========================================================================
+
+    #[tokio::test]
+    async fn test_server_status_empty() {
+        let manager = McpManager::with_config(McpConfig::new());
+        assert!(manager.server_status().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_health_with_none_running() {
+        let manager = McpManager::with_config(McpConfig::new());
+        assert!(manager.check_health().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_start_does_not_record_status() {
+        let mut config = McpConfig::new();
+        config.add_server(
+            "broken",
+            McpServerEntry::new("nonexistent_command_that_does_not_exist_12345"),
+        );
+        let manager = McpManager::with_config(config);
+
+        assert!(manager.start_server("broken").await.is_err());
+        assert!(manager.server_status().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restart_server_not_found() {
+        let manager = McpManager::with_config(McpConfig::new());
+        let result = manager.restart_server("missing").await;
+        assert!(matches!(result, Err(McpManagerError::ServerNotFound(_))));
+        assert!(manager.server_status().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restart_server_failure_marks_crashed() {
+        let mut config = McpConfig::new();
+        config.add_server(
+            "broken",
+            McpServerEntry::new("nonexistent_command_that_does_not_exist_12345"),
+        );
+        let manager = McpManager::with_config(config);
+
+        assert!(manager.restart_server("broken").await.is_err());
+        assert_eq!(
+            manager.server_status().await.get("broken"),
+            Some(&ServerStatus::Crashed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_gives_up_after_max_restarts() {
+        let mut config = McpConfig::new();
+        config.add_server(
+            "broken",
+            McpServerEntry::new("nonexistent_command_that_does_not_exist_12345"),
+        );
+        let manager = Arc::new(McpManager::with_config(config));
+        manager.set_status("broken", ServerStatus::Crashed).await;
+
+        let bus = crate::messaging::MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let policy = RestartPolicy {
+            max_restarts: 2,
+            initial_backoff: Duration::from_millis(1),
+            check_interval: Duration::from_millis(5),
+        };
+        let supervisor = Arc::clone(&manager).spawn_supervisor(policy, Some(bus.sender()));
+
+        let msg = tokio::time::timeout(Duration::from_secs(10), receiver.recv())
+            .await
+            .expect("supervisor did not report")
+            .unwrap();
+        supervisor.abort();
+
+        assert!(matches!(
+            msg,
+            Message::McpServer(m) if m.event == McpServerEvent::GaveUp && m.attempt == 2
+        ));
+        assert_eq!(
+            manager.server_status().await.get("broken"),
+            Some(&ServerStatus::Crashed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_server_state() {
+        let manager = McpManager::with_config(McpConfig::new());
+        let clone = manager.clone();
+
+        manager.set_status("shared", ServerStatus::Crashed).await;
+        assert_eq!(
+            clone.server_status().await.get("shared"),
+            Some(&ServerStatus::Crashed)
+        );
+    }
+
+    #[test]
+    fn test_restart_policy_backoff_doubles() {
+        let policy = RestartPolicy {
+            initial_backoff: Duration::from_millis(100),
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+    }
+
     // =========================================================================
     // Error Variant Matching Tests
     // =========================================================================
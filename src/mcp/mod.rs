@@ -3,6 +3,7 @@
 //! This module provides:
 //! - Configuration loading from `~/.stockpot/mcp_servers.json`
 //! - MCP server lifecycle management (start/stop)
+//! - Health checks and auto-restart of crashed servers
 //! - Integration with the agent executor via McpToolset
 //!
 //! ## Configuration File Format
@@ -21,12 +22,20 @@
 //!       "args": ["-y", "@modelcontextprotocol/server-github"],
 //!       "env": {
 //!         "GITHUB_TOKEN": "${GITHUB_TOKEN}"
-//!       }
+//!       },
+//!       "timeout_secs": 60
 //!     }
 //!   }
 //! }
 //! ```
 //!
+//! `${VAR}` and `$VAR` references in `command`, `args` and `env` are resolved
+//! when a server is spawned, from the process environment and then from stored
+//! API keys. Referencing an unset variable fails the launch.
+//!
+//! Each tool call is cancelled after `timeout_secs` (30 by default) and
+//! reported to the model as a tool error.
+//!
 //! ## Usage
 //!
 //! ```ignore
@@ -45,5 +54,5 @@
 mod config;
 mod manager;
 
-pub use config::{McpConfig, McpServerEntry};
-pub use manager::McpManager;
+pub use config::{McpConfig, McpServerEntry, DEFAULT_TOOL_TIMEOUT};
+pub use manager::{McpManager, RestartPolicy, ServerStatus};
@@ -0,0 +1,417 @@
+//! Versioned NDJSON protocol for external front-ends.
+//!
+//! Editor extensions and other out-of-process clients consume the bus as
+//! newline-delimited JSON: one [`BridgeEvent`] object per line. Every line
+//! carries the protocol `version` and a `type` tag; the first line of a
+//! session is always a `handshake`.
+//!
+//! ```text
+//! {"version":1,"type":"handshake","protocol":"stockpot-bridge","app_version":"0.19.1"}
+//! {"version":1,"type":"agent","agent_name":"stockpot","display_name":"Stockpot",...}
+//! {"version":1,"type":"text_delta","text":"Hello"}
+//! {"version":1,"type":"tool","tool_name":"grep","status":"completed",...}
+//! ```
+//!
+//! Apart from `handshake`, the event types and their fields mirror
+//! [`Message`] exactly. Within a protocol version, fields are only ever
+//! added (and optional fields may be omitted), so clients should ignore
+//! unknown fields. Any incompatible change bumps
+//! [`BRIDGE_PROTOCOL_VERSION`].
+//!
+//! Clients drive the session with [`BridgeCommand`]s, also one JSON object
+//! per line (the `version` field is optional on commands):
+//!
+//! ```text
+//! {"type":"prompt","text":"Explain src/main.rs"}
+//! {"type":"cancel"}
+//! {"type":"switch_agent","name":"planning"}
+//! ```
+
+use std::io::{self, Stdout, Write};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::renderer::MessageRenderer;
+use super::{
+    AgentMessage, DiffMessage, FileMessage, InputRequest, McpServerMessage, Message,
+    MetricsMessage, ReasoningMessage, ResponseMessage, ShellMessage, SpinnerMessage,
+    TextDeltaMessage, TextMessage, ThinkingMessage, ToolMessage, ToolProgressMessage,
+};
+
+/// Current bridge protocol version.
+pub const BRIDGE_PROTOCOL_VERSION: u32 = 1;
+
+/// Protocol name announced in the handshake.
+pub const BRIDGE_PROTOCOL_NAME: &str = "stockpot-bridge";
+
+/// Errors decoding a bridge line.
+#[derive(Debug, Error)]
+pub enum BridgeError {
+    #[error("Invalid bridge event: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Unsupported bridge protocol version {found} (supported: {supported})")]
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+/// One line of the bridge protocol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeEvent {
+    /// Protocol version the line was written with
+    pub version: u32,
+    #[serde(flatten)]
+    pub kind: BridgeEventKind,
+}
+
+/// Payload of a bridge line, tagged by `type`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BridgeEventKind {
+    /// First line of every session
+    Handshake {
+        protocol: String,
+        app_version: String,
+    },
+    Text(TextMessage),
+    Reasoning(ReasoningMessage),
+    Response(ResponseMessage),
+    Shell(ShellMessage),
+    File(FileMessage),
+    Diff(DiffMessage),
+    Spinner(SpinnerMessage),
+    InputRequest(InputRequest),
+    Agent(AgentMessage),
+    Tool(ToolMessage),
+    TextDelta(TextDeltaMessage),
+    Thinking(ThinkingMessage),
+    ToolProgress(ToolProgressMessage),
+    McpServer(McpServerMessage),
+    Metrics(MetricsMessage),
+    Divider,
+    Clear,
+}
+
+impl BridgeEvent {
+    /// The handshake announcing this build's protocol version.
+    pub fn handshake() -> Self {
+        Self {
+            version: BRIDGE_PROTOCOL_VERSION,
+            kind: BridgeEventKind::Handshake {
+                protocol: BRIDGE_PROTOCOL_NAME.to_string(),
+                app_version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+        }
+    }
+
+    /// Whether this is the handshake line.
+    pub fn is_handshake(&self) -> bool {
+        matches!(self.kind, BridgeEventKind::Handshake { .. })
+    }
+
+    /// Encode as a single JSON line (without the trailing newline).
+    pub fn encode(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Decode a single line, rejecting newer protocol versions.
+    pub fn decode(line: &str) -> Result<Self, BridgeError> {
+        let event: Self = serde_json::from_str(line.trim())?;
+        if event.version > BRIDGE_PROTOCOL_VERSION {
+            return Err(BridgeError::UnsupportedVersion {
+                found: event.version,
+                supported: BRIDGE_PROTOCOL_VERSION,
+            });
+        }
+        Ok(event)
+    }
+
+    /// The bus message this event carries, if any.
+    pub fn into_message(self) -> Option<Message> {
+        Some(match self.kind {
+            BridgeEventKind::Handshake { .. } => return None,
+            BridgeEventKind::Text(m) => Message::Text(m),
+            BridgeEventKind::Reasoning(m) => Message::Reasoning(m),
+            BridgeEventKind::Response(m) => Message::Response(m),
+            BridgeEventKind::Shell(m) => Message::Shell(m),
+            BridgeEventKind::File(m) => Message::File(m),
+            BridgeEventKind::Diff(m) => Message::Diff(m),
+            BridgeEventKind::Spinner(m) => Message::Spinner(m),
+            BridgeEventKind::InputRequest(m) => Message::InputRequest(m),
+            BridgeEventKind::Agent(m) => Message::Agent(m),
+            BridgeEventKind::Tool(m) => Message::Tool(m),
+            BridgeEventKind::TextDelta(m) => Message::TextDelta(m),
+            BridgeEventKind::Thinking(m) => Message::Thinking(m),
+            BridgeEventKind::ToolProgress(m) => Message::ToolProgress(m),
+            BridgeEventKind::McpServer(m) => Message::McpServer(m),
+            BridgeEventKind::Metrics(m) => Message::Metrics(m),
+            BridgeEventKind::Divider => Message::Divider,
+            BridgeEventKind::Clear => Message::Clear,
+        })
+    }
+}
+
+impl From<Message> for BridgeEvent {
+    fn from(msg: Message) -> Self {
+        let kind = match msg {
+            Message::Text(m) => BridgeEventKind::Text(m),
+            Message::Reasoning(m) => BridgeEventKind::Reasoning(m),
+            Message::Response(m) => BridgeEventKind::Response(m),
+            Message::Shell(m) => BridgeEventKind::Shell(m),
+            Message::File(m) => BridgeEventKind::File(m),
+            Message::Diff(m) => BridgeEventKind::Diff(m),
+            Message::Spinner(m) => BridgeEventKind::Spinner(m),
+            Message::InputRequest(m) => BridgeEventKind::InputRequest(m),
+            Message::Agent(m) => BridgeEventKind::Agent(m),
+            Message::Tool(m) => BridgeEventKind::Tool(m),
+            Message::TextDelta(m) => BridgeEventKind::TextDelta(m),
+            Message::Thinking(m) => BridgeEventKind::Thinking(m),
+            Message::ToolProgress(m) => BridgeEventKind::ToolProgress(m),
+            Message::McpServer(m) => BridgeEventKind::McpServer(m),
+            Message::Metrics(m) => BridgeEventKind::Metrics(m),
+            Message::Divider => BridgeEventKind::Divider,
+            Message::Clear => BridgeEventKind::Clear,
+        };
+        Self {
+            version: BRIDGE_PROTOCOL_VERSION,
+            kind,
+        }
+    }
+}
+
+/// A command sent by a bridge client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BridgeCommand {
+    /// Run the current agent on a prompt
+    Prompt { text: String },
+    /// Cancel the run in progress
+    Cancel,
+    /// Use a different agent for subsequent prompts
+    SwitchAgent { name: String },
+}
+
+/// Wire form of a command, with the optional protocol version.
+#[derive(Serialize, Deserialize)]
+struct CommandLine {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    #[serde(flatten)]
+    command: BridgeCommand,
+}
+
+impl BridgeCommand {
+    /// Encode as a single JSON line (without the trailing newline).
+    pub fn encode(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&CommandLine {
+            version: Some(BRIDGE_PROTOCOL_VERSION),
+            command: self.clone(),
+        })
+    }
+
+    /// Decode a single line, rejecting newer protocol versions.
+    pub fn decode(line: &str) -> Result<Self, BridgeError> {
+        let line: CommandLine = serde_json::from_str(line.trim())?;
+        match line.version {
+            Some(found) if found > BRIDGE_PROTOCOL_VERSION => {
+                Err(BridgeError::UnsupportedVersion {
+                    found,
+                    supported: BRIDGE_PROTOCOL_VERSION,
+                })
+            }
+            _ => Ok(line.command),
+        }
+    }
+}
+
+/// Writes bus messages as bridge protocol NDJSON.
+pub struct BridgeRenderer<W: Write + Send = Stdout> {
+    out: W,
+}
+
+impl BridgeRenderer<Stdout> {
+    /// Write the protocol to stdout.
+    pub fn new() -> Self {
+        Self::with_writer(io::stdout())
+    }
+}
+
+impl Default for BridgeRenderer<Stdout> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write + Send> BridgeRenderer<W> {
+    /// Write the protocol to an arbitrary writer.
+    pub fn with_writer(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Consume the renderer, returning the writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Write one event as a line and flush it.
+    pub fn write_event(&mut self, event: &BridgeEvent) -> io::Result<()> {
+        let line = event.encode().map_err(io::Error::other)?;
+        writeln!(self.out, "{}", line)?;
+        self.out.flush()
+    }
+}
+
+#[async_trait]
+impl<W: Write + Send> MessageRenderer for BridgeRenderer<W> {
+    fn start(&mut self) -> io::Result<()> {
+        self.write_event(&BridgeEvent::handshake())
+    }
+
+    fn render(&mut self, msg: &Message) -> io::Result<()> {
+        self.write_event(&BridgeEvent::from(msg.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::{MessageBus, ToolResultContent, ToolStatus};
+
+    #[test]
+    fn test_handshake_line_format() {
+        let line = BridgeEvent::handshake().encode().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["version"], BRIDGE_PROTOCOL_VERSION);
+        assert_eq!(value["type"], "handshake");
+        assert_eq!(value["protocol"], BRIDGE_PROTOCOL_NAME);
+        assert_eq!(value["app_version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_message_fields_are_flattened_next_to_version() {
+        let line = BridgeEvent::from(Message::text_delta("hi"))
+            .encode()
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["type"], "text_delta");
+        assert_eq!(value["text"], "hi");
+    }
+
+    #[test]
+    fn test_round_trip_through_bridge() {
+        let messages = vec![
+            Message::info("hello"),
+            Message::tool_completed("list_agents").with_tool_content(ToolResultContent::Table {
+                headers: vec!["name".to_string()],
+                rows: vec![vec!["stockpot".to_string()]],
+            }),
+            Message::tool_failed("grep", "bad regex"),
+            Message::metrics_from(120, 1500, "stockpot"),
+            Message::Divider,
+        ];
+
+        for msg in messages {
+            let expected = serde_json::to_value(&msg).unwrap();
+            let line = BridgeEvent::from(msg).encode().unwrap();
+            let decoded = BridgeEvent::decode(&line).unwrap();
+            let back = decoded.into_message().unwrap();
+            assert_eq!(serde_json::to_value(&back).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_decode_preserves_tool_status() {
+        let line = BridgeEvent::from(Message::tool_failed("grep", "boom"))
+            .encode()
+            .unwrap();
+        match BridgeEvent::decode(&line).unwrap().kind {
+            BridgeEventKind::Tool(tool) => {
+                assert_eq!(tool.status, ToolStatus::Failed);
+                assert_eq!(tool.error.as_deref(), Some("boom"));
+            }
+            other => panic!("expected tool event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_newer_version() {
+        let line = r#"{"version":99,"type":"divider"}"#;
+        assert!(matches!(
+            BridgeEvent::decode(line),
+            Err(BridgeError::UnsupportedVersion { found: 99, .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_type() {
+        let line = r#"{"version":1,"type":"teleport"}"#;
+        assert!(matches!(
+            BridgeEvent::decode(line),
+            Err(BridgeError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_commands() {
+        assert_eq!(
+            BridgeCommand::decode(r#"{"type":"prompt","text":"hi"}"#).unwrap(),
+            BridgeCommand::Prompt {
+                text: "hi".to_string()
+            }
+        );
+        assert_eq!(
+            BridgeCommand::decode(r#"{"type":"cancel"}"#).unwrap(),
+            BridgeCommand::Cancel
+        );
+        assert_eq!(
+            BridgeCommand::decode(r#"{"version":1,"type":"switch_agent","name":"planning"}"#)
+                .unwrap(),
+            BridgeCommand::SwitchAgent {
+                name: "planning".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_command_round_trip() {
+        let command = BridgeCommand::SwitchAgent {
+            name: "explore".to_string(),
+        };
+        let line = command.encode().unwrap();
+        assert!(line.contains(r#""version":1"#));
+        assert_eq!(BridgeCommand::decode(&line).unwrap(), command);
+    }
+
+    #[test]
+    fn test_decode_command_errors() {
+        assert!(matches!(
+            BridgeCommand::decode(r#"{"type":"prompt"}"#),
+            Err(BridgeError::Parse(_))
+        ));
+        assert!(matches!(
+            BridgeCommand::decode(r#"{"version":2,"type":"cancel"}"#),
+            Err(BridgeError::UnsupportedVersion { found: 2, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bridge_renderer_writes_handshake_first() {
+        let bus = MessageBus::new();
+        let receiver = bus.subscribe();
+        bus.sender().send(Message::info("ready")).unwrap();
+        drop(bus);
+
+        let mut renderer = BridgeRenderer::with_writer(Vec::new());
+        renderer.run_loop(receiver).await.unwrap();
+        let out = String::from_utf8(renderer.into_inner()).unwrap();
+
+        let events: Vec<BridgeEvent> = out
+            .lines()
+            .map(|line| BridgeEvent::decode(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert!(events[0].is_handshake());
+        assert!(matches!(events[1].kind, BridgeEventKind::Text(_)));
+    }
+}
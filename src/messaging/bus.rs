@@ -4,7 +4,7 @@ use super::Message;
 use tokio::sync::broadcast;
 
 /// Sender half of the message bus.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct MessageSender {
     tx: broadcast::Sender<Message>,
 }
@@ -4,9 +4,51 @@
 //! from the agent runtime into UI-agnostic messages that can be rendered
 //! by any subscriber (terminal, web UI, etc.).
 
-use super::{Message, MessageSender};
+use super::{Message, MessageSender, ToolResultContent};
 use serdes_ai_agent::AgentStreamEvent as StreamEvent;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Minimum time between [`Message::Metrics`] readings while streaming.
+const METRICS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Structured tool results waiting to be attached to their completion message.
+///
+/// `StreamEvent::ToolExecuted` carries no payload, so tools that opt in to
+/// structured output push it here by tool name as they finish. The bridge
+/// takes the oldest entry for that name when the matching event arrives.
+#[derive(Debug, Clone, Default)]
+pub struct ToolContentStore {
+    pending: Arc<Mutex<HashMap<String, VecDeque<ToolResultContent>>>>,
+}
+
+impl ToolContentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a structured result for a finished call of `tool_name`.
+    pub fn push(&self, tool_name: &str, content: ToolResultContent) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(tool_name.to_string())
+            .or_default()
+            .push_back(content);
+    }
+
+    /// Take the oldest pending result for `tool_name`, if any.
+    pub fn take(&self, tool_name: &str) -> Option<ToolResultContent> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let queue = pending.get_mut(tool_name)?;
+        let content = queue.pop_front();
+        if queue.is_empty() {
+            pending.remove(tool_name);
+        }
+        content
+    }
+}
 
 /// Converts StreamEvents to Messages and publishes to the message bus.
 ///
@@ -20,6 +62,24 @@ pub struct EventBridge {
     tool_states: HashMap<String, CurrentToolState>,
     /// Whether we've sent the first text (for agent header)
     first_text_sent: bool,
+    /// Structured results from tools, attached to completion messages
+    contents: ToolContentStore,
+    /// Output throughput, reported as periodic metrics messages
+    throughput: Throughput,
+    /// Whether shared reasoning is published as reasoning messages
+    show_reasoning: bool,
+    /// Nesting depth of the agent, carried on its lifecycle messages
+    depth: usize,
+}
+
+/// Streamed output counted towards [`Message::Metrics`].
+#[derive(Default)]
+struct Throughput {
+    /// When the first text, thinking or tool-args delta arrived
+    first_token_at: Option<Instant>,
+    last_reported_at: Option<Instant>,
+    /// Bytes streamed so far; tokens are estimated at four bytes each
+    bytes: usize,
 }
 
 /// State for tracking an in-progress tool call.
@@ -38,7 +98,34 @@ impl EventBridge {
             agent_display_name: display_name.to_string(),
             tool_states: HashMap::new(),
             first_text_sent: false,
+            contents: ToolContentStore::new(),
+            throughput: Throughput::default(),
+            show_reasoning: false,
+            depth: 0,
+        }
+    }
+
+    /// Mark the agent as nested `depth` deep under the one the user ran,
+    /// so renderers can set its output apart.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Tag a lifecycle message with this agent's depth.
+    fn nested(&self, mut msg: Message) -> Message {
+        if let Message::Agent(agent) = &mut msg {
+            agent.depth = self.depth;
         }
+        msg
+    }
+
+    /// Publish `share_your_reasoning` calls as [`Message::Reasoning`], so
+    /// renderers can show them apart from the answer. Follows the
+    /// `show_reasoning` setting.
+    pub fn with_reasoning(mut self, show: bool) -> Self {
+        self.show_reasoning = show;
+        self
     }
 
     /// Get the agent name.
@@ -46,32 +133,68 @@ impl EventBridge {
         &self.agent_name
     }
 
+    /// Store that tools push structured results into for this bridge.
+    pub fn content_store(&self) -> ToolContentStore {
+        self.contents.clone()
+    }
+
     /// Signal that the agent has started execution.
     pub fn agent_started(&self) {
-        let _ = self.sender.send(Message::agent_started(
+        let _ = self.sender.send(self.nested(Message::agent_started(
             &self.agent_name,
             &self.agent_display_name,
-        ));
+        )));
     }
 
     /// Signal that the agent has completed execution.
     pub fn agent_completed(&self, run_id: &str) {
-        let _ = self.sender.send(Message::agent_completed(
+        let _ = self.sender.send(self.nested(Message::agent_completed(
             &self.agent_name,
             &self.agent_display_name,
             run_id,
-        ));
+        )));
     }
 
     /// Signal that the agent encountered an error.
     pub fn agent_error(&self, error: &str) {
-        let _ = self.sender.send(Message::agent_error(
+        let _ = self.sender.send(self.nested(Message::agent_error(
             &self.agent_name,
             &self.agent_display_name,
             error,
+        )));
+    }
+
+    /// Publish a [`Message::Metrics`] reading for the output streamed so far.
+    ///
+    /// [`process`](Self::process) already does this periodically; call it
+    /// once the stream ends for a final reading. Does nothing before the
+    /// first token.
+    pub fn report_metrics(&mut self) {
+        let Some(first_token_at) = self.throughput.first_token_at else {
+            return;
+        };
+        let now = Instant::now();
+        self.throughput.last_reported_at = Some(now);
+        let _ = self.sender.send(Message::metrics_from(
+            // Same ~4 chars per token as tokens::estimate_text_tokens
+            self.throughput.bytes / 4,
+            now.duration_since(first_token_at).as_millis() as u64,
+            &self.agent_name,
         ));
     }
 
+    /// Count streamed output, reporting metrics at most once per interval.
+    fn record_output(&mut self, len: usize) {
+        let now = Instant::now();
+        let first_token_at = *self.throughput.first_token_at.get_or_insert(now);
+        self.throughput.bytes += len;
+
+        let last = self.throughput.last_reported_at.unwrap_or(first_token_at);
+        if now.duration_since(last) >= METRICS_INTERVAL {
+            self.report_metrics();
+        }
+    }
+
     /// Process a stream event and publish appropriate messages.
     ///
     /// This is the main entry point - call this for each event from the stream.
@@ -88,6 +211,7 @@ impl EventBridge {
             }
 
             StreamEvent::TextDelta { text } => {
+                self.record_output(text.len());
                 // Send as text delta with agent attribution
                 let _ = self
                     .sender
@@ -96,6 +220,7 @@ impl EventBridge {
             }
 
             StreamEvent::ThinkingDelta { text } => {
+                self.record_output(text.len());
                 let _ = self
                     .sender
                     .send(Message::thinking_from(&text, &self.agent_name));
@@ -136,6 +261,7 @@ impl EventBridge {
                 delta,
                 tool_call_id,
             } => {
+                self.record_output(delta.len());
                 // Accumulate args for the tool with matching tool_call_id, or last started
                 if let Some(ref id) = tool_call_id {
                     if let Some(state) = self.tool_states.get_mut(id) {
@@ -158,7 +284,7 @@ impl EventBridge {
                         .and_then(|s| s.tool_call_id.clone())
                 });
 
-                let args = if let Some(ref id) = resolved_id {
+                let args: Option<serde_json::Value> = if let Some(ref id) = resolved_id {
                     self.tool_states
                         .get(id)
                         .and_then(|s| serde_json::from_str(&s.args_buffer).ok())
@@ -169,6 +295,20 @@ impl EventBridge {
                         .and_then(|s| serde_json::from_str(&s.args_buffer).ok())
                 };
 
+                let reasoning = if self.show_reasoning && tool_name == "share_your_reasoning" {
+                    args.as_ref().and_then(|args| {
+                        let reasoning = args.get("reasoning")?.as_str()?;
+                        let next_steps = args.get("next_steps").and_then(|v| v.as_str());
+                        Some(Message::reasoning_from(
+                            reasoning,
+                            next_steps,
+                            &self.agent_name,
+                        ))
+                    })
+                } else {
+                    None
+                };
+
                 if let Some(ref id) = resolved_id {
                     let _ = self.sender.send(Message::tool_executing_with_id_from(
                         &tool_name,
@@ -183,6 +323,10 @@ impl EventBridge {
                         &self.agent_name,
                     ));
                 }
+
+                if let Some(reasoning) = reasoning {
+                    let _ = self.sender.send(reasoning);
+                }
             }
 
             StreamEvent::ToolExecuted {
@@ -204,18 +348,19 @@ impl EventBridge {
                     self.tool_states.remove(key);
                 }
 
+                // Always drain, so a failed call can't leave content for the next one
+                let content = self.contents.take(&tool_name);
+
                 if success {
-                    if let Some(ref id) = resolved_id {
-                        let _ = self.sender.send(Message::tool_completed_with_id_from(
-                            &tool_name,
-                            id,
-                            &self.agent_name,
-                        ));
+                    let mut msg = if let Some(ref id) = resolved_id {
+                        Message::tool_completed_with_id_from(&tool_name, id, &self.agent_name)
                     } else {
-                        let _ = self
-                            .sender
-                            .send(Message::tool_completed_from(&tool_name, &self.agent_name));
+                        Message::tool_completed_from(&tool_name, &self.agent_name)
+                    };
+                    if let Some(content) = content {
+                        msg = msg.with_tool_content(content);
                     }
+                    let _ = self.sender.send(msg);
                 } else if let Some(ref id) = resolved_id {
                     let _ = self.sender.send(Message::tool_failed_with_id_from(
                         &tool_name,
@@ -255,6 +400,7 @@ impl EventBridge {
     pub fn reset(&mut self) {
         self.tool_states.clear();
         self.first_text_sent = false;
+        self.throughput = Throughput::default();
     }
 }
 
@@ -345,7 +491,7 @@ mod tests {
         bridge.agent_completed("run-123");
 
         let msg1 = receiver.recv().await.unwrap();
-        assert!(matches!(msg1, Message::Agent(a) if a.agent_name == "reviewer"));
+        assert!(matches!(msg1, Message::Agent(a) if a.agent_name == "reviewer" && a.depth == 0));
 
         let msg2 = receiver.recv().await.unwrap();
         if let Message::Agent(a) = msg2 {
@@ -372,7 +518,7 @@ mod tests {
         });
 
         // Simulate sub-agent starting (same bus!)
-        let mut child_bridge = EventBridge::new(bus.sender(), "child", "Child Agent");
+        let mut child_bridge = EventBridge::new(bus.sender(), "child", "Child Agent").with_depth(1);
         child_bridge.agent_started();
         child_bridge.process(StreamEvent::TextDelta {
             text: "I am the child!".to_string(),
@@ -414,7 +560,7 @@ mod tests {
 
         // Third should be child agent started
         assert!(
-            matches!(&messages[2], Message::Agent(a) if a.agent_name == "child"),
+            matches!(&messages[2], Message::Agent(a) if a.agent_name == "child" && a.depth == 1),
             "Expected child agent started, got {:?}",
             messages[2]
         );
@@ -1773,4 +1919,157 @@ mod tests {
         assert!(!bridge_a.tool_states.contains_key("b-id"));
         assert!(!bridge_b.tool_states.contains_key("a-id"));
     }
+
+    // =========================================================================
+    // Structured Tool Content Tests
+    // =========================================================================
+
+    #[tokio::test]
+    async fn test_tool_completed_carries_structured_content() {
+        let bus = MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut bridge = EventBridge::new(bus.sender(), "test-agent", "Test Agent");
+        let store = bridge.content_store();
+
+        store.push(
+            "screenshot",
+            ToolResultContent::Image {
+                media_type: "image/png".to_string(),
+                data: "aGVsbG8=".to_string(),
+            },
+        );
+        bridge.process(StreamEvent::ToolExecuted {
+            tool_call_id: Some("call-1".to_string()),
+            tool_name: "screenshot".to_string(),
+            success: true,
+            error: None,
+        });
+
+        match receiver.recv().await.unwrap() {
+            Message::Tool(t) => {
+                assert_eq!(t.status, crate::messaging::ToolStatus::Completed);
+                assert!(matches!(t.content, Some(ToolResultContent::Image { .. })));
+            }
+            _ => panic!("Expected Tool message"),
+        }
+
+        // Content is consumed, so a later call without content stays plain
+        bridge.process(StreamEvent::ToolExecuted {
+            tool_call_id: Some("call-2".to_string()),
+            tool_name: "screenshot".to_string(),
+            success: true,
+            error: None,
+        });
+        match receiver.recv().await.unwrap() {
+            Message::Tool(t) => assert!(t.content.is_none()),
+            _ => panic!("Expected Tool message"),
+        }
+    }
+
+    fn share_reasoning(bridge: &mut EventBridge) {
+        bridge.process(StreamEvent::ToolCallStart {
+            tool_name: "share_your_reasoning".to_string(),
+            tool_call_id: Some("r1".to_string()),
+        });
+        bridge.process(StreamEvent::ToolCallDelta {
+            tool_call_id: Some("r1".to_string()),
+            delta: r#"{"reasoning":"Check the config first","next_steps":"Read it"}"#.to_string(),
+        });
+        bridge.process(StreamEvent::ToolCallComplete {
+            tool_call_id: Some("r1".to_string()),
+            tool_name: "share_your_reasoning".to_string(),
+        });
+    }
+
+    #[tokio::test]
+    async fn test_shared_reasoning_published_when_enabled() {
+        let bus = MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut bridge =
+            EventBridge::new(bus.sender(), "test-agent", "Test Agent").with_reasoning(true);
+
+        share_reasoning(&mut bridge);
+
+        let mut reasoning = None;
+        while let Ok(Some(msg)) = receiver.try_recv() {
+            if let Message::Reasoning(r) = msg {
+                reasoning = Some(r);
+            }
+        }
+        let reasoning = reasoning.expect("Expected Reasoning message");
+        assert_eq!(reasoning.reasoning, "Check the config first");
+        assert_eq!(reasoning.next_steps.as_deref(), Some("Read it"));
+        assert_eq!(reasoning.agent_name.as_deref(), Some("test-agent"));
+    }
+
+    #[tokio::test]
+    async fn test_shared_reasoning_hidden_by_default() {
+        let bus = MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut bridge = EventBridge::new(bus.sender(), "test-agent", "Test Agent");
+
+        share_reasoning(&mut bridge);
+
+        while let Ok(Some(msg)) = receiver.try_recv() {
+            assert!(!matches!(msg, Message::Reasoning(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_metrics_estimates_streamed_tokens() {
+        let bus = MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut bridge = EventBridge::new(bus.sender(), "test-agent", "Test Agent");
+
+        // Nothing streamed yet, so nothing to report
+        bridge.report_metrics();
+
+        bridge.process(StreamEvent::ThinkingDelta {
+            text: "a".repeat(40),
+        });
+        bridge.process(StreamEvent::TextDelta {
+            text: "b".repeat(40),
+        });
+        bridge.report_metrics();
+
+        assert!(matches!(
+            receiver.recv().await.unwrap(),
+            Message::Thinking(_)
+        ));
+        assert!(matches!(
+            receiver.recv().await.unwrap(),
+            Message::TextDelta(_)
+        ));
+        match receiver.recv().await.unwrap() {
+            Message::Metrics(metrics) => {
+                assert_eq!(metrics.tokens_so_far, 20);
+                assert_eq!(metrics.agent_name, Some("test-agent".to_string()));
+                assert!(metrics.tokens_per_sec >= 0.0);
+            }
+            other => panic!("Expected Metrics message, got {:?}", other),
+        }
+
+        bridge.reset();
+        bridge.report_metrics();
+        assert!(receiver.try_recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_tool_content_store_is_fifo_per_tool() {
+        let store = ToolContentStore::new();
+        store.push("a", ToolResultContent::Text("first".to_string()));
+        store.push("a", ToolResultContent::Text("second".to_string()));
+        store.push("b", ToolResultContent::Text("other".to_string()));
+
+        assert_eq!(
+            store.take("a"),
+            Some(ToolResultContent::Text("first".to_string()))
+        );
+        assert_eq!(
+            store.take("a"),
+            Some(ToolResultContent::Text("second".to_string()))
+        );
+        assert_eq!(store.take("a"), None);
+        assert!(store.take("b").is_some());
+    }
 }
@@ -0,0 +1,578 @@
+//! Terminal formatting for streamed markdown responses.
+//!
+//! [`MarkdownStream`] sits between streamed deltas and the terminal. Prose
+//! passes through as it arrives, word-wrapped to the terminal width when
+//! one is given. Lines that could be a code fence or a table row are held
+//! until they are complete and then written as-is, and code lines are
+//! written a whole line at a time, ANSI-highlighted by language when
+//! highlighting is on. Code in a language syntect doesn't know is passed
+//! through plain.
+//!
+//! In plain mode ([`MarkdownStream::plain`]) markdown is stripped to
+//! readable text instead: emphasis, inline code ticks and heading marks
+//! are dropped, links become `text (url)`, and code blocks lose their
+//! fences but keep their content. Each line is held until it is complete.
+
+use std::sync::OnceLock;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::as_24_bit_terminal_escaped;
+
+const FENCE: &str = "```";
+const RESET: &str = "\x1b[0m";
+
+/// Formats a stream of markdown text for the terminal.
+#[derive(Default)]
+pub(super) struct MarkdownStream {
+    /// Start of the current line, not yet written
+    pending: String,
+    /// Part of the current (prose) line has already been written
+    line_started: bool,
+    /// Inside a code block; `None` inside means no highlighting for it
+    block: Option<Option<HighlightLines<'static>>>,
+    wrap: WordWrap,
+    /// Strip markdown instead of formatting it
+    plain: bool,
+}
+
+impl MarkdownStream {
+    /// A stream that strips markdown to plain text.
+    pub fn plain() -> Self {
+        Self {
+            plain: true,
+            ..Self::default()
+        }
+    }
+
+    /// Take the next chunk of text, returning what can be written now.
+    ///
+    /// `highlight` applies to code blocks opened in this chunk; `width`
+    /// wraps prose, and `None` leaves it unwrapped.
+    pub fn push(&mut self, text: &str, highlight: bool, width: Option<usize>) -> String {
+        let mut out = String::new();
+        for piece in text.split_inclusive('\n') {
+            let complete = piece.ends_with('\n');
+
+            if self.line_started {
+                self.wrap.push(piece, width, &mut out);
+                self.line_started = !complete;
+                continue;
+            }
+
+            self.pending.push_str(piece);
+            if complete {
+                let line = std::mem::take(&mut self.pending);
+                self.finish_line(&line, highlight, width, &mut out);
+            } else if self.block.is_none() && !self.plain && !could_be_held(&self.pending) {
+                // Plain prose: stream it rather than waiting for the newline
+                let partial = std::mem::take(&mut self.pending);
+                self.wrap.push(&partial, width, &mut out);
+                self.line_started = true;
+            }
+        }
+        out
+    }
+
+    /// Write out anything held back and forget any open code block.
+    pub fn flush(&mut self) -> String {
+        let mut out = String::new();
+        self.wrap.flush(&mut out);
+        let pending = std::mem::take(&mut self.pending);
+        match self.block.as_mut() {
+            Some(Some(highlighter)) => out.push_str(&highlight(highlighter, &pending)),
+            None if self.plain && !is_table_row(&pending) => out.push_str(&strip_line(&pending)),
+            _ => out.push_str(&pending),
+        }
+        self.block = None;
+        self.line_started = false;
+        self.wrap.col = 0;
+        out
+    }
+
+    fn finish_line(
+        &mut self,
+        line: &str,
+        highlight_code: bool,
+        width: Option<usize>,
+        out: &mut String,
+    ) {
+        let fence = line.trim_start().strip_prefix(FENCE);
+        match (self.block.as_mut(), fence) {
+            (Some(_), Some(_)) => {
+                self.block = None;
+                if !self.plain {
+                    out.push_str(line);
+                }
+            }
+            (Some(Some(highlighter)), None) => out.push_str(&highlight(highlighter, line)),
+            (Some(None), None) => out.push_str(line),
+            (None, Some(info)) => {
+                self.block = Some(if highlight_code && !self.plain {
+                    highlighter_for(info.trim())
+                } else {
+                    None
+                });
+                if !self.plain {
+                    out.push_str(line);
+                }
+            }
+            // Table rows keep their layout
+            (None, None) if is_table_row(line) => out.push_str(line),
+            (None, None) if self.plain => self.wrap.push(&strip_line(line), width, out),
+            (None, None) => self.wrap.push(line, width, out),
+        }
+    }
+}
+
+/// Word-wraps prose as it streams in.
+///
+/// The word being typed is held until the whitespace after it arrives, so
+/// it can move to the next line whole. Words longer than the width are
+/// left to overflow.
+#[derive(Debug, Default)]
+struct WordWrap {
+    /// Columns written on the current line
+    col: usize,
+    word: String,
+    /// Spaces seen since the last word
+    spaces: usize,
+}
+
+impl WordWrap {
+    fn push(&mut self, text: &str, width: Option<usize>, out: &mut String) {
+        let Some(width) = width else {
+            self.flush(out);
+            out.push_str(text);
+            self.col = match text.rfind('\n') {
+                Some(i) => text[i + 1..].chars().count(),
+                None => self.col + text.chars().count(),
+            };
+            return;
+        };
+
+        for c in text.chars() {
+            match c {
+                '\n' => {
+                    self.flush_word(width, out);
+                    self.spaces = 0;
+                    out.push('\n');
+                    self.col = 0;
+                }
+                ' ' => {
+                    self.flush_word(width, out);
+                    self.spaces += 1;
+                }
+                _ => self.word.push(c),
+            }
+        }
+    }
+
+    /// Write the held word and spaces without wrapping.
+    fn flush(&mut self, out: &mut String) {
+        self.flush_word(usize::MAX, out);
+        out.push_str(&" ".repeat(self.spaces));
+        self.col += self.spaces;
+        self.spaces = 0;
+    }
+
+    fn flush_word(&mut self, width: usize, out: &mut String) {
+        if self.word.is_empty() {
+            return;
+        }
+
+        let len = self.word.chars().count();
+        if self.col > 0 && self.col.saturating_add(self.spaces + len) > width {
+            out.push('\n');
+            self.col = 0;
+        } else {
+            out.push_str(&" ".repeat(self.spaces));
+            self.col += self.spaces;
+        }
+        self.spaces = 0;
+        out.push_str(&self.word);
+        self.col += len;
+        self.word.clear();
+    }
+}
+
+/// Whether an incomplete line might still turn out to be a fence or a
+/// table row.
+fn could_be_held(partial: &str) -> bool {
+    let trimmed = partial.trim_start();
+    FENCE.starts_with(trimmed) || trimmed.starts_with(FENCE) || trimmed.starts_with('|')
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+/// A prose line with its markdown stripped, keeping indentation and any
+/// trailing newline.
+fn strip_line(line: &str) -> String {
+    let (body, newline) = match line.strip_suffix('\n') {
+        Some(body) => (body, "\n"),
+        None => (line, ""),
+    };
+    let text = body.trim_start();
+    let indent = &body[..body.len() - text.len()];
+
+    let mut text = text;
+    while let Some(rest) = text.strip_prefix('>') {
+        text = rest.trim_start();
+    }
+    let hashes = text.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&hashes) && text[hashes..].starts_with(' ') {
+        text = text[hashes..].trim();
+    }
+    let bullet = match text.strip_prefix("* ").or_else(|| text.strip_prefix("+ ")) {
+        Some(rest) => {
+            text = rest;
+            "- "
+        }
+        None => "",
+    };
+
+    format!("{}{}{}{}", indent, bullet, strip_inline(text), newline)
+}
+
+/// `text` without inline markdown: emphasis, strikethrough, code ticks,
+/// escapes, and links rewritten as `text (url)`.
+fn strip_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\\' if next.is_some_and(|n| n.is_ascii_punctuation()) => {
+                out.extend(next);
+                i += 2;
+            }
+            '`' => {
+                // Inline code is copied verbatim, without its ticks
+                let ticks = chars[i..].iter().take_while(|c| **c == '`').count();
+                let body = i + ticks;
+                let close = (body..chars.len()).find(|&j| {
+                    chars[j..].iter().take_while(|c| **c == '`').count() == ticks
+                        && (j == 0 || chars[j - 1] != '`')
+                });
+                match close {
+                    Some(close) => {
+                        out.extend(&chars[body..close]);
+                        i = close + ticks;
+                    }
+                    None => {
+                        out.extend(&chars[i..body]);
+                        i = body;
+                    }
+                }
+            }
+            '*' | '_' | '~' if next == Some(c) => match closing_pair(&chars, i) {
+                Some(close) => {
+                    let inner: String = chars[i + 2..close].iter().collect();
+                    out.push_str(&strip_inline(&inner));
+                    i = close + 2;
+                }
+                None => {
+                    out.extend(&chars[i..i + 2]);
+                    i += 2;
+                }
+            },
+            '*' if is_emphasis(&chars, i) => i += 1,
+            '!' if next == Some('[') && link_at(&chars, i + 1).is_some() => i += 1,
+            '[' => match link_at(&chars, i) {
+                Some((label, url, end)) => {
+                    let label = strip_inline(&label);
+                    if label.is_empty() || label == url {
+                        out.push_str(&url);
+                    } else {
+                        out.push_str(&format!("{} ({})", label, url));
+                    }
+                    i = end;
+                }
+                None => {
+                    out.push(c);
+                    i += 1;
+                }
+            },
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Whether the `*` at `i` opens or closes emphasis, rather than being a
+/// literal such as a multiplication sign.
+fn is_emphasis(chars: &[char], i: usize) -> bool {
+    let before = i.checked_sub(1).map(|j| chars[j]);
+    let after = chars.get(i + 1).copied();
+    let word = |c: Option<char>| c.is_some_and(|c| !c.is_whitespace());
+    let boundary =
+        |c: Option<char>| c.is_none_or(|c| c.is_whitespace() || c.is_ascii_punctuation());
+    (word(after) && boundary(before)) || (word(before) && boundary(after))
+}
+
+/// The end of the `**`, `__` or `~~` pair opened at `i`: a run of the same
+/// two characters that can close it, following the CommonMark flanking
+/// rules. `None` leaves the run as text, as in `2 ** 8`.
+///
+/// A pair of underscores around a single identifier, like `__init__`, is
+/// taken for a Python name rather than emphasis.
+fn closing_pair(chars: &[char], i: usize) -> Option<usize> {
+    let c = chars[i];
+    let punctuation = |c: Option<char>| c.is_some_and(|c| c.is_ascii_punctuation());
+    let can_open = left_flanking(chars, i, i + 2)
+        && (c != '_' || !right_flanking(chars, i, i + 2) || punctuation(char_before(chars, i)));
+    if !can_open {
+        return None;
+    }
+    let close = (i + 3..chars.len().saturating_sub(1)).find(|&j| {
+        chars[j] == c
+            && chars[j + 1] == c
+            && chars[j - 1] != c
+            && right_flanking(chars, j, j + 2)
+            && (c != '_'
+                || !left_flanking(chars, j, j + 2)
+                || punctuation(chars.get(j + 2).copied()))
+    })?;
+    let identifier = chars[i + 2..close]
+        .iter()
+        .all(|c| c.is_alphanumeric() || *c == '_');
+    (c != '_' || !identifier).then_some(close)
+}
+
+fn char_before(chars: &[char], i: usize) -> Option<char> {
+    i.checked_sub(1).map(|j| chars[j])
+}
+
+/// Whether the delimiter run `chars[start..end]` can open emphasis: it's
+/// followed by text, and not punctuation unless preceded by a boundary.
+fn left_flanking(chars: &[char], start: usize, end: usize) -> bool {
+    let before = char_before(chars, start);
+    let after = chars.get(end).copied();
+    let boundary =
+        |c: Option<char>| c.is_none_or(|c| c.is_whitespace() || c.is_ascii_punctuation());
+    after.is_some_and(|c| !c.is_whitespace())
+        && (after.is_some_and(|c| !c.is_ascii_punctuation()) || boundary(before))
+}
+
+/// Whether the delimiter run `chars[start..end]` can close emphasis, the
+/// mirror image of [`left_flanking`].
+fn right_flanking(chars: &[char], start: usize, end: usize) -> bool {
+    let before = char_before(chars, start);
+    let after = chars.get(end).copied();
+    let boundary =
+        |c: Option<char>| c.is_none_or(|c| c.is_whitespace() || c.is_ascii_punctuation());
+    before.is_some_and(|c| !c.is_whitespace())
+        && (before.is_some_and(|c| !c.is_ascii_punctuation()) || boundary(after))
+}
+
+/// A `[label](url)` link starting at `i`: its label, url and end index.
+fn link_at(chars: &[char], i: usize) -> Option<(String, String, usize)> {
+    let label_end = (i + 1..chars.len()).find(|&j| chars[j] == ']')?;
+    if chars.get(label_end + 1) != Some(&'(') {
+        return None;
+    }
+    let url_end = (label_end + 2..chars.len()).find(|&j| chars[j] == ')')?;
+    let label = chars[i + 1..label_end].iter().collect();
+    let url = chars[label_end + 2..url_end].iter().collect();
+    Some((label, url, url_end + 1))
+}
+
+fn highlight(highlighter: &mut HighlightLines<'static>, line: &str) -> String {
+    let escaped = match highlighter.highlight_line(line, syntax_set()) {
+        Ok(ranges) => as_24_bit_terminal_escaped(&ranges, false),
+        Err(_) => return line.to_string(),
+    };
+    // Reset before the newline so the line still ends with it
+    match escaped.strip_suffix('\n') {
+        Some(body) => format!("{}{}\n", body, RESET),
+        None => format!("{}{}", escaped, RESET),
+    }
+}
+
+/// A highlighter for a fence's info string (e.g. `rust` or `py title=x`).
+fn highlighter_for(info: &str) -> Option<HighlightLines<'static>> {
+    let token = info.split_whitespace().next()?;
+    let syntax = syntax_set().find_syntax_by_token(token)?;
+    Some(HighlightLines::new(syntax, theme()))
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        themes
+            .themes
+            .remove("base16-ocean.dark")
+            .unwrap_or_else(|| themes.themes.into_values().next().unwrap_or_default())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(chunks: &[&str], width: Option<usize>) -> String {
+        let mut stream = MarkdownStream::default();
+        let mut out: String = chunks.iter().map(|c| stream.push(c, true, width)).collect();
+        out.push_str(&stream.flush());
+        out
+    }
+
+    fn chars(text: &str) -> Vec<String> {
+        text.chars().map(String::from).collect()
+    }
+
+    // =========================================================================
+    // Highlighting Tests
+    // =========================================================================
+
+    #[test]
+    fn test_prose_streams_immediately() {
+        let mut stream = MarkdownStream::default();
+        assert_eq!(stream.push("Hello, ", true, None), "Hello, ");
+        assert_eq!(stream.push("world", true, None), "world");
+        assert_eq!(stream.push("\n", true, None), "\n");
+    }
+
+    #[test]
+    fn test_possible_fence_is_held_until_line_ends() {
+        let mut stream = MarkdownStream::default();
+        assert_eq!(stream.push("``", true, None), "");
+        assert_eq!(stream.push("`rust\n", true, None), "```rust\n");
+        // Code waits for its newline
+        assert_eq!(stream.push("let x", true, None), "");
+        assert!(stream.push(" = 1;\n", true, None).contains('\x1b'));
+    }
+
+    #[test]
+    fn test_known_language_is_highlighted() {
+        let out = run(&["Look:\n```rust\nfn main() {}\n```\ndone\n"], None);
+        assert!(out.starts_with("Look:\n```rust\n"));
+        assert!(out.contains("\x1b[38;2;"));
+        assert!(out.ends_with("```\ndone\n"));
+    }
+
+    #[test]
+    fn test_highlighting_off_leaves_code_plain() {
+        let input = "```rust\nfn main() {}\n```\n";
+        let mut stream = MarkdownStream::default();
+        assert_eq!(stream.push(input, false, None), input);
+    }
+
+    #[test]
+    fn test_unknown_language_falls_back_to_plain() {
+        let input = "```nosuchlang\nsome code\n```\n";
+        assert_eq!(run(&[input], None), input);
+
+        let input = "```\nplain block\n```\n";
+        assert_eq!(run(&[input], None), input);
+    }
+
+    #[test]
+    fn test_split_deltas_match_single_chunk() {
+        let text = "Intro\n```python\nprint('hi')\n```\nOutro";
+        let chunks = chars(text);
+        let chunks: Vec<&str> = chunks.iter().map(String::as_str).collect();
+        assert_eq!(run(&chunks, None), run(&[text], None));
+    }
+
+    #[test]
+    fn test_flush_emits_unterminated_code() {
+        let mut stream = MarkdownStream::default();
+        stream.push("```\ncut off", true, None);
+        assert_eq!(stream.flush(), "cut off");
+        // The block is closed, so the next text is prose again
+        assert_eq!(stream.push("next", true, None), "next");
+    }
+
+    // =========================================================================
+    // Plain Mode Tests
+    // =========================================================================
+
+    #[test]
+    fn test_plain_strips_inline_markdown() {
+        assert_eq!(
+            strip_line("> ## **Bold** and *em* with `a*b` ~~gone~~\n"),
+            "Bold and em with a*b gone\n"
+        );
+        assert_eq!(strip_line("  * item 2 * 3\n"), "  - item 2 * 3\n");
+        assert_eq!(
+            strip_line("![logo](img.png) [https://x.io](https://x.io) \\*not em\\*"),
+            "logo (img.png) https://x.io *not em*"
+        );
+    }
+
+    #[test]
+    fn test_plain_keeps_doubled_markers_that_are_not_emphasis() {
+        assert_eq!(strip_line("2 ** 8 is 256"), "2 ** 8 is 256");
+        assert_eq!(
+            strip_line("override __init__ and self.__dict__"),
+            "override __init__ and self.__dict__"
+        );
+        assert_eq!(strip_line("a__b__c and **open"), "a__b__c and **open");
+        assert_eq!(
+            strip_line("__quite so__ **very** ~~old~~"),
+            "quite so very old"
+        );
+    }
+
+    #[test]
+    fn test_plain_drops_fences_but_keeps_code() {
+        let text = "Run:\n```sh\ncargo **test**\n```\n| a | b |\n";
+        let mut stream = MarkdownStream::plain();
+        let chunks = chars(text);
+        let mut out: String = chunks.iter().map(|c| stream.push(c, true, None)).collect();
+        out.push_str(&stream.flush());
+        assert_eq!(out, "Run:\ncargo **test**\n| a | b |\n");
+    }
+
+    // =========================================================================
+    // Wrapping Tests
+    // =========================================================================
+
+    #[test]
+    fn test_prose_wraps_at_width() {
+        let out = run(&["the quick brown fox jumps over the lazy dog\n"], Some(16));
+        assert_eq!(out, "the quick brown\nfox jumps over\nthe lazy dog\n");
+        assert!(out.lines().all(|line| line.chars().count() <= 16));
+    }
+
+    #[test]
+    fn test_wrapping_streamed_deltas_matches_single_chunk() {
+        let text = "the quick brown fox jumps over the lazy dog";
+        let chunks = chars(text);
+        let chunks: Vec<&str> = chunks.iter().map(String::as_str).collect();
+        assert_eq!(run(&chunks, Some(16)), run(&[text], Some(16)));
+    }
+
+    #[test]
+    fn test_wrapping_keeps_indentation_and_long_words() {
+        let out = run(
+            &["  - item\nhttps://example.com/a/very/long/path ok\n"],
+            Some(10),
+        );
+        assert_eq!(out, "  - item\nhttps://example.com/a/very/long/path\nok\n");
+    }
+
+    #[test]
+    fn test_code_and_tables_are_not_wrapped() {
+        let code = "```\nlet numbers = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];\n```\n";
+        assert_eq!(run(&[code], Some(10)), code);
+
+        let table = "| name | description of the agent |\n|---|---|\n";
+        assert_eq!(run(&[table], Some(10)), table);
+    }
+}
@@ -37,23 +37,22 @@
 //! - [`Message`]: UI-agnostic event types (agent lifecycle, tool calls, text, etc.)
 //! - [`MessageBus`]: Broadcast channel for pub/sub
 //! - [`EventBridge`]: Converts `StreamEvent` to `Message` and publishes
+//! - [`MessageRenderer`]: Trait for front-ends that consume the bus
 //! - [`TerminalRenderer`]: Renders messages to terminal with colors/formatting
+//! - [`BridgeRenderer`]: Writes messages as versioned NDJSON ([`BridgeEvent`])
+//!   for out-of-process clients such as editor extensions
 //!
 //! ## Usage
 //!
 //! ```ignore
-//! use stockpot::messaging::{MessageBus, EventBridge, TerminalRenderer};
+//! use stockpot::messaging::{MessageBus, EventBridge, MessageRenderer, TerminalRenderer};
 //!
 //! let bus = MessageBus::new();
-//! let renderer = TerminalRenderer::new();
+//! let mut renderer = TerminalRenderer::new();
 //!
-//! // Subscribe and render messages
-//! let mut receiver = bus.subscribe();
-//! tokio::spawn(async move {
-//!     while let Ok(message) = receiver.recv().await {
-//!         let _ = renderer.render(&message);
-//!     }
-//! });
+//! // Subscribe and render messages until the bus closes
+//! let receiver = bus.subscribe();
+//! tokio::spawn(async move { renderer.run_loop(receiver).await });
 //!
 //! // Create executor with bus
 //! let executor = AgentExecutor::new(&db, &registry)
@@ -63,6 +62,12 @@
 //! executor.execute_with_bus(agent, model, prompt, ...).await;
 //! ```
 //!
+//! ## Custom front-ends
+//!
+//! Any UI can consume the same bus by implementing [`MessageRenderer`]:
+//! only `render` is required, and the provided `run_loop` handles receiving,
+//! lag and shutdown. [`TerminalRenderer`] is itself just one implementation.
+//!
 //! ## Provides
 //!
 //! - [`Message`] types for agent-UI communication
@@ -70,10 +75,20 @@
 //! - Terminal rendering with syntax highlighting
 //! - Animated spinner for activity indication
 
+mod bridge;
 mod bus;
 mod event_bridge;
+mod markdown;
+mod renderer;
+mod spinner;
 mod types;
 
-pub use bus::{MessageBus, MessageSender};
-pub use event_bridge::EventBridge;
+pub use bridge::{
+    BridgeCommand, BridgeError, BridgeEvent, BridgeEventKind, BridgeRenderer, BRIDGE_PROTOCOL_NAME,
+    BRIDGE_PROTOCOL_VERSION,
+};
+pub use bus::{MessageBus, MessageReceiver, MessageSender};
+pub use event_bridge::{EventBridge, ToolContentStore};
+pub use renderer::{MessageRenderer, RenderStyle, TerminalRenderer};
+pub use spinner::{SpinnerConfig, SpinnerHandle};
 pub use types::*;
@@ -0,0 +1,804 @@
+//! Pluggable rendering of bus messages.
+//!
+//! [`MessageRenderer`] is the extension point for front-ends: implement
+//! `render` for your UI (a TUI, a log sink, a web socket) and call
+//! `run_loop` with a [`MessageReceiver`] to drive it from the bus. The
+//! provided [`TerminalRenderer`] is one such implementation for plain
+//! line-oriented terminals.
+
+use std::io::{self, Stdout, Write};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use nu_ansi_term::{Color, Style};
+
+use super::bus::BusError;
+use super::markdown::MarkdownStream;
+use super::spinner::{SpinnerConfig, SpinnerHandle};
+use super::{
+    AgentEvent, AgentMessage, DiffLineType, McpServerEvent, Message, MessageLevel, MessageReceiver,
+    ToolResultContent, ToolStatus,
+};
+
+/// Renders messages from the bus for a particular front-end.
+#[async_trait]
+pub trait MessageRenderer: Send {
+    /// Called once by `run_loop` before the first message.
+    fn start(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Render a single message.
+    fn render(&mut self, msg: &Message) -> io::Result<()>;
+
+    /// Render messages until the bus closes.
+    ///
+    /// Lagging behind the bus skips the dropped messages rather than
+    /// stopping. Returns the first render error.
+    async fn run_loop(&mut self, mut receiver: MessageReceiver) -> io::Result<()> {
+        self.start()?;
+        loop {
+            match receiver.recv().await {
+                Ok(msg) => self.render(&msg)?,
+                Err(BusError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "Renderer lagged behind the message bus");
+                }
+                Err(BusError::Closed) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Layout of [`TerminalRenderer`] output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStyle {
+    /// Word-wrap response prose; code blocks and tables are left intact
+    pub wrap: bool,
+    /// Wrap at this many columns instead of the terminal's current width
+    pub width: Option<usize>,
+    /// Strip markdown to plain text and never write ANSI codes, so output
+    /// can be copied cleanly
+    pub plain: bool,
+    /// Show what sub-agents write, indented under the agent that invoked
+    /// them, rather than only a summary line when each finishes
+    pub expand_agents: bool,
+}
+
+impl RenderStyle {
+    /// Unwrapped plain text without markdown or colors.
+    pub fn plain() -> Self {
+        Self {
+            wrap: false,
+            width: None,
+            plain: true,
+            expand_agents: false,
+        }
+    }
+}
+
+/// A sub-agent run being rendered.
+struct SubAgentRun {
+    name: String,
+    display_name: String,
+    depth: usize,
+    started: Instant,
+    tool_calls: usize,
+}
+
+impl SubAgentRun {
+    /// The line shown when the run finishes.
+    fn summary(&self) -> String {
+        format!(
+            "▸ {} finished · {} tool call{} · {:.1}s",
+            self.display_name,
+            self.tool_calls,
+            if self.tool_calls == 1 { "" } else { "s" },
+            self.started.elapsed().as_secs_f64()
+        )
+    }
+}
+
+/// Line-oriented terminal renderer with optional ANSI colors.
+///
+/// With colors on, fenced code blocks in the agent's responses are
+/// syntax-highlighted by language. While the agent thinks or a tool runs,
+/// a spinner is drawn on stderr and cleared before the next output.
+///
+/// Sub-agents started with invoke_agent are collapsed to one summary line
+/// when they finish, with the spinner showing what they're doing meanwhile.
+/// With [`RenderStyle::expand_agents`] their output is shown too, behind a
+/// `│` per level of nesting.
+pub struct TerminalRenderer<W: Write + Send = Stdout> {
+    out: W,
+    color: bool,
+    style: RenderStyle,
+    /// Whether the last write ended mid-line (streamed text)
+    mid_line: bool,
+    /// Wraps and highlights response text
+    markdown: MarkdownStream,
+    spinner_config: SpinnerConfig,
+    /// The running spinner, if any
+    spinner: Option<SpinnerHandle>,
+    /// Sub-agents running, innermost last
+    sub_agents: Vec<SubAgentRun>,
+    /// Nesting depth of the output being written
+    indent: usize,
+}
+
+impl TerminalRenderer<Stdout> {
+    /// Render to stdout, with colors unless `NO_COLOR` is set or stdout
+    /// isn't a terminal, and prose wrapped when it is one. The spinner
+    /// follows [`SpinnerConfig::from_env`].
+    pub fn new() -> Self {
+        use std::io::IsTerminal;
+        let tty = io::stdout().is_terminal();
+        let color = std::env::var_os("NO_COLOR").is_none() && tty;
+        Self::with_writer(io::stdout())
+            .with_color(color)
+            .with_style(RenderStyle {
+                wrap: tty,
+                ..RenderStyle::default()
+            })
+            .with_spinner(SpinnerConfig::from_env())
+    }
+}
+
+impl Default for TerminalRenderer<Stdout> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write + Send> TerminalRenderer<W> {
+    /// Render to an arbitrary writer, without colors, wrapping or spinner.
+    pub fn with_writer(out: W) -> Self {
+        Self {
+            out,
+            color: false,
+            style: RenderStyle::default(),
+            mid_line: false,
+            markdown: MarkdownStream::default(),
+            spinner_config: SpinnerConfig::disabled(),
+            spinner: None,
+            sub_agents: Vec::new(),
+            indent: 0,
+        }
+    }
+
+    /// Enable or disable ANSI colors.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// How output is laid out.
+    pub fn style(&self) -> RenderStyle {
+        self.style
+    }
+
+    /// Set how output is laid out.
+    pub fn with_style(mut self, style: RenderStyle) -> Self {
+        self.style = style;
+        self.markdown = if style.plain {
+            MarkdownStream::plain()
+        } else {
+            MarkdownStream::default()
+        };
+        self
+    }
+
+    /// Configure the spinner; [`SpinnerConfig::disabled`] turns it off.
+    pub fn with_spinner(mut self, config: SpinnerConfig) -> Self {
+        self.spinner_config = config;
+        self
+    }
+
+    /// Consume the renderer, returning the writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn paint(&self, style: Style, text: &str) -> String {
+        if self.color && !self.style.plain {
+            style.paint(text).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Show the spinner next to `text`, starting it if needed.
+    ///
+    /// Not while streamed text ends mid-line: the spinner draws over the
+    /// current terminal line and would erase it.
+    fn spin(&mut self, text: &str) {
+        match &self.spinner {
+            Some(spinner) => spinner.set_text(text),
+            None if self.mid_line => {}
+            None => {
+                let spinner = SpinnerHandle::start(&self.spinner_config, text);
+                if spinner.is_active() {
+                    self.spinner = Some(spinner);
+                }
+            }
+        }
+    }
+
+    /// Stop the spinner, clearing its line.
+    fn stop_spinner(&mut self) {
+        if let Some(mut spinner) = self.spinner.take() {
+            spinner.stop();
+        }
+    }
+
+    /// Write a full line, first ending any streamed text.
+    fn line(&mut self, text: &str) -> io::Result<()> {
+        self.stop_spinner();
+        self.end_line()?;
+        let prefix = self.prefix();
+        for line in text.split('\n') {
+            writeln!(self.out, "{}{}", prefix, line)?;
+        }
+        Ok(())
+    }
+
+    /// Marks nested output, one `│` per level.
+    fn prefix(&self) -> String {
+        match self.indent {
+            0 => String::new(),
+            depth => self.paint(Style::new().dimmed(), &"│ ".repeat(depth)),
+        }
+    }
+
+    /// Write the following output at `depth`, ending any streamed text
+    /// written at another.
+    fn set_indent(&mut self, depth: usize) -> io::Result<()> {
+        if depth != self.indent {
+            self.end_line()?;
+            self.indent = depth;
+        }
+        Ok(())
+    }
+
+    /// How deeply the agent that sent `msg` is nested, 0 for the agent the
+    /// user ran or a message from no agent in particular.
+    fn depth_of(&self, msg: &Message) -> usize {
+        let agent = match msg {
+            Message::Agent(agent) => return agent.depth,
+            Message::Text(text) => text.agent_name.as_deref(),
+            Message::Diff(diff) => diff.agent_name.as_deref(),
+            Message::TextDelta(delta) => delta.agent_name.as_deref(),
+            Message::Thinking(thinking) => thinking.agent_name.as_deref(),
+            Message::Reasoning(reasoning) => reasoning.agent_name.as_deref(),
+            Message::Tool(tool) => tool.agent_name.as_deref(),
+            _ => None,
+        };
+        agent
+            .and_then(|name| self.sub_agents.iter().rev().find(|run| run.name == name))
+            .map_or(0, |run| run.depth)
+    }
+
+    /// Follow a collapsed sub-agent's progress on the spinner.
+    fn track_collapsed(&mut self, msg: &Message) {
+        let Message::Tool(tool) = msg else {
+            return;
+        };
+        if tool.status != ToolStatus::Executing {
+            return;
+        }
+        let Some(run) = self
+            .sub_agents
+            .iter_mut()
+            .rev()
+            .find(|run| Some(run.name.as_str()) == tool.agent_name.as_deref())
+        else {
+            return;
+        };
+        run.tool_calls += 1;
+        let text = format!("{}: running {}…", run.display_name, tool.tool_name);
+        self.spin(&text);
+    }
+
+    /// Start or finish a sub-agent run.
+    fn render_sub_agent(&mut self, agent: &AgentMessage) -> io::Result<()> {
+        let expand = self.style.expand_agents;
+        match &agent.event {
+            AgentEvent::Started => {
+                self.sub_agents.push(SubAgentRun {
+                    name: agent.agent_name.clone(),
+                    display_name: agent.display_name.clone(),
+                    depth: agent.depth,
+                    started: Instant::now(),
+                    tool_calls: 0,
+                });
+                if expand {
+                    self.set_indent(agent.depth)?;
+                    let text = format!("▶ {}", agent.display_name);
+                    self.line(&self.paint(Style::new().fg(Color::Cyan).bold(), &text))?;
+                }
+                self.spin(&format!("{} is working…", agent.display_name));
+            }
+            AgentEvent::Completed { .. } | AgentEvent::Error { .. } => {
+                let run = self
+                    .sub_agents
+                    .iter()
+                    .rposition(|run| run.name == agent.agent_name)
+                    .map(|i| self.sub_agents.remove(i));
+                self.set_indent(agent.depth - 1)?;
+                match (&agent.event, run) {
+                    (AgentEvent::Error { message }, _) => {
+                        let text = format!("✗ {}: {}", agent.display_name, message);
+                        self.line(&self.paint(Style::new().fg(Color::Red), &text))?;
+                    }
+                    (_, Some(run)) => {
+                        let text = run.summary();
+                        self.line(&self.paint(Style::new().fg(Color::Cyan), &text))?;
+                    }
+                    _ => {}
+                }
+                self.spin("Thinking…");
+            }
+        }
+        Ok(())
+    }
+
+    /// End any streamed text, so the next write starts a fresh line.
+    fn end_line(&mut self) -> io::Result<()> {
+        self.flush_markdown()?;
+        if self.mid_line {
+            writeln!(self.out)?;
+            self.mid_line = false;
+        }
+        Ok(())
+    }
+
+    /// Columns to wrap prose at, if wrapping.
+    ///
+    /// Checked on every write, so a resized window takes effect at once.
+    fn wrap_width(&self) -> Option<usize> {
+        if !self.style.wrap {
+            return None;
+        }
+        self.style
+            .width
+            .or_else(|| {
+                crossterm::terminal::size()
+                    .ok()
+                    .map(|(cols, _)| cols as usize)
+            })
+            .filter(|width| *width > 0)
+    }
+
+    /// Write streamed response text, wrapping prose and highlighting code
+    /// blocks.
+    fn stream_markdown(&mut self, text: &str) -> io::Result<()> {
+        let width = self
+            .wrap_width()
+            .map(|width| width.saturating_sub(2 * self.indent).max(1));
+        let text = self
+            .markdown
+            .push(text, self.color && !self.style.plain, width);
+        self.write_streamed(&text)
+    }
+
+    /// Write out response text still being held back.
+    fn flush_markdown(&mut self) -> io::Result<()> {
+        let rest = self.markdown.flush();
+        self.write_streamed(&rest)
+    }
+
+    /// Write streamed text as-is.
+    fn stream(&mut self, text: &str) -> io::Result<()> {
+        self.flush_markdown()?;
+        self.write_streamed(text)
+    }
+
+    fn write_streamed(&mut self, text: &str) -> io::Result<()> {
+        if !text.is_empty() {
+            self.stop_spinner();
+        }
+        for piece in text.split_inclusive('\n') {
+            if !self.mid_line {
+                let prefix = self.prefix();
+                write!(self.out, "{}", prefix)?;
+            }
+            write!(self.out, "{}", piece)?;
+            self.mid_line = !piece.ends_with('\n');
+        }
+        Ok(())
+    }
+
+    fn render_tool_content(&mut self, content: &ToolResultContent) -> io::Result<()> {
+        let dim = Style::new().dimmed();
+        match content {
+            ToolResultContent::Text(text) => self.line(&self.paint(dim, text)),
+            ToolResultContent::Json(value) => {
+                let pretty =
+                    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+                self.line(&self.paint(dim, &pretty))
+            }
+            ToolResultContent::Image { media_type, data } => {
+                // Terminals can't show images portably; note the size instead
+                let text = format!("  [image: {}, ~{} bytes]", media_type, data.len() * 3 / 4);
+                self.line(&self.paint(dim, &text))
+            }
+            ToolResultContent::Table { headers, rows } => {
+                self.line(&self.paint(Style::new().bold(), &format!("  {}", headers.join(" | "))))?;
+                for row in rows {
+                    self.line(&format!("  {}", row.join(" | ")))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<W: Write + Send> MessageRenderer for TerminalRenderer<W> {
+    fn render(&mut self, msg: &Message) -> io::Result<()> {
+        let dim = Style::new().dimmed();
+        let depth = self.depth_of(msg);
+        if let Message::Agent(agent) = msg {
+            if depth > 0 {
+                self.render_sub_agent(agent)?;
+                return self.out.flush();
+            }
+        }
+        if depth > 0 && !self.style.expand_agents {
+            self.track_collapsed(msg);
+            return Ok(());
+        }
+        self.set_indent(depth)?;
+        match msg {
+            Message::Text(text) => {
+                let style = match text.level {
+                    MessageLevel::Info => Style::new(),
+                    MessageLevel::Success => Style::new().fg(Color::Green),
+                    MessageLevel::Warning => Style::new().fg(Color::Yellow),
+                    MessageLevel::Error => Style::new().fg(Color::Red).bold(),
+                    MessageLevel::Debug => dim,
+                };
+                self.line(&self.paint(style, &text.text))?;
+            }
+            Message::Reasoning(reasoning) => {
+                self.line(&self.paint(dim, &reasoning.reasoning))?;
+                if let Some(next) = &reasoning.next_steps {
+                    self.line(&self.paint(dim, &format!("Next: {}", next)))?;
+                }
+            }
+            Message::Response(response) => {
+                if response.is_streaming {
+                    self.stream_markdown(&response.content)?;
+                } else {
+                    self.end_line()?;
+                    self.stream_markdown(&response.content)?;
+                    self.end_line()?;
+                }
+            }
+            Message::Shell(shell) => {
+                let prompt = self.paint(Style::new().fg(Color::Cyan), "$");
+                self.line(&format!("{} {}", prompt, shell.command))?;
+                if let Some(output) = &shell.output {
+                    self.line(output.trim_end())?;
+                }
+                if let Some(code) = shell.exit_code.filter(|c| *c != 0) {
+                    self.line(&self.paint(Style::new().fg(Color::Red), &format!("exit {}", code)))?;
+                }
+            }
+            Message::File(file) => {
+                let text = format!("{:?} {}", file.operation, file.path);
+                self.line(&self.paint(dim, &text))?;
+                if let Some(error) = &file.error {
+                    self.line(&self.paint(Style::new().fg(Color::Red), error))?;
+                }
+            }
+            Message::Diff(diff) => {
+                self.line(&self.paint(Style::new().bold(), &diff.path))?;
+                for line in &diff.lines {
+                    let (prefix, style) = match line.line_type {
+                        DiffLineType::Added => ("+", Style::new().fg(Color::Green)),
+                        DiffLineType::Removed => ("-", Style::new().fg(Color::Red)),
+                        DiffLineType::Header => ("", Style::new().fg(Color::Cyan)),
+                        DiffLineType::Context => (" ", Style::new()),
+                    };
+                    let text = format!("{}{}", prefix, line.content);
+                    self.line(&self.paint(style, &text))?;
+                }
+            }
+            Message::Spinner(spinner) => {
+                if spinner.is_active {
+                    self.spin(&spinner.text);
+                } else {
+                    self.stop_spinner();
+                }
+            }
+            Message::InputRequest(request) => {
+                self.line(&self.paint(Style::new().bold(), &request.prompt))?;
+                for (i, option) in request.options.iter().flatten().enumerate() {
+                    self.line(&format!("  {}. {}", i + 1, option))?;
+                }
+            }
+            Message::Agent(agent) => match &agent.event {
+                AgentEvent::Started => {
+                    let text = format!("▶ {}", agent.display_name);
+                    self.line(&self.paint(Style::new().fg(Color::Cyan).bold(), &text))?;
+                    self.spin("Thinking…");
+                }
+                AgentEvent::Completed { .. } => {
+                    self.stop_spinner();
+                    self.flush_markdown()?;
+                    if self.mid_line {
+                        self.line("")?;
+                    }
+                }
+                AgentEvent::Error { message } => {
+                    let text = format!("✗ {}: {}", agent.display_name, message);
+                    self.line(&self.paint(Style::new().fg(Color::Red), &text))?;
+                }
+            },
+            Message::Tool(tool) => match tool.status {
+                ToolStatus::Executing => {
+                    let text = format!("• {}", tool.tool_name);
+                    self.line(&self.paint(Style::new().fg(Color::Purple), &text))?;
+                    self.spin(&format!("Running {}…", tool.tool_name));
+                }
+                ToolStatus::Completed => {
+                    if let Some(content) = &tool.content {
+                        self.render_tool_content(content)?;
+                    }
+                    self.spin("Thinking…");
+                }
+                ToolStatus::Failed => {
+                    let text = format!(
+                        "✗ {}: {}",
+                        tool.tool_name,
+                        tool.error.as_deref().unwrap_or("failed")
+                    );
+                    self.line(&self.paint(Style::new().fg(Color::Red), &text))?;
+                    self.spin("Thinking…");
+                }
+                ToolStatus::Started | ToolStatus::ArgsStreaming => {}
+            },
+            Message::TextDelta(delta) => self.stream_markdown(&delta.text)?,
+            Message::Thinking(thinking) => {
+                let text = self.paint(dim, &thinking.text);
+                self.stream(&text)?;
+            }
+            Message::ToolProgress(_) | Message::Metrics(_) => {}
+            Message::McpServer(server) => {
+                let (text, style) = match server.event {
+                    McpServerEvent::Crashed => (
+                        format!("MCP server '{}' stopped responding", server.server),
+                        Style::new().fg(Color::Yellow),
+                    ),
+                    McpServerEvent::Restarted => (
+                        format!("MCP server '{}' restarted", server.server),
+                        Style::new().fg(Color::Green),
+                    ),
+                    McpServerEvent::GaveUp => (
+                        format!(
+                            "MCP server '{}' could not be restarted after {} attempts",
+                            server.server, server.attempt
+                        ),
+                        Style::new().fg(Color::Red),
+                    ),
+                };
+                self.line(&self.paint(style, &text))?;
+            }
+            Message::Divider => self.line(&self.paint(dim, &"─".repeat(40)))?,
+            Message::Clear => {
+                self.mid_line = false;
+            }
+        }
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::MessageBus;
+
+    fn rendered(msgs: &[Message]) -> String {
+        let mut renderer = TerminalRenderer::with_writer(Vec::new());
+        for msg in msgs {
+            renderer.render(msg).unwrap();
+        }
+        String::from_utf8(renderer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_terminal_renderer_streams_then_breaks_line() {
+        let out = rendered(&[
+            Message::text_delta("Hello, "),
+            Message::text_delta("world"),
+            Message::info("done"),
+        ]);
+        assert_eq!(out, "Hello, world\ndone\n");
+    }
+
+    #[test]
+    fn test_terminal_renderer_without_color_has_no_escapes() {
+        let out = rendered(&[
+            Message::error("boom"),
+            Message::tool_failed("grep", "bad regex"),
+        ]);
+        assert!(!out.contains('\x1b'));
+        assert!(out.contains("boom"));
+        assert!(out.contains("✗ grep: bad regex"));
+    }
+
+    #[test]
+    fn test_terminal_renderer_highlights_code_blocks_with_color() {
+        let deltas = [
+            Message::text_delta("Here:\n```rust\nfn main() {\n"),
+            Message::text_delta("    println!(\"hi\");\n}\n```"),
+            Message::text_delta("\nDone"),
+            Message::info("next"),
+        ];
+
+        let mut renderer = TerminalRenderer::with_writer(Vec::new()).with_color(true);
+        for msg in &deltas {
+            renderer.render(msg).unwrap();
+        }
+        let out = String::from_utf8(renderer.into_inner()).unwrap();
+        assert!(out.starts_with("Here:\n```rust\n"));
+        assert!(out.contains("\x1b[38;2;"));
+        assert!(out.contains("println"));
+        assert!(out.contains("```\nDone\n"));
+
+        // Without color the text is untouched
+        let plain = rendered(&deltas);
+        assert_eq!(
+            plain,
+            "Here:\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\nDone\nnext\n"
+        );
+    }
+
+    #[test]
+    fn test_terminal_renderer_spinner_writes_nothing_to_output() {
+        let spinner = |is_active| {
+            Message::Spinner(crate::messaging::SpinnerMessage {
+                text: "Working".to_string(),
+                is_active,
+            })
+        };
+        let out = rendered(&[
+            spinner(true),
+            Message::text_delta("partial"),
+            spinner(false),
+            Message::info("done"),
+        ]);
+        assert_eq!(out, "partial\ndone\n");
+    }
+
+    #[test]
+    fn test_terminal_renderer_wraps_prose_to_style_width() {
+        let mut renderer = TerminalRenderer::with_writer(Vec::new()).with_style(RenderStyle {
+            wrap: true,
+            width: Some(20),
+            ..RenderStyle::default()
+        });
+        for msg in [
+            Message::text_delta("Stockpot wraps long answers "),
+            Message::text_delta(
+                "to the terminal width.\n```\nlet code_stays = \"on one line\";\n```\n",
+            ),
+            Message::info("a status line that is longer than twenty columns"),
+        ] {
+            renderer.render(&msg).unwrap();
+        }
+        let out = String::from_utf8(renderer.into_inner()).unwrap();
+        assert_eq!(
+            out,
+            "Stockpot wraps long\nanswers to the\nterminal width.\n```\nlet code_stays = \"on one line\";\n```\na status line that is longer than twenty columns\n"
+        );
+    }
+
+    #[test]
+    fn test_terminal_renderer_plain_style_strips_markdown_and_color() {
+        let mut renderer = TerminalRenderer::with_writer(Vec::new())
+            .with_color(true)
+            .with_style(RenderStyle::plain());
+        for msg in [
+            Message::text_delta("## Summary\nUse **`cargo fmt`**, see "),
+            Message::text_delta("[the docs](https://example.com).\n```rust\nlet x = 1;\n```\n"),
+            Message::error("boom"),
+        ] {
+            renderer.render(&msg).unwrap();
+        }
+        let out = String::from_utf8(renderer.into_inner()).unwrap();
+        assert_eq!(
+            out,
+            "Summary\nUse cargo fmt, see the docs (https://example.com).\nlet x = 1;\nboom\n"
+        );
+    }
+
+    #[test]
+    fn test_terminal_renderer_table_content() {
+        let msg =
+            Message::tool_completed("list_agents").with_tool_content(ToolResultContent::Table {
+                headers: vec!["name".to_string(), "description".to_string()],
+                rows: vec![vec!["stockpot".to_string(), "default".to_string()]],
+            });
+        let out = rendered(&[msg]);
+        assert!(out.contains("name | description"));
+        assert!(out.contains("stockpot | default"));
+    }
+
+    /// A parent agent invoking a sub-agent that runs one tool.
+    fn nested_run() -> Vec<Message> {
+        let child = |msg: Message| match msg {
+            Message::Agent(agent) => Message::Agent(AgentMessage { depth: 1, ..agent }),
+            other => other,
+        };
+        vec![
+            Message::agent_started("stockpot", "Stockpot"),
+            Message::text_delta_from("Asking the reviewer.", "stockpot"),
+            Message::tool_executing_from("invoke_agent", None, "stockpot"),
+            child(Message::agent_started("reviewer", "Reviewer")),
+            Message::tool_executing_from("read_file", None, "reviewer"),
+            Message::tool_completed_from("read_file", "reviewer"),
+            Message::warning("src/lib.rs is long").from_agent("reviewer"),
+            Message::text_delta_from("Looks good.\nShip it.", "reviewer"),
+            child(Message::agent_completed("reviewer", "Reviewer", "run-2")),
+            Message::tool_completed_from("invoke_agent", "stockpot"),
+            Message::text_delta_from("Done.", "stockpot"),
+            Message::agent_completed("stockpot", "Stockpot", "run-1"),
+        ]
+    }
+
+    #[test]
+    fn test_terminal_renderer_collapses_sub_agents_to_summary() {
+        let out = rendered(&nested_run());
+        assert!(out.starts_with("▶ Stockpot\nAsking the reviewer.\n• invoke_agent\n"));
+        assert!(out.contains("▸ Reviewer finished · 1 tool call · "));
+        assert!(out.ends_with("s\nDone.\n"));
+        assert!(!out.contains("read_file"));
+        assert!(!out.contains("is long"));
+        assert!(!out.contains("Looks good"));
+    }
+
+    #[test]
+    fn test_terminal_renderer_expands_sub_agents_indented() {
+        let mut renderer = TerminalRenderer::with_writer(Vec::new()).with_style(RenderStyle {
+            expand_agents: true,
+            ..RenderStyle::default()
+        });
+        for msg in nested_run() {
+            renderer.render(&msg).unwrap();
+        }
+        let out = String::from_utf8(renderer.into_inner()).unwrap();
+        assert!(out.contains("• invoke_agent\n│ ▶ Reviewer\n│ • read_file\n"));
+        assert!(out.contains("│ src/lib.rs is long\n"));
+        assert!(out.contains("│ Looks good.\n│ Ship it.\n▸ Reviewer finished · 1 tool call · "));
+        assert!(out.ends_with("s\nDone.\n"));
+    }
+
+    /// A custom front-end only needs `render`; `run_loop` comes for free.
+    struct Collecting(Vec<String>);
+
+    #[async_trait]
+    impl MessageRenderer for Collecting {
+        fn render(&mut self, msg: &Message) -> io::Result<()> {
+            if let Message::TextDelta(delta) = msg {
+                self.0.push(delta.text.clone());
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_renderer_run_loop_until_bus_closes() {
+        let bus = MessageBus::new();
+        let receiver = bus.subscribe();
+        let sender = bus.sender();
+        sender.send(Message::text_delta("a")).unwrap();
+        sender.send(Message::text_delta("b")).unwrap();
+        drop(sender);
+        drop(bus);
+
+        let mut renderer = Collecting(Vec::new());
+        renderer.run_loop(receiver).await.unwrap();
+        assert_eq!(renderer.0, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Animated terminal spinner for [`TerminalRenderer`](super::TerminalRenderer).
+//!
+//! The spinner draws on stderr from a background thread. It only runs when
+//! stderr is a terminal, so redirected output never fills up with frames.
+
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Erase the current terminal line and return to its start.
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+/// How the spinner looks, and whether it runs at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpinnerConfig {
+    /// Frames drawn in turn, one per tick
+    pub frames: Vec<String>,
+    /// Time between frames
+    pub interval: Duration,
+    /// `false` turns the spinner into a no-op
+    pub enabled: bool,
+}
+
+impl Default for SpinnerConfig {
+    fn default() -> Self {
+        Self {
+            frames: ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            interval: Duration::from_millis(80),
+            enabled: true,
+        }
+    }
+}
+
+impl SpinnerConfig {
+    /// The default spinner, turned off when `NO_COLOR` is set.
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var_os("NO_COLOR").is_none(),
+            ..Default::default()
+        }
+    }
+
+    /// A spinner that never draws (`--no-spinner`).
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Default::default()
+        }
+    }
+
+    /// Use these frames instead of the default braille dots.
+    pub fn with_frames<S: Into<String>>(mut self, frames: impl IntoIterator<Item = S>) -> Self {
+        self.frames = frames.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Draw a new frame every `interval`.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// A running spinner. Stopping or dropping it clears its line.
+///
+/// Inactive (a no-op) when the config is disabled, has no frames, or
+/// stderr isn't a terminal.
+pub struct SpinnerHandle {
+    running: Option<Running>,
+}
+
+struct Running {
+    text: Arc<Mutex<String>>,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl SpinnerHandle {
+    /// Start spinning next to `text`.
+    pub fn start(config: &SpinnerConfig, text: &str) -> Self {
+        if !config.enabled || config.frames.is_empty() || !io::stderr().is_terminal() {
+            return Self { running: None };
+        }
+
+        let text = Arc::new(Mutex::new(text.to_string()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let text = Arc::clone(&text);
+            let stop = Arc::clone(&stop);
+            let frames = config.frames.clone();
+            let interval = config.interval;
+            std::thread::spawn(move || {
+                let mut stderr = io::stderr();
+                for frame in frames.iter().cycle() {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let text = text.lock().map(|t| t.clone()).unwrap_or_default();
+                    let _ = write!(stderr, "{}{} {}", CLEAR_LINE, frame, text);
+                    let _ = stderr.flush();
+                    std::thread::sleep(interval);
+                }
+            })
+        };
+
+        Self {
+            running: Some(Running { text, stop, thread }),
+        }
+    }
+
+    /// Whether the spinner is drawing.
+    pub fn is_active(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Change the text next to the spinner.
+    pub fn set_text(&self, text: &str) {
+        if let Some(running) = &self.running {
+            if let Ok(mut current) = running.text.lock() {
+                *current = text.to_string();
+            }
+        }
+    }
+
+    /// Stop the spinner and clear its line.
+    pub fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.stop.store(true, Ordering::Relaxed);
+            let _ = running.thread.join();
+            let mut stderr = io::stderr();
+            let _ = write!(stderr, "{}", CLEAR_LINE);
+            let _ = stderr.flush();
+        }
+    }
+}
+
+impl Drop for SpinnerHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_builders() {
+        let config = SpinnerConfig::default()
+            .with_frames(["-", "\\", "|", "/"])
+            .with_interval(Duration::from_millis(120));
+        assert_eq!(config.frames, vec!["-", "\\", "|", "/"]);
+        assert_eq!(config.interval, Duration::from_millis(120));
+        assert!(config.enabled);
+        assert!(!SpinnerConfig::disabled().enabled);
+    }
+
+    #[test]
+    fn test_disabled_spinner_is_a_no_op() {
+        let mut spinner = SpinnerHandle::start(&SpinnerConfig::disabled(), "working");
+        assert!(!spinner.is_active());
+        spinner.set_text("still working");
+        spinner.stop();
+    }
+
+    #[test]
+    fn test_spinner_without_frames_is_a_no_op() {
+        let config = SpinnerConfig::default().with_frames(Vec::<String>::new());
+        assert!(!SpinnerHandle::start(&config, "working").is_active());
+    }
+}
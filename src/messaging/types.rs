@@ -18,13 +18,22 @@ pub enum MessageLevel {
 pub struct TextMessage {
     pub level: MessageLevel,
     pub text: String,
+    /// Agent name for attribution (None = main agent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
 }
 
-/// Agent reasoning/thinking.
+/// Reasoning an agent chose to share, kept apart from its answer text.
+///
+/// Emitted for `share_your_reasoning` calls when `show_reasoning` is on.
+/// Streamed model thinking arrives as [`ThinkingMessage`] deltas instead.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReasoningMessage {
     pub reasoning: String,
     pub next_steps: Option<String>,
+    /// Agent name for attribution (None = main agent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
 }
 
 /// Agent response (markdown content).
@@ -67,6 +76,9 @@ pub enum FileOperation {
 pub struct DiffMessage {
     pub path: String,
     pub lines: Vec<DiffLine>,
+    /// Agent name for attribution (None = main agent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -114,6 +126,10 @@ pub struct AgentMessage {
     pub agent_name: String,
     pub display_name: String,
     pub event: AgentEvent,
+    /// How deeply the agent is nested: 0 for the agent the user ran, 1 for
+    /// one it invoked with invoke_agent, and so on.
+    #[serde(default)]
+    pub depth: usize,
 }
 
 /// Agent lifecycle event types.
@@ -142,6 +158,51 @@ pub struct ToolMessage {
     /// Agent that executed this tool (for nested agent routing)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agent_name: Option<String>,
+    /// Structured result for rich rendering (only from tools that opt in)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<ToolResultContent>,
+}
+
+/// Structured tool result, preserved so UIs can render more than plain text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum ToolResultContent {
+    Text(String),
+    Json(serde_json::Value),
+    /// Base64-encoded image data (e.g. a screenshot from an MCP server).
+    Image {
+        media_type: String,
+        data: String,
+    },
+    Table {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+impl ToolResultContent {
+    /// Build a table from JSON objects, taking one column per key.
+    ///
+    /// Missing keys become empty cells; non-string values are rendered as JSON.
+    pub fn table_from_objects(columns: &[&str], items: &[serde_json::Value]) -> Self {
+        let rows = items
+            .iter()
+            .map(|item| {
+                columns
+                    .iter()
+                    .map(|column| match item.get(*column) {
+                        Some(serde_json::Value::String(s)) => s.clone(),
+                        Some(serde_json::Value::Null) | None => String::new(),
+                        Some(other) => other.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        Self::Table {
+            headers: columns.iter().map(|c| c.to_string()).collect(),
+            rows,
+        }
+    }
 }
 
 /// Tool execution status.
@@ -173,6 +234,53 @@ pub struct ThinkingMessage {
     pub agent_name: Option<String>,
 }
 
+/// Progress update from a long-running tool (e.g. a large `list_files` or `grep`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolProgressMessage {
+    pub tool_name: String,
+    /// Number of items processed so far (files scanned, entries listed).
+    pub processed: usize,
+    /// Number of matches found so far, for search-style tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<usize>,
+}
+
+/// Lifecycle event for a supervised MCP server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpServerEvent {
+    /// The server stopped responding or its process exited.
+    Crashed,
+    /// The server was restarted successfully.
+    Restarted,
+    /// Restart attempts are exhausted; the server stays down.
+    GaveUp,
+}
+
+/// Health change for an MCP server, emitted by the supervisor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerMessage {
+    pub server: String,
+    pub event: McpServerEvent,
+    /// Number of restart attempts made so far.
+    pub attempt: u32,
+}
+
+/// Periodic throughput reading for a streaming agent response.
+///
+/// Token counts are estimates from the streamed text, not provider usage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsMessage {
+    /// Estimated output tokens streamed so far in this run.
+    pub tokens_so_far: usize,
+    /// Milliseconds since the first token arrived.
+    pub elapsed_ms: u64,
+    pub tokens_per_sec: f64,
+    /// Agent name for attribution (None = main agent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
+}
+
 /// Any message type (for serialization).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -189,6 +297,9 @@ pub enum Message {
     Tool(ToolMessage),
     TextDelta(TextDeltaMessage),
     Thinking(ThinkingMessage),
+    ToolProgress(ToolProgressMessage),
+    McpServer(McpServerMessage),
+    Metrics(MetricsMessage),
     Divider,
     Clear,
 }
@@ -199,6 +310,7 @@ impl Message {
         Self::Text(TextMessage {
             level: MessageLevel::Info,
             text: text.into(),
+            agent_name: None,
         })
     }
 
@@ -207,6 +319,7 @@ impl Message {
         Self::Text(TextMessage {
             level: MessageLevel::Success,
             text: text.into(),
+            agent_name: None,
         })
     }
 
@@ -215,6 +328,7 @@ impl Message {
         Self::Text(TextMessage {
             level: MessageLevel::Warning,
             text: text.into(),
+            agent_name: None,
         })
     }
 
@@ -223,6 +337,7 @@ impl Message {
         Self::Text(TextMessage {
             level: MessageLevel::Error,
             text: text.into(),
+            agent_name: None,
         })
     }
 
@@ -240,6 +355,7 @@ impl Message {
             agent_name: name.to_string(),
             display_name: display_name.to_string(),
             event: AgentEvent::Started,
+            depth: 0,
         })
     }
 
@@ -251,6 +367,7 @@ impl Message {
             event: AgentEvent::Completed {
                 run_id: run_id.to_string(),
             },
+            depth: 0,
         })
     }
 
@@ -262,6 +379,7 @@ impl Message {
             event: AgentEvent::Error {
                 message: error.to_string(),
             },
+            depth: 0,
         })
     }
 
@@ -467,6 +585,29 @@ impl Message {
         })
     }
 
+    /// Attach a structured result to a tool message.
+    ///
+    /// Has no effect on other message types.
+    pub fn with_tool_content(mut self, content: ToolResultContent) -> Self {
+        if let Self::Tool(ref mut tool) = self {
+            tool.content = Some(content);
+        }
+        self
+    }
+
+    /// Attribute a text or diff message to `agent_name`.
+    ///
+    /// Has no effect on other message types, which take the name in their
+    /// `_from` constructors.
+    pub fn from_agent(mut self, agent_name: &str) -> Self {
+        match &mut self {
+            Self::Text(text) => text.agent_name = Some(agent_name.to_string()),
+            Self::Diff(diff) => diff.agent_name = Some(agent_name.to_string()),
+            _ => {}
+        }
+        self
+    }
+
     /// Create a text delta message.
     pub fn text_delta(text: &str) -> Self {
         Self::TextDelta(TextDeltaMessage {
@@ -483,6 +624,15 @@ impl Message {
         })
     }
 
+    /// Create a reasoning message with agent attribution.
+    pub fn reasoning_from(reasoning: &str, next_steps: Option<&str>, agent_name: &str) -> Self {
+        Self::Reasoning(ReasoningMessage {
+            reasoning: reasoning.to_string(),
+            next_steps: next_steps.map(str::to_string),
+            agent_name: Some(agent_name.to_string()),
+        })
+    }
+
     /// Create a thinking delta message (unattributed, for main agent).
     pub fn thinking(text: &str) -> Self {
         Self::Thinking(ThinkingMessage {
@@ -498,6 +648,39 @@ impl Message {
             agent_name: Some(agent_name.to_string()),
         })
     }
+
+    /// Create a tool progress message.
+    pub fn tool_progress(tool_name: &str, processed: usize, matches: Option<usize>) -> Self {
+        Self::ToolProgress(ToolProgressMessage {
+            tool_name: tool_name.to_string(),
+            processed,
+            matches,
+        })
+    }
+
+    /// Create an MCP server health message.
+    pub fn mcp_server(server: &str, event: McpServerEvent, attempt: u32) -> Self {
+        Self::McpServer(McpServerMessage {
+            server: server.to_string(),
+            event,
+            attempt,
+        })
+    }
+
+    /// Create a throughput metrics message with agent attribution.
+    pub fn metrics_from(tokens_so_far: usize, elapsed_ms: u64, agent_name: &str) -> Self {
+        let tokens_per_sec = if elapsed_ms == 0 {
+            0.0
+        } else {
+            tokens_so_far as f64 * 1000.0 / elapsed_ms as f64
+        };
+        Self::Metrics(MetricsMessage {
+            tokens_so_far,
+            elapsed_ms,
+            tokens_per_sec,
+            agent_name: Some(agent_name.to_string()),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -1016,6 +1199,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_message_tool_progress() {
+        let msg = Message::tool_progress("grep", 1500, Some(12));
+        if let Message::ToolProgress(progress) = msg {
+            assert_eq!(progress.tool_name, "grep");
+            assert_eq!(progress.processed, 1500);
+            assert_eq!(progress.matches, Some(12));
+        } else {
+            panic!("Expected ToolProgress variant");
+        }
+    }
+
+    #[test]
+    fn test_message_mcp_server() {
+        let msg = Message::mcp_server("python-tools", McpServerEvent::Restarted, 2);
+        if let Message::McpServer(m) = msg {
+            assert_eq!(m.server, "python-tools");
+            assert_eq!(m.event, McpServerEvent::Restarted);
+            assert_eq!(m.attempt, 2);
+        } else {
+            panic!("Expected McpServer variant");
+        }
+    }
+
     // =========================================================================
     // Message Struct Tests
     // =========================================================================
@@ -1025,6 +1232,7 @@ mod tests {
         let msg = TextMessage {
             level: MessageLevel::Info,
             text: "Hello world".to_string(),
+            agent_name: None,
         };
         let json = serde_json::to_string(&msg).unwrap();
         let parsed: TextMessage = serde_json::from_str(&json).unwrap();
@@ -1037,6 +1245,7 @@ mod tests {
         let msg = ReasoningMessage {
             reasoning: "I need to analyze this".to_string(),
             next_steps: Some("First, read the file".to_string()),
+            agent_name: None,
         };
         let json = serde_json::to_string(&msg).unwrap();
         let parsed: ReasoningMessage = serde_json::from_str(&json).unwrap();
@@ -1049,6 +1258,7 @@ mod tests {
         let msg = ReasoningMessage {
             reasoning: "Thinking...".to_string(),
             next_steps: None,
+            agent_name: None,
         };
         let json = serde_json::to_string(&msg).unwrap();
         let parsed: ReasoningMessage = serde_json::from_str(&json).unwrap();
@@ -1153,8 +1363,10 @@ mod tests {
                     line_number: Some(2),
                 },
             ],
+            agent_name: None,
         };
         let json = serde_json::to_string(&msg).unwrap();
+        assert!(!json.contains("agent_name"));
         let parsed: DiffMessage = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.path, "src/main.rs");
         assert_eq!(parsed.lines.len(), 4);
@@ -1206,11 +1418,13 @@ mod tests {
             agent_name: "stockpot".to_string(),
             display_name: "Stockpot".to_string(),
             event: AgentEvent::Started,
+            depth: 1,
         };
         let json = serde_json::to_string(&msg).unwrap();
         let parsed: AgentMessage = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.agent_name, "stockpot");
         assert_eq!(parsed.display_name, "Stockpot");
+        assert_eq!(parsed.depth, 1);
     }
 
     #[test]
@@ -1223,6 +1437,7 @@ mod tests {
             result: Some("file contents".to_string()),
             error: None,
             agent_name: Some("explorer".to_string()),
+            content: None,
         };
         let json = serde_json::to_string(&msg).unwrap();
         let parsed: ToolMessage = serde_json::from_str(&json).unwrap();
@@ -1242,6 +1457,63 @@ mod tests {
         assert!(msg.result.is_none());
         assert!(msg.error.is_none());
         assert!(msg.agent_name.is_none());
+        assert!(msg.content.is_none());
+    }
+
+    #[test]
+    fn test_tool_result_content_serde_roundtrip() {
+        let variants = vec![
+            ToolResultContent::Text("hello".to_string()),
+            ToolResultContent::Json(serde_json::json!([1, 2, 3])),
+            ToolResultContent::Image {
+                media_type: "image/png".to_string(),
+                data: "aGVsbG8=".to_string(),
+            },
+            ToolResultContent::Table {
+                headers: vec!["name".to_string()],
+                rows: vec![vec!["stockpot".to_string()]],
+            },
+        ];
+        for content in variants {
+            let json = serde_json::to_string(&content).unwrap();
+            let parsed: ToolResultContent = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, content);
+        }
+
+        let json = serde_json::to_value(ToolResultContent::Text("hi".to_string())).unwrap();
+        assert_eq!(json, serde_json::json!({"kind": "text", "value": "hi"}));
+    }
+
+    #[test]
+    fn test_tool_result_content_table_from_objects() {
+        let items = vec![
+            serde_json::json!({"name": "stockpot", "count": 2}),
+            serde_json::json!({"name": "planning"}),
+        ];
+        let table = ToolResultContent::table_from_objects(&["name", "count"], &items);
+        assert_eq!(
+            table,
+            ToolResultContent::Table {
+                headers: vec!["name".to_string(), "count".to_string()],
+                rows: vec![
+                    vec!["stockpot".to_string(), "2".to_string()],
+                    vec!["planning".to_string(), String::new()],
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_with_tool_content_only_affects_tool_messages() {
+        let content = ToolResultContent::Text("done".to_string());
+        let msg = Message::tool_completed("read_file").with_tool_content(content.clone());
+        match msg {
+            Message::Tool(tool) => assert_eq!(tool.content, Some(content.clone())),
+            _ => panic!("expected tool message"),
+        }
+
+        let msg = Message::text_delta("hi").with_tool_content(content);
+        assert!(matches!(msg, Message::TextDelta(_)));
     }
 
     #[test]
@@ -1289,6 +1561,7 @@ mod tests {
         let msg = Message::Text(TextMessage {
             level: MessageLevel::Info,
             text: "test".to_string(),
+            agent_name: None,
         });
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("\"type\":\"text\""));
@@ -1297,6 +1570,28 @@ mod tests {
         matches!(parsed, Message::Text(_));
     }
 
+    #[test]
+    fn test_message_enum_tool_progress_serde() {
+        let msg = Message::tool_progress("list_files", 42, None);
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"type\":\"tool_progress\""));
+        assert!(!json.contains("matches"));
+
+        let parsed: Message = serde_json::from_str(&json).unwrap();
+        assert!(matches!(parsed, Message::ToolProgress(p) if p.processed == 42));
+    }
+
+    #[test]
+    fn test_message_enum_mcp_server_serde() {
+        let msg = Message::mcp_server("fetch", McpServerEvent::GaveUp, 3);
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"type\":\"mcp_server\""));
+        assert!(json.contains("\"event\":\"gave_up\""));
+
+        let parsed: Message = serde_json::from_str(&json).unwrap();
+        assert!(matches!(parsed, Message::McpServer(m) if m.event == McpServerEvent::GaveUp));
+    }
+
     #[test]
     fn test_message_enum_divider_serde() {
         let msg = Message::Divider;
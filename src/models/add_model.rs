@@ -348,6 +348,7 @@ pub async fn run_add_model(db: &Database) -> Result<()> {
         supports_thinking: false,
         supports_vision: false,
         supports_tools: true,
+        supports_streaming: true,
         description: Some(model.name.clone().unwrap_or_else(|| model.id.clone())),
         custom_endpoint: Some(CustomEndpoint {
             url: api_url,
@@ -974,6 +975,7 @@ mod tests {
             supports_thinking: false,
             supports_vision: false,
             supports_tools: true,
+            supports_streaming: true,
             description: Some(model.name.clone().unwrap_or_else(|| model.id.clone())),
             custom_endpoint: Some(CustomEndpoint {
                 url: api_url.clone(),
@@ -1340,6 +1342,7 @@ mod tests {
             supports_thinking: false,
             supports_vision: false,
             supports_tools: true,
+            supports_streaming: true,
             description: Some(model.name.clone().unwrap_or_else(|| model.id.clone())),
             custom_endpoint: Some(CustomEndpoint {
                 url: api_url,
@@ -0,0 +1,371 @@
+//! What a model can do, and a probe for custom endpoints.
+//!
+//! Capabilities come from the model's registry entry: set from models.dev
+//! when the model was added, or by hand for custom endpoints. Tool calling
+//! on a custom endpoint often isn't known up front, so [`probe_tool_support`]
+//! can check it with one small request offering a single tool.
+
+use std::fmt;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::agents::{endpoint_api_key, http_client};
+use crate::db::Database;
+
+use super::catalog::{self, ModelInfo};
+use super::{ModelConfig, ModelType};
+
+/// How long the probe request may take.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A model's capabilities, as recorded in the registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    pub thinking: bool,
+    pub vision: bool,
+    pub tools: bool,
+    pub context_length: usize,
+}
+
+impl ModelConfig {
+    /// What this model supports.
+    pub fn capabilities(&self) -> ModelCapabilities {
+        ModelCapabilities {
+            thinking: self.supports_thinking,
+            vision: self.supports_vision,
+            tools: self.supports_tools,
+            context_length: self.context_length,
+        }
+    }
+
+    /// This model's entry in the bundled models.dev catalog, for details
+    /// the registry doesn't keep, such as pricing.
+    pub fn catalog_info(&self) -> Option<&'static ModelInfo> {
+        let (prefix, rest) = match self.name.split_once(':') {
+            Some((prefix, rest)) => (Some(prefix), rest),
+            None => (None, self.name.as_str()),
+        };
+        let provider = match self.model_type {
+            ModelType::Openai | ModelType::ChatgptOauth => "openai",
+            ModelType::Anthropic | ModelType::ClaudeCode => "anthropic",
+            ModelType::Gemini => "google",
+            ModelType::Openrouter => "openrouter",
+            _ => prefix?,
+        };
+        catalog::find_model(provider, self.model_id.as_deref().unwrap_or(rest))
+    }
+}
+
+impl ModelCapabilities {
+    /// One line for model lists, e.g. `200k context · thinking, vision, tools`.
+    pub fn summary(&self) -> String {
+        let features: Vec<&str> = [
+            (self.thinking, "thinking"),
+            (self.vision, "vision"),
+            (self.tools, "tools"),
+        ]
+        .into_iter()
+        .filter_map(|(supported, name)| supported.then_some(name))
+        .collect();
+        let features = if features.is_empty() {
+            "text only".to_string()
+        } else {
+            features.join(", ")
+        };
+        format!("{}k context · {}", self.context_length / 1000, features)
+    }
+}
+
+impl fmt::Display for ModelCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_no = |supported: bool| if supported { "yes" } else { "no" };
+        writeln!(f, "Thinking:       {}", yes_no(self.thinking))?;
+        writeln!(f, "Vision:         {}", yes_no(self.vision))?;
+        writeln!(f, "Tool calling:   {}", yes_no(self.tools))?;
+        write!(f, "Context length: {} tokens", self.context_length)
+    }
+}
+
+/// Result of probing an endpoint for tool calling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolProbe {
+    /// The model called the offered tool
+    Supported,
+    /// The endpoint rejected a request with tools
+    Unsupported(String),
+    /// The request worked but the model didn't call the tool
+    Inconclusive,
+}
+
+impl fmt::Display for ToolProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Supported => f.write_str("tool calling works"),
+            Self::Unsupported(reason) => write!(f, "tool calling rejected: {}", reason),
+            Self::Inconclusive => {
+                f.write_str("request with tools accepted, but the model didn't call the tool")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ProbeError {
+    #[error("Model {0} has no custom endpoint to probe")]
+    NoEndpoint(String),
+    #[error("{0}")]
+    ApiKey(String),
+    #[error("Probe request failed: {0}")]
+    Request(String),
+}
+
+/// The request format a custom endpoint speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProbeApi {
+    /// `POST /chat/completions`
+    OpenAi,
+    /// `POST /v1/messages`
+    Anthropic,
+}
+
+impl ProbeApi {
+    fn of(model_type: ModelType) -> Self {
+        match model_type {
+            ModelType::CustomAnthropic => Self::Anthropic,
+            _ => Self::OpenAi,
+        }
+    }
+
+    fn url(self, base: &str) -> String {
+        let base = base.trim_end_matches('/');
+        match self {
+            Self::OpenAi => format!("{}/chat/completions", base),
+            Self::Anthropic if base.ends_with("/v1") => format!("{}/messages", base),
+            Self::Anthropic => format!("{}/v1/messages", base),
+        }
+    }
+
+    /// A request offering the `ping` tool and asking for it to be called.
+    fn body(self, model_id: &str) -> Value {
+        let messages = json!([{"role": "user", "content": "Call the ping tool."}]);
+        let parameters = json!({"type": "object", "properties": {}});
+        match self {
+            Self::OpenAi => json!({
+                "model": model_id,
+                "messages": messages,
+                "tools": [{
+                    "type": "function",
+                    "function": {
+                        "name": "ping",
+                        "description": "Reply to a ping.",
+                        "parameters": parameters,
+                    },
+                }],
+                "max_tokens": 64,
+            }),
+            Self::Anthropic => json!({
+                "model": model_id,
+                "messages": messages,
+                "tools": [{
+                    "name": "ping",
+                    "description": "Reply to a ping.",
+                    "input_schema": parameters,
+                }],
+                "max_tokens": 64,
+            }),
+        }
+    }
+
+    /// Whether a successful response called a tool.
+    fn called_tool(self, response: &Value) -> bool {
+        match self {
+            Self::OpenAi => response["choices"][0]["message"]["tool_calls"]
+                .as_array()
+                .is_some_and(|calls| !calls.is_empty()),
+            Self::Anthropic => response["content"]
+                .as_array()
+                .is_some_and(|blocks| blocks.iter().any(|block| block["type"] == "tool_use")),
+        }
+    }
+}
+
+/// Check whether a custom endpoint model can call tools.
+///
+/// Sends one short request offering a `ping` tool and asks the model to
+/// call it, as a chat completion or, for Anthropic-compatible endpoints,
+/// a Messages API call.
+pub async fn probe_tool_support(
+    db: &Database,
+    config: &ModelConfig,
+) -> Result<ToolProbe, ProbeError> {
+    let endpoint = config
+        .custom_endpoint
+        .as_ref()
+        .ok_or_else(|| ProbeError::NoEndpoint(config.name.clone()))?;
+    let api_key = endpoint
+        .api_key
+        .as_deref()
+        .map(|template| endpoint_api_key(db, template))
+        .transpose()
+        .map_err(|e| ProbeError::ApiKey(e.to_string()))?;
+
+    let api = ProbeApi::of(config.model_type);
+    let body = api.body(config.effective_model_id());
+    let client = http_client(db).map_err(|e| ProbeError::Request(e.to_string()))?;
+    let mut request = client
+        .post(api.url(&endpoint.url))
+        .timeout(PROBE_TIMEOUT)
+        .json(&body);
+    request = match (api, api_key) {
+        (ProbeApi::OpenAi, Some(key)) => request.bearer_auth(key),
+        (ProbeApi::Anthropic, Some(key)) => request.header("x-api-key", key),
+        (_, None) => request,
+    };
+    if api == ProbeApi::Anthropic {
+        request = request.header("anthropic-version", "2023-06-01");
+    }
+    for (name, value) in &endpoint.headers {
+        request = request.header(name, value);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| ProbeError::Request(e.to_string()))?;
+    let status = response.status().as_u16();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| ProbeError::Request(e.to_string()))?;
+    classify_probe_response(api, status, &text)
+}
+
+/// Read the probe's response.
+fn classify_probe_response(
+    api: ProbeApi,
+    status: u16,
+    body: &str,
+) -> Result<ToolProbe, ProbeError> {
+    match status {
+        200..=299 => {
+            let response: Value = serde_json::from_str(body)
+                .map_err(|e| ProbeError::Request(format!("unreadable response: {}", e)))?;
+            Ok(if api.called_tool(&response) {
+                ToolProbe::Supported
+            } else {
+                ToolProbe::Inconclusive
+            })
+        }
+        400 | 422 if mentions_tools(body) => Ok(ToolProbe::Unsupported(error_message(body))),
+        _ => Err(ProbeError::Request(format!(
+            "status {}: {}",
+            status,
+            error_message(body)
+        ))),
+    }
+}
+
+fn mentions_tools(body: &str) -> bool {
+    let body = body.to_lowercase();
+    body.contains("tool") || body.contains("function")
+}
+
+/// The error message from an error response, or the start of its body.
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|value| value["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.chars().take(200).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_display() {
+        let config = ModelConfig {
+            supports_vision: false,
+            context_length: 32_768,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.capabilities().to_string(),
+            "Thinking:       no\nVision:         no\nTool calling:   yes\nContext length: 32768 tokens"
+        );
+    }
+
+    #[test]
+    fn test_capabilities_summary() {
+        let config = ModelConfig {
+            supports_thinking: true,
+            supports_vision: true,
+            context_length: 200_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.capabilities().summary(),
+            "200k context · thinking, vision, tools"
+        );
+
+        let config = ModelConfig {
+            supports_tools: false,
+            context_length: 8_192,
+            ..Default::default()
+        };
+        assert_eq!(config.capabilities().summary(), "8k context · text only");
+    }
+
+    #[test]
+    fn test_classify_probe_response() {
+        let called = r#"{"choices":[{"message":{"tool_calls":[{"id":"1","type":"function","function":{"name":"ping","arguments":"{}"}}]}}]}"#;
+        assert_eq!(
+            classify_probe_response(ProbeApi::OpenAi, 200, called).unwrap(),
+            ToolProbe::Supported
+        );
+
+        let answered = r#"{"choices":[{"message":{"content":"pong"}}]}"#;
+        assert_eq!(
+            classify_probe_response(ProbeApi::OpenAi, 200, answered).unwrap(),
+            ToolProbe::Inconclusive
+        );
+
+        let rejected = r#"{"error":{"message":"this model does not support tools"}}"#;
+        assert_eq!(
+            classify_probe_response(ProbeApi::OpenAi, 400, rejected).unwrap(),
+            ToolProbe::Unsupported("this model does not support tools".into())
+        );
+
+        assert!(matches!(
+            classify_probe_response(ProbeApi::OpenAi, 401, r#"{"error":{"message":"bad key"}}"#),
+            Err(ProbeError::Request(message)) if message == "status 401: bad key"
+        ));
+    }
+
+    #[test]
+    fn test_anthropic_probe() {
+        let api = ProbeApi::of(ModelType::CustomAnthropic);
+        assert_eq!(
+            api.url("https://llm.example.com/"),
+            "https://llm.example.com/v1/messages"
+        );
+        assert_eq!(
+            api.url("https://llm.example.com/v1"),
+            "https://llm.example.com/v1/messages"
+        );
+        assert_eq!(api.body("claude-x")["tools"][0]["name"], "ping");
+
+        let called = r#"{"content":[{"type":"text","text":"Sure."},{"type":"tool_use","id":"t1","name":"ping","input":{}}]}"#;
+        assert_eq!(
+            classify_probe_response(api, 200, called).unwrap(),
+            ToolProbe::Supported
+        );
+        let answered = r#"{"content":[{"type":"text","text":"pong"}]}"#;
+        assert_eq!(
+            classify_probe_response(api, 200, answered).unwrap(),
+            ToolProbe::Inconclusive
+        );
+    }
+}
@@ -6,6 +6,7 @@
 use anyhow::{anyhow, Result};
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::sync::OnceLock;
 
 /// Models catalog downloaded at build time from https://models.dev/api.json
 /// See build.rs for the download logic, caching, and fallback behavior.
@@ -56,6 +57,32 @@ pub async fn fetch_providers() -> Result<HashMap<String, ProviderInfo>> {
     Ok(providers)
 }
 
+/// Look up a model in the bundled catalog by provider and model id.
+pub fn find_model(provider_id: &str, model_id: &str) -> Option<&'static ModelInfo> {
+    static PROVIDERS: OnceLock<HashMap<String, ProviderInfo>> = OnceLock::new();
+    let providers = PROVIDERS.get_or_init(|| {
+        serde_json::from_str(BUNDLED_MODELS_CATALOG_JSON).unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Failed to parse bundled catalog");
+            HashMap::new()
+        })
+    });
+    let provider = providers.get(provider_id)?;
+    provider
+        .models
+        .get(model_id)
+        .or_else(|| provider.models.values().find(|m| m.id == model_id))
+}
+
+impl ModelInfo {
+    /// Input/output price per million tokens, if the catalog lists both.
+    pub fn price_label(&self) -> Option<String> {
+        match (self.input_price, self.output_price) {
+            (Some(input), Some(output)) => Some(format!("${:.2}/${:.2} per 1M", input, output)),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,4 +122,15 @@ mod tests {
         assert!(model.input_price.is_none());
         assert!(model.output_price.is_none());
     }
+
+    #[test]
+    fn test_price_label_needs_both_prices() {
+        let json = r#"{"id": "m", "input_price": 3.0, "output_price": 15.0}"#;
+        let model: ModelInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(model.price_label().as_deref(), Some("$3.00/$15.00 per 1M"));
+
+        let json = r#"{"id": "m", "input_price": 3.0}"#;
+        let model: ModelInfo = serde_json::from_str(json).unwrap();
+        assert!(model.price_label().is_none());
+    }
 }
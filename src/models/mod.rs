@@ -6,7 +6,9 @@
 //! - Model type definitions
 //! - Default model configurations
 //! - Model catalog from models.dev API
+//! - Capability reporting and tool-calling probes
 
+pub mod capabilities;
 pub mod catalog;
 pub mod defaults;
 pub mod model_config;
@@ -16,8 +18,9 @@ pub mod types;
 pub mod utils;
 
 // Re-export main types for convenience
+pub use capabilities::{probe_tool_support, ModelCapabilities, ProbeError, ToolProbe};
 pub use model_config::ModelConfig;
-pub use registry::ModelRegistry;
+pub use registry::{ModelRegistry, ModelRemoval};
 pub use types::{CustomEndpoint, ModelType};
 pub use utils::resolve_api_key;
 
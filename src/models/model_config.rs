@@ -4,7 +4,11 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::types::{CustomEndpoint, ModelType};
+use super::types::{CustomEndpoint, ModelConfigError, ModelType};
+
+/// Azure OpenAI API version served under `/openai/v1`, which takes the
+/// deployment name as the model and needs no `api-version` parameter.
+pub const AZURE_V1_API_VERSION: &str = "v1";
 
 /// Configuration for a specific model.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +36,10 @@ pub struct ModelConfig {
     /// Whether this model supports tool use/function calling
     #[serde(default = "default_true")]
     pub supports_tools: bool,
+    /// Whether the endpoint can stream responses; runs on models that can't
+    /// fall back to blocking requests
+    #[serde(default = "default_true")]
+    pub supports_streaming: bool,
     /// Description of the model
     #[serde(default)]
     pub description: Option<String>,
@@ -65,6 +73,7 @@ impl Default for ModelConfig {
             supports_thinking: false,
             supports_vision: true,
             supports_tools: true,
+            supports_streaming: true,
             description: None,
             azure_deployment: None,
             azure_api_version: None,
@@ -94,6 +103,44 @@ impl ModelConfig {
             ModelType::CustomOpenai | ModelType::CustomAnthropic
         )
     }
+
+    /// OpenAI-compatible base URL for an Azure OpenAI model on
+    /// `resource_endpoint` (e.g. `https://my-resource.openai.azure.com`),
+    /// with the deployment name to send as the model.
+    ///
+    /// Fails if the deployment or endpoint is missing, or the API version
+    /// isn't one the OpenAI-compatible route serves.
+    pub fn azure_target(
+        &self,
+        resource_endpoint: Option<&str>,
+    ) -> Result<(String, String), ModelConfigError> {
+        let missing = |setting| ModelConfigError::AzureSettingMissing {
+            model: self.name.clone(),
+            setting,
+        };
+        let deployment = self
+            .azure_deployment
+            .as_deref()
+            .filter(|d| !d.trim().is_empty())
+            .ok_or_else(|| missing("deployment name"))?;
+        let endpoint = resource_endpoint
+            .map(|e| e.trim().trim_end_matches('/'))
+            .filter(|e| !e.is_empty())
+            .ok_or_else(|| missing("resource endpoint (or AZURE_OPENAI_ENDPOINT)"))?;
+        match self.azure_api_version.as_deref() {
+            None | Some(AZURE_V1_API_VERSION) => {}
+            Some(version) => {
+                return Err(ModelConfigError::UnsupportedAzureApiVersion(
+                    version.to_string(),
+                ))
+            }
+        }
+
+        let base = endpoint
+            .trim_end_matches("/openai/v1")
+            .trim_end_matches("/openai");
+        Ok((format!("{}/openai/v1", base), deployment.to_string()))
+    }
 }
 
 #[cfg(test)]
@@ -118,6 +165,41 @@ mod tests {
         assert_eq!(config.effective_model_id(), "gpt-4-turbo-preview");
     }
 
+    #[test]
+    fn test_azure_target() {
+        let mut config = ModelConfig {
+            name: "azure-gpt".to_string(),
+            model_type: ModelType::AzureOpenai,
+            model_id: Some("gpt-4o".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.azure_target(Some("https://corp.openai.azure.com")),
+            Err(ModelConfigError::AzureSettingMissing {
+                setting: "deployment name",
+                ..
+            })
+        ));
+
+        config.azure_deployment = Some("corp-gpt4o-prod".to_string());
+        assert!(config.azure_target(None).is_err());
+        assert_eq!(
+            config
+                .azure_target(Some("https://corp.openai.azure.com/"))
+                .unwrap(),
+            (
+                "https://corp.openai.azure.com/openai/v1".to_string(),
+                "corp-gpt4o-prod".to_string()
+            )
+        );
+
+        config.azure_api_version = Some("2024-02-01".to_string());
+        assert!(matches!(
+            config.azure_target(Some("https://corp.openai.azure.com")),
+            Err(ModelConfigError::UnsupportedAzureApiVersion(v)) if v == "2024-02-01"
+        ));
+    }
+
     #[test]
     fn test_is_oauth() {
         let mut config = ModelConfig::default();
@@ -10,12 +10,29 @@ use std::path::PathBuf;
 
 use rusqlite::params;
 
+use crate::config::Settings;
 use crate::db::Database;
 
 use super::model_config::ModelConfig;
 use super::types::{ModelConfigError, ModelType};
 use super::utils::{build_custom_endpoint, has_api_key, has_oauth_tokens, parse_model_type};
 
+/// References cleaned up by [`ModelRegistry::remove`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelRemoval {
+    /// Agents whose pin pointed at the removed model (now unpinned)
+    pub unpinned_agents: Vec<String>,
+    /// Round-robin models that listed the removed model as a member
+    pub round_robin_models: Vec<String>,
+}
+
+impl ModelRemoval {
+    /// Whether anything besides the model itself was touched.
+    pub fn is_empty(&self) -> bool {
+        self.unpinned_agents.is_empty() && self.round_robin_models.is_empty()
+    }
+}
+
 /// Registry of available models loaded from configuration files.
 #[derive(Debug, Default)]
 pub struct ModelRegistry {
@@ -42,7 +59,8 @@ impl ModelRegistry {
             .prepare(
                 "SELECT name, model_type, model_id, context_length, supports_thinking,
                         supports_vision, supports_tools, description, api_endpoint,
-                        api_key_env, headers, azure_deployment, azure_api_version
+                        api_key_env, headers, azure_deployment, azure_api_version,
+                        supports_streaming
                  FROM models ORDER BY name",
             )
             .map_err(|e| ModelConfigError::Io(std::io::Error::other(e.to_string())))?;
@@ -60,6 +78,7 @@ impl ModelRegistry {
                     supports_thinking: row.get::<_, i64>(4)? != 0,
                     supports_vision: row.get::<_, i64>(5)? != 0,
                     supports_tools: row.get::<_, i64>(6)? != 0,
+                    supports_streaming: row.get::<_, Option<i64>>(13)?.unwrap_or(1) != 0,
                     description: row.get(7)?,
                     custom_endpoint: build_custom_endpoint(
                         row.get::<_, Option<String>>(8)?,
@@ -131,8 +150,8 @@ impl ModelRegistry {
             "INSERT OR REPLACE INTO models (name, model_type, model_id, context_length,
                 supports_thinking, supports_vision, supports_tools, description,
                 api_endpoint, api_key_env, headers, azure_deployment, azure_api_version,
-                is_builtin, source, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, unixepoch())",
+                supports_streaming, is_builtin, source, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, unixepoch())",
             params![
                 &config.name,
                 config.model_type.to_string(),
@@ -150,6 +169,7 @@ impl ModelRegistry {
                 headers_json,
                 &config.azure_deployment,
                 &config.azure_api_version,
+                config.supports_streaming as i64,
                 source,
             ],
         );
@@ -164,6 +184,21 @@ impl ModelRegistry {
         Ok(())
     }
 
+    /// Record whether a model's endpoint can stream responses.
+    pub fn set_supports_streaming(
+        db: &Database,
+        name: &str,
+        supports_streaming: bool,
+    ) -> Result<(), ModelConfigError> {
+        db.conn()
+            .execute(
+                "UPDATE models SET supports_streaming = ?, updated_at = unixepoch() WHERE name = ?",
+                params![supports_streaming as i64, name],
+            )
+            .map_err(|e| ModelConfigError::Io(std::io::Error::other(e.to_string())))?;
+        Ok(())
+    }
+
     /// Remove a custom model from the database.
     pub fn remove_model_from_db(db: &Database, name: &str) -> Result<(), ModelConfigError> {
         db.conn()
@@ -175,6 +210,54 @@ impl ModelRegistry {
         Ok(())
     }
 
+    /// Remove a model from the database and the registry, cleaning up references to it.
+    ///
+    /// Agent pins pointing at the model are cleared and it is dropped from
+    /// the members of any round-robin model. Refuses to remove the current
+    /// default model; switch the default first.
+    pub fn remove(&mut self, name: &str, db: &Database) -> Result<ModelRemoval, ModelConfigError> {
+        let settings = Settings::new(db);
+        if settings.model() == name {
+            return Err(ModelConfigError::DefaultModel(name.to_string()));
+        }
+
+        Self::remove_model_from_db(db, name)?;
+        self.models.remove(name);
+
+        let mut removal = ModelRemoval::default();
+
+        let pins = settings
+            .get_all_agent_pinned_models()
+            .map_err(|e| ModelConfigError::Io(std::io::Error::other(e.to_string())))?;
+        for (agent, model) in pins {
+            if model == name {
+                settings
+                    .clear_agent_pinned_model(&agent)
+                    .map_err(|e| ModelConfigError::Io(std::io::Error::other(e.to_string())))?;
+                removal.unpinned_agents.push(agent);
+            }
+        }
+        removal.unpinned_agents.sort();
+
+        for config in self.models.values_mut() {
+            let before = config.round_robin_models.len();
+            config.round_robin_models.retain(|member| member != name);
+            if config.round_robin_models.len() != before {
+                removal.round_robin_models.push(config.name.clone());
+            }
+        }
+        removal.round_robin_models.sort();
+
+        tracing::debug!(
+            model = %name,
+            unpinned_agents = ?removal.unpinned_agents,
+            round_robin_models = ?removal.round_robin_models,
+            "Removed model and cleaned up references"
+        );
+
+        Ok(removal)
+    }
+
     /// Reload the registry from database.
     pub fn reload_from_db(&mut self, db: &Database) -> Result<(), ModelConfigError> {
         self.models.clear();
@@ -297,12 +380,13 @@ impl ModelRegistry {
             }
             ModelType::CustomOpenai | ModelType::CustomAnthropic => {
                 // Custom endpoints - check if API key is configured
-                // The api_key can be a literal or $ENV_VAR reference
+                // The api_key can be a literal or $ENV_VAR reference, or left
+                // out for endpoints that don't need one (e.g. local servers)
                 config
                     .custom_endpoint
                     .as_ref()
                     .map(|e| {
-                        e.api_key.as_ref().is_some_and(|key| {
+                        e.api_key.as_ref().is_none_or(|key| {
                             if key.starts_with('$') {
                                 // It's an env var reference, check DB then env
                                 let var_name = key
@@ -351,6 +435,7 @@ mod tests {
             supports_thinking: false,
             supports_vision: true,
             supports_tools: true,
+            supports_streaming: true,
             description: Some(format!("Test model: {}", name)),
             custom_endpoint: None,
             azure_deployment: None,
@@ -368,6 +453,7 @@ mod tests {
             supports_thinking: false,
             supports_vision: false,
             supports_tools: true,
+            supports_streaming: true,
             description: None,
             custom_endpoint: Some(CustomEndpoint {
                 url: url.to_string(),
@@ -690,6 +776,7 @@ mod tests {
             supports_thinking: true,
             supports_vision: true,
             supports_tools: false,
+            supports_streaming: false,
             ..Default::default()
         };
 
@@ -701,6 +788,86 @@ mod tests {
         assert!(loaded.supports_thinking);
         assert!(loaded.supports_vision);
         assert!(!loaded.supports_tools);
+        assert!(!loaded.supports_streaming);
+
+        ModelRegistry::set_supports_streaming(&db, "capable-model", true).unwrap();
+        let registry = ModelRegistry::load_from_db(&db).unwrap();
+        assert!(registry.get("capable-model").unwrap().supports_streaming);
+    }
+
+    // =========================================================================
+    // Removal Tests
+    // =========================================================================
+
+    #[test]
+    fn test_remove_cleans_up_pins_and_round_robin() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+        settings.set("model", "keeper").unwrap();
+        ModelRegistry::add_model_to_db(&db, &create_test_model("doomed")).unwrap();
+        ModelRegistry::add_model_to_db(&db, &create_test_model("keeper")).unwrap();
+        settings
+            .set_agent_pinned_model("explore", "doomed")
+            .unwrap();
+        settings
+            .set_agent_pinned_model("planning", "keeper")
+            .unwrap();
+
+        let mut registry = ModelRegistry::load_from_db(&db).unwrap();
+        registry.add(ModelConfig {
+            name: "rr".to_string(),
+            model_type: ModelType::RoundRobin,
+            round_robin_models: vec!["doomed".to_string(), "keeper".to_string()],
+            ..Default::default()
+        });
+
+        let removal = registry.remove("doomed", &db).unwrap();
+
+        assert_eq!(removal.unpinned_agents, vec!["explore"]);
+        assert_eq!(removal.round_robin_models, vec!["rr"]);
+        assert!(!registry.contains("doomed"));
+        assert_eq!(
+            registry.get("rr").unwrap().round_robin_models,
+            vec!["keeper"]
+        );
+        assert!(settings.get_agent_pinned_model("explore").is_none());
+        assert_eq!(
+            settings.get_agent_pinned_model("planning"),
+            Some("keeper".to_string())
+        );
+        assert!(!ModelRegistry::load_from_db(&db).unwrap().contains("doomed"));
+    }
+
+    #[test]
+    fn test_remove_without_references_reports_nothing() {
+        let (_temp, db) = setup_test_db();
+        Settings::new(&db).set("model", "keeper").unwrap();
+        ModelRegistry::add_model_to_db(&db, &create_test_model("lonely")).unwrap();
+        let mut registry = ModelRegistry::load_from_db(&db).unwrap();
+
+        let removal = registry.remove("lonely", &db).unwrap();
+        assert!(removal.is_empty());
+        assert!(!registry.contains("lonely"));
+    }
+
+    #[test]
+    fn test_remove_refuses_default_model() {
+        let (_temp, db) = setup_test_db();
+        let settings = Settings::new(&db);
+        settings.set("model", "current").unwrap();
+        settings
+            .set_agent_pinned_model("explore", "current")
+            .unwrap();
+        ModelRegistry::add_model_to_db(&db, &create_test_model("current")).unwrap();
+        let mut registry = ModelRegistry::load_from_db(&db).unwrap();
+
+        let result = registry.remove("current", &db);
+        assert!(matches!(result, Err(ModelConfigError::DefaultModel(ref n)) if n == "current"));
+        assert!(registry.contains("current"));
+        assert_eq!(
+            settings.get_agent_pinned_model("explore"),
+            Some("current".to_string())
+        );
     }
 
     // =========================================================================
@@ -908,7 +1075,7 @@ mod tests {
         let registry = ModelRegistry::load_from_db(&db).unwrap();
         let available = registry.list_available(&db);
 
-        assert!(!available.contains(&"custom-no-key".to_string()));
+        assert!(available.contains(&"custom-no-key".to_string()));
     }
 
     #[test]
@@ -1046,6 +1213,7 @@ mod tests {
             supports_thinking: false,
             supports_vision: false,
             supports_tools: false,
+            supports_streaming: true,
             description: None,
             custom_endpoint: None,
             azure_deployment: None,
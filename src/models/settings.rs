@@ -18,6 +18,36 @@ pub enum ModelSettingsError {
     ParseError(String),
 }
 
+/// One-off sampling overrides for a single run, never persisted.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SamplingOverride {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+}
+
+impl SamplingOverride {
+    /// Create an override, validating temperature (0.0 - 2.0) and top_p (0.0 - 1.0).
+    pub fn new(temperature: Option<f32>, top_p: Option<f32>) -> Result<Self, ModelSettingsError> {
+        check_sampling(temperature, top_p)?;
+        Ok(Self { temperature, top_p })
+    }
+
+    /// Whether the override changes anything.
+    pub fn is_empty(&self) -> bool {
+        self.temperature.is_none() && self.top_p.is_none()
+    }
+
+    /// Layer the override on top of loaded settings.
+    pub fn apply(&self, settings: &mut ModelSettings) {
+        if let Some(t) = self.temperature {
+            settings.temperature = Some(t);
+        }
+        if let Some(p) = self.top_p {
+            settings.top_p = Some(p);
+        }
+    }
+}
+
 /// Per-model settings that can be customized.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ModelSettings {
@@ -39,6 +69,8 @@ pub struct ModelSettings {
     pub reasoning_effort: Option<String>,
     /// Verbosity level (0-3)
     pub verbosity: Option<i32>,
+    /// Seconds each model request may take before it fails as timed out
+    pub request_timeout_secs: Option<u64>,
 }
 
 impl ModelSettings {
@@ -128,6 +160,20 @@ impl ModelSettings {
                 }
                 self.verbosity = Some(v);
             }
+            "request_timeout_secs" => {
+                let secs: u64 = value.parse().map_err(|_| {
+                    ModelSettingsError::ParseError(format!(
+                        "Invalid request_timeout_secs: {}",
+                        value
+                    ))
+                })?;
+                if !(1..=86_400).contains(&secs) {
+                    return Err(ModelSettingsError::InvalidValue(
+                        "request_timeout_secs must be 1-86400".to_string(),
+                    ));
+                }
+                self.request_timeout_secs = Some(secs);
+            }
             _ => {
                 // Ignore unknown settings for forward compatibility
             }
@@ -145,6 +191,7 @@ impl ModelSettings {
         // Validate the setting first
         let mut temp = Self::new();
         temp.apply_setting(key, value)?;
+        check_sampling(temp.temperature, temp.top_p)?;
 
         let full_key = format!("model_settings.{}.{}", model_name, key);
         db.conn().execute(
@@ -219,11 +266,32 @@ impl ModelSettings {
         self.extended_thinking.unwrap_or(false)
     }
 
+    /// Whether thinking will be on for a model, given whether it supports it.
+    ///
+    /// Thinking is on by default for models that support it, unless turned
+    /// off with `extended_thinking`.
+    pub fn thinking_enabled(&self, model_supports_thinking: bool) -> bool {
+        model_supports_thinking && self.extended_thinking != Some(false)
+    }
+
+    /// The saved temperature, if it won't be used: thinking models always
+    /// run at 1.0.
+    pub fn ignored_temperature(&self, model_supports_thinking: bool) -> Option<f32> {
+        self.temperature
+            .filter(|&t| t != 1.0 && self.thinking_enabled(model_supports_thinking))
+    }
+
     /// Check if interleaved thinking is enabled.
     pub fn is_interleaved_thinking(&self) -> bool {
         self.interleaved_thinking.unwrap_or(false)
     }
 
+    /// How long a single model request may take, if limited.
+    pub fn request_timeout(&self) -> Option<std::time::Duration> {
+        self.request_timeout_secs
+            .map(std::time::Duration::from_secs)
+    }
+
     /// Get a list of all valid setting keys.
     pub fn valid_keys() -> &'static [&'static str] {
         &[
@@ -236,6 +304,7 @@ impl ModelSettings {
             "interleaved_thinking",
             "reasoning_effort",
             "verbosity",
+            "request_timeout_secs",
         ]
     }
 
@@ -255,9 +324,31 @@ impl ModelSettings {
             && self.interleaved_thinking.is_none()
             && self.reasoning_effort.is_none()
             && self.verbosity.is_none()
+            && self.request_timeout_secs.is_none()
     }
 }
 
+/// Check temperature (0.0 - 2.0) and top_p (0.0 - 1.0) are in range.
+fn check_sampling(temperature: Option<f32>, top_p: Option<f32>) -> Result<(), ModelSettingsError> {
+    if let Some(t) = temperature {
+        if !(0.0..=2.0).contains(&t) {
+            return Err(ModelSettingsError::InvalidValue(format!(
+                "temperature must be between 0.0 and 2.0, got {}",
+                t
+            )));
+        }
+    }
+    if let Some(p) = top_p {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(ModelSettingsError::InvalidValue(format!(
+                "top_p must be between 0.0 and 1.0, got {}",
+                p
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Parse a boolean from various string representations.
 fn parse_bool(value: &str) -> bool {
     matches!(
@@ -577,6 +668,49 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_sampling_out_of_range_rejected() {
+        let (_tmp, db) = setup_test_db();
+
+        for (key, value) in [
+            ("temperature", "2.5"),
+            ("temperature", "-0.1"),
+            ("temperature", "NaN"),
+            ("top_p", "10"),
+            ("top_p", "-1"),
+        ] {
+            let result = ModelSettings::save_setting(&db, "test", key, value);
+            assert!(
+                matches!(result, Err(ModelSettingsError::InvalidValue(_))),
+                "{} = {} should be rejected",
+                key,
+                value
+            );
+        }
+        ModelSettings::save_setting(&db, "test", "top_p", "1").unwrap();
+        assert!(ModelSettings::list(&db, "test")
+            .unwrap()
+            .iter()
+            .all(|(key, _)| key == "top_p"));
+    }
+
+    #[test]
+    fn test_ignored_temperature_with_thinking() {
+        let mut settings = ModelSettings::new();
+        assert_eq!(settings.ignored_temperature(true), None);
+
+        settings.temperature = Some(0.3);
+        assert_eq!(settings.ignored_temperature(true), Some(0.3));
+        assert_eq!(settings.ignored_temperature(false), None);
+
+        settings.extended_thinking = Some(false);
+        assert_eq!(settings.ignored_temperature(true), None);
+
+        settings.extended_thinking = None;
+        settings.temperature = Some(1.0);
+        assert_eq!(settings.ignored_temperature(true), None);
+    }
+
     // =========================================================================
     // Validation Tests - Seed
     // =========================================================================
@@ -783,7 +917,8 @@ mod tests {
         assert!(keys.contains(&"interleaved_thinking"));
         assert!(keys.contains(&"reasoning_effort"));
         assert!(keys.contains(&"verbosity"));
-        assert_eq!(keys.len(), 9);
+        assert!(keys.contains(&"request_timeout_secs"));
+        assert_eq!(keys.len(), 10);
     }
 
     #[test]
@@ -1202,6 +1337,27 @@ mod tests {
         assert!(matches!(result, Err(ModelSettingsError::InvalidValue(_))));
     }
 
+    #[test]
+    fn test_request_timeout_secs() {
+        let mut settings = ModelSettings::new();
+        assert_eq!(settings.request_timeout(), None);
+        settings
+            .apply_setting("request_timeout_secs", "90")
+            .unwrap();
+        assert_eq!(
+            settings.request_timeout(),
+            Some(std::time::Duration::from_secs(90))
+        );
+        assert!(!settings.is_empty());
+
+        let result = settings.apply_setting("request_timeout_secs", "0");
+        assert!(matches!(result, Err(ModelSettingsError::InvalidValue(_))));
+        let result = settings.apply_setting("request_timeout_secs", "9999999999999");
+        assert!(matches!(result, Err(ModelSettingsError::InvalidValue(_))));
+        let result = settings.apply_setting("request_timeout_secs", "soon");
+        assert!(matches!(result, Err(ModelSettingsError::ParseError(_))));
+    }
+
     // =========================================================================
     // Serialization Edge Cases
     // =========================================================================
@@ -1426,4 +1582,38 @@ mod tests {
             );
         }
     }
+
+    // =========================================================================
+    // SamplingOverride Tests
+    // =========================================================================
+
+    #[test]
+    fn test_sampling_override_validates_ranges() {
+        assert!(SamplingOverride::new(Some(0.0), Some(1.0)).is_ok());
+        assert!(SamplingOverride::new(Some(2.0), None).is_ok());
+        assert!(matches!(
+            SamplingOverride::new(Some(2.5), None),
+            Err(ModelSettingsError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            SamplingOverride::new(None, Some(-0.1)),
+            Err(ModelSettingsError::InvalidValue(_))
+        ));
+        assert!(SamplingOverride::new(Some(f32::NAN), None).is_err());
+    }
+
+    #[test]
+    fn test_sampling_override_apply() {
+        let mut settings = ModelSettings {
+            temperature: Some(0.3),
+            top_p: Some(0.9),
+            ..Default::default()
+        };
+        SamplingOverride::new(Some(1.2), None)
+            .unwrap()
+            .apply(&mut settings);
+        assert_eq!(settings.temperature, Some(1.2));
+        assert_eq!(settings.top_p, Some(0.9));
+        assert!(SamplingOverride::default().is_empty());
+    }
 }
@@ -22,6 +22,15 @@ pub enum ModelConfigError {
     ModelNotFound(String),
     #[error("Environment variable not found: {0}")]
     EnvVarNotFound(String),
+    #[error("Cannot remove {0}: it is the default model")]
+    DefaultModel(String),
+    #[error("Azure model {model} has no {setting} configured")]
+    AzureSettingMissing {
+        model: String,
+        setting: &'static str,
+    },
+    #[error("Azure API version {0} isn't supported; use v1 or leave it unset")]
+    UnsupportedAzureApiVersion(String),
 }
 
 /// Supported model provider types.
@@ -0,0 +1,103 @@
+//! Saving the conversation as it goes (`autosave_every_turns`).
+//!
+//! Every N finished turns the history is written to the conversation's
+//! session, through the same atomic write as an explicit save, so a crash
+//! loses at most the turns since. The first save names the session
+//! `autosave-<timestamp>` unless the conversation was resumed from one; a
+//! new conversation gets a new one.
+
+use serdes_ai_core::ModelRequest;
+
+use super::{SessionError, SessionManager, SessionMeta};
+
+/// Turns since the last autosave, and the session it went to.
+#[derive(Debug, Clone, Default)]
+pub struct Autosave {
+    since_save: u32,
+    name: Option<String>,
+}
+
+impl Autosave {
+    /// The session autosaves go to, once the first one is made.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Send later autosaves to `name`, e.g. a resumed session.
+    pub fn continue_in(&mut self, name: &str) {
+        self.since_save = 0;
+        self.name = Some(name.to_string());
+    }
+
+    /// Count a finished turn. Returns whether a save is due when saving
+    /// every `every` turns; 0 turns autosave off.
+    pub fn turn_finished(&mut self, every: u32) -> bool {
+        if every == 0 {
+            return false;
+        }
+        self.since_save += 1;
+        self.since_save >= every
+    }
+
+    /// Save `messages`, naming the session on the first save.
+    pub fn save(
+        &mut self,
+        manager: &SessionManager,
+        messages: &[ModelRequest],
+        agent: &str,
+        model: &str,
+    ) -> Result<SessionMeta, SessionError> {
+        let name = self
+            .name
+            .get_or_insert_with(|| manager.generate_name("autosave"));
+        let meta = manager.save(name, messages, agent, model)?;
+        self.since_save = 0;
+        Ok(meta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn prompt(text: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.add_user_prompt(text.to_string());
+        request
+    }
+
+    #[test]
+    fn test_turn_finished_counts_to_the_interval() {
+        let mut autosave = Autosave::default();
+        assert!(!autosave.turn_finished(0));
+        assert!(!autosave.turn_finished(2));
+        assert!(autosave.turn_finished(2));
+        // Still due until a save resets the count
+        assert!(autosave.turn_finished(2));
+        assert!(Autosave::default().turn_finished(1));
+    }
+
+    #[test]
+    fn test_save_reuses_the_session() {
+        let dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(dir.path());
+        let mut autosave = Autosave::default();
+        let mut history = vec![prompt("first")];
+
+        assert!(autosave.turn_finished(1));
+        let meta = autosave
+            .save(&manager, &history, "stockpot", "gpt-4o")
+            .unwrap();
+        assert!(meta.name.starts_with("autosave-"));
+        assert_eq!(autosave.name(), Some(meta.name.as_str()));
+        assert!(!autosave.turn_finished(2));
+
+        history.push(prompt("second"));
+        autosave
+            .save(&manager, &history, "stockpot", "gpt-4o")
+            .unwrap();
+        assert_eq!(manager.list().unwrap().len(), 1);
+        assert_eq!(manager.load(&meta.name).unwrap().messages.len(), 2);
+    }
+}
@@ -0,0 +1,325 @@
+//! Token and time budgets for a session.
+//!
+//! A [`SessionBudget`] lives on the session's metadata next to the
+//! [`SessionUsage`] accumulated so far. During a run, a [`BudgetTracker`]
+//! starts from that usage, counts what the run consumes, and is checked by
+//! the executor before each model turn and each tool call.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::tokens::format_tokens_with_separator;
+
+/// Limits for a session. `None` means no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionBudget {
+    /// Estimated tokens sent and received across all runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<usize>,
+
+    /// Wall-clock time spent in runs, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_seconds: Option<u64>,
+}
+
+impl SessionBudget {
+    /// Whether no limit is set.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_tokens.is_none() && self.max_seconds.is_none()
+    }
+
+    /// Check `usage` against the limits.
+    pub fn check(&self, usage: &SessionUsage) -> Result<(), BudgetExceeded> {
+        if let Some(limit) = self.max_tokens {
+            if usage.tokens > limit {
+                return Err(BudgetExceeded::Tokens {
+                    used: usage.tokens,
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = self.max_seconds {
+            let elapsed = usage.elapsed_ms / 1000;
+            if elapsed >= limit {
+                return Err(BudgetExceeded::Time { elapsed, limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a session has consumed so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionUsage {
+    /// Estimated tokens sent and received.
+    #[serde(default)]
+    pub tokens: usize,
+
+    /// Wall-clock time spent in runs, in milliseconds.
+    #[serde(default)]
+    pub elapsed_ms: u64,
+}
+
+impl SessionUsage {
+    /// Add another run's usage to this one.
+    pub fn add(&mut self, other: SessionUsage) {
+        self.tokens += other.tokens;
+        self.elapsed_ms += other.elapsed_ms;
+    }
+}
+
+/// A session went over its budget.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BudgetExceeded {
+    #[error("Session token budget exceeded: used ~{} of {} tokens", format_tokens_with_separator(*.used), format_tokens_with_separator(*.limit))]
+    Tokens { used: usize, limit: usize },
+
+    #[error("Session time budget exceeded: ran {elapsed}s of {limit}s")]
+    Time { elapsed: u64, limit: u64 },
+}
+
+/// Tracks one run's consumption against a session budget.
+///
+/// Shared between the executor and its tool adapters, so counting is
+/// lock-free.
+#[derive(Debug)]
+pub struct BudgetTracker {
+    budget: SessionBudget,
+    /// Usage from earlier runs in the session
+    prior: SessionUsage,
+    started: Instant,
+    tokens: AtomicUsize,
+}
+
+impl BudgetTracker {
+    /// Start tracking a run on top of the session's earlier usage.
+    pub fn new(budget: SessionBudget, prior: SessionUsage) -> Self {
+        Self {
+            budget,
+            prior,
+            started: Instant::now(),
+            tokens: AtomicUsize::new(0),
+        }
+    }
+
+    /// Count tokens consumed by this run.
+    pub fn record_tokens(&self, tokens: usize) {
+        self.tokens.fetch_add(tokens, Ordering::Relaxed);
+    }
+
+    /// What this run has consumed so far.
+    pub fn run_usage(&self) -> SessionUsage {
+        SessionUsage {
+            tokens: self.tokens.load(Ordering::Relaxed),
+            elapsed_ms: duration_ms(self.started.elapsed()),
+        }
+    }
+
+    /// Session usage including this run.
+    pub fn total_usage(&self) -> SessionUsage {
+        let mut total = self.prior;
+        total.add(self.run_usage());
+        total
+    }
+
+    /// Check the session, including this run, against its budget.
+    pub fn check(&self) -> Result<(), BudgetExceeded> {
+        self.budget.check(&self.total_usage())
+    }
+}
+
+/// A `/budget` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetCommand {
+    /// Show the limits and what the session has used.
+    Show,
+    /// Set the token limit, or clear it with `None`.
+    Tokens(Option<usize>),
+    /// Set the time limit in seconds, or clear it with `None`.
+    Seconds(Option<u64>),
+    /// Clear both limits.
+    Off,
+}
+
+impl BudgetCommand {
+    /// Parse the arguments after `/budget`.
+    pub fn parse(args: &str) -> Result<Self, String> {
+        let usage = || {
+            "Usage: /budget, /budget tokens <n|off>, /budget time <seconds|off>, /budget off"
+                .to_string()
+        };
+        let limit = |n: &str| -> Result<Option<u64>, String> {
+            match n {
+                "off" => Ok(None),
+                n => n
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .map(Some)
+                    .ok_or_else(usage),
+            }
+        };
+        let mut parts = args.split_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => Ok(Self::Show),
+            (Some("off"), None, _) => Ok(Self::Off),
+            (Some("tokens"), Some(n), None) => {
+                limit(n).map(|n| Self::Tokens(n.map(|n| n as usize)))
+            }
+            (Some("time"), Some(n), None) => limit(n).map(Self::Seconds),
+            _ => Err(usage()),
+        }
+    }
+
+    /// Change `budget` as the command says.
+    pub fn apply(self, budget: &mut SessionBudget) {
+        match self {
+            Self::Show => {}
+            Self::Tokens(limit) => budget.max_tokens = limit,
+            Self::Seconds(limit) => budget.max_seconds = limit,
+            Self::Off => *budget = SessionBudget::default(),
+        }
+    }
+}
+
+/// What a session has used against its limits, for `/budget`.
+pub fn describe_budget(budget: &SessionBudget, usage: &SessionUsage) -> String {
+    let tokens = match budget.max_tokens {
+        Some(limit) => format!(
+            "~{} of {} tokens",
+            format_tokens_with_separator(usage.tokens),
+            format_tokens_with_separator(limit)
+        ),
+        None => format!(
+            "~{} tokens (no limit)",
+            format_tokens_with_separator(usage.tokens)
+        ),
+    };
+    let elapsed = usage.elapsed_ms / 1000;
+    let time = match budget.max_seconds {
+        Some(limit) => format!("{}s of {}s", elapsed, limit),
+        None => format!("{}s (no limit)", elapsed),
+    };
+    format!("Session budget: used {} and {}", tokens, time)
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_budget_never_trips() {
+        let budget = SessionBudget::default();
+        assert!(budget.is_unlimited());
+        let usage = SessionUsage {
+            tokens: usize::MAX,
+            elapsed_ms: u64::MAX,
+        };
+        assert!(budget.check(&usage).is_ok());
+    }
+
+    #[test]
+    fn test_token_budget() {
+        let budget = SessionBudget {
+            max_tokens: Some(1_000),
+            ..Default::default()
+        };
+        let mut usage = SessionUsage {
+            tokens: 1_000,
+            elapsed_ms: 0,
+        };
+        assert!(budget.check(&usage).is_ok());
+
+        usage.tokens = 1_500;
+        let err = budget.check(&usage).unwrap_err();
+        assert_eq!(
+            err,
+            BudgetExceeded::Tokens {
+                used: 1_500,
+                limit: 1_000
+            }
+        );
+        assert!(err.to_string().contains("1,500 of 1,000 tokens"));
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let budget = SessionBudget {
+            max_seconds: Some(60),
+            ..Default::default()
+        };
+        let usage = SessionUsage {
+            tokens: 0,
+            elapsed_ms: 61_000,
+        };
+        assert_eq!(
+            budget.check(&usage),
+            Err(BudgetExceeded::Time {
+                elapsed: 61,
+                limit: 60
+            })
+        );
+    }
+
+    #[test]
+    fn test_budget_command() {
+        assert_eq!(BudgetCommand::parse(""), Ok(BudgetCommand::Show));
+        assert_eq!(BudgetCommand::parse("off"), Ok(BudgetCommand::Off));
+        assert_eq!(
+            BudgetCommand::parse("tokens 200000"),
+            Ok(BudgetCommand::Tokens(Some(200_000)))
+        );
+        assert_eq!(
+            BudgetCommand::parse("time off"),
+            Ok(BudgetCommand::Seconds(None))
+        );
+        assert!(BudgetCommand::parse("tokens 0").is_err());
+        assert!(BudgetCommand::parse("tokens").is_err());
+        assert!(BudgetCommand::parse("money 5").is_err());
+
+        let mut budget = SessionBudget::default();
+        BudgetCommand::Tokens(Some(1_000)).apply(&mut budget);
+        BudgetCommand::Seconds(Some(60)).apply(&mut budget);
+        let usage = SessionUsage {
+            tokens: 250,
+            elapsed_ms: 12_500,
+        };
+        assert_eq!(
+            describe_budget(&budget, &usage),
+            "Session budget: used ~250 of 1,000 tokens and 12s of 60s"
+        );
+        BudgetCommand::Off.apply(&mut budget);
+        assert!(budget.is_unlimited());
+    }
+
+    #[test]
+    fn test_tracker_counts_on_top_of_prior_usage() {
+        let budget = SessionBudget {
+            max_tokens: Some(100),
+            ..Default::default()
+        };
+        let prior = SessionUsage {
+            tokens: 80,
+            elapsed_ms: 5_000,
+        };
+        let tracker = BudgetTracker::new(budget, prior);
+
+        tracker.record_tokens(20);
+        assert!(tracker.check().is_ok());
+        assert_eq!(tracker.run_usage().tokens, 20);
+
+        tracker.record_tokens(1);
+        assert!(matches!(
+            tracker.check(),
+            Err(BudgetExceeded::Tokens { used: 101, .. })
+        ));
+        assert!(tracker.total_usage().elapsed_ms >= 5_000);
+    }
+}
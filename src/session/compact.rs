@@ -0,0 +1,397 @@
+//! Trimming the context on request (`/compact`).
+//!
+//! Compaction here works in whole turns, a prompt and everything after it
+//! up to the next prompt, so a tool return is never kept without the call
+//! that asked for it. Older turns are dropped, not summarized, except for
+//! pinned messages (`/pin`), which are always kept along with the tool call
+//! or tool return they pair with.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serdes_ai_core::{ModelRequest, ModelRequestPart, ModelResponsePart};
+
+use super::rewind::prompt_text;
+use crate::tokens::{
+    estimate_message_tokens, estimate_tokens, format_tokens_with_separator, should_compact,
+    MIN_COMPLETION_TOKENS,
+};
+
+/// Share of the context window `/compact` trims the history to.
+const COMPACT_TARGET: f64 = 0.5;
+
+/// Share of the context window a history can fill before sending it is
+/// likely to fail.
+const OVERSIZED_SHARE: f64 = 0.9;
+
+/// A `/compact` or `/compact keep <n>` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompactCommand {
+    /// Keep the newest turns that fit in half the context window.
+    Fit,
+    /// Keep the last `n` turns.
+    KeepTurns(usize),
+}
+
+impl CompactCommand {
+    /// Parse the arguments after `/compact`.
+    pub fn parse(args: &str) -> Result<Self, String> {
+        let usage = || "Usage: /compact, /compact keep <n> (n at least 1)".to_string();
+        let mut parts = args.split_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => Ok(Self::Fit),
+            (Some("keep"), Some(n), None) => match n.parse() {
+                Ok(n) if n > 0 => Ok(Self::KeepTurns(n)),
+                _ => Err(usage()),
+            },
+            _ => Err(usage()),
+        }
+    }
+}
+
+/// What a compaction changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactReport {
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    pub messages_dropped: usize,
+    pub turns_kept: usize,
+    /// Pinned messages kept from before the kept turns, with the tool
+    /// calls or returns they pair with.
+    pub pinned_kept: usize,
+}
+
+impl fmt::Display for CompactReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.messages_dropped == 0 {
+            return write!(
+                f,
+                "Nothing to compact (~{} tokens)",
+                format_tokens_with_separator(self.tokens_before)
+            );
+        }
+        write!(
+            f,
+            "Compacted context from ~{} to ~{} tokens: dropped {} message{}, kept the last {} turn{}",
+            format_tokens_with_separator(self.tokens_before),
+            format_tokens_with_separator(self.tokens_after),
+            self.messages_dropped,
+            if self.messages_dropped == 1 { "" } else { "s" },
+            self.turns_kept,
+            if self.turns_kept == 1 { "" } else { "s" },
+        )?;
+        match self.pinned_kept {
+            0 => Ok(()),
+            1 => write!(f, ", plus 1 pinned message"),
+            n => write!(f, ", plus {} pinned messages", n),
+        }
+    }
+}
+
+/// Drop older turns from `history`, keeping the messages in `pinned` and
+/// re-indexing them.
+///
+/// [`CompactCommand::Fit`] keeps as many of the newest turns as fit in
+/// half of `context_length` next to the pinned messages, but always at
+/// least the last one.
+pub fn compact_turns(
+    history: &mut Vec<ModelRequest>,
+    pinned: &mut BTreeSet<usize>,
+    command: CompactCommand,
+    context_length: usize,
+) -> CompactReport {
+    let tokens_before = estimate_tokens(history);
+    let starts: Vec<usize> = history
+        .iter()
+        .enumerate()
+        .filter(|(_, request)| prompt_text(request).is_some())
+        .map(|(i, _)| i)
+        .collect();
+    let anchored = with_tool_pairs(history, pinned);
+    // Pinned messages ahead of `start`, which are kept on top of the turns
+    let pinned_before = |start: usize| anchored.range(..start).copied();
+
+    let turns_kept = match command {
+        CompactCommand::KeepTurns(n) => n.min(starts.len()),
+        CompactCommand::Fit => {
+            let budget = (context_length as f64 * COMPACT_TARGET) as usize;
+            let fitting = starts
+                .iter()
+                .rev()
+                .take_while(|&&start| {
+                    let pinned_tokens: usize = pinned_before(start)
+                        .filter_map(|i| history.get(i))
+                        .map(estimate_message_tokens)
+                        .sum();
+                    estimate_tokens(&history[start..]) + pinned_tokens <= budget
+                })
+                .count();
+            fitting.max(1).min(starts.len())
+        }
+    };
+
+    // Everything before the first kept prompt goes, including stray
+    // requests ahead of the first prompt, unless it's pinned
+    let cut = match turns_kept {
+        0 => 0,
+        n => starts[starts.len() - n],
+    };
+    let pinned_kept = pinned_before(cut).count();
+    let kept: Vec<usize> = pinned_before(cut).chain(cut..history.len()).collect();
+    let messages_dropped = history.len() - kept.len();
+    *pinned = kept
+        .iter()
+        .enumerate()
+        .filter(|(_, old)| pinned.contains(old))
+        .map(|(new, _)| new)
+        .collect();
+    *history = kept.iter().map(|&i| history[i].clone()).collect();
+
+    CompactReport {
+        tokens_before,
+        tokens_after: estimate_tokens(history),
+        messages_dropped,
+        turns_kept,
+        pinned_kept,
+    }
+}
+
+/// `pinned` along with the message each pinned tool call or tool return
+/// pairs with, so neither is kept without the other.
+fn with_tool_pairs(history: &[ModelRequest], pinned: &BTreeSet<usize>) -> BTreeSet<usize> {
+    let mut anchored = BTreeSet::new();
+    for &i in pinned.range(..history.len()) {
+        anchored.insert(i);
+        if calls_tools(&history[i]) && history.get(i + 1).is_some_and(returns_tools) {
+            anchored.insert(i + 1);
+        }
+        if returns_tools(&history[i]) && i > 0 && calls_tools(&history[i - 1]) {
+            anchored.insert(i - 1);
+        }
+    }
+    anchored
+}
+
+/// Whether a request is a model response that calls tools.
+pub(super) fn calls_tools(request: &ModelRequest) -> bool {
+    request.parts.iter().any(|part| match part {
+        ModelRequestPart::ModelResponse(response) => response
+            .parts
+            .iter()
+            .any(|part| matches!(part, ModelResponsePart::ToolCall(_))),
+        _ => false,
+    })
+}
+
+/// Whether a request carries tool returns.
+pub(super) fn returns_tools(request: &ModelRequest) -> bool {
+    request
+        .parts
+        .iter()
+        .any(|part| matches!(part, ModelRequestPart::ToolReturn(_)))
+}
+
+/// A warning for a history of `tokens` that nearly fills the context
+/// window, e.g. a session checked on load, before the first turn sends it.
+///
+/// That's past [`OVERSIZED_SHARE`] of the window, or when the room left is
+/// under the [`MIN_COMPLETION_TOKENS`] a run always asks for: the run
+/// would then request more than the window holds.
+pub fn oversized_history_warning(tokens: usize, context_length: usize) -> Option<String> {
+    if context_length == 0 {
+        return None;
+    }
+    let room = context_length.saturating_sub(tokens) as u64;
+    let oversized =
+        should_compact(tokens, context_length, OVERSIZED_SHARE) || room < MIN_COMPLETION_TOKENS;
+    oversized.then(|| {
+        format!(
+            "This history is ~{} tokens, close to the model's {} token context window; \
+             /compact it before continuing",
+            format_tokens_with_separator(tokens),
+            format_tokens_with_separator(context_length)
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serdes_ai_core::{ModelResponse, TextPart, ToolCallArgs, ToolCallPart, ToolReturnPart};
+
+    fn prompt(text: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.add_user_prompt(text.to_string());
+        request
+    }
+
+    fn reply(text: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.parts.push(ModelRequestPart::ModelResponse(Box::new(
+            ModelResponse::with_parts(vec![ModelResponsePart::Text(TextPart::new(
+                text.to_string(),
+            ))]),
+        )));
+        request
+    }
+
+    fn tool_call(name: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.parts.push(ModelRequestPart::ModelResponse(Box::new(
+            ModelResponse::with_parts(vec![ModelResponsePart::ToolCall(ToolCallPart::new(
+                name.to_string(),
+                ToolCallArgs::from("{}".to_string()),
+            ))]),
+        )));
+        request
+    }
+
+    fn tool_return(name: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request
+            .parts
+            .push(ModelRequestPart::ToolReturn(ToolReturnPart::error(
+                name,
+                "no such file".to_string(),
+            )));
+        request
+    }
+
+    fn conversation(turns: usize, reply_len: usize) -> Vec<ModelRequest> {
+        (0..turns)
+            .flat_map(|i| {
+                [
+                    prompt(&format!("question {}", i)),
+                    reply(&"x".repeat(reply_len)),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_compact_command() {
+        assert_eq!(CompactCommand::parse(""), Ok(CompactCommand::Fit));
+        assert_eq!(
+            CompactCommand::parse(" keep 3 "),
+            Ok(CompactCommand::KeepTurns(3))
+        );
+        assert!(CompactCommand::parse("keep 0").is_err());
+        assert!(CompactCommand::parse("keep").is_err());
+        assert!(CompactCommand::parse("everything").is_err());
+    }
+
+    #[test]
+    fn test_keep_turns_drops_whole_turns() {
+        let mut history = conversation(5, 100);
+        let report = compact_turns(
+            &mut history,
+            &mut BTreeSet::new(),
+            CompactCommand::KeepTurns(2),
+            128_000,
+        );
+
+        assert_eq!(history.len(), 4);
+        assert_eq!(prompt_text(&history[0]).as_deref(), Some("question 3"));
+        assert_eq!(report.messages_dropped, 6);
+        assert_eq!(report.turns_kept, 2);
+        assert!(report.tokens_after < report.tokens_before);
+
+        // Asking for more turns than exist keeps everything
+        let report = compact_turns(
+            &mut history,
+            &mut BTreeSet::new(),
+            CompactCommand::KeepTurns(10),
+            128_000,
+        );
+        assert_eq!(report.messages_dropped, 0);
+        assert_eq!(history.len(), 4);
+    }
+
+    #[test]
+    fn test_pinned_messages_survive_compaction() {
+        let mut history = conversation(5, 100);
+        let mut pinned = BTreeSet::from([0, 9]);
+        let report = compact_turns(
+            &mut history,
+            &mut pinned,
+            CompactCommand::KeepTurns(1),
+            128_000,
+        );
+
+        // The first prompt is kept ahead of the last turn, and the pin in
+        // the kept turns moves with it
+        assert_eq!(history.len(), 3);
+        assert_eq!(prompt_text(&history[0]).as_deref(), Some("question 0"));
+        assert_eq!(prompt_text(&history[1]).as_deref(), Some("question 4"));
+        assert_eq!(pinned, BTreeSet::from([0, 2]));
+        assert_eq!(report.messages_dropped, 7);
+        assert_eq!(report.pinned_kept, 1);
+        assert!(report.to_string().ends_with("plus 1 pinned message"));
+    }
+
+    #[test]
+    fn test_pinned_tool_call_keeps_its_return() {
+        let mut history = vec![
+            prompt("question 0"),
+            tool_call("read_file"),
+            tool_return("read_file"),
+            reply("answer 0"),
+            prompt("question 1"),
+            tool_call("grep"),
+            tool_return("grep"),
+            reply("answer 1"),
+            prompt("question 2"),
+            reply("answer 2"),
+        ];
+        // A call in the first turn and a return in the second
+        let mut pinned = BTreeSet::from([1, 6]);
+        let report = compact_turns(
+            &mut history,
+            &mut pinned,
+            CompactCommand::KeepTurns(1),
+            128_000,
+        );
+
+        assert_eq!(report.pinned_kept, 4);
+        assert_eq!(history.len(), 6);
+        assert!(calls_tools(&history[0]) && returns_tools(&history[1]));
+        assert!(calls_tools(&history[2]) && returns_tools(&history[3]));
+        assert_eq!(prompt_text(&history[4]).as_deref(), Some("question 2"));
+        // Only the messages pinned by hand stay pinned
+        assert_eq!(pinned, BTreeSet::from([0, 3]));
+    }
+
+    #[test]
+    fn test_oversized_history_warning() {
+        assert!(oversized_history_warning(50_000, 128_000).is_none());
+        assert!(oversized_history_warning(10, 0).is_none());
+        // A small window fills up before 90% once a run's minimum
+        // completion is counted
+        assert!(oversized_history_warning(3_500, 4_096).is_some());
+
+        let warning = oversized_history_warning(120_000, 128_000).unwrap();
+        assert!(warning.contains("120 000"));
+        assert!(warning.contains("/compact"));
+    }
+
+    #[test]
+    fn test_fit_keeps_last_turn_even_when_too_big() {
+        let mut history = conversation(3, 4_000);
+        let report = compact_turns(
+            &mut history,
+            &mut BTreeSet::new(),
+            CompactCommand::Fit,
+            1_000,
+        );
+
+        assert_eq!(report.turns_kept, 1);
+        assert_eq!(prompt_text(&history[0]).as_deref(), Some("question 2"));
+
+        let mut empty = Vec::new();
+        assert_eq!(
+            compact_turns(&mut empty, &mut BTreeSet::new(), CompactCommand::Fit, 1_000)
+                .messages_dropped,
+            0
+        );
+    }
+}
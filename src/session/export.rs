@@ -0,0 +1,636 @@
+//! Standalone HTML transcripts of a session.
+//!
+//! The output is a single file with everything inlined: CSS in a `<style>`
+//! block, code highlighted with inline colors, tool calls in collapsible
+//! `<details>` sections and images downscaled and embedded as data URIs,
+//! so it can be mailed or attached anywhere.
+//!
+//! Request parts other than model responses and tool returns (user and
+//! system prompts) are read from their serialized form, which keeps this
+//! working as new prompt part kinds are added.
+
+use std::io::Cursor;
+use std::sync::OnceLock;
+
+use base64::Engine;
+use image::{imageops::FilterType, ImageFormat, ImageReader};
+use serde_json::Value as JsonValue;
+use serdes_ai_core::{ModelRequest, ModelRequestPart, ModelResponsePart};
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+use super::SessionData;
+
+/// Images wider or taller than this are scaled down before embedding.
+const MAX_IMAGE_DIMENSION: u32 = 800;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; max-width: 860px; margin: 2rem auto; padding: 0 1rem; color: #1f2328; background: #fff; line-height: 1.5; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5rem; }
+header p { color: #59636e; margin-top: 0; }
+.message { border-radius: 8px; padding: 0.75rem 1rem; margin: 1rem 0; }
+.message .role { font-size: 0.75rem; font-weight: 600; text-transform: uppercase; color: #59636e; margin-bottom: 0.25rem; }
+.user { background: #ddf4ff; }
+.assistant { background: #f6f8fa; }
+.system { background: #fff8c5; }
+pre { overflow-x: auto; padding: 0.75rem; border-radius: 6px; font-size: 0.85rem; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, monospace; }
+p code { background: rgba(175, 184, 193, 0.2); padding: 0.1em 0.3em; border-radius: 4px; }
+details.tool { border: 1px solid #d0d7de; border-radius: 6px; margin: 0.5rem 0; padding: 0.25rem 0.75rem; background: #fff; }
+details.tool summary { cursor: pointer; font-family: ui-monospace, SFMono-Regular, Menlo, monospace; font-size: 0.85rem; }
+details.tool.error summary { color: #cf222e; }
+details.tool pre { background: #f6f8fa; }
+img { max-width: 100%; border-radius: 6px; }
+"#;
+
+/// Who a transcript entry came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    User,
+    Assistant,
+    System,
+}
+
+impl Role {
+    fn class(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::System => "system",
+        }
+    }
+}
+
+/// One piece of rendered content.
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Text(String),
+    Image { media_type: String, data: Vec<u8> },
+    ToolCall { name: String, args: String },
+    ToolReturn { name: String, content: String },
+}
+
+/// Render a session as a self-contained HTML page.
+pub fn export_html(session: &SessionData) -> String {
+    let mut body = String::new();
+    for message in &session.messages {
+        for (role, blocks) in message_blocks(message) {
+            if blocks.is_empty() {
+                continue;
+            }
+            body.push_str(&format!(
+                "<section class=\"message {}\">\n<div class=\"role\">{}</div>\n",
+                role.class(),
+                role.class()
+            ));
+            for block in &blocks {
+                body.push_str(&render_block(block));
+            }
+            body.push_str("</section>\n");
+        }
+    }
+
+    let meta = &session.meta;
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n\
+         <header>\n<h1>{title}</h1>\n<p>{agent} · {model} · {count} messages · {updated}</p>\n</header>\n\
+         <main>\n{body}</main>\n</body>\n</html>\n",
+        title = escape(&meta.name),
+        style = STYLE,
+        agent = escape(&meta.agent),
+        model = escape(&meta.model),
+        count = meta.message_count,
+        updated = meta.updated_at.format("%Y-%m-%d %H:%M UTC"),
+        body = body,
+    )
+}
+
+/// The searchable text of a request: what the user and model wrote, tool
+/// arguments and tool output. System prompts are left out.
+pub(super) fn message_text(message: &ModelRequest) -> Vec<String> {
+    message_blocks(message)
+        .into_iter()
+        .filter(|(role, _)| *role != Role::System)
+        .flat_map(|(_, blocks)| blocks)
+        .filter_map(|block| match block {
+            Block::Text(text) => Some(text),
+            Block::ToolCall { args, .. } => Some(args),
+            Block::ToolReturn { content, .. } => Some(content),
+            Block::Image { .. } => None,
+        })
+        .collect()
+}
+
+/// A request as plain text, one entry per role: what the user and model
+/// wrote, tool calls with their arguments and tool output.
+pub(super) fn message_plain_text(message: &ModelRequest) -> Vec<(&'static str, String)> {
+    message_blocks(message)
+        .into_iter()
+        .filter(|(_, blocks)| !blocks.is_empty())
+        .map(|(role, blocks)| {
+            let texts: Vec<String> = blocks
+                .into_iter()
+                .map(|block| match block {
+                    Block::Text(text) => text,
+                    Block::Image { media_type, .. } => format!("[image: {}]", media_type),
+                    Block::ToolCall { name, args } => format!("→ {}({})", name, args),
+                    Block::ToolReturn { name, content } => format!("← {}: {}", name, content),
+                })
+                .collect();
+            (role.class(), texts.join("\n\n"))
+        })
+        .collect()
+}
+
+/// What the user and model wrote in a request, one entry per role, without
+/// tool calls, tool output or images.
+pub(super) fn message_dialogue(message: &ModelRequest) -> Vec<(&'static str, String)> {
+    message_blocks(message)
+        .into_iter()
+        .filter_map(|(role, blocks)| {
+            let texts: Vec<String> = blocks
+                .into_iter()
+                .filter_map(|block| match block {
+                    Block::Text(text) => Some(text),
+                    _ => None,
+                })
+                .collect();
+            (!texts.is_empty()).then(|| (role.class(), texts.join("\n\n")))
+        })
+        .collect()
+}
+
+/// Split a request into renderable blocks, grouped by role.
+fn message_blocks(message: &ModelRequest) -> Vec<(Role, Vec<Block>)> {
+    let mut groups: Vec<(Role, Vec<Block>)> = Vec::new();
+    let mut push = |role: Role, block: Block| match groups.last_mut() {
+        Some((last, blocks)) if *last == role => blocks.push(block),
+        _ => groups.push((role, vec![block])),
+    };
+
+    for part in &message.parts {
+        match part {
+            ModelRequestPart::ModelResponse(response) => {
+                for part in &response.parts {
+                    let value = serde_json::to_value(part).unwrap_or_default();
+                    match part {
+                        ModelResponsePart::Text(_) => {
+                            push(Role::Assistant, Block::Text(text_of(&value)))
+                        }
+                        ModelResponsePart::ToolCall(_) => push(
+                            Role::Assistant,
+                            Block::ToolCall {
+                                name: string_field(&value, "tool_name"),
+                                args: pretty(value.get("args").unwrap_or(&JsonValue::Null)),
+                            },
+                        ),
+                        _ => {}
+                    }
+                }
+            }
+            ModelRequestPart::ToolReturn(tool_return) => {
+                let value = serde_json::to_value(tool_return).unwrap_or_default();
+                let content = value.get("content").map(pretty).unwrap_or_default();
+                push(
+                    Role::Assistant,
+                    Block::ToolReturn {
+                        name: tool_return.tool_name.clone(),
+                        content,
+                    },
+                );
+            }
+            other => {
+                let value = serde_json::to_value(other).unwrap_or_default();
+                let role = if mentions_system(&value) {
+                    Role::System
+                } else {
+                    Role::User
+                };
+                let mut blocks = Vec::new();
+                collect_prompt_blocks(&value, &mut blocks);
+                for block in blocks {
+                    push(role, block);
+                }
+            }
+        }
+    }
+
+    groups
+}
+
+/// Whether a serialized part is tagged as a system prompt.
+pub(super) fn mentions_system(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(map) => map.iter().any(|(key, value)| {
+            key.to_lowercase().contains("system")
+                || value
+                    .as_str()
+                    .is_some_and(|s| matches!(s, "system" | "system-prompt" | "system_prompt"))
+        }),
+        _ => false,
+    }
+}
+
+/// Pull text and images out of a serialized prompt part.
+fn collect_prompt_blocks(value: &JsonValue, blocks: &mut Vec<Block>) {
+    match value {
+        JsonValue::String(text) => blocks.push(Block::Text(text.clone())),
+        JsonValue::Array(items) => {
+            for item in items {
+                collect_prompt_blocks(item, blocks);
+            }
+        }
+        JsonValue::Object(map) => {
+            if let Some(image) = image_block(value) {
+                blocks.push(image);
+                return;
+            }
+            for key in ["content", "text", "parts"] {
+                if let Some(inner) = map.get(key) {
+                    collect_prompt_blocks(inner, blocks);
+                    return;
+                }
+            }
+            // Externally tagged enums wrap the part in a single-key object
+            if map.len() == 1 {
+                if let Some(inner) = map.values().next() {
+                    collect_prompt_blocks(inner, blocks);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Recognize a serialized binary image: raw `data` plus a media type.
+fn image_block(value: &JsonValue) -> Option<Block> {
+    let map = value.as_object()?;
+    let media_type = map.iter().find_map(|(key, value)| {
+        let key = key.to_lowercase();
+        (key.contains("media") || key.contains("mime"))
+            .then(|| value.as_str())
+            .flatten()
+    })?;
+    let data = match map.get("data")? {
+        JsonValue::String(encoded) => base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()?,
+        JsonValue::Array(bytes) => bytes
+            .iter()
+            .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+            .collect::<Option<Vec<u8>>>()?,
+        _ => return None,
+    };
+
+    let media_type = if media_type.contains('/') {
+        media_type.to_lowercase()
+    } else {
+        format!("image/{}", media_type.to_lowercase())
+    };
+    Some(Block::Image { media_type, data })
+}
+
+fn string_field(value: &JsonValue, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(JsonValue::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Text of a serialized text part, wherever the string lives.
+fn text_of(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(text) => text.clone(),
+        JsonValue::Object(map) => ["content", "text"]
+            .iter()
+            .find_map(|key| map.get(*key))
+            .or_else(|| (map.len() == 1).then(|| map.values().next()).flatten())
+            .map(text_of)
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// JSON strings as-is (they are often JSON themselves), anything else pretty-printed.
+fn pretty(value: &JsonValue) -> String {
+    let text = match value {
+        JsonValue::String(text) => text.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    };
+    match serde_json::from_str::<JsonValue>(&text) {
+        Ok(parsed @ (JsonValue::Object(_) | JsonValue::Array(_))) => {
+            serde_json::to_string_pretty(&parsed).unwrap_or(text)
+        }
+        _ => text,
+    }
+}
+
+fn render_block(block: &Block) -> String {
+    match block {
+        Block::Text(text) => render_markdown(text),
+        Block::Image { media_type, data } => match image_data_uri(media_type, data) {
+            Some(uri) => format!("<img src=\"{}\" alt=\"attached image\">\n", escape(&uri)),
+            None => "<p><em>[image could not be embedded]</em></p>\n".to_string(),
+        },
+        Block::ToolCall { name, args } => format!(
+            "<details class=\"tool\">\n<summary>🔧 {}</summary>\n{}</details>\n",
+            escape(name),
+            highlight(args, "json")
+        ),
+        Block::ToolReturn { name, content } => {
+            let class = if content.starts_with("Error") || content.contains("\"error\"") {
+                "tool error"
+            } else {
+                "tool"
+            };
+            format!(
+                "<details class=\"{}\">\n<summary>↳ {} result</summary>\n<pre><code>{}</code></pre>\n</details>\n",
+                class,
+                escape(name),
+                escape(content)
+            )
+        }
+    }
+}
+
+/// Minimal markdown: fenced code blocks are highlighted, other lines become
+/// paragraphs with inline `code`.
+fn render_markdown(text: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<(String, Vec<&str>)> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(fence) = trimmed.strip_prefix("```") {
+            match code.take() {
+                Some((lang, lines)) => html.push_str(&highlight(&lines.join("\n"), &lang)),
+                None => {
+                    flush_paragraph(&mut paragraph, &mut html);
+                    code = Some((fence.trim().to_string(), Vec::new()));
+                }
+            }
+        } else if let Some((_, lines)) = code.as_mut() {
+            lines.push(line);
+        } else if trimmed.is_empty() {
+            flush_paragraph(&mut paragraph, &mut html);
+        } else {
+            paragraph.push(line);
+        }
+    }
+
+    // An unclosed fence still renders as code
+    if let Some((lang, lines)) = code {
+        html.push_str(&highlight(&lines.join("\n"), &lang));
+    }
+    flush_paragraph(&mut paragraph, &mut html);
+    html
+}
+
+fn flush_paragraph(lines: &mut Vec<&str>, html: &mut String) {
+    if lines.is_empty() {
+        return;
+    }
+    let escaped: Vec<String> = lines.drain(..).map(inline_code).collect();
+    html.push_str(&format!("<p>{}</p>\n", escaped.join("<br>\n")));
+}
+
+/// Escape a line, turning `backtick spans` into `<code>`.
+fn inline_code(line: &str) -> String {
+    let mut out = String::new();
+    for (i, piece) in line.split('`').enumerate() {
+        if i % 2 == 1 {
+            out.push_str(&format!("<code>{}</code>", escape(piece)));
+        } else {
+            out.push_str(&escape(piece));
+        }
+    }
+    out
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        themes
+            .themes
+            .remove("InspiredGitHub")
+            .unwrap_or_else(|| themes.themes.into_values().next().unwrap_or_default())
+    })
+}
+
+/// Highlight code with inline styles, falling back to plain escaped text.
+fn highlight(code: &str, lang: &str) -> String {
+    let syntaxes = syntax_set();
+    let syntax = syntaxes
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let mut source = code.to_string();
+    if !source.ends_with('\n') {
+        source.push('\n');
+    }
+    highlighted_html_for_string(&source, syntaxes, syntax, theme())
+        .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>\n", escape(code)))
+}
+
+/// Downscale an image and encode it as a PNG data URI.
+///
+/// Images that can't be decoded are embedded as-is, if their media type is
+/// a plain `image/<subtype>`: it comes from the session file.
+fn image_data_uri(media_type: &str, data: &[u8]) -> Option<String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let decoded = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.decode().ok());
+
+    let Some(img) = decoded else {
+        if data.is_empty() || !is_image_media_type(media_type) {
+            return None;
+        }
+        return Some(format!(
+            "data:{};base64,{}",
+            media_type,
+            engine.encode(data)
+        ));
+    };
+
+    let img = if img.width() > MAX_IMAGE_DIMENSION || img.height() > MAX_IMAGE_DIMENSION {
+        img.resize(
+            MAX_IMAGE_DIMENSION,
+            MAX_IMAGE_DIMENSION,
+            FilterType::Triangle,
+        )
+    } else {
+        img
+    };
+    let mut png = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .ok()?;
+    Some(format!("data:image/png;base64,{}", engine.encode(png)))
+}
+
+/// Whether `media_type` is `image/` and a subtype of `[a-z0-9.+-]`.
+fn is_image_media_type(media_type: &str) -> bool {
+    media_type.strip_prefix("image/").is_some_and(|subtype| {
+        !subtype.is_empty()
+            && subtype
+                .chars()
+                .all(|c| matches!(c, 'a'..='z' | '0'..='9' | '.' | '+' | '-'))
+    })
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serdes_ai_core::messages::ToolCallArgs;
+    use serdes_ai_core::{ModelResponse, TextPart, ToolCallPart, ToolReturnPart};
+
+    fn transcript() -> SessionData {
+        let mut session = SessionData::new("demo-session", "stockpot", "gpt-4o");
+
+        let mut question = ModelRequest::new();
+        question.add_user_prompt("How do I print <hello> in Rust?".to_string());
+
+        let mut answer = ModelRequest::new();
+        answer.parts.push(ModelRequestPart::ModelResponse(Box::new(
+            ModelResponse::with_parts(vec![
+                ModelResponsePart::Text(TextPart::new(
+                    "Use `println!`:\n\n```rust\nfn main() {\n    println!(\"hello\");\n}\n```"
+                        .to_string(),
+                )),
+                ModelResponsePart::ToolCall(ToolCallPart::new(
+                    "read_file".to_string(),
+                    ToolCallArgs::from("{\"file_path\": \"src/main.rs\"}".to_string()),
+                )),
+            ])
+            .with_model_name("gpt-4o".to_string()),
+        )));
+
+        let mut tool_result = ModelRequest::new();
+        tool_result
+            .parts
+            .push(ModelRequestPart::ToolReturn(ToolReturnPart::error(
+                "read_file",
+                "File not found".to_string(),
+            )));
+
+        session.update(vec![question, answer, tool_result]);
+        session
+    }
+
+    #[test]
+    fn test_export_html_is_standalone_document() {
+        let html = export_html(&transcript());
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.trim_end().ends_with("</html>"));
+        assert_eq!(
+            html.matches("<section").count(),
+            html.matches("</section>").count()
+        );
+        assert_eq!(
+            html.matches("<details").count(),
+            html.matches("</details>").count()
+        );
+        assert!(html.contains("<style>"));
+        // No external assets
+        assert!(!html.contains("<link"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("src=\"http"));
+    }
+
+    #[test]
+    fn test_export_html_contains_messages_and_highlighting() {
+        let html = export_html(&transcript());
+
+        assert!(html.contains("demo-session"));
+        assert!(html.contains("How do I print &lt;hello&gt; in Rust?"));
+        assert!(html.contains("<code>println!</code>"));
+        // syntect emits inline-colored spans for highlighted code
+        assert!(html.contains("<pre style="));
+        assert!(html.contains("<span style=\"color:"));
+        assert!(html.contains("<summary>🔧 read_file</summary>"));
+        assert!(html.contains("File not found"));
+    }
+
+    #[test]
+    fn test_render_markdown_unclosed_fence() {
+        let html = render_markdown("before\n```python\nprint(1)");
+        assert!(html.starts_with("<p>before</p>"));
+        assert!(html.contains("print"));
+        assert!(html.contains("<pre"));
+    }
+
+    #[test]
+    fn test_image_block_from_serialized_part() {
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgb8(2_000, 1_000)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&png);
+
+        let value = serde_json::json!({"data": encoded, "media_type": "png"});
+        let Some(Block::Image { media_type, data }) = image_block(&value) else {
+            panic!("Expected an image block");
+        };
+        assert_eq!(media_type, "image/png");
+        assert_eq!(data, png);
+
+        let uri = image_data_uri(&media_type, &data).unwrap();
+        let embedded = base64::engine::general_purpose::STANDARD
+            .decode(uri.strip_prefix("data:image/png;base64,").unwrap())
+            .unwrap();
+        let img = image::load_from_memory(&embedded).unwrap();
+        assert_eq!((img.width(), img.height()), (800, 400));
+    }
+
+    #[test]
+    fn test_undecodable_image_needs_a_plain_media_type() {
+        let data = b"not really an image";
+        let uri = image_data_uri("image/svg+xml", data).unwrap();
+        assert!(uri.starts_with("data:image/svg+xml;base64,"));
+
+        assert!(image_data_uri("image/png\"><script>alert(1)</script>", data).is_none());
+        assert!(image_data_uri("text/html", data).is_none());
+        assert!(image_data_uri("image/", data).is_none());
+
+        let html = render_block(&Block::Image {
+            media_type: "image/png\" onerror=\"alert(1)".to_string(),
+            data: data.to_vec(),
+        });
+        assert!(!html.contains("onerror"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">&'"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;"
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! Looking through and rolling back the context (`/history`).
+//!
+//! Messages are numbered from 1 in the order they are sent to the model.
+//! A message is one request in the history: a prompt, a model response, or
+//! the tool returns for the response before it. Truncating never cuts
+//! between a response's tool calls and their returns.
+
+use serdes_ai_core::{ModelRequest, ModelRequestPart, ModelResponse, ModelResponsePart, TextPart};
+
+use super::compact::calls_tools;
+use super::export::{message_dialogue, message_plain_text};
+use super::rewind::prompt_text;
+
+/// Longest snippet shown per message in a listing, in characters.
+const SNIPPET_CHARS: usize = 80;
+
+/// A `/history` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryCommand {
+    /// List every message with its number, role and a snippet.
+    List,
+    /// Show message `n` in full.
+    Show(usize),
+    /// Drop every message after `n`.
+    Truncate(usize),
+}
+
+impl HistoryCommand {
+    /// Parse the arguments after `/history`.
+    pub fn parse(args: &str) -> Result<Self, String> {
+        let usage = || "Usage: /history, /history show <n>, /history truncate <n>".to_string();
+        let mut parts = args.split_whitespace();
+        let number = |n: &str| -> Result<usize, String> {
+            n.parse().ok().filter(|&n| n > 0).ok_or_else(usage)
+        };
+        match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => Ok(Self::List),
+            (Some("show"), Some(n), None) => number(n).map(Self::Show),
+            (Some("truncate"), Some(n), None) => number(n).map(Self::Truncate),
+            _ => Err(usage()),
+        }
+    }
+}
+
+/// One line per message: number, roles and the start of its text.
+pub fn list_history(history: &[ModelRequest]) -> String {
+    if history.is_empty() {
+        return "The history is empty".to_string();
+    }
+    history
+        .iter()
+        .enumerate()
+        .map(|(i, message)| {
+            let entries = message_plain_text(message);
+            let roles: Vec<&str> = entries.iter().map(|(role, _)| *role).collect();
+            let text: Vec<&str> = entries.iter().map(|(_, text)| text.as_str()).collect();
+            format!(
+                "{}. **{}** {}",
+                i + 1,
+                roles.join("/"),
+                snippet(&text.join(" "))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Message `n` in full.
+pub fn show_message(history: &[ModelRequest], n: usize) -> Result<String, String> {
+    let message = n
+        .checked_sub(1)
+        .and_then(|i| history.get(i))
+        .ok_or_else(|| out_of_range(n, history.len()))?;
+    let sections: Vec<String> = message_plain_text(message)
+        .into_iter()
+        .map(|(role, text)| format!("**{}**\n\n{}", role, text))
+        .collect();
+    Ok(format!("Message {}\n\n{}", n, sections.join("\n\n")))
+}
+
+/// Keep the first `n` messages. Returns how many were dropped.
+///
+/// Refuses to keep a response that calls tools without the returns that
+/// follow it, which the next request couldn't carry.
+pub fn truncate_history(history: &mut Vec<ModelRequest>, n: usize) -> Result<usize, String> {
+    if n > history.len() {
+        return Err(out_of_range(n, history.len()));
+    }
+    if n > 0 && n < history.len() && calls_tools(&history[n - 1]) {
+        return Err(format!(
+            "Message {} calls tools that message {} returns; truncate at {} to keep both",
+            n,
+            n + 1,
+            n + 1
+        ));
+    }
+    let dropped = history.len() - n;
+    history.truncate(n);
+    Ok(dropped)
+}
+
+/// The conversation as (role, text) pairs, without system prompts, for
+/// showing a loaded history again.
+pub fn transcript(history: &[ModelRequest]) -> Vec<(&'static str, String)> {
+    history
+        .iter()
+        .flat_map(message_plain_text)
+        .filter(|(role, _)| *role != "system")
+        .collect()
+}
+
+/// The messages of the last `turns` turns of `history`, each starting at
+/// a user prompt.
+pub fn recent_turns(history: &[ModelRequest], turns: usize) -> &[ModelRequest] {
+    if turns == 0 {
+        return &[];
+    }
+    let starts: Vec<usize> = history
+        .iter()
+        .enumerate()
+        .filter(|(_, request)| prompt_text(request).is_some())
+        .map(|(i, _)| i)
+        .collect();
+    let start = starts
+        .len()
+        .checked_sub(turns)
+        .map_or(0, |first| starts[first]);
+    &history[start..]
+}
+
+/// The last `turns` turns of `history` as a plain dialogue, e.g. to show
+/// another agent the discussion so far. Tool calls, tool output, images and
+/// system prompts are left out.
+pub fn recent_dialogue(history: &[ModelRequest], turns: usize) -> String {
+    recent_turns(history, turns)
+        .iter()
+        .flat_map(message_dialogue)
+        .filter(|(role, _)| *role != "system")
+        .map(|(role, text)| format!("**{}**: {}", role, text))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Record a run that was stopped part-way: its prompt, then the reply as
+/// far as it had streamed, so the next prompt follows on from it.
+pub fn keep_interrupted_turn(history: &mut Vec<ModelRequest>, prompt: &str, partial: &str) {
+    let mut request = ModelRequest::new();
+    request.add_user_prompt(prompt.to_string());
+    history.push(request);
+    if partial.trim().is_empty() {
+        return;
+    }
+    let mut reply = ModelRequest::new();
+    reply.parts.push(ModelRequestPart::ModelResponse(Box::new(
+        ModelResponse::with_parts(vec![ModelResponsePart::Text(TextPart::new(
+            partial.to_string(),
+        ))]),
+    )));
+    history.push(reply);
+}
+
+fn out_of_range(n: usize, len: usize) -> String {
+    format!("No message {}: the history has {}", n, len)
+}
+
+/// The text on one line, cut to [`SNIPPET_CHARS`].
+fn snippet(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(SNIPPET_CHARS) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serdes_ai_core::{ToolCallArgs, ToolCallPart, ToolReturnPart};
+
+    fn prompt(text: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.add_user_prompt(text.to_string());
+        request
+    }
+
+    fn reply(text: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.parts.push(ModelRequestPart::ModelResponse(Box::new(
+            ModelResponse::with_parts(vec![ModelResponsePart::Text(TextPart::new(
+                text.to_string(),
+            ))]),
+        )));
+        request
+    }
+
+    #[test]
+    fn test_parse_history_command() {
+        assert_eq!(HistoryCommand::parse(""), Ok(HistoryCommand::List));
+        assert_eq!(HistoryCommand::parse("show 2"), Ok(HistoryCommand::Show(2)));
+        assert_eq!(
+            HistoryCommand::parse(" truncate 4 "),
+            Ok(HistoryCommand::Truncate(4))
+        );
+        assert!(HistoryCommand::parse("show 0").is_err());
+        assert!(HistoryCommand::parse("truncate").is_err());
+        assert!(HistoryCommand::parse("rewind 2").is_err());
+    }
+
+    #[test]
+    fn test_list_and_show() {
+        let long = "word ".repeat(40);
+        let history = vec![prompt("what is\nthis?"), reply(&long)];
+
+        let listing = list_history(&history);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines[0], "1. **user** what is this?");
+        assert!(lines[1].starts_with("2. **assistant** word word"));
+        assert!(lines[1].ends_with('…'));
+
+        let shown = show_message(&history, 2).unwrap();
+        assert!(shown.contains(long.trim_end()));
+        assert!(show_message(&history, 3).is_err());
+
+        let lines = transcript(&history);
+        assert_eq!(lines[0], ("user", "what is\nthis?".to_string()));
+        assert_eq!(lines[1].0, "assistant");
+    }
+
+    #[test]
+    fn test_recent_dialogue_takes_whole_turns() {
+        let history = vec![prompt("a"), reply("b"), prompt("c"), reply("d")];
+        assert_eq!(recent_dialogue(&history, 0), "");
+        assert_eq!(
+            recent_dialogue(&history, 1),
+            "**user**: c\n\n**assistant**: d"
+        );
+        assert!(recent_dialogue(&history, 5).starts_with("**user**: a"));
+        assert_eq!(recent_turns(&history, 1).len(), 2);
+        assert!(recent_turns(&history, 0).is_empty());
+    }
+
+    #[test]
+    fn test_truncate_history() {
+        let mut history = vec![prompt("a"), reply("b"), prompt("c"), reply("d")];
+        assert_eq!(truncate_history(&mut history, 2), Ok(2));
+        assert_eq!(history.len(), 2);
+        assert!(truncate_history(&mut history, 5).is_err());
+        assert_eq!(truncate_history(&mut history, 2), Ok(0));
+    }
+
+    #[test]
+    fn test_truncate_history_keeps_tool_returns() {
+        let mut call = ModelRequest::new();
+        call.parts.push(ModelRequestPart::ModelResponse(Box::new(
+            ModelResponse::with_parts(vec![ModelResponsePart::ToolCall(ToolCallPart::new(
+                "read_file".to_string(),
+                ToolCallArgs::from("{}".to_string()),
+            ))]),
+        )));
+        let mut returned = ModelRequest::new();
+        returned
+            .parts
+            .push(ModelRequestPart::ToolReturn(ToolReturnPart::error(
+                "read_file",
+                "no such file".to_string(),
+            )));
+        let mut history = vec![prompt("a"), call, returned, reply("b")];
+
+        let refused = truncate_history(&mut history, 2).unwrap_err();
+        assert!(refused.contains("truncate at 3"));
+        assert_eq!(history.len(), 4);
+        assert_eq!(truncate_history(&mut history, 3), Ok(1));
+    }
+
+    #[test]
+    fn test_keep_interrupted_turn() {
+        let mut history = vec![prompt("a"), reply("b")];
+        keep_interrupted_turn(&mut history, "c", "half an ans");
+        assert_eq!(
+            recent_dialogue(&history, 1),
+            "**user**: c\n\n**assistant**: half an ans"
+        );
+
+        // Stopped before any reply: only the prompt is kept
+        keep_interrupted_turn(&mut history, "d", "");
+        assert_eq!(history.len(), 5);
+        assert_eq!(recent_dialogue(&history, 1), "**user**: d");
+    }
+}
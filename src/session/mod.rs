@@ -26,15 +26,49 @@
 //!
 //! // Load a session
 //! let (messages, meta) = manager.load("my-project")?;
+//!
+//! // Share it as a single HTML file
+//! std::fs::write("my-project.html", manager.export_html("my-project")?)?;
 //! ```
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serdes_ai_core::ModelRequest;
+use std::collections::BTreeSet;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+use crate::atomic_file::write_atomic;
+use crate::config::Settings;
+use crate::tokens::{compact_history, CompactionStrategy};
+
+mod autosave;
+mod budget;
+mod compact;
+mod export;
+mod history;
+mod recover;
+mod rewind;
+mod search;
+mod tool_toggles;
+
+pub use autosave::Autosave;
+pub use budget::{
+    describe_budget, BudgetCommand, BudgetExceeded, BudgetTracker, SessionBudget, SessionUsage,
+};
+pub use compact::{compact_turns, oversized_history_warning, CompactCommand, CompactReport};
+pub use export::export_html;
+pub use history::{
+    keep_interrupted_turn, list_history, recent_dialogue, recent_turns, show_message, transcript,
+    truncate_history, HistoryCommand,
+};
+pub use recover::RecoveredSession;
+pub use rewind::rewind_last_prompt;
+pub use search::{SessionSearchHit, MAX_SEARCH_RESULTS};
+pub use tool_toggles::{ToolToggles, ToolsCommand};
+
 /// Error type for session operations.
 #[derive(Debug, Error)]
 pub enum SessionError {
@@ -49,6 +83,15 @@ pub enum SessionError {
 
     #[error("Invalid session name: {0}")]
     InvalidName(String),
+
+    #[error("Invalid tag '{0}': use letters, numbers, dashes and underscores")]
+    InvalidTag(String),
+
+    #[error("Session already exists: {0}")]
+    AlreadyExists(String),
+
+    #[error("No message at index {index} (session has {len} messages)")]
+    InvalidIndex { index: usize, len: usize },
 }
 
 /// Session metadata.
@@ -78,6 +121,26 @@ pub struct SessionMeta {
     /// Optional description/summary.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// Token/time limits; runs halt once the session exceeds them.
+    #[serde(default, skip_serializing_if = "SessionBudget::is_unlimited")]
+    pub budget: SessionBudget,
+
+    /// Usage accumulated across runs, checked against `budget`.
+    #[serde(default)]
+    pub usage: SessionUsage,
+
+    /// Session this one was forked from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<String>,
+
+    /// Labels for organizing sessions, lowercase and sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Kept by cleanup however old, and not counted toward the limit.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
 }
 
 impl SessionMeta {
@@ -93,6 +156,11 @@ impl SessionMeta {
             agent: agent.to_string(),
             model: model.to_string(),
             description: None,
+            budget: SessionBudget::default(),
+            usage: SessionUsage::default(),
+            forked_from: None,
+            tags: Vec::new(),
+            pinned: false,
         }
     }
 
@@ -102,6 +170,72 @@ impl SessionMeta {
         self.message_count = messages.len();
         self.token_estimate = estimate_tokens(messages);
     }
+
+    /// Tag the session. Returns `false` if it already had the tag.
+    pub fn add_tag(&mut self, tag: &str) -> Result<bool, SessionError> {
+        let tag = normalize_tag(tag)?;
+        match self.tags.binary_search(&tag) {
+            Ok(_) => Ok(false),
+            Err(i) => {
+                self.tags.insert(i, tag);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Remove a tag. Returns `false` if the session didn't have it.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let tag = tag.trim().to_lowercase();
+        let before = self.tags.len();
+        self.tags.retain(|t| *t != tag);
+        self.tags.len() != before
+    }
+
+    /// Whether the session has `tag`, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim().to_lowercase();
+        self.tags.iter().any(|t| *t == tag)
+    }
+
+    /// Start tracking a run against this session's budget.
+    pub fn budget_tracker(&self) -> BudgetTracker {
+        BudgetTracker::new(self.budget, self.usage)
+    }
+}
+
+/// One line for session listings: name, title, tags and age.
+impl fmt::Display for SessionMeta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(description) = &self.description {
+            write!(f, " - {}", description)?;
+        }
+        if !self.tags.is_empty() {
+            write!(f, " [{}]", self.tags.join(", "))?;
+        }
+        if self.pinned {
+            f.write_str(" (pinned)")?;
+        }
+        write!(
+            f,
+            " ({} messages, {})",
+            self.message_count,
+            format_relative_time(self.updated_at)
+        )
+    }
+}
+
+/// Lowercase a tag and check it only uses name characters.
+fn normalize_tag(tag: &str) -> Result<String, SessionError> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty()
+        || !tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(SessionError::InvalidTag(tag));
+    }
+    Ok(tag)
 }
 
 /// Session data stored on disk.
@@ -112,6 +246,14 @@ pub struct SessionData {
 
     /// Message history.
     pub messages: Vec<ModelRequest>,
+
+    /// Indices of messages that compaction must always keep.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub pinned: BTreeSet<usize>,
+
+    /// Tools turned off with `/tools`.
+    #[serde(default, skip_serializing_if = "ToolToggles::is_empty")]
+    pub tools: ToolToggles,
 }
 
 impl SessionData {
@@ -120,14 +262,133 @@ impl SessionData {
         Self {
             meta: SessionMeta::new(name, agent, model),
             messages: Vec::new(),
+            pinned: BTreeSet::new(),
+            tools: ToolToggles::default(),
         }
     }
 
     /// Update with new messages.
+    ///
+    /// Pins pointing past the end of the new history are dropped.
     pub fn update(&mut self, messages: Vec<ModelRequest>) {
         self.messages = messages;
+        let len = self.messages.len();
+        self.pinned.retain(|&i| i < len);
         self.meta.update(&self.messages);
     }
+
+    /// Pin a message so compaction always retains it.
+    ///
+    /// Returns `false` if it was already pinned.
+    pub fn pin(&mut self, index: usize) -> Result<bool, SessionError> {
+        if index >= self.messages.len() {
+            return Err(SessionError::InvalidIndex {
+                index,
+                len: self.messages.len(),
+            });
+        }
+        Ok(self.pinned.insert(index))
+    }
+
+    /// Unpin a message. Returns `false` if it wasn't pinned.
+    pub fn unpin(&mut self, index: usize) -> bool {
+        self.pinned.remove(&index)
+    }
+
+    /// Apply a `/pin` or `/unpin` command, returning a status line.
+    pub fn apply_pin_command(&mut self, command: PinCommand) -> Result<String, SessionError> {
+        command.apply(&mut self.pinned, self.messages.len())
+    }
+
+    /// Compact the history, keeping pinned messages and re-indexing them.
+    pub fn compact(&mut self, strategy: CompactionStrategy) {
+        let compacted = compact_history(&self.messages, strategy, &self.pinned);
+        self.pinned = compacted.pinned;
+        self.update(compacted.messages);
+    }
+}
+
+/// A `/pin <n>` or `/unpin <n>` command (message index, 0-based).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinCommand {
+    Pin(usize),
+    Unpin(usize),
+}
+
+impl PinCommand {
+    /// Parse user input, returning `None` if it isn't a pin command.
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut parts = input.split_whitespace();
+        let command = parts.next()?;
+        let index = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        match command {
+            "/pin" => Some(Self::Pin(index)),
+            "/unpin" => Some(Self::Unpin(index)),
+            _ => None,
+        }
+    }
+
+    /// Apply the command to the pins of a history of `len` messages,
+    /// returning a status line.
+    pub fn apply(self, pinned: &mut BTreeSet<usize>, len: usize) -> Result<String, SessionError> {
+        match self {
+            Self::Pin(index) if index >= len => Err(SessionError::InvalidIndex { index, len }),
+            Self::Pin(index) => Ok(if pinned.insert(index) {
+                format!("Pinned message {}", index)
+            } else {
+                format!("Message {} is already pinned", index)
+            }),
+            Self::Unpin(index) => Ok(if pinned.remove(&index) {
+                format!("Unpinned message {}", index)
+            } else {
+                format!("Message {} was not pinned", index)
+            }),
+        }
+    }
+}
+
+/// Environment variable that overrides where sessions are saved.
+pub const SESSIONS_DIR_ENV: &str = "STOCKPOT_SESSIONS_DIR";
+
+/// Project-local sessions directory, relative to a project root.
+pub const PROJECT_SESSIONS_DIR: &str = ".stockpot/sessions";
+
+/// Where sessions are saved, in order of precedence:
+///
+/// 1. `$STOCKPOT_SESSIONS_DIR`
+/// 2. the nearest existing [`PROJECT_SESSIONS_DIR`] at or above `cwd`,
+///    so a project can keep its conversations next to its code
+/// 3. the `sessions_dir` setting
+/// 4. `~/.stockpot/sessions`
+///
+/// `~` is expanded in the environment variable and the setting.
+pub fn resolve_sessions_dir(
+    env: Option<&str>,
+    cwd: Option<&Path>,
+    configured: Option<&str>,
+) -> PathBuf {
+    let expand = |dir: &str| PathBuf::from(shellexpand::tilde(dir.trim()).as_ref());
+    let default = dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(PROJECT_SESSIONS_DIR);
+    if let Some(dir) = env.filter(|dir| !dir.trim().is_empty()) {
+        return expand(dir);
+    }
+    // The default directory has the same shape; it's not a project's
+    if let Some(dir) = cwd.and_then(|cwd| {
+        cwd.ancestors()
+            .map(|dir| dir.join(PROJECT_SESSIONS_DIR))
+            .find(|dir| *dir != default && dir.is_dir())
+    }) {
+        return dir;
+    }
+    match configured {
+        Some(dir) => expand(dir),
+        None => default,
+    }
 }
 
 /// Session manager for saving and loading sessions.
@@ -140,15 +401,24 @@ pub struct SessionManager {
 }
 
 impl SessionManager {
-    /// Create a new session manager with default settings.
+    /// Create a session manager for the sessions directory in use, see
+    /// [`resolve_sessions_dir`]. The `sessions_dir` setting needs
+    /// [`SessionManager::from_settings`].
     pub fn new() -> Self {
-        let sessions_dir = dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".stockpot")
-            .join("sessions");
+        Self::with_sessions_dir(None)
+    }
+
+    /// Like [`SessionManager::new`], also honoring the `sessions_dir` setting.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let configured = settings.sessions_dir();
+        Self::with_sessions_dir(Some(configured.as_str()).filter(|dir| !dir.trim().is_empty()))
+    }
 
+    fn with_sessions_dir(configured: Option<&str>) -> Self {
+        let env = std::env::var(SESSIONS_DIR_ENV).ok();
+        let cwd = std::env::current_dir().ok();
         Self {
-            sessions_dir,
+            sessions_dir: resolve_sessions_dir(env.as_deref(), cwd.as_deref(), configured),
             max_sessions: 50, // Keep last 50 sessions by default
         }
     }
@@ -227,7 +497,7 @@ impl SessionManager {
 
         // Write to disk
         let content = serde_json::to_string_pretty(&session)?;
-        fs::write(&path, content)?;
+        write_atomic(&path, content)?;
 
         // Cleanup old sessions if needed
         self.cleanup()?;
@@ -251,6 +521,157 @@ impl SessionManager {
         Ok(session)
     }
 
+    /// Copy a saved session under a new name, to take it in another
+    /// direction without changing the original.
+    ///
+    /// The copy keeps the source's messages, pins, budget and usage so far,
+    /// and records the source in [`SessionMeta::forked_from`].
+    pub fn fork(&self, source: &str, new_name: &str) -> Result<SessionMeta, SessionError> {
+        Self::validate_name(new_name)?;
+        let mut session = self.load(source)?;
+        if self.session_path(new_name).exists() {
+            return Err(SessionError::AlreadyExists(new_name.to_string()));
+        }
+
+        let now = Utc::now();
+        session.meta.name = new_name.to_string();
+        session.meta.created_at = now;
+        session.meta.updated_at = now;
+        session.meta.forked_from = Some(source.to_string());
+        self.write(new_name, &session)?;
+        self.cleanup()?;
+        Ok(session.meta)
+    }
+
+    /// Pin or unpin a message in a saved session.
+    pub fn apply_pin_command(
+        &self,
+        name: &str,
+        command: PinCommand,
+    ) -> Result<String, SessionError> {
+        let mut session = self.load(name)?;
+        let status = session.apply_pin_command(command)?;
+        let content = serde_json::to_string_pretty(&session)?;
+        write_atomic(&self.session_path(name), content)?;
+        Ok(status)
+    }
+
+    /// Render a saved session as a standalone HTML page.
+    pub fn export_html(&self, name: &str) -> Result<String, SessionError> {
+        Ok(export_html(&self.load(name)?))
+    }
+
+    /// Set the token/time budget of a saved session.
+    pub fn set_budget(&self, name: &str, budget: SessionBudget) -> Result<(), SessionError> {
+        let mut session = self.load(name)?;
+        session.meta.budget = budget;
+        self.write(name, &session)
+    }
+
+    /// Set a saved session's budget and what it has used so far, e.g. for
+    /// a conversation that was tracked before it was first saved.
+    pub fn record_budget(
+        &self,
+        name: &str,
+        budget: SessionBudget,
+        usage: SessionUsage,
+    ) -> Result<(), SessionError> {
+        let mut session = self.load(name)?;
+        session.meta.budget = budget;
+        session.meta.usage = usage;
+        self.write(name, &session)
+    }
+
+    /// Set the pinned messages of a saved session, e.g. after its
+    /// conversation pinned some since the last save.
+    pub fn record_pins(&self, name: &str, pinned: &BTreeSet<usize>) -> Result<(), SessionError> {
+        let mut session = self.load(name)?;
+        let len = session.messages.len();
+        session.pinned = pinned.iter().copied().filter(|&i| i < len).collect();
+        self.write(name, &session)
+    }
+
+    /// Replace the tool toggles of a saved session.
+    pub fn record_tools(&self, name: &str, tools: &ToolToggles) -> Result<(), SessionError> {
+        let mut session = self.load(name)?;
+        session.tools = tools.clone();
+        self.write(name, &session)
+    }
+
+    /// Set the description of a saved session.
+    pub fn set_description(&self, name: &str, description: &str) -> Result<(), SessionError> {
+        let mut session = self.load(name)?;
+        session.meta.description = Some(description.to_string());
+        self.write(name, &session)
+    }
+
+    /// Tag a saved session. Returns `false` if it already had the tag.
+    pub fn add_tag(&self, name: &str, tag: &str) -> Result<bool, SessionError> {
+        let mut session = self.load(name)?;
+        let added = session.meta.add_tag(tag)?;
+        if added {
+            self.write(name, &session)?;
+        }
+        Ok(added)
+    }
+
+    /// Untag a saved session. Returns `false` if it didn't have the tag.
+    pub fn remove_tag(&self, name: &str, tag: &str) -> Result<bool, SessionError> {
+        let mut session = self.load(name)?;
+        let removed = session.meta.remove_tag(tag);
+        if removed {
+            self.write(name, &session)?;
+        }
+        Ok(removed)
+    }
+
+    /// Pin or unpin a saved session, so cleanup keeps it. Returns `false`
+    /// if it was already in that state.
+    pub fn set_pinned(&self, name: &str, pinned: bool) -> Result<bool, SessionError> {
+        let mut session = self.load(name)?;
+        if session.meta.pinned == pinned {
+            return Ok(false);
+        }
+        session.meta.pinned = pinned;
+        self.write(name, &session)?;
+        Ok(true)
+    }
+
+    /// Sessions with `tag`, most recent first.
+    pub fn list_by_tag(&self, tag: &str) -> Result<Vec<SessionMeta>, SessionError> {
+        let mut sessions = self.list()?;
+        sessions.retain(|meta| meta.has_tag(tag));
+        Ok(sessions)
+    }
+
+    /// Every tag in use, sorted.
+    pub fn tags(&self) -> Result<Vec<String>, SessionError> {
+        let tags: BTreeSet<String> = self
+            .list()?
+            .into_iter()
+            .flat_map(|meta| meta.tags)
+            .collect();
+        Ok(tags.into_iter().collect())
+    }
+
+    /// Add a run's usage to a saved session.
+    pub fn record_usage(
+        &self,
+        name: &str,
+        usage: SessionUsage,
+    ) -> Result<SessionMeta, SessionError> {
+        let mut session = self.load(name)?;
+        session.meta.usage.add(usage);
+        self.write(name, &session)?;
+        Ok(session.meta)
+    }
+
+    fn write(&self, name: &str, session: &SessionData) -> Result<(), SessionError> {
+        let content = serde_json::to_string_pretty(session)?;
+        write_atomic(&self.session_path(name), content)?;
+        Ok(())
+    }
+
     /// List all sessions.
     pub fn list(&self) -> Result<Vec<SessionMeta>, SessionError> {
         self.ensure_dir()?;
@@ -284,6 +705,14 @@ impl SessionManager {
         Ok(sessions)
     }
 
+    /// Load the most recently updated session, if there is one.
+    pub fn latest(&self) -> Result<Option<SessionData>, SessionError> {
+        match self.list()?.first() {
+            Some(meta) => self.load(&meta.name).map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// Delete a session.
     pub fn delete(&self, name: &str) -> Result<(), SessionError> {
         Self::validate_name(name)?;
@@ -325,7 +754,8 @@ impl SessionManager {
         format!("{}-{}", base_name, rand_suffix())
     }
 
-    /// Cleanup old sessions beyond the limit.
+    /// Cleanup old sessions beyond the limit. Pinned sessions are never
+    /// deleted and don't count toward it.
     fn cleanup(&self) -> Result<(), SessionError> {
         if self.max_sessions == 0 {
             return Ok(()); // Unlimited
@@ -333,11 +763,13 @@ impl SessionManager {
 
         let sessions = self.list()?;
 
-        if sessions.len() > self.max_sessions {
-            // Delete oldest sessions
-            for session in sessions.iter().skip(self.max_sessions) {
-                let _ = self.delete(&session.name);
-            }
+        // Delete oldest sessions
+        for session in sessions
+            .iter()
+            .filter(|meta| !meta.pinned)
+            .skip(self.max_sessions)
+        {
+            let _ = self.delete(&session.name);
         }
 
         Ok(())
@@ -584,6 +1016,177 @@ mod tests {
         assert_eq!(data.messages.len(), deserialized.messages.len());
     }
 
+    // =========================================================================
+    // Pinned Message Tests
+    // =========================================================================
+
+    #[test]
+    fn test_pin_command_parse() {
+        assert_eq!(PinCommand::parse("/pin 3"), Some(PinCommand::Pin(3)));
+        assert_eq!(PinCommand::parse("  /unpin 0 "), Some(PinCommand::Unpin(0)));
+        assert_eq!(PinCommand::parse("/pin"), None);
+        assert_eq!(PinCommand::parse("/pin x"), None);
+        assert_eq!(PinCommand::parse("/pin 1 2"), None);
+        assert_eq!(PinCommand::parse("pin 1"), None);
+    }
+
+    #[test]
+    fn test_session_data_pin_and_unpin() {
+        let mut data = SessionData::new("test", "agent", "model");
+        data.update(create_test_messages(3));
+
+        assert!(data.pin(1).unwrap());
+        assert!(!data.pin(1).unwrap());
+        assert!(matches!(
+            data.pin(3),
+            Err(SessionError::InvalidIndex { index: 3, len: 3 })
+        ));
+        assert!(data.unpin(1));
+        assert!(!data.unpin(1));
+    }
+
+    #[test]
+    fn test_pinned_messages_survive_aggressive_compaction() {
+        let mut data = SessionData::new("test", "agent", "model");
+        let messages = create_test_messages(20);
+        let requirement = serde_json::to_string(&messages[2]).unwrap();
+        data.update(messages);
+        data.pin(2).unwrap();
+
+        data.compact(CompactionStrategy::Truncate { max_tokens: 0 });
+
+        assert_eq!(data.messages.len(), 1);
+        assert_eq!(
+            serde_json::to_string(&data.messages[0]).unwrap(),
+            requirement
+        );
+        assert_eq!(data.pinned, BTreeSet::from([0]));
+    }
+
+    #[test]
+    fn test_update_drops_out_of_range_pins() {
+        let mut data = SessionData::new("test", "agent", "model");
+        data.update(create_test_messages(5));
+        data.pin(1).unwrap();
+        data.pin(4).unwrap();
+
+        data.update(create_test_messages(2));
+        assert_eq!(data.pinned, BTreeSet::from([1]));
+    }
+
+    #[test]
+    fn test_manager_pin_persists_with_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path());
+        manager
+            .save("pinned", &create_test_messages(4), "agent", "model")
+            .unwrap();
+
+        let status = manager
+            .apply_pin_command("pinned", PinCommand::Pin(2))
+            .unwrap();
+        assert!(status.contains("Pinned"));
+        assert_eq!(manager.load("pinned").unwrap().pinned, BTreeSet::from([2]));
+
+        // Re-saving the same history keeps the pin
+        manager
+            .save("pinned", &create_test_messages(4), "agent", "model")
+            .unwrap();
+        assert_eq!(manager.load("pinned").unwrap().pinned, BTreeSet::from([2]));
+
+        // Pins made in a conversation replace the saved ones
+        manager
+            .record_pins("pinned", &BTreeSet::from([0, 3, 9]))
+            .unwrap();
+        assert_eq!(
+            manager.load("pinned").unwrap().pinned,
+            BTreeSet::from([0, 3])
+        );
+    }
+
+    #[test]
+    fn test_record_tools_survives_save() {
+        let (_temp, manager) = setup_test_manager();
+        manager
+            .save("tools", &create_test_messages(2), "agent", "model")
+            .unwrap();
+
+        let mut tools = ToolToggles::default();
+        tools.set_enabled("agent", "run_shell_command", false);
+        manager.record_tools("tools", &tools).unwrap();
+
+        // Autosaving the next turn keeps the toggles
+        manager
+            .save("tools", &create_test_messages(4), "agent", "model")
+            .unwrap();
+        assert_eq!(manager.load("tools").unwrap().tools, tools);
+    }
+
+    // =========================================================================
+    // Budget Tests
+    // =========================================================================
+
+    #[test]
+    fn test_session_meta_budget_defaults_to_unlimited() {
+        let meta = SessionMeta::new("s", "stockpot", "gpt-4o");
+        assert!(meta.budget.is_unlimited());
+        assert_eq!(meta.usage, SessionUsage::default());
+
+        // Older session files have neither field
+        let json = serde_json::to_value(&meta).unwrap();
+        assert!(json.get("budget").is_none());
+        let mut json = json;
+        json.as_object_mut().unwrap().remove("usage");
+        let meta: SessionMeta = serde_json::from_value(json).unwrap();
+        assert_eq!(meta.usage, SessionUsage::default());
+    }
+
+    #[test]
+    fn test_session_usage_accumulates_until_budget_halts() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path());
+        manager
+            .save("budgeted", &create_test_messages(1), "stockpot", "gpt-4o")
+            .unwrap();
+        manager
+            .set_budget(
+                "budgeted",
+                SessionBudget {
+                    max_tokens: Some(1_000),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        for _ in 0..2 {
+            let meta = manager.load("budgeted").unwrap().meta;
+            let tracker = meta.budget_tracker();
+            assert!(tracker.check().is_ok());
+            tracker.record_tokens(600);
+            manager
+                .record_usage("budgeted", tracker.run_usage())
+                .unwrap();
+        }
+
+        let meta = manager.load("budgeted").unwrap().meta;
+        assert_eq!(meta.usage.tokens, 1_200);
+        assert!(matches!(
+            meta.budget_tracker().check(),
+            Err(BudgetExceeded::Tokens {
+                used: 1_200,
+                limit: 1_000
+            })
+        ));
+    }
+
+    #[test]
+    fn test_record_usage_missing_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path());
+        let result = manager.record_usage("nope", SessionUsage::default());
+        assert!(matches!(result, Err(SessionError::NotFound(_))));
+    }
+
     // =========================================================================
     // SessionManager Validation Tests
     // =========================================================================
@@ -657,6 +1260,37 @@ mod tests {
         assert!(manager.sessions_dir().ends_with("sessions"));
     }
 
+    #[test]
+    fn test_resolve_sessions_dir_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let nested = temp_dir.path().join("repo/src");
+        fs::create_dir_all(&nested).unwrap();
+        let home_default = dirs::home_dir().unwrap().join(".stockpot/sessions");
+
+        assert_eq!(
+            resolve_sessions_dir(None, Some(&nested), None),
+            home_default
+        );
+        assert_eq!(
+            resolve_sessions_dir(None, Some(&nested), Some("/data/sessions")),
+            PathBuf::from("/data/sessions")
+        );
+
+        // A project directory beats the setting
+        let project = temp_dir.path().join("repo").join(PROJECT_SESSIONS_DIR);
+        fs::create_dir_all(&project).unwrap();
+        assert_eq!(
+            resolve_sessions_dir(None, Some(&nested), Some("/data/sessions")),
+            project
+        );
+
+        // The environment beats everything, with ~ expanded
+        assert_eq!(
+            resolve_sessions_dir(Some("~/chats"), Some(&nested), Some("/data/sessions")),
+            dirs::home_dir().unwrap().join("chats")
+        );
+    }
+
     #[test]
     fn test_session_manager_with_dir() {
         let temp_dir = TempDir::new().unwrap();
@@ -819,6 +1453,22 @@ mod tests {
         assert_eq!(sessions[1].name, "old");
     }
 
+    #[test]
+    fn test_session_manager_latest() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path());
+        assert!(manager.latest().unwrap().is_none());
+
+        manager.save("old", &[], "agent", "model").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        manager.save("new", &[], "reviewer", "other-model").unwrap();
+
+        let latest = manager.latest().unwrap().unwrap();
+        assert_eq!(latest.meta.name, "new");
+        assert_eq!(latest.meta.agent, "reviewer");
+        assert_eq!(latest.meta.model, "other-model");
+    }
+
     #[test]
     fn test_session_manager_list_skips_meta_files() {
         let temp_dir = TempDir::new().unwrap();
@@ -925,6 +1575,86 @@ mod tests {
         assert!(!manager.exists(""));
     }
 
+    #[test]
+    fn test_fork_copies_session_and_records_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path());
+        manager
+            .save("main", &create_test_messages(4), "agent", "model")
+            .unwrap();
+
+        let meta = manager.fork("main", "experiment").unwrap();
+        assert_eq!(meta.name, "experiment");
+        assert_eq!(meta.forked_from.as_deref(), Some("main"));
+
+        let fork = manager.load("experiment").unwrap();
+        assert_eq!(fork.messages.len(), 4);
+        assert_eq!(fork.meta.agent, "agent");
+
+        // Continuing the fork leaves the original alone
+        manager
+            .save("experiment", &create_test_messages(6), "agent", "model")
+            .unwrap();
+        assert_eq!(manager.load("main").unwrap().messages.len(), 4);
+        assert!(manager.load("main").unwrap().meta.forked_from.is_none());
+
+        assert!(matches!(
+            manager.fork("main", "experiment"),
+            Err(SessionError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            manager.fork("missing", "other"),
+            Err(SessionError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_tags_filter_listing() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path());
+        manager.save("ui", &[], "agent", "model").unwrap();
+        manager.save("deploy", &[], "agent", "model").unwrap();
+
+        assert!(manager.add_tag("ui", "Frontend").unwrap());
+        assert!(!manager.add_tag("ui", "frontend").unwrap());
+        manager.add_tag("deploy", "infra").unwrap();
+        manager.add_tag("ui", "infra").unwrap();
+        assert!(matches!(
+            manager.add_tag("ui", "two words"),
+            Err(SessionError::InvalidTag(_))
+        ));
+
+        let names = |metas: Vec<SessionMeta>| {
+            let mut names: Vec<String> = metas.into_iter().map(|m| m.name).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(manager.list_by_tag("FRONTEND").unwrap()), ["ui"]);
+        assert_eq!(
+            names(manager.list_by_tag("infra").unwrap()),
+            ["deploy", "ui"]
+        );
+        assert_eq!(manager.tags().unwrap(), ["frontend", "infra"]);
+
+        // Tags survive saving new messages
+        manager
+            .save("ui", &create_test_messages(2), "agent", "model")
+            .unwrap();
+        assert_eq!(manager.load("ui").unwrap().meta.tags, ["frontend", "infra"]);
+
+        assert!(manager.remove_tag("ui", "infra").unwrap());
+        assert!(!manager.remove_tag("ui", "infra").unwrap());
+        assert_eq!(names(manager.list_by_tag("infra").unwrap()), ["deploy"]);
+    }
+
+    #[test]
+    fn test_meta_without_tags_still_loads() {
+        let json = r#"{"name":"old","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","message_count":0,"token_estimate":0,"agent":"a","model":"m"}"#;
+        let meta: SessionMeta = serde_json::from_str(json).unwrap();
+        assert!(meta.tags.is_empty());
+        assert!(!serde_json::to_string(&meta).unwrap().contains("tags"));
+    }
+
     // =========================================================================
     // SessionManager Generate Name Tests
     // =========================================================================
@@ -995,6 +1725,33 @@ mod tests {
         assert!(names.contains(&"session-3"));
     }
 
+    #[test]
+    fn test_cleanup_keeps_pinned_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path()).with_max_sessions(2);
+
+        manager.save("important", &[], "agent", "model").unwrap();
+        assert!(manager.set_pinned("important", true).unwrap());
+        assert!(!manager.set_pinned("important", true).unwrap());
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        for i in 1..=4 {
+            manager
+                .save(&format!("session-{}", i), &[], "agent", "model")
+                .unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        // The pinned session is the oldest but doesn't use up the limit
+        let mut names: Vec<_> = manager
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["important", "session-3", "session-4"]);
+    }
+
     #[test]
     fn test_cleanup_unlimited_sessions() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,284 @@
+//! Salvaging what's readable from a damaged session file.
+//!
+//! A session file is one JSON object: `meta`, then `messages`, then
+//! `pinned`. When it no longer parses (cut off by a crash, or hand-edited),
+//! each part is read on its own and the message array entry by entry, so
+//! one bad message, or a missing tail, only loses that much.
+
+use std::collections::BTreeSet;
+use std::fs;
+
+use serde::Deserialize;
+use serde_json::{Deserializer, Value as JsonValue};
+use serdes_ai_core::ModelRequest;
+
+use super::{SessionData, SessionError, SessionManager, SessionMeta, ToolToggles};
+
+/// A session read leniently, and what couldn't be read.
+#[derive(Debug, Clone)]
+pub struct RecoveredSession {
+    pub data: SessionData,
+    /// One line per entry that was dropped or rebuilt.
+    pub dropped: Vec<String>,
+}
+
+impl SessionManager {
+    /// Load a session, salvaging what parses if the file is damaged.
+    ///
+    /// An intact file loads as [`SessionManager::load`] would, with nothing
+    /// dropped. Otherwise unreadable messages are skipped, and metadata that
+    /// can't be read is rebuilt from the legacy `{name}_meta.json` if there
+    /// is one, else from scratch.
+    pub fn load_lenient(&self, name: &str) -> Result<RecoveredSession, SessionError> {
+        match self.load(name) {
+            Ok(data) => Ok(RecoveredSession {
+                data,
+                dropped: Vec::new(),
+            }),
+            Err(SessionError::Serialization(_)) => {
+                let content = fs::read_to_string(self.session_path(name))?;
+                let legacy_meta = fs::read_to_string(self.session_path(&format!("{}_meta", name)))
+                    .ok()
+                    .and_then(|meta| serde_json::from_str(&meta).ok());
+                Ok(salvage(name, &content, legacy_meta))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Save a recovered session under `name`, leaving the damaged file as
+    /// it is. Fails if `name` is taken.
+    pub fn save_recovered(
+        &self,
+        name: &str,
+        recovered: &RecoveredSession,
+    ) -> Result<SessionMeta, SessionError> {
+        Self::validate_name(name)?;
+        if self.session_path(name).exists() {
+            return Err(SessionError::AlreadyExists(name.to_string()));
+        }
+        let mut data = recovered.data.clone();
+        data.meta.name = name.to_string();
+        self.write(name, &data)?;
+        Ok(data.meta)
+    }
+}
+
+/// Read what can be read from the text of a session file.
+fn salvage(name: &str, content: &str, legacy_meta: Option<SessionMeta>) -> RecoveredSession {
+    let mut dropped = Vec::new();
+
+    let mut cursor = 0;
+    let meta = match find_key(content, 0, "meta").map(|at| read_value(content, at)) {
+        Some(Some((value, end))) => {
+            cursor = end;
+            serde_json::from_value::<SessionMeta>(value).map_err(|e| e.to_string())
+        }
+        Some(None) => Err("unreadable".to_string()),
+        None => Err("missing".to_string()),
+    };
+    let mut meta = match (meta, legacy_meta) {
+        (Ok(meta), _) => meta,
+        (Err(reason), Some(legacy)) => {
+            dropped.push(format!(
+                "metadata ({}), restored from {}_meta.json",
+                reason, name
+            ));
+            legacy
+        }
+        (Err(reason), None) => {
+            dropped.push(format!(
+                "metadata ({}), rebuilt with unknown agent and model",
+                reason
+            ));
+            SessionMeta::new(name, "unknown", "unknown")
+        }
+    };
+
+    let mut messages = Vec::new();
+    let mut salvaged_indices = Vec::new();
+    let mut pins_at = None;
+    let mut tools_at = None;
+    match find_key(content, cursor, "messages") {
+        Some(at) => {
+            let (entries, end) = read_array(content, at);
+            for (index, entry) in entries.into_iter().enumerate() {
+                match serde_json::from_value::<ModelRequest>(entry) {
+                    Ok(message) => {
+                        salvaged_indices.push(index);
+                        messages.push(message);
+                    }
+                    Err(e) => dropped.push(format!("message {} ({})", index, e)),
+                }
+            }
+            match end {
+                Ok(end) => {
+                    pins_at = find_key(content, end, "pinned");
+                    tools_at = find_key(content, end, "tools");
+                }
+                Err((count, reason)) => {
+                    dropped.push(format!("everything after message {} ({})", count, reason))
+                }
+            }
+        }
+        None => dropped.push("messages (missing)".to_string()),
+    }
+
+    // Pins are by original index; keep those whose message survived
+    let pinned: BTreeSet<usize> = pins_at
+        .and_then(|at| read_value(content, at))
+        .and_then(|(value, _)| BTreeSet::<usize>::deserialize(value).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|old| salvaged_indices.iter().position(|&kept| kept == old))
+        .collect();
+
+    let tools = tools_at
+        .and_then(|at| read_value(content, at))
+        .and_then(|(value, _)| ToolToggles::deserialize(value).ok())
+        .unwrap_or_default();
+
+    meta.update(&messages);
+    RecoveredSession {
+        data: SessionData {
+            meta,
+            messages,
+            pinned,
+            tools,
+        },
+        dropped,
+    }
+}
+
+/// Byte offset just past `"key":`, searching from `from`.
+fn find_key(content: &str, from: usize, key: &str) -> Option<usize> {
+    let needle = format!("\"{}\"", key);
+    let mut search = from;
+    while let Some(found) = content.get(search..)?.find(&needle) {
+        let after = search + found + needle.len();
+        let rest = &content[after..];
+        let trimmed = rest.trim_start();
+        if let Some(value) = trimmed.strip_prefix(':') {
+            return Some(content.len() - value.len());
+        }
+        search = after;
+    }
+    None
+}
+
+/// The JSON value starting at `at` (after whitespace) and the offset
+/// just past it.
+fn read_value(content: &str, at: usize) -> Option<(JsonValue, usize)> {
+    let mut stream = Deserializer::from_str(&content[at..]).into_iter::<JsonValue>();
+    let value = stream.next()?.ok()?;
+    Some((value, at + stream.byte_offset()))
+}
+
+/// The entries of the JSON array starting at `at`, read one at a time,
+/// and the offset just past the array.
+///
+/// Stops at the first entry that isn't valid JSON, returning how many were
+/// read before it and why instead of an offset.
+fn read_array(content: &str, at: usize) -> (Vec<JsonValue>, Result<usize, (usize, String)>) {
+    let mut entries = Vec::new();
+    let rest = content[at..].trim_start();
+    let Some(rest) = rest.strip_prefix('[') else {
+        return (entries, Err((0, "not a list".to_string())));
+    };
+    let mut pos = content.len() - rest.len();
+
+    loop {
+        let rest = content[pos..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        pos = content.len() - rest.len();
+        if rest.starts_with(']') {
+            return (entries, Ok(pos + 1));
+        }
+        match read_value(content, pos) {
+            Some((value, end)) => {
+                entries.push(value);
+                pos = end;
+            }
+            None => {
+                let count = entries.len();
+                return (
+                    entries,
+                    Err((count, "file is cut off or garbled".to_string())),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn prompt(text: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.add_user_prompt(text.to_string());
+        request
+    }
+
+    #[test]
+    fn test_truncated_file_keeps_complete_messages() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path());
+        let messages: Vec<_> = (0..5).map(|i| prompt(&format!("turn {}", i))).collect();
+        manager.save("long", &messages, "agent", "model").unwrap();
+        manager
+            .apply_pin_command("long", super::super::PinCommand::Pin(1))
+            .unwrap();
+
+        // Cut the file off partway through the fourth message
+        let path = manager.session_path("long");
+        let content = fs::read_to_string(&path).unwrap();
+        let cut = content.find("turn 3").unwrap();
+        fs::write(&path, &content[..cut]).unwrap();
+        assert!(manager.load("long").is_err());
+
+        let recovered = manager.load_lenient("long").unwrap();
+        assert_eq!(recovered.data.messages.len(), 3);
+        assert_eq!(recovered.data.meta.agent, "agent");
+        assert_eq!(recovered.data.meta.message_count, 3);
+        assert_eq!(recovered.dropped.len(), 1);
+        assert!(recovered.dropped[0].starts_with("everything after message 3"));
+
+        let meta = manager
+            .save_recovered("long-recovered", &recovered)
+            .unwrap();
+        assert_eq!(meta.name, "long-recovered");
+        assert_eq!(manager.load("long-recovered").unwrap().messages.len(), 3);
+    }
+
+    #[test]
+    fn test_bad_entries_and_meta_are_dropped() {
+        let good = serde_json::to_string(&prompt("kept")).unwrap();
+        let content = format!(
+            r#"{{"meta": {{"name": 5}}, "messages": [{}, "not a message", {}], "pinned": [2]}}"#,
+            good, good
+        );
+
+        let recovered = salvage("broken", &content, None);
+        assert_eq!(recovered.data.messages.len(), 2);
+        assert_eq!(recovered.data.meta.name, "broken");
+        // The pin follows message 2, now at index 1
+        assert_eq!(recovered.data.pinned, BTreeSet::from([1]));
+        assert_eq!(recovered.dropped.len(), 2);
+        assert!(recovered.dropped[0].starts_with("metadata"));
+        assert!(recovered.dropped[1].starts_with("message 1"));
+    }
+
+    #[test]
+    fn test_intact_file_drops_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path());
+        manager
+            .save("fine", &[prompt("hello")], "agent", "model")
+            .unwrap();
+
+        let recovered = manager.load_lenient("fine").unwrap();
+        assert!(recovered.dropped.is_empty());
+        assert_eq!(recovered.data.messages.len(), 1);
+    }
+}
@@ -0,0 +1,139 @@
+//! Taking back the last prompt of a conversation (`/edit`).
+//!
+//! Each prompt starts a new request in the history, followed by the model's
+//! responses and tool returns for it. Rewinding drops that request and
+//! everything after it, and hands back the prompt's text so it can be
+//! revised and sent again.
+
+use serde_json::Value as JsonValue;
+use serdes_ai_core::{ModelRequest, ModelRequestPart};
+
+use super::export::mentions_system;
+
+/// Drop the last user prompt and the replies to it from `history`.
+///
+/// Returns the prompt's text, or `None` (leaving `history` untouched) when
+/// there is no user prompt to take back. Images sent with the prompt are
+/// not part of the returned text.
+pub fn rewind_last_prompt(history: &mut Vec<ModelRequest>) -> Option<String> {
+    let index = history
+        .iter()
+        .rposition(|request| prompt_text(request).is_some())?;
+    let text = prompt_text(&history[index]);
+    history.truncate(index);
+    text
+}
+
+/// Text of the user prompt in a request, if it has one.
+pub(super) fn prompt_text(request: &ModelRequest) -> Option<String> {
+    let mut texts = Vec::new();
+    let mut found = false;
+    for part in &request.parts {
+        if matches!(
+            part,
+            ModelRequestPart::ModelResponse(_) | ModelRequestPart::ToolReturn(_)
+        ) {
+            continue;
+        }
+        let value = serde_json::to_value(part).unwrap_or_default();
+        if mentions_system(&value) || mentions_retry(&value) {
+            continue;
+        }
+        found = true;
+        collect_text(&value, &mut texts);
+    }
+    found.then(|| texts.join("\n\n"))
+}
+
+/// Whether a serialized part is a retry prompt sent back to the model.
+fn mentions_retry(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(map) => map.iter().any(|(key, value)| {
+            key.to_lowercase().contains("retry")
+                || value
+                    .as_str()
+                    .is_some_and(|s| matches!(s, "retry" | "retry-prompt" | "retry_prompt"))
+        }),
+        _ => false,
+    }
+}
+
+/// Pull the text out of a serialized prompt part, skipping binary content.
+fn collect_text(value: &JsonValue, texts: &mut Vec<String>) {
+    match value {
+        JsonValue::String(text) => texts.push(text.clone()),
+        JsonValue::Array(items) => {
+            for item in items {
+                collect_text(item, texts);
+            }
+        }
+        JsonValue::Object(map) => {
+            for key in ["content", "text", "parts"] {
+                if let Some(inner) = map.get(key) {
+                    collect_text(inner, texts);
+                    return;
+                }
+            }
+            // Externally tagged enums wrap the part in a single-key object
+            if map.len() == 1 {
+                if let Some(inner) = map.values().next() {
+                    collect_text(inner, texts);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serdes_ai_core::{ModelResponse, ModelResponsePart, TextPart};
+
+    fn prompt(text: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.add_user_prompt(text.to_string());
+        request
+    }
+
+    fn reply(text: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.parts.push(ModelRequestPart::ModelResponse(Box::new(
+            ModelResponse::with_parts(vec![ModelResponsePart::Text(TextPart::new(
+                text.to_string(),
+            ))]),
+        )));
+        request
+    }
+
+    #[test]
+    fn test_rewind_drops_last_prompt_and_reply() {
+        let mut history = vec![
+            prompt("first question"),
+            reply("first answer"),
+            prompt("second question"),
+            reply("second answer"),
+        ];
+
+        assert_eq!(
+            rewind_last_prompt(&mut history).as_deref(),
+            Some("second question")
+        );
+        assert_eq!(history.len(), 2);
+
+        assert_eq!(
+            rewind_last_prompt(&mut history).as_deref(),
+            Some("first question")
+        );
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn test_rewind_without_prompt_leaves_history_alone() {
+        assert_eq!(rewind_last_prompt(&mut Vec::new()), None);
+
+        let mut history = vec![reply("orphaned answer")];
+        assert_eq!(rewind_last_prompt(&mut history), None);
+        assert_eq!(history.len(), 1);
+    }
+}
@@ -0,0 +1,233 @@
+//! Finding saved sessions by what was said in them.
+
+use std::fmt;
+use std::fs;
+
+use chrono::{DateTime, Utc};
+
+use super::export::message_text;
+use super::{format_relative_time, SessionData, SessionManager};
+
+/// Most sessions a search returns.
+pub const MAX_SEARCH_RESULTS: usize = 20;
+
+/// Characters of context shown on each side of a match.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// A session whose messages contain the query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionSearchHit {
+    pub name: String,
+    pub updated_at: DateTime<Utc>,
+    /// Times the query appears across the session's messages.
+    pub matches: usize,
+    /// The first match with some text around it, on one line.
+    pub snippet: String,
+}
+
+impl fmt::Display for SessionSearchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} match{}, {}): {}",
+            self.name,
+            self.matches,
+            if self.matches == 1 { "" } else { "es" },
+            format_relative_time(self.updated_at),
+            self.snippet
+        )
+    }
+}
+
+impl SessionManager {
+    /// Sessions whose messages contain `query`, ignoring case.
+    ///
+    /// Session files are read one at a time, most recently modified first,
+    /// and the search stops after [`MAX_SEARCH_RESULTS`] hits.
+    pub fn search(&self, query: &str) -> Vec<SessionSearchHit> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let Ok(entries) = fs::read_dir(self.sessions_dir()) else {
+            return Vec::new();
+        };
+
+        let mut files: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension().is_some_and(|e| e == "json")
+                    && !path
+                        .file_stem()
+                        .is_some_and(|stem| stem.to_string_lossy().ends_with("_meta"))
+            })
+            .filter_map(|path| {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+                Some((modified, path))
+            })
+            .collect();
+        files.sort_by(|a, b| b.0.cmp(&a.0));
+
+        files
+            .into_iter()
+            .filter_map(|(_, path)| {
+                let content = fs::read_to_string(path).ok()?;
+                let session: SessionData = serde_json::from_str(&content).ok()?;
+                search_session(&session, &query)
+            })
+            .take(MAX_SEARCH_RESULTS)
+            .collect()
+    }
+}
+
+/// Match a lowercased query against one session.
+fn search_session(session: &SessionData, query: &str) -> Option<SessionSearchHit> {
+    let mut matches = 0;
+    let mut snippet = None;
+    for text in session.messages.iter().flat_map(message_text) {
+        let lower = text.to_lowercase();
+        let count = lower.matches(query).count();
+        if count == 0 {
+            continue;
+        }
+        matches += count;
+        if snippet.is_none() {
+            snippet = Some(make_snippet(&text, query));
+        }
+    }
+
+    Some(SessionSearchHit {
+        name: session.meta.name.clone(),
+        updated_at: session.meta.updated_at,
+        matches,
+        snippet: snippet?,
+    })
+}
+
+/// The first match of the lowercased `query` in `text` with
+/// [`SNIPPET_CONTEXT`] characters either side, whitespace collapsed.
+///
+/// Lowercasing can change a character's byte length, so `text` is
+/// lowercased one character at a time and the match mapped back to the
+/// characters it came from.
+fn make_snippet(text: &str, query: &str) -> String {
+    if query.is_empty() {
+        return String::new();
+    }
+    let mut lower = String::new();
+    // Where each character of `text` starts in `lower`, and its bytes in `text`
+    let mut origins = Vec::new();
+    for (i, c) in text.char_indices() {
+        origins.push((lower.len(), i..i + c.len_utf8()));
+        lower.extend(c.to_lowercase());
+    }
+    let Some(start) = lower.find(query) else {
+        // Matched only in context, like a final sigma: cut from the
+        // lowercased text instead
+        let lower = text.to_lowercase();
+        return lower.find(query).map_or_else(String::new, |start| {
+            snippet_around(&lower, start, start + query.len())
+        });
+    };
+    let origin = |offset: usize| {
+        let char_index = origins.partition_point(|(from, _)| *from <= offset) - 1;
+        origins[char_index].1.clone()
+    };
+    snippet_around(
+        text,
+        origin(start).start,
+        origin(start + query.len() - 1).end,
+    )
+}
+
+/// `source[start..end]` with [`SNIPPET_CONTEXT`] characters either side,
+/// whitespace collapsed.
+fn snippet_around(source: &str, start: usize, end: usize) -> String {
+    let before: Vec<char> = source[..start].chars().collect();
+    let after: Vec<char> = source[end..].chars().collect();
+    let from = before.len().saturating_sub(SNIPPET_CONTEXT);
+    let to = after.len().min(SNIPPET_CONTEXT);
+
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(&before[from..]);
+    snippet.push_str(&source[start..end]);
+    snippet.extend(&after[..to]);
+    if to < after.len() {
+        snippet.push('…');
+    }
+    snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serdes_ai_core::ModelRequest;
+    use tempfile::TempDir;
+
+    fn prompt(text: &str) -> ModelRequest {
+        let mut request = ModelRequest::new();
+        request.add_user_prompt(text.to_string());
+        request
+    }
+
+    #[test]
+    fn test_search_finds_sessions_by_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_dir(temp_dir.path());
+        manager
+            .save(
+                "parser-bug",
+                &[
+                    prompt("The tokenizer panics on a trailing backslash"),
+                    prompt("Still PANICS after the fix"),
+                ],
+                "agent",
+                "model",
+            )
+            .unwrap();
+        manager
+            .save("unrelated", &[prompt("Write a haiku")], "agent", "model")
+            .unwrap();
+
+        let hits = manager.search("panics");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].name, "parser-bug");
+        assert_eq!(hits[0].matches, 2);
+        assert_eq!(
+            hits[0].snippet,
+            "The tokenizer panics on a trailing backslash"
+        );
+
+        assert!(manager.search("parser").is_empty(), "names aren't searched");
+        assert!(manager.search("  ").is_empty());
+    }
+
+    #[test]
+    fn test_snippet_trims_long_text() {
+        let text = format!("{} needle {}", "a".repeat(100), "b".repeat(100));
+        let snippet = make_snippet(&text, "needle");
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains(" needle "));
+        assert_eq!(
+            snippet.chars().count(),
+            2 * SNIPPET_CONTEXT + "needle".len() + 2
+        );
+    }
+
+    #[test]
+    fn test_snippet_maps_case_folded_matches_back() {
+        // 'İ' grows and the Kelvin sign shrinks when lowercased, leaving
+        // the total length unchanged but every boundary after them shifted
+        assert_eq!(make_snippet("İa\u{212A}", "a"), "İa\u{212A}");
+        assert_eq!(
+            make_snippet("İ\u{212A} MIXED case", "mixed"),
+            "İ\u{212A} MIXED case"
+        );
+        assert_eq!(make_snippet("ΟΔΟΣ", "οδος"), "οδος");
+        assert_eq!(make_snippet("nothing here", "needle"), "");
+    }
+}
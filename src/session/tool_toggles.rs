@@ -0,0 +1,173 @@
+//! Turning an agent's tools off and on for the session (`/tools`).
+//!
+//! Toggles are kept per agent and saved with the conversation's session,
+//! so resuming it brings them back; the agent's own tool list is left as
+//! it is.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+/// A `/tools` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolsCommand {
+    /// List the current agent's tools and whether each is on.
+    List,
+    /// Turn all tools off, for plain chat.
+    AllOff,
+    /// Turn tools back on, apart from ones disabled by name.
+    AllOn,
+    /// Turn one tool off.
+    Disable(String),
+    /// Turn one tool back on.
+    Enable(String),
+}
+
+impl ToolsCommand {
+    /// Parse the arguments after `/tools`.
+    pub fn parse(args: &str) -> Result<Self, String> {
+        let usage = || {
+            "Usage: /tools, /tools on, /tools off, /tools disable <tool>, /tools enable <tool>"
+                .to_string()
+        };
+        let mut parts = args.split_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => Ok(Self::List),
+            (Some("off"), None, _) => Ok(Self::AllOff),
+            (Some("on"), None, _) => Ok(Self::AllOn),
+            (Some("disable"), Some(tool), None) => Ok(Self::Disable(tool.to_string())),
+            (Some("enable"), Some(tool), None) => Ok(Self::Enable(tool.to_string())),
+            _ => Err(usage()),
+        }
+    }
+}
+
+/// Tools turned off by name, per agent, and whether all are off.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolToggles {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    disabled: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    all_off: bool,
+}
+
+impl ToolToggles {
+    /// Whether nothing is turned off.
+    pub fn is_empty(&self) -> bool {
+        self.disabled.is_empty() && !self.all_off
+    }
+
+    /// Whether all tools are off, for plain chat (`/tools off`).
+    pub fn all_off(&self) -> bool {
+        self.all_off
+    }
+
+    /// Turn all tools off, or back on apart from ones disabled by name.
+    pub fn set_all_off(&mut self, off: bool) {
+        self.all_off = off;
+    }
+
+    /// Turn `tool` off or on for `agent`. Returns whether that changed
+    /// anything.
+    pub fn set_enabled(&mut self, agent: &str, tool: &str, enabled: bool) -> bool {
+        if enabled {
+            let Some(tools) = self.disabled.get_mut(agent) else {
+                return false;
+            };
+            let removed = tools.remove(tool);
+            if tools.is_empty() {
+                self.disabled.remove(agent);
+            }
+            removed
+        } else {
+            self.disabled
+                .entry(agent.to_string())
+                .or_default()
+                .insert(tool.to_string())
+        }
+    }
+
+    /// Whether `tool` is on for `agent`.
+    pub fn is_enabled(&self, agent: &str, tool: &str) -> bool {
+        self.disabled
+            .get(agent)
+            .is_none_or(|tools| !tools.contains(tool))
+    }
+
+    /// The tools turned off for `agent`, sorted.
+    pub fn disabled(&self, agent: &str) -> Vec<String> {
+        self.disabled
+            .get(agent)
+            .map(|tools| tools.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// `tools` as a markdown list, marking the ones turned off for `agent`.
+    pub fn list(&self, agent: &str, tools: &[&str]) -> String {
+        if tools.is_empty() {
+            return format!("{} has no tools", agent);
+        }
+        let lines: Vec<String> = tools
+            .iter()
+            .map(|tool| {
+                if self.is_enabled(agent, tool) {
+                    format!("- {}", tool)
+                } else {
+                    format!("- {} (disabled)", tool)
+                }
+            })
+            .collect();
+        format!("Tools for {}:\n{}", agent, lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ToolsCommand::parse(""), Ok(ToolsCommand::List));
+        assert_eq!(ToolsCommand::parse(" off "), Ok(ToolsCommand::AllOff));
+        assert_eq!(
+            ToolsCommand::parse("disable run_shell_command"),
+            Ok(ToolsCommand::Disable("run_shell_command".to_string()))
+        );
+        assert!(ToolsCommand::parse("disable").is_err());
+        assert!(ToolsCommand::parse("enable a b").is_err());
+        assert!(ToolsCommand::parse("sideways").is_err());
+    }
+
+    #[test]
+    fn test_toggles_are_per_agent() {
+        let mut toggles = ToolToggles::default();
+        assert!(toggles.set_enabled("stockpot", "run_shell_command", false));
+        assert!(!toggles.set_enabled("stockpot", "run_shell_command", false));
+
+        assert!(!toggles.is_enabled("stockpot", "run_shell_command"));
+        assert!(toggles.is_enabled("reviewer", "run_shell_command"));
+        assert_eq!(toggles.disabled("stockpot"), vec!["run_shell_command"]);
+        assert!(toggles
+            .list("stockpot", &["read_file", "run_shell_command"])
+            .contains("- run_shell_command (disabled)"));
+
+        assert!(toggles.set_enabled("stockpot", "run_shell_command", true));
+        assert!(!toggles.set_enabled("stockpot", "run_shell_command", true));
+        assert!(toggles.disabled("stockpot").is_empty());
+    }
+
+    #[test]
+    fn test_toggles_round_trip() {
+        let mut toggles = ToolToggles::default();
+        assert!(toggles.is_empty());
+        assert_eq!(serde_json::to_string(&toggles).unwrap(), "{}");
+
+        toggles.set_enabled("stockpot", "github_search", false);
+        toggles.set_all_off(true);
+        let json = serde_json::to_string(&toggles).unwrap();
+        let restored: ToolToggles = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, toggles);
+        assert!(restored.all_off());
+        assert!(!restored.is_enabled("stockpot", "github_search"));
+    }
+}
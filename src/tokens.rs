@@ -3,21 +3,62 @@
 //! Provides rough token counting for messages to help users
 //! understand context usage and trigger compaction.
 
-use serdes_ai_core::ModelRequest;
+use std::collections::BTreeSet;
+
+use serdes_ai_core::messages::{UserContent, UserContentPart};
+use serdes_ai_core::{ModelRequest, ModelRequestPart};
+
+/// Tokens an image in a prompt is assumed to take.
+const IMAGE_TOKENS: usize = 1500;
+
+/// Fewest output tokens to ask for, however full the context window is.
+pub const MIN_COMPLETION_TOKENS: u64 = 1024;
 
 /// Rough token estimate for a collection of messages.
-/// Uses ~4 chars per token approximation based on JSON serialization.
+/// Uses ~4 chars per token, see [`estimate_message_tokens`].
 pub fn estimate_tokens(messages: &[ModelRequest]) -> usize {
     messages.iter().map(estimate_message_tokens).sum()
 }
 
-/// Estimate tokens for a single message.
+/// Estimate tokens for a single message, part by part.
+///
+/// User prompts go through [`estimate_content_tokens`], so an attached
+/// image counts as [`IMAGE_TOKENS`] rather than by its base64 size; other
+/// parts are estimated from their JSON.
 pub fn estimate_message_tokens(msg: &ModelRequest) -> usize {
-    // Serialize to JSON and estimate tokens from character count
-    // ~4 chars per token is a reasonable approximation
-    serde_json::to_string(msg)
-        .map(|s| (s.len() / 4).max(10))
-        .unwrap_or(25)
+    let tokens: usize = msg
+        .parts
+        .iter()
+        .map(|part| match part {
+            ModelRequestPart::UserPrompt(prompt) => estimate_content_tokens(&prompt.content),
+            part => serde_json::to_string(part)
+                .map(|s| estimate_text_tokens(&s))
+                .unwrap_or(25),
+        })
+        .sum();
+    tokens.max(10)
+}
+
+/// Rough token estimate for plain text, at ~4 chars per token.
+pub fn estimate_text_tokens(text: &str) -> usize {
+    text.len() / 4
+}
+
+/// Rough token estimate for a user prompt, counting each image as a
+/// fixed amount rather than by its encoded size.
+pub fn estimate_content_tokens(content: &UserContent) -> usize {
+    match content {
+        UserContent::Text(text) => estimate_text_tokens(text),
+        UserContent::Parts(parts) => parts
+            .iter()
+            .map(|part| match part {
+                UserContentPart::Image { .. } => IMAGE_TOKENS,
+                part => serde_json::to_string(part)
+                    .map(|s| estimate_text_tokens(&s))
+                    .unwrap_or(25),
+            })
+            .sum(),
+    }
 }
 
 /// Check if context usage exceeds a threshold.
@@ -32,6 +73,78 @@ pub fn should_compact(estimated_tokens: usize, context_length: usize, threshold:
     usage >= threshold
 }
 
+/// How [`compact_history`] chooses which unpinned messages to keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompactionStrategy {
+    /// Keep the newest messages that fit within `max_tokens`.
+    Truncate { max_tokens: usize },
+    /// Keep only the last `n` messages.
+    KeepRecent(usize),
+}
+
+/// A compacted history and its pinned messages, re-indexed.
+#[derive(Debug, Clone)]
+pub struct CompactedHistory {
+    pub messages: Vec<ModelRequest>,
+    /// Pinned indices, remapped to positions in `messages`.
+    pub pinned: BTreeSet<usize>,
+}
+
+/// Drop older messages from a history, always retaining pinned ones.
+///
+/// Pinned messages (by index) count toward the strategy's budget but are
+/// kept first, even when they alone exceed it. The remaining budget goes to
+/// the newest unpinned messages. Original ordering is preserved.
+pub fn compact_history(
+    messages: &[ModelRequest],
+    strategy: CompactionStrategy,
+    pinned: &BTreeSet<usize>,
+) -> CompactedHistory {
+    let pinned: BTreeSet<usize> = pinned
+        .iter()
+        .copied()
+        .filter(|&i| i < messages.len())
+        .collect();
+    let mut keep = pinned.clone();
+    let newest_unpinned = (0..messages.len()).rev().filter(|i| !pinned.contains(i));
+
+    match strategy {
+        CompactionStrategy::Truncate { max_tokens } => {
+            let pinned_tokens: usize = pinned
+                .iter()
+                .map(|&i| estimate_message_tokens(&messages[i]))
+                .sum();
+            let mut remaining = max_tokens.saturating_sub(pinned_tokens);
+            // Stop at the first message that doesn't fit, so no gaps appear
+            for i in newest_unpinned {
+                let cost = estimate_message_tokens(&messages[i]);
+                if cost > remaining {
+                    break;
+                }
+                remaining -= cost;
+                keep.insert(i);
+            }
+        }
+        CompactionStrategy::KeepRecent(n) => {
+            keep.extend(newest_unpinned.take(n.saturating_sub(pinned.len())));
+        }
+    }
+
+    let mut compacted = Vec::with_capacity(keep.len());
+    let mut remapped = BTreeSet::new();
+    for (new_index, &old_index) in keep.iter().enumerate() {
+        compacted.push(messages[old_index].clone());
+        if pinned.contains(&old_index) {
+            remapped.insert(new_index);
+        }
+    }
+
+    CompactedHistory {
+        messages: compacted,
+        pinned: remapped,
+    }
+}
+
 /// Calculate context usage as a percentage.
 pub fn usage_percent(estimated_tokens: usize, context_length: usize) -> f64 {
     if context_length == 0 {
@@ -40,6 +153,59 @@ pub fn usage_percent(estimated_tokens: usize, context_length: usize) -> f64 {
     (estimated_tokens as f64 / context_length as f64) * 100.0
 }
 
+/// How close context usage is to the model's limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageLevel {
+    Normal,
+    /// Over 60% of the context window.
+    Warning,
+    /// Over 80%, where compaction is close.
+    Critical,
+}
+
+/// Estimated tokens in a history against a model's context window,
+/// displayed as `[42%] ~53 760/128 000 tokens`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextUsage {
+    pub used: usize,
+    pub window: usize,
+}
+
+impl ContextUsage {
+    /// Usage of `messages` in a window of `window` tokens.
+    pub fn of(messages: &[ModelRequest], window: usize) -> Self {
+        Self {
+            used: estimate_tokens(messages),
+            window,
+        }
+    }
+
+    /// Share of the window in use, capped at 100.
+    pub fn percent(&self) -> f64 {
+        usage_percent(self.used, self.window).min(100.0)
+    }
+
+    pub fn level(&self) -> UsageLevel {
+        match self.percent() {
+            p if p > 80.0 => UsageLevel::Critical,
+            p if p > 60.0 => UsageLevel::Warning,
+            _ => UsageLevel::Normal,
+        }
+    }
+}
+
+impl std::fmt::Display for ContextUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{:.0}%] ~{}/{} tokens",
+            self.percent(),
+            format_tokens_with_separator(self.used),
+            format_tokens_with_separator(self.window)
+        )
+    }
+}
+
 /// Format a token count with space as thousands separator.
 ///
 /// Examples:
@@ -72,6 +238,33 @@ mod tests {
         assert_eq!(estimate_tokens(&messages), 0);
     }
 
+    #[test]
+    fn test_estimate_content_counts_images_flat() {
+        use serdes_ai_core::messages::ImageMediaType;
+
+        assert_eq!(estimate_content_tokens(&UserContent::text("abcdefgh")), 2);
+        let image = UserContentPart::image_binary(vec![0; 100_000], ImageMediaType::Png);
+        assert_eq!(
+            estimate_content_tokens(&UserContent::parts(vec![image])),
+            IMAGE_TOKENS
+        );
+    }
+
+    #[test]
+    fn test_estimate_message_counts_images_flat() {
+        use serdes_ai_core::messages::ImageMediaType;
+
+        let image = UserContentPart::image_binary(vec![0; 1_000_000], ImageMediaType::Png);
+        let mut msg = ModelRequest::new();
+        msg.add_user_prompt(UserContent::parts(vec![
+            UserContentPart::text("look"),
+            image,
+        ]));
+        let tokens = estimate_message_tokens(&msg);
+        assert!(tokens >= IMAGE_TOKENS);
+        assert!(tokens < IMAGE_TOKENS + 100);
+    }
+
     #[test]
     fn test_should_compact() {
         // 80% threshold
@@ -89,6 +282,19 @@ mod tests {
         assert!((usage_percent(1000, 0)).abs() < 0.01);
     }
 
+    #[test]
+    fn test_context_usage_levels() {
+        let usage = |used| ContextUsage {
+            used,
+            window: 128_000,
+        };
+        assert_eq!(usage(53_760).to_string(), "[42%] ~53 760/128 000 tokens");
+        assert_eq!(usage(53_760).level(), UsageLevel::Normal);
+        assert_eq!(usage(90_000).level(), UsageLevel::Warning);
+        assert_eq!(usage(200_000).level(), UsageLevel::Critical);
+        assert_eq!(usage(200_000).percent(), 100.0);
+    }
+
     #[test]
     fn test_format_tokens_with_separator() {
         // Small numbers - no separator needed
@@ -497,4 +703,86 @@ mod tests {
             assert!(!result.contains(' '));
         }
     }
+
+    // ==================== Compaction ====================
+
+    fn numbered_messages(count: usize) -> Vec<ModelRequest> {
+        (0..count)
+            .map(|i| {
+                let mut msg = ModelRequest::new();
+                msg.add_user_prompt(format!("message {}", i));
+                msg
+            })
+            .collect()
+    }
+
+    fn prompt_text(msg: &ModelRequest) -> String {
+        serde_json::to_string(msg).unwrap()
+    }
+
+    #[test]
+    fn test_compact_history_pinned_survive_aggressive_truncation() {
+        let messages = numbered_messages(10);
+        let pinned = BTreeSet::from([1, 4]);
+
+        let result = compact_history(
+            &messages,
+            CompactionStrategy::Truncate { max_tokens: 0 },
+            &pinned,
+        );
+        assert_eq!(result.messages.len(), 2);
+        assert_eq!(prompt_text(&result.messages[0]), prompt_text(&messages[1]));
+        assert_eq!(prompt_text(&result.messages[1]), prompt_text(&messages[4]));
+        assert_eq!(result.pinned, BTreeSet::from([0, 1]));
+
+        let result = compact_history(&messages, CompactionStrategy::KeepRecent(0), &pinned);
+        assert_eq!(result.messages.len(), 2);
+    }
+
+    #[test]
+    fn test_compact_history_pinned_count_toward_budget() {
+        let messages = numbered_messages(10);
+        let pinned = BTreeSet::from([0]);
+
+        let result = compact_history(&messages, CompactionStrategy::KeepRecent(3), &pinned);
+        // One pinned + the two newest
+        assert_eq!(result.messages.len(), 3);
+        assert_eq!(prompt_text(&result.messages[0]), prompt_text(&messages[0]));
+        assert_eq!(prompt_text(&result.messages[1]), prompt_text(&messages[8]));
+        assert_eq!(prompt_text(&result.messages[2]), prompt_text(&messages[9]));
+        assert_eq!(result.pinned, BTreeSet::from([0]));
+
+        let per_message = messages.iter().map(estimate_message_tokens).max().unwrap();
+        let result = compact_history(
+            &messages,
+            CompactionStrategy::Truncate {
+                max_tokens: per_message * 3,
+            },
+            &pinned,
+        );
+        assert_eq!(result.messages.len(), 3);
+        assert_eq!(prompt_text(&result.messages[0]), prompt_text(&messages[0]));
+    }
+
+    #[test]
+    fn test_compact_history_without_pins_keeps_newest() {
+        let messages = numbered_messages(5);
+        let result = compact_history(
+            &messages,
+            CompactionStrategy::KeepRecent(2),
+            &BTreeSet::new(),
+        );
+        assert_eq!(result.messages.len(), 2);
+        assert_eq!(prompt_text(&result.messages[1]), prompt_text(&messages[4]));
+        assert!(result.pinned.is_empty());
+
+        // Out-of-range pins are ignored
+        let result = compact_history(
+            &messages,
+            CompactionStrategy::KeepRecent(10),
+            &BTreeSet::from([99]),
+        );
+        assert_eq!(result.messages.len(), 5);
+        assert!(result.pinned.is_empty());
+    }
 }
@@ -2,7 +2,7 @@
 //!
 //! These tools allow agents to delegate tasks to other specialized agents.
 
-use crate::agents::{AgentExecutor, AgentManager};
+use crate::agents::{AgentExecutor, AgentInfo, AgentManager, AgentVisibility};
 use crate::db::Database;
 use crate::mcp::McpManager;
 use crate::models::ModelRegistry;
@@ -31,7 +31,7 @@ pub struct InvokeAgentTool;
 struct InvokeAgentArgs {
     /// Name of the agent to invoke.
     agent_name: String,
-    // Note: prompt and session_id are part of the JSON schema but not used
+    // Note: prompt, session_id and context_turns are part of the JSON schema but not used
     // in this fallback implementation. The real implementation is in
     // executor/sub_agents.rs which has its own args parsing.
 }
@@ -57,6 +57,14 @@ impl Tool for InvokeAgentTool {
                     "Optional session ID for conversation continuity",
                     false,
                 )
+                .integer(
+                    "context_turns",
+                    "Optional: share this many of the latest turns of the current conversation \
+                     with the agent, as text without tool output (default 0: the prompt only). \
+                     Each shared turn adds to the agent's input tokens on every request it makes, \
+                     so share only what it needs, e.g. 1-3 turns for a review. At most 10.",
+                    false,
+                )
                 .build()
                 .expect("schema build failed"),
         )
@@ -88,30 +96,132 @@ impl Tool for InvokeAgentTool {
 
 /// Tool for listing available agents.
 ///
-/// Returns information about all registered agents that can be invoked.
+/// Returns information about the registered agents that can be invoked,
+/// optionally narrowed by [`ListAgentsFilter`].
 #[derive(Debug, Clone, Default)]
 pub struct ListAgentsTool;
 
+/// Optional filters for list_agents; an agent is listed if it passes all
+/// that are given.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListAgentsFilter {
+    /// Only agents with this visibility.
+    #[serde(default)]
+    visibility: Option<AgentVisibility>,
+    /// Only agents whose name or display name contains this, ignoring case.
+    #[serde(default)]
+    name: Option<String>,
+    /// Only agents that can (true) or can't (false) run shell commands.
+    #[serde(default)]
+    shell: Option<bool>,
+    /// Only agents that can (true) or can't (false) modify files.
+    #[serde(default)]
+    file_write: Option<bool>,
+    /// Only agents that can (true) or can't (false) read files.
+    #[serde(default)]
+    file_read: Option<bool>,
+    /// Only agents that can (true) or can't (false) invoke sub-agents.
+    #[serde(default)]
+    sub_agents: Option<bool>,
+    /// Only agents that can (true) or can't (false) use MCP tools.
+    #[serde(default)]
+    mcp: Option<bool>,
+}
+
+impl ListAgentsFilter {
+    /// Parse the filter from list_agents arguments; no arguments lists all.
+    pub fn from_args(args: &JsonValue) -> Result<Self, ToolError> {
+        if args.is_null() {
+            return Ok(Self::default());
+        }
+        serde_json::from_value(args.clone()).map_err(|e| {
+            warn!(tool = "list_agents", error = %e, ?args, "Failed to parse arguments");
+            ToolError::execution_failed(format!("Invalid arguments: {e}. Got: {args}"))
+        })
+    }
+
+    /// Whether `agent` passes every filter given.
+    pub fn matches(&self, agent: &AgentInfo) -> bool {
+        let caps = &agent.capabilities;
+        let flag = |wanted: Option<bool>, has: bool| wanted.map_or(true, |wanted| wanted == has);
+        let name = self.name.as_deref().map(str::to_lowercase);
+        self.visibility.map_or(true, |v| v == agent.visibility)
+            && name.map_or(true, |name| {
+                agent.name.to_lowercase().contains(&name)
+                    || agent.display_name.to_lowercase().contains(&name)
+            })
+            && flag(self.shell, caps.shell)
+            && flag(self.file_write, caps.file_write)
+            && flag(self.file_read, caps.file_read)
+            && flag(self.sub_agents, caps.sub_agents)
+            && flag(self.mcp, caps.mcp)
+    }
+}
+
 #[async_trait]
 impl Tool for ListAgentsTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition::new(
             "list_agents",
             "List all available agents. Use this to discover what specialized agents \
-             are available for delegation.",
+             are available for delegation. Pass filters to get a shortlist, e.g. \
+             file_write: false for read-only agents such as reviewers.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string(
+                    "visibility",
+                    "Optional: only agents of this kind: 'main' (primary agents), \
+                     'sub' (specialists such as reviewers) or 'hidden' (examples)",
+                    false,
+                )
+                .string(
+                    "name",
+                    "Optional: only agents whose name contains this text, ignoring case",
+                    false,
+                )
+                .boolean(
+                    "shell",
+                    "Optional: only agents that can (true) or can't (false) run shell commands",
+                    false,
+                )
+                .boolean(
+                    "file_write",
+                    "Optional: only agents that can (true) or can't (false) modify files",
+                    false,
+                )
+                .boolean(
+                    "file_read",
+                    "Optional: only agents that can (true) or can't (false) read files",
+                    false,
+                )
+                .boolean(
+                    "sub_agents",
+                    "Optional: only agents that can (true) or can't (false) invoke other agents",
+                    false,
+                )
+                .boolean(
+                    "mcp",
+                    "Optional: only agents that can (true) or can't (false) use MCP tools",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
         )
-        .with_parameters(SchemaBuilder::new().build().expect("schema build failed"))
     }
 
     async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
         debug!(tool = "list_agents", ?args, "Tool called");
 
+        let filter = ListAgentsFilter::from_args(&args)?;
+
         // Create a temporary manager to list agents
         let manager = AgentManager::new();
         let agents = manager.list();
 
         let agent_list: Vec<_> = agents
             .iter()
+            .filter(|a| filter.matches(a))
             .map(|a| {
                 serde_json::json!({
                     "name": a.name,
@@ -230,7 +340,7 @@ pub async fn invoke_agent_with_executor(
     let model_registry = ModelRegistry::load_from_db(db).unwrap_or_default();
     let executor = AgentExecutor::new(db, &model_registry);
     let tool_registry = SpotToolRegistry::new();
-    let mcp_manager = McpManager::new();
+    let mcp_manager = McpManager::new().with_api_keys_from_db(db);
 
     match executor
         .execute(
@@ -631,16 +741,66 @@ mod tests {
         assert!(props_obj.contains_key("agent_name"));
         assert!(props_obj.contains_key("prompt"));
         assert!(props_obj.contains_key("session_id"));
+        assert!(props_obj.contains_key("context_turns"));
     }
 
     #[test]
-    fn test_list_agents_tool_schema_is_empty_object() {
+    fn test_list_agents_tool_schema_has_optional_filters() {
         let tool = ListAgentsTool;
         let def = tool.definition();
         let params = def.parameters();
 
-        // list_agents has no parameters
-        assert!(params.is_object());
+        let props = params.get("properties").unwrap().as_object().unwrap();
+        for key in [
+            "visibility",
+            "name",
+            "shell",
+            "file_write",
+            "file_read",
+            "sub_agents",
+            "mcp",
+        ] {
+            assert!(props.contains_key(key), "missing {key}");
+        }
+        let required = params.get("required").and_then(|r| r.as_array());
+        assert!(required.map_or(true, |r| r.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_list_agents_tool_filters_shortlist() {
+        let tool = ListAgentsTool;
+        let ctx = RunContext::minimal("test");
+        let names = |result: ToolResult| -> Vec<String> {
+            let json = result.unwrap().as_json().unwrap().clone();
+            json["agents"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|a| a["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let read_only = names(
+            tool.call(
+                &ctx,
+                serde_json::json!({"file_write": false, "shell": false, "name": "REVIEWER"}),
+            )
+            .await,
+        );
+        assert!(read_only.contains(&"code-reviewer".to_string()));
+        assert!(read_only.iter().all(|name| name.contains("reviewer")));
+
+        let main = names(
+            tool.call(&ctx, serde_json::json!({"visibility": "main"}))
+                .await,
+        );
+        assert!(main.contains(&"stockpot".to_string()));
+        assert!(!main.contains(&"code-reviewer".to_string()));
+
+        let bad = tool
+            .call(&ctx, serde_json::json!({"visibility": "everyone"}))
+            .await;
+        assert!(bad.unwrap_err().to_string().contains("Invalid arguments"));
     }
 
     #[test]
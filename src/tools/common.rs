@@ -1,5 +1,9 @@
 //! Common utilities for tools.
 
+use std::path::Path;
+
+use super::project_ignore::{self, ProjectIgnore};
+
 /// Directory patterns to ignore.
 pub static IGNORE_PATTERNS: &[&str] = &[
     // Version control
@@ -31,9 +35,25 @@ pub static IGNORE_PATTERNS: &[&str] = &[
     ".pnpm-store",
 ];
 
-/// Check if a path should be ignored.
+/// Check if a path should be ignored, by the built-in patterns or the
+/// project's `.stockpotignore`.
 pub fn should_ignore(path: &str) -> bool {
-    let path_lower = path.to_lowercase();
+    should_ignore_entry(path, Path::new(path))
+}
+
+/// Like [`should_ignore`], for a `path` shown as `shown`, e.g. relative to
+/// a directory being listed. The built-in patterns look at `shown`; the
+/// project's patterns at where `path` is.
+pub fn should_ignore_entry(shown: &str, path: &Path) -> bool {
+    ignored_by(project_ignore::current().as_deref(), shown, path)
+}
+
+fn ignored_by(project: Option<&ProjectIgnore>, shown: &str, path: &Path) -> bool {
+    if let Some(ignore) = project.and_then(|p| p.decision(path, path.is_dir())) {
+        return ignore;
+    }
+
+    let path_lower = shown.to_lowercase();
 
     for pattern in IGNORE_PATTERNS {
         if path_lower.contains(pattern) {
@@ -227,6 +247,26 @@ mod tests {
         assert!(!should_ignore("README.md"));
     }
 
+    #[test]
+    fn test_project_patterns_merge_with_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join(project_ignore::IGNORE_FILE),
+            "fixtures/\n!target/\n",
+        )
+        .unwrap();
+        std::fs::create_dir(root.join("fixtures")).unwrap();
+        let project = ProjectIgnore::load(&root.join(project_ignore::IGNORE_FILE));
+        let check = |relative: &str| ignored_by(Some(&project), relative, &root.join(relative));
+
+        assert!(check("fixtures"));
+        assert!(check("fixtures/big.json"));
+        assert!(!check("target/debug/report.txt"));
+        assert!(check("node_modules/react/index.js"));
+        assert!(!check("src/main.rs"));
+    }
+
     // =========================================================================
     // get_extension Tests
     // =========================================================================
@@ -0,0 +1,229 @@
+//! File path completion for the chat input.
+//!
+//! The word being typed is completed as a path when it looks like one
+//! (`src/ma`, `./x`, `~/notes`), is an `@path` file reference, or follows a
+//! command that takes a path. `/config get` and `/config set` complete
+//! setting keys instead, and `/sessions --tag`, `/tag` and `/untag`
+//! complete session tags.
+//! Directories in [`IGNORE_PATTERNS`] and hidden entries are only offered
+//! when asked for by name, so `target/` doesn't crowd out the source tree.
+
+use std::path::Path;
+
+use super::common::IGNORE_PATTERNS;
+use crate::config::{SETTINGS, SETTING_FAMILIES};
+use crate::session::SessionManager;
+
+/// Commands whose argument is a file path.
+const PATH_COMMANDS: &[&str] = &["/diff"];
+
+/// Paths under `base` that `partial` could be completed to, sorted.
+///
+/// Each keeps `partial`'s directory part as typed; directories end in `/`.
+pub fn complete_path(partial: &str, base: &Path) -> Vec<String> {
+    let (dir_part, prefix) = match partial.rfind('/') {
+        Some(i) => partial.split_at(i + 1),
+        None => ("", partial),
+    };
+    let dir = base.join(shellexpand::tilde(dir_part).as_ref());
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut matches: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with(prefix) {
+                return None;
+            }
+            let asked_for = name == prefix;
+            if name.starts_with('.') && !prefix.starts_with('.') {
+                return None;
+            }
+            if IGNORE_PATTERNS.contains(&name.as_str()) && !asked_for {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            Some(format!(
+                "{}{}{}",
+                dir_part,
+                name,
+                if is_dir { "/" } else { "" }
+            ))
+        })
+        .collect();
+    matches.sort();
+    matches
+}
+
+/// Complete the path or setting key at the end of `line` as far as it is
+/// unambiguous.
+///
+/// Returns the new line, or `None` when the last word isn't either or
+/// there's nothing to add. Tags come from the sessions in `sessions`.
+pub fn complete_input(line: &str, base: &Path, sessions: &SessionManager) -> Option<String> {
+    let start = line
+        .rfind(char::is_whitespace)
+        .map(|i| i + line[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    let word = &line[start..];
+
+    // `@path` and `@image:path` references complete the path and keep the prefix
+    let prefix_len = ["@image:", "@"]
+        .iter()
+        .find(|prefix| word.starts_with(**prefix))
+        .map_or(0, |prefix| prefix.len());
+    let is_reference = prefix_len > 0;
+    let (start, word) = (start + prefix_len, &word[prefix_len..]);
+
+    let before: Vec<&str> = line[..start].split_whitespace().collect();
+    let matches = match before.as_slice() {
+        ["/config", "get" | "set"] if !is_reference => setting_keys(word),
+        ["/sessions", "--tag"] | ["/tag" | "/untag", _] if !is_reference => {
+            matching(sessions.tags().unwrap_or_default(), word)
+        }
+        _ => {
+            let after_command =
+                matches!(before.as_slice(), [command] if PATH_COMMANDS.contains(command));
+            let looks_like_path =
+                word.contains('/') || word.starts_with('.') || word.starts_with('~');
+            if !after_command && !looks_like_path && !is_reference {
+                return None;
+            }
+            complete_path(word, base)
+        }
+    };
+
+    let completed = common_prefix(&matches)?;
+    (completed.len() > word.len()).then(|| format!("{}{}", &line[..start], completed))
+}
+
+/// Setting keys starting with `prefix`. Families complete to their
+/// `name.` prefix, ready for the agent, tool or model name.
+fn setting_keys(prefix: &str) -> Vec<String> {
+    let mut keys: Vec<String> = SETTINGS
+        .iter()
+        .map(|def| def.key.to_string())
+        .chain(SETTING_FAMILIES.iter().map(|def| format!("{}.", def.key)))
+        .chain(std::iter::once("model_settings.".to_string()))
+        .filter(|key| key.starts_with(prefix))
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// `candidates` starting with `prefix`.
+fn matching(candidates: Vec<String>, prefix: &str) -> Vec<String> {
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(prefix))
+        .collect()
+}
+
+/// The longest prefix shared by all of `items`.
+fn common_prefix(items: &[String]) -> Option<String> {
+    let (first, rest) = items.split_first()?;
+    let mut len = first.len();
+    for item in rest {
+        len = first
+            .char_indices()
+            .zip(item.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, c), _)| i + c.len_utf8())
+            .min(len);
+    }
+    Some(first[..len].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/messaging")).unwrap();
+        fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(dir.path().join("src/mod.rs"), "").unwrap();
+        fs::write(dir.path().join(".env"), "").unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_complete_path_lists_matches() {
+        let dir = tree();
+        assert_eq!(
+            complete_path("src/m", dir.path()),
+            vec!["src/main.rs", "src/messaging/", "src/mod.rs"]
+        );
+        assert_eq!(complete_path("src/ma", dir.path()), vec!["src/main.rs"]);
+        assert!(complete_path("nope/", dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_complete_path_skips_ignored_and_hidden() {
+        let dir = tree();
+        assert_eq!(complete_path("", dir.path()), vec!["Cargo.toml", "src/"]);
+        assert_eq!(complete_path("target", dir.path()), vec!["target/"]);
+        assert_eq!(complete_path(".e", dir.path()), vec![".env"]);
+    }
+
+    #[test]
+    fn test_complete_input() {
+        let dir = tree();
+        let sessions = SessionManager::with_dir(dir.path().join("sessions"));
+        assert_eq!(
+            complete_input("look at src/ma", dir.path(), &sessions).as_deref(),
+            Some("look at src/main.rs")
+        );
+        assert_eq!(
+            complete_input("src/me", dir.path(), &sessions).as_deref(),
+            Some("src/messaging/")
+        );
+        // Ambiguous with nothing shared beyond what's typed
+        assert_eq!(complete_input("src/m", dir.path(), &sessions), None);
+        assert_eq!(
+            complete_input("/diff Ca", dir.path(), &sessions).as_deref(),
+            Some("/diff Cargo.toml")
+        );
+        assert_eq!(
+            complete_input("explain @Ca", dir.path(), &sessions).as_deref(),
+            Some("explain @Cargo.toml")
+        );
+        assert_eq!(
+            complete_input("see @image:src/ma", dir.path(), &sessions).as_deref(),
+            Some("see @image:src/main.rs")
+        );
+        assert_eq!(
+            complete_input("/config set show_r", dir.path(), &sessions).as_deref(),
+            Some("/config set show_reasoning")
+        );
+        assert_eq!(
+            complete_input("/config get agent_p", dir.path(), &sessions).as_deref(),
+            Some("/config get agent_pin.")
+        );
+        // Plain words aren't paths
+        assert_eq!(complete_input("explain Ca", dir.path(), &sessions), None);
+    }
+
+    #[test]
+    fn test_complete_input_tags_from_the_given_sessions() {
+        let dir = tree();
+        let sessions = SessionManager::with_dir(dir.path().join("sessions"));
+        sessions.save("ui", &[], "agent", "model").unwrap();
+        sessions.add_tag("ui", "frontend").unwrap();
+
+        assert_eq!(
+            complete_input("/tag ui fr", dir.path(), &sessions).as_deref(),
+            Some("/tag ui frontend")
+        );
+        assert_eq!(
+            complete_input("/sessions --tag f", dir.path(), &sessions).as_deref(),
+            Some("/sessions --tag frontend")
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! DeleteFile tool implementation.
 //!
-//! Provides a serdesAI-compatible tool for deleting files.
+//! Provides a serdesAI-compatible tool for deleting files. With an
+//! [`UndoRun`] attached, the file is saved before it is deleted.
 
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -9,9 +10,21 @@ use tracing::{debug, warn};
 
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
+use super::undo::UndoRun;
+
 /// Tool for deleting files.
 #[derive(Debug, Clone, Default)]
-pub struct DeleteFileTool;
+pub struct DeleteFileTool {
+    undo: Option<UndoRun>,
+}
+
+impl DeleteFileTool {
+    /// Save each file to `run` before deleting it.
+    pub fn with_undo(mut self, run: UndoRun) -> Self {
+        self.undo = Some(run);
+        self
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct DeleteFileArgs {
@@ -60,6 +73,15 @@ impl Tool for DeleteFileTool {
             )));
         }
 
+        if let Some(undo) = &self.undo {
+            if let Err(e) = undo.record(path) {
+                return Ok(ToolReturn::error(format!(
+                    "Not deleting {}: failed to save it for undo: {}",
+                    args.file_path, e
+                )));
+            }
+        }
+
         match std::fs::remove_file(path) {
             Ok(()) => Ok(ToolReturn::text(format!(
                 "Successfully deleted: {}",
@@ -77,21 +99,21 @@ mod tests {
 
     #[test]
     fn test_definition_returns_correct_name() {
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::default();
         let def = tool.definition();
         assert_eq!(def.name(), "delete_file");
     }
 
     #[test]
     fn test_definition_has_description() {
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::default();
         let def = tool.definition();
         assert!(def.description().contains("delete"));
     }
 
     #[test]
     fn test_definition_has_parameters() {
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::default();
         let def = tool.definition();
         let params = def.parameters();
         assert!(params.is_object());
@@ -106,7 +128,7 @@ mod tests {
         fs::write(&file_path, "content").expect("write failed");
         assert!(file_path.exists());
 
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -121,7 +143,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_file_not_found_returns_error() {
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -142,7 +164,7 @@ mod tests {
         let subdir = dir.path().join("subdir");
         fs::create_dir(&subdir).expect("mkdir failed");
 
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -159,7 +181,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_missing_file_path_returns_error() {
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool.call(&ctx, serde_json::json!({})).await;
         assert!(result.is_err());
@@ -167,7 +189,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_wrong_type_file_path_returns_error() {
-        let tool = DeleteFileTool;
+        let tool = DeleteFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(&ctx, serde_json::json!({ "file_path": 123 }))
@@ -1,4 +1,4 @@
-//! Unified diff parsing and application.
+//! Unified diff parsing, application and generation.
 //!
 //! Supports standard unified diff format:
 //! ```text
@@ -11,9 +11,14 @@
 //!  more context
 //! ```
 
+use std::fmt;
 use std::str::Lines;
 use thiserror::Error;
 
+/// Changed region sizes (old × new lines) above which [`UnifiedDiff::between`]
+/// stops looking for common lines and replaces the region wholesale.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
 /// Diff parsing/application errors.
 #[derive(Debug, Error)]
 pub enum DiffError {
@@ -122,6 +127,18 @@ impl UnifiedDiff {
         })
     }
 
+    /// Count added and removed lines across all hunks.
+    pub fn stats(&self) -> (usize, usize) {
+        self.hunks
+            .iter()
+            .flat_map(|h| &h.lines)
+            .fold((0, 0), |(added, removed), line| match line {
+                DiffLine::Add(_) => (added + 1, removed),
+                DiffLine::Remove(_) => (added, removed + 1),
+                DiffLine::Context(_) => (added, removed),
+            })
+    }
+
     /// Apply this diff to the given content.
     pub fn apply(&self, original: &str) -> Result<String, DiffError> {
         if self.is_new_file {
@@ -158,6 +175,180 @@ impl UnifiedDiff {
     }
 }
 
+impl UnifiedDiff {
+    /// Compute the diff from `old` to `new`, with `context` unchanged lines
+    /// around each change.
+    ///
+    /// A `None` path marks the file as created (old) or deleted (new).
+    pub fn between(
+        old_path: Option<&str>,
+        new_path: Option<&str>,
+        old: &str,
+        new: &str,
+        context: usize,
+    ) -> Self {
+        let old_lines: Vec<&str> = old.lines().collect();
+        let new_lines: Vec<&str> = new.lines().collect();
+        let edits = line_edits(&old_lines, &new_lines);
+
+        // Keep a line only if a change is within `context` lines of it
+        let mut keep = vec![false; edits.len()];
+        for backward in [false, true] {
+            let mut since_change = usize::MAX;
+            for i in 0..edits.len() {
+                let i = if backward { edits.len() - 1 - i } else { i };
+                if matches!(edits[i], DiffLine::Context(_)) {
+                    since_change = since_change.saturating_add(1);
+                    keep[i] |= since_change <= context;
+                } else {
+                    since_change = 0;
+                    keep[i] = true;
+                }
+            }
+        }
+
+        let mut hunks = Vec::new();
+        let (mut old_pos, mut new_pos) = (0, 0);
+        let mut current: Option<Hunk> = None;
+        for (edit, keep) in edits.into_iter().zip(keep) {
+            if keep {
+                let hunk = current.get_or_insert_with(|| Hunk {
+                    old_start: old_pos + 1,
+                    old_count: 0,
+                    new_start: new_pos + 1,
+                    new_count: 0,
+                    lines: Vec::new(),
+                });
+                match &edit {
+                    DiffLine::Context(_) => {
+                        hunk.old_count += 1;
+                        hunk.new_count += 1;
+                    }
+                    DiffLine::Remove(_) => hunk.old_count += 1,
+                    DiffLine::Add(_) => hunk.new_count += 1,
+                }
+                hunk.lines.push(edit.clone());
+            } else if let Some(hunk) = current.take() {
+                hunks.push(hunk);
+            }
+            match edit {
+                DiffLine::Context(_) => {
+                    old_pos += 1;
+                    new_pos += 1;
+                }
+                DiffLine::Remove(_) => old_pos += 1,
+                DiffLine::Add(_) => new_pos += 1,
+            }
+        }
+        hunks.extend(current);
+
+        // An empty side starts at the line before it, e.g. `-0,0`
+        for hunk in &mut hunks {
+            if hunk.old_count == 0 {
+                hunk.old_start -= 1;
+            }
+            if hunk.new_count == 0 {
+                hunk.new_start -= 1;
+            }
+        }
+
+        UnifiedDiff {
+            old_path: old_path.map(String::from),
+            new_path: new_path.map(String::from),
+            is_new_file: old_path.is_none(),
+            is_delete: new_path.is_none(),
+            hunks,
+        }
+    }
+}
+
+impl fmt::Display for UnifiedDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "--- {}", self.old_path.as_deref().unwrap_or("/dev/null"))?;
+        writeln!(f, "+++ {}", self.new_path.as_deref().unwrap_or("/dev/null"))?;
+        for hunk in &self.hunks {
+            writeln!(
+                f,
+                "@@ -{},{} +{},{} @@",
+                hunk.old_start, hunk.old_count, hunk.new_start, hunk.new_count
+            )?;
+            for line in &hunk.lines {
+                match line {
+                    DiffLine::Context(text) => writeln!(f, " {}", text)?,
+                    DiffLine::Remove(text) => writeln!(f, "-{}", text)?,
+                    DiffLine::Add(text) => writeln!(f, "+{}", text)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Line-by-line edits turning `old` into `new`, via the longest common
+/// subsequence of the region between their common prefix and suffix.
+fn line_edits(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut edits: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|line| DiffLine::Context(line.to_string()))
+        .collect();
+
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_DIFF_CELLS {
+        edits.extend(
+            old_mid
+                .iter()
+                .map(|line| DiffLine::Remove(line.to_string())),
+        );
+        edits.extend(new_mid.iter().map(|line| DiffLine::Add(line.to_string())));
+    } else {
+        // lcs[i][j]: common lines between old_mid[i..] and new_mid[j..]
+        let width = new_mid.len() + 1;
+        let mut lcs = vec![0u32; (old_mid.len() + 1) * width];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() || j < new_mid.len() {
+            if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+                edits.push(DiffLine::Context(old_mid[i].to_string()));
+                i += 1;
+                j += 1;
+            } else if j == new_mid.len()
+                || (i < old_mid.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                edits.push(DiffLine::Remove(old_mid[i].to_string()));
+                i += 1;
+            } else {
+                edits.push(DiffLine::Add(new_mid[j].to_string()));
+                j += 1;
+            }
+        }
+    }
+
+    edits.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::Context(line.to_string())),
+    );
+    edits
+}
+
 /// Parse a file path from a --- or +++ line.
 fn parse_file_path(line: &str, prefix: &str) -> String {
     let path = line.strip_prefix(prefix).unwrap_or(line).trim();
@@ -362,6 +553,22 @@ mod tests {
         assert!(!parsed.is_delete);
     }
 
+    #[test]
+    fn test_diff_stats() {
+        let diff = r#"--- a/file.txt
++++ b/file.txt
+@@ -1,3 +1,4 @@
+ line 1
+-line 2
++line 2 modified
++line 2.5 added
+ line 3
+"#;
+
+        let parsed = UnifiedDiff::parse(diff).unwrap();
+        assert_eq!(parsed.stats(), (2, 1));
+    }
+
     #[test]
     fn test_apply_simple_diff() {
         let original = "line 1\nline 2\nline 3";
@@ -453,6 +660,54 @@ mod tests {
         assert_eq!((os, oc, ns, nc), (1, 1, 1, 2));
     }
 
+    // ===== diff generation tests =====
+
+    #[test]
+    fn test_between_renders_hunk_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\n";
+        let new = "a\nb\nc\nD\ne\nf\ng\n";
+        let diff = UnifiedDiff::between(Some("f.txt"), Some("f.txt"), old, new, 1);
+        assert_eq!(
+            diff.to_string(),
+            "--- f.txt\n+++ f.txt\n@@ -3,3 +3,3 @@\n c\n-d\n+D\n e\n"
+        );
+        assert_eq!(diff.stats(), (1, 1));
+    }
+
+    #[test]
+    fn test_between_splits_distant_changes_into_hunks() {
+        let old: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let new = old.replace("2\n", "two\n").replace("19\n", "nineteen\n");
+        let diff = UnifiedDiff::between(Some("n"), Some("n"), &old, &new, 3);
+        assert_eq!(diff.hunks.len(), 2);
+        assert_eq!(diff.hunks[1].old_start, 16);
+    }
+
+    #[test]
+    fn test_between_round_trips_through_apply() {
+        let old = "fn main() {\n    println!(\"hi\");\n}\n";
+        let new = "use std::io;\n\nfn main() {\n    println!(\"hello\");\n    io::stdin();\n}\n";
+        let diff = UnifiedDiff::between(Some("main.rs"), Some("main.rs"), old, new, 3);
+        let reparsed = UnifiedDiff::parse(&diff.to_string()).unwrap();
+        assert_eq!(reparsed.apply(old).unwrap(), new.trim_end());
+    }
+
+    #[test]
+    fn test_between_new_and_deleted_files() {
+        let created = UnifiedDiff::between(None, Some("new.txt"), "", "x\ny\n", 3);
+        assert!(created
+            .to_string()
+            .starts_with("--- /dev/null\n+++ new.txt\n@@ -0,0 +1,2 @@\n"));
+
+        let deleted = UnifiedDiff::between(Some("old.txt"), None, "x\n", "", 3);
+        assert!(deleted.to_string().contains("@@ -1,1 +0,0 @@\n-x\n"));
+        assert!(
+            UnifiedDiff::between(Some("same"), Some("same"), "x\n", "x\n", 3)
+                .hunks
+                .is_empty()
+        );
+    }
+
     // ===== parse_file_path tests =====
 
     #[test]
@@ -1,6 +1,9 @@
 //! EditFile tool implementation.
 //!
-//! Provides a serdesAI-compatible tool for creating or editing files.
+//! Provides a serdesAI-compatible tool for creating or editing files, either
+//! from full content or by applying a unified diff. With `dry_run` set, the
+//! result is computed in memory and returned without touching disk. With an
+//! [`UndoRun`] attached, the file's prior state is saved before writing.
 
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -9,18 +12,35 @@ use tracing::{debug, warn};
 
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
+use super::diff::UnifiedDiff;
 use super::file_ops;
+use super::undo::UndoRun;
 
 /// Tool for creating or editing files.
 #[derive(Debug, Clone, Default)]
-pub struct EditFileTool;
+pub struct EditFileTool {
+    undo: Option<UndoRun>,
+}
+
+impl EditFileTool {
+    /// Save each file's prior state to `run` before writing it.
+    pub fn with_undo(mut self, run: UndoRun) -> Self {
+        self.undo = Some(run);
+        self
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct EditFileArgs {
     file_path: String,
-    content: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    diff: Option<String>,
     #[serde(default)]
     create_directories: bool,
+    #[serde(default)]
+    dry_run: bool,
 }
 
 #[async_trait]
@@ -28,18 +48,33 @@ impl Tool for EditFileTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition::new(
             "edit_file",
-            "Create or overwrite a file with the provided content. \
-             Supports creating parent directories if they don't exist.",
+            "Create or overwrite a file with the provided content, or apply a unified diff \
+             to it. Supports creating parent directories if they don't exist. \
+             Set dry_run to preview the result without writing.",
         )
         .with_parameters(
             SchemaBuilder::new()
                 .string("file_path", "Path to the file to create or edit.", true)
-                .string("content", "The full content to write to the file.", true)
+                .string(
+                    "content",
+                    "The full content to write to the file. Required unless diff is given.",
+                    false,
+                )
+                .string(
+                    "diff",
+                    "A unified diff to apply to the file's current content instead of content.",
+                    false,
+                )
                 .boolean(
                     "create_directories",
                     "Whether to create parent directories if they don't exist. Defaults to false.",
                     false,
                 )
+                .boolean(
+                    "dry_run",
+                    "Return the resulting content without writing it. Defaults to false.",
+                    false,
+                )
                 .build()
                 .expect("schema build failed"),
         )
@@ -56,42 +91,97 @@ impl Tool for EditFileTool {
             ))
         })?;
 
-        match file_ops::write_file(&args.file_path, &args.content, args.create_directories) {
-            Ok(()) => {
-                let line_count = args.content.lines().count();
-                let byte_count = args.content.len();
-                Ok(ToolReturn::text(format!(
-                    "Successfully wrote {} lines ({} bytes) to {}",
-                    line_count, byte_count, args.file_path
-                )))
+        let (content, diff_stats) = match (&args.content, &args.diff) {
+            (_, Some(diff_text)) => match apply_diff_to_file(&args.file_path, diff_text) {
+                Ok(result) => result,
+                Err(e) => return Ok(ToolReturn::error(e)),
+            },
+            (Some(content), None) => (content.clone(), None),
+            (None, None) => {
+                return Err(serdes_ai_tools::ToolError::execution_failed(
+                    "Invalid arguments: either content or diff is required",
+                ));
             }
+        };
+
+        let line_count = content.lines().count();
+        let byte_count = content.len();
+        let stat = diff_stats
+            .map(|(added, removed)| format!(" (+{} -{})", added, removed))
+            .unwrap_or_default();
+
+        if args.dry_run {
+            return Ok(ToolReturn::text(format!(
+                "Dry run: would write {} lines ({} bytes) to {}{}. No changes made.\n\n{}",
+                line_count, byte_count, args.file_path, stat, content
+            )));
+        }
+
+        if let Some(undo) = &self.undo {
+            if let Err(e) = undo.record(&args.file_path) {
+                return Ok(ToolReturn::error(format!(
+                    "Not writing {}: failed to save it for undo: {}",
+                    args.file_path, e
+                )));
+            }
+        }
+
+        match file_ops::write_file(&args.file_path, &content, args.create_directories) {
+            Ok(()) => Ok(ToolReturn::text(format!(
+                "Successfully wrote {} lines ({} bytes) to {}{}",
+                line_count, byte_count, args.file_path, stat
+            ))),
             Err(e) => Ok(ToolReturn::error(format!("Failed to write file: {}", e))),
         }
     }
 }
 
+/// Apply a unified diff to the file's current content in memory.
+///
+/// Returns the patched content and `(added, removed)` line counts. A missing
+/// file is treated as empty so new-file diffs work.
+fn apply_diff_to_file(
+    path: &str,
+    diff_text: &str,
+) -> Result<(String, Option<(usize, usize)>), String> {
+    let diff = UnifiedDiff::parse(diff_text).map_err(|e| format!("Invalid diff: {}", e))?;
+
+    let original = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read file: {}", e)),
+    };
+
+    let patched = diff
+        .apply(&original)
+        .map_err(|e| format!("Failed to apply diff: {}", e))?;
+
+    Ok((patched, Some(diff.stats())))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tools::UndoJournal;
     use std::fs;
 
     #[test]
     fn test_definition_returns_correct_name() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let def = tool.definition();
         assert_eq!(def.name(), "edit_file");
     }
 
     #[test]
     fn test_definition_has_description() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let def = tool.definition();
         assert!(def.description().contains("Create"));
     }
 
     #[test]
     fn test_definition_has_parameters() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let def = tool.definition();
         let params = def.parameters();
         assert!(params.is_object());
@@ -99,6 +189,8 @@ mod tests {
         assert!(schema_str.contains("file_path"));
         assert!(schema_str.contains("content"));
         assert!(schema_str.contains("create_directories"));
+        assert!(schema_str.contains("diff"));
+        assert!(schema_str.contains("dry_run"));
     }
 
     #[tokio::test]
@@ -106,7 +198,7 @@ mod tests {
         let dir = tempfile::tempdir().expect("tempdir failed");
         let file_path = dir.path().join("new_file.txt");
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -131,7 +223,7 @@ mod tests {
         let file_path = dir.path().join("existing.txt");
         fs::write(&file_path, "old content").expect("write failed");
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -152,7 +244,7 @@ mod tests {
         let dir = tempfile::tempdir().expect("tempdir failed");
         let file_path = dir.path().join("count.txt");
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -175,7 +267,7 @@ mod tests {
         let dir = tempfile::tempdir().expect("tempdir failed");
         let file_path = dir.path().join("subdir/nested/file.txt");
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -199,7 +291,7 @@ mod tests {
         let dir = tempfile::tempdir().expect("tempdir failed");
         let file_path = dir.path().join("nonexistent/file.txt");
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -220,7 +312,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_missing_file_path_returns_error() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(&ctx, serde_json::json!({ "content": "hello" }))
@@ -230,7 +322,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_missing_content_returns_error() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(&ctx, serde_json::json!({ "file_path": "/tmp/test.txt" }))
@@ -240,7 +332,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_wrong_type_file_path_returns_error() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -253,7 +345,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_wrong_type_content_returns_error() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -269,7 +361,7 @@ mod tests {
         let dir = tempfile::tempdir().expect("tempdir failed");
         let file_path = dir.path().join("empty.txt");
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -288,16 +380,155 @@ mod tests {
         assert_eq!(fs::read_to_string(&file_path).unwrap(), "");
     }
 
+    // =========================================================================
+    // Diff and Dry Run Tests
+    // =========================================================================
+
+    const SAMPLE_DIFF: &str =
+        "--- a/file.txt\n+++ b/file.txt\n@@ -1,3 +1,3 @@\n line 1\n-line 2\n+line two\n line 3\n";
+
+    #[tokio::test]
+    async fn test_call_applies_diff() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file_path = dir.path().join("file.txt");
+        fs::write(&file_path, "line 1\nline 2\nline 3").expect("write failed");
+
+        let tool = EditFileTool::default();
+        let ctx = RunContext::minimal("test");
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "file_path": file_path.to_str().unwrap(),
+                    "diff": SAMPLE_DIFF
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert!(!result.is_error());
+        assert!(result.as_text().unwrap().contains("(+1 -1)"));
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "line 1\nline two\nline 3"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_diff_dry_run_leaves_file_unchanged() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file_path = dir.path().join("file.txt");
+        fs::write(&file_path, "line 1\nline 2\nline 3").expect("write failed");
+
+        let tool = EditFileTool::default();
+        let ctx = RunContext::minimal("test");
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "file_path": file_path.to_str().unwrap(),
+                    "diff": SAMPLE_DIFF,
+                    "dry_run": true
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert!(!result.is_error());
+        let text = result.as_text().unwrap();
+        assert!(text.contains("Dry run"));
+        assert!(text.contains("(+1 -1)"));
+        assert!(text.contains("line 1\nline two\nline 3"));
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "line 1\nline 2\nline 3"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_content_dry_run_does_not_create_file() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file_path = dir.path().join("preview.txt");
+
+        let tool = EditFileTool::default();
+        let ctx = RunContext::minimal("test");
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "file_path": file_path.to_str().unwrap(),
+                    "content": "preview",
+                    "dry_run": true
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert!(!result.is_error());
+        assert!(result.as_text().unwrap().contains("1 lines (7 bytes)"));
+        assert!(!file_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_call_invalid_diff_returns_error() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file_path = dir.path().join("file.txt");
+        fs::write(&file_path, "line 1").expect("write failed");
+
+        let tool = EditFileTool::default();
+        let ctx = RunContext::minimal("test");
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "file_path": file_path.to_str().unwrap(),
+                    "diff": "--- a/file.txt\n+++ b/file.txt\n@@ -x,y +1,1 @@\n-line 1\n+line one\n"
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_error());
+        assert!(result.as_text().unwrap().contains("Invalid diff"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "line 1");
+    }
+
+    #[tokio::test]
+    async fn test_call_records_undo_snapshot() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file_path = dir.path().join("tracked.txt");
+        fs::write(&file_path, "before").expect("write failed");
+
+        let journal = UndoJournal::with_dir(dir.path().join("undo"));
+        let run = journal.begin();
+        let tool = EditFileTool::default().with_undo(run.clone());
+        let ctx = RunContext::minimal("test");
+        tool.call(
+            &ctx,
+            serde_json::json!({
+                "file_path": file_path.to_str().unwrap(),
+                "content": "after"
+            }),
+        )
+        .await
+        .unwrap();
+        run.finish("run-1").unwrap();
+
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "after");
+        journal.undo_last().unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "before");
+    }
+
     #[test]
     fn test_tool_debug_impl() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let debug_str = format!("{:?}", tool);
         assert!(debug_str.contains("EditFileTool"));
     }
 
     #[test]
     fn test_tool_clone_impl() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::default();
         let cloned = tool.clone();
         assert_eq!(tool.definition().name(), cloned.definition().name());
     }
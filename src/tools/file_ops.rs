@@ -1,10 +1,13 @@
 //! File operation tools.
 
-use super::common::{is_text_file, should_ignore};
+use super::common::{is_text_file, should_ignore, should_ignore_entry};
+use super::git_status::{GitStatus, RepoStatus};
+use super::progress::ProgressReporter;
 use grep_regex::RegexMatcher;
-use grep_searcher::{Searcher, Sink, SinkMatch};
+use grep_searcher::{Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkMatch};
 use ignore::WalkBuilder;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -20,6 +23,12 @@ pub enum FileError {
     TooLarge(u64, u64),
     #[error("Grep error: {0}")]
     GrepError(String),
+    #[error("Already exists: {0}")]
+    AlreadyExists(String),
+    #[error("Not a file: {0}")]
+    NotAFile(String),
+    #[error("Not a directory: {0}")]
+    NotADirectory(String),
     #[error("File too large: ~{estimated_tokens} tokens ({total_lines} lines). Read in chunks using start_line and num_lines parameters. Suggested: start_line=1, num_lines={suggested_chunk_size}")]
     TokenLimitExceeded {
         estimated_tokens: usize,
@@ -36,6 +45,13 @@ pub struct FileEntry {
     pub is_dir: bool,
     pub size: u64,
     pub depth: usize,
+    /// Status in git, when listing inside a repository and the entry is dirty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_status: Option<GitStatus>,
+    /// Whether the entry is a symbolic link; `is_dir` and `size` describe
+    /// its target.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_symlink: bool,
 }
 
 /// Result of listing files.
@@ -47,6 +63,79 @@ pub struct ListFilesResult {
     pub total_size: u64,
     pub truncated: bool,
     pub max_entries: usize,
+    /// Symlinked directories that weren't descended into, either because
+    /// symlinks aren't being followed or because their target was already
+    /// listed.
+    #[serde(default)]
+    pub skipped_symlinks: Vec<String>,
+    /// Offset of the next page, when there are more entries.
+    #[serde(default)]
+    pub next_cursor: Option<usize>,
+}
+
+impl ListFilesResult {
+    /// Render the entries as an indented tree, like `tree`, under a `root`
+    /// line. With `collapse_depth`, directories that many levels down are
+    /// shown with a count of their contents instead of being expanded.
+    pub fn render_tree(&self, root: &str, collapse_depth: Option<usize>) -> String {
+        let entries = &self.entries;
+
+        // Entries come depth-first, so an entry is the last of its siblings
+        // if no later entry at its depth comes before its parent ends.
+        let mut is_last = vec![false; entries.len()];
+        let mut later_sibling: Vec<bool> = Vec::new();
+        for (i, entry) in entries.iter().enumerate().rev() {
+            later_sibling.resize(entry.depth + 1, false);
+            is_last[i] = !later_sibling[entry.depth];
+            later_sibling[entry.depth] = true;
+        }
+
+        let mut out = root.to_string();
+        let mut ancestors_last: Vec<bool> = Vec::new();
+        let mut i = 0;
+        while i < entries.len() {
+            let entry = &entries[i];
+            let last = is_last[i];
+            i += 1;
+
+            ancestors_last.truncate(entry.depth);
+            out.push('\n');
+            for &ancestor_last in &ancestors_last {
+                out.push_str(if ancestor_last { "    " } else { "│   " });
+            }
+            out.push_str(if last { "└── " } else { "├── " });
+            out.push_str(&entry.name);
+            ancestors_last.push(last);
+
+            if !entry.is_dir {
+                if let Some(status) = entry.git_status {
+                    out.push_str(&format!(" [{}]", status));
+                }
+                continue;
+            }
+            out.push('/');
+            if let Some(status) = entry.git_status {
+                out.push_str(&format!(" [{}]", status));
+            }
+
+            if collapse_depth.is_some_and(|levels| entry.depth + 1 >= levels.max(1)) {
+                let (mut dirs, mut files) = (0, 0);
+                while i < entries.len() && entries[i].depth > entry.depth {
+                    if entries[i].is_dir {
+                        dirs += 1;
+                    } else {
+                        files += 1;
+                    }
+                    i += 1;
+                }
+                if dirs + files > 0 {
+                    out.push_str(&format!(" [{} dirs, {} files]", dirs, files));
+                }
+            }
+        }
+
+        out
+    }
 }
 
 const LIST_FILES_DEFAULT_MAX_ENTRIES: usize = 2_000;
@@ -57,11 +146,21 @@ const LIST_FILES_HARD_MAX_DEPTH: usize = 50;
 /// Context for recursive file listing.
 struct ListFilesContext<'a> {
     base: &'a Path,
+    /// `base` made absolute, to match paths against git's.
+    absolute_base: PathBuf,
+    /// Status of the repository being listed, if any.
+    repo: Option<RepoStatus>,
+    respect_gitignore: bool,
+    follow_symlinks: bool,
+    /// Canonical paths of directories already listed, to break symlink loops.
+    visited: HashSet<PathBuf>,
+    skipped_symlinks: &'a mut Vec<String>,
     entries: &'a mut Vec<FileEntry>,
     recursive: bool,
     max_depth: usize,
     max_entries: usize,
     truncated: &'a mut bool,
+    progress: &'a mut ProgressReporter,
 }
 
 /// Maximum tokens allowed in a single file read to protect context window
@@ -69,19 +168,71 @@ const READ_FILE_MAX_TOKENS: usize = 10_000;
 /// Approximate characters per token (conservative estimate)
 const CHARS_PER_TOKEN: usize = 4;
 
+/// How [`list_files_with_progress`] walks a directory and which page of
+/// entries it returns.
+#[derive(Debug, Clone)]
+pub struct ListFilesOptions {
+    pub recursive: bool,
+    pub max_depth: Option<usize>,
+    /// Page size. Defaults to 2000 (hard cap: 10000).
+    pub max_entries: Option<usize>,
+    /// Entries to skip, from a previous page's `next_cursor`.
+    pub offset: usize,
+    /// Leave out what `.gitignore` excludes, inside a git repository.
+    pub respect_gitignore: bool,
+    /// Descend into symlinked directories.
+    pub follow_symlinks: bool,
+}
+
+impl Default for ListFilesOptions {
+    fn default() -> Self {
+        Self {
+            recursive: true,
+            max_depth: None,
+            max_entries: None,
+            offset: 0,
+            respect_gitignore: true,
+            follow_symlinks: false,
+        }
+    }
+}
+
 /// List files in a directory.
 pub fn list_files(
     directory: &str,
     recursive: bool,
     max_depth: Option<usize>,
     max_entries: Option<usize>,
+) -> Result<ListFilesResult, FileError> {
+    let options = ListFilesOptions {
+        recursive,
+        max_depth,
+        max_entries,
+        ..ListFilesOptions::default()
+    };
+    list_files_with_progress(directory, &options, &mut ProgressReporter::disabled())
+}
+
+/// List files in a directory, reporting the running entry count as it goes.
+///
+/// Inside a git repository, dirty entries carry their git status and, with
+/// `respect_gitignore`, ignored ones are left out. Symlinked directories are
+/// only descended into with `follow_symlinks`, and never twice.
+///
+/// Entries come in a stable order, so pages can be walked with `offset` and
+/// each result's `next_cursor`.
+pub fn list_files_with_progress(
+    directory: &str,
+    options: &ListFilesOptions,
+    progress: &mut ProgressReporter,
 ) -> Result<ListFilesResult, FileError> {
     let path = Path::new(directory);
     if !path.exists() {
         return Err(FileError::NotFound(directory.to_string()));
     }
 
-    let max_entries = max_entries
+    let max_entries = options
+        .max_entries
         .unwrap_or(LIST_FILES_DEFAULT_MAX_ENTRIES)
         .clamp(1, LIST_FILES_HARD_MAX_ENTRIES);
 
@@ -90,20 +241,32 @@ pub fn list_files(
     let mut total_dirs = 0;
     let mut total_size = 0u64;
     let mut truncated = false;
+    let mut skipped_symlinks = Vec::new();
 
-    let max_depth = max_depth
+    let max_depth = options
+        .max_depth
         .unwrap_or(LIST_FILES_DEFAULT_MAX_DEPTH)
         .min(LIST_FILES_HARD_MAX_DEPTH);
 
+    let absolute_base = path.canonicalize()?;
     let mut ctx = ListFilesContext {
         base: path,
+        visited: HashSet::from([absolute_base.clone()]),
+        absolute_base,
+        repo: RepoStatus::load(path),
+        respect_gitignore: options.respect_gitignore,
+        follow_symlinks: options.follow_symlinks,
+        skipped_symlinks: &mut skipped_symlinks,
         entries: &mut entries,
-        recursive,
+        recursive: options.recursive,
         max_depth,
-        max_entries,
+        // Walk through the earlier pages too, then drop them
+        max_entries: options.offset.saturating_add(max_entries),
         truncated: &mut truncated,
+        progress,
     };
     list_files_recursive(&mut ctx, path, 0)?;
+    entries.drain(..options.offset.min(entries.len()));
 
     for entry in &entries {
         if entry.is_dir {
@@ -121,6 +284,8 @@ pub fn list_files(
         total_size,
         truncated,
         max_entries,
+        skipped_symlinks,
+        next_cursor: truncated.then(|| options.offset + max_entries),
     })
 }
 
@@ -157,7 +322,7 @@ fn list_files_recursive(
         let relative = path.strip_prefix(ctx.base).unwrap_or(&path);
         let relative_str = relative.to_string_lossy().to_string();
 
-        if should_ignore(&relative_str) {
+        if should_ignore_entry(&relative_str, &path) {
             continue;
         }
 
@@ -171,19 +336,56 @@ fn list_files_recursive(
             Err(_) => continue,
         };
 
-        let is_dir = file_type.is_dir();
+        // Describe a symlink by its target; a broken one stays a plain entry
+        let is_symlink = file_type.is_symlink();
+        let metadata = if is_symlink {
+            fs::metadata(&path).unwrap_or(metadata)
+        } else {
+            metadata
+        };
+
+        let is_dir = metadata.is_dir();
         let name = entry.file_name().to_string_lossy().to_string();
 
+        // Git sees a symlink as a file, whatever it points to
+        let absolute = ctx.absolute_base.join(relative);
+        let git_is_dir = file_type.is_dir();
+        let git_status = match &ctx.repo {
+            Some(repo) if ctx.respect_gitignore && repo.is_ignored(&absolute, git_is_dir) => {
+                continue
+            }
+            Some(repo) => repo.status_of(&absolute, git_is_dir),
+            None => None,
+        };
+
         ctx.entries.push(FileEntry {
             path: relative_str.clone(),
             name,
             is_dir,
             size: if is_dir { 0 } else { metadata.len() },
             depth,
+            git_status,
+            is_symlink,
         });
+        ctx.progress.report(ctx.entries.len(), None);
 
         if is_dir && ctx.recursive {
-            list_files_recursive(ctx, &path, depth + 1)?;
+            // Only symlinks can loop back; real directories are always
+            // listed, and recorded so links into them can be skipped
+            let target = path.canonicalize();
+            let descend = if is_symlink {
+                ctx.follow_symlinks && target.is_ok_and(|target| ctx.visited.insert(target))
+            } else {
+                if let Ok(target) = target {
+                    ctx.visited.insert(target);
+                }
+                true
+            };
+            if descend {
+                list_files_recursive(ctx, &path, depth + 1)?;
+            } else {
+                ctx.skipped_symlinks.push(relative_str);
+            }
         }
     }
 
@@ -198,8 +400,30 @@ pub struct ReadFileResult {
     pub size: u64,
     pub lines: usize,
     pub estimated_tokens: usize,
+    /// 1-based number of the first line in `content`.
+    pub start_line: usize,
+    /// 1-based number of the last line in `content`; `start_line - 1` when
+    /// the range is empty.
+    pub end_line: usize,
 }
 
+impl ReadFileResult {
+    /// `content` with each line prefixed by its 1-based number, right-aligned
+    /// to the widest number and followed by a tab, like `cat -n`.
+    pub fn numbered_content(&self) -> String {
+        let width = self.end_line.max(1).to_string().len();
+        self.content
+            .lines()
+            .zip(self.start_line..)
+            .map(|(line, number)| format!("{:>width$}\t{}", number, line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Largest file `read_file` reads unless told otherwise.
+pub const DEFAULT_MAX_READ_BYTES: u64 = 10 * 1024 * 1024;
+
 pub fn read_file(
     path: &str,
     start_line: Option<usize>,
@@ -212,7 +436,7 @@ pub fn read_file(
     }
 
     let metadata = fs::metadata(file_path)?;
-    let max = max_size.unwrap_or(10 * 1024 * 1024); // 10MB default
+    let max = max_size.unwrap_or(DEFAULT_MAX_READ_BYTES);
 
     if metadata.len() > max {
         return Err(FileError::TooLarge(metadata.len(), max));
@@ -235,15 +459,15 @@ pub fn read_file(
         }
     }
 
-    let content = if let Some(start) = start_line {
-        let start_idx = start.saturating_sub(1); // 1-based to 0-based
+    let (content, start_idx, end_idx) = if let Some(start) = start_line {
+        let start_idx = start.saturating_sub(1).min(total_lines); // 1-based to 0-based
         let end_idx = num_lines
-            .map(|n| (start_idx + n).min(total_lines))
+            .map(|n| start_idx.saturating_add(n).min(total_lines))
             .unwrap_or(total_lines);
 
-        lines[start_idx..end_idx].join("\n")
+        (lines[start_idx..end_idx].join("\n"), start_idx, end_idx)
     } else {
-        content
+        (content, 0, total_lines)
     };
 
     let estimated_tokens = content.len() / CHARS_PER_TOKEN;
@@ -254,9 +478,101 @@ pub fn read_file(
         size: metadata.len(),
         lines: total_lines,
         estimated_tokens,
+        start_line: start_idx + 1,
+        end_line: end_idx,
+    })
+}
+
+/// Bytes sniffed for a NUL byte when the extension doesn't say a file is text.
+const STAT_SNIFF_BYTES: usize = 8 * 1024;
+
+/// What [`stat_file`] found out about a path, without reading it into memory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStat {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// Line count, counted the way [`read_file`] does; `None` for
+    /// directories and binary files.
+    pub lines: Option<usize>,
+    pub is_binary: bool,
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Size, type and line count of a file or directory.
+///
+/// A file is text if [`is_text_file`] recognizes its name, or failing that
+/// if its first 8 KiB contain no NUL byte.
+pub fn stat_file(path: &str) -> Result<FileStat, FileError> {
+    let file_path = Path::new(path);
+    if !file_path.exists() {
+        return Err(FileError::NotFound(path.to_string()));
+    }
+
+    let metadata = fs::metadata(file_path)?;
+    let modified = metadata.modified().ok().map(chrono::DateTime::from);
+    if metadata.is_dir() {
+        return Ok(FileStat {
+            path: path.to_string(),
+            is_dir: true,
+            size: metadata.len(),
+            lines: None,
+            is_binary: false,
+            modified,
+        });
+    }
+
+    let is_binary = !is_text_file(path) && looks_binary(file_path)?;
+    let lines = if is_binary {
+        None
+    } else {
+        Some(count_lines(file_path)?)
+    };
+
+    Ok(FileStat {
+        path: path.to_string(),
+        is_dir: false,
+        size: metadata.len(),
+        lines,
+        is_binary,
+        modified,
     })
 }
 
+fn looks_binary(path: &Path) -> io::Result<bool> {
+    use std::io::Read;
+
+    let mut head = Vec::with_capacity(STAT_SNIFF_BYTES);
+    fs::File::open(path)?
+        .take(STAT_SNIFF_BYTES as u64)
+        .read_to_end(&mut head)?;
+    Ok(head.contains(&0))
+}
+
+/// Lines in the file, streamed so large files aren't held in memory. A
+/// trailing newline doesn't start another line, as with [`str::lines`].
+fn count_lines(path: &Path) -> io::Result<usize> {
+    use std::io::{BufRead, BufReader};
+
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let mut lines = 0;
+    let mut last = b'\n';
+    loop {
+        let buf = reader.fill_buf()?;
+        let Some(&end) = buf.last() else {
+            break;
+        };
+        lines += buf.iter().filter(|&&b| b == b'\n').count();
+        last = end;
+        let len = buf.len();
+        reader.consume(len);
+    }
+    if last != b'\n' {
+        lines += 1;
+    }
+    Ok(lines)
+}
+
 /// Write content to a file.
 pub fn write_file(path: &str, content: &str, create_dirs: bool) -> Result<(), FileError> {
     let file_path = Path::new(path);
@@ -271,12 +587,123 @@ pub fn write_file(path: &str, content: &str, create_dirs: bool) -> Result<(), Fi
     Ok(())
 }
 
+/// Create a directory along with any missing parents.
+///
+/// Returns whether anything was created. A directory already at `path` is
+/// fine unless `fail_if_exists` is set; a file there is always an error.
+pub fn make_directory(path: &str, fail_if_exists: bool) -> Result<bool, FileError> {
+    let dir = Path::new(path);
+
+    if dir.is_dir() {
+        if fail_if_exists {
+            return Err(FileError::AlreadyExists(path.to_string()));
+        }
+        return Ok(false);
+    }
+    if dir.exists() {
+        return Err(FileError::NotADirectory(path.to_string()));
+    }
+
+    fs::create_dir_all(dir)?;
+    Ok(true)
+}
+
+/// A file moved by [`move_file`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovedFile {
+    pub from: String,
+    pub to: String,
+}
+
+/// Move or rename a file.
+///
+/// Renames in place when `from` and `to` are on the same filesystem, which
+/// is atomic; otherwise copies and then deletes the original. Refuses to
+/// replace an existing file unless `overwrite` is set, and never replaces a
+/// directory. With `create_dirs`, missing parents of `to` are created.
+pub fn move_file(
+    from: &str,
+    to: &str,
+    overwrite: bool,
+    create_dirs: bool,
+) -> Result<MovedFile, FileError> {
+    let source = Path::new(from);
+    let destination = Path::new(to);
+
+    if !source.exists() {
+        return Err(FileError::NotFound(from.to_string()));
+    }
+    if !source.is_file() {
+        return Err(FileError::NotAFile(from.to_string()));
+    }
+    if destination.is_dir() {
+        return Err(FileError::NotAFile(to.to_string()));
+    }
+    if destination.exists() && !overwrite {
+        return Err(FileError::AlreadyExists(to.to_string()));
+    }
+
+    if create_dirs {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    match fs::rename(source, destination) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            fs::copy(source, destination)?;
+            if let Err(e) = fs::remove_file(source) {
+                // Don't leave the file in two places
+                let _ = fs::remove_file(destination);
+                return Err(e.into());
+            }
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    Ok(MovedFile {
+        from: from.to_string(),
+        to: to.to_string(),
+    })
+}
+
 /// Grep match result.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrepMatch {
     pub path: String,
     pub line_number: usize,
     pub content: String,
+    /// Context lines before the match that weren't already reported as
+    /// context after the previous one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<GrepContextLine>,
+    /// Context lines after the match, up to the next match.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<GrepContextLine>,
+}
+
+impl GrepMatch {
+    /// Number of the first line reported with this match.
+    pub fn first_line(&self) -> usize {
+        self.before
+            .first()
+            .map_or(self.line_number, |line| line.line_number)
+    }
+
+    /// Number of the last line reported with this match.
+    pub fn last_line(&self) -> usize {
+        self.after
+            .last()
+            .map_or(self.line_number, |line| line.line_number)
+    }
+}
+
+/// A line of context around a grep match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrepContextLine {
+    pub line_number: usize,
+    pub content: String,
 }
 
 /// Grep results.
@@ -284,6 +711,9 @@ pub struct GrepMatch {
 pub struct GrepResult {
     pub matches: Vec<GrepMatch>,
     pub total_matches: usize,
+    /// Offset of the next page, when there are more matches.
+    #[serde(default)]
+    pub next_cursor: Option<usize>,
 }
 
 /// Safety caps to prevent huge context blowups.
@@ -293,6 +723,7 @@ const GREP_MAX_MATCHES_PER_FILE: usize = 10;
 const GREP_MAX_LINE_LENGTH: usize = 512;
 const GREP_MAX_FILE_SIZE_BYTES: u64 = 5 * 1024 * 1024;
 const GREP_MAX_DEPTH: usize = 10;
+const GREP_MAX_CONTEXT_LINES: usize = 10;
 
 fn truncate_line(s: &str, max_chars: usize) -> (String, usize) {
     let char_count = s.chars().count();
@@ -304,8 +735,21 @@ fn truncate_line(s: &str, max_chars: usize) -> (String, usize) {
     }
 }
 
+/// A matched or context line as reported, truncated to a sane length.
+fn grep_line(bytes: &[u8]) -> String {
+    let raw = String::from_utf8_lossy(bytes);
+    let raw = raw.trim_end_matches(&['\r', '\n'][..]);
+    let (mut line, truncated_chars) = truncate_line(raw, GREP_MAX_LINE_LENGTH);
+    if truncated_chars > 0 {
+        line.push_str(&format!(" [...{} more chars]", truncated_chars));
+    }
+    line
+}
+
 struct MatchCollector {
     matches: Vec<GrepMatch>,
+    /// Context lines waiting for the match they precede.
+    pending_before: Vec<GrepContextLine>,
     file_path: String,
     max_matches: usize,
     max_per_file: usize,
@@ -324,43 +768,46 @@ impl Sink for MatchCollector {
             return Ok(false);
         }
 
-        let raw = String::from_utf8_lossy(mat.bytes());
-        let raw = raw.trim_end_matches(&['\r', '\n'][..]);
-        let (mut line_content, truncated_chars) = truncate_line(raw, GREP_MAX_LINE_LENGTH);
-
-        if truncated_chars > 0 {
-            line_content.push_str(&format!(" [...{} more chars]", truncated_chars));
-        }
-
         let line_number = mat.line_number().unwrap_or(0);
         let line_number = usize::try_from(line_number).unwrap_or(0);
 
         self.matches.push(GrepMatch {
             path: self.file_path.clone(),
             line_number,
-            content: line_content,
+            content: grep_line(mat.bytes()),
+            before: std::mem::take(&mut self.pending_before),
+            after: Vec::new(),
         });
 
         self.file_match_count += 1;
         Ok(true)
     }
-}
 
-/// Search for a pattern in files.
-pub fn grep(
-    pattern: &str,
-    directory: &str,
-    max_results: Option<usize>,
-) -> Result<GrepResult, FileError> {
-    let requested = max_results.unwrap_or(GREP_DEFAULT_MAX_MATCHES);
-    let max_matches = requested.min(GREP_HARD_MAX_MATCHES);
+    fn context(
+        &mut self,
+        _searcher: &Searcher,
+        context: &SinkContext<'_>,
+    ) -> Result<bool, Self::Error> {
+        let line = GrepContextLine {
+            line_number: usize::try_from(context.line_number().unwrap_or(0)).unwrap_or(0),
+            content: grep_line(context.bytes()),
+        };
 
-    if pattern.is_empty() {
-        return Err(FileError::GrepError(
-            "pattern must not be empty".to_string(),
-        ));
+        match context.kind() {
+            SinkContextKind::Before => self.pending_before.push(line),
+            SinkContextKind::After => {
+                if let Some(last) = self.matches.last_mut() {
+                    last.after.push(line);
+                }
+            }
+            SinkContextKind::Other => {}
+        }
+        Ok(true)
     }
+}
 
+/// Resolve the directory a search starts from.
+fn search_root(directory: &str) -> Result<PathBuf, FileError> {
     let path = PathBuf::from(directory);
     let abs_path = if path.is_absolute() {
         path
@@ -379,7 +826,16 @@ pub fn grep(
         )));
     }
 
-    // Support a tiny subset of common ripgrep-ish flags embedded in the pattern.
+    Ok(abs_path)
+}
+
+/// Build the line matcher for a search pattern, along with the regex it was
+/// built from.
+///
+/// Supports a tiny subset of common ripgrep-ish flags embedded in the
+/// pattern, and falls back to a literal search when the pattern isn't a
+/// valid regex.
+fn search_pattern(pattern: &str) -> Result<(RegexMatcher, String), FileError> {
     let (pattern, case_insensitive) = if let Some(rest) = pattern.strip_prefix("--ignore-case ") {
         (rest, true)
     } else if let Some(rest) = pattern.strip_prefix("-i ") {
@@ -395,88 +851,441 @@ pub fn grep(
         ));
     }
 
-    let regex_pattern = if case_insensitive {
-        format!("(?i){}", pattern)
-    } else {
-        pattern.to_string()
+    let with_flags = |regex: String| {
+        if case_insensitive {
+            format!("(?i){}", regex)
+        } else {
+            regex
+        }
     };
 
     // Try regex, then fall back to literal (same behavior as ticca-desktop).
-    let matcher = RegexMatcher::new_line_matcher(&regex_pattern)
-        .or_else(|_| {
-            let escaped = regex::escape(pattern);
-            let escaped_pattern = if case_insensitive {
-                format!("(?i){}", escaped)
-            } else {
-                escaped
-            };
-            RegexMatcher::new_line_matcher(&escaped_pattern)
-        })
+    let regex_pattern = with_flags(pattern.to_string());
+    if let Ok(matcher) = RegexMatcher::new_line_matcher(&regex_pattern) {
+        return Ok((matcher, regex_pattern));
+    }
+    let escaped_pattern = with_flags(regex::escape(pattern));
+    let matcher = RegexMatcher::new_line_matcher(&escaped_pattern)
         .map_err(|e| FileError::GrepError(format!("Invalid search pattern: {}", e)))?;
+    Ok((matcher, escaped_pattern))
+}
 
-    let walker = WalkBuilder::new(&abs_path)
+/// Text files under `root` worth searching, in a stable order.
+fn searchable_files(root: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkBuilder::new(root)
         .hidden(false)
         .git_ignore(true)
         .git_global(false)
         .git_exclude(false)
         .max_depth(Some(GREP_MAX_DEPTH))
         .max_filesize(Some(GREP_MAX_FILE_SIZE_BYTES))
+        .sort_by_file_name(|a, b| a.cmp(b))
         .filter_entry(|e| !should_ignore(&e.path().to_string_lossy()))
-        .build();
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            let path = path.to_string_lossy();
+            !should_ignore(&path) && is_text_file(&path)
+        })
+}
 
-    let mut searcher = Searcher::new();
-    let mut matches: Vec<GrepMatch> = Vec::new();
+/// `path` relative to `root`, or in full if it isn't under it.
+fn relative_to(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
 
-    for entry in walker.flatten() {
-        if matches.len() >= max_matches {
-            break;
-        }
+/// Search for a pattern in files.
+pub fn grep(
+    pattern: &str,
+    directory: &str,
+    max_results: Option<usize>,
+) -> Result<GrepResult, FileError> {
+    grep_with_progress(
+        pattern,
+        directory,
+        max_results,
+        0,
+        0,
+        0,
+        &mut ProgressReporter::disabled(),
+    )
+}
 
-        let entry_path = entry.path();
+/// Search for a pattern in files, reporting files scanned and matches found so far.
+///
+/// Each match carries up to `before` and `after` lines of context (capped at
+/// 10 each), like `grep -B` and `grep -A`.
+///
+/// Files are searched in a stable order, so matches past the first
+/// `max_results` can be paged through with `offset` and each result's
+/// `next_cursor`.
+pub fn grep_with_progress(
+    pattern: &str,
+    directory: &str,
+    max_results: Option<usize>,
+    offset: usize,
+    before: usize,
+    after: usize,
+    progress: &mut ProgressReporter,
+) -> Result<GrepResult, FileError> {
+    let requested = max_results.unwrap_or(GREP_DEFAULT_MAX_MATCHES);
+    let max_matches = requested.min(GREP_HARD_MAX_MATCHES);
+    let page_end = offset.saturating_add(max_matches);
+    // Search through the earlier pages, plus one match to tell if there's more
+    let limit = page_end.saturating_add(1);
 
-        let is_file = entry.file_type().map(|ft| ft.is_file()).unwrap_or(false);
-        if !is_file {
-            continue;
-        }
+    let (matcher, _) = search_pattern(pattern)?;
+    let abs_path = search_root(directory)?;
 
-        let entry_path_str = entry_path.to_string_lossy().to_string();
-        if should_ignore(&entry_path_str) || !is_text_file(&entry_path_str) {
-            continue;
+    let mut searcher = SearcherBuilder::new()
+        .before_context(before.min(GREP_MAX_CONTEXT_LINES))
+        .after_context(after.min(GREP_MAX_CONTEXT_LINES))
+        .build();
+    let mut matches: Vec<GrepMatch> = Vec::new();
+    let mut files_scanned = 0usize;
+
+    for entry_path in searchable_files(&abs_path) {
+        if matches.len() >= limit {
+            break;
         }
 
-        let relative_path = entry_path
-            .strip_prefix(&abs_path)
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| entry_path_str.clone());
+        let relative_path = relative_to(&entry_path, &abs_path);
 
         let mut collector = MatchCollector {
             matches: Vec::new(),
+            pending_before: Vec::new(),
             file_path: relative_path,
-            max_matches: max_matches - matches.len(),
+            max_matches: limit - matches.len(),
             max_per_file: GREP_MAX_MATCHES_PER_FILE,
             file_match_count: 0,
         };
 
         if searcher
-            .search_path(&matcher, entry_path, &mut collector)
+            .search_path(&matcher, &entry_path, &mut collector)
             .is_ok()
         {
             matches.extend(collector.matches);
         }
+
+        files_scanned += 1;
+        progress.report(files_scanned, Some(matches.len()));
     }
 
+    let next_cursor = (matches.len() > page_end).then_some(page_end);
+    matches.truncate(page_end);
+    matches.drain(..offset.min(matches.len()));
+
     Ok(GrepResult {
         total_matches: matches.len(),
         matches,
+        next_cursor,
     })
 }
 
+/// A line a replacement changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacedLine {
+    pub line_number: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// Replacements planned for one file.
+#[derive(Debug, Clone)]
+pub struct FileReplacement {
+    /// Path relative to the search directory.
+    pub path: String,
+    pub absolute_path: PathBuf,
+    /// Number of matches replaced.
+    pub replacements: usize,
+    pub lines: Vec<ReplacedLine>,
+    /// The file's content with every replacement made.
+    pub content: String,
+}
+
+/// Most files a single replacement may touch.
+pub const REPLACE_MAX_FILES: usize = 200;
+
+/// Work out what replacing `pattern` with `replacement` would change in the
+/// text files under `directory`, without writing anything.
+///
+/// Files are found with the same matcher and walk as [`grep`], and patterns
+/// match within a line. `replacement` may refer to capture groups as `$1` or
+/// `${name}`. Fails rather than planning edits to more than
+/// [`REPLACE_MAX_FILES`] files.
+pub fn plan_replacements(
+    pattern: &str,
+    replacement: &str,
+    directory: &str,
+) -> Result<Vec<FileReplacement>, FileError> {
+    let (matcher, regex_pattern) = search_pattern(pattern)?;
+    let regex = regex::Regex::new(&regex_pattern)
+        .map_err(|e| FileError::GrepError(format!("Invalid search pattern: {}", e)))?;
+    let abs_path = search_root(directory)?;
+
+    let mut searcher = Searcher::new();
+    let mut plans = Vec::new();
+
+    for entry_path in searchable_files(&abs_path) {
+        let mut matched_lines = HashSet::new();
+        let found = searcher.search_path(
+            &matcher,
+            &entry_path,
+            grep_searcher::sinks::Lossy(|line_number, _| {
+                matched_lines.insert(usize::try_from(line_number).unwrap_or(0));
+                Ok(true)
+            }),
+        );
+        if found.is_err() || matched_lines.is_empty() {
+            continue;
+        }
+
+        let original = fs::read_to_string(&entry_path)?;
+        let mut content = String::with_capacity(original.len());
+        let mut replacements = 0;
+        let mut lines = Vec::new();
+
+        for (line, line_number) in original.split_inclusive('\n').zip(1..) {
+            let text = line.trim_end_matches(&['\r', '\n'][..]);
+            if !matched_lines.contains(&line_number) {
+                content.push_str(line);
+                continue;
+            }
+
+            let replaced = regex.replace_all(text, replacement);
+            if replaced != text {
+                replacements += regex.find_iter(text).count();
+                lines.push(ReplacedLine {
+                    line_number,
+                    before: text.to_string(),
+                    after: replaced.to_string(),
+                });
+            }
+            content.push_str(&replaced);
+            content.push_str(&line[text.len()..]);
+        }
+
+        if lines.is_empty() {
+            continue;
+        }
+        if plans.len() == REPLACE_MAX_FILES {
+            return Err(FileError::GrepError(format!(
+                "Pattern matches more than {} files; narrow the directory or pattern",
+                REPLACE_MAX_FILES
+            )));
+        }
+
+        plans.push(FileReplacement {
+            path: relative_to(&entry_path, &abs_path),
+            absolute_path: entry_path,
+            replacements,
+            lines,
+            content,
+        });
+    }
+
+    Ok(plans)
+}
+
 /// Apply a unified diff to a file.
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn stat_file_reports_size_lines_and_binary() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let text = dir.path().join("notes.log");
+        let binary = dir.path().join("blob.bin");
+        fs::write(&text, "one\ntwo\nthree").expect("write failed");
+        fs::write(&binary, [0x7f, b'E', b'L', b'F', 0, 1]).expect("write failed");
+
+        let stat = stat_file(text.to_str().unwrap()).expect("stat failed");
+        assert_eq!(stat.size, 13);
+        assert_eq!(stat.lines, Some(3));
+        assert!(!stat.is_binary);
+        assert!(stat.modified.is_some());
+
+        let stat = stat_file(binary.to_str().unwrap()).expect("stat failed");
+        assert!(stat.is_binary);
+        assert_eq!(stat.lines, None);
+
+        let stat = stat_file(dir.path().to_str().unwrap()).expect("stat failed");
+        assert!(stat.is_dir);
+        assert!(matches!(
+            stat_file(dir.path().join("missing").to_str().unwrap()),
+            Err(FileError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn count_lines_matches_str_lines() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file = dir.path().join("f.txt");
+        for content in ["", "a", "a\n", "a\nb", "a\n\nb\n", "\n"] {
+            fs::write(&file, content).expect("write failed");
+            assert_eq!(
+                count_lines(&file).unwrap(),
+                content.lines().count(),
+                "{:?}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn make_directory_creates_parents_once() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let nested = dir.path().join("src").join("components");
+        let file = dir.path().join("file.txt");
+        fs::write(&file, "").expect("write failed");
+        let nested_path = nested.to_str().unwrap();
+
+        assert!(make_directory(nested_path, false).expect("mkdir failed"));
+        assert!(nested.is_dir());
+        assert!(!make_directory(nested_path, false).expect("mkdir failed"));
+        assert!(matches!(
+            make_directory(nested_path, true),
+            Err(FileError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            make_directory(file.to_str().unwrap(), false),
+            Err(FileError::NotADirectory(_))
+        ));
+    }
+
+    #[test]
+    fn move_file_renames_and_guards_destination() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let from = dir.path().join("old.rs");
+        let to = dir.path().join("nested").join("new.rs");
+        let taken = dir.path().join("taken.rs");
+        fs::write(&from, "mod a;").expect("write failed");
+        fs::write(&taken, "keep").expect("write failed");
+        let path = |p: &Path| p.to_str().unwrap().to_string();
+
+        assert!(matches!(
+            move_file(&path(&from), &path(&to), false, false),
+            Err(FileError::Io(_))
+        ));
+        let moved = move_file(&path(&from), &path(&to), false, true).expect("move failed");
+        assert_eq!(moved.to, path(&to));
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "mod a;");
+
+        assert!(matches!(
+            move_file(&path(&to), &path(&taken), false, false),
+            Err(FileError::AlreadyExists(_))
+        ));
+        assert_eq!(fs::read_to_string(&taken).unwrap(), "keep");
+        assert!(matches!(
+            move_file(&path(&to), dir.path().to_str().unwrap(), true, false),
+            Err(FileError::NotAFile(_))
+        ));
+
+        move_file(&path(&to), &path(&taken), true, false).expect("overwrite failed");
+        assert_eq!(fs::read_to_string(&taken).unwrap(), "mod a;");
+    }
+
+    #[test]
+    fn plan_replacements_rewrites_matching_lines() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        fs::write(
+            dir.path().join("a.rs"),
+            "let old_name = 1;\r\nkeep\nold_name + old_name\n",
+        )
+        .expect("write failed");
+        fs::write(dir.path().join("b.rs"), "nothing here\n").expect("write failed");
+
+        let plans = plan_replacements(r"old_(\w+)", "new_$1", dir.path().to_str().unwrap())
+            .expect("plan failed");
+
+        let [plan] = &plans[..] else {
+            panic!("expected one file, got {:?}", plans);
+        };
+        assert_eq!(plan.path, "a.rs");
+        assert_eq!(plan.replacements, 3);
+        assert_eq!(plan.lines.len(), 2);
+        assert_eq!(plan.lines[1].line_number, 3);
+        assert_eq!(plan.lines[1].after, "new_name + new_name");
+        assert_eq!(
+            plan.content,
+            "let new_name = 1;\r\nkeep\nnew_name + new_name\n"
+        );
+        // Planning doesn't write
+        let on_disk = fs::read_to_string(dir.path().join("a.rs")).unwrap();
+        assert!(on_disk.contains("old_name"));
+    }
+
+    #[test]
+    fn grep_pages_with_cursor() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        fs::write(dir.path().join("a.txt"), "foo 1\nfoo 2\n").expect("write failed");
+        fs::write(dir.path().join("b.txt"), "foo 3\n").expect("write failed");
+        let path = dir.path().to_str().unwrap();
+        let page = |offset| {
+            grep_with_progress(
+                "foo",
+                path,
+                Some(2),
+                offset,
+                0,
+                0,
+                &mut ProgressReporter::disabled(),
+            )
+            .expect("grep failed")
+        };
+
+        let first = page(0);
+        assert_eq!(first.next_cursor, Some(2));
+        let second = page(2);
+        assert_eq!(second.next_cursor, None);
+        let contents: Vec<_> = first
+            .matches
+            .iter()
+            .chain(&second.matches)
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(contents, ["foo 1", "foo 2", "foo 3"]);
+    }
+
+    #[test]
+    fn grep_includes_context_lines() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let content = "a\nfoo 1\nb\nc\nd\ne\nfoo 2\nf\n";
+        fs::write(dir.path().join("a.txt"), content).expect("write failed");
+
+        let result = grep_with_progress(
+            "foo",
+            dir.path().to_str().unwrap(),
+            None,
+            0,
+            1,
+            2,
+            &mut ProgressReporter::disabled(),
+        )
+        .expect("grep failed");
+
+        let lines = |context: &[GrepContextLine]| -> Vec<usize> {
+            context.iter().map(|line| line.line_number).collect()
+        };
+        let [first, second] = &result.matches[..] else {
+            panic!("expected two matches, got {:?}", result.matches);
+        };
+        assert_eq!(lines(&first.before), vec![1]);
+        assert_eq!(lines(&first.after), vec![3, 4]);
+        assert_eq!(lines(&second.before), vec![6]);
+        assert_eq!(lines(&second.after), vec![8]);
+        assert_eq!(second.before[0].content, "e");
+        assert_eq!((second.first_line(), second.last_line()), (6, 8));
+    }
+
     #[test]
     fn grep_finds_matches_and_line_numbers() {
         let dir = tempfile::tempdir().expect("tempdir failed");
@@ -592,6 +1401,30 @@ mod tests {
         assert!(!read_result.content.contains("Line 101"));
     }
 
+    #[test]
+    fn read_file_numbers_lines_from_start_line() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file_path = dir.path().join("twelve.txt");
+        let lines: Vec<String> = (1..=12).map(|i| format!("line {}", i)).collect();
+        fs::write(&file_path, lines.join("\n")).expect("write failed");
+        let path = file_path.to_str().unwrap();
+
+        let result = read_file(path, Some(8), Some(3), None).unwrap();
+        assert_eq!((result.start_line, result.end_line), (8, 10));
+        assert_eq!(result.content, "line 8\nline 9\nline 10");
+        assert_eq!(
+            result.numbered_content(),
+            " 8\tline 8\n 9\tline 9\n10\tline 10"
+        );
+
+        let result = read_file(path, Some(11), Some(50), None).unwrap();
+        assert_eq!((result.start_line, result.end_line), (11, 12));
+
+        let result = read_file(path, Some(40), Some(5), None).unwrap();
+        assert_eq!(result.content, "");
+        assert_eq!(result.numbered_content(), "");
+    }
+
     #[test]
     fn read_file_small_file_includes_token_estimate() {
         let dir = tempfile::tempdir().expect("tempdir failed");
@@ -635,4 +1468,205 @@ mod tests {
         assert_eq!(root_entry.depth, 0);
         assert_eq!(child_entry.depth, 1);
     }
+
+    #[test]
+    fn test_list_files_git_status() {
+        if which::which("git").is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(args)
+                .output()
+                .expect("git failed")
+        };
+        git(&["init", "-q"]);
+        fs::write(dir.path().join(".gitignore"), "secret.txt\n").expect("write failed");
+        fs::write(dir.path().join("staged.txt"), "a").expect("write failed");
+        fs::write(dir.path().join("new.txt"), "b").expect("write failed");
+        fs::write(dir.path().join("secret.txt"), "c").expect("write failed");
+        git(&["add", "staged.txt"]);
+
+        let path = dir.path().to_str().unwrap();
+        let result = list_files(path, false, None, None).unwrap();
+        let status = |name: &str| {
+            result
+                .entries
+                .iter()
+                .find(|e| e.name == name)
+                .map(|e| e.git_status)
+        };
+        assert_eq!(status("staged.txt"), Some(Some(GitStatus::Staged)));
+        assert_eq!(status("new.txt"), Some(Some(GitStatus::Untracked)));
+        assert_eq!(status("secret.txt"), None);
+
+        let options = ListFilesOptions {
+            recursive: false,
+            respect_gitignore: false,
+            ..ListFilesOptions::default()
+        };
+        let unfiltered =
+            list_files_with_progress(path, &options, &mut ProgressReporter::disabled()).unwrap();
+        assert!(unfiltered.entries.iter().any(|e| e.name == "secret.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_files_symlink_loop() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        fs::create_dir(dir.path().join("sub")).expect("mkdir failed");
+        fs::write(dir.path().join("sub").join("a.txt"), "a").expect("write failed");
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("sub").join("parent"))
+            .expect("symlink failed");
+        let path = dir.path().to_str().unwrap();
+
+        let result = list_files(path, true, None, None).unwrap();
+        let link = result.entries.iter().find(|e| e.name == "parent").unwrap();
+        assert!(link.is_dir && link.is_symlink);
+        assert_eq!(result.entries.len(), 3);
+        assert_eq!(result.skipped_symlinks, vec!["sub/parent".to_string()]);
+
+        let options = ListFilesOptions {
+            follow_symlinks: true,
+            ..ListFilesOptions::default()
+        };
+        let followed =
+            list_files_with_progress(path, &options, &mut ProgressReporter::disabled()).unwrap();
+        assert_eq!(followed.entries.len(), 3);
+        assert_eq!(followed.skipped_symlinks, vec!["sub/parent".to_string()]);
+    }
+
+    #[test]
+    fn test_list_files_pages_with_cursor() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        for i in 0..5 {
+            fs::write(dir.path().join(format!("f{}.txt", i)), "").expect("write failed");
+        }
+        let path = dir.path().to_str().unwrap();
+
+        let mut names = Vec::new();
+        let mut options = ListFilesOptions {
+            max_entries: Some(2),
+            ..ListFilesOptions::default()
+        };
+        loop {
+            let page = list_files_with_progress(path, &options, &mut ProgressReporter::disabled())
+                .unwrap();
+            names.extend(page.entries.into_iter().map(|e| e.name));
+            match page.next_cursor {
+                Some(cursor) => options.offset = cursor,
+                None => break,
+            }
+        }
+
+        assert_eq!(names, ["f0.txt", "f1.txt", "f2.txt", "f3.txt", "f4.txt"]);
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        fs::create_dir_all(dir.path().join("src").join("tools")).expect("mkdir failed");
+        fs::write(dir.path().join("src").join("main.rs"), "").expect("write failed");
+        fs::write(dir.path().join("src").join("tools").join("a.rs"), "").expect("write failed");
+        fs::write(dir.path().join("README.md"), "").expect("write failed");
+
+        let result = list_files(dir.path().to_str().unwrap(), true, None, None).unwrap();
+
+        let expected = [
+            ".",
+            "├── README.md",
+            "└── src/",
+            "    ├── main.rs",
+            "    └── tools/",
+            "        └── a.rs",
+        ];
+        assert_eq!(result.render_tree(".", None), expected.join("\n"));
+        assert_eq!(
+            result.render_tree(".", Some(1)),
+            ".\n├── README.md\n└── src/ [1 dirs, 2 files]"
+        );
+    }
+
+    // =========================================================================
+    // Progress Reporting Tests
+    // =========================================================================
+
+    fn make_large_tree(dir: &Path) {
+        for d in 0..10 {
+            let sub = dir.join(format!("dir{}", d));
+            fs::create_dir(&sub).expect("mkdir failed");
+            for f in 0..30 {
+                fs::write(sub.join(format!("file{}.txt", f)), "needle\n").expect("write failed");
+            }
+        }
+    }
+
+    fn collect_progress(
+        receiver: &mut crate::messaging::MessageReceiver,
+    ) -> Vec<crate::messaging::ToolProgressMessage> {
+        let mut events = Vec::new();
+        while let Ok(Some(msg)) = receiver.try_recv() {
+            if let crate::messaging::Message::ToolProgress(p) = msg {
+                events.push(p);
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn list_files_emits_progress_during_large_traversal() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        make_large_tree(dir.path());
+
+        let bus = crate::messaging::MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut progress = ProgressReporter::new(Some(bus.sender()), "list_files")
+            .with_step(50)
+            .with_interval(std::time::Duration::ZERO);
+
+        let result = list_files_with_progress(
+            dir.path().to_str().unwrap(),
+            &ListFilesOptions::default(),
+            &mut progress,
+        )
+        .expect("list_files failed");
+        assert_eq!(result.entries.len(), 310);
+
+        let events = collect_progress(&mut receiver);
+        assert_eq!(events.len(), 6);
+        assert!(events.iter().all(|p| p.tool_name == "list_files"));
+        assert!(events.windows(2).all(|w| w[0].processed < w[1].processed));
+    }
+
+    #[test]
+    fn grep_emits_progress_with_match_counts() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        make_large_tree(dir.path());
+
+        let bus = crate::messaging::MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut progress = ProgressReporter::new(Some(bus.sender()), "grep")
+            .with_step(10)
+            .with_interval(std::time::Duration::ZERO);
+
+        grep_with_progress(
+            "needle",
+            dir.path().to_str().unwrap(),
+            Some(GREP_HARD_MAX_MATCHES),
+            0,
+            0,
+            0,
+            &mut progress,
+        )
+        .expect("grep failed");
+
+        let events = collect_progress(&mut receiver);
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|p| p.matches.is_some()));
+        assert_eq!(events[0].processed, 10);
+        assert_eq!(events[0].matches, Some(10));
+    }
 }
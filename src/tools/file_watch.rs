@@ -0,0 +1,186 @@
+//! Watching files the agent has read.
+//!
+//! When enabled, `read_file` registers every file it reads with a
+//! [`FileWatcher`]. Changes made outside the agent are collected until the
+//! next turn, which starts with a note listing them so the model knows its
+//! copy is stale.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{debug, warn};
+
+/// Tracks files read during a session and which of them have changed since.
+///
+/// Cheap to clone; clones share the same watcher.
+#[derive(Clone)]
+pub struct FileWatcher {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    watcher: Mutex<RecommendedWatcher>,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Files the agent has read.
+    files: HashSet<PathBuf>,
+    /// Directories being watched. Editors often save by replacing the file,
+    /// which a watch on the file itself wouldn't survive.
+    dirs: HashSet<PathBuf>,
+    /// Read files changed since the last [`FileWatcher::take_changes`].
+    changed: BTreeSet<PathBuf>,
+}
+
+impl FileWatcher {
+    /// Start a watcher with nothing watched yet.
+    pub fn new() -> notify::Result<Self> {
+        let state = Arc::new(Mutex::new(State::default()));
+        let events = Arc::clone(&state);
+        let watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+            Ok(event) => record(&events, &event),
+            Err(e) => warn!(error = %e, "File watcher error"),
+        })?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                watcher: Mutex::new(watcher),
+                state,
+            }),
+        })
+    }
+
+    /// Watch `path` for changes. Failures are logged and otherwise ignored;
+    /// a file we can't watch just won't be reported.
+    pub fn watch(&self, path: &Path) {
+        let Ok(path) = path.canonicalize() else {
+            return;
+        };
+        let Some(dir) = path.parent().map(Path::to_path_buf) else {
+            return;
+        };
+
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            state.files.insert(path);
+            if !state.dirs.insert(dir.clone()) {
+                return;
+            }
+        }
+
+        // Not holding the state lock: the event handler takes it, and some
+        // backends wait on their event thread while adding a watch.
+        let mut watcher = self.inner.watcher.lock().unwrap();
+        match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            Ok(()) => debug!(dir = %dir.display(), "Watching directory"),
+            Err(e) => {
+                warn!(dir = %dir.display(), error = %e, "Failed to watch directory");
+                self.inner.state.lock().unwrap().dirs.remove(&dir);
+            }
+        }
+    }
+
+    /// Read files changed since the last call, sorted by path.
+    pub fn take_changes(&self) -> Vec<PathBuf> {
+        let mut state = self.inner.state.lock().unwrap();
+        std::mem::take(&mut state.changed).into_iter().collect()
+    }
+
+    /// Forget pending changes, e.g. ones the agent made itself.
+    pub fn clear_changes(&self) {
+        self.inner.state.lock().unwrap().changed.clear();
+    }
+}
+
+impl fmt::Debug for FileWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.inner.state.lock().unwrap();
+        f.debug_struct("FileWatcher")
+            .field("files", &state.files.len())
+            .field("changed", &state.changed)
+            .finish()
+    }
+}
+
+/// Note the agent's next turn starts with, or `None` if nothing changed.
+pub fn changed_files_note(paths: &[PathBuf]) -> Option<String> {
+    if paths.is_empty() {
+        return None;
+    }
+
+    let mut note = String::from(
+        "[System note: these files changed on disk since you read them; \
+         re-read them before relying on their contents]\n",
+    );
+    for path in paths {
+        note.push_str(&format!("- {}\n", path.display()));
+    }
+    Some(note)
+}
+
+fn record(state: &Mutex<State>, event: &Event) {
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return;
+    }
+
+    let mut state = state.lock().unwrap();
+    for path in &event.paths {
+        if state.files.contains(path) {
+            state.changed.insert(path.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, ModifyKind};
+
+    fn event(kind: EventKind, path: &Path) -> Event {
+        Event::new(kind).add_path(path.to_path_buf())
+    }
+
+    #[test]
+    fn test_record_only_tracks_read_files() {
+        let state = Mutex::new(State::default());
+        state
+            .lock()
+            .unwrap()
+            .files
+            .insert(PathBuf::from("/p/read.rs"));
+
+        record(
+            &state,
+            &event(EventKind::Modify(ModifyKind::Any), Path::new("/p/read.rs")),
+        );
+        record(
+            &state,
+            &event(EventKind::Modify(ModifyKind::Any), Path::new("/p/other.rs")),
+        );
+        record(
+            &state,
+            &event(EventKind::Access(AccessKind::Any), Path::new("/p/read.rs")),
+        );
+
+        let changed: Vec<_> = state.lock().unwrap().changed.iter().cloned().collect();
+        assert_eq!(changed, vec![PathBuf::from("/p/read.rs")]);
+    }
+
+    #[test]
+    fn test_changed_files_note() {
+        assert_eq!(changed_files_note(&[]), None);
+
+        let note =
+            changed_files_note(&[PathBuf::from("/p/a.rs"), PathBuf::from("/p/b.rs")]).unwrap();
+        assert!(note.starts_with("[System note:"));
+        assert!(note.contains("- /p/a.rs\n- /p/b.rs\n"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Git status for file listings.
+//!
+//! Shells out to `git status` once per listing, which gives both each
+//! file's status and what `.gitignore` excludes. The repository being
+//! listed may be untrusted, so git runs with the config that could start
+//! other programs (`core.fsmonitor`) turned off.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+/// A file's state in git.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitStatus {
+    /// Not tracked by git.
+    Untracked,
+    /// Changed in the working tree, whether or not other changes are staged.
+    Modified,
+    /// Changes staged and nothing more in the working tree.
+    Staged,
+}
+
+impl fmt::Display for GitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Untracked => "untracked",
+            Self::Modified => "modified",
+            Self::Staged => "staged",
+        })
+    }
+}
+
+/// Status of the repository a directory is in.
+#[derive(Debug, Default)]
+pub struct RepoStatus {
+    root: PathBuf,
+    /// Dirty paths relative to `root`. Untracked directories end in `/`.
+    statuses: HashMap<String, GitStatus>,
+    /// Ignored paths relative to `root`. Directories end in `/`.
+    ignored: HashSet<String>,
+}
+
+impl RepoStatus {
+    /// Status of the repository containing `dir`, or `None` outside one or
+    /// when git isn't installed.
+    pub fn load(dir: &Path) -> Option<Self> {
+        let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let root = PathBuf::from(root.trim_end()).canonicalize().ok()?;
+        let output = git(dir, &["status", "--porcelain=v1", "-z", "--ignored"])?;
+        Some(Self::parse(root, &output))
+    }
+
+    fn parse(root: PathBuf, porcelain: &str) -> Self {
+        let mut status = Self {
+            root,
+            ..Self::default()
+        };
+
+        let mut fields = porcelain.split('\0');
+        while let Some(field) = fields.next() {
+            let (Some(code), Some(path)) = (field.get(..2), field.get(3..)) else {
+                continue;
+            };
+            let (index, worktree) = (code.as_bytes()[0], code.as_bytes()[1]);
+
+            // Renames and copies are followed by the original path
+            if matches!(index, b'R' | b'C') {
+                fields.next();
+            }
+
+            let state = match (index, worktree) {
+                (b'!', b'!') => {
+                    status.ignored.insert(path.to_string());
+                    continue;
+                }
+                (b'?', b'?') => GitStatus::Untracked,
+                (_, b' ') => GitStatus::Staged,
+                _ => GitStatus::Modified,
+            };
+            status.statuses.insert(path.to_string(), state);
+        }
+
+        status
+    }
+
+    /// Status of the file or directory at `path`, if it's dirty.
+    pub fn status_of(&self, path: &Path, is_dir: bool) -> Option<GitStatus> {
+        let relative = self.relative(path, is_dir)?;
+        if let Some(state) = self.statuses.get(&relative) {
+            return Some(*state);
+        }
+
+        // Everything inside an untracked directory is untracked
+        parent_dirs(&relative)
+            .any(|dir| self.statuses.get(dir) == Some(&GitStatus::Untracked))
+            .then_some(GitStatus::Untracked)
+    }
+
+    /// Whether `.gitignore` excludes `path`.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(relative) = self.relative(path, is_dir) else {
+            return false;
+        };
+        self.ignored.contains(&relative)
+            || parent_dirs(&relative).any(|dir| self.ignored.contains(dir))
+    }
+
+    /// `path` relative to the repository root, in git's form.
+    fn relative(&self, path: &Path, is_dir: bool) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let mut relative = relative.to_string_lossy().replace('\\', "/");
+        if is_dir {
+            relative.push('/');
+        }
+        Some(relative)
+    }
+}
+
+/// The directories above `relative`, each ending in `/`: `a/`, `a/b/` for
+/// `a/b/c`.
+fn parent_dirs(relative: &str) -> impl Iterator<Item = &str> {
+    let trimmed = relative.trim_end_matches('/');
+    trimmed.match_indices('/').map(|(i, _)| &relative[..=i])
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "-c",
+            "core.fsmonitor=false",
+            "-c",
+            "core.untrackedCache=false",
+        ])
+        .args(args)
+        // Don't rewrite the index of a repository we're only looking at
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo() -> RepoStatus {
+        RepoStatus::parse(
+            PathBuf::from("/repo"),
+            " M src/lib.rs\0M  src/main.rs\0MM Cargo.toml\0R  new.rs\0old.rs\0\
+             ?? notes/\0?? todo.txt\0!! target/\0!! .env\0",
+        )
+    }
+
+    #[test]
+    fn test_status_of() {
+        let repo = repo();
+        let status = |path: &str| repo.status_of(Path::new(path), false);
+
+        assert_eq!(status("/repo/src/lib.rs"), Some(GitStatus::Modified));
+        assert_eq!(status("/repo/src/main.rs"), Some(GitStatus::Staged));
+        assert_eq!(status("/repo/Cargo.toml"), Some(GitStatus::Modified));
+        assert_eq!(status("/repo/new.rs"), Some(GitStatus::Staged));
+        assert_eq!(status("/repo/old.rs"), None);
+        assert_eq!(status("/repo/todo.txt"), Some(GitStatus::Untracked));
+        assert_eq!(status("/repo/notes/a.md"), Some(GitStatus::Untracked));
+        assert_eq!(status("/repo/README.md"), None);
+        assert_eq!(
+            repo.status_of(Path::new("/repo/notes"), true),
+            Some(GitStatus::Untracked)
+        );
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        let repo = repo();
+
+        assert!(repo.is_ignored(Path::new("/repo/target"), true));
+        assert!(repo.is_ignored(Path::new("/repo/target/debug/spot"), false));
+        assert!(repo.is_ignored(Path::new("/repo/.env"), false));
+        assert!(!repo.is_ignored(Path::new("/repo/src/lib.rs"), false));
+        assert!(!repo.is_ignored(Path::new("/elsewhere/.env"), false));
+    }
+
+    #[test]
+    fn test_parent_dirs() {
+        let dirs: Vec<_> = parent_dirs("a/b/c").collect();
+        assert_eq!(dirs, vec!["a/", "a/b/"]);
+        let dirs: Vec<_> = parent_dirs("a/b/").collect();
+        assert_eq!(dirs, vec!["a/"]);
+        assert_eq!(parent_dirs("top.rs").count(), 0);
+    }
+}
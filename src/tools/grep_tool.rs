@@ -10,16 +10,32 @@ use tracing::{debug, warn};
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
 use super::file_ops;
+use super::progress::ProgressReporter;
+use crate::messaging::MessageSender;
 
 /// Tool for searching text patterns across files.
 #[derive(Debug, Clone, Default)]
-pub struct GrepTool;
+pub struct GrepTool {
+    /// Optional message bus for publishing progress on large searches.
+    bus: Option<MessageSender>,
+}
+
+impl GrepTool {
+    /// Publish progress events to the message bus while searching.
+    pub fn with_bus(mut self, sender: MessageSender) -> Self {
+        self.bus = Some(sender);
+        self
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct GrepArgs {
     pattern: String,
     directory: Option<String>,
     max_results: Option<usize>,
+    offset: Option<usize>,
+    before: Option<usize>,
+    after: Option<usize>,
 }
 
 #[async_trait]
@@ -29,7 +45,7 @@ impl Tool for GrepTool {
             "grep",
             "Recursively search for text patterns across files. \
              Searches across recognized text file types while limiting results for performance. \
-             Safety rails: max 200 matches total, max 10 per file, lines truncated at 512 chars, files over 5MB skipped.",
+             Safety rails: max 200 matches per page (page with offset), max 10 per file, lines truncated at 512 chars, files over 5MB skipped.",
         )
         .with_parameters(
             SchemaBuilder::new()
@@ -48,6 +64,22 @@ impl Tool for GrepTool {
                     "Maximum number of matches to return. Defaults to 100.",
                     false,
                 )
+                .integer(
+                    "offset",
+                    "Matches to skip, to page past max_results. Use the offset a \
+                     truncated search suggests.",
+                    false,
+                )
+                .integer(
+                    "before",
+                    "Lines of context to show before each match, like grep -B. Defaults to 0 (max 10).",
+                    false,
+                )
+                .integer(
+                    "after",
+                    "Lines of context to show after each match, like grep -A. Defaults to 0 (max 10).",
+                    false,
+                )
                 .build()
                 .expect("schema build failed"),
         )
@@ -66,7 +98,17 @@ impl Tool for GrepTool {
 
         let directory = args.directory.as_deref().unwrap_or(".");
 
-        match file_ops::grep(&args.pattern, directory, args.max_results) {
+        let mut progress = ProgressReporter::new(self.bus.clone(), "grep");
+
+        match file_ops::grep_with_progress(
+            &args.pattern,
+            directory,
+            args.max_results,
+            args.offset.unwrap_or(0),
+            args.before.unwrap_or(0),
+            args.after.unwrap_or(0),
+            &mut progress,
+        ) {
             Ok(result) => {
                 if result.matches.is_empty() {
                     return Ok(ToolReturn::text(format!(
@@ -80,8 +122,37 @@ impl Tool for GrepTool {
                     result.total_matches, args.pattern, directory
                 );
 
+                // Like grep: `:` after match lines, `-` after context lines,
+                // and `--` between groups that aren't contiguous
+                let with_context = args.before.unwrap_or(0) + args.after.unwrap_or(0) > 0;
+                let mut previous: Option<&file_ops::GrepMatch> = None;
                 for m in &result.matches {
+                    let contiguous = previous
+                        .is_some_and(|p| p.path == m.path && p.last_line() + 1 >= m.first_line());
+                    if with_context && previous.is_some() && !contiguous {
+                        output.push_str("\n--");
+                    }
+                    for line in &m.before {
+                        output.push_str(&format!(
+                            "\n{}-{}-{}",
+                            m.path, line.line_number, line.content
+                        ));
+                    }
                     output.push_str(&format!("\n{}:{}:{}", m.path, m.line_number, m.content));
+                    for line in &m.after {
+                        output.push_str(&format!(
+                            "\n{}-{}-{}",
+                            m.path, line.line_number, line.content
+                        ));
+                    }
+                    previous = Some(m);
+                }
+
+                if let Some(cursor) = result.next_cursor {
+                    output.push_str(&format!(
+                        "\n\n[More matches available: call again with offset={}]",
+                        cursor
+                    ));
                 }
 
                 Ok(ToolReturn::text(output))
@@ -98,21 +169,21 @@ mod tests {
 
     #[test]
     fn test_definition_returns_correct_name() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let def = tool.definition();
         assert_eq!(def.name(), "grep");
     }
 
     #[test]
     fn test_definition_has_description() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let def = tool.definition();
         assert!(def.description().contains("search"));
     }
 
     #[test]
     fn test_definition_has_parameters() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let def = tool.definition();
         let params = def.parameters();
         assert!(params.is_object());
@@ -128,7 +199,7 @@ mod tests {
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "hello world\nfoo bar\nhello again").expect("write failed");
 
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -148,13 +219,37 @@ mod tests {
         assert!(text.contains("hello"));
     }
 
+    #[tokio::test]
+    async fn test_call_with_context_separates_groups() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file_path = dir.path().join("test.txt");
+        fs::write(&file_path, "hit\nx\ny\nz\nhit\n").expect("write failed");
+
+        let tool = GrepTool::default();
+        let ctx = RunContext::minimal("test");
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "pattern": "hit",
+                    "directory": dir.path().to_str().unwrap(),
+                    "after": 1
+                }),
+            )
+            .await
+            .unwrap();
+
+        let text = ret.as_text().unwrap();
+        assert!(text.ends_with("\ntest.txt:1:hit\ntest.txt-2-x\n--\ntest.txt:5:hit"));
+    }
+
     #[tokio::test]
     async fn test_call_no_matches() {
         let dir = tempfile::tempdir().expect("tempdir failed");
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "hello world").expect("write failed");
 
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -179,7 +274,7 @@ mod tests {
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "line1\nline2\nline3\nline4\nline5").expect("write failed");
 
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -199,7 +294,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_invalid_directory() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -224,7 +319,7 @@ mod tests {
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "has [invalid bracket").expect("write failed");
 
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -246,7 +341,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_missing_pattern_returns_error() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(&ctx, serde_json::json!({ "directory": "/tmp" }))
@@ -256,7 +351,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_wrong_type_pattern_returns_error() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool.call(&ctx, serde_json::json!({ "pattern": 123 })).await;
         assert!(result.is_err());
@@ -264,7 +359,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_wrong_type_directory_returns_error() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -277,7 +372,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_wrong_type_max_results_returns_error() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         let result = tool
             .call(
@@ -294,7 +389,7 @@ mod tests {
         let dir = tempfile::tempdir().expect("tempdir");
         std::fs::write(dir.path().join("test.txt"), "hello world").unwrap();
 
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let ctx = RunContext::minimal("test");
         // Omit directory - should default to "." but we pass explicit to be reliable
         let result = tool
@@ -311,14 +406,14 @@ mod tests {
 
     #[test]
     fn test_tool_debug_impl() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let debug_str = format!("{:?}", tool);
         assert!(debug_str.contains("GrepTool"));
     }
 
     #[test]
     fn test_tool_clone_impl() {
-        let tool = GrepTool;
+        let tool = GrepTool::default();
         let cloned = tool.clone();
         assert_eq!(tool.definition().name(), cloned.definition().name());
     }
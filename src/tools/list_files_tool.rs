@@ -10,13 +10,26 @@ use tracing::{debug, warn};
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
 use super::file_ops;
+use super::progress::ProgressReporter;
+use crate::messaging::MessageSender;
 
 /// Maximum characters in list_files output to protect context window
 const LIST_FILES_MAX_OUTPUT_CHARS: usize = 100_000;
 
 /// Tool for listing files in a directory.
 #[derive(Debug, Clone, Default)]
-pub struct ListFilesTool;
+pub struct ListFilesTool {
+    /// Optional message bus for publishing progress on large listings.
+    bus: Option<MessageSender>,
+}
+
+impl ListFilesTool {
+    /// Publish progress events to the message bus while listing.
+    pub fn with_bus(mut self, sender: MessageSender) -> Self {
+        self.bus = Some(sender);
+        self
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct ListFilesArgs {
@@ -24,6 +37,11 @@ struct ListFilesArgs {
     recursive: Option<bool>,
     max_depth: Option<usize>,
     max_entries: Option<usize>,
+    format: Option<String>,
+    collapse_depth: Option<usize>,
+    respect_gitignore: Option<bool>,
+    follow_symlinks: Option<bool>,
+    offset: Option<usize>,
 }
 
 #[async_trait]
@@ -33,7 +51,8 @@ impl Tool for ListFilesTool {
             "list_files",
             "List files and directories with intelligent filtering. \
              Automatically ignores common build artifacts, cache directories, \
-             and other noise while providing rich file metadata.",
+             and other noise while providing rich file metadata. Inside a git \
+             repository, changed files are marked [modified], [staged] or [untracked].",
         )
         .with_parameters(
             SchemaBuilder::new()
@@ -58,6 +77,35 @@ impl Tool for ListFilesTool {
                     "Maximum number of entries to return. Defaults to 2000 (hard cap: 10000).",
                     false,
                 )
+                .integer(
+                    "offset",
+                    "Entries to skip, to page through listings larger than max_entries. \
+                     Use the offset a truncated listing suggests.",
+                    false,
+                )
+                .boolean(
+                    "respect_gitignore",
+                    "Leave out files the repository's .gitignore excludes. Defaults to true.",
+                    false,
+                )
+                .boolean(
+                    "follow_symlinks",
+                    "Descend into symlinked directories. Each directory is still listed \
+                     only once, so symlink loops are safe. Defaults to false.",
+                    false,
+                )
+                .string(
+                    "format",
+                    "'list' (default) for entries with sizes, or 'tree' for an indented \
+                     tree that shows project structure at a glance.",
+                    false,
+                )
+                .integer(
+                    "collapse_depth",
+                    "With format='tree', show directories this many levels down with a \
+                     count of their contents instead of expanding them.",
+                    false,
+                )
                 .build()
                 .expect("schema build failed"),
         )
@@ -76,33 +124,66 @@ impl Tool for ListFilesTool {
 
         let directory = args.directory.as_deref().unwrap_or(".");
         let recursive = args.recursive.unwrap_or(true);
-        let max_depth = args.max_depth;
-        let max_entries = args.max_entries;
+        let options = file_ops::ListFilesOptions {
+            recursive,
+            max_depth: args.max_depth,
+            max_entries: args.max_entries,
+            offset: args.offset.unwrap_or(0),
+            respect_gitignore: args.respect_gitignore.unwrap_or(true),
+            follow_symlinks: args.follow_symlinks.unwrap_or(false),
+        };
+        let tree = match args.format.as_deref() {
+            None | Some("list") => false,
+            Some("tree") => true,
+            Some(other) => {
+                return Ok(ToolReturn::error(format!(
+                    "Unknown format '{}': use 'list' or 'tree'",
+                    other
+                )))
+            }
+        };
 
-        match file_ops::list_files(directory, recursive, max_depth, max_entries) {
+        let mut progress = ProgressReporter::new(self.bus.clone(), "list_files");
+
+        match file_ops::list_files_with_progress(directory, &options, &mut progress) {
             Ok(result) => {
                 // Format as a readable summary with file tree
-                let mut output =
-                    format!("DIRECTORY LISTING: {} (recursive={})", directory, recursive);
-
-                for entry in &result.entries {
-                    let indent = "  ".repeat(entry.depth);
-                    let marker = if entry.is_dir { "/" } else { "" };
-                    let size = if entry.is_dir {
-                        String::new()
-                    } else {
-                        format!(" ({} bytes)", entry.size)
-                    };
-                    output.push_str(&format!("\n{}{}{}{}", indent, entry.name, marker, size));
-                }
-
-                let truncation_note = if result.truncated {
+                let mut output = if tree {
                     format!(
-                        " (truncated to {} entries; totals reflect returned entries only)",
-                        result.max_entries
+                        "DIRECTORY TREE:\n{}",
+                        result.render_tree(directory, args.collapse_depth)
                     )
                 } else {
-                    String::new()
+                    let mut listing =
+                        format!("DIRECTORY LISTING: {} (recursive={})", directory, recursive);
+                    for entry in &result.entries {
+                        let indent = "  ".repeat(entry.depth);
+                        let marker = if entry.is_dir { "/" } else { "" };
+                        let size = match (entry.is_dir, entry.is_symlink) {
+                            (true, false) => String::new(),
+                            (true, true) => " (symlink)".to_string(),
+                            (false, false) => format!(" ({} bytes)", entry.size),
+                            (false, true) => format!(" (symlink, {} bytes)", entry.size),
+                        };
+                        let status = entry
+                            .git_status
+                            .map(|status| format!(" [{}]", status))
+                            .unwrap_or_default();
+                        listing.push_str(&format!(
+                            "\n{}{}{}{}{}",
+                            indent, entry.name, marker, size, status
+                        ));
+                    }
+                    listing
+                };
+
+                let truncation_note = match result.next_cursor {
+                    Some(cursor) => format!(
+                        " (truncated to {} entries; totals reflect returned entries only; \
+                         continue with offset={})",
+                        result.max_entries, cursor
+                    ),
+                    None => String::new(),
                 };
 
                 output.push_str(&format!(
@@ -110,6 +191,14 @@ impl Tool for ListFilesTool {
                     result.total_files, result.total_dirs, result.total_size, truncation_note
                 ));
 
+                if !result.skipped_symlinks.is_empty() {
+                    output.push_str(&format!(
+                        "\nSymlinked directories not descended into (not following symlinks, \
+                         or already listed): {}",
+                        result.skipped_symlinks.join(", ")
+                    ));
+                }
+
                 // Protect against massive output overwhelming context
                 if output.len() > LIST_FILES_MAX_OUTPUT_CHARS {
                     output.truncate(LIST_FILES_MAX_OUTPUT_CHARS);
@@ -133,7 +222,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_list_files_tool() {
-        let tool = ListFilesTool;
+        let tool = ListFilesTool::default();
         let ctx = RunContext::minimal("test");
 
         // Test with current directory
@@ -149,4 +238,33 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_list_files_tool_tree_format() {
+        let tool = ListFilesTool::default();
+        let ctx = RunContext::minimal("test");
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub").join("a.txt"), "a").unwrap();
+
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "directory": dir.path().to_str().unwrap(),
+                    "format": "tree"
+                }),
+            )
+            .await
+            .unwrap();
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("└── sub/\n    └── a.txt"));
+        assert!(text.contains("Summary: 1 files, 1 directories"));
+
+        let ret = tool
+            .call(&ctx, serde_json::json!({ "format": "graph" }))
+            .await
+            .unwrap();
+        assert!(ret.as_text().unwrap().contains("Unknown format"));
+    }
 }
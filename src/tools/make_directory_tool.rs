@@ -0,0 +1,112 @@
+//! MakeDirectory tool implementation.
+//!
+//! Provides a serdesAI-compatible tool for creating directories, e.g. to lay
+//! out a new project before writing files into it.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::{debug, warn};
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use super::file_ops::{self, FileError};
+
+/// Tool for creating directories.
+#[derive(Debug, Clone, Default)]
+pub struct MakeDirectoryTool;
+
+#[derive(Debug, Deserialize)]
+struct MakeDirectoryArgs {
+    path: String,
+    #[serde(default)]
+    fail_if_exists: bool,
+}
+
+#[async_trait]
+impl Tool for MakeDirectoryTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "make_directory",
+            "Create a directory, including any missing parent directories. \
+             Succeeds if it already exists unless fail_if_exists is set.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string("path", "Path of the directory to create.", true)
+                .boolean(
+                    "fail_if_exists",
+                    "Return an error if the directory already exists. Defaults to false.",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "make_directory", ?args, "Tool called");
+
+        let args: MakeDirectoryArgs = serde_json::from_value(args.clone()).map_err(|e| {
+            warn!(tool = "make_directory", error = %e, ?args, "Failed to parse arguments");
+            serdes_ai_tools::ToolError::execution_failed(format!(
+                "Invalid arguments: {}. Got: {}",
+                e, args
+            ))
+        })?;
+
+        match file_ops::make_directory(&args.path, args.fail_if_exists) {
+            Ok(true) => Ok(ToolReturn::text(format!(
+                "Created directory: {}",
+                args.path
+            ))),
+            Ok(false) => Ok(ToolReturn::text(format!(
+                "Directory already exists: {}",
+                args.path
+            ))),
+            Err(FileError::AlreadyExists(path)) => Ok(ToolReturn::error(format!(
+                "Directory already exists: {}",
+                path
+            ))),
+            Err(FileError::NotADirectory(path)) => Ok(ToolReturn::error(format!(
+                "A file already exists at {}",
+                path
+            ))),
+            Err(e) => Ok(ToolReturn::error(format!(
+                "Failed to create directory: {}",
+                e
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_call_creates_nested_directory() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let nested = dir.path().join("src").join("components");
+        let args = |fail_if_exists: bool| {
+            serde_json::json!({
+                "path": nested.to_str().unwrap(),
+                "fail_if_exists": fail_if_exists
+            })
+        };
+
+        let tool = MakeDirectoryTool;
+        let ctx = RunContext::minimal("test");
+
+        let ret = tool.call(&ctx, args(false)).await.unwrap();
+        assert!(!ret.is_error());
+        assert!(ret.as_text().unwrap().starts_with("Created directory"));
+        assert!(nested.is_dir());
+
+        let ret = tool.call(&ctx, args(false)).await.unwrap();
+        assert!(!ret.is_error());
+
+        let ret = tool.call(&ctx, args(true)).await.unwrap();
+        assert!(ret.is_error());
+    }
+}
@@ -17,23 +17,41 @@
 // Core operations (low-level)
 pub mod agent_tools;
 mod common;
+mod completion;
 pub mod diff;
 mod file_ops;
+mod file_watch;
+mod git_status;
+mod progress;
+mod project_ignore;
+mod references;
 mod shell;
+mod undo;
 
 // Tool implementations (serdesAI wrappers)
 mod delete_file_tool;
 mod edit_file_tool;
 mod grep_tool;
 mod list_files_tool;
+mod make_directory_tool;
+mod move_file_tool;
 mod read_file_tool;
 mod reasoning_tool;
+mod replace_in_files_tool;
 mod shell_tool;
+mod stat_file_tool;
 
 // Registry
 pub mod registry;
 
 // Re-export low-level operations (for direct use)
+pub use completion::{complete_input, complete_path};
+pub use file_watch::{changed_files_note, FileWatcher};
+pub use references::{
+    expand_file_references, ExpandedPrompt, MAX_IMAGE_BYTES, MAX_REFERENCE_BYTES,
+};
+pub use shell::CommandRules;
+pub use undo::{UndoError, UndoJournal, UndoReport, UndoRun};
 
 // Re-export tool types for convenience
 
@@ -0,0 +1,169 @@
+//! MoveFile tool implementation.
+//!
+//! Provides a serdesAI-compatible tool for moving or renaming files. With an
+//! [`UndoRun`] attached, both paths are saved first, so undoing puts the file
+//! back and removes it from its new location.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::{debug, warn};
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use super::file_ops::{self, FileError};
+use super::undo::UndoRun;
+
+/// Tool for moving or renaming files.
+#[derive(Debug, Clone, Default)]
+pub struct MoveFileTool {
+    undo: Option<UndoRun>,
+}
+
+impl MoveFileTool {
+    /// Save both paths to `run` before each move.
+    pub fn with_undo(mut self, run: UndoRun) -> Self {
+        self.undo = Some(run);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MoveFileArgs {
+    from: String,
+    to: String,
+    #[serde(default)]
+    overwrite: bool,
+    #[serde(default)]
+    create_directories: bool,
+}
+
+#[async_trait]
+impl Tool for MoveFileTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "move_file",
+            "Move or rename a file. Fails if the destination exists unless overwrite is set. \
+             Does not move directories.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string("from", "Path of the file to move.", true)
+                .string("to", "New path for the file.", true)
+                .boolean(
+                    "overwrite",
+                    "Replace the destination if it already exists. Defaults to false.",
+                    false,
+                )
+                .boolean(
+                    "create_directories",
+                    "Whether to create the destination's parent directories if they don't \
+                     exist. Defaults to false.",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "move_file", ?args, "Tool called");
+
+        let args: MoveFileArgs = serde_json::from_value(args.clone()).map_err(|e| {
+            warn!(tool = "move_file", error = %e, ?args, "Failed to parse arguments");
+            serdes_ai_tools::ToolError::execution_failed(format!(
+                "Invalid arguments: {}. Got: {}",
+                e, args
+            ))
+        })?;
+
+        if let Some(undo) = &self.undo {
+            let recorded = undo.record(&args.from).and_then(|()| undo.record(&args.to));
+            if let Err(e) = recorded {
+                return Ok(ToolReturn::error(format!(
+                    "Not moving {}: failed to save it for undo: {}",
+                    args.from, e
+                )));
+            }
+        }
+
+        match file_ops::move_file(
+            &args.from,
+            &args.to,
+            args.overwrite,
+            args.create_directories,
+        ) {
+            Ok(moved) => Ok(ToolReturn::text(format!(
+                "Moved {} -> {}",
+                moved.from, moved.to
+            ))),
+            Err(FileError::NotFound(path)) => {
+                Ok(ToolReturn::error(format!("File not found: {}", path)))
+            }
+            Err(FileError::AlreadyExists(path)) => Ok(ToolReturn::error(format!(
+                "Destination already exists: {}. Set overwrite to replace it.",
+                path
+            ))),
+            Err(e) => Ok(ToolReturn::error(format!("Failed to move file: {}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_call_moves_file() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let from = dir.path().join("a.txt");
+        let to = dir.path().join("sub").join("b.txt");
+        fs::write(&from, "content").expect("write failed");
+
+        let tool = MoveFileTool::default();
+        let ctx = RunContext::minimal("test");
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "from": from.to_str().unwrap(),
+                    "to": to.to_str().unwrap(),
+                    "create_directories": true
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert!(!ret.is_error());
+        assert!(ret.as_text().unwrap().starts_with("Moved "));
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "content");
+    }
+
+    #[tokio::test]
+    async fn test_call_refuses_to_overwrite() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let from = dir.path().join("a.txt");
+        let to = dir.path().join("b.txt");
+        fs::write(&from, "new").expect("write failed");
+        fs::write(&to, "old").expect("write failed");
+
+        let tool = MoveFileTool::default();
+        let ctx = RunContext::minimal("test");
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "from": from.to_str().unwrap(),
+                    "to": to.to_str().unwrap()
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert!(ret.is_error());
+        assert!(from.exists());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "old");
+    }
+}
@@ -0,0 +1,139 @@
+//! Progress reporting for long-running tools.
+//!
+//! Tools like `list_files` and `grep` can walk thousands of files before
+//! returning. [`ProgressReporter`] publishes periodic
+//! [`Message::ToolProgress`](crate::messaging::Message::ToolProgress) events
+//! to the message bus so renderers can show a live count.
+//!
+//! Emission is gated two ways to avoid flooding the bus:
+//! - at most one event per `step` processed items
+//! - at most one event per `interval` of wall-clock time
+
+use std::time::{Duration, Instant};
+
+use crate::messaging::{Message, MessageSender};
+
+/// Default number of items between progress events.
+const PROGRESS_DEFAULT_STEP: usize = 500;
+/// Default minimum time between progress events.
+const PROGRESS_DEFAULT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Throttled publisher of tool progress events.
+pub struct ProgressReporter {
+    sender: Option<MessageSender>,
+    tool_name: String,
+    step: usize,
+    interval: Duration,
+    last_reported: usize,
+    last_emit: Option<Instant>,
+}
+
+impl ProgressReporter {
+    /// Create a reporter for a tool. With no sender, reporting is a no-op.
+    pub fn new(sender: Option<MessageSender>, tool_name: &str) -> Self {
+        Self {
+            sender,
+            tool_name: tool_name.to_string(),
+            step: PROGRESS_DEFAULT_STEP,
+            interval: PROGRESS_DEFAULT_INTERVAL,
+            last_reported: 0,
+            last_emit: None,
+        }
+    }
+
+    /// Create a reporter that never emits anything.
+    pub fn disabled() -> Self {
+        Self::new(None, "")
+    }
+
+    /// Set the number of items between progress events.
+    pub fn with_step(mut self, step: usize) -> Self {
+        self.step = step.max(1);
+        self
+    }
+
+    /// Set the minimum time between progress events.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Record progress, publishing an event if the throttle allows it.
+    pub fn report(&mut self, processed: usize, matches: Option<usize>) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        if processed < self.last_reported + self.step {
+            return;
+        }
+
+        if let Some(last_emit) = self.last_emit {
+            if last_emit.elapsed() < self.interval {
+                return;
+            }
+        }
+
+        let _ = sender.send(Message::tool_progress(&self.tool_name, processed, matches));
+        self.last_reported = processed;
+        self.last_emit = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::MessageBus;
+
+    fn drain(receiver: &mut crate::messaging::MessageReceiver) -> Vec<Message> {
+        let mut messages = Vec::new();
+        while let Ok(Some(msg)) = receiver.try_recv() {
+            messages.push(msg);
+        }
+        messages
+    }
+
+    #[test]
+    fn test_disabled_reporter_is_noop() {
+        let mut reporter = ProgressReporter::disabled().with_step(1);
+        for i in 0..10 {
+            reporter.report(i, None);
+        }
+    }
+
+    #[test]
+    fn test_reporter_respects_step() {
+        let bus = MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut reporter = ProgressReporter::new(Some(bus.sender()), "list_files")
+            .with_step(10)
+            .with_interval(Duration::ZERO);
+
+        for i in 1..=100 {
+            reporter.report(i, None);
+        }
+
+        let messages = drain(&mut receiver);
+        assert_eq!(messages.len(), 10);
+        assert!(matches!(
+            &messages[0],
+            Message::ToolProgress(p) if p.tool_name == "list_files" && p.processed == 10
+        ));
+    }
+
+    #[test]
+    fn test_reporter_respects_interval() {
+        let bus = MessageBus::new();
+        let mut receiver = bus.subscribe();
+        let mut reporter = ProgressReporter::new(Some(bus.sender()), "grep")
+            .with_step(1)
+            .with_interval(Duration::from_secs(3600));
+
+        for i in 1..=100 {
+            reporter.report(i, Some(i / 2));
+        }
+
+        // Only the first event fits inside the hour-long interval
+        assert_eq!(drain(&mut receiver).len(), 1);
+    }
+}
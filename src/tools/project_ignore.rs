@@ -0,0 +1,124 @@
+//! Per-project ignore rules (`.stockpotignore`).
+//!
+//! A `.stockpotignore` in the working directory or any directory above it
+//! adds gitignore-style patterns to the built-in
+//! [`IGNORE_PATTERNS`](super::common::IGNORE_PATTERNS), e.g. `fixtures/`.
+//! A negated pattern (`!build/`) brings back a path the built-in patterns
+//! would skip. The nearest file applies, with patterns relative to the
+//! directory it's in.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+/// Name of the ignore file.
+pub const IGNORE_FILE: &str = ".stockpotignore";
+
+/// How long a lookup is reused before checking for the file again.
+const RECHECK_AFTER: Duration = Duration::from_secs(2);
+
+/// The patterns from one `.stockpotignore`.
+#[derive(Debug)]
+pub struct ProjectIgnore {
+    root: PathBuf,
+    rules: Gitignore,
+}
+
+impl ProjectIgnore {
+    /// The nearest ignore file at or above `start`.
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(IGNORE_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Parse the ignore file at `path`. Lines that aren't valid patterns are
+    /// skipped.
+    pub fn load(path: &Path) -> Self {
+        let root = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let mut builder = GitignoreBuilder::new(&root);
+        if let Some(e) = builder.add(path) {
+            tracing::warn!(path = %path.display(), error = %e, "Skipped invalid ignore patterns");
+        }
+        let rules = builder.build().unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), error = %e, "Failed to read ignore file");
+            Gitignore::empty()
+        });
+        Self { root, rules }
+    }
+
+    /// `Some(true)` if a pattern ignores `path`, `Some(false)` if a negated
+    /// pattern keeps it, `None` if no pattern is about it.
+    pub fn decision(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let path = std::path::absolute(path).ok()?;
+        // The matcher only takes paths under its root
+        if !path.starts_with(&self.root) || path == self.root {
+            return None;
+        }
+        match self.rules.matched_path_or_any_parents(&path, is_dir) {
+            Match::Ignore(_) => Some(true),
+            Match::Whitelist(_) => Some(false),
+            Match::None => None,
+        }
+    }
+}
+
+struct Lookup {
+    cwd: PathBuf,
+    at: Instant,
+    rules: Option<Arc<ProjectIgnore>>,
+}
+
+/// The rules that apply in the current directory, if there's an ignore file.
+pub fn current() -> Option<Arc<ProjectIgnore>> {
+    static LAST: Mutex<Option<Lookup>> = Mutex::new(None);
+
+    let cwd = std::env::current_dir().ok()?;
+    let mut last = LAST.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(lookup) = last.as_ref() {
+        if lookup.cwd == cwd && lookup.at.elapsed() < RECHECK_AFTER {
+            return lookup.rules.clone();
+        }
+    }
+    let rules = ProjectIgnore::find(&cwd).map(|path| Arc::new(ProjectIgnore::load(&path)));
+    *last = Some(Lookup {
+        cwd,
+        at: Instant::now(),
+        rules: rules.clone(),
+    });
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_and_negations() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("app/src")).unwrap();
+        std::fs::write(root.join(IGNORE_FILE), "fixtures/\n*.snap\n!build/\n").unwrap();
+
+        let found = ProjectIgnore::find(&root.join("app/src")).unwrap();
+        assert_eq!(found, root.join(IGNORE_FILE));
+        let rules = ProjectIgnore::load(&found);
+
+        assert_eq!(rules.decision(&root.join("fixtures"), true), Some(true));
+        assert_eq!(
+            rules.decision(&root.join("app/fixtures/big.json"), false),
+            Some(true)
+        );
+        assert_eq!(rules.decision(&root.join("app/ui.snap"), false), Some(true));
+        assert_eq!(
+            rules.decision(&root.join("build/out.txt"), false),
+            Some(false)
+        );
+        assert_eq!(rules.decision(&root.join("app/src/main.rs"), false), None);
+        assert_eq!(rules.decision(Path::new("/elsewhere/fixtures"), true), None);
+    }
+}
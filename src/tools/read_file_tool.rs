@@ -10,16 +10,37 @@ use tracing::{debug, warn};
 use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
 
 use super::file_ops::{self, FileError};
+use super::file_watch::FileWatcher;
 
 /// Tool for reading file contents.
 #[derive(Debug, Clone, Default)]
-pub struct ReadFileTool;
+pub struct ReadFileTool {
+    /// Optional watcher told about every file read.
+    watcher: Option<FileWatcher>,
+    /// Largest file to read, in bytes; `None` uses the default.
+    max_size: Option<u64>,
+}
+
+impl ReadFileTool {
+    /// Watch files after reading them, so external changes can be reported.
+    pub fn with_file_watcher(mut self, watcher: FileWatcher) -> Self {
+        self.watcher = Some(watcher);
+        self
+    }
+
+    /// Refuse files larger than `bytes` instead of the default 10MB.
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct ReadFileArgs {
     file_path: String,
     start_line: Option<usize>,
     num_lines: Option<usize>,
+    with_line_numbers: Option<bool>,
 }
 
 #[async_trait]
@@ -49,6 +70,12 @@ impl Tool for ReadFileTool {
                     "Number of lines to read starting from start_line.",
                     false,
                 )
+                .boolean(
+                    "with_line_numbers",
+                    "Prefix each line with its 1-based line number and a tab, counting \
+                     from start_line for partial reads. Useful before editing. Defaults to false.",
+                    false,
+                )
                 .build()
                 .expect("schema build failed"),
         )
@@ -69,20 +96,24 @@ impl Tool for ReadFileTool {
             &args.file_path,
             args.start_line,
             args.num_lines,
-            None, // use default max size
+            self.max_size,
         ) {
             Ok(result) => {
-                let mut output = result.content;
+                if let Some(watcher) = &self.watcher {
+                    watcher.watch(std::path::Path::new(&args.file_path));
+                }
+
+                let mut output = if args.with_line_numbers.unwrap_or(false) {
+                    result.numbered_content()
+                } else {
+                    result.content.clone()
+                };
 
                 // Add metadata as a comment if we're reading a partial file
                 if args.start_line.is_some() {
                     output = format!(
                         "# File: {} (lines {}..{} of {})\n{}",
-                        result.path,
-                        args.start_line.unwrap_or(1),
-                        args.start_line.unwrap_or(1) + args.num_lines.unwrap_or(result.lines) - 1,
-                        result.lines,
-                        output
+                        result.path, result.start_line, result.end_line, result.lines, output
                     );
                 }
 
@@ -115,7 +146,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_read_file_tool_not_found() {
-        let tool = ReadFileTool;
+        let tool = ReadFileTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -131,4 +162,54 @@ mod tests {
         let ret = result.unwrap();
         assert!(ret.as_text().unwrap().contains("not found"));
     }
+
+    #[tokio::test]
+    async fn test_read_file_tool_with_line_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("f.txt");
+        std::fs::write(&path, "a\nb\nc\nd").unwrap();
+        let tool = ReadFileTool::default();
+        let ctx = RunContext::minimal("test");
+
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({
+                    "file_path": path.to_str().unwrap(),
+                    "start_line": 2,
+                    "num_lines": 10,
+                    "with_line_numbers": true
+                }),
+            )
+            .await
+            .unwrap();
+
+        let text = ret.as_text().unwrap();
+        assert!(text.starts_with("# File: "));
+        assert!(text.ends_with("(lines 2..4 of 4)\n2\tb\n3\tc\n4\td"));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_tool_max_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        std::fs::write(&path, "id,value\n1,2\n").unwrap();
+        let ctx = RunContext::minimal("test");
+        let args = serde_json::json!({ "file_path": path.to_str().unwrap() });
+
+        let ret = ReadFileTool::default()
+            .with_max_size(8)
+            .call(&ctx, args.clone())
+            .await
+            .unwrap();
+        assert!(ret.is_error());
+        assert!(ret.as_text().unwrap().contains("(max: 8)"));
+
+        let ret = ReadFileTool::default()
+            .with_max_size(64)
+            .call(&ctx, args)
+            .await
+            .unwrap();
+        assert_eq!(ret.as_text(), Some("id,value\n1,2\n"));
+    }
 }
@@ -0,0 +1,234 @@
+//! `@path` file references in prompts.
+//!
+//! A word starting with `@` (`explain @src/db/mod.rs`) names a file whose
+//! content is read and put ahead of the prompt as labeled context, so the
+//! agent doesn't spend a tool call reading it. The prompt itself is kept as
+//! typed. Large files are cut off at [`MAX_REFERENCE_BYTES`]; files that
+//! can't be read are reported back instead.
+//!
+//! `@image:shot.png` attaches an image instead, for vision models. Its type
+//! comes from the file's magic bytes, falling back to the extension.
+
+use std::path::Path;
+
+use serdes_ai_core::messages::ImageMediaType;
+
+/// Content kept from each referenced file.
+pub const MAX_REFERENCE_BYTES: usize = 64 * 1024;
+
+/// Largest image an `@image:` reference will attach.
+pub const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// Marks a reference as an image to attach rather than text to inline.
+const IMAGE_PREFIX: &str = "image:";
+
+/// Punctuation that can follow a reference without being part of it.
+const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']', '"', '\''];
+
+/// A prompt with its `@path` references read in.
+#[derive(Debug, Clone, Default)]
+pub struct ExpandedPrompt {
+    /// The prompt to send, with referenced files ahead of it
+    pub prompt: String,
+    /// References that were included, images as `image:path`
+    pub included: Vec<String>,
+    /// Images to send with the prompt
+    pub images: Vec<(Vec<u8>, ImageMediaType)>,
+    /// References that couldn't be read, with the reason
+    pub failed: Vec<(String, String)>,
+}
+
+/// Read the files `prompt` references with `@path`, relative to `base`.
+pub fn expand_file_references(prompt: &str, base: &Path) -> ExpandedPrompt {
+    let mut expanded = ExpandedPrompt::default();
+    let mut body = String::new();
+
+    for reference in references(prompt) {
+        if expanded.included.contains(&reference)
+            || expanded.failed.iter().any(|(r, _)| *r == reference)
+        {
+            continue;
+        }
+        if let Some(image) = reference.strip_prefix(IMAGE_PREFIX) {
+            match read_image(image, base) {
+                Ok(attachment) => {
+                    expanded.images.push(attachment);
+                    expanded.included.push(reference);
+                }
+                Err(reason) => expanded.failed.push((reference, reason)),
+            }
+            continue;
+        }
+        match read_reference(&reference, base) {
+            Ok(content) => {
+                body.push_str(&format!("--- {} ---\n{}", reference, content));
+                if !body.ends_with('\n') {
+                    body.push('\n');
+                }
+                expanded.included.push(reference);
+            }
+            Err(reason) => expanded.failed.push((reference, reason)),
+        }
+    }
+
+    expanded.prompt = if body.is_empty() {
+        prompt.to_string()
+    } else {
+        format!(
+            "<referenced_files>\n{}</referenced_files>\n\n{}",
+            body, prompt
+        )
+    };
+    expanded
+}
+
+/// The paths named by `@` words, in order.
+fn references(prompt: &str) -> impl Iterator<Item = String> + '_ {
+    prompt
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('@'))
+        .map(|path| path.trim_end_matches(TRAILING))
+        .filter(|path| !path.is_empty() && *path != IMAGE_PREFIX)
+        .map(String::from)
+}
+
+fn read_reference(reference: &str, base: &Path) -> Result<String, String> {
+    let path = base.join(shellexpand::tilde(reference).as_ref());
+    if path.is_dir() {
+        return Err("is a directory".to_string());
+    }
+    let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+
+    let (bytes, truncated) = if bytes.len() > MAX_REFERENCE_BYTES {
+        (&bytes[..MAX_REFERENCE_BYTES], true)
+    } else {
+        (&bytes[..], false)
+    };
+    let content = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        // A cut can land inside a character; keep what's whole
+        Err(e) if truncated && e.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return Err("not a text file".to_string()),
+    };
+
+    if truncated {
+        Ok(format!(
+            "{}\n… [truncated at {} KB]",
+            content,
+            MAX_REFERENCE_BYTES / 1024
+        ))
+    } else {
+        Ok(content.to_string())
+    }
+}
+
+fn read_image(reference: &str, base: &Path) -> Result<(Vec<u8>, ImageMediaType), String> {
+    let path = base.join(shellexpand::tilde(reference).as_ref());
+    let size = std::fs::metadata(&path).map_err(|e| e.to_string())?.len();
+    if size > MAX_IMAGE_BYTES as u64 {
+        return Err(format!(
+            "image is larger than {} MB",
+            MAX_IMAGE_BYTES / (1024 * 1024)
+        ));
+    }
+    let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+    let media_type = image_media_type(&bytes, &path)
+        .ok_or_else(|| "not a PNG, JPEG, GIF or WebP image".to_string())?;
+    Ok((bytes, media_type))
+}
+
+/// The type of an image, from its magic bytes or else its extension.
+fn image_media_type(bytes: &[u8], path: &Path) -> Option<ImageMediaType> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(ImageMediaType::Png);
+    }
+    if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        return Some(ImageMediaType::Jpeg);
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some(ImageMediaType::Gif);
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some(ImageMediaType::Webp);
+    }
+
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some(ImageMediaType::Png),
+        "jpg" | "jpeg" => Some(ImageMediaType::Jpeg),
+        "gif" => Some(ImageMediaType::Gif),
+        "webp" => Some(ImageMediaType::Webp),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_references_are_inlined_ahead_of_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "pub mod db;").unwrap();
+
+        let expanded = expand_file_references("explain @src/lib.rs, please", dir.path());
+        assert_eq!(expanded.included, vec!["src/lib.rs"]);
+        assert!(expanded.failed.is_empty());
+        assert_eq!(
+            expanded.prompt,
+            "<referenced_files>\n--- src/lib.rs ---\npub mod db;\n</referenced_files>\n\n\
+             explain @src/lib.rs, please"
+        );
+    }
+
+    #[test]
+    fn test_unreadable_references_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("blob.bin"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let expanded = expand_file_references("see @missing.rs @src @blob.bin", dir.path());
+        assert!(expanded.included.is_empty());
+        assert_eq!(expanded.prompt, "see @missing.rs @src @blob.bin");
+        let failed: Vec<_> = expanded.failed.iter().map(|(r, _)| r.as_str()).collect();
+        assert_eq!(failed, vec!["missing.rs", "src", "blob.bin"]);
+        assert_eq!(expanded.failed[1].1, "is a directory");
+    }
+
+    #[test]
+    fn test_large_files_are_truncated_and_emails_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("big.txt"), "é".repeat(MAX_REFERENCE_BYTES)).unwrap();
+
+        let expanded = expand_file_references("mail me@example.com about @big.txt", dir.path());
+        assert_eq!(expanded.included, vec!["big.txt"]);
+        assert!(expanded.prompt.contains("… [truncated at 64 KB]"));
+        assert!(expanded.prompt.len() < MAX_REFERENCE_BYTES + 200);
+    }
+
+    #[test]
+    fn test_image_references_are_attached() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("shot.dat"), b"\x89PNG\r\n\x1a\nrest").unwrap();
+        fs::write(dir.path().join("photo.JPG"), b"no magic here").unwrap();
+        fs::write(dir.path().join("notes.txt"), "text").unwrap();
+
+        let expanded = expand_file_references(
+            "what's wrong in @image:shot.dat and @image:photo.JPG? @image:notes.txt",
+            dir.path(),
+        );
+        assert_eq!(expanded.included, vec!["image:shot.dat", "image:photo.JPG"]);
+        assert!(matches!(
+            expanded.images.as_slice(),
+            [(_, ImageMediaType::Png), (_, ImageMediaType::Jpeg)]
+        ));
+        assert_eq!(expanded.failed.len(), 1);
+        assert_eq!(expanded.failed[0].0, "image:notes.txt");
+        // Images go alongside the prompt, not into it
+        assert!(!expanded.prompt.contains("<referenced_files>"));
+    }
+}
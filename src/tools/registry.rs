@@ -7,14 +7,21 @@ use std::sync::Arc;
 
 use serdes_ai_tools::Tool;
 
+use crate::messaging::MessageSender;
+
 use super::agent_tools::{InvokeAgentTool, ListAgentsTool};
 use super::delete_file_tool::DeleteFileTool;
 use super::edit_file_tool::EditFileTool;
+use super::file_watch::FileWatcher;
 use super::grep_tool::GrepTool;
 use super::list_files_tool::ListFilesTool;
+use super::make_directory_tool::MakeDirectoryTool;
+use super::move_file_tool::MoveFileTool;
 use super::read_file_tool::ReadFileTool;
 use super::reasoning_tool::ShareReasoningTool;
+use super::replace_in_files_tool::ReplaceInFilesTool;
 use super::shell_tool::RunShellCommandTool;
+use super::stat_file_tool::StatFileTool;
 
 /// Arc-wrapped tool for shared ownership.
 pub type ArcTool = Arc<dyn Tool + Send + Sync>;
@@ -38,9 +45,13 @@ pub type ArcTool = Arc<dyn Tool + Send + Sync>;
 pub struct SpotToolRegistry {
     pub list_files: ListFilesTool,
     pub read_file: ReadFileTool,
+    pub stat_file: StatFileTool,
     pub edit_file: EditFileTool,
     pub delete_file: DeleteFileTool,
+    pub move_file: MoveFileTool,
+    pub make_directory: MakeDirectoryTool,
     pub grep: GrepTool,
+    pub replace_in_files: ReplaceInFilesTool,
     pub run_shell_command: RunShellCommandTool,
     pub share_reasoning: ShareReasoningTool,
     pub invoke_agent: InvokeAgentTool,
@@ -53,14 +64,37 @@ impl SpotToolRegistry {
         Self::default()
     }
 
+    /// Publish progress events from long-running tools to the message bus.
+    pub fn with_bus(mut self, sender: MessageSender) -> Self {
+        self.list_files = self.list_files.with_bus(sender.clone());
+        self.grep = self.grep.with_bus(sender);
+        self
+    }
+
+    /// Keep `run_shell_command` inside the current directory.
+    pub fn with_shell_confinement(mut self, confine: bool) -> Self {
+        self.run_shell_command = self.run_shell_command.with_confinement(confine);
+        self
+    }
+
+    /// Watch files `read_file` reads for changes made outside the agent.
+    pub fn with_file_watcher(mut self, watcher: FileWatcher) -> Self {
+        self.read_file = self.read_file.with_file_watcher(watcher);
+        self
+    }
+
     /// Get all tools as Arc-wrapped trait objects for shared ownership.
     pub fn all_tools(&self) -> Vec<ArcTool> {
         vec![
             Arc::new(self.list_files.clone()),
             Arc::new(self.read_file.clone()),
+            Arc::new(self.stat_file.clone()),
             Arc::new(self.edit_file.clone()),
             Arc::new(self.delete_file.clone()),
+            Arc::new(self.move_file.clone()),
+            Arc::new(self.make_directory.clone()),
             Arc::new(self.grep.clone()),
+            Arc::new(self.replace_in_files.clone()),
             Arc::new(self.run_shell_command.clone()),
             Arc::new(self.share_reasoning.clone()),
             Arc::new(self.invoke_agent.clone()),
@@ -81,9 +115,13 @@ impl SpotToolRegistry {
             match *name {
                 "list_files" => tools.push(Arc::new(self.list_files.clone())),
                 "read_file" => tools.push(Arc::new(self.read_file.clone())),
+                "stat_file" => tools.push(Arc::new(self.stat_file.clone())),
                 "edit_file" => tools.push(Arc::new(self.edit_file.clone())),
                 "delete_file" => tools.push(Arc::new(self.delete_file.clone())),
+                "move_file" => tools.push(Arc::new(self.move_file.clone())),
+                "make_directory" => tools.push(Arc::new(self.make_directory.clone())),
                 "grep" => tools.push(Arc::new(self.grep.clone())),
+                "replace_in_files" => tools.push(Arc::new(self.replace_in_files.clone())),
                 "run_shell_command" => tools.push(Arc::new(self.run_shell_command.clone())),
                 "share_your_reasoning" => tools.push(Arc::new(self.share_reasoning.clone())),
                 "invoke_agent" => tools.push(Arc::new(self.invoke_agent.clone())),
@@ -100,6 +138,7 @@ impl SpotToolRegistry {
         vec![
             Arc::new(self.list_files.clone()),
             Arc::new(self.read_file.clone()),
+            Arc::new(self.stat_file.clone()),
             Arc::new(self.grep.clone()),
             Arc::new(self.share_reasoning.clone()),
         ]
@@ -110,9 +149,13 @@ impl SpotToolRegistry {
         vec![
             Arc::new(self.list_files.clone()),
             Arc::new(self.read_file.clone()),
+            Arc::new(self.stat_file.clone()),
             Arc::new(self.edit_file.clone()),
             Arc::new(self.delete_file.clone()),
+            Arc::new(self.move_file.clone()),
+            Arc::new(self.make_directory.clone()),
             Arc::new(self.grep.clone()),
+            Arc::new(self.replace_in_files.clone()),
         ]
     }
 }
@@ -129,14 +172,14 @@ mod tests {
     #[test]
     fn test_registry_creation() {
         let registry = SpotToolRegistry::new();
-        assert_eq!(registry.all_tools().len(), 9);
-        assert_eq!(registry.definitions().len(), 9);
+        assert_eq!(registry.all_tools().len(), 13);
+        assert_eq!(registry.definitions().len(), 13);
     }
 
     #[test]
     fn test_registry_default_trait() {
         let registry = SpotToolRegistry::default();
-        assert_eq!(registry.all_tools().len(), 9);
+        assert_eq!(registry.all_tools().len(), 13);
     }
 
     #[test]
@@ -165,7 +208,7 @@ mod tests {
     #[test]
     fn test_all_tools_returns_correct_count() {
         let registry = SpotToolRegistry::new();
-        assert_eq!(registry.all_tools().len(), 9);
+        assert_eq!(registry.all_tools().len(), 13);
     }
 
     #[test]
@@ -180,9 +223,13 @@ mod tests {
         let expected = [
             "list_files",
             "read_file",
+            "stat_file",
             "edit_file",
             "delete_file",
+            "move_file",
+            "make_directory",
             "grep",
+            "replace_in_files",
             "run_shell_command",
             "share_your_reasoning",
             "invoke_agent",
@@ -244,7 +291,7 @@ mod tests {
     #[test]
     fn test_definitions_returns_correct_count() {
         let registry = SpotToolRegistry::new();
-        assert_eq!(registry.definitions().len(), 9);
+        assert_eq!(registry.definitions().len(), 13);
     }
 
     #[test]
@@ -310,9 +357,13 @@ mod tests {
         let names = [
             "list_files",
             "read_file",
+            "stat_file",
             "edit_file",
             "delete_file",
+            "move_file",
+            "make_directory",
             "grep",
+            "replace_in_files",
             "run_shell_command",
             "share_your_reasoning",
             "invoke_agent",
@@ -320,7 +371,7 @@ mod tests {
         ];
 
         let tools = registry.tools_by_name(&names);
-        assert_eq!(tools.len(), 9);
+        assert_eq!(tools.len(), 13);
     }
 
     #[test]
@@ -399,6 +450,7 @@ mod tests {
             assert!(
                 name == "list_files"
                     || name == "read_file"
+                    || name == "stat_file"
                     || name == "grep"
                     || name == "share_your_reasoning",
                 "Unexpected tool in read_only: {}",
@@ -411,7 +463,7 @@ mod tests {
     fn test_read_only_tools_count() {
         let registry = SpotToolRegistry::new();
         let tools = registry.read_only_tools();
-        assert_eq!(tools.len(), 4);
+        assert_eq!(tools.len(), 5);
     }
 
     #[test]
@@ -426,6 +478,7 @@ mod tests {
         let expected: HashSet<_> = [
             "list_files".to_string(),
             "read_file".to_string(),
+            "stat_file".to_string(),
             "grep".to_string(),
             "share_your_reasoning".to_string(),
         ]
@@ -459,7 +512,7 @@ mod tests {
     fn test_file_tools_count() {
         let registry = SpotToolRegistry::new();
         let tools = registry.file_tools();
-        assert_eq!(tools.len(), 5);
+        assert_eq!(tools.len(), 9);
     }
 
     #[test]
@@ -474,9 +527,13 @@ mod tests {
         let expected: HashSet<_> = [
             "list_files".to_string(),
             "read_file".to_string(),
+            "stat_file".to_string(),
             "edit_file".to_string(),
             "delete_file".to_string(),
+            "move_file".to_string(),
+            "make_directory".to_string(),
             "grep".to_string(),
+            "replace_in_files".to_string(),
         ]
         .into_iter()
         .collect();
@@ -698,9 +755,10 @@ mod tests {
 
         let intersection: HashSet<_> = read_only_names.intersection(&file_names).collect();
 
-        // list_files, read_file, grep should be in both
+        // list_files, read_file, stat_file, grep should be in both
         assert!(intersection.contains(&"list_files".to_string()));
         assert!(intersection.contains(&"read_file".to_string()));
+        assert!(intersection.contains(&"stat_file".to_string()));
         assert!(intersection.contains(&"grep".to_string()));
     }
 
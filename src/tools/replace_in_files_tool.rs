@@ -0,0 +1,226 @@
+//! ReplaceInFiles tool implementation.
+//!
+//! Provides a serdesAI-compatible tool for regex find-and-replace across the
+//! files under a directory. It previews by default: nothing is written unless
+//! `dry_run` is explicitly false. With an [`UndoRun`] attached, every file's
+//! prior state is saved before any of them is written.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::{debug, warn};
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use super::file_ops::{self, FileReplacement};
+use super::undo::UndoRun;
+use crate::atomic_file::replace_atomic;
+
+/// Changed lines shown in a preview before the rest are summarized.
+const PREVIEW_MAX_LINES: usize = 50;
+
+/// Tool for replacing a pattern across files.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceInFilesTool {
+    undo: Option<UndoRun>,
+}
+
+impl ReplaceInFilesTool {
+    /// Save each file's prior state to `run` before writing it.
+    pub fn with_undo(mut self, run: UndoRun) -> Self {
+        self.undo = Some(run);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplaceInFilesArgs {
+    pattern: String,
+    replacement: String,
+    directory: Option<String>,
+    dry_run: Option<bool>,
+}
+
+#[async_trait]
+impl Tool for ReplaceInFilesTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "replace_in_files",
+            "Find and replace a regex pattern across the text files under a directory. \
+             Previews the affected files and lines by default; review the preview, then \
+             call again with dry_run=false to apply. Touches at most 200 files.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string(
+                    "pattern",
+                    "Regex to find, matched within a line. Falls back to a literal search \
+                     if it isn't a valid regex.",
+                    true,
+                )
+                .string(
+                    "replacement",
+                    "Text to replace each match with. Use $1 or ${name} for capture groups.",
+                    true,
+                )
+                .string(
+                    "directory",
+                    "Directory to search recursively. Defaults to '.'.",
+                    false,
+                )
+                .boolean(
+                    "dry_run",
+                    "Preview without writing. Defaults to true; set false to apply.",
+                    false,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "replace_in_files", ?args, "Tool called");
+
+        let args: ReplaceInFilesArgs = serde_json::from_value(args.clone()).map_err(|e| {
+            warn!(tool = "replace_in_files", error = %e, ?args, "Failed to parse arguments");
+            serdes_ai_tools::ToolError::execution_failed(format!(
+                "Invalid arguments: {}. Got: {}",
+                e, args
+            ))
+        })?;
+
+        let directory = args.directory.as_deref().unwrap_or(".");
+        let plans = match file_ops::plan_replacements(&args.pattern, &args.replacement, directory) {
+            Ok(plans) => plans,
+            Err(e) => return Ok(ToolReturn::error(format!("Replace failed: {}", e))),
+        };
+
+        if plans.is_empty() {
+            return Ok(ToolReturn::text(format!(
+                "No matches found for pattern '{}' in {}",
+                args.pattern, directory
+            )));
+        }
+
+        let summary = summarize(&plans);
+        if args.dry_run.unwrap_or(true) {
+            return Ok(ToolReturn::text(format!(
+                "Dry run: would replace {}. No changes made; call again with dry_run=false \
+                 to apply.\n{}",
+                summary,
+                preview(&plans)
+            )));
+        }
+
+        // Save everything before writing anything, so a failure leaves no
+        // file changed without a way back
+        if let Some(undo) = &self.undo {
+            for plan in &plans {
+                if let Err(e) = undo.record(&plan.absolute_path) {
+                    return Ok(ToolReturn::error(format!(
+                        "Not replacing: failed to save {} for undo: {}",
+                        plan.path, e
+                    )));
+                }
+            }
+        }
+
+        let mut written = Vec::new();
+        for plan in &plans {
+            if let Err(e) = replace_atomic(&plan.absolute_path, plan.content.as_bytes()) {
+                let done = if written.is_empty() {
+                    "no files".to_string()
+                } else {
+                    written.join(", ")
+                };
+                return Ok(ToolReturn::error(format!(
+                    "Failed to write {}: {}. Already written: {}",
+                    plan.path, e, done
+                )));
+            }
+            written.push(plan.path.as_str());
+        }
+
+        Ok(ToolReturn::text(format!(
+            "Replaced {}:\n{}",
+            summary,
+            written.join("\n")
+        )))
+    }
+}
+
+/// "N occurrences on M lines in K files"
+fn summarize(plans: &[FileReplacement]) -> String {
+    let replacements: usize = plans.iter().map(|plan| plan.replacements).sum();
+    let lines: usize = plans.iter().map(|plan| plan.lines.len()).sum();
+    format!(
+        "{} occurrences on {} lines in {} files",
+        replacements,
+        lines,
+        plans.len()
+    )
+}
+
+/// Each changed line before and after, up to [`PREVIEW_MAX_LINES`].
+fn preview(plans: &[FileReplacement]) -> String {
+    let mut output = String::new();
+    let mut shown = 0;
+    for plan in plans {
+        for line in &plan.lines {
+            if shown == PREVIEW_MAX_LINES {
+                let total: usize = plans.iter().map(|plan| plan.lines.len()).sum();
+                output.push_str(&format!("\n... and {} more lines", total - shown));
+                return output;
+            }
+            output.push_str(&format!(
+                "\n{}:{}\n- {}\n+ {}",
+                plan.path, line.line_number, line.before, line.after
+            ));
+            shown += 1;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_call_previews_then_applies() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file_path = dir.path().join("a.txt");
+        fs::write(&file_path, "color\ncolour\ncolor color\n").expect("write failed");
+
+        let tool = ReplaceInFilesTool::default();
+        let ctx = RunContext::minimal("test");
+        let args = |dry_run: Option<bool>| {
+            let mut args = serde_json::json!({
+                "pattern": r"\bcolor\b",
+                "replacement": "hue",
+                "directory": dir.path().to_str().unwrap()
+            });
+            if let Some(dry_run) = dry_run {
+                args["dry_run"] = dry_run.into();
+            }
+            args
+        };
+
+        let ret = tool.call(&ctx, args(None)).await.unwrap();
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("would replace 3 occurrences on 2 lines in 1 files"));
+        assert!(text.contains("a.txt:3\n- color color\n+ hue hue"));
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "color\ncolour\ncolor color\n"
+        );
+
+        let ret = tool.call(&ctx, args(Some(false))).await.unwrap();
+        assert!(ret.as_text().unwrap().starts_with("Replaced 3 occurrences"));
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "hue\ncolour\nhue hue\n"
+        );
+    }
+}
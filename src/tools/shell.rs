@@ -1,8 +1,11 @@
 //! Shell command execution.
 
+use std::path::{Component, Path, PathBuf};
 use std::process::{Command, Stdio};
 use thiserror::Error;
 
+use crate::agents::restore_child_env;
+
 /// Maximum characters in shell command output to protect context window
 const SHELL_MAX_OUTPUT_CHARS: usize = 50_000;
 
@@ -10,8 +13,17 @@ const SHELL_MAX_OUTPUT_CHARS: usize = 50_000;
 pub enum ShellError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("{path} is outside the working directory {}", root.display())]
+    OutsideWorkingDir { path: String, root: PathBuf },
+    #[error("`{0}` is blocked by the shell denylist")]
+    Denied(String),
+    #[error("`{0}` is not on the shell allowlist")]
+    NotAllowed(String),
 }
 
+/// Device files a confined command may still use, e.g. `2>/dev/null`.
+const ALLOWED_DEVICES: &[&str] = &["/dev/null", "/dev/stdin", "/dev/stdout", "/dev/stderr"];
+
 /// Result of running a command.
 #[derive(Debug, Clone)]
 pub struct CommandResult {
@@ -45,20 +57,212 @@ fn truncate_output(output: String, max_chars: usize) -> (String, bool) {
     (truncated, true)
 }
 
+/// Resolve `.` and `..` in a path without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Whether `path`, taken relative to `root`, stays inside `root`.
+pub fn is_within(path: &Path, root: &Path) -> bool {
+    normalize(&root.join(path)).starts_with(normalize(root))
+}
+
+/// Path-like words in a command that could reach outside the working
+/// directory: absolute paths, `~` paths and anything with `..`.
+///
+/// This is a plain word scan, not a shell parser; it catches the obvious
+/// `cd /`, `cat ../secret` or `rm -rf ~/x`, not paths built at run time.
+fn escaping_candidates(command: &str) -> impl Iterator<Item = &str> {
+    command
+        .split(|c: char| c.is_whitespace() || "<>|;&()`".contains(c))
+        .map(|word| word.trim_matches(|c| c == '\'' || c == '"'))
+        .map(|word| word.rsplit_once('=').map_or(word, |(_, value)| value))
+        .filter(|word| word.starts_with('/') || word.starts_with('~') || word.contains(".."))
+        .filter(|word| !ALLOWED_DEVICES.contains(word))
+}
+
+/// Commands the shell tool may or may not run.
+///
+/// Patterns are leading words, so `git push` matches `git push origin main`
+/// but not `git pull`, and `rm` matches `/bin/rm -rf x`. Every command in a
+/// pipeline or `&&`/`;` chain is checked: any denied one blocks the whole
+/// line, and with a non-empty allowlist each one must be on it. Commands
+/// run through a wrapper (`sudo`, `env`, `nice`, `timeout`, `xargs`,
+/// `sh -c` and the like) are checked as well as the wrapper, and it's the
+/// wrapped command that has to be on the allowlist.
+///
+/// The rules keep an agent from running commands by mistake; they aren't a
+/// security boundary. The shell can still reach a denied program other
+/// ways, through variables, `eval` or a script, so use `sandbox_mode` or
+/// approvals to contain a model that isn't trusted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandRules {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl CommandRules {
+    /// Whether no rule is set.
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Check every command in `line` against the rules.
+    pub fn check(&self, line: &str) -> Result<(), ShellError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        for argv in simple_commands(line) {
+            let command = argv.join(" ");
+            let layers = wrapped_commands(&argv);
+            let matches = |argv: &[&str], patterns: &[String]| {
+                patterns
+                    .iter()
+                    .any(|pattern| matches_pattern(argv, pattern))
+            };
+            if layers.iter().any(|layer| matches(layer, &self.deny)) {
+                return Err(ShellError::Denied(command));
+            }
+            let innermost = layers.last().copied().unwrap_or(&argv);
+            if !self.allow.is_empty() && !matches(innermost, &self.allow) {
+                return Err(ShellError::NotAllowed(command));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `argv` followed by each command it runs through a wrapper, so
+/// `sudo nice -n 5 rm x` gives itself, `nice -n 5 rm x` and `rm x`.
+fn wrapped_commands<'a, 'b>(argv: &'b [&'a str]) -> Vec<&'b [&'a str]> {
+    let mut layers = vec![argv];
+    let mut current = argv;
+    while let Some(inner) = wrapped_command(current) {
+        layers.push(inner);
+        current = inner;
+    }
+    layers
+}
+
+/// The command a wrapper such as `env` or `sh -c` runs, after its options.
+fn wrapped_command<'a, 'b>(argv: &'b [&'a str]) -> Option<&'b [&'a str]> {
+    let args = &argv[1..];
+    let start = match program_name(argv[0]) {
+        "env" => args
+            .iter()
+            .position(|word| !word.starts_with('-') && !is_assignment(word))?,
+        "sudo" | "doas" => skip_options(args, &["-u", "-g", "-C", "-D", "-h", "-p", "-r", "-t"]),
+        "nice" => skip_options(args, &["-n"]),
+        // The duration comes before the command
+        "timeout" => skip_options(args, &["-s", "-k"]) + 1,
+        "xargs" => skip_options(args, &["-I", "-n", "-P", "-L", "-d", "-E", "-s", "-a"]),
+        "nohup" | "exec" | "command" | "time" | "stdbuf" => skip_options(args, &[]),
+        // Only with -c is the rest a command rather than a script to run
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => {
+            let flags = args.iter().take_while(|word| word.starts_with('-'));
+            let c = flags
+                .clone()
+                .position(|word| !word.starts_with("--") && word.contains('c'))?;
+            c + 1
+        }
+        _ => return None,
+    };
+    args.get(start..).filter(|inner| !inner.is_empty())
+}
+
+/// Index of the first word after the options in `args`, where the options
+/// in `with_value` take the next word as their value.
+fn skip_options(args: &[&str], with_value: &[&str]) -> usize {
+    let mut i = 0;
+    while let Some(word) = args.get(i).filter(|word| word.starts_with('-')) {
+        if *word == "--" {
+            return i + 1;
+        }
+        i += if with_value.contains(word) { 2 } else { 1 };
+    }
+    i
+}
+
+/// Split a command line into the argv of each command it runs, skipping
+/// leading `VAR=value` assignments.
+fn simple_commands(line: &str) -> Vec<Vec<&str>> {
+    line.split([';', '|', '&', '\n', '(', ')', '`'])
+        .map(|command| {
+            command
+                .split_whitespace()
+                .map(|word| word.trim_matches(|c| c == '\'' || c == '"'))
+                .skip_while(|word| is_assignment(word))
+                .collect::<Vec<_>>()
+        })
+        .filter(|argv| !argv.is_empty())
+        .collect()
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Whether `argv` starts with the words of `pattern`, comparing the program
+/// by file name.
+fn matches_pattern(argv: &[&str], pattern: &str) -> bool {
+    let words: Vec<&str> = pattern.split_whitespace().collect();
+    if words.is_empty() || words.len() > argv.len() {
+        return false;
+    }
+
+    program_name(argv[0]) == words[0] && argv[1..words.len()] == words[1..]
+}
+
+/// The file name of a program, `rm` for `/bin/rm`.
+fn program_name(program: &str) -> &str {
+    Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program)
+}
+
 /// Command runner with configuration.
 pub struct CommandRunner {
-    working_dir: Option<String>,
+    /// Directory the command runs in, set explicitly on the child
+    working_dir: PathBuf,
+    /// Refuse commands that name paths outside `working_dir`
+    confine: bool,
 }
 
 impl CommandRunner {
-    /// Create a new command runner.
+    /// Create a new command runner in the current directory.
+    ///
+    /// That is the directory stockpot was launched in (or given with `-C`)
+    /// unless something has changed it since.
     pub fn new() -> Self {
-        Self { working_dir: None }
+        Self {
+            working_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            confine: false,
+        }
     }
 
     /// Set working directory.
-    pub fn working_dir(mut self, dir: impl Into<String>) -> Self {
-        self.working_dir = Some(dir.into());
+    pub fn working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = dir.into();
+        self
+    }
+
+    /// Reject commands that name paths outside the working directory.
+    pub fn confine(mut self, confine: bool) -> Self {
+        self.confine = confine;
         self
     }
 
@@ -68,17 +272,36 @@ impl CommandRunner {
         self
     }
 
+    /// Check a command against the working directory when confined.
+    fn check_confined(&self, command: &str) -> Result<(), ShellError> {
+        if !self.confine {
+            return Ok(());
+        }
+
+        for word in escaping_candidates(command) {
+            let path = shellexpand::tilde(word);
+            if !is_within(Path::new(path.as_ref()), &self.working_dir) {
+                return Err(ShellError::OutsideWorkingDir {
+                    path: word.to_string(),
+                    root: self.working_dir.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Run a command.
     pub fn run(&self, command: &str) -> Result<CommandResult, ShellError> {
+        self.check_confined(command)?;
+
         let shell = if cfg!(windows) { "cmd" } else { "sh" };
         let shell_arg = if cfg!(windows) { "/C" } else { "-c" };
 
         let mut cmd = Command::new(shell);
-        cmd.arg(shell_arg).arg(command);
-
-        if let Some(dir) = &self.working_dir {
-            cmd.current_dir(dir);
-        }
+        cmd.arg(shell_arg)
+            .arg(command)
+            .current_dir(&self.working_dir);
+        restore_child_env(&mut cmd);
 
         let output = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).output()?;
 
@@ -166,20 +389,25 @@ mod tests {
     #[test]
     fn test_command_runner_new() {
         let runner = CommandRunner::new();
-        assert!(runner.working_dir.is_none());
+        assert_eq!(runner.working_dir, std::env::current_dir().unwrap());
+        assert!(!runner.confine);
     }
 
     #[test]
     fn test_command_runner_default() {
         let runner = CommandRunner::default();
-        assert!(runner.working_dir.is_none());
+        assert_eq!(runner.working_dir, std::env::current_dir().unwrap());
     }
 
     #[test]
     fn test_command_runner_builder_chain() {
-        let runner = CommandRunner::new().working_dir("/tmp").timeout(30); // timeout is a no-op but should compile
+        let runner = CommandRunner::new()
+            .working_dir("/tmp")
+            .confine(true)
+            .timeout(30); // timeout is a no-op but should compile
 
-        assert_eq!(runner.working_dir, Some("/tmp".to_string()));
+        assert_eq!(runner.working_dir, PathBuf::from("/tmp"));
+        assert!(runner.confine);
     }
 
     #[test]
@@ -190,6 +418,175 @@ mod tests {
         assert_eq!(result.exit_code, 0);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_run_uses_working_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = CommandRunner::new()
+            .working_dir(dir.path())
+            .run("pwd")
+            .unwrap();
+        let pwd = PathBuf::from(result.stdout.trim());
+        assert_eq!(
+            pwd.canonicalize().unwrap(),
+            dir.path().canonicalize().unwrap()
+        );
+    }
+
+    // =========================================================================
+    // Confinement Tests
+    // =========================================================================
+
+    #[test]
+    fn test_is_within() {
+        let root = Path::new("/work/project");
+        assert!(is_within(Path::new("src/main.rs"), root));
+        assert!(is_within(Path::new("/work/project/target"), root));
+        assert!(is_within(Path::new("src/../Cargo.toml"), root));
+        assert!(!is_within(Path::new("../other"), root));
+        assert!(!is_within(Path::new("/etc/passwd"), root));
+        assert!(!is_within(Path::new("/work/project-other"), root));
+    }
+
+    #[test]
+    fn test_confined_runner_rejects_escaping_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = CommandRunner::new().working_dir(dir.path()).confine(true);
+
+        for command in [
+            "cd / && ls",
+            "cat ../secret.txt",
+            "rm -rf ~/projects",
+            "ls>/tmp/out.txt",
+            "cp a.txt --target-directory=/etc",
+            "cat '/etc/passwd'",
+        ] {
+            let err = runner.run(command).unwrap_err();
+            assert!(
+                matches!(err, ShellError::OutsideWorkingDir { .. }),
+                "{} should be rejected",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn test_confined_runner_allows_paths_inside() {
+        let dir = tempfile::tempdir().unwrap();
+        let inside = dir.path().join("notes.txt");
+        std::fs::write(&inside, "hi").unwrap();
+        let runner = CommandRunner::new().working_dir(dir.path()).confine(true);
+
+        assert!(runner.run("echo ok 2>/dev/null").unwrap().success);
+        assert!(runner.run("ls ./sub/.. > /dev/null").is_ok());
+        let command = format!("cat {}", inside.display());
+        assert!(runner.run(&command).is_ok());
+    }
+
+    #[test]
+    fn test_unconfined_runner_allows_any_path() {
+        let runner = CommandRunner::new().confine(false);
+        assert!(runner.check_confined("cd / && ls ../..").is_ok());
+    }
+
+    // =========================================================================
+    // Command Rules Tests
+    // =========================================================================
+
+    fn rules(allow: &[&str], deny: &[&str]) -> CommandRules {
+        CommandRules {
+            allow: allow.iter().map(|s| s.to_string()).collect(),
+            deny: deny.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_simple_commands() {
+        assert_eq!(
+            simple_commands("FOO=1 cargo test && git push origin main | tee log"),
+            vec![
+                vec!["cargo", "test"],
+                vec!["git", "push", "origin", "main"],
+                vec!["tee", "log"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_denylist_blocks_matching_commands() {
+        let rules = rules(&[], &["rm", "curl", "git push"]);
+
+        assert!(rules.check("ls -la && git status").is_ok());
+        assert!(rules.check("git pull").is_ok());
+        // A word that merely contains a denied name is fine
+        assert!(rules.check("cargo rmeta").is_ok());
+
+        for (line, offending) in [
+            ("rm -rf target", "rm -rf target"),
+            ("/bin/rm x", "/bin/rm x"),
+            ("cargo build; git push --force", "git push --force"),
+            ("echo hi | curl -d @- example.com", "curl -d @- example.com"),
+        ] {
+            match rules.check(line) {
+                Err(ShellError::Denied(command)) => assert_eq!(command, offending),
+                other => panic!("{} should be denied, got {:?}", line, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_rules_see_through_wrappers() {
+        let denying = rules(&[], &["rm", "git push"]);
+
+        for line in [
+            "sudo rm -rf /",
+            "sudo -u root /bin/rm x",
+            "env FOO=1 rm x",
+            "nice -n 5 rm x",
+            "timeout -s KILL 10 git push",
+            "find . -name '*.o' | xargs -n 1 rm",
+            "sh -c 'rm -rf target'",
+            "bash -lc \"cargo build && git push\"",
+            "sudo env nice rm x",
+        ] {
+            assert!(
+                matches!(denying.check(line), Err(ShellError::Denied(_))),
+                "{} should be denied",
+                line
+            );
+        }
+        assert!(denying.check("bash script.sh").is_ok());
+        assert!(denying.check("timeout 10 cargo test").is_ok());
+
+        // A wrapped command has to be allowed itself
+        let allowing = rules(&["cargo"], &[]);
+        assert!(allowing.check("timeout 60 cargo test").is_ok());
+        assert!(matches!(
+            allowing.check("timeout 60 curl example.com"),
+            Err(ShellError::NotAllowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_allowlist_requires_every_command_to_match() {
+        let rules = rules(&["cargo", "git status", "ls"], &["cargo publish"]);
+
+        assert!(rules.check("cargo test && git status").is_ok());
+        assert!(matches!(
+            rules.check("ls | wc -l"),
+            Err(ShellError::NotAllowed(command)) if command == "wc -l"
+        ));
+        assert!(matches!(
+            rules.check("git log"),
+            Err(ShellError::NotAllowed(_))
+        ));
+        // The denylist wins over the allowlist
+        assert!(matches!(
+            rules.check("cargo publish"),
+            Err(ShellError::Denied(_))
+        ));
+    }
+
     #[test]
     fn test_shell_error_display() {
         let io_err = ShellError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "test"));
@@ -2,6 +2,8 @@
 //!
 //! Provides a serdesAI-compatible tool for executing shell commands.
 
+use std::path::PathBuf;
+
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::Value as JsonValue;
@@ -13,7 +15,43 @@ use super::shell;
 
 /// Tool for executing shell commands.
 #[derive(Debug, Clone, Default)]
-pub struct RunShellCommandTool;
+pub struct RunShellCommandTool {
+    /// Project root commands run in. `None` uses the current directory at
+    /// call time, which follows the GUI's folder picker.
+    working_dir: Option<PathBuf>,
+    /// Refuse commands and working directories outside the project root.
+    confine: bool,
+    /// Commands this tool may or may not run.
+    rules: shell::CommandRules,
+}
+
+impl RunShellCommandTool {
+    /// Pin commands to `dir` instead of the current directory.
+    pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Keep commands inside the project root.
+    pub fn with_confinement(mut self, confine: bool) -> Self {
+        self.confine = confine;
+        self
+    }
+
+    /// Only run commands the rules allow.
+    pub fn with_command_rules(mut self, rules: shell::CommandRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// The project root for a call.
+    fn root(&self) -> PathBuf {
+        self.working_dir
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+}
 
 #[derive(Debug, Deserialize)]
 struct RunShellCommandArgs {
@@ -36,8 +74,8 @@ impl Tool for RunShellCommandTool {
                 .string("command", "The shell command to execute.", true)
                 .string(
                     "working_directory",
-                    "Working directory for command execution. If not specified, \
-                     uses the current working directory.",
+                    "Working directory for command execution, relative to the project \
+                     root. If not specified, uses the project root.",
                     false,
                 )
                 .integer(
@@ -62,13 +100,28 @@ impl Tool for RunShellCommandTool {
             ))
         })?;
 
-        // Build the command runner with options
-        let mut runner = shell::CommandRunner::new();
-
-        if let Some(dir) = &args.working_directory {
-            runner = runner.working_dir(dir);
+        if let Err(e) = self.rules.check(&args.command) {
+            warn!(tool = "run_shell_command", error = %e, "Command blocked");
+            return Ok(ToolReturn::error(format!("Command blocked: {}", e)));
         }
 
+        // Build the command runner with options
+        let root = self.root();
+        let working_dir = match &args.working_directory {
+            Some(dir) if self.confine && !shell::is_within(dir.as_ref(), &root) => {
+                return Ok(ToolReturn::error(format!(
+                    "Working directory {} is outside the project root {}",
+                    dir,
+                    root.display()
+                )));
+            }
+            Some(dir) => root.join(dir),
+            None => root,
+        };
+        let mut runner = shell::CommandRunner::new()
+            .working_dir(working_dir)
+            .confine(self.confine);
+
         if let Some(timeout) = args.timeout_seconds {
             runner = runner.timeout(timeout);
         }
@@ -127,14 +180,14 @@ mod tests {
 
     #[test]
     fn test_definition_returns_correct_name() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let def = tool.definition();
         assert_eq!(def.name(), "run_shell_command");
     }
 
     #[test]
     fn test_definition_has_description() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let def = tool.definition();
         assert!(def.description().contains("Execute"));
         assert!(def.description().contains("shell"));
@@ -142,7 +195,7 @@ mod tests {
 
     #[test]
     fn test_definition_has_parameters() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let def = tool.definition();
         let params = def.parameters();
         assert!(params.is_object());
@@ -154,7 +207,7 @@ mod tests {
 
     #[test]
     fn test_definition_command_is_required() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let def = tool.definition();
         let params = def.parameters();
         let schema_str = serde_json::to_string(params).unwrap();
@@ -168,7 +221,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_success_with_output() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -185,7 +238,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_success_exit_code_zero() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -199,7 +252,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_includes_stdout_section() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -221,7 +274,7 @@ mod tests {
     async fn test_call_with_working_directory() {
         let dir = tempfile::tempdir().expect("tempdir failed");
 
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -247,7 +300,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_invalid_working_directory() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -272,7 +325,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_with_timeout_seconds() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         // Quick command with timeout - should succeed
@@ -299,7 +352,7 @@ mod tests {
     #[tokio::test]
     #[cfg(unix)]
     async fn test_call_command_not_found() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -322,7 +375,7 @@ mod tests {
     #[tokio::test]
     #[cfg(unix)]
     async fn test_call_failed_exit_code() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -338,7 +391,7 @@ mod tests {
     #[tokio::test]
     #[cfg(unix)]
     async fn test_call_exit_code_1() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -354,7 +407,7 @@ mod tests {
     #[tokio::test]
     #[cfg(unix)]
     async fn test_call_captures_stderr() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -373,7 +426,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_missing_command_returns_error() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool.call(&ctx, serde_json::json!({})).await;
@@ -383,7 +436,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_wrong_type_command_returns_error() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool.call(&ctx, serde_json::json!({ "command": 123 })).await;
@@ -393,7 +446,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_wrong_type_working_directory_returns_error() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -408,7 +461,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_wrong_type_timeout_returns_error() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -423,7 +476,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_array_args_returns_error() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool.call(&ctx, serde_json::json!(["echo", "hello"])).await;
@@ -433,7 +486,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_null_command_returns_error() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -449,7 +502,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_empty_command() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         // Empty command should execute (shell handles it)
@@ -461,7 +514,7 @@ mod tests {
     #[tokio::test]
     #[cfg(unix)]
     async fn test_call_command_with_pipe() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -480,7 +533,7 @@ mod tests {
     #[tokio::test]
     #[cfg(unix)]
     async fn test_call_command_with_multiple_statements() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -499,7 +552,7 @@ mod tests {
     #[tokio::test]
     #[cfg(unix)]
     async fn test_call_command_with_env_expansion() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -514,7 +567,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_call_extra_fields_ignored() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -533,7 +586,7 @@ mod tests {
     #[tokio::test]
     #[cfg(unix)]
     async fn test_call_mixed_stdout_stderr() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -551,16 +604,93 @@ mod tests {
         assert!(text.contains("err"));
     }
 
+    // =========================================================================
+    // Confinement Tests
+    // =========================================================================
+
+    #[tokio::test]
+    async fn test_call_resolves_working_directory_against_root() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/marker.txt"), "").unwrap();
+
+        let tool = RunShellCommandTool::default().with_working_dir(dir.path());
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "command": "ls", "working_directory": "sub" }),
+            )
+            .await
+            .unwrap();
+        assert!(result.as_text().unwrap().contains("marker.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_confined_call_rejects_escapes() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let tool = RunShellCommandTool::default()
+            .with_working_dir(dir.path())
+            .with_confinement(true);
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(&ctx, serde_json::json!({ "command": "cd / && ls" }))
+            .await
+            .unwrap();
+        assert!(result.is_error());
+        assert!(result
+            .as_text()
+            .unwrap()
+            .contains("outside the working directory"));
+
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "command": "ls", "working_directory": "/" }),
+            )
+            .await
+            .unwrap();
+        assert!(result.is_error());
+        assert!(result
+            .as_text()
+            .unwrap()
+            .contains("outside the project root"));
+    }
+
+    #[tokio::test]
+    async fn test_call_blocked_by_denylist_names_command() {
+        let tool = RunShellCommandTool::default().with_command_rules(shell::CommandRules {
+            allow: Vec::new(),
+            deny: vec!["git push".to_string()],
+        });
+        let ctx = RunContext::minimal("test");
+
+        let result = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "command": "git status && git push origin main" }),
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_error());
+        let text = result.as_text().unwrap();
+        assert!(text.contains("`git push origin main`"), "{}", text);
+        assert!(text.contains("denylist"));
+    }
+
     #[test]
     fn test_tool_debug_impl() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let debug_str = format!("{:?}", tool);
         assert!(debug_str.contains("RunShellCommandTool"));
     }
 
     #[test]
     fn test_tool_clone_impl() {
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let cloned = tool.clone();
         assert_eq!(tool.definition().name(), cloned.definition().name());
     }
@@ -575,7 +705,7 @@ mod tests {
     async fn test_call_with_all_options() {
         let dir = tempfile::tempdir().expect("tempdir failed");
 
-        let tool = RunShellCommandTool;
+        let tool = RunShellCommandTool::default();
         let ctx = RunContext::minimal("test");
 
         let result = tool
@@ -0,0 +1,132 @@
+//! StatFile tool implementation.
+//!
+//! Provides a serdesAI-compatible tool for checking a file's size and type
+//! before reading it, so agents can skip binaries and read large files in
+//! chunks instead of running into read_file's limits.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::{debug, warn};
+
+use serdes_ai_tools::{RunContext, SchemaBuilder, Tool, ToolDefinition, ToolResult, ToolReturn};
+
+use super::file_ops::{self, FileError, FileStat};
+
+/// Tool for inspecting file metadata.
+#[derive(Debug, Clone, Default)]
+pub struct StatFileTool;
+
+#[derive(Debug, Deserialize)]
+struct StatFileArgs {
+    file_path: String,
+}
+
+#[async_trait]
+impl Tool for StatFileTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "stat_file",
+            "Get a file's size, line count, last modified time, and whether it is a \
+             directory or binary, without reading its contents. Use this before \
+             reading files that may be large or binary.",
+        )
+        .with_parameters(
+            SchemaBuilder::new()
+                .string(
+                    "file_path",
+                    "Path to the file or directory. Can be relative or absolute.",
+                    true,
+                )
+                .build()
+                .expect("schema build failed"),
+        )
+    }
+
+    async fn call(&self, _ctx: &RunContext, args: JsonValue) -> ToolResult {
+        debug!(tool = "stat_file", ?args, "Tool called");
+
+        let args: StatFileArgs = serde_json::from_value(args.clone()).map_err(|e| {
+            warn!(tool = "stat_file", error = %e, ?args, "Failed to parse arguments");
+            serdes_ai_tools::ToolError::execution_failed(format!(
+                "Invalid arguments: {}. Got: {}",
+                e, args
+            ))
+        })?;
+
+        match file_ops::stat_file(&args.file_path) {
+            Ok(stat) => Ok(ToolReturn::text(describe(&stat))),
+            Err(FileError::NotFound(path)) => {
+                Ok(ToolReturn::error(format!("File not found: {}", path)))
+            }
+            Err(e) => Ok(ToolReturn::error(format!("Failed to stat file: {}", e))),
+        }
+    }
+}
+
+fn describe(stat: &FileStat) -> String {
+    let kind = if stat.is_dir {
+        "directory"
+    } else if stat.is_binary {
+        "binary file"
+    } else {
+        "text file"
+    };
+
+    let mut output = format!(
+        "path: {}\ntype: {}\nsize: {} bytes",
+        stat.path, kind, stat.size
+    );
+    if let Some(lines) = stat.lines {
+        output.push_str(&format!("\nlines: {}", lines));
+    }
+    if let Some(modified) = stat.modified {
+        output.push_str(&format!("\nmodified: {}", modified.to_rfc3339()));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_call_describes_text_file() {
+        let dir = tempfile::tempdir().expect("tempdir failed");
+        let file_path = dir.path().join("app.log");
+        fs::write(&file_path, "a\nb\n").expect("write failed");
+
+        let tool = StatFileTool;
+        let ctx = RunContext::minimal("test");
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "file_path": file_path.to_str().unwrap() }),
+            )
+            .await
+            .unwrap();
+
+        let text = ret.as_text().unwrap();
+        assert!(text.contains("type: text file"));
+        assert!(text.contains("size: 4 bytes"));
+        assert!(text.contains("lines: 2"));
+        assert!(text.contains("modified: "));
+    }
+
+    #[tokio::test]
+    async fn test_call_file_not_found_returns_error() {
+        let tool = StatFileTool;
+        let ctx = RunContext::minimal("test");
+        let ret = tool
+            .call(
+                &ctx,
+                serde_json::json!({ "file_path": "/nonexistent/path/file.txt" }),
+            )
+            .await
+            .unwrap();
+
+        assert!(ret.is_error());
+        assert!(ret.as_text().unwrap().contains("File not found"));
+    }
+}
@@ -0,0 +1,550 @@
+//! Undo snapshots for file changes made by agents.
+//!
+//! Before `edit_file`, `delete_file`, `move_file` or `replace_in_files`
+//! first touch a file in a run, its current contents are copied into the
+//! project's `.stockpot/undo/<run>/`, or, for a file that doesn't exist yet,
+//! its path is noted as created. Undoing a run puts every file back exactly
+//! as it was and deletes the ones it created.
+//!
+//! The project is the nearest directory at or above the working directory
+//! that has a `.stockpot` or `.git` directory, so `/undo` in one project
+//! never touches another's files.
+//!
+//! A run's snapshot is written under a provisional key while it's in
+//! progress and renamed to its run ID once the run completes. Only the most
+//! recent runs are kept. The same snapshots back a diff of everything the
+//! kept runs changed.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::diff::UnifiedDiff;
+use crate::atomic_file::write_atomic;
+
+const MANIFEST: &str = "manifest.json";
+
+/// Error type for undo operations.
+#[derive(Debug, Error)]
+pub enum UndoError {
+    #[error("Nothing to undo")]
+    NothingToUndo,
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid undo snapshot: {0}")]
+    Manifest(#[from] serde_json::Error),
+}
+
+/// The files a run changed, as stored in its snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    run_id: Option<String>,
+    started_at: DateTime<Utc>,
+    files: Vec<FileEntry>,
+}
+
+/// One changed file and where its prior contents were saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    path: PathBuf,
+    /// Backup file name in the snapshot; `None` if the run created the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backup: Option<String>,
+}
+
+/// Where undo snapshots live and how many are kept.
+#[derive(Debug, Clone)]
+pub struct UndoJournal {
+    dir: PathBuf,
+    max_runs: usize,
+}
+
+impl Default for UndoJournal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UndoJournal {
+    /// Snapshots in the current project's `.stockpot/undo`, keeping the
+    /// last 10 runs.
+    pub fn new() -> Self {
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Self::for_project(&cwd)
+    }
+
+    /// Snapshots for the project that `start` is in.
+    pub fn for_project(start: &Path) -> Self {
+        Self::with_dir(project_root(start).join(".stockpot").join("undo"))
+    }
+
+    /// Snapshots in a custom directory.
+    pub fn with_dir(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            max_runs: 10,
+        }
+    }
+
+    /// Set how many runs can be undone (at least 1).
+    pub fn with_max_runs(mut self, max: usize) -> Self {
+        self.max_runs = max.max(1);
+        self
+    }
+
+    /// Start recording a run. Nothing is written until a file is changed.
+    pub fn begin(&self) -> UndoRun {
+        let started_at = Utc::now();
+        let key = format!(
+            "pending-{}-{}",
+            started_at.format("%Y%m%d%H%M%S%3f"),
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        );
+        UndoRun {
+            inner: Arc::new(Mutex::new(RunState {
+                journal: self.clone(),
+                dir: self.dir.join(key),
+                manifest: Manifest {
+                    run_id: None,
+                    started_at,
+                    files: Vec::new(),
+                },
+                seen: HashSet::new(),
+            })),
+        }
+    }
+
+    /// Restore the files changed by the most recent run and drop its
+    /// snapshot.
+    pub fn undo_last(&self) -> Result<UndoReport, UndoError> {
+        let (dir, manifest) = self.snapshots()?.pop().ok_or(UndoError::NothingToUndo)?;
+
+        let mut report = UndoReport {
+            run_id: manifest.run_id.clone(),
+            restored: Vec::new(),
+            removed: Vec::new(),
+        };
+        for entry in manifest.files.iter().rev() {
+            match &entry.backup {
+                Some(backup) => {
+                    if let Some(parent) = entry.path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::copy(dir.join(backup), &entry.path)?;
+                    report.restored.push(entry.path.clone());
+                }
+                None => match fs::remove_file(&entry.path) {
+                    Ok(()) => report.removed.push(entry.path.clone()),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                },
+            }
+        }
+
+        fs::remove_dir_all(&dir)?;
+        Ok(report)
+    }
+
+    /// A unified diff of every file the kept runs changed, from its state
+    /// before the earliest of them to its contents now.
+    ///
+    /// `only` limits the diff to one file. Files that are back to how they
+    /// were are left out, so an empty string means no changes.
+    pub fn diff(&self, only: Option<&Path>) -> Result<String, UndoError> {
+        let cwd = std::env::current_dir()?;
+        let only = only.map(|path| cwd.join(path));
+        let mut seen = HashSet::new();
+        let mut out = String::new();
+
+        for (dir, manifest) in self.snapshots()? {
+            for entry in manifest.files {
+                if only.as_ref().is_some_and(|path| *path != entry.path)
+                    || !seen.insert(entry.path.clone())
+                {
+                    continue;
+                }
+
+                let before = match &entry.backup {
+                    Some(backup) => Some(fs::read(dir.join(backup))?),
+                    None => None,
+                };
+                let after = match fs::read(&entry.path) {
+                    Ok(content) => Some(content),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(e) => return Err(e.into()),
+                };
+                if before == after {
+                    continue;
+                }
+
+                let label = entry.path.strip_prefix(&cwd).unwrap_or(&entry.path);
+                let label = label.display().to_string();
+                let old = std::str::from_utf8(before.as_deref().unwrap_or_default());
+                let new = std::str::from_utf8(after.as_deref().unwrap_or_default());
+                match (old, new) {
+                    (Ok(old), Ok(new)) => {
+                        let diff = UnifiedDiff::between(
+                            before.is_some().then_some(label.as_str()),
+                            after.is_some().then_some(label.as_str()),
+                            old,
+                            new,
+                            3,
+                        );
+                        out.push_str(&diff.to_string());
+                    }
+                    _ => out.push_str(&format!("Binary file {} differs\n", label)),
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Snapshots on disk, oldest first.
+    fn snapshots(&self) -> Result<Vec<(PathBuf, Manifest)>, UndoError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut snapshots = Vec::new();
+        for entry in entries {
+            let dir = entry?.path();
+            // Skip anything that isn't a readable snapshot
+            let Ok(content) = fs::read_to_string(dir.join(MANIFEST)) else {
+                continue;
+            };
+            match serde_json::from_str::<Manifest>(&content) {
+                Ok(manifest) => snapshots.push((dir, manifest)),
+                Err(e) => tracing::warn!(dir = %dir.display(), error = %e, "Bad undo snapshot"),
+            }
+        }
+        snapshots.sort_by_key(|(_, manifest)| manifest.started_at);
+        Ok(snapshots)
+    }
+
+    /// Remove the oldest snapshots beyond `max_runs`.
+    fn prune(&self) -> Result<(), UndoError> {
+        let snapshots = self.snapshots()?;
+        let excess = snapshots.len().saturating_sub(self.max_runs);
+        for (dir, _) in snapshots.into_iter().take(excess) {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// What undoing a run changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UndoReport {
+    pub run_id: Option<String>,
+    /// Files put back to their earlier contents
+    pub restored: Vec<PathBuf>,
+    /// Files the run created, now deleted
+    pub removed: Vec<PathBuf>,
+}
+
+impl fmt::Display for UndoReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.run_id {
+            Some(run_id) => write!(f, "Undid run {}", run_id)?,
+            None => f.write_str("Undid the last run")?,
+        }
+        write!(
+            f,
+            ": restored {} file(s), removed {} created file(s)",
+            self.restored.len(),
+            self.removed.len()
+        )?;
+        for path in &self.restored {
+            write!(f, "\n  restored {}", path.display())?;
+        }
+        for path in &self.removed {
+            write!(f, "\n  removed  {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the files one run changes. Clones share the same snapshot.
+#[derive(Debug, Clone)]
+pub struct UndoRun {
+    inner: Arc<Mutex<RunState>>,
+}
+
+#[derive(Debug)]
+struct RunState {
+    journal: UndoJournal,
+    dir: PathBuf,
+    manifest: Manifest,
+    /// Files already saved; only the state before a run's first change counts
+    seen: HashSet<PathBuf>,
+}
+
+impl UndoRun {
+    /// Save `path` as it is now, before it is written or deleted.
+    pub fn record(&self, path: impl AsRef<Path>) -> Result<(), UndoError> {
+        let path = std::env::current_dir()?.join(path);
+        let mut state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.seen.insert(path.clone()) {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&state.dir)?;
+        // Snapshots sit inside the project, but aren't part of it
+        let ignore = state.journal.dir.join(".gitignore");
+        if !ignore.exists() {
+            fs::write(ignore, "*\n")?;
+        }
+        let backup = if path.is_file() {
+            let name = state.manifest.files.len().to_string();
+            fs::copy(&path, state.dir.join(&name))?;
+            Some(name)
+        } else {
+            None
+        };
+        state.manifest.files.push(FileEntry { path, backup });
+        write_manifest(&state.dir, &state.manifest)
+    }
+
+    /// Whether any file was recorded.
+    pub fn is_empty(&self) -> bool {
+        let state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        state.manifest.files.is_empty()
+    }
+
+    /// Key the snapshot by the run's ID and drop snapshots beyond the
+    /// journal's limit. Does nothing if no file was changed.
+    pub fn finish(&self, run_id: &str) -> Result<(), UndoError> {
+        let mut state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if state.manifest.files.is_empty() {
+            return Ok(());
+        }
+
+        state.manifest.run_id = Some(run_id.to_string());
+        write_manifest(&state.dir, &state.manifest)?;
+
+        let keyed = state.journal.dir.join(safe_name(run_id));
+        if keyed != state.dir && !keyed.exists() {
+            fs::rename(&state.dir, &keyed)?;
+            state.dir = keyed;
+        }
+        state.journal.prune()
+    }
+}
+
+/// The nearest directory at or above `start` with a `.stockpot` or `.git`
+/// directory, or `start` itself. The search stops below the home directory,
+/// whose `.stockpot` holds the global settings rather than a project's.
+fn project_root(start: &Path) -> &Path {
+    let home = dirs::home_dir();
+    start
+        .ancestors()
+        .take_while(|dir| *dir == start || Some(*dir) != home.as_deref())
+        .find(|dir| dir.join(".stockpot").is_dir() || dir.join(".git").exists())
+        .unwrap_or(start)
+}
+
+fn write_manifest(dir: &Path, manifest: &Manifest) -> Result<(), UndoError> {
+    write_atomic(&dir.join(MANIFEST), serde_json::to_string_pretty(manifest)?)?;
+    Ok(())
+}
+
+/// A run ID made safe to use as a directory name.
+fn safe_name(run_id: &str) -> String {
+    run_id
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn journal() -> (TempDir, UndoJournal) {
+        let temp = TempDir::new().unwrap();
+        let journal = UndoJournal::with_dir(temp.path().join("undo"));
+        (temp, journal)
+    }
+
+    #[test]
+    fn test_undo_restores_exact_contents_and_removes_created_files() {
+        let (temp, journal) = journal();
+        let edited = temp.path().join("edited.txt");
+        let created = temp.path().join("new/created.txt");
+        fs::write(&edited, b"original\r\nbytes\x00").unwrap();
+
+        let run = journal.begin();
+        run.record(&edited).unwrap();
+        fs::write(&edited, "first change").unwrap();
+        // A second change in the same run keeps the original backup
+        run.record(&edited).unwrap();
+        fs::write(&edited, "second change").unwrap();
+        run.record(&created).unwrap();
+        fs::create_dir_all(created.parent().unwrap()).unwrap();
+        fs::write(&created, "brand new").unwrap();
+        run.finish("run-42").unwrap();
+
+        let report = journal.undo_last().unwrap();
+        assert_eq!(report.run_id.as_deref(), Some("run-42"));
+        assert_eq!(report.restored, vec![edited.clone()]);
+        assert_eq!(report.removed, vec![created.clone()]);
+        assert_eq!(fs::read(&edited).unwrap(), b"original\r\nbytes\x00");
+        assert!(!created.exists());
+
+        assert!(matches!(journal.undo_last(), Err(UndoError::NothingToUndo)));
+    }
+
+    #[test]
+    fn test_undo_restores_deleted_file() {
+        let (temp, journal) = journal();
+        let file = temp.path().join("doomed.txt");
+        fs::write(&file, "keep me").unwrap();
+
+        let run = journal.begin();
+        run.record(&file).unwrap();
+        fs::remove_file(&file).unwrap();
+        run.finish("run-1").unwrap();
+
+        journal.undo_last().unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn test_undo_takes_most_recent_run_first() {
+        let (temp, journal) = journal();
+        let file = temp.path().join("file.txt");
+        fs::write(&file, "v1").unwrap();
+
+        for (run_id, next) in [("run-a", "v2"), ("run-b", "v3")] {
+            let run = journal.begin();
+            run.record(&file).unwrap();
+            fs::write(&file, next).unwrap();
+            run.finish(run_id).unwrap();
+        }
+
+        assert_eq!(
+            journal.undo_last().unwrap().run_id.as_deref(),
+            Some("run-b")
+        );
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v2");
+        assert_eq!(
+            journal.undo_last().unwrap().run_id.as_deref(),
+            Some("run-a")
+        );
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let (temp, journal) = journal();
+        let journal = journal.with_max_runs(2);
+        let file = temp.path().join("file.txt");
+
+        for i in 0..4 {
+            let run = journal.begin();
+            run.record(&file).unwrap();
+            fs::write(&file, i.to_string()).unwrap();
+            run.finish(&format!("run-{}", i)).unwrap();
+        }
+
+        let runs: Vec<_> = journal
+            .snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|(_, manifest)| manifest.run_id.unwrap())
+            .collect();
+        assert_eq!(runs, vec!["run-2", "run-3"]);
+    }
+
+    #[test]
+    fn test_diff_spans_runs_and_can_be_scoped() {
+        let (temp, journal) = journal();
+        let edited = temp.path().join("edited.txt");
+        let created = temp.path().join("created.txt");
+        let reverted = temp.path().join("reverted.txt");
+        fs::write(&edited, "one\ntwo\n").unwrap();
+        fs::write(&reverted, "same\n").unwrap();
+
+        let run = journal.begin();
+        run.record(&edited).unwrap();
+        fs::write(&edited, "one\n2\n").unwrap();
+        run.record(&reverted).unwrap();
+        fs::write(&reverted, "changed\n").unwrap();
+        run.finish("run-a").unwrap();
+
+        let run = journal.begin();
+        run.record(&edited).unwrap();
+        fs::write(&edited, "one\n2\nthree\n").unwrap();
+        run.record(&created).unwrap();
+        fs::write(&created, "hello\n").unwrap();
+        run.record(&reverted).unwrap();
+        fs::write(&reverted, "same\n").unwrap();
+        run.finish("run-b").unwrap();
+
+        let diff = journal.diff(None).unwrap();
+        // Measured from before the first run, across both
+        assert!(diff.contains(" one\n-two\n+2\n+three\n"));
+        assert!(diff.contains("--- /dev/null\n"));
+        assert!(diff.contains("+hello\n"));
+        assert!(!diff.contains("reverted.txt"));
+
+        let scoped = journal.diff(Some(&created)).unwrap();
+        assert!(scoped.contains("+hello\n"));
+        assert!(!scoped.contains("edited.txt"));
+    }
+
+    #[test]
+    fn test_run_without_changes_leaves_no_snapshot() {
+        let (_temp, journal) = journal();
+        let run = journal.begin();
+        assert!(run.is_empty());
+        run.finish("run-quiet").unwrap();
+        assert!(journal.snapshots().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_journal_is_scoped_to_the_project() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        let nested = project.join("src/deep");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(project.join(".git")).unwrap();
+        assert_eq!(project_root(&nested), project);
+
+        let other = temp.path().join("other");
+        fs::create_dir_all(&other).unwrap();
+        assert_eq!(project_root(&other), other);
+
+        let file = other.join("file.txt");
+        fs::write(&file, "v1").unwrap();
+        let run = UndoJournal::for_project(&other).begin();
+        run.record(&file).unwrap();
+        run.finish("run-other").unwrap();
+
+        // The other project's run can't be undone from this one
+        assert!(matches!(
+            UndoJournal::for_project(&nested).undo_last(),
+            Err(UndoError::NothingToUndo)
+        ));
+        assert!(other.join(".stockpot/undo/run-other").is_dir());
+        assert!(other.join(".stockpot/undo/.gitignore").is_file());
+    }
+}